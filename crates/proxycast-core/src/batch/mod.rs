@@ -0,0 +1,20 @@
+//! 批处理任务模块
+//!
+//! 在本地模拟 Anthropic Message Batches 和 OpenAI Batch API：
+//! - `/v1/messages/batches`：Anthropic 批处理任务
+//! - `/v1/batches` + `/v1/files`：OpenAI 批处理任务及输入/输出文件
+//!
+//! 批处理请求保存在 SQLite 任务队列中，由 [`BatchRunner`] 按配置的并发数和速率上限
+//! 逐条交给 [`BatchExecutor`] 执行，执行器负责走正常的请求处理管道并在凭证池中分摊负载。
+
+pub mod runner;
+pub mod store;
+pub mod types;
+
+pub use runner::{BatchExecution, BatchExecutor, BatchManager, BatchRunner};
+pub use store::BatchStore;
+pub use types::{
+    parse_anthropic_batch_requests, parse_openai_batch_input, BatchError, BatchFile, BatchItem,
+    BatchItemStatus, BatchJob, BatchKind, BatchRequestCounts, BatchStatus, NewBatchRequest,
+    BATCH_FILE_PURPOSE, BATCH_OUTPUT_FILE_PURPOSE, DEFAULT_COMPLETION_WINDOW_SECS,
+};
@@ -0,0 +1,470 @@
+//! 批处理任务执行器
+//!
+//! [`BatchRunner`] 从 [`BatchStore`] 队列中领取请求项，按并发数和每分钟速率上限
+//! 交给 [`BatchExecutor`] 执行。上游返回配额超限错误时，将对应凭证交给
+//! [`QuotaManager`] 冷却并把请求放回队列，由执行器改用其他凭证重试；
+//! 所有凭证都在冷却期时请求被放回队列等待恢复，不计入尝试次数。
+//!
+//! 存储操作是同步的 SQLite 调用，统一通过 [`BatchManager::with_store`] 在阻塞线程池中执行。
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Notify, RwLock, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use super::store::BatchStore;
+use super::types::{BatchError, BatchItem, BatchItemStatus, BatchKind};
+use crate::config::BatchConfig;
+use crate::credential::QuotaManager;
+
+/// 队列为空时的轮询间隔
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 单个批处理请求的执行结果
+#[derive(Debug, Clone)]
+pub enum BatchExecution {
+    /// 已得到上游响应（包括错误响应）
+    Completed {
+        status: u16,
+        body: Value,
+        /// 使用的凭证 ID（用于配额超限时标记冷却）
+        credential_id: Option<String>,
+    },
+    /// 所有凭证都处于配额冷却期，稍后重试
+    Deferred { until: Option<DateTime<Utc>> },
+}
+
+/// 批处理请求执行器
+///
+/// 由服务器层实现，负责将请求体送入正常的请求处理管道
+#[async_trait]
+pub trait BatchExecutor: Send + Sync {
+    /// 执行单个批处理请求
+    ///
+    /// `body` 为 Anthropic Messages 请求（[`BatchKind::Anthropic`]）或
+    /// OpenAI Chat Completions 请求（[`BatchKind::OpenAi`]）
+    async fn execute(&self, kind: BatchKind, body: &Value) -> BatchExecution;
+}
+
+/// 批处理任务管理器
+///
+/// 在 HTTP 处理器和后台执行器之间共享存储、配置和唤醒信号
+pub struct BatchManager {
+    store: BatchStore,
+    config: RwLock<BatchConfig>,
+    notify: Notify,
+}
+
+impl BatchManager {
+    pub fn new(store: BatchStore, config: BatchConfig) -> Self {
+        Self {
+            store,
+            config: RwLock::new(config),
+            notify: Notify::new(),
+        }
+    }
+
+    /// 获取存储
+    pub fn store(&self) -> &BatchStore {
+        &self.store
+    }
+
+    /// 获取当前配置
+    pub async fn config(&self) -> BatchConfig {
+        self.config.read().await.clone()
+    }
+
+    /// 更新配置（并发数在执行器重启后生效）
+    pub async fn set_config(&self, config: BatchConfig) {
+        *self.config.write().await = config;
+        self.notify.notify_one();
+    }
+
+    /// 通知执行器有新的请求入队
+    pub fn notify_new_work(&self) {
+        self.notify.notify_one();
+    }
+
+    /// 在阻塞线程池中执行存储操作
+    pub async fn with_store<T, F>(self: &Arc<Self>, f: F) -> Result<T, BatchError>
+    where
+        F: FnOnce(&BatchStore) -> Result<T, BatchError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let manager = self.clone();
+        tokio::task::spawn_blocking(move || f(manager.store()))
+            .await
+            .unwrap_or_else(|e| Err(BatchError::Task(e.to_string())))
+    }
+}
+
+/// 批处理后台执行器
+pub struct BatchRunner<E: BatchExecutor + 'static> {
+    manager: Arc<BatchManager>,
+    executor: Arc<E>,
+    quota: Arc<QuotaManager>,
+}
+
+impl<E: BatchExecutor + 'static> Clone for BatchRunner<E> {
+    fn clone(&self) -> Self {
+        Self {
+            manager: self.manager.clone(),
+            executor: self.executor.clone(),
+            quota: self.quota.clone(),
+        }
+    }
+}
+
+impl<E: BatchExecutor + 'static> BatchRunner<E> {
+    pub fn new(manager: Arc<BatchManager>, executor: Arc<E>, quota: Arc<QuotaManager>) -> Self {
+        Self {
+            manager,
+            executor,
+            quota,
+        }
+    }
+
+    /// 启动后台执行循环
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move { self.run().await })
+    }
+
+    async fn run(self) {
+        match self
+            .manager
+            .with_store(|store| store.requeue_running())
+            .await
+        {
+            Ok(n) if n > 0 => tracing::info!("[BATCH] 恢复 {} 个中断的批处理请求", n),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("[BATCH] 恢复中断请求失败: {}", e),
+        }
+
+        let concurrency = self.manager.config().await.concurrency.max(1);
+        let semaphore = Arc::new(Semaphore::new(concurrency));
+        let mut next_dispatch = Instant::now();
+
+        loop {
+            let Ok(permit) = semaphore.clone().acquire_owned().await else {
+                return;
+            };
+
+            // 速率上限：相邻两次发出之间至少间隔 60s / rpm。
+            // 先等待再领取，避免请求项在等待期间处于执行中状态
+            let rpm = self.manager.config().await.requests_per_minute;
+            if rpm > 0 {
+                tokio::time::sleep_until(next_dispatch).await;
+            }
+
+            let now = Utc::now().timestamp();
+            let claimed = self
+                .manager
+                .with_store(move |store| {
+                    if let Err(e) = store.expire_overdue(now) {
+                        tracing::warn!("[BATCH] 标记过期请求失败: {}", e);
+                    }
+                    store.claim_next_item(now)
+                })
+                .await;
+
+            match claimed {
+                Ok(Some(item)) => {
+                    if rpm > 0 {
+                        next_dispatch = Instant::now() + Duration::from_secs(60) / rpm;
+                    }
+                    let runner = self.clone();
+                    tokio::spawn(async move {
+                        runner.process_item(item).await;
+                        drop(permit);
+                    });
+                }
+                Ok(None) => {
+                    drop(permit);
+                    if let Err(e) = self
+                        .manager
+                        .with_store(|store| store.finalize_idle_batches())
+                        .await
+                    {
+                        tracing::warn!("[BATCH] 结束批处理任务失败: {}", e);
+                    }
+                    tokio::select! {
+                        _ = self.manager.notify.notified() => {}
+                        _ = tokio::time::sleep(IDLE_POLL_INTERVAL) => {}
+                    }
+                }
+                Err(e) => {
+                    drop(permit);
+                    tracing::error!("[BATCH] 领取批处理请求失败: {}", e);
+                    tokio::time::sleep(IDLE_POLL_INTERVAL).await;
+                }
+            }
+        }
+    }
+
+    /// 执行单个请求项并记录结果
+    pub async fn process_item(&self, item: BatchItem) {
+        let max_attempts = self.manager.config().await.max_attempts;
+        let now = Utc::now();
+        let batch_id = item.batch_id.clone();
+        let idx = item.idx;
+
+        let result = match self.executor.execute(item.kind, &item.body).await {
+            BatchExecution::Completed {
+                status,
+                body,
+                credential_id,
+            } => {
+                // 只检查失败响应，避免成功响应正文中的关键词被误判
+                let quota_exceeded = !(200..300).contains(&status)
+                    && QuotaManager::is_quota_exceeded_error(Some(status), &body.to_string());
+                match credential_id {
+                    Some(cred) if quota_exceeded && item.attempts + 1 < max_attempts => {
+                        // 冷却该凭证后立即放回队列，执行器会选择其他凭证
+                        self.quota
+                            .mark_quota_exceeded(&cred, "batch request quota exceeded");
+                        let not_before = now.timestamp();
+                        self.manager
+                            .with_store(move |store| {
+                                store.defer_item(&batch_id, idx, not_before).map(|_| ())
+                            })
+                            .await
+                    }
+                    _ => {
+                        let item_status = if (200..300).contains(&status) {
+                            BatchItemStatus::Succeeded
+                        } else {
+                            BatchItemStatus::Errored
+                        };
+                        self.manager
+                            .with_store(move |store| {
+                                store.complete_item(
+                                    &batch_id,
+                                    idx,
+                                    item_status,
+                                    Some(status),
+                                    Some(&body),
+                                )
+                            })
+                            .await
+                    }
+                }
+            }
+            BatchExecution::Deferred { until } => {
+                // 节流不是失败：释放领取状态，不消耗尝试次数
+                let not_before = until
+                    .unwrap_or_else(|| now + self.quota.cooldown_duration())
+                    .timestamp();
+                tracing::debug!(
+                    "[BATCH] 所有凭证冷却中，请求 {}#{} 推迟到 {}",
+                    item.batch_id,
+                    item.idx,
+                    not_before
+                );
+                self.manager
+                    .with_store(move |store| store.release_item(&batch_id, idx, not_before))
+                    .await
+            }
+        };
+        if let Err(e) = result {
+            tracing::error!(
+                "[BATCH] 记录请求 {}#{} 结果失败: {}",
+                item.batch_id,
+                item.idx,
+                e
+            );
+        }
+
+        let batch_id = item.batch_id.clone();
+        if let Err(e) = self
+            .manager
+            .with_store(move |store| store.finalize_batch(&batch_id).map(|_| ()))
+            .await
+        {
+            tracing::warn!("[BATCH] 结束批处理任务 {} 失败: {}", item.batch_id, e);
+        }
+        self.manager.notify_new_work();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch::types::{BatchStatus, NewBatchRequest};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// 按预设顺序返回结果的执行器
+    struct ScriptedExecutor {
+        results: Mutex<Vec<BatchExecution>>,
+        calls: AtomicUsize,
+    }
+
+    impl ScriptedExecutor {
+        fn new(mut results: Vec<BatchExecution>) -> Self {
+            results.reverse();
+            Self {
+                results: Mutex::new(results),
+                calls: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl BatchExecutor for ScriptedExecutor {
+        async fn execute(&self, _kind: BatchKind, body: &Value) -> BatchExecution {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.results
+                .lock()
+                .unwrap()
+                .pop()
+                .unwrap_or(BatchExecution::Completed {
+                    status: 200,
+                    body: body.clone(),
+                    credential_id: Some("cred-a".to_string()),
+                })
+        }
+    }
+
+    fn setup(
+        results: Vec<BatchExecution>,
+        n: usize,
+    ) -> (BatchRunner<ScriptedExecutor>, Arc<ScriptedExecutor>, String) {
+        let store = BatchStore::in_memory().unwrap();
+        let requests = (0..n)
+            .map(|i| NewBatchRequest {
+                custom_id: format!("req-{}", i),
+                body: json!({"i": i}),
+            })
+            .collect();
+        let job = store
+            .create_batch(BatchKind::Anthropic, "/v1/messages", None, None, requests)
+            .unwrap();
+        let config = BatchConfig {
+            requests_per_minute: 0,
+            max_attempts: 3,
+            ..Default::default()
+        };
+        let manager = Arc::new(BatchManager::new(store, config));
+        let executor = Arc::new(ScriptedExecutor::new(results));
+        let runner = BatchRunner::new(
+            manager,
+            executor.clone(),
+            Arc::new(QuotaManager::with_defaults()),
+        );
+        (runner, executor, job.id)
+    }
+
+    #[tokio::test]
+    async fn test_process_item_records_success_and_error() {
+        let (runner, _, batch_id) = setup(
+            vec![
+                BatchExecution::Completed {
+                    status: 200,
+                    body: json!({"id": "msg_1"}),
+                    credential_id: Some("cred-a".to_string()),
+                },
+                BatchExecution::Completed {
+                    status: 400,
+                    body: json!({"error": {"message": "bad request"}}),
+                    credential_id: Some("cred-a".to_string()),
+                },
+            ],
+            2,
+        );
+        let store = runner.manager.store();
+        let now = Utc::now().timestamp();
+        for _ in 0..2 {
+            let item = store.claim_next_item(now).unwrap().unwrap();
+            runner.process_item(item).await;
+        }
+
+        let job = store.get_batch(&batch_id).unwrap().unwrap();
+        assert_eq!(job.status, BatchStatus::Ended);
+        assert_eq!(job.request_counts.succeeded, 1);
+        assert_eq!(job.request_counts.errored, 1);
+        let items = store.get_items(&batch_id).unwrap();
+        assert_eq!(items[0].response_body, Some(json!({"id": "msg_1"})));
+    }
+
+    #[tokio::test]
+    async fn test_quota_error_cools_credential_and_requeues() {
+        let (runner, _, batch_id) = setup(
+            vec![BatchExecution::Completed {
+                status: 429,
+                body: json!({"error": {"message": "quota exceeded"}}),
+                credential_id: Some("cred-a".to_string()),
+            }],
+            1,
+        );
+        let store = runner.manager.store();
+        let now = Utc::now().timestamp();
+        let item = store.claim_next_item(now).unwrap().unwrap();
+        runner.process_item(item).await;
+
+        assert!(!runner.quota.is_available("cred-a"));
+        let item = store.claim_next_item(now).unwrap().unwrap();
+        assert_eq!(item.attempts, 1);
+        runner.process_item(item).await;
+        let job = store.get_batch(&batch_id).unwrap().unwrap();
+        assert_eq!(job.request_counts.succeeded, 1);
+    }
+
+    #[tokio::test]
+    async fn test_deferred_releases_claim_without_consuming_attempts() {
+        let until = Utc::now() + chrono::Duration::seconds(30);
+        let (runner, _, batch_id) = setup(
+            vec![
+                BatchExecution::Deferred { until: Some(until) },
+                BatchExecution::Deferred { until: None },
+                BatchExecution::Deferred { until: None },
+            ],
+            1,
+        );
+        let store = runner.manager.store();
+        let item = store
+            .claim_next_item(Utc::now().timestamp())
+            .unwrap()
+            .unwrap();
+        runner.process_item(item).await;
+        assert!(store
+            .claim_next_item(Utc::now().timestamp())
+            .unwrap()
+            .is_none());
+
+        // 推迟次数达到 max_attempts 也不会把请求标记为失败
+        let far = (Utc::now() + chrono::Duration::days(1)).timestamp();
+        for _ in 0..3 {
+            let item = store.claim_next_item(far).unwrap().unwrap();
+            assert_eq!(item.attempts, 0);
+            runner.process_item(item).await;
+        }
+        let items = store.get_items(&batch_id).unwrap();
+        assert_eq!(items[0].status, BatchItemStatus::Succeeded);
+        assert_eq!(items[0].attempts, 0);
+    }
+
+    #[tokio::test]
+    async fn test_spawned_runner_drains_queue() {
+        let (runner, executor, batch_id) = setup(Vec::new(), 5);
+        let manager = runner.manager.clone();
+        let handle = runner.spawn();
+
+        for _ in 0..100 {
+            let job = manager.store().get_batch(&batch_id).unwrap().unwrap();
+            if job.status == BatchStatus::Ended {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        handle.abort();
+
+        let job = manager.store().get_batch(&batch_id).unwrap().unwrap();
+        assert_eq!(job.status, BatchStatus::Ended);
+        assert_eq!(job.request_counts.succeeded, 5);
+        assert_eq!(executor.calls.load(Ordering::SeqCst), 5);
+    }
+}
@@ -0,0 +1,870 @@
+//! 批处理任务 SQLite 存储
+//!
+//! 保存批处理任务、请求项及 OpenAI 输入/输出文件，作为本地任务队列使用。
+//! 所有队列操作（领取、推迟、完成、取消）都在同一把连接锁内完成，保证原子性。
+
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
+
+use super::types::{
+    BatchError, BatchFile, BatchItem, BatchItemStatus, BatchJob, BatchKind, BatchRequestCounts,
+    BatchStatus, NewBatchRequest, BATCH_OUTPUT_FILE_PURPOSE, DEFAULT_COMPLETION_WINDOW_SECS,
+};
+
+type Result<T> = std::result::Result<T, BatchError>;
+
+/// 批处理任务存储
+pub struct BatchStore {
+    db: Mutex<Connection>,
+}
+
+impl BatchStore {
+    /// 打开（或创建）批处理数据库
+    ///
+    /// # Arguments
+    /// * `db_path` - SQLite 数据库路径
+    pub fn new(db_path: PathBuf) -> Result<Self> {
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| BatchError::InvalidRequest(format!("无法创建目录: {}", e)))?;
+        }
+        Self::from_connection(Connection::open(&db_path)?)
+    }
+
+    /// 创建内存数据库存储（数据库文件不可用时回退使用）
+    pub fn in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// 从现有连接创建存储
+    pub fn from_connection(conn: Connection) -> Result<Self> {
+        Self::init_database(&conn)?;
+        Ok(Self {
+            db: Mutex::new(conn),
+        })
+    }
+
+    /// 默认数据库路径：`~/.proxycast/batches.db`
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".proxycast").join("batches.db"))
+    }
+
+    fn init_database(conn: &Connection) -> Result<()> {
+        conn.execute_batch(
+            r#"
+            -- 批处理任务表
+            CREATE TABLE IF NOT EXISTS batch_jobs (
+                id TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                status TEXT NOT NULL,
+                endpoint TEXT NOT NULL,
+                input_file_id TEXT,
+                output_file_id TEXT,
+                error_file_id TEXT,
+                completion_window TEXT NOT NULL,
+                metadata TEXT,
+                created_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL,
+                cancel_initiated_at INTEGER,
+                ended_at INTEGER
+            );
+
+            -- 批处理请求项表
+            CREATE TABLE IF NOT EXISTS batch_items (
+                batch_id TEXT NOT NULL,
+                idx INTEGER NOT NULL,
+                custom_id TEXT NOT NULL,
+                body TEXT NOT NULL,
+                status TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                not_before INTEGER NOT NULL DEFAULT 0,
+                response_status INTEGER,
+                response_body TEXT,
+                completed_at INTEGER,
+                PRIMARY KEY (batch_id, idx),
+                FOREIGN KEY (batch_id) REFERENCES batch_jobs(id) ON DELETE CASCADE
+            );
+
+            -- 批处理文件表
+            CREATE TABLE IF NOT EXISTS batch_files (
+                id TEXT PRIMARY KEY,
+                filename TEXT NOT NULL,
+                purpose TEXT NOT NULL,
+                bytes INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                content BLOB NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_batch_items_status ON batch_items(status, not_before);
+            CREATE INDEX IF NOT EXISTS idx_batch_jobs_kind ON batch_jobs(kind, created_at);
+            "#,
+        )?;
+        Ok(())
+    }
+
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.db.lock().map_err(|_| BatchError::LockPoisoned)
+    }
+
+    // ========================================================================
+    // 文件
+    // ========================================================================
+
+    /// 保存文件
+    pub fn create_file(&self, filename: &str, purpose: &str, content: &[u8]) -> Result<BatchFile> {
+        let conn = self.conn()?;
+        Self::insert_file(&conn, filename, purpose, content)
+    }
+
+    fn insert_file(
+        conn: &Connection,
+        filename: &str,
+        purpose: &str,
+        content: &[u8],
+    ) -> Result<BatchFile> {
+        let file = BatchFile {
+            id: format!("file-{}", Uuid::new_v4().simple()),
+            filename: filename.to_string(),
+            purpose: purpose.to_string(),
+            bytes: content.len() as u64,
+            created_at: Utc::now().timestamp(),
+        };
+        conn.execute(
+            "INSERT INTO batch_files (id, filename, purpose, bytes, created_at, content)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                file.id,
+                file.filename,
+                file.purpose,
+                file.bytes as i64,
+                file.created_at,
+                content
+            ],
+        )?;
+        Ok(file)
+    }
+
+    /// 获取文件元数据
+    pub fn get_file(&self, id: &str) -> Result<Option<BatchFile>> {
+        let conn = self.conn()?;
+        Ok(conn
+            .query_row(
+                "SELECT id, filename, purpose, bytes, created_at FROM batch_files WHERE id = ?1",
+                params![id],
+                Self::row_to_file,
+            )
+            .optional()?)
+    }
+
+    /// 获取文件内容
+    pub fn get_file_content(&self, id: &str) -> Result<Option<Vec<u8>>> {
+        let conn = self.conn()?;
+        Ok(conn
+            .query_row(
+                "SELECT content FROM batch_files WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// 列出文件（按创建时间倒序）
+    pub fn list_files(&self, purpose: Option<&str>) -> Result<Vec<BatchFile>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, filename, purpose, bytes, created_at FROM batch_files
+             WHERE ?1 IS NULL OR purpose = ?1 ORDER BY created_at DESC, id",
+        )?;
+        let files = stmt
+            .query_map(params![purpose], Self::row_to_file)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(files)
+    }
+
+    /// 删除文件
+    pub fn delete_file(&self, id: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        Ok(conn.execute("DELETE FROM batch_files WHERE id = ?1", params![id])? > 0)
+    }
+
+    fn row_to_file(row: &Row) -> rusqlite::Result<BatchFile> {
+        Ok(BatchFile {
+            id: row.get(0)?,
+            filename: row.get(1)?,
+            purpose: row.get(2)?,
+            bytes: row.get::<_, i64>(3)? as u64,
+            created_at: row.get(4)?,
+        })
+    }
+
+    // ========================================================================
+    // 批处理任务
+    // ========================================================================
+
+    /// 创建批处理任务并将所有请求入队
+    pub fn create_batch(
+        &self,
+        kind: BatchKind,
+        endpoint: &str,
+        input_file_id: Option<&str>,
+        metadata: Option<Value>,
+        requests: Vec<NewBatchRequest>,
+    ) -> Result<BatchJob> {
+        let now = Utc::now().timestamp();
+        let id = match kind {
+            BatchKind::Anthropic => format!("msgbatch_{}", Uuid::new_v4().simple()),
+            BatchKind::OpenAi => format!("batch_{}", Uuid::new_v4().simple()),
+        };
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO batch_jobs (id, kind, status, endpoint, input_file_id, completion_window,
+                                     metadata, created_at, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, '24h', ?6, ?7, ?8)",
+            params![
+                id,
+                kind.as_str(),
+                BatchStatus::InProgress.as_str(),
+                endpoint,
+                input_file_id,
+                metadata.as_ref().map(|m| m.to_string()),
+                now,
+                now + DEFAULT_COMPLETION_WINDOW_SECS
+            ],
+        )?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO batch_items (batch_id, idx, custom_id, body, status)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for (idx, request) in requests.iter().enumerate() {
+                stmt.execute(params![
+                    id,
+                    idx as i64,
+                    request.custom_id,
+                    request.body.to_string(),
+                    BatchItemStatus::Pending.as_str()
+                ])?;
+            }
+        }
+        tx.commit()?;
+
+        Self::load_batch(&conn, &id)?.ok_or(BatchError::BatchNotFound(id))
+    }
+
+    /// 获取批处理任务
+    pub fn get_batch(&self, id: &str) -> Result<Option<BatchJob>> {
+        let conn = self.conn()?;
+        Self::load_batch(&conn, id)
+    }
+
+    /// 列出批处理任务（按创建时间倒序）
+    ///
+    /// `after_id` 用于分页：返回创建时间早于该任务的记录
+    pub fn list_batches(
+        &self,
+        kind: BatchKind,
+        limit: usize,
+        after_id: Option<&str>,
+    ) -> Result<Vec<BatchJob>> {
+        let conn = self.conn()?;
+        let cursor: Option<(i64, String)> = match after_id {
+            Some(after) => conn
+                .query_row(
+                    "SELECT created_at, id FROM batch_jobs WHERE id = ?1",
+                    params![after],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?,
+            None => None,
+        };
+        let (cursor_ts, cursor_id) = cursor.unwrap_or((i64::MAX, String::new()));
+
+        let mut stmt = conn.prepare(
+            "SELECT id FROM batch_jobs
+             WHERE kind = ?1 AND (created_at < ?2 OR (created_at = ?2 AND id < ?3))
+             ORDER BY created_at DESC, id DESC LIMIT ?4",
+        )?;
+        let ids = stmt
+            .query_map(
+                params![kind.as_str(), cursor_ts, cursor_id, limit as i64],
+                |row| row.get::<_, String>(0),
+            )?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        drop(stmt);
+
+        let mut jobs = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(job) = Self::load_batch(&conn, &id)? {
+                jobs.push(job);
+            }
+        }
+        Ok(jobs)
+    }
+
+    /// 请求取消批处理任务
+    ///
+    /// 尚未执行的请求立即标记为已取消；执行中的请求完成后任务结束。
+    pub fn cancel_batch(&self, id: &str) -> Result<BatchJob> {
+        let now = Utc::now().timestamp();
+        {
+            let conn = self.conn()?;
+            let job = Self::load_batch(&conn, id)?
+                .ok_or_else(|| BatchError::BatchNotFound(id.to_string()))?;
+            match job.status {
+                BatchStatus::Ended => return Err(BatchError::AlreadyEnded(id.to_string())),
+                BatchStatus::Canceling => return Ok(job),
+                BatchStatus::InProgress => {}
+            }
+            conn.execute(
+                "UPDATE batch_jobs SET status = ?2, cancel_initiated_at = ?3 WHERE id = ?1",
+                params![id, BatchStatus::Canceling.as_str(), now],
+            )?;
+            conn.execute(
+                "UPDATE batch_items SET status = ?2, completed_at = ?3
+                 WHERE batch_id = ?1 AND status = ?4",
+                params![
+                    id,
+                    BatchItemStatus::Canceled.as_str(),
+                    now,
+                    BatchItemStatus::Pending.as_str()
+                ],
+            )?;
+        }
+        self.finalize_batch(id)?;
+        self.get_batch(id)?
+            .ok_or_else(|| BatchError::BatchNotFound(id.to_string()))
+    }
+
+    /// 将超出完成窗口的待执行请求标记为过期，返回受影响的任务 ID
+    pub fn expire_overdue(&self, now: i64) -> Result<Vec<String>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT j.id FROM batch_jobs j JOIN batch_items i ON i.batch_id = j.id
+             WHERE j.status != ?1 AND j.expires_at <= ?2 AND i.status = ?3",
+        )?;
+        let ids = stmt
+            .query_map(
+                params![
+                    BatchStatus::Ended.as_str(),
+                    now,
+                    BatchItemStatus::Pending.as_str()
+                ],
+                |row| row.get::<_, String>(0),
+            )?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        drop(stmt);
+
+        for id in &ids {
+            conn.execute(
+                "UPDATE batch_items SET status = ?2, completed_at = ?3
+                 WHERE batch_id = ?1 AND status = ?4",
+                params![
+                    id,
+                    BatchItemStatus::Expired.as_str(),
+                    now,
+                    BatchItemStatus::Pending.as_str()
+                ],
+            )?;
+        }
+        Ok(ids)
+    }
+
+    /// 若任务中没有待执行或执行中的请求，则结束任务
+    ///
+    /// OpenAI 任务结束时生成输出文件和错误文件。返回任务是否在本次调用中结束。
+    pub fn finalize_batch(&self, id: &str) -> Result<bool> {
+        let mut conn = self.conn()?;
+        let Some(job) = Self::load_batch(&conn, id)? else {
+            return Ok(false);
+        };
+        if job.status == BatchStatus::Ended || job.request_counts.processing > 0 {
+            return Ok(false);
+        }
+
+        let now = Utc::now().timestamp();
+        let tx = conn.transaction()?;
+        let (mut output_file_id, mut error_file_id) = (None, None);
+        if job.kind == BatchKind::OpenAi {
+            let items = Self::load_items(&tx, id)?;
+            let (succeeded, failed): (Vec<_>, Vec<_>) =
+                items.iter().partition(|item| item.is_openai_success());
+            let to_jsonl = |items: &[&BatchItem]| -> String {
+                items
+                    .iter()
+                    .map(|item| format!("{}\n", item.to_openai_output()))
+                    .collect()
+            };
+            if !succeeded.is_empty() {
+                let file = Self::insert_file(
+                    &tx,
+                    &format!("{}_output.jsonl", id),
+                    BATCH_OUTPUT_FILE_PURPOSE,
+                    to_jsonl(&succeeded).as_bytes(),
+                )?;
+                output_file_id = Some(file.id);
+            }
+            if !failed.is_empty() {
+                let file = Self::insert_file(
+                    &tx,
+                    &format!("{}_error.jsonl", id),
+                    BATCH_OUTPUT_FILE_PURPOSE,
+                    to_jsonl(&failed).as_bytes(),
+                )?;
+                error_file_id = Some(file.id);
+            }
+        }
+        tx.execute(
+            "UPDATE batch_jobs SET status = ?2, ended_at = ?3, output_file_id = ?4,
+                                   error_file_id = ?5
+             WHERE id = ?1",
+            params![
+                id,
+                BatchStatus::Ended.as_str(),
+                now,
+                output_file_id,
+                error_file_id
+            ],
+        )?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// 获取任务的全部请求项（按序号排列）
+    pub fn get_items(&self, batch_id: &str) -> Result<Vec<BatchItem>> {
+        let conn = self.conn()?;
+        Self::load_items(&conn, batch_id)
+    }
+
+    fn load_batch(conn: &Connection, id: &str) -> Result<Option<BatchJob>> {
+        let job = conn
+            .query_row(
+                "SELECT id, kind, status, endpoint, input_file_id, output_file_id, error_file_id,
+                        completion_window, metadata, created_at, expires_at,
+                        cancel_initiated_at, ended_at
+                 FROM batch_jobs WHERE id = ?1",
+                params![id],
+                |row| {
+                    let kind: String = row.get(1)?;
+                    let status: String = row.get(2)?;
+                    let metadata: Option<String> = row.get(8)?;
+                    Ok(BatchJob {
+                        id: row.get(0)?,
+                        kind: BatchKind::parse(&kind).unwrap_or(BatchKind::OpenAi),
+                        status: BatchStatus::parse(&status).unwrap_or(BatchStatus::Ended),
+                        endpoint: row.get(3)?,
+                        input_file_id: row.get(4)?,
+                        output_file_id: row.get(5)?,
+                        error_file_id: row.get(6)?,
+                        completion_window: row.get(7)?,
+                        metadata: metadata.and_then(|m| serde_json::from_str(&m).ok()),
+                        created_at: row.get(9)?,
+                        expires_at: row.get(10)?,
+                        cancel_initiated_at: row.get(11)?,
+                        ended_at: row.get(12)?,
+                        request_counts: BatchRequestCounts::default(),
+                    })
+                },
+            )
+            .optional()?;
+        let Some(mut job) = job else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn.prepare(
+            "SELECT status, COUNT(*) FROM batch_items WHERE batch_id = ?1 GROUP BY status",
+        )?;
+        let rows = stmt.query_map(params![id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+        })?;
+        for row in rows {
+            let (status, count) = row?;
+            let counts = &mut job.request_counts;
+            match BatchItemStatus::parse(&status) {
+                Some(BatchItemStatus::Pending | BatchItemStatus::Running) => {
+                    counts.processing += count
+                }
+                Some(BatchItemStatus::Succeeded) => counts.succeeded += count,
+                Some(BatchItemStatus::Errored) => counts.errored += count,
+                Some(BatchItemStatus::Canceled) => counts.canceled += count,
+                Some(BatchItemStatus::Expired) => counts.expired += count,
+                None => {}
+            }
+        }
+        Ok(Some(job))
+    }
+
+    fn load_items(conn: &Connection, batch_id: &str) -> Result<Vec<BatchItem>> {
+        let mut stmt = conn.prepare(
+            "SELECT i.batch_id, i.idx, j.kind, i.custom_id, i.body, i.status, i.attempts,
+                    i.response_status, i.response_body
+             FROM batch_items i JOIN batch_jobs j ON j.id = i.batch_id
+             WHERE i.batch_id = ?1 ORDER BY i.idx",
+        )?;
+        let items = stmt
+            .query_map(params![batch_id], Self::row_to_item)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(items)
+    }
+
+    fn row_to_item(row: &Row) -> rusqlite::Result<BatchItem> {
+        let kind: String = row.get(2)?;
+        let body: String = row.get(4)?;
+        let status: String = row.get(5)?;
+        let response_body: Option<String> = row.get(8)?;
+        Ok(BatchItem {
+            batch_id: row.get(0)?,
+            idx: row.get(1)?,
+            kind: BatchKind::parse(&kind).unwrap_or(BatchKind::OpenAi),
+            custom_id: row.get(3)?,
+            body: serde_json::from_str(&body).unwrap_or(Value::Null),
+            status: BatchItemStatus::parse(&status).unwrap_or(BatchItemStatus::Errored),
+            attempts: row.get::<_, i64>(6)? as u32,
+            response_status: row.get::<_, Option<i64>>(7)?.map(|s| s as u16),
+            response_body: response_body.and_then(|b| serde_json::from_str(&b).ok()),
+        })
+    }
+
+    // ========================================================================
+    // 队列操作
+    // ========================================================================
+
+    /// 领取下一个可执行的请求项并标记为执行中
+    ///
+    /// 按任务创建时间和请求序号先进先出，跳过推迟时间未到的请求
+    pub fn claim_next_item(&self, now: i64) -> Result<Option<BatchItem>> {
+        let conn = self.conn()?;
+        let item = conn
+            .query_row(
+                "SELECT i.batch_id, i.idx, j.kind, i.custom_id, i.body, i.status, i.attempts,
+                        i.response_status, i.response_body
+                 FROM batch_items i JOIN batch_jobs j ON j.id = i.batch_id
+                 WHERE i.status = ?1 AND i.not_before <= ?2 AND j.status = ?3
+                 ORDER BY j.created_at, i.batch_id, i.idx LIMIT 1",
+                params![
+                    BatchItemStatus::Pending.as_str(),
+                    now,
+                    BatchStatus::InProgress.as_str()
+                ],
+                Self::row_to_item,
+            )
+            .optional()?;
+        let Some(mut item) = item else {
+            return Ok(None);
+        };
+        conn.execute(
+            "UPDATE batch_items SET status = ?3 WHERE batch_id = ?1 AND idx = ?2",
+            params![item.batch_id, item.idx, BatchItemStatus::Running.as_str()],
+        )?;
+        item.status = BatchItemStatus::Running;
+        Ok(Some(item))
+    }
+
+    /// 将执行中的请求项放回队列，在 `not_before` 之后重试，返回累计推迟次数
+    pub fn defer_item(&self, batch_id: &str, idx: i64, not_before: i64) -> Result<u32> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE batch_items SET status = ?3, attempts = attempts + 1, not_before = ?4
+             WHERE batch_id = ?1 AND idx = ?2",
+            params![batch_id, idx, BatchItemStatus::Pending.as_str(), not_before],
+        )?;
+        Ok(conn.query_row(
+            "SELECT attempts FROM batch_items WHERE batch_id = ?1 AND idx = ?2",
+            params![batch_id, idx],
+            |row| row.get::<_, i64>(0),
+        )? as u32)
+    }
+
+    /// 将执行中的请求项放回队列但不计入尝试次数（凭证冷却等节流场景）
+    pub fn release_item(&self, batch_id: &str, idx: i64, not_before: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE batch_items SET status = ?3, not_before = ?4 WHERE batch_id = ?1 AND idx = ?2",
+            params![batch_id, idx, BatchItemStatus::Pending.as_str(), not_before],
+        )?;
+        Ok(())
+    }
+
+    /// 记录请求项的执行结果
+    pub fn complete_item(
+        &self,
+        batch_id: &str,
+        idx: i64,
+        status: BatchItemStatus,
+        response_status: Option<u16>,
+        response_body: Option<&Value>,
+    ) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE batch_items SET status = ?3, response_status = ?4, response_body = ?5,
+                                    completed_at = ?6
+             WHERE batch_id = ?1 AND idx = ?2",
+            params![
+                batch_id,
+                idx,
+                status.as_str(),
+                response_status.map(|s| s as i64),
+                response_body.map(|b| b.to_string()),
+                Utc::now().timestamp()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 将上次运行中断时遗留的执行中请求放回队列（启动时调用）
+    pub fn requeue_running(&self) -> Result<usize> {
+        let conn = self.conn()?;
+        Ok(conn.execute(
+            "UPDATE batch_items SET status = ?1 WHERE status = ?2",
+            params![
+                BatchItemStatus::Pending.as_str(),
+                BatchItemStatus::Running.as_str()
+            ],
+        )?)
+    }
+
+    /// 取消中的任务在执行中请求全部完成后结束，返回本次结束的任务 ID
+    pub fn finalize_idle_batches(&self) -> Result<Vec<String>> {
+        let ids: Vec<String> = {
+            let conn = self.conn()?;
+            let mut stmt = conn.prepare(
+                "SELECT j.id FROM batch_jobs j WHERE j.status != ?1 AND NOT EXISTS (
+                     SELECT 1 FROM batch_items i
+                     WHERE i.batch_id = j.id AND i.status IN (?2, ?3))",
+            )?;
+            let ids = stmt
+                .query_map(
+                    params![
+                        BatchStatus::Ended.as_str(),
+                        BatchItemStatus::Pending.as_str(),
+                        BatchItemStatus::Running.as_str()
+                    ],
+                    |row| row.get::<_, String>(0),
+                )?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            ids
+        };
+        let mut finalized = Vec::new();
+        for id in ids {
+            if self.finalize_batch(&id)? {
+                finalized.push(id);
+            }
+        }
+        Ok(finalized)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn store() -> BatchStore {
+        BatchStore::in_memory().unwrap()
+    }
+
+    fn requests(n: usize) -> Vec<NewBatchRequest> {
+        (0..n)
+            .map(|i| NewBatchRequest {
+                custom_id: format!("req-{}", i),
+                body: json!({"model": "m", "messages": []}),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_files_roundtrip() {
+        let store = store();
+        let file = store.create_file("in.jsonl", "batch", b"{}\n").unwrap();
+        assert!(file.id.starts_with("file-"));
+        assert_eq!(file.bytes, 3);
+        assert_eq!(store.get_file_content(&file.id).unwrap().unwrap(), b"{}\n");
+        assert_eq!(store.list_files(Some("batch")).unwrap().len(), 1);
+        assert!(store.list_files(Some("other")).unwrap().is_empty());
+        assert!(store.delete_file(&file.id).unwrap());
+        assert!(store.get_file(&file.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_claim_complete_and_finalize_openai() {
+        let store = store();
+        let job = store
+            .create_batch(
+                BatchKind::OpenAi,
+                "/v1/chat/completions",
+                Some("file-in"),
+                None,
+                requests(2),
+            )
+            .unwrap();
+        assert_eq!(job.request_counts.processing, 2);
+
+        let now = Utc::now().timestamp();
+        let first = store.claim_next_item(now).unwrap().unwrap();
+        assert_eq!(first.idx, 0);
+        let second = store.claim_next_item(now).unwrap().unwrap();
+        assert_eq!(second.idx, 1);
+        assert!(store.claim_next_item(now).unwrap().is_none());
+
+        store
+            .complete_item(
+                &job.id,
+                0,
+                BatchItemStatus::Succeeded,
+                Some(200),
+                Some(&json!({"ok": true})),
+            )
+            .unwrap();
+        assert!(!store.finalize_batch(&job.id).unwrap());
+        store
+            .complete_item(&job.id, 1, BatchItemStatus::Errored, Some(500), None)
+            .unwrap();
+        assert!(store.finalize_batch(&job.id).unwrap());
+
+        let job = store.get_batch(&job.id).unwrap().unwrap();
+        assert_eq!(job.status, BatchStatus::Ended);
+        assert_eq!(job.openai_status(), "completed");
+        let output = store
+            .get_file_content(job.output_file_id.as_deref().unwrap())
+            .unwrap()
+            .unwrap();
+        let line: Value = serde_json::from_slice(output.trim_ascii_end()).unwrap();
+        assert_eq!(line["custom_id"], "req-0");
+        assert_eq!(line["response"]["body"]["ok"], true);
+        assert!(job.error_file_id.is_some());
+    }
+
+    #[test]
+    fn test_defer_respects_not_before() {
+        let store = store();
+        let job = store
+            .create_batch(
+                BatchKind::Anthropic,
+                "/v1/messages",
+                None,
+                None,
+                requests(1),
+            )
+            .unwrap();
+        let now = Utc::now().timestamp();
+        let item = store.claim_next_item(now).unwrap().unwrap();
+        assert_eq!(store.defer_item(&job.id, item.idx, now + 60).unwrap(), 1);
+        assert!(store.claim_next_item(now).unwrap().is_none());
+        let item = store.claim_next_item(now + 60).unwrap().unwrap();
+        assert_eq!(item.attempts, 1);
+
+        store.release_item(&job.id, item.idx, now + 120).unwrap();
+        assert!(store.claim_next_item(now + 60).unwrap().is_none());
+        let item = store.claim_next_item(now + 120).unwrap().unwrap();
+        assert_eq!(item.attempts, 1);
+    }
+
+    #[test]
+    fn test_cancel_batch() {
+        let store = store();
+        let job = store
+            .create_batch(
+                BatchKind::Anthropic,
+                "/v1/messages",
+                None,
+                None,
+                requests(3),
+            )
+            .unwrap();
+        let now = Utc::now().timestamp();
+        let running = store.claim_next_item(now).unwrap().unwrap();
+
+        let canceled = store.cancel_batch(&job.id).unwrap();
+        assert_eq!(canceled.status, BatchStatus::Canceling);
+        assert_eq!(canceled.request_counts.canceled, 2);
+        assert_eq!(canceled.request_counts.processing, 1);
+        assert!(store.claim_next_item(now).unwrap().is_none());
+
+        store
+            .complete_item(
+                &job.id,
+                running.idx,
+                BatchItemStatus::Succeeded,
+                Some(200),
+                Some(&json!({})),
+            )
+            .unwrap();
+        assert_eq!(store.finalize_idle_batches().unwrap(), vec![job.id.clone()]);
+        let ended = store.get_batch(&job.id).unwrap().unwrap();
+        assert_eq!(ended.status, BatchStatus::Ended);
+        assert!(ended.cancel_initiated_at.is_some());
+        assert!(matches!(
+            store.cancel_batch(&job.id),
+            Err(BatchError::AlreadyEnded(_))
+        ));
+    }
+
+    #[test]
+    fn test_expire_overdue_and_requeue_running() {
+        let store = store();
+        let job = store
+            .create_batch(
+                BatchKind::Anthropic,
+                "/v1/messages",
+                None,
+                None,
+                requests(2),
+            )
+            .unwrap();
+        let now = Utc::now().timestamp();
+        store.claim_next_item(now).unwrap().unwrap();
+        assert_eq!(store.requeue_running().unwrap(), 1);
+
+        let expired = store.expire_overdue(job.expires_at + 1).unwrap();
+        assert_eq!(expired, vec![job.id.clone()]);
+        store.finalize_batch(&job.id).unwrap();
+        let job = store.get_batch(&job.id).unwrap().unwrap();
+        assert_eq!(job.request_counts.expired, 2);
+        assert_eq!(job.status, BatchStatus::Ended);
+    }
+
+    #[test]
+    fn test_list_batches_pagination() {
+        let store = store();
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            ids.push(
+                store
+                    .create_batch(
+                        BatchKind::OpenAi,
+                        "/v1/chat/completions",
+                        None,
+                        None,
+                        requests(1),
+                    )
+                    .unwrap()
+                    .id,
+            );
+        }
+        store
+            .create_batch(
+                BatchKind::Anthropic,
+                "/v1/messages",
+                None,
+                None,
+                requests(1),
+            )
+            .unwrap();
+
+        let page1 = store.list_batches(BatchKind::OpenAi, 2, None).unwrap();
+        assert_eq!(page1.len(), 2);
+        let page2 = store
+            .list_batches(BatchKind::OpenAi, 2, Some(&page1[1].id))
+            .unwrap();
+        assert_eq!(page2.len(), 1);
+        let mut seen: Vec<_> = page1.iter().chain(&page2).map(|j| j.id.clone()).collect();
+        seen.sort();
+        ids.sort();
+        assert_eq!(seen, ids);
+    }
+}
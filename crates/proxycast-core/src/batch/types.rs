@@ -0,0 +1,758 @@
+//! 批处理任务数据类型
+//!
+//! 定义批处理任务、请求项、文件的数据结构，以及 Anthropic / OpenAI 两种
+//! 对外 JSON 格式的渲染和输入解析。
+
+use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::openai::ChatCompletionRequest;
+use chrono::{TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use thiserror::Error;
+
+/// 批处理输入文件用途
+pub const BATCH_FILE_PURPOSE: &str = "batch";
+
+/// 批处理输出文件用途
+pub const BATCH_OUTPUT_FILE_PURPOSE: &str = "batch_output";
+
+/// 默认完成窗口（24 小时）
+pub const DEFAULT_COMPLETION_WINDOW_SECS: i64 = 24 * 60 * 60;
+
+/// OpenAI 批处理支持的端点
+const SUPPORTED_OPENAI_ENDPOINTS: &[&str] = &["/v1/chat/completions"];
+
+// ============================================================================
+// 错误类型
+// ============================================================================
+
+/// 批处理错误
+#[derive(Debug, Error)]
+pub enum BatchError {
+    #[error("SQLite 错误: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("{0}")]
+    InvalidRequest(String),
+
+    #[error("批处理任务不存在: {0}")]
+    BatchNotFound(String),
+
+    #[error("文件不存在: {0}")]
+    FileNotFound(String),
+
+    #[error("批处理任务已结束，无法取消: {0}")]
+    AlreadyEnded(String),
+
+    #[error("数据库锁获取失败")]
+    LockPoisoned,
+
+    #[error("存储任务执行失败: {0}")]
+    Task(String),
+}
+
+impl BatchError {
+    /// 对应的 HTTP 状态码
+    pub fn status_code(&self) -> u16 {
+        match self {
+            BatchError::InvalidRequest(_) | BatchError::AlreadyEnded(_) => 400,
+            BatchError::BatchNotFound(_) | BatchError::FileNotFound(_) => 404,
+            BatchError::Sqlite(_) | BatchError::LockPoisoned | BatchError::Task(_) => 500,
+        }
+    }
+
+    /// OpenAI 格式的错误响应体
+    pub fn to_openai_json(&self) -> Value {
+        let error_type = match self.status_code() {
+            404 => "not_found_error",
+            400 => "invalid_request_error",
+            _ => "server_error",
+        };
+        json!({
+            "error": {
+                "message": self.to_string(),
+                "type": error_type,
+                "param": null,
+                "code": null
+            }
+        })
+    }
+
+    /// Anthropic 格式的错误响应体
+    pub fn to_anthropic_json(&self) -> Value {
+        let error_type = match self.status_code() {
+            404 => "not_found_error",
+            400 => "invalid_request_error",
+            _ => "api_error",
+        };
+        json!({
+            "type": "error",
+            "error": {
+                "type": error_type,
+                "message": self.to_string()
+            }
+        })
+    }
+}
+
+// ============================================================================
+// 枚举
+// ============================================================================
+
+/// 批处理任务类型（决定请求格式和对外 JSON 格式）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchKind {
+    /// Anthropic Message Batches
+    Anthropic,
+    /// OpenAI Batch API
+    #[serde(rename = "openai")]
+    OpenAi,
+}
+
+impl BatchKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BatchKind::Anthropic => "anthropic",
+            BatchKind::OpenAi => "openai",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "anthropic" => Some(BatchKind::Anthropic),
+            "openai" => Some(BatchKind::OpenAi),
+            _ => None,
+        }
+    }
+}
+
+/// 批处理任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    /// 处理中
+    InProgress,
+    /// 取消中（等待执行中的请求完成）
+    Canceling,
+    /// 已结束
+    Ended,
+}
+
+impl BatchStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BatchStatus::InProgress => "in_progress",
+            BatchStatus::Canceling => "canceling",
+            BatchStatus::Ended => "ended",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "in_progress" => Some(BatchStatus::InProgress),
+            "canceling" => Some(BatchStatus::Canceling),
+            "ended" => Some(BatchStatus::Ended),
+            _ => None,
+        }
+    }
+}
+
+/// 批处理请求项状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    /// 等待执行
+    Pending,
+    /// 执行中
+    Running,
+    /// 成功
+    Succeeded,
+    /// 失败
+    Errored,
+    /// 已取消
+    Canceled,
+    /// 超出完成窗口
+    Expired,
+}
+
+impl BatchItemStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BatchItemStatus::Pending => "pending",
+            BatchItemStatus::Running => "running",
+            BatchItemStatus::Succeeded => "succeeded",
+            BatchItemStatus::Errored => "errored",
+            BatchItemStatus::Canceled => "canceled",
+            BatchItemStatus::Expired => "expired",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(BatchItemStatus::Pending),
+            "running" => Some(BatchItemStatus::Running),
+            "succeeded" => Some(BatchItemStatus::Succeeded),
+            "errored" => Some(BatchItemStatus::Errored),
+            "canceled" => Some(BatchItemStatus::Canceled),
+            "expired" => Some(BatchItemStatus::Expired),
+            _ => None,
+        }
+    }
+
+    /// 是否为终态
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, BatchItemStatus::Pending | BatchItemStatus::Running)
+    }
+}
+
+// ============================================================================
+// 数据结构
+// ============================================================================
+
+/// 批处理请求计数
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchRequestCounts {
+    /// 等待或执行中
+    pub processing: u64,
+    pub succeeded: u64,
+    pub errored: u64,
+    pub canceled: u64,
+    pub expired: u64,
+}
+
+impl BatchRequestCounts {
+    pub fn total(&self) -> u64 {
+        self.processing + self.succeeded + self.errored + self.canceled + self.expired
+    }
+}
+
+/// 批处理任务
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchJob {
+    pub id: String,
+    pub kind: BatchKind,
+    pub status: BatchStatus,
+    /// 目标端点（如 `/v1/chat/completions`、`/v1/messages`）
+    pub endpoint: String,
+    pub input_file_id: Option<String>,
+    pub output_file_id: Option<String>,
+    pub error_file_id: Option<String>,
+    pub completion_window: String,
+    pub metadata: Option<Value>,
+    /// Unix 时间戳（秒）
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub cancel_initiated_at: Option<i64>,
+    pub ended_at: Option<i64>,
+    pub request_counts: BatchRequestCounts,
+}
+
+impl BatchJob {
+    /// 渲染为 Anthropic `message_batch` 对象
+    pub fn to_anthropic_json(&self, base_url: &str) -> Value {
+        let results_url = (self.status == BatchStatus::Ended).then(|| {
+            format!(
+                "{}/v1/messages/batches/{}/results",
+                base_url.trim_end_matches('/'),
+                self.id
+            )
+        });
+        json!({
+            "id": self.id,
+            "type": "message_batch",
+            "processing_status": self.status.as_str(),
+            "request_counts": {
+                "processing": self.request_counts.processing,
+                "succeeded": self.request_counts.succeeded,
+                "errored": self.request_counts.errored,
+                "canceled": self.request_counts.canceled,
+                "expired": self.request_counts.expired,
+            },
+            "ended_at": self.ended_at.map(rfc3339),
+            "created_at": rfc3339(self.created_at),
+            "expires_at": rfc3339(self.expires_at),
+            "archived_at": null,
+            "cancel_initiated_at": self.cancel_initiated_at.map(rfc3339),
+            "results_url": results_url,
+        })
+    }
+
+    /// OpenAI 格式的任务状态
+    pub fn openai_status(&self) -> &'static str {
+        match self.status {
+            BatchStatus::InProgress => "in_progress",
+            BatchStatus::Canceling => "cancelling",
+            BatchStatus::Ended if self.cancel_initiated_at.is_some() => "cancelled",
+            BatchStatus::Ended
+                if self.request_counts.expired > 0
+                    && self.request_counts.succeeded + self.request_counts.errored == 0 =>
+            {
+                "expired"
+            }
+            BatchStatus::Ended => "completed",
+        }
+    }
+
+    /// 渲染为 OpenAI `batch` 对象
+    pub fn to_openai_json(&self) -> Value {
+        let status = self.openai_status();
+        let ended_at = |s: &str| if status == s { self.ended_at } else { None };
+        json!({
+            "id": self.id,
+            "object": "batch",
+            "endpoint": self.endpoint,
+            "errors": null,
+            "input_file_id": self.input_file_id,
+            "completion_window": self.completion_window,
+            "status": status,
+            "output_file_id": self.output_file_id,
+            "error_file_id": self.error_file_id,
+            "created_at": self.created_at,
+            "in_progress_at": self.created_at,
+            "expires_at": self.expires_at,
+            "finalizing_at": self.ended_at,
+            "completed_at": ended_at("completed"),
+            "failed_at": null,
+            "expired_at": ended_at("expired"),
+            "cancelling_at": self.cancel_initiated_at,
+            "cancelled_at": ended_at("cancelled"),
+            "request_counts": {
+                "total": self.request_counts.total(),
+                "completed": self.request_counts.succeeded,
+                "failed": self.request_counts.errored
+                    + self.request_counts.canceled
+                    + self.request_counts.expired,
+            },
+            "metadata": self.metadata,
+        })
+    }
+}
+
+/// 批处理请求项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItem {
+    pub batch_id: String,
+    /// 在批处理任务中的序号（从 0 开始）
+    pub idx: i64,
+    pub kind: BatchKind,
+    pub custom_id: String,
+    /// 请求体（Anthropic `params` 或 OpenAI `body`）
+    pub body: Value,
+    pub status: BatchItemStatus,
+    /// 因配额冷却被推迟的次数
+    pub attempts: u32,
+    pub response_status: Option<u16>,
+    pub response_body: Option<Value>,
+}
+
+impl BatchItem {
+    /// 渲染为 Anthropic 结果 JSONL 行
+    pub fn to_anthropic_result(&self) -> Value {
+        let result = match self.status {
+            BatchItemStatus::Succeeded => json!({
+                "type": "succeeded",
+                "message": self.response_body,
+            }),
+            BatchItemStatus::Errored => json!({
+                "type": "errored",
+                "error": self.anthropic_error_body(),
+            }),
+            BatchItemStatus::Canceled => json!({"type": "canceled"}),
+            BatchItemStatus::Expired => json!({"type": "expired"}),
+            BatchItemStatus::Pending | BatchItemStatus::Running => json!({"type": "processing"}),
+        };
+        json!({
+            "custom_id": self.custom_id,
+            "result": result,
+        })
+    }
+
+    /// 失败项的 Anthropic 错误体，上游返回非 Anthropic 格式时进行包装
+    fn anthropic_error_body(&self) -> Value {
+        match &self.response_body {
+            Some(body) if body.get("type").and_then(|t| t.as_str()) == Some("error") => {
+                body.clone()
+            }
+            Some(body) => json!({
+                "type": "error",
+                "error": {
+                    "type": "api_error",
+                    "message": body
+                        .pointer("/error/message")
+                        .and_then(|m| m.as_str())
+                        .map(String::from)
+                        .unwrap_or_else(|| body.to_string()),
+                }
+            }),
+            None => json!({
+                "type": "error",
+                "error": {"type": "api_error", "message": "Unknown error"}
+            }),
+        }
+    }
+
+    /// 渲染为 OpenAI 输出/错误文件的 JSONL 行
+    pub fn to_openai_output(&self) -> Value {
+        let response = self.response_status.map(|status| {
+            json!({
+                "status_code": status,
+                "request_id": format!("{}-{}", self.batch_id, self.idx),
+                "body": self.response_body,
+            })
+        });
+        let error = match self.status {
+            BatchItemStatus::Canceled => json!({
+                "code": "batch_cancelled",
+                "message": "This request was cancelled before it was processed."
+            }),
+            BatchItemStatus::Expired => json!({
+                "code": "batch_expired",
+                "message": "This request could not be executed before the completion window expired."
+            }),
+            _ => Value::Null,
+        };
+        json!({
+            "id": format!("batch_req_{}_{}", self.batch_id.trim_start_matches("batch_"), self.idx),
+            "custom_id": self.custom_id,
+            "response": response,
+            "error": error,
+        })
+    }
+
+    /// 是否写入 OpenAI 输出文件（其余写入错误文件）
+    pub fn is_openai_success(&self) -> bool {
+        self.status == BatchItemStatus::Succeeded
+    }
+}
+
+/// 批处理文件元数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchFile {
+    pub id: String,
+    pub filename: String,
+    pub purpose: String,
+    pub bytes: u64,
+    pub created_at: i64,
+}
+
+impl BatchFile {
+    /// 渲染为 OpenAI `file` 对象
+    pub fn to_openai_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "object": "file",
+            "bytes": self.bytes,
+            "created_at": self.created_at,
+            "filename": self.filename,
+            "purpose": self.purpose,
+            "status": "processed",
+            "status_details": null,
+        })
+    }
+}
+
+/// 待入队的批处理请求
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewBatchRequest {
+    pub custom_id: String,
+    pub body: Value,
+}
+
+// ============================================================================
+// 输入解析
+// ============================================================================
+
+/// 解析 Anthropic `POST /v1/messages/batches` 请求体
+///
+/// 每个请求的 `params` 必须是合法的 Messages 请求，且 `custom_id` 在批次内唯一。
+/// 流式参数会被强制关闭。
+pub fn parse_anthropic_batch_requests(
+    payload: &Value,
+    max_requests: usize,
+) -> Result<Vec<NewBatchRequest>, BatchError> {
+    let requests = payload
+        .get("requests")
+        .and_then(|r| r.as_array())
+        .ok_or_else(|| BatchError::InvalidRequest("requests: Field required".to_string()))?;
+
+    let mut parsed = Vec::with_capacity(requests.len());
+    let mut seen = HashSet::new();
+    for (i, request) in requests.iter().enumerate() {
+        let custom_id = request
+            .get("custom_id")
+            .and_then(|c| c.as_str())
+            .ok_or_else(|| {
+                BatchError::InvalidRequest(format!("requests.{}.custom_id: Field required", i))
+            })?;
+        let mut params = request.get("params").cloned().ok_or_else(|| {
+            BatchError::InvalidRequest(format!("requests.{}.params: Field required", i))
+        })?;
+        if let Some(obj) = params.as_object_mut() {
+            obj.insert("stream".to_string(), Value::Bool(false));
+        }
+        serde_json::from_value::<AnthropicMessagesRequest>(params.clone())
+            .map_err(|e| BatchError::InvalidRequest(format!("requests.{}.params: {}", i, e)))?;
+        parsed.push(NewBatchRequest {
+            custom_id: custom_id.to_string(),
+            body: params,
+        });
+        if !seen.insert(custom_id) {
+            return Err(BatchError::InvalidRequest(format!(
+                "requests.{}.custom_id: Duplicate custom_id `{}`",
+                i, custom_id
+            )));
+        }
+    }
+
+    validate_request_count(parsed.len(), max_requests)?;
+    Ok(parsed)
+}
+
+/// 解析 OpenAI 批处理输入文件（JSONL）
+///
+/// 每行格式：`{"custom_id": "...", "method": "POST", "url": "/v1/chat/completions", "body": {...}}`，
+/// 所有行的 `url` 必须与批处理任务的 `endpoint` 一致。
+pub fn parse_openai_batch_input(
+    content: &str,
+    endpoint: &str,
+    max_requests: usize,
+) -> Result<Vec<NewBatchRequest>, BatchError> {
+    if !SUPPORTED_OPENAI_ENDPOINTS.contains(&endpoint) {
+        return Err(BatchError::InvalidRequest(format!(
+            "Unsupported endpoint `{}`, supported: {}",
+            endpoint,
+            SUPPORTED_OPENAI_ENDPOINTS.join(", ")
+        )));
+    }
+
+    let mut parsed = Vec::new();
+    let mut seen = HashSet::new();
+    for (i, line) in content.lines().enumerate() {
+        let line_no = i + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid =
+            |msg: String| BatchError::InvalidRequest(format!("line {}: {}", line_no, msg));
+
+        let value: Value = serde_json::from_str(line).map_err(|e| invalid(e.to_string()))?;
+        let custom_id = value
+            .get("custom_id")
+            .and_then(|c| c.as_str())
+            .ok_or_else(|| invalid("missing custom_id".to_string()))?;
+        let method = value
+            .get("method")
+            .and_then(|m| m.as_str())
+            .unwrap_or("POST");
+        if !method.eq_ignore_ascii_case("POST") {
+            return Err(invalid(format!("unsupported method `{}`", method)));
+        }
+        let url = value
+            .get("url")
+            .and_then(|u| u.as_str())
+            .unwrap_or(endpoint);
+        if url != endpoint {
+            return Err(invalid(format!(
+                "url `{}` does not match batch endpoint `{}`",
+                url, endpoint
+            )));
+        }
+        let mut body = value
+            .get("body")
+            .cloned()
+            .ok_or_else(|| invalid("missing body".to_string()))?;
+        if let Some(obj) = body.as_object_mut() {
+            obj.insert("stream".to_string(), Value::Bool(false));
+        }
+        serde_json::from_value::<ChatCompletionRequest>(body.clone())
+            .map_err(|e| invalid(e.to_string()))?;
+        if !seen.insert(custom_id.to_string()) {
+            return Err(invalid(format!("duplicate custom_id `{}`", custom_id)));
+        }
+        parsed.push(NewBatchRequest {
+            custom_id: custom_id.to_string(),
+            body,
+        });
+    }
+
+    validate_request_count(parsed.len(), max_requests)?;
+    Ok(parsed)
+}
+
+fn validate_request_count(count: usize, max_requests: usize) -> Result<(), BatchError> {
+    if count == 0 {
+        return Err(BatchError::InvalidRequest(
+            "Batch must contain at least one request".to_string(),
+        ));
+    }
+    if count > max_requests {
+        return Err(BatchError::InvalidRequest(format!(
+            "Batch contains {} requests, limit is {}",
+            count, max_requests
+        )));
+    }
+    Ok(())
+}
+
+/// Unix 时间戳转 RFC 3339 字符串
+fn rfc3339(ts: i64) -> String {
+    Utc.timestamp_opt(ts, 0)
+        .single()
+        .unwrap_or_default()
+        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anthropic_params() -> Value {
+        json!({
+            "model": "claude-sonnet-4-5",
+            "max_tokens": 64,
+            "messages": [{"role": "user", "content": "hi"}]
+        })
+    }
+
+    #[test]
+    fn test_parse_anthropic_batch_requests() {
+        let payload = json!({
+            "requests": [
+                {"custom_id": "a", "params": anthropic_params()},
+                {"custom_id": "b", "params": anthropic_params()}
+            ]
+        });
+        let parsed = parse_anthropic_batch_requests(&payload, 10).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].custom_id, "b");
+        assert_eq!(parsed[0].body["stream"], false);
+    }
+
+    #[test]
+    fn test_parse_anthropic_batch_requests_rejects_duplicates_and_limits() {
+        let payload = json!({
+            "requests": [
+                {"custom_id": "a", "params": anthropic_params()},
+                {"custom_id": "a", "params": anthropic_params()}
+            ]
+        });
+        let err = parse_anthropic_batch_requests(&payload, 10).unwrap_err();
+        assert!(err.to_string().contains("Duplicate custom_id"));
+
+        let payload = json!({
+            "requests": [
+                {"custom_id": "a", "params": anthropic_params()},
+                {"custom_id": "b", "params": anthropic_params()}
+            ]
+        });
+        let err = parse_anthropic_batch_requests(&payload, 1).unwrap_err();
+        assert_eq!(err.status_code(), 400);
+
+        let err = parse_anthropic_batch_requests(&json!({"requests": []}), 10).unwrap_err();
+        assert!(err.to_string().contains("at least one"));
+    }
+
+    #[test]
+    fn test_parse_openai_batch_input() {
+        let content = concat!(
+            r#"{"custom_id":"r1","method":"POST","url":"/v1/chat/completions","body":{"model":"gpt-4o","messages":[{"role":"user","content":"hi"}]}}"#,
+            "\n\n",
+            r#"{"custom_id":"r2","method":"POST","url":"/v1/chat/completions","body":{"model":"gpt-4o","messages":[{"role":"user","content":"yo"}],"stream":true}}"#,
+            "\n"
+        );
+        let parsed = parse_openai_batch_input(content, "/v1/chat/completions", 10).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].body["stream"], false);
+    }
+
+    #[test]
+    fn test_parse_openai_batch_input_errors() {
+        let err = parse_openai_batch_input("{}", "/v1/embeddings", 10).unwrap_err();
+        assert!(err.to_string().contains("Unsupported endpoint"));
+
+        let line =
+            r#"{"custom_id":"r1","url":"/v1/completions","body":{"model":"m","messages":[]}}"#;
+        let err = parse_openai_batch_input(line, "/v1/chat/completions", 10).unwrap_err();
+        assert!(err.to_string().starts_with("line 1:"));
+
+        let err = parse_openai_batch_input("not json", "/v1/chat/completions", 10).unwrap_err();
+        assert_eq!(err.status_code(), 400);
+    }
+
+    fn job(status: BatchStatus, counts: BatchRequestCounts) -> BatchJob {
+        BatchJob {
+            id: "batch_1".to_string(),
+            kind: BatchKind::OpenAi,
+            status,
+            endpoint: "/v1/chat/completions".to_string(),
+            input_file_id: Some("file-1".to_string()),
+            output_file_id: None,
+            error_file_id: None,
+            completion_window: "24h".to_string(),
+            metadata: None,
+            created_at: 1_700_000_000,
+            expires_at: 1_700_086_400,
+            cancel_initiated_at: None,
+            ended_at: None,
+            request_counts: counts,
+        }
+    }
+
+    #[test]
+    fn test_batch_job_status_mapping() {
+        let mut j = job(BatchStatus::InProgress, BatchRequestCounts::default());
+        assert_eq!(j.openai_status(), "in_progress");
+        assert!(j.to_anthropic_json("http://x")["results_url"].is_null());
+
+        j.status = BatchStatus::Ended;
+        j.ended_at = Some(1_700_000_100);
+        j.request_counts.succeeded = 1;
+        assert_eq!(j.openai_status(), "completed");
+        assert_eq!(j.to_openai_json()["completed_at"], 1_700_000_100);
+        assert_eq!(
+            j.to_anthropic_json("http://x/")["results_url"],
+            "http://x/v1/messages/batches/batch_1/results"
+        );
+
+        j.cancel_initiated_at = Some(1_700_000_050);
+        assert_eq!(j.openai_status(), "cancelled");
+
+        let mut expired = job(
+            BatchStatus::Ended,
+            BatchRequestCounts {
+                expired: 2,
+                ..Default::default()
+            },
+        );
+        expired.ended_at = Some(1);
+        assert_eq!(expired.openai_status(), "expired");
+        assert_eq!(expired.to_openai_json()["request_counts"]["failed"], 2);
+    }
+
+    #[test]
+    fn test_batch_item_results() {
+        let mut item = BatchItem {
+            batch_id: "msgbatch_1".to_string(),
+            idx: 0,
+            kind: BatchKind::Anthropic,
+            custom_id: "a".to_string(),
+            body: anthropic_params(),
+            status: BatchItemStatus::Errored,
+            attempts: 0,
+            response_status: Some(500),
+            response_body: Some(json!({"error": {"message": "boom"}})),
+        };
+        let result = item.to_anthropic_result();
+        assert_eq!(result["result"]["type"], "errored");
+        assert_eq!(result["result"]["error"]["error"]["message"], "boom");
+
+        item.status = BatchItemStatus::Canceled;
+        item.response_status = None;
+        assert_eq!(item.to_anthropic_result()["result"]["type"], "canceled");
+        let output = item.to_openai_output();
+        assert_eq!(output["error"]["code"], "batch_cancelled");
+        assert!(output["response"].is_null());
+    }
+}
@@ -0,0 +1,317 @@
+//! 配置语义差异
+//!
+//! 比较两份已解析的配置（而非文本），忽略格式、注释与字段顺序的差别。
+//! 带 `id` 或 `name` 的列表项（凭证、规则、Sink 等）按标识匹配，调整顺序不会产生差异。
+//! 输出中的值经过 [`ExportService::redact_config`] 脱敏，密钥变更只提示“已变更”。
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::export::ExportService;
+use super::types::Config;
+
+/// 列表项的标识字段（按顺序尝试）
+const IDENTITY_FIELDS: &[&str] = &["id", "name"];
+
+/// 变更类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// 新增字段或列表项
+    Added,
+    /// 删除字段或列表项
+    Removed,
+    /// 值被修改
+    Modified,
+}
+
+/// 单项配置变更
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigChange {
+    /// 字段路径（如 `credential_pool.kiro[id=kiro-1].disabled`）
+    pub path: String,
+    /// 变更类型
+    pub kind: ChangeKind,
+    /// 变更前的值（已脱敏）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+    /// 变更后的值（已脱敏）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+    /// 是否涉及敏感字段
+    pub secret: bool,
+}
+
+impl fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |value: &Option<Value>| {
+            value
+                .as_ref()
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "null".to_string())
+        };
+        match self.kind {
+            ChangeKind::Added => write!(f, "+ {}: {}", self.path, show(&self.after)),
+            ChangeKind::Removed => write!(f, "- {}: {}", self.path, show(&self.before)),
+            ChangeKind::Modified if self.secret && self.before == self.after => {
+                write!(f, "~ {}: （敏感值已变更）", self.path)
+            }
+            ChangeKind::Modified => write!(
+                f,
+                "~ {}: {} -> {}",
+                self.path,
+                show(&self.before),
+                show(&self.after)
+            ),
+        }
+    }
+}
+
+/// 路径片段
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Key(String),
+    Index(usize),
+    /// 按标识字段匹配的列表项
+    Item {
+        field: &'static str,
+        value: String,
+    },
+}
+
+fn format_path(path: &[Step]) -> String {
+    let mut out = String::new();
+    for step in path {
+        match step {
+            Step::Key(name) => {
+                if !out.is_empty() {
+                    out.push('.');
+                }
+                out.push_str(name);
+            }
+            Step::Index(index) => out.push_str(&format!("[{}]", index)),
+            Step::Item { field, value } => out.push_str(&format!("[{}={}]", field, value)),
+        }
+    }
+    out
+}
+
+fn lookup<'a>(value: &'a Value, path: &[Step]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |current, step| match step {
+        Step::Key(name) => current.get(name),
+        Step::Index(index) => current.get(*index),
+        Step::Item { field, value } => current
+            .as_array()?
+            .iter()
+            .find(|item| identity(item, field) == Some(value.as_str())),
+    })
+}
+
+fn identity<'a>(item: &'a Value, field: &str) -> Option<&'a str> {
+    item.get(field).and_then(Value::as_str)
+}
+
+/// 找出两侧列表共同可用的标识字段（每一项都有且不重复）
+fn identity_field(before: &[Value], after: &[Value]) -> Option<&'static str> {
+    IDENTITY_FIELDS.iter().copied().find(|field| {
+        [before, after].iter().all(|items| {
+            let mut seen = BTreeSet::new();
+            items
+                .iter()
+                .all(|item| identity(item, field).is_some_and(|id| seen.insert(id)))
+        })
+    })
+}
+
+fn walk(
+    before: &Value,
+    after: &Value,
+    path: &mut Vec<Step>,
+    changes: &mut Vec<(Vec<Step>, ChangeKind)>,
+) {
+    match (before, after) {
+        (Value::Object(a), Value::Object(b)) => {
+            let names: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for name in names {
+                path.push(Step::Key(name.clone()));
+                match (a.get(name), b.get(name)) {
+                    (Some(x), Some(y)) => walk(x, y, path, changes),
+                    (Some(_), None) => changes.push((path.clone(), ChangeKind::Removed)),
+                    (None, Some(_)) => changes.push((path.clone(), ChangeKind::Added)),
+                    (None, None) => {}
+                }
+                path.pop();
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if !a.is_empty() || !b.is_empty() => {
+            match identity_field(a, b) {
+                Some(field) => {
+                    let find = |items: &'_ [Value], id: &str| {
+                        items
+                            .iter()
+                            .find(|item| identity(item, field) == Some(id))
+                            .cloned()
+                    };
+                    let mut ids: Vec<&str> = a.iter().filter_map(|i| identity(i, field)).collect();
+                    ids.extend(
+                        b.iter()
+                            .filter_map(|i| identity(i, field))
+                            .filter(|id| find(a, id).is_none()),
+                    );
+                    for id in ids {
+                        path.push(Step::Item {
+                            field,
+                            value: id.to_string(),
+                        });
+                        match (find(a, id), find(b, id)) {
+                            (Some(x), Some(y)) => walk(&x, &y, path, changes),
+                            (Some(_), None) => changes.push((path.clone(), ChangeKind::Removed)),
+                            (None, Some(_)) => changes.push((path.clone(), ChangeKind::Added)),
+                            (None, None) => {}
+                        }
+                        path.pop();
+                    }
+                }
+                None => {
+                    for index in 0..a.len().max(b.len()) {
+                        path.push(Step::Index(index));
+                        match (a.get(index), b.get(index)) {
+                            (Some(x), Some(y)) => walk(x, y, path, changes),
+                            (Some(_), None) => changes.push((path.clone(), ChangeKind::Removed)),
+                            (None, Some(_)) => changes.push((path.clone(), ChangeKind::Added)),
+                            (None, None) => {}
+                        }
+                        path.pop();
+                    }
+                }
+            }
+        }
+        _ => {
+            if before != after {
+                changes.push((path.clone(), ChangeKind::Modified));
+            }
+        }
+    }
+}
+
+/// 计算两份配置之间的语义差异
+///
+/// `current` 通常为运行中的配置，`candidate` 为待应用的配置。
+pub fn diff_configs(current: &Config, candidate: &Config) -> Vec<ConfigChange> {
+    let to_value = |config: &Config| serde_json::to_value(config).unwrap_or(Value::Null);
+    let raw_before = to_value(current);
+    let raw_after = to_value(candidate);
+    let shown_before = to_value(&ExportService::redact_config(current));
+    let shown_after = to_value(&ExportService::redact_config(candidate));
+
+    let mut raw_changes = Vec::new();
+    walk(&raw_before, &raw_after, &mut Vec::new(), &mut raw_changes);
+
+    raw_changes
+        .into_iter()
+        .map(|(path, kind)| {
+            let before = lookup(&shown_before, &path).cloned();
+            let after = lookup(&shown_after, &path).cloned();
+            let secret = before.as_ref() != lookup(&raw_before, &path)
+                || after.as_ref() != lookup(&raw_after, &path);
+            ConfigChange {
+                path: format_path(&path),
+                kind,
+                before: before.filter(|_| kind != ChangeKind::Added),
+                after: after.filter(|_| kind != ChangeKind::Removed),
+                secret,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CredentialEntry, REDACTED_PLACEHOLDER};
+
+    fn entry(id: &str, token_file: &str) -> CredentialEntry {
+        CredentialEntry {
+            id: id.to_string(),
+            token_file: token_file.to_string(),
+            disabled: false,
+            proxy_url: None,
+        }
+    }
+
+    #[test]
+    fn test_identical_configs_have_no_changes() {
+        let config = Config::default();
+        assert!(diff_configs(&config, &config.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_scalar_and_optional_changes() {
+        let current = Config::default();
+        let mut candidate = current.clone();
+        candidate.server.port = 9000;
+        candidate.proxy_url = Some("http://proxy:8080".to_string());
+
+        let changes = diff_configs(&current, &candidate);
+        let rendered: Vec<String> = changes.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            rendered,
+            vec![
+                "+ proxy_url: \"http://proxy:8080\"".to_string(),
+                format!("~ server.port: {} -> 9000", current.server.port),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_items_matched_by_identity() {
+        let mut current = Config::default();
+        current.credential_pool.kiro = vec![entry("a", "a.json"), entry("b", "b.json")];
+        let mut candidate = current.clone();
+        // 调整顺序不算变更
+        candidate.credential_pool.kiro = vec![entry("b", "b.json"), entry("a", "a.json")];
+        assert!(diff_configs(&current, &candidate).is_empty());
+
+        candidate.credential_pool.kiro[0].disabled = true;
+        candidate.credential_pool.kiro.remove(1);
+        candidate.credential_pool.kiro.push(entry("c", "c.json"));
+
+        let changes = diff_configs(&current, &candidate);
+        let summary: Vec<(&str, ChangeKind)> =
+            changes.iter().map(|c| (c.path.as_str(), c.kind)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("credential_pool.kiro[id=a]", ChangeKind::Removed),
+                ("credential_pool.kiro[id=b].disabled", ChangeKind::Modified),
+                ("credential_pool.kiro[id=c]", ChangeKind::Added),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_secrets_are_redacted() {
+        let mut current = Config::default();
+        current.server.api_key = "old-secret".to_string();
+        let mut candidate = current.clone();
+        candidate.server.api_key = "new-secret".to_string();
+        candidate.providers.openai.api_key = Some("sk-added".to_string());
+
+        let changes = diff_configs(&current, &candidate);
+        let rendered = changes
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(!rendered.contains("secret"), "{}", rendered);
+        assert!(!rendered.contains("sk-added"), "{}", rendered);
+
+        let api_key = changes.iter().find(|c| c.path == "server.api_key").unwrap();
+        assert!(api_key.secret);
+        assert_eq!(api_key.after, Some(Value::from(REDACTED_PLACEHOLDER)));
+        assert!(api_key.to_string().contains("敏感值已变更"));
+    }
+}
@@ -5,7 +5,8 @@
 //! - 支持原子性配置更新
 //! - 失败时自动回滚到之前的配置
 
-use super::types::{is_default_api_key, Config};
+use super::types::Config;
+use super::validation::first_runtime_violation;
 use super::yaml::ConfigManager;
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use parking_lot::RwLock;
@@ -372,68 +373,10 @@ impl HotReloadManager {
 
     /// 验证配置
     fn validate_config(&self, config: &Config) -> Result<(), HotReloadError> {
-        let is_localhost = is_localhost_host(&config.server.host);
-
-        // 验证端口范围
-        if config.server.port == 0 {
-            return Err(HotReloadError::ValidationError(
-                "端口号不能为 0".to_string(),
-            ));
-        }
-
-        if !is_localhost {
-            return Err(HotReloadError::ValidationError(
-                "当前版本仅支持本地监听，请使用 127.0.0.1/localhost/::1".to_string(),
-            ));
-        }
-
-        // 验证重试配置
-        if config.retry.max_retries > 100 {
-            return Err(HotReloadError::ValidationError(
-                "最大重试次数不能超过 100".to_string(),
-            ));
-        }
-
-        if config.retry.base_delay_ms == 0 {
-            return Err(HotReloadError::ValidationError(
-                "基础延迟不能为 0".to_string(),
-            ));
-        }
-
-        // 验证日志保留天数
-        if config.logging.retention_days == 0 {
-            return Err(HotReloadError::ValidationError(
-                "日志保留天数不能为 0".to_string(),
-            ));
-        }
-
-        if config.server.api_key.trim().is_empty() {
-            return Err(HotReloadError::ValidationError(
-                "API Key 不能为空".to_string(),
-            ));
-        }
-
-        if (!is_localhost || config.remote_management.allow_remote)
-            && is_default_api_key(&config.server.api_key)
-        {
-            return Err(HotReloadError::ValidationError(
-                "非本地访问场景下禁止使用默认 API Key，请设置强口令".to_string(),
-            ));
-        }
-
-        if config.server.tls.enable {
-            return Err(HotReloadError::ValidationError(
-                "当前版本暂不支持 TLS，请关闭 TLS 配置".to_string(),
-            ));
-        }
-
-        if config.remote_management.allow_remote {
-            return Err(HotReloadError::ValidationError(
-                "当前版本未启用 TLS，禁止开启远程管理".to_string(),
-            ));
+        match first_runtime_violation(config) {
+            Some(message) => Err(HotReloadError::ValidationError(message)),
+            None => Ok(()),
         }
-
-        Ok(())
     }
 
     /// 手动回滚到备份配置
@@ -473,15 +416,6 @@ impl HotReloadManager {
     }
 }
 
-fn is_localhost_host(host: &str) -> bool {
-    if host == "localhost" {
-        return true;
-    }
-    host.parse::<std::net::IpAddr>()
-        .map(|addr| addr.is_loopback())
-        .unwrap_or(false)
-}
-
 /// 热重载状态
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct HotReloadStatus {
@@ -622,6 +556,75 @@ server:
         }
     }
 
+    fn reload_yaml(yaml: &str) -> ReloadResult {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(yaml.as_bytes()).unwrap();
+        HotReloadManager::new(Config::default(), temp_file.path().to_path_buf()).reload()
+    }
+
+    #[test]
+    fn test_hot_reload_remote_requires_tls() {
+        let result = reload_yaml(
+            r#"
+server:
+  host: "0.0.0.0"
+  port: 9000
+  api_key: "strong-key"
+"#,
+        );
+        match result {
+            ReloadResult::RolledBack { error, .. } => assert!(error.contains("本地监听")),
+            _ => panic!("Expected RolledBack result"),
+        }
+
+        let result = reload_yaml(
+            r#"
+server:
+  host: "127.0.0.1"
+  port: 9000
+  api_key: "strong-key"
+remote_management:
+  allow_remote: true
+"#,
+        );
+        match result {
+            ReloadResult::RolledBack { error, .. } => assert!(error.contains("远程管理")),
+            _ => panic!("Expected RolledBack result"),
+        }
+    }
+
+    #[test]
+    fn test_hot_reload_tls_lifts_remote_restriction() {
+        let cert = NamedTempFile::new().unwrap();
+        let key = NamedTempFile::new().unwrap();
+        let yaml = format!(
+            r#"
+server:
+  host: "0.0.0.0"
+  port: 9000
+  api_key: "strong-key"
+  tls:
+    enable: true
+    cert_path: "{}"
+    key_path: "{}"
+remote_management:
+  allow_remote: true
+"#,
+            cert.path().display(),
+            key.path().display()
+        );
+        assert!(matches!(reload_yaml(&yaml), ReloadResult::Success { .. }));
+
+        let missing = yaml.replace(
+            &key.path().display().to_string(),
+            "/nonexistent/proxycast/key.pem",
+        );
+        match reload_yaml(&missing) {
+            ReloadResult::RolledBack { error, .. } => assert!(error.contains("私钥文件不存在")),
+            _ => panic!("Expected RolledBack result"),
+        }
+    }
+
     #[test]
     fn test_config_change_kind_eq() {
         assert_eq!(ConfigChangeKind::Modified, ConfigChangeKind::Modified);
@@ -0,0 +1,453 @@
+//! 配置值插值
+//!
+//! 密钥字段（见 [`SECRET_FIELDS`]）中可以引用环境变量和密钥文件，在加载与热重载时解析：
+//! - `${NAME}`：环境变量，未设置时报错
+//! - `${NAME:-default}`：环境变量，未设置或为空时使用默认值
+//! - `${file:/run/secrets/x}`：读取文件内容（去除末尾换行，支持 ~ 展开）
+//! - `$${`：转义，表示字面量 `${`
+//!
+//! 其他字段（如模型名、注入参数）中的 `${` 按字面量保留，不需要转义。
+//!
+//! 解析前的原始写法记录在 [`ConfigReferences`] 中，保存与导出配置时按字段路径写回原始引用，
+//! 避免解析出的密钥落盘。
+
+use std::fmt;
+
+use serde_yaml::Value;
+
+use super::path_utils::expand_tilde;
+use super::types::Config;
+use super::validation::{format_path, FieldPath, Segment};
+
+/// 支持插值的字段名（任意层级，如 `server.api_key`、`credential_pool.openai[0].api_key`）
+const SECRET_FIELDS: &[&str] = &["api_key", "proxy_url", "secret_key", "redis_url"];
+
+/// 单个字符串值的插值记录
+#[derive(Debug, Clone, PartialEq)]
+struct ReferenceEntry {
+    /// 字段路径
+    path: FieldPath,
+    /// 原始写法（如 `${OPENAI_API_KEY}`）
+    template: String,
+    /// 解析结果
+    resolved: String,
+}
+
+/// 配置中的插值引用
+///
+/// 随 [`Config`] 一起传递，序列化配置时用于还原原始引用。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigReferences {
+    entries: Vec<ReferenceEntry>,
+}
+
+impl ConfigReferences {
+    /// 是否没有任何引用
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 引用数量
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 含引用的字段路径及原始写法
+    pub fn templates(&self) -> impl Iterator<Item = (String, &str)> {
+        self.entries
+            .iter()
+            .map(|entry| (format_path(&entry.path), entry.template.as_str()))
+    }
+}
+
+/// 无法解析的引用
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct UnresolvedReference {
+    /// 字段路径
+    pub path: FieldPath,
+    /// 引用原文（如 `${OPENAI_API_KEY}`）
+    pub reference: String,
+    /// 失败原因
+    pub reason: String,
+}
+
+impl fmt::Display for UnresolvedReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: 无法解析的引用 `{}`（{}）",
+            format_path(&self.path),
+            self.reference,
+            self.reason
+        )
+    }
+}
+
+/// 插值后的配置文档
+pub(crate) struct Interpolated {
+    /// 已替换引用的 YAML 文档（无法解析的引用保持原样）
+    pub document: Value,
+    /// 成功解析的引用
+    pub references: ConfigReferences,
+    /// 无法解析的引用
+    pub unresolved: Vec<UnresolvedReference>,
+}
+
+impl Interpolated {
+    /// 反序列化为配置
+    ///
+    /// 没有发生替换时直接解析原文，保留错误信息中的行号。
+    pub fn into_config(self, source: &str) -> Result<Config, serde_yaml::Error> {
+        if self.references.is_empty() {
+            return serde_yaml::from_str(source);
+        }
+        let mut config: Config = serde_yaml::from_value(self.document)?;
+        config.references = self.references;
+        Ok(config)
+    }
+}
+
+/// 解析 YAML 并使用进程环境变量插值
+pub(crate) fn interpolate_source(source: &str) -> Result<Interpolated, serde_yaml::Error> {
+    let document: Value = serde_yaml::from_str(source)?;
+    Ok(interpolate_document(document, &|name| {
+        std::env::var(name).ok()
+    }))
+}
+
+/// 对文档中密钥字段的字符串值插值
+fn interpolate_document(mut document: Value, env: &dyn Fn(&str) -> Option<String>) -> Interpolated {
+    let mut references = ConfigReferences::default();
+    let mut unresolved = Vec::new();
+    walk(
+        &mut document,
+        &mut Vec::new(),
+        env,
+        &mut references,
+        &mut unresolved,
+    );
+    Interpolated {
+        document,
+        references,
+        unresolved,
+    }
+}
+
+fn walk(
+    value: &mut Value,
+    path: &mut FieldPath,
+    env: &dyn Fn(&str) -> Option<String>,
+    references: &mut ConfigReferences,
+    unresolved: &mut Vec<UnresolvedReference>,
+) {
+    match value {
+        Value::Mapping(mapping) => {
+            for (key, child) in mapping.iter_mut() {
+                let name = match key {
+                    Value::String(s) => s.clone(),
+                    other => serde_yaml::to_string(other)
+                        .map(|s| s.trim().to_string())
+                        .unwrap_or_default(),
+                };
+                path.push(Segment::Key(name));
+                walk(child, path, env, references, unresolved);
+                path.pop();
+            }
+        }
+        Value::Sequence(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                path.push(Segment::Index(index));
+                walk(item, path, env, references, unresolved);
+                path.pop();
+            }
+        }
+        Value::Tagged(tagged) => walk(&mut tagged.value, path, env, references, unresolved),
+        Value::String(s) if s.contains('$') && is_secret_field(path) => {
+            let (resolved, failures) = interpolate_str(s, env);
+            if failures.is_empty() && resolved != *s {
+                references.entries.push(ReferenceEntry {
+                    path: path.clone(),
+                    template: s.clone(),
+                    resolved: resolved.clone(),
+                });
+                *s = resolved;
+            }
+            unresolved.extend(failures.into_iter().map(|(reference, reason)| {
+                UnresolvedReference {
+                    path: path.clone(),
+                    reference,
+                    reason,
+                }
+            }));
+        }
+        _ => {}
+    }
+}
+
+/// 字段路径是否指向密钥字段
+fn is_secret_field(path: &[Segment]) -> bool {
+    matches!(path.last(), Some(Segment::Key(name)) if SECRET_FIELDS.contains(&name.as_str()))
+}
+
+/// 替换字符串中的引用，返回替换结果与无法解析的 (引用原文, 原因) 列表
+fn interpolate_str(
+    input: &str,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> (String, Vec<(String, String)>) {
+    let mut output = String::with_capacity(input.len());
+    let mut failures = Vec::new();
+    let mut rest = input;
+
+    while let Some(pos) = rest.find('$') {
+        output.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+        if let Some(after) = tail.strip_prefix("$${") {
+            output.push_str("${");
+            rest = after;
+        } else if tail.starts_with("${") {
+            let Some(end) = tail.find('}') else {
+                failures.push((tail.to_string(), "缺少右花括号 `}`".to_string()));
+                output.push_str(tail);
+                rest = "";
+                break;
+            };
+            let reference = &tail[..=end];
+            match resolve(&tail[2..end], env) {
+                Ok(value) => output.push_str(&value),
+                Err(reason) => {
+                    failures.push((reference.to_string(), reason));
+                    output.push_str(reference);
+                }
+            }
+            rest = &tail[end + 1..];
+        } else {
+            output.push('$');
+            rest = &tail[1..];
+        }
+    }
+    output.push_str(rest);
+    (output, failures)
+}
+
+/// 解析单个引用表达式（`${` 与 `}` 之间的内容）
+fn resolve(expression: &str, env: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    if let Some(path) = expression.strip_prefix("file:") {
+        let path = path.trim();
+        if path.is_empty() {
+            return Err("文件路径为空".to_string());
+        }
+        return std::fs::read_to_string(expand_tilde(path))
+            .map(|content| content.trim_end_matches(['\r', '\n']).to_string())
+            .map_err(|e| format!("读取文件 {} 失败: {}", path, e));
+    }
+
+    let (name, default) = match expression.split_once(":-") {
+        Some((name, default)) => (name, Some(default)),
+        None => (expression, None),
+    };
+    let valid_name = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_name {
+        return Err(format!("无效的环境变量名 `{}`", name));
+    }
+
+    match (env(name), default) {
+        (Some(value), Some(default)) if value.is_empty() => Ok(default.to_string()),
+        (Some(value), _) => Ok(value),
+        (None, Some(default)) => Ok(default.to_string()),
+        (None, None) => Err(format!("环境变量 {} 未设置", name)),
+    }
+}
+
+/// 将配置序列化为 YAML 文档，并把解析结果还原为原始引用
+///
+/// 只在原字段路径上的值仍等于解析结果时还原；值被修改或字段位置变化（如凭证被重新排序）后，
+/// 按当前值保存。
+pub(crate) fn to_document(config: &Config) -> Result<Value, serde_yaml::Error> {
+    let mut document = serde_yaml::to_value(config)?;
+    for entry in &config.references.entries {
+        if let Some(value) = value_at(&mut document, &entry.path)
+            .filter(|value| value.as_str() == Some(entry.resolved.as_str()))
+        {
+            *value = Value::String(entry.template.clone());
+        }
+    }
+    Ok(document)
+}
+
+fn value_at<'a>(document: &'a mut Value, path: &[Segment]) -> Option<&'a mut Value> {
+    path.iter()
+        .try_fold(document, |current, segment| match segment {
+            Segment::Key(name) => current.get_mut(name.as_str()),
+            Segment::Index(index) => current.get_mut(*index),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env_of(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_interpolate_env_and_defaults() {
+        let env = env_of(&[("KEY", "sk-123"), ("EMPTY", "")]);
+        let cases = [
+            ("${KEY}", "sk-123"),
+            ("Bearer ${KEY}", "Bearer sk-123"),
+            ("${MISSING:-fallback}", "fallback"),
+            ("${EMPTY:-fallback}", "fallback"),
+            ("${EMPTY}", ""),
+            ("${KEY:-fallback}", "sk-123"),
+            ("price: $5", "price: $5"),
+            ("$${KEY}", "${KEY}"),
+        ];
+        for (input, expected) in cases {
+            let (output, failures) = interpolate_str(input, &env);
+            assert!(failures.is_empty(), "{}: {:?}", input, failures);
+            assert_eq!(output, expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_interpolate_failures() {
+        let env = env_of(&[]);
+        let (output, failures) = interpolate_str("a ${MISSING} b ${1BAD} c ${OPEN", &env);
+        assert_eq!(output, "a ${MISSING} b ${1BAD} c ${OPEN");
+        let references: Vec<&str> = failures.iter().map(|(r, _)| r.as_str()).collect();
+        assert_eq!(references, vec!["${MISSING}", "${1BAD}", "${OPEN"]);
+        assert!(failures[0].1.contains("MISSING"));
+    }
+
+    #[test]
+    fn test_interpolate_secret_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret = dir.path().join("api_key");
+        std::fs::write(&secret, "file-secret\n").unwrap();
+
+        let env = env_of(&[]);
+        let (output, failures) = interpolate_str(&format!("${{file:{}}}", secret.display()), &env);
+        assert!(failures.is_empty());
+        assert_eq!(output, "file-secret");
+
+        let missing = format!("${{file:{}}}", dir.path().join("missing").display());
+        let (_, failures) = interpolate_str(&missing, &env);
+        assert_eq!(failures.len(), 1);
+    }
+
+    #[test]
+    fn test_document_interpolation_and_restore() {
+        let yaml = "\
+server:
+  api_key: ${PROXY_KEY}
+  port: 8999
+credential_pool:
+  openai:
+    - id: a
+      api_key: ${OPENAI_A}
+    - id: b
+      api_key: ${OPENAI_B:-sk-default}
+proxy_url: ${MISSING_PROXY}
+";
+        let document: Value = serde_yaml::from_str(yaml).unwrap();
+        let env = env_of(&[("PROXY_KEY", "server-secret"), ("OPENAI_A", "sk-a")]);
+        let interpolated = interpolate_document(document, &env);
+
+        assert_eq!(interpolated.references.len(), 3);
+        assert_eq!(interpolated.unresolved.len(), 1);
+        assert_eq!(
+            interpolated.unresolved[0].to_string(),
+            "proxy_url: 无法解析的引用 `${MISSING_PROXY}`（环境变量 MISSING_PROXY 未设置）"
+        );
+
+        let mut interpolated = interpolated;
+        interpolated.unresolved.clear();
+        interpolated.document["proxy_url"] = Value::Null;
+        let mut config = interpolated.into_config(yaml).unwrap();
+        assert_eq!(config.server.api_key, "server-secret");
+        assert_eq!(config.credential_pool.openai[1].api_key, "sk-default");
+
+        // 按原字段路径写回原始引用
+        let saved = serde_yaml::to_string(&to_document(&config).unwrap()).unwrap();
+        assert!(saved.contains("${PROXY_KEY}"));
+        assert!(saved.contains("${OPENAI_A}"));
+        assert!(saved.contains("${OPENAI_B:-sk-default}"));
+        assert!(!saved.contains("server-secret"));
+        assert!(!saved.contains("sk-a\n"));
+
+        // 修改过的值按新值保存
+        config.server.api_key = "rotated".to_string();
+        let saved = serde_yaml::to_string(&to_document(&config).unwrap()).unwrap();
+        assert!(saved.contains("api_key: rotated"));
+    }
+
+    #[test]
+    fn test_only_secret_fields_are_interpolated() {
+        let yaml = "\
+server:
+  api_key: ${PROXY_KEY}
+routing:
+  model_aliases:
+    fast: ${MODEL}
+injection:
+  rules:
+    - id: r1
+      pattern: \"*\"
+      parameters: {system: \"price: ${amount}\"}
+credential_pool:
+  openai:
+    - id: a
+      api_key: shared-secret
+shared_state:
+  redis_url: redis://:${REDIS_PASSWORD}@127.0.0.1:6379/0
+";
+        let env = env_of(&[
+            ("PROXY_KEY", "shared-secret"),
+            ("MODEL", "gpt-4o"),
+            ("REDIS_PASSWORD", "pw"),
+        ]);
+        let interpolated = interpolate_document(serde_yaml::from_str(yaml).unwrap(), &env);
+        assert!(interpolated.unresolved.is_empty());
+        assert_eq!(interpolated.references.len(), 2);
+
+        let config = interpolated.into_config(yaml).unwrap();
+        assert_eq!(config.server.api_key, "shared-secret");
+        assert_eq!(config.routing.model_aliases["fast"], "${MODEL}");
+        assert_eq!(
+            config.shared_state.redis_url.as_deref(),
+            Some("redis://:pw@127.0.0.1:6379/0")
+        );
+
+        // 与解析结果相同的其他字段按原值保存
+        let document = to_document(&config).unwrap();
+        assert_eq!(document["server"]["api_key"], "${PROXY_KEY}");
+        assert_eq!(
+            document["credential_pool"]["openai"][0]["api_key"],
+            "shared-secret"
+        );
+        assert_eq!(
+            document["injection"]["rules"][0]["parameters"]["system"],
+            "price: ${amount}"
+        );
+    }
+
+    #[test]
+    fn test_escape_is_preserved_on_save() {
+        let yaml = "server:\n  api_key: pa$${x}\n";
+        let interpolated = interpolate_document(serde_yaml::from_str(yaml).unwrap(), &env_of(&[]));
+        let config = interpolated.into_config(yaml).unwrap();
+        assert_eq!(config.server.api_key, "pa${x}");
+        let saved = serde_yaml::to_string(&to_document(&config).unwrap()).unwrap();
+        assert!(saved.contains("pa$${x}"));
+    }
+}
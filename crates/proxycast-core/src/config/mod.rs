@@ -1,27 +1,41 @@
 //! 配置管理模块
 //!
-//! 提供 YAML 配置文件支持、热重载和配置导入导出功能
+//! 提供 YAML 配置文件支持、热重载、配置验证和配置导入导出功能
 //! 同时保持与旧版 JSON 配置的向后兼容性
 
+mod diff;
 mod export;
 mod hot_reload;
 mod import;
+mod interpolation;
 mod path_utils;
+mod schema;
 mod types;
+mod validation;
 mod yaml;
 
+pub use diff::{diff_configs, ChangeKind, ConfigChange};
 pub use export::{ExportBundle, ExportOptions, ExportService, REDACTED_PLACEHOLDER};
 pub use hot_reload::{
     ConfigChangeEvent, ConfigChangeKind, FileWatcher, HotReloadManager, ReloadResult,
 };
 pub use import::{ImportOptions, ImportService, ValidationResult};
+pub use interpolation::ConfigReferences;
 pub use path_utils::{collapse_tilde, contains_tilde, expand_tilde};
+pub use schema::{config_json_schema, JSON_SCHEMA_DRAFT};
 pub use types::{
-    generate_secure_api_key, AmpConfig, AmpModelMapping, ApiKeyEntry, Config, CredentialEntry,
-    CredentialPoolConfig, CustomProviderConfig, EndpointProvidersConfig, GeminiApiKeyEntry,
-    IFlowCredentialEntry, InjectionRuleConfig, InjectionSettings, LoggingConfig, ProviderConfig,
-    ProvidersConfig, QuotaExceededConfig, RemoteManagementConfig, RetrySettings, RoutingConfig,
-    ServerConfig, TlsConfig, VertexApiKeyEntry, VertexModelAlias, DEFAULT_API_KEY,
+    generate_secure_api_key, AmpConfig, AmpModelMapping, ApiKeyEntry, BatchConfig, Config,
+    CredentialEntry, CredentialPoolConfig, CustomProviderConfig, EndpointProvidersConfig,
+    GeminiApiKeyEntry, IFlowCredentialEntry, ImageConfig, InjectionRuleConfig, InjectionSettings,
+    LoggingConfig, MirrorConfig, MirrorRule, NotificationSinkConfig, NotificationSinkKind,
+    NotificationsConfig, PriorityClassConfig, ProviderConfig, ProvidersConfig, QuotaExceededConfig,
+    QuotaSelectionStrategy, RemoteManagementConfig, RequestQueueConfig, RetrySettings,
+    RoutingConfig, ServerConfig, SharedStateBackendKind, SharedStateConfig, StructuredOutputConfig,
+    TlsConfig, UsageQuotaConfig, VertexApiKeyEntry, VertexModelAlias, DEFAULT_API_KEY,
+};
+pub use validation::{
+    check_config, validate_config_file, validate_config_source, ConfigIssue, IssueSeverity,
+    ValidationReport,
 };
 pub use yaml::{load_config, save_config, ConfigError, ConfigManager, YamlService};
 
@@ -0,0 +1,169 @@
+//! 配置 JSON Schema
+//!
+//! 由 [`Config`] 及其子结构派生的 [`JsonSchema`](schemars::JsonSchema) 生成，
+//! 字段的文档注释即 `description`，`default` 取自 serde 默认值。
+//! 可供编辑器补全与校验（如在 YAML 文件头部加入 `# yaml-language-server: $schema=<schema 路径>`），
+//! 配置验证也基于它识别未知字段。
+
+use schemars::generate::SchemaSettings;
+use serde_json::{json, Value};
+
+use super::types::Config;
+
+/// JSON Schema 方言
+pub const JSON_SCHEMA_DRAFT: &str = "http://json-schema.org/draft-07/schema#";
+
+/// 生成配置文件的 JSON Schema
+pub fn config_json_schema() -> Value {
+    let mut schema = SchemaSettings::draft07()
+        .with(|settings| settings.inline_subschemas = true)
+        .into_generator()
+        .into_root_schema_for::<Config>()
+        .to_value();
+    schema["$schema"] = json!(JSON_SCHEMA_DRAFT);
+    schema["title"] = json!("ProxyCast 配置文件");
+    deny_unknown_fields(&mut schema);
+    schema
+}
+
+/// 为所有声明了 `properties` 的对象补上 `additionalProperties: false`
+///
+/// 解析时未知字段会被静默忽略，schema 中标记出来以便编辑器与配置验证提示拼写错误。
+fn deny_unknown_fields(schema: &mut Value) {
+    match schema {
+        Value::Object(fields) => {
+            if fields.contains_key("properties") && !fields.contains_key("additionalProperties") {
+                fields.insert("additionalProperties".to_string(), Value::Bool(false));
+            }
+            fields.values_mut().for_each(deny_unknown_fields);
+        }
+        Value::Array(items) => items.iter_mut().for_each(deny_unknown_fields),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 收集 schema 未声明的字段路径
+    fn undeclared_keys(schema: &Value, value: &Value, path: &str, missing: &mut Vec<String>) {
+        match value {
+            Value::Object(fields) => {
+                if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                    for (key, child) in fields {
+                        let child_path = format!("{}.{}", path, key);
+                        match properties.get(key) {
+                            Some(child_schema) => {
+                                undeclared_keys(child_schema, child, &child_path, missing)
+                            }
+                            None => missing.push(child_path),
+                        }
+                    }
+                } else if let Some(values) = schema.get("additionalProperties") {
+                    for (key, child) in fields {
+                        undeclared_keys(values, child, &format!("{}.{}", path, key), missing);
+                    }
+                }
+            }
+            Value::Array(items) => {
+                if let Some(item_schema) = schema.get("items") {
+                    for (index, item) in items.iter().enumerate() {
+                        undeclared_keys(
+                            item_schema,
+                            item,
+                            &format!("{}[{}]", path, index),
+                            missing,
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 尽量填满所有可选字段的配置，用于检查 schema 是否覆盖全部字段
+    const FULL_CONFIG: &str = r#"
+server:
+  tls:
+    enable: true
+    cert_path: /etc/cert.pem
+    key_path: /etc/key.pem
+    client_ca_path: /etc/ca.pem
+providers:
+  kiro: {enabled: true, credentials_path: a, region: us-east-1, project_id: p}
+  openai: {enabled: true, api_key: k, base_url: http://localhost}
+routing:
+  rules:
+    - {pattern: "claude-*", provider: claude, priority: 1}
+  model_aliases: {fast: gpt-4o-mini}
+  exclusions: {kiro: ["o1-*"]}
+injection:
+  rules:
+    - {id: r1, pattern: "*", parameters: {temperature: 0.2}, mode: override}
+remote_management: {secret_key: s}
+proxy_url: http://proxy:8080
+ampcode:
+  upstream_url: https://amp.example.com
+  model_mappings: [{from: a, to: b}]
+endpoint_providers: {cursor: kiro, claude_code: claude, codex: codex, windsurf: qwen, kiro: kiro, other: openai}
+credential_pool:
+  kiro: [{id: k1, token_file: kiro.json, proxy_url: http://p}]
+  openai: [{id: o1, api_key: sk, base_url: http://b, proxy_url: http://p}]
+  gemini_api_keys: [{id: g1, api_key: g, base_url: http://b, proxy_url: http://p, excluded_models: [x]}]
+  vertex_api_keys: [{id: v1, api_key: v, base_url: http://b, models: [{name: n, alias: a}], proxy_url: http://p}]
+  iflow: [{id: i1, token_file: t.json, auth_type: oauth, cookies: c, proxy_url: http://p}]
+request_queue:
+  api_key_classes: {sk-1: batch}
+  client_classes: {cursor: interactive}
+mirror:
+  rules: [{name: m, model_pattern: "*", target_provider: qwen, target_model: qwen-max}]
+notifications:
+  dead_letter_path: /tmp/dl.jsonl
+  sinks:
+    - {name: ops, kind: command, url: http://x, secret: s, command: [notify], events: [quota]}
+shared_state:
+  backend: redis
+  sqlite_path: /tmp/state.db
+  redis_url: redis://:pw@127.0.0.1:6379/0
+  node_id: replica-1
+"#;
+
+    #[test]
+    fn test_schema_covers_all_config_fields() {
+        let schema = config_json_schema();
+        for config in [
+            Config::default(),
+            serde_yaml::from_str::<Config>(FULL_CONFIG).unwrap(),
+        ] {
+            let value = serde_json::to_value(&config).unwrap();
+            let mut missing = Vec::new();
+            undeclared_keys(&schema, &value, "", &mut missing);
+            assert!(missing.is_empty(), "schema 缺少字段: {:?}", missing);
+        }
+    }
+
+    #[test]
+    fn test_schema_metadata_and_defaults() {
+        let schema = config_json_schema();
+        assert_eq!(schema["$schema"], JSON_SCHEMA_DRAFT);
+        assert_eq!(schema["additionalProperties"], false);
+
+        let port = &schema["properties"]["server"]["properties"]["port"];
+        assert_eq!(port["default"], Config::default().server.port);
+        let strategy = &schema["properties"]["usage_quota"]["properties"]["strategy"];
+        assert_eq!(
+            strategy["default"],
+            serde_json::to_value(Config::default().usage_quota.strategy).unwrap()
+        );
+        assert!(strategy["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|variant| variant["const"] == "drain_evenly"));
+
+        let ratio = &schema["properties"]["usage_quota"]["properties"]["min_remaining_ratio"];
+        assert_eq!(ratio["minimum"], 0.0);
+        assert_eq!(ratio["maximum"], 1.0);
+    }
+}
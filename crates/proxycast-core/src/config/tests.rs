@@ -215,10 +215,19 @@ fn arb_config() -> impl Strategy<Value = Config> {
             credential_pool: crate::config::CredentialPoolConfig::default(),
             remote_management: crate::config::RemoteManagementConfig::default(),
             quota_exceeded: crate::config::QuotaExceededConfig::default(),
+            usage_quota: crate::config::UsageQuotaConfig::default(),
             proxy_url: None,
             ampcode: crate::config::AmpConfig::default(),
             endpoint_providers: crate::config::EndpointProvidersConfig::default(),
             minimize_to_tray: true,
+            images: crate::config::ImageConfig::default(),
+            structured_output: crate::config::StructuredOutputConfig::default(),
+            batch: crate::config::BatchConfig::default(),
+            request_queue: crate::config::RequestQueueConfig::default(),
+            mirror: crate::config::MirrorConfig::default(),
+            notifications: crate::config::NotificationsConfig::default(),
+            shared_state: crate::config::SharedStateConfig::default(),
+            references: crate::config::ConfigReferences::default(),
         })
 }
 
@@ -488,10 +497,19 @@ fn arb_valid_config() -> impl Strategy<Value = Config> {
             credential_pool: crate::config::CredentialPoolConfig::default(),
             remote_management: crate::config::RemoteManagementConfig::default(),
             quota_exceeded: crate::config::QuotaExceededConfig::default(),
+            usage_quota: crate::config::UsageQuotaConfig::default(),
             proxy_url: None,
             ampcode: crate::config::AmpConfig::default(),
             endpoint_providers: crate::config::EndpointProvidersConfig::default(),
             minimize_to_tray: true,
+            images: crate::config::ImageConfig::default(),
+            structured_output: crate::config::StructuredOutputConfig::default(),
+            batch: crate::config::BatchConfig::default(),
+            request_queue: crate::config::RequestQueueConfig::default(),
+            mirror: crate::config::MirrorConfig::default(),
+            notifications: crate::config::NotificationsConfig::default(),
+            shared_state: crate::config::SharedStateConfig::default(),
+            references: crate::config::ConfigReferences::default(),
         })
 }
 
@@ -533,10 +551,19 @@ fn arb_invalid_config() -> impl Strategy<Value = Config> {
                     credential_pool: crate::config::CredentialPoolConfig::default(),
                     remote_management: crate::config::RemoteManagementConfig::default(),
                     quota_exceeded: crate::config::QuotaExceededConfig::default(),
+                    usage_quota: crate::config::UsageQuotaConfig::default(),
                     proxy_url: None,
                     ampcode: crate::config::AmpConfig::default(),
                     endpoint_providers: crate::config::EndpointProvidersConfig::default(),
                     minimize_to_tray: true,
+                    images: crate::config::ImageConfig::default(),
+                    structured_output: crate::config::StructuredOutputConfig::default(),
+                    batch: crate::config::BatchConfig::default(),
+                    request_queue: crate::config::RequestQueueConfig::default(),
+                    mirror: crate::config::MirrorConfig::default(),
+                    notifications: crate::config::NotificationsConfig::default(),
+                    shared_state: crate::config::SharedStateConfig::default(),
+                    references: crate::config::ConfigReferences::default(),
                 };
                 // 根据类型使配置无效
                 match invalid_type {
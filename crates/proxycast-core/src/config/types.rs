@@ -3,7 +3,9 @@
 //! 定义 ProxyCast 的配置结构，支持 YAML 和 JSON 序列化/反序列化
 //! 保持与旧版 JSON 配置的向后兼容性
 
+use super::interpolation::ConfigReferences;
 use crate::injection::{InjectionMode, InjectionRule};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -12,7 +14,7 @@ use std::collections::HashMap;
 /// 凭证池配置
 ///
 /// 管理多个 Provider 的多个凭证，支持负载均衡
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Default)]
 pub struct CredentialPoolConfig {
     /// Kiro 凭证列表（OAuth）
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -46,7 +48,7 @@ pub struct CredentialPoolConfig {
 /// Gemini API Key 凭证条目
 ///
 /// 用于 Gemini API Key 多账号负载均衡
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct GeminiApiKeyEntry {
     /// 凭证 ID
     pub id: String,
@@ -67,7 +69,7 @@ pub struct GeminiApiKeyEntry {
 }
 
 /// Vertex AI 模型别名映射
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct VertexModelAlias {
     /// 上游模型名称
     pub name: String,
@@ -76,7 +78,7 @@ pub struct VertexModelAlias {
 }
 
 /// Vertex AI 凭证条目
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct VertexApiKeyEntry {
     /// 凭证 ID
     pub id: String,
@@ -99,7 +101,7 @@ pub struct VertexApiKeyEntry {
 /// iFlow 凭证条目
 ///
 /// 支持 OAuth 和 Cookie 两种认证方式
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct IFlowCredentialEntry {
     /// 凭证 ID
     pub id: String,
@@ -108,6 +110,7 @@ pub struct IFlowCredentialEntry {
     pub token_file: Option<String>,
     /// 认证类型：oauth 或 cookie
     #[serde(default = "default_auth_type")]
+    #[schemars(extend("enum" = ["oauth", "cookie"]))]
     pub auth_type: String,
     /// Cookie 字符串（Cookie 模式）
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -127,7 +130,7 @@ fn default_auth_type() -> String {
 /// OAuth 凭证条目
 ///
 /// 用于 Kiro、Gemini、Qwen 等 OAuth 认证的 Provider
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct CredentialEntry {
     /// 凭证 ID
     pub id: String,
@@ -144,7 +147,7 @@ pub struct CredentialEntry {
 /// API Key 凭证条目
 ///
 /// 用于 OpenAI、Claude 等 API Key 认证的 Provider
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct ApiKeyEntry {
     /// 凭证 ID
     pub id: String,
@@ -170,7 +173,7 @@ fn default_auth_dir() -> String {
 ///
 /// 允许为不同的客户端端点配置不同的 Provider
 /// 例如：Cursor 使用 Qwen，Claude Code 使用 Kiro，Codex 使用 Codex
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Default)]
 pub struct EndpointProvidersConfig {
     /// Cursor 客户端使用的 Provider
     /// 如果为空，则使用 default_provider
@@ -263,7 +266,7 @@ impl EndpointProvidersConfig {
 /// 支持两种格式：
 /// - 旧版 JSON 格式：`default_provider` 在顶层
 /// - 新版 YAML 格式：`default_provider` 在 `routing` 中
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Config {
     /// 服务器配置
     #[serde(default)]
@@ -298,6 +301,9 @@ pub struct Config {
     /// 配额超限配置
     #[serde(default)]
     pub quota_exceeded: QuotaExceededConfig,
+    /// 上游用量配额配置
+    #[serde(default)]
+    pub usage_quota: UsageQuotaConfig,
     /// 全局代理 URL
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub proxy_url: Option<String>,
@@ -311,6 +317,30 @@ pub struct Config {
     /// 关闭时最小化到托盘（而不是退出应用）
     #[serde(default = "default_minimize_to_tray")]
     pub minimize_to_tray: bool,
+    /// 多模态图片输入配置
+    #[serde(default)]
+    pub images: ImageConfig,
+    /// 结构化输出（JSON 模式）配置
+    #[serde(default)]
+    pub structured_output: StructuredOutputConfig,
+    /// 批处理任务配置
+    #[serde(default)]
+    pub batch: BatchConfig,
+    /// 请求优先级队列配置
+    #[serde(default)]
+    pub request_queue: RequestQueueConfig,
+    /// 影子流量镜像配置
+    #[serde(default)]
+    pub mirror: MirrorConfig,
+    /// 运维告警通知配置
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    /// 多实例共享状态配置
+    #[serde(default)]
+    pub shared_state: SharedStateConfig,
+    /// `${...}` 插值引用（不参与序列化，保存时用于还原原始写法）
+    #[serde(skip)]
+    pub references: ConfigReferences,
 }
 
 fn default_minimize_to_tray() -> bool {
@@ -318,7 +348,7 @@ fn default_minimize_to_tray() -> bool {
 }
 
 /// 服务器配置
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct ServerConfig {
     /// 监听地址
     #[serde(default = "default_host")]
@@ -336,24 +366,30 @@ pub struct ServerConfig {
 
 /// TLS 配置
 ///
-/// 用于启用 HTTPS 支持
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+/// 用于启用 HTTPS 支持。证书、私钥和 CA 文件变更后自动重新加载，不中断已有连接
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Default)]
 pub struct TlsConfig {
     /// 是否启用 TLS
     #[serde(default)]
     pub enable: bool,
-    /// 证书文件路径
+    /// 证书文件路径（PEM，可包含完整证书链）
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub cert_path: Option<String>,
-    /// 私钥文件路径
+    /// 私钥文件路径（PEM）
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub key_path: Option<String>,
+    /// 客户端证书 CA 文件路径（PEM），设置后启用 mTLS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_ca_path: Option<String>,
+    /// 是否允许不提供客户端证书的连接（仅在设置 `client_ca_path` 时生效）
+    #[serde(default)]
+    pub client_auth_optional: bool,
 }
 
 /// 远程管理配置
 ///
 /// 用于配置远程管理 API 的访问控制
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Default)]
 pub struct RemoteManagementConfig {
     /// 是否允许远程访问（非 localhost）
     #[serde(default)]
@@ -369,7 +405,7 @@ pub struct RemoteManagementConfig {
 /// 配额超限配置
 ///
 /// 用于配置配额超限时的自动切换策略
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct QuotaExceededConfig {
     /// 是否自动切换到下一个凭证
     #[serde(default = "default_switch_project")]
@@ -404,8 +440,473 @@ impl Default for QuotaExceededConfig {
     }
 }
 
+/// 基于上游剩余配额的凭证选择策略
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaSelectionStrategy {
+    /// 沿用凭证池默认的权重算法，仅做预冷却
+    Default,
+    /// 优先选择剩余配额最多的凭证
+    #[default]
+    MostRemaining,
+    /// 按距离重置的时间平均消耗，优先选择单位时间可用配额最多的凭证
+    DrainEvenly,
+}
+
+/// 上游用量配额配置
+///
+/// 定期查询支持用量接口的凭证（目前为 Kiro）的剩余配额，用于凭证选择和预冷却
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct UsageQuotaConfig {
+    /// 是否启用用量轮询
+    #[serde(default)]
+    pub enabled: bool,
+    /// 轮询间隔（秒）
+    #[serde(default = "default_usage_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    /// 选择策略
+    #[serde(default)]
+    pub strategy: QuotaSelectionStrategy,
+    /// 剩余比例低于该值时预先冷却凭证（0.0 - 1.0，0 表示不预冷却）
+    #[serde(default = "default_usage_min_remaining_ratio")]
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub min_remaining_ratio: f64,
+}
+
+fn default_usage_poll_interval_secs() -> u64 {
+    300
+}
+
+fn default_usage_min_remaining_ratio() -> f64 {
+    0.05
+}
+
+impl Default for UsageQuotaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poll_interval_secs: default_usage_poll_interval_secs(),
+            strategy: QuotaSelectionStrategy::default(),
+            min_remaining_ratio: default_usage_min_remaining_ratio(),
+        }
+    }
+}
+
+/// 多模态图片输入配置
+///
+/// 控制转发到 Kiro/Antigravity/Gemini 等需要内联图片数据的 Provider 时的图片处理
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ImageConfig {
+    /// 是否允许下载 http(s) 图片 URL 并转换为内联数据
+    #[serde(default)]
+    pub fetch_remote: bool,
+    /// 单张图片最大字节数（解码后）
+    #[serde(default = "default_max_image_bytes")]
+    pub max_image_bytes: usize,
+    /// 下载远程图片的超时时间（秒）
+    #[serde(default = "default_image_fetch_timeout_secs")]
+    pub fetch_timeout_secs: u64,
+}
+
+fn default_max_image_bytes() -> usize {
+    5 * 1024 * 1024
+}
+
+fn default_image_fetch_timeout_secs() -> u64 {
+    15
+}
+
+impl Default for ImageConfig {
+    fn default() -> Self {
+        Self {
+            fetch_remote: false,
+            max_image_bytes: default_max_image_bytes(),
+            fetch_timeout_secs: default_image_fetch_timeout_secs(),
+        }
+    }
+}
+
+/// 结构化输出（JSON 模式）配置
+///
+/// 用于不支持原生 `response_format` 的 Provider 的校验与重试
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct StructuredOutputConfig {
+    /// 输出未通过 schema 校验时的最大重试次数
+    #[serde(default = "default_structured_output_max_retries")]
+    pub max_retries: u32,
+}
+
+fn default_structured_output_max_retries() -> u32 {
+    2
+}
+
+impl Default for StructuredOutputConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: default_structured_output_max_retries(),
+        }
+    }
+}
+
+/// 批处理任务配置
+///
+/// 控制 `/v1/messages/batches` 和 `/v1/batches` 本地任务队列的执行速率
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct BatchConfig {
+    /// 是否启用批处理端点
+    #[serde(default = "default_batch_enabled")]
+    pub enabled: bool,
+    /// 同时执行的批处理请求数
+    #[serde(default = "default_batch_concurrency")]
+    pub concurrency: usize,
+    /// 每分钟最多发出的批处理请求数（0 表示不限制）
+    #[serde(default = "default_batch_requests_per_minute")]
+    pub requests_per_minute: u32,
+    /// 单个批处理任务允许的最大请求数
+    #[serde(default = "default_batch_max_requests")]
+    pub max_requests_per_batch: usize,
+    /// 单个请求因上游配额超限错误被重新排队的最大次数（所有凭证都在冷却期时的等待不计入）
+    #[serde(default = "default_batch_max_attempts")]
+    pub max_attempts: u32,
+}
+
+fn default_batch_enabled() -> bool {
+    true
+}
+
+fn default_batch_concurrency() -> usize {
+    4
+}
+
+fn default_batch_requests_per_minute() -> u32 {
+    60
+}
+
+fn default_batch_max_requests() -> usize {
+    10_000
+}
+
+fn default_batch_max_attempts() -> u32 {
+    5
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_batch_enabled(),
+            concurrency: default_batch_concurrency(),
+            requests_per_minute: default_batch_requests_per_minute(),
+            max_requests_per_batch: default_batch_max_requests(),
+            max_attempts: default_batch_max_attempts(),
+        }
+    }
+}
+
+/// 优先级类别配置
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct PriorityClassConfig {
+    /// 类别名称
+    pub name: String,
+    /// 公平队列权重（越大分得的上游并发越多）
+    #[serde(default = "default_priority_weight")]
+    pub weight: u32,
+}
+
+fn default_priority_weight() -> u32 {
+    1
+}
+
+/// 请求优先级队列配置
+///
+/// 凭证池饱和时，在调用上游之前按优先级类别进行加权公平排队
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct RequestQueueConfig {
+    /// 是否启用排队
+    #[serde(default)]
+    pub enabled: bool,
+    /// 同时调用上游的最大请求数
+    #[serde(default = "default_queue_max_concurrent")]
+    pub max_concurrent: usize,
+    /// 最大排队等待时间（毫秒），超时返回 503 + Retry-After
+    #[serde(default = "default_queue_max_wait_ms")]
+    pub max_wait_ms: u64,
+    /// 凭证全部处于配额冷却期时，是否在等待时间内排队等待恢复
+    #[serde(default = "default_queue_wait_for_quota")]
+    pub wait_for_quota_recovery: bool,
+    /// 优先级类别及权重
+    #[serde(default = "default_queue_classes")]
+    pub classes: Vec<PriorityClassConfig>,
+    /// 未匹配任何规则时使用的类别
+    #[serde(default = "default_queue_default_class")]
+    pub default_class: String,
+    /// 指定优先级类别的请求头
+    #[serde(default = "default_queue_priority_header")]
+    pub priority_header: String,
+    /// API Key 到类别的绑定
+    #[serde(default)]
+    pub api_key_classes: HashMap<String, String>,
+    /// 客户端类型（如 cursor、claude_code）到类别的映射
+    #[serde(default)]
+    pub client_classes: HashMap<String, String>,
+}
+
+fn default_queue_max_concurrent() -> usize {
+    16
+}
+
+fn default_queue_max_wait_ms() -> u64 {
+    30_000
+}
+
+fn default_queue_wait_for_quota() -> bool {
+    true
+}
+
+fn default_queue_classes() -> Vec<PriorityClassConfig> {
+    [("interactive", 8), ("normal", 4), ("background", 1)]
+        .into_iter()
+        .map(|(name, weight)| PriorityClassConfig {
+            name: name.to_string(),
+            weight,
+        })
+        .collect()
+}
+
+fn default_queue_default_class() -> String {
+    "normal".to_string()
+}
+
+fn default_queue_priority_header() -> String {
+    "x-proxycast-priority".to_string()
+}
+
+impl Default for RequestQueueConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_concurrent: default_queue_max_concurrent(),
+            max_wait_ms: default_queue_max_wait_ms(),
+            wait_for_quota_recovery: default_queue_wait_for_quota(),
+            classes: default_queue_classes(),
+            default_class: default_queue_default_class(),
+            priority_header: default_queue_priority_header(),
+            api_key_classes: HashMap::new(),
+            client_classes: HashMap::new(),
+        }
+    }
+}
+
+/// 影子流量镜像规则
+///
+/// 按采样率把匹配模型的请求异步复制一份发送到影子目标，客户端只会收到主响应
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct MirrorRule {
+    /// 规则名称（用于关联 Flow 和聚合报告）
+    pub name: String,
+    /// 模型模式（支持通配符，如 `claude-*`）
+    pub model_pattern: String,
+    /// 采样率（0.0 - 1.0）
+    #[serde(default = "default_mirror_sample_rate")]
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub sample_rate: f64,
+    /// 影子目标 Provider（如 `antigravity`、`qwen`）
+    pub target_provider: String,
+    /// 影子目标模型（为空时沿用主请求的模型）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_model: Option<String>,
+    /// 是否启用
+    #[serde(default = "default_mirror_rule_enabled")]
+    pub enabled: bool,
+}
+
+fn default_mirror_sample_rate() -> f64 {
+    1.0
+}
+
+fn default_mirror_rule_enabled() -> bool {
+    true
+}
+
+/// 影子流量镜像配置
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Default)]
+pub struct MirrorConfig {
+    /// 是否启用镜像
+    #[serde(default)]
+    pub enabled: bool,
+    /// 镜像规则（按顺序匹配，命中第一条启用的规则）
+    #[serde(default)]
+    pub rules: Vec<MirrorRule>,
+}
+
+/// 通知 Sink 类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationSinkKind {
+    /// 通用 Webhook（JSON 请求体，配置密钥时附带 HMAC-SHA256 签名）
+    #[default]
+    Webhook,
+    /// Slack Incoming Webhook
+    Slack,
+    /// 飞书自定义机器人
+    Feishu,
+    /// 钉钉自定义机器人
+    Dingtalk,
+    /// 本地命令（通知 JSON 写入标准输入）
+    Command,
+}
+
+/// 通知 Sink 配置
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct NotificationSinkConfig {
+    /// Sink 名称（用于日志、死信记录和测试发送）
+    pub name: String,
+    /// Sink 类型
+    #[serde(default)]
+    pub kind: NotificationSinkKind,
+    /// 目标 URL（command 类型不使用）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// 签名密钥（webhook 为 HMAC 密钥，飞书 / 钉钉为机器人加签密钥）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// 本地命令及参数（仅 command 类型）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command: Vec<String>,
+    /// 订阅的事件类型或主题（为空时接收全部告警）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<String>,
+    /// 去重节流窗口（秒），窗口内相同告警只发送一次，0 表示不节流
+    #[serde(default = "default_notification_throttle_secs")]
+    pub throttle_secs: u64,
+    /// 失败后的最大重试次数
+    #[serde(default = "default_notification_max_retries")]
+    pub max_retries: u32,
+    /// 首次重试的退避时间（毫秒），之后按指数增长
+    #[serde(default = "default_notification_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+    /// 单次投递超时（秒）
+    #[serde(default = "default_notification_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 是否启用
+    #[serde(default = "default_notification_sink_enabled")]
+    pub enabled: bool,
+}
+
+fn default_notification_throttle_secs() -> u64 {
+    300
+}
+
+fn default_notification_max_retries() -> u32 {
+    3
+}
+
+fn default_notification_retry_backoff_ms() -> u64 {
+    1000
+}
+
+fn default_notification_timeout_secs() -> u64 {
+    10
+}
+
+fn default_notification_sink_enabled() -> bool {
+    true
+}
+
+/// 运维告警通知配置
+///
+/// 将事件总线上的告警（错误 Flow、阈值警告、凭证耗尽、Token 刷新失败等）
+/// 推送到外部 Webhook / 机器人 / 本地命令
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Default)]
+pub struct NotificationsConfig {
+    /// 是否启用通知
+    #[serde(default)]
+    pub enabled: bool,
+    /// 通知 Sink 列表
+    #[serde(default)]
+    pub sinks: Vec<NotificationSinkConfig>,
+    /// 死信日志路径（JSONL，支持 ~ 展开，默认 `~/.proxycast/notification_dead_letters.jsonl`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dead_letter_path: Option<String>,
+}
+
+/// 共享状态后端类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SharedStateBackendKind {
+    /// 进程内存储（单实例，不与其他副本共享）
+    #[default]
+    Memory,
+    /// SQLite 文件（依赖文件锁，适用于同一主机上的多个副本）
+    Sqlite,
+    /// Redis 协议服务器（适用于跨主机部署）
+    Redis,
+}
+
+fn default_shared_state_key_prefix() -> String {
+    "proxycast".to_string()
+}
+
+fn default_shared_state_lock_ttl_secs() -> u64 {
+    60
+}
+
+fn default_shared_state_lock_wait_secs() -> u64 {
+    30
+}
+
+fn default_shared_state_sync_interval_secs() -> u64 {
+    5
+}
+
+/// 多实例共享状态配置
+///
+/// 多个 ProxyCast 副本指向同一后端时，共享 Token 刷新锁、配额冷却状态、
+/// 刷新后的 Token 缓存与请求计数器
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct SharedStateConfig {
+    /// 后端类型
+    #[serde(default)]
+    pub backend: SharedStateBackendKind,
+    /// SQLite 状态文件路径（支持 ~ 展开，默认 `~/.proxycast/shared_state.db`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sqlite_path: Option<String>,
+    /// Redis 地址（`redis://[:password@]host:port[/db]`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redis_url: Option<String>,
+    /// 键前缀（同一后端上区分不同部署）
+    #[serde(default = "default_shared_state_key_prefix")]
+    pub key_prefix: String,
+    /// 实例标识（默认自动生成，用于日志与锁持有者）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    /// 分布式锁租期（秒），持有者崩溃后锁在租期结束时自动释放
+    #[serde(default = "default_shared_state_lock_ttl_secs")]
+    pub lock_ttl_secs: u64,
+    /// 获取分布式锁的最长等待时间（秒）
+    #[serde(default = "default_shared_state_lock_wait_secs")]
+    pub lock_wait_secs: u64,
+    /// 从后端同步配额冷却状态的间隔（秒）
+    #[serde(default = "default_shared_state_sync_interval_secs")]
+    pub sync_interval_secs: u64,
+}
+
+impl Default for SharedStateConfig {
+    fn default() -> Self {
+        Self {
+            backend: SharedStateBackendKind::default(),
+            sqlite_path: None,
+            redis_url: None,
+            key_prefix: default_shared_state_key_prefix(),
+            node_id: None,
+            lock_ttl_secs: default_shared_state_lock_ttl_secs(),
+            lock_wait_secs: default_shared_state_lock_wait_secs(),
+            sync_interval_secs: default_shared_state_sync_interval_secs(),
+        }
+    }
+}
+
 /// Amp CLI 模型映射
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct AmpModelMapping {
     /// 源模型名称
     pub from: String,
@@ -416,7 +917,7 @@ pub struct AmpModelMapping {
 /// Amp CLI 配置
 ///
 /// 用于 Amp CLI 集成
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Default)]
 pub struct AmpConfig {
     /// 上游 URL
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -473,7 +974,7 @@ impl Default for ServerConfig {
 }
 
 /// Provider 配置集合
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct ProvidersConfig {
     /// Kiro Provider 配置
     #[serde(default)]
@@ -528,7 +1029,7 @@ impl Default for ProvidersConfig {
 }
 
 /// OAuth Provider 配置
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Default)]
 pub struct ProviderConfig {
     /// 是否启用
     #[serde(default)]
@@ -545,7 +1046,7 @@ pub struct ProviderConfig {
 }
 
 /// 自定义 Provider 配置（API Key 方式）
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Default)]
 pub struct CustomProviderConfig {
     /// 是否启用
     #[serde(default)]
@@ -559,7 +1060,7 @@ pub struct CustomProviderConfig {
 }
 
 /// 路由配置
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct RoutingConfig {
     /// 默认 Provider
     #[serde(default = "default_provider")]
@@ -591,7 +1092,7 @@ impl Default for RoutingConfig {
 }
 
 /// 路由规则配置
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct RoutingRuleConfig {
     /// 模型模式（支持通配符）
     pub pattern: String,
@@ -607,7 +1108,7 @@ fn default_priority() -> i32 {
 }
 
 /// 重试配置
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct RetrySettings {
     /// 最大重试次数
     #[serde(default = "default_max_retries")]
@@ -651,7 +1152,7 @@ impl Default for RetrySettings {
 }
 
 /// 日志配置
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct LoggingConfig {
     /// 是否启用日志
     #[serde(default = "default_logging_enabled")]
@@ -691,7 +1192,7 @@ impl Default for LoggingConfig {
 }
 
 /// 参数注入配置
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct InjectionSettings {
     /// 是否启用参数注入
     #[serde(default = "default_injection_enabled")]
@@ -715,7 +1216,7 @@ impl Default for InjectionSettings {
 }
 
 /// 注入规则配置（用于 YAML/JSON 序列化）
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct InjectionRuleConfig {
     /// 规则 ID
     pub id: String,
@@ -775,10 +1276,19 @@ impl Default for Config {
             credential_pool: CredentialPoolConfig::default(),
             remote_management: RemoteManagementConfig::default(),
             quota_exceeded: QuotaExceededConfig::default(),
+            usage_quota: UsageQuotaConfig::default(),
             proxy_url: None,
             ampcode: AmpConfig::default(),
             endpoint_providers: EndpointProvidersConfig::default(),
             minimize_to_tray: default_minimize_to_tray(),
+            images: ImageConfig::default(),
+            structured_output: StructuredOutputConfig::default(),
+            batch: BatchConfig::default(),
+            request_queue: RequestQueueConfig::default(),
+            mirror: MirrorConfig::default(),
+            notifications: NotificationsConfig::default(),
+            shared_state: SharedStateConfig::default(),
+            references: ConfigReferences::default(),
         }
     }
 }
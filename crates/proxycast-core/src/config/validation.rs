@@ -0,0 +1,976 @@
+//! 配置验证
+//!
+//! 对配置文件做完整检查，返回带行号的问题列表：
+//! - YAML 语法与字段类型错误
+//! - 无法解析的 `${...}` 引用（环境变量未设置、密钥文件不可读）
+//! - 未知字段、枚举取值与数值范围（基于 [`config_json_schema`]）
+//! - 运行时约束（端口、TLS、API Key 等，与热重载使用同一套规则）
+//! - 无法解析的 Provider 引用（如 `routing.rules[].provider`）
+//! - 凭证池中不存在的 Token 文件
+
+use std::fmt;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
+
+use super::interpolation::interpolate_source;
+use super::path_utils::expand_tilde;
+use super::schema::config_json_schema;
+use super::types::{is_default_api_key, Config};
+use crate::services::notification_service::validate_notifications_config;
+use crate::services::shared_state::validate_shared_state_config;
+use crate::ProviderType;
+
+/// 问题严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSeverity {
+    /// 错误：配置无法使用或会被拒绝加载
+    Error,
+    /// 警告：配置可以加载，但部分功能可能不可用
+    Warning,
+}
+
+impl fmt::Display for IssueSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IssueSeverity::Error => write!(f, "错误"),
+            IssueSeverity::Warning => write!(f, "警告"),
+        }
+    }
+}
+
+/// 配置问题
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigIssue {
+    /// 严重程度
+    pub severity: IssueSeverity,
+    /// 字段路径（如 `routing.rules[0].provider`，语法错误时为空）
+    pub path: String,
+    /// 行号（从 1 开始，无法定位时为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// 列号（从 1 开始）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    /// 问题描述
+    pub message: String,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}: {}", self.severity, self.message)
+        } else {
+            write!(f, "{}: {}: {}", self.severity, self.path, self.message)
+        }
+    }
+}
+
+/// 配置验证报告
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    /// 解析成功的配置（语法或类型错误时为 None）
+    #[serde(skip)]
+    pub config: Option<Config>,
+    /// 发现的问题（按行号排序）
+    pub issues: Vec<ConfigIssue>,
+}
+
+impl ValidationReport {
+    /// 配置是否可用（已解析且没有错误）
+    pub fn is_valid(&self) -> bool {
+        self.config.is_some() && self.error_count() == 0
+    }
+
+    /// 错误数量
+    pub fn error_count(&self) -> usize {
+        self.count(IssueSeverity::Error)
+    }
+
+    /// 警告数量
+    pub fn warning_count(&self) -> usize {
+        self.count(IssueSeverity::Warning)
+    }
+
+    fn count(&self, severity: IssueSeverity) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == severity)
+            .count()
+    }
+}
+
+/// 字段路径片段
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+pub(crate) type FieldPath = Vec<Segment>;
+
+fn key(name: &str) -> Segment {
+    Segment::Key(name.to_string())
+}
+
+pub(crate) fn format_path(path: &[Segment]) -> String {
+    let mut out = String::new();
+    for segment in path {
+        match segment {
+            Segment::Key(name) => {
+                if !out.is_empty() {
+                    out.push('.');
+                }
+                out.push_str(name);
+            }
+            Segment::Index(index) => out.push_str(&format!("[{}]", index)),
+        }
+    }
+    out
+}
+
+/// 尚未定位到源文件行号的问题
+struct Finding {
+    severity: IssueSeverity,
+    path: FieldPath,
+    message: String,
+}
+
+impl Finding {
+    fn error(path: FieldPath, message: impl Into<String>) -> Self {
+        Self {
+            severity: IssueSeverity::Error,
+            path,
+            message: message.into(),
+        }
+    }
+
+    fn warning(path: FieldPath, message: impl Into<String>) -> Self {
+        Self {
+            severity: IssueSeverity::Warning,
+            path,
+            message: message.into(),
+        }
+    }
+
+    fn into_issue(self, locator: Option<&KeyLocator>) -> ConfigIssue {
+        let position = locator.and_then(|l| l.locate(&self.path));
+        ConfigIssue {
+            severity: self.severity,
+            path: format_path(&self.path),
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, column)| column),
+            message: self.message,
+        }
+    }
+}
+
+/// 验证配置文件
+pub fn validate_config_file(path: &Path) -> ValidationReport {
+    match std::fs::read_to_string(path) {
+        Ok(content) => validate_config_source(&content),
+        Err(e) => ValidationReport {
+            config: None,
+            issues: vec![ConfigIssue {
+                severity: IssueSeverity::Error,
+                path: String::new(),
+                line: None,
+                column: None,
+                message: format!("读取配置文件失败: {}", e),
+            }],
+        },
+    }
+}
+
+/// 验证配置文件内容（YAML 或旧版 JSON）
+///
+/// `${...}` 引用按当前进程环境解析，无法解析的引用报告为错误。
+pub fn validate_config_source(content: &str) -> ValidationReport {
+    let interpolated = match interpolate_source(content) {
+        Ok(interpolated) => interpolated,
+        Err(e) => {
+            return ValidationReport {
+                config: None,
+                issues: vec![syntax_issue(&e)],
+            }
+        }
+    };
+
+    let locator = KeyLocator::new(content);
+    let mut findings: Vec<Finding> = interpolated
+        .unresolved
+        .iter()
+        .map(|unresolved| {
+            Finding::error(
+                unresolved.path.clone(),
+                format!(
+                    "无法解析的引用 `{}`（{}）",
+                    unresolved.reference, unresolved.reason
+                ),
+            )
+        })
+        .collect();
+    let schema = config_json_schema();
+    check_against_schema(
+        &interpolated.document,
+        &schema,
+        &mut Vec::new(),
+        &mut findings,
+    );
+
+    let mut issues = Vec::new();
+    let config = match interpolated.into_config(content) {
+        Ok(config) => {
+            findings.extend(config_findings(&config));
+            Some(config)
+        }
+        Err(e) => {
+            issues.push(syntax_issue(&e));
+            None
+        }
+    };
+
+    issues.extend(findings.into_iter().map(|f| f.into_issue(Some(&locator))));
+    issues.sort_by_key(|issue| issue.line.unwrap_or(usize::MAX));
+    ValidationReport { config, issues }
+}
+
+/// 检查已解析配置的语义问题（不含行号）
+pub fn check_config(config: &Config) -> Vec<ConfigIssue> {
+    config_findings(config)
+        .into_iter()
+        .map(|f| f.into_issue(None))
+        .collect()
+}
+
+/// 运行时约束检查，返回第一个违反的约束
+///
+/// 热重载在应用新配置前调用，违反约束的配置会被拒绝。
+pub(crate) fn first_runtime_violation(config: &Config) -> Option<String> {
+    runtime_findings(config)
+        .into_iter()
+        .next()
+        .map(|finding| finding.message)
+}
+
+fn syntax_issue(error: &serde_yaml::Error) -> ConfigIssue {
+    let location = error.location();
+    ConfigIssue {
+        severity: IssueSeverity::Error,
+        path: String::new(),
+        line: location.as_ref().map(|l| l.line()),
+        column: location.as_ref().map(|l| l.column()),
+        message: error.to_string(),
+    }
+}
+
+fn config_findings(config: &Config) -> Vec<Finding> {
+    let mut findings = runtime_findings(config);
+    findings.extend(reference_findings(config));
+    findings.extend(credential_file_findings(config));
+    findings
+}
+
+// ============================================================================
+// Schema 检查
+// ============================================================================
+
+/// 按 schema 检查未知字段、枚举取值与数值范围
+fn check_against_schema(
+    value: &YamlValue,
+    schema: &JsonValue,
+    path: &mut FieldPath,
+    findings: &mut Vec<Finding>,
+) {
+    match value {
+        YamlValue::Mapping(mapping) => {
+            let properties = schema.get("properties").and_then(JsonValue::as_object);
+            let additional = schema.get("additionalProperties");
+            for (name, child) in mapping {
+                let name = match name {
+                    YamlValue::String(s) => s.clone(),
+                    other => serde_yaml::to_string(other)
+                        .map(|s| s.trim().to_string())
+                        .unwrap_or_default(),
+                };
+                path.push(Segment::Key(name.clone()));
+                match properties.and_then(|p| p.get(&name)) {
+                    Some(child_schema) => check_against_schema(child, child_schema, path, findings),
+                    None => match additional {
+                        Some(JsonValue::Bool(false)) => {
+                            findings
+                                .push(Finding::error(path.clone(), format!("未知字段 `{}`", name)));
+                        }
+                        Some(child_schema) if child_schema.is_object() => {
+                            check_against_schema(child, child_schema, path, findings)
+                        }
+                        _ => {}
+                    },
+                }
+                path.pop();
+            }
+        }
+        YamlValue::Sequence(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    path.push(Segment::Index(index));
+                    check_against_schema(item, item_schema, path, findings);
+                    path.pop();
+                }
+            }
+        }
+        YamlValue::String(s) => {
+            if let Some(allowed) = allowed_values(schema) {
+                if !allowed.contains(&s.as_str()) {
+                    findings.push(Finding::error(
+                        path.clone(),
+                        format!("无效取值 `{}`，可选值: {}", s, allowed.join(", ")),
+                    ));
+                }
+            }
+        }
+        YamlValue::Number(n) => {
+            if let Some(n) = n.as_f64() {
+                let minimum = schema.get("minimum").and_then(JsonValue::as_f64);
+                let maximum = schema.get("maximum").and_then(JsonValue::as_f64);
+                let out_of_range =
+                    minimum.is_some_and(|min| n < min) || maximum.is_some_and(|max| n > max);
+                if out_of_range {
+                    let range = match (minimum, maximum) {
+                        (Some(min), Some(max)) => format!("{} - {}", min, max),
+                        (Some(min), None) => format!(">= {}", min),
+                        (None, Some(max)) => format!("<= {}", max),
+                        (None, None) => unreachable!(),
+                    };
+                    findings.push(Finding::error(
+                        path.clone(),
+                        format!("取值 {} 超出范围（{}）", n, range),
+                    ));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 取出 schema 允许的字符串取值
+///
+/// 兼容 `enum` 与带文档注释的枚举生成的 `oneOf: [{ "const": ... }]` 两种写法。
+fn allowed_values(schema: &JsonValue) -> Option<Vec<&str>> {
+    if let Some(values) = schema.get("enum").and_then(JsonValue::as_array) {
+        return Some(values.iter().filter_map(JsonValue::as_str).collect());
+    }
+    let variants = schema.get("oneOf").and_then(JsonValue::as_array)?;
+    variants
+        .iter()
+        .map(|variant| variant.get("const").and_then(JsonValue::as_str))
+        .collect()
+}
+
+// ============================================================================
+// 语义检查
+// ============================================================================
+
+/// 判断主机是否为本地回环地址
+fn is_localhost_host(host: &str) -> bool {
+    if host == "localhost" {
+        return true;
+    }
+    host.parse::<std::net::IpAddr>()
+        .map(|addr| addr.is_loopback())
+        .unwrap_or(false)
+}
+
+/// 运行时约束（顺序与热重载的拒绝优先级一致）
+fn runtime_findings(config: &Config) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let is_localhost = is_localhost_host(&config.server.host);
+
+    if config.server.port == 0 {
+        findings.push(Finding::error(
+            vec![key("server"), key("port")],
+            "端口号不能为 0",
+        ));
+    }
+
+    if !is_localhost && !config.server.tls.enable {
+        findings.push(Finding::error(
+            vec![key("server"), key("host")],
+            "未启用 TLS 时仅支持本地监听，请使用 127.0.0.1/localhost/::1",
+        ));
+    }
+
+    if config.retry.max_retries > 100 {
+        findings.push(Finding::error(
+            vec![key("retry"), key("max_retries")],
+            "最大重试次数不能超过 100",
+        ));
+    }
+
+    if config.retry.base_delay_ms == 0 {
+        findings.push(Finding::error(
+            vec![key("retry"), key("base_delay_ms")],
+            "基础延迟不能为 0",
+        ));
+    }
+
+    if config.logging.retention_days == 0 {
+        findings.push(Finding::error(
+            vec![key("logging"), key("retention_days")],
+            "日志保留天数不能为 0",
+        ));
+    }
+
+    if config.server.api_key.trim().is_empty() {
+        findings.push(Finding::error(
+            vec![key("server"), key("api_key")],
+            "API Key 不能为空",
+        ));
+    } else if (!is_localhost || config.remote_management.allow_remote)
+        && is_default_api_key(&config.server.api_key)
+    {
+        findings.push(Finding::error(
+            vec![key("server"), key("api_key")],
+            "非本地访问场景下禁止使用默认 API Key，请设置强口令",
+        ));
+    }
+
+    if config.server.tls.enable {
+        let tls = &config.server.tls;
+        let files = [
+            ("证书", "cert_path", tls.cert_path.as_deref(), true),
+            ("私钥", "key_path", tls.key_path.as_deref(), true),
+            (
+                "客户端 CA",
+                "client_ca_path",
+                tls.client_ca_path.as_deref(),
+                false,
+            ),
+        ];
+        for (name, field, path, required) in files {
+            let field_path = vec![key("server"), key("tls"), key(field)];
+            match path.filter(|p| !p.trim().is_empty()) {
+                Some(path) if !Path::new(path).exists() => {
+                    findings.push(Finding::error(
+                        field_path,
+                        format!("TLS {}文件不存在: {}", name, path),
+                    ));
+                }
+                None if required => {
+                    findings.push(Finding::error(
+                        field_path,
+                        format!("启用 TLS 时必须配置{}文件路径", name),
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if config.remote_management.allow_remote && !config.server.tls.enable {
+        findings.push(Finding::error(
+            vec![key("remote_management"), key("allow_remote")],
+            "未启用 TLS，禁止开启远程管理",
+        ));
+    }
+
+    findings
+}
+
+/// Provider 引用等交叉引用检查
+fn reference_findings(config: &Config) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let unresolvable = |provider: &str| provider.parse::<ProviderType>().is_err();
+
+    for (index, rule) in config.routing.rules.iter().enumerate() {
+        if unresolvable(&rule.provider) {
+            findings.push(Finding::error(
+                vec![
+                    key("routing"),
+                    key("rules"),
+                    Segment::Index(index),
+                    key("provider"),
+                ],
+                format!("无法解析的 Provider `{}`", rule.provider),
+            ));
+        }
+    }
+
+    for (index, rule) in config.mirror.rules.iter().enumerate() {
+        if unresolvable(&rule.target_provider) {
+            findings.push(Finding::error(
+                vec![
+                    key("mirror"),
+                    key("rules"),
+                    Segment::Index(index),
+                    key("target_provider"),
+                ],
+                format!("无法解析的 Provider `{}`", rule.target_provider),
+            ));
+        }
+    }
+
+    // 默认 Provider 与端点 Provider 也可能指向自定义 Provider，仅给出警告
+    if unresolvable(&config.routing.default_provider) {
+        findings.push(Finding::warning(
+            vec![key("routing"), key("default_provider")],
+            format!(
+                "`{}` 不是内置 Provider，请确认存在同名的自定义 Provider",
+                config.routing.default_provider
+            ),
+        ));
+    }
+    let endpoints = &config.endpoint_providers;
+    let endpoint_fields = [
+        ("cursor", &endpoints.cursor),
+        ("claude_code", &endpoints.claude_code),
+        ("codex", &endpoints.codex),
+        ("windsurf", &endpoints.windsurf),
+        ("kiro", &endpoints.kiro),
+        ("other", &endpoints.other),
+    ];
+    for (field, provider) in endpoint_fields {
+        if let Some(provider) = provider.as_deref().filter(|p| unresolvable(p)) {
+            findings.push(Finding::warning(
+                vec![key("endpoint_providers"), key(field)],
+                format!(
+                    "`{}` 不是内置 Provider，请确认存在同名的自定义 Provider",
+                    provider
+                ),
+            ));
+        }
+    }
+
+    if let Err(e) = validate_notifications_config(&config.notifications) {
+        findings.push(Finding::error(vec![key("notifications"), key("sinks")], e));
+    }
+
+    if let Err(e) = validate_shared_state_config(&config.shared_state) {
+        findings.push(Finding::error(vec![key("shared_state")], e));
+    }
+
+    findings
+}
+
+/// 凭证文件存在性检查
+fn credential_file_findings(config: &Config) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let auth_dir = expand_tilde(&config.auth_dir);
+    let pool = &config.credential_pool;
+
+    let token_file_finding = |path: FieldPath, token_file: &str, disabled: bool| {
+        let resolved = auth_dir.join(expand_tilde(token_file));
+        if resolved.exists() {
+            return None;
+        }
+        let message = format!("Token 文件不存在: {}", resolved.display());
+        Some(if disabled {
+            Finding::warning(path, format!("{}（凭证已禁用）", message))
+        } else {
+            Finding::error(path, message)
+        })
+    };
+
+    let oauth_pools = [
+        ("kiro", &pool.kiro),
+        ("gemini", &pool.gemini),
+        ("qwen", &pool.qwen),
+        ("codex", &pool.codex),
+    ];
+    for (provider, entries) in oauth_pools {
+        for (index, entry) in entries.iter().enumerate() {
+            findings.extend(token_file_finding(
+                vec![
+                    key("credential_pool"),
+                    key(provider),
+                    Segment::Index(index),
+                    key("token_file"),
+                ],
+                &entry.token_file,
+                entry.disabled,
+            ));
+        }
+    }
+    for (index, entry) in pool.iflow.iter().enumerate() {
+        let path = vec![
+            key("credential_pool"),
+            key("iflow"),
+            Segment::Index(index),
+            key("token_file"),
+        ];
+        match entry.token_file.as_deref() {
+            Some(token_file) => {
+                findings.extend(token_file_finding(path, token_file, entry.disabled))
+            }
+            None if entry.auth_type == "oauth" && !entry.disabled => {
+                findings.push(Finding::error(path, "OAuth 模式必须配置 token_file"));
+            }
+            None => {}
+        }
+    }
+
+    // Provider 凭证文件缺失时仅该 Provider 不可用
+    let providers = [
+        ("kiro", &config.providers.kiro),
+        ("gemini", &config.providers.gemini),
+        ("qwen", &config.providers.qwen),
+    ];
+    for (name, provider) in providers {
+        let Some(credentials_path) = provider.credentials_path.as_deref() else {
+            continue;
+        };
+        if provider.enabled && !expand_tilde(credentials_path).exists() {
+            findings.push(Finding::warning(
+                vec![key("providers"), key(name), key("credentials_path")],
+                format!("凭证文件不存在: {}", credentials_path),
+            ));
+        }
+    }
+
+    findings
+}
+
+// ============================================================================
+// 行号定位
+// ============================================================================
+
+#[derive(Debug)]
+enum TokenKind {
+    /// 序列项（`- `）
+    Item,
+    /// 映射键
+    Key(String),
+}
+
+#[derive(Debug)]
+struct Token {
+    line: usize,
+    column: usize,
+    kind: TokenKind,
+}
+
+/// 基于缩进的键定位器
+///
+/// 只识别块风格的映射与序列；流式写法（`{a: 1}`、`[a, b]`）中的字段定位到所在的父级键。
+struct KeyLocator {
+    tokens: Vec<Token>,
+}
+
+impl KeyLocator {
+    fn new(source: &str) -> Self {
+        let mut tokens = Vec::new();
+        for (index, raw) in source.lines().enumerate() {
+            let mut column = raw.len() - raw.trim_start_matches(' ').len();
+            let mut rest = &raw[column..];
+            if rest.is_empty() || rest.starts_with('#') || rest.starts_with("---") {
+                continue;
+            }
+            while rest == "-" || rest.starts_with("- ") {
+                tokens.push(Token {
+                    line: index + 1,
+                    column,
+                    kind: TokenKind::Item,
+                });
+                let after = &rest[1..];
+                let skipped = after.len() - after.trim_start_matches(' ').len();
+                column += 1 + skipped;
+                rest = &after[skipped..];
+            }
+            if let Some(name) = parse_key(rest) {
+                tokens.push(Token {
+                    line: index + 1,
+                    column,
+                    kind: TokenKind::Key(name),
+                });
+            }
+        }
+        Self { tokens }
+    }
+
+    /// 定位字段路径，返回 (行号, 列号)；找不到时返回最近的已定位父级
+    fn locate(&self, path: &[Segment]) -> Option<(usize, usize)> {
+        let mut found = None;
+        let mut parent: Option<usize> = None;
+        let mut start = 0;
+
+        for segment in path {
+            let is_item = matches!(segment, Segment::Index(_));
+            // 序列项允许与父级键同列（`key:\n- item`）
+            let block = self.tokens[start..]
+                .iter()
+                .enumerate()
+                .take_while(|(_, t)| {
+                    parent.is_none_or(|p| {
+                        t.column > p
+                            || (is_item && t.column == p && matches!(t.kind, TokenKind::Item))
+                    })
+                });
+
+            let matched = match segment {
+                Segment::Key(name) => {
+                    let mut child_column = None;
+                    block
+                        .filter(|(_, t)| matches!(t.kind, TokenKind::Key(_)))
+                        .find(|(_, t)| {
+                            let column = *child_column.get_or_insert(t.column);
+                            t.column == column && matches!(&t.kind, TokenKind::Key(k) if k == name)
+                        })
+                }
+                Segment::Index(target) => {
+                    let mut child_column = None;
+                    block
+                        .filter(|(_, t)| matches!(t.kind, TokenKind::Item))
+                        .filter(|(_, t)| t.column == *child_column.get_or_insert(t.column))
+                        .nth(*target)
+                }
+            };
+
+            match matched {
+                Some((offset, token)) => {
+                    found = Some((token.line, token.column + 1));
+                    parent = Some(token.column);
+                    start += offset + 1;
+                }
+                None => break,
+            }
+        }
+        found
+    }
+}
+
+/// 解析行首的映射键（支持带引号的键）
+fn parse_key(rest: &str) -> Option<String> {
+    let (name, remainder) = match rest.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let inner = &rest[1..];
+            let end = inner.find(quote)?;
+            (&inner[..end], &inner[end + 1..])
+        }
+        '{' | '[' | '&' | '*' | '!' | '|' | '>' => return None,
+        _ => {
+            let end = rest.find(':')?;
+            (rest[..end].trim_end(), &rest[end..])
+        }
+    };
+    let after_colon = remainder.strip_prefix(':')?;
+    if after_colon.is_empty() || after_colon.starts_with([' ', '\t']) {
+        Some(name.to_string())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(report: &ValidationReport) -> Vec<String> {
+        report.issues.iter().map(|i| i.to_string()).collect()
+    }
+
+    #[test]
+    fn test_default_config_has_no_errors() {
+        let yaml = serde_yaml::to_string(&Config::default()).unwrap();
+        let report = validate_config_source(&yaml);
+        assert!(report.config.is_some());
+        assert_eq!(report.error_count(), 0, "{:?}", messages(&report));
+    }
+
+    #[test]
+    fn test_syntax_error_has_location() {
+        let report = validate_config_source("server:\n  port: [8999\n");
+        assert!(report.config.is_none());
+        assert!(!report.is_valid());
+        assert!(report.issues[0].line.is_some());
+    }
+
+    #[test]
+    fn test_type_error_has_location() {
+        let report = validate_config_source("server:\n  port: not-a-number\n");
+        assert!(report.config.is_none());
+        let issue = &report.issues[0];
+        assert_eq!(issue.severity, IssueSeverity::Error);
+        assert_eq!(issue.line, Some(2));
+    }
+
+    #[test]
+    fn test_unknown_keys_are_located() {
+        let yaml = "\
+server:
+  host: 127.0.0.1
+  prot: 8999
+routing:
+  rules:
+    - pattern: claude-*
+      provider: claude
+      weight: 3
+";
+        let report = validate_config_source(yaml);
+        let unknown: Vec<_> = report
+            .issues
+            .iter()
+            .filter(|i| i.message.contains("未知字段"))
+            .map(|i| (i.path.as_str(), i.line, i.column))
+            .collect();
+        assert_eq!(
+            unknown,
+            vec![
+                ("server.prot", Some(3), Some(3)),
+                ("routing.rules[0].weight", Some(8), Some(7)),
+            ]
+        );
+        // 未知字段不影响解析
+        assert!(report.config.is_some());
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn test_enum_and_range_violations() {
+        let yaml = "\
+usage_quota:
+  min_remaining_ratio: 1.5
+credential_pool:
+  iflow:
+  - id: i1
+    auth_type: token
+";
+        let report = validate_config_source(yaml);
+        let paths: Vec<_> = report
+            .issues
+            .iter()
+            .filter(|i| i.severity == IssueSeverity::Error)
+            .map(|i| (i.path.as_str(), i.line))
+            .collect();
+        assert!(paths.contains(&("usage_quota.min_remaining_ratio", Some(2))));
+        assert!(paths.contains(&("credential_pool.iflow[0].auth_type", Some(6))));
+    }
+
+    #[test]
+    fn test_unresolvable_routing_provider() {
+        let yaml = "\
+routing:
+  default_provider: kiro
+  rules:
+    - pattern: gpt-*
+      provider: openai
+    - pattern: claude-*
+      provider: anthropic
+";
+        let report = validate_config_source(yaml);
+        let issue = report
+            .issues
+            .iter()
+            .find(|i| i.path == "routing.rules[1].provider")
+            .expect("应报告无法解析的 Provider");
+        assert_eq!(issue.severity, IssueSeverity::Error);
+        assert_eq!(issue.line, Some(7));
+        assert!(issue.message.contains("anthropic"));
+    }
+
+    #[test]
+    fn test_missing_token_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("present.json"), "{}").unwrap();
+        let yaml = format!(
+            "\
+auth_dir: {}
+credential_pool:
+  kiro:
+    - id: a
+      token_file: present.json
+    - id: b
+      token_file: missing.json
+    - id: c
+      token_file: gone.json
+      disabled: true
+",
+            dir.path().display()
+        );
+        let report = validate_config_source(&yaml);
+        let token_issues: Vec<_> = report
+            .issues
+            .iter()
+            .filter(|i| i.path.ends_with("token_file"))
+            .map(|i| (i.path.as_str(), i.severity, i.line))
+            .collect();
+        assert_eq!(
+            token_issues,
+            vec![
+                (
+                    "credential_pool.kiro[1].token_file",
+                    IssueSeverity::Error,
+                    Some(7)
+                ),
+                (
+                    "credential_pool.kiro[2].token_file",
+                    IssueSeverity::Warning,
+                    Some(9)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unresolved_references_are_reported() {
+        let yaml = "\
+server:
+  api_key: ${PROXYCAST_TEST_UNSET_KEY}
+proxy_url: ${PROXYCAST_TEST_UNSET_PROXY:-http://127.0.0.1:7890}
+";
+        let report = validate_config_source(yaml);
+        let issue = report
+            .issues
+            .iter()
+            .find(|i| i.path == "server.api_key")
+            .expect("应报告无法解析的引用");
+        assert_eq!(issue.severity, IssueSeverity::Error);
+        assert_eq!(issue.line, Some(2));
+        assert!(issue.message.contains("PROXYCAST_TEST_UNSET_KEY"));
+        assert!(!report.issues.iter().any(|i| i.path == "proxy_url"));
+        assert_eq!(
+            report.config.unwrap().proxy_url.as_deref(),
+            Some("http://127.0.0.1:7890")
+        );
+    }
+
+    #[test]
+    fn test_runtime_constraints_match_hot_reload_order() {
+        let mut config = Config::default();
+        config.server.port = 0;
+        config.retry.base_delay_ms = 0;
+        assert_eq!(
+            first_runtime_violation(&config).as_deref(),
+            Some("端口号不能为 0")
+        );
+
+        let issues = check_config(&config);
+        assert!(issues.iter().any(|i| i.path == "retry.base_delay_ms"));
+        assert!(issues.iter().all(|i| i.line.is_none()));
+    }
+
+    #[test]
+    fn test_locator_handles_quoted_keys_and_same_column_items() {
+        let source = "\
+\"routing\":
+  rules:
+  - pattern: a
+    provider: kiro
+  - pattern: b # comment
+    provider: qwen
+";
+        let locator = KeyLocator::new(source);
+        let path = vec![
+            key("routing"),
+            key("rules"),
+            Segment::Index(1),
+            key("provider"),
+        ];
+        assert_eq!(locator.locate(&path), Some((6, 5)));
+        // 不存在的字段定位到最近的父级
+        let missing = vec![key("routing"), key("rules"), Segment::Index(5)];
+        assert_eq!(locator.locate(&missing), Some((2, 3)));
+    }
+}
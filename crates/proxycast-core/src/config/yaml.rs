@@ -3,6 +3,7 @@
 //! 提供 YAML 配置的加载、保存和管理功能
 //! 支持保留注释的配置保存
 
+use super::interpolation::{interpolate_source, to_document};
 use super::types::Config;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -83,13 +84,28 @@ impl ConfigManager {
     }
 
     /// 从 YAML 字符串解析配置
+    ///
+    /// 解析密钥字段（`api_key`、`proxy_url`、`secret_key`、`redis_url`）中的 `${ENV}`、`${ENV:-default}`
+    /// 与 `${file:/path}` 引用，
+    /// 存在无法解析的引用时返回错误。
     pub fn parse_yaml(yaml: &str) -> Result<Config, ConfigError> {
-        serde_yaml::from_str(yaml).map_err(|e| ConfigError::ParseError(e.to_string()))
+        let interpolated =
+            interpolate_source(yaml).map_err(|e| ConfigError::ParseError(e.to_string()))?;
+        if let Some(unresolved) = interpolated.unresolved.first() {
+            return Err(ConfigError::ParseError(unresolved.to_string()));
+        }
+        interpolated
+            .into_config(yaml)
+            .map_err(|e| ConfigError::ParseError(e.to_string()))
     }
 
     /// 将配置序列化为 YAML 字符串
+    ///
+    /// 来自插值引用的值写回原始引用。
     pub fn to_yaml(config: &Config) -> Result<String, ConfigError> {
-        serde_yaml::to_string(config).map_err(|e| ConfigError::SerializeError(e.to_string()))
+        to_document(config)
+            .and_then(|document| serde_yaml::to_string(&document))
+            .map_err(|e| ConfigError::SerializeError(e.to_string()))
     }
 
     /// 保存配置到文件
@@ -670,7 +686,7 @@ pub fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
     // 优先尝试 YAML 配置
     if yaml_path.exists() {
         let content = std::fs::read_to_string(&yaml_path)?;
-        let mut config: Config = serde_yaml::from_str(&content)?;
+        let mut config = ConfigManager::parse_yaml(&content)?;
         // 如果配置中使用默认 API Key，生成强随机 Key 并保存
         if is_default_api_key(&config.server.api_key) {
             let new_key = generate_secure_api_key();
@@ -723,7 +739,7 @@ pub fn save_config(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    let content = serde_json::to_string_pretty(config)?;
+    let content = serde_json::to_string_pretty(&to_document(config)?)?;
     std::fs::write(&path, content)?;
     Ok(())
 }
@@ -738,7 +754,7 @@ pub fn save_config_yaml(config: &Config) -> Result<(), Box<dyn std::error::Error
         let backup_path = path.with_extension("yaml.backup");
         let _ = std::fs::copy(&path, &backup_path);
     }
-    let content = serde_yaml::to_string(config)?;
+    let content = ConfigManager::to_yaml(config)?;
     std::fs::write(&path, content)?;
     Ok(())
 }
@@ -49,6 +49,7 @@ pub fn convert_anthropic_to_openai(request: &AnthropicMessagesRequest) -> ChatCo
         stream: request.stream,
         tools,
         tool_choice: request.tool_choice.clone(),
+        response_format: None,
     }
 }
 
@@ -86,6 +87,7 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
         }
         serde_json::Value::Array(parts) => {
             let mut text_parts: Vec<String> = Vec::new();
+            let mut image_parts: Vec<ContentPart> = Vec::new();
             let mut tool_calls: Vec<ToolCall> = Vec::new();
             let mut tool_results: Vec<(String, String)> = Vec::new(); // (tool_use_id, content)
 
@@ -124,6 +126,13 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
                         let content = extract_tool_result_content(part.get("content"));
                         tool_results.push((tool_use_id.to_string(), content));
                     }
+                    "image" => {
+                        if let Some(url) = image_source_to_url(part.get("source")) {
+                            image_parts.push(ContentPart::ImageUrl {
+                                image_url: ImageUrl { url, detail: None },
+                            });
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -160,8 +169,22 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
                     });
                 }
 
-                // 添加文本内容
-                if !text_parts.is_empty() {
+                // 添加文本和图片内容
+                if !image_parts.is_empty() {
+                    let mut content_parts = Vec::new();
+                    if !text_parts.is_empty() {
+                        content_parts.push(ContentPart::Text {
+                            text: text_parts.join(""),
+                        });
+                    }
+                    content_parts.extend(image_parts);
+                    result.push(ChatMessage {
+                        role: "user".to_string(),
+                        content: Some(MessageContent::Parts(content_parts)),
+                        tool_calls: None,
+                        tool_call_id: None,
+                    });
+                } else if !text_parts.is_empty() {
                     result.push(ChatMessage {
                         role: "user".to_string(),
                         content: Some(MessageContent::Text(text_parts.join(""))),
@@ -177,6 +200,23 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
     result
 }
 
+/// 将 Anthropic 图片源转换为 OpenAI image_url（base64 转为 data URL）
+fn image_source_to_url(source: Option<&serde_json::Value>) -> Option<String> {
+    let source = source?;
+    match source.get("type").and_then(|t| t.as_str())? {
+        "base64" => {
+            let media_type = source.get("media_type").and_then(|m| m.as_str())?;
+            let data = source.get("data").and_then(|d| d.as_str())?;
+            Some(format!("data:{media_type};base64,{data}"))
+        }
+        "url" => source
+            .get("url")
+            .and_then(|u| u.as_str())
+            .map(|u| u.to_string()),
+        _ => None,
+    }
+}
+
 fn extract_tool_result_content(content: Option<&serde_json::Value>) -> String {
     match content {
         Some(serde_json::Value::String(s)) => s.clone(),
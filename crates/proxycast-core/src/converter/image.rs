@@ -0,0 +1,749 @@
+//! 多模态图片输入处理
+//!
+//! 将 OpenAI `image_url` 和 Anthropic `image` 内容块统一规整为内联 base64 数据，
+//! 供 Kiro/CodeWhisperer、Antigravity 和 Gemini 转换器使用。
+//!
+//! - data URL 会解码并通过文件头识别真实格式（忽略声明的 MIME 类型）
+//! - http(s) URL 仅在 `ImageConfig::fetch_remote` 开启时下载，并受大小限制；
+//!   目标解析到回环、内网或链路本地地址时拒绝下载
+//! - 不支持的格式直接拒绝，由调用方返回 400
+use crate::config::ImageConfig;
+use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::codewhisperer::{CWImage, CWImageSource};
+use crate::models::openai::{ChatCompletionRequest, ChatMessage, ContentPart, MessageContent};
+use crate::proxy::ProxyClientFactory;
+use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// CodeWhisperer / Gemini 均支持的图片格式
+pub const SUPPORTED_IMAGE_FORMATS: &[&str] = &["png", "jpeg", "gif", "webp"];
+
+/// 下载远程图片时最多跟随的重定向次数
+const MAX_IMAGE_REDIRECTS: usize = 5;
+
+/// 图片处理错误
+///
+/// 所有变体都表示客户端输入问题，对应 HTTP 400
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ImageError {
+    /// data URL 格式错误或 base64 无法解码
+    #[error("Invalid image data: {0}")]
+    InvalidData(String),
+    /// 图片格式不受支持
+    #[error("Unsupported image format '{0}'. Supported formats: png, jpeg, gif, webp")]
+    UnsupportedFormat(String),
+    /// 图片超过大小限制
+    #[error("Image is too large: {size} bytes exceeds the limit of {limit} bytes")]
+    TooLarge { size: usize, limit: usize },
+    /// 未开启远程图片下载
+    #[error("Remote image URLs are not supported by this provider; send the image as a base64 data URL instead")]
+    RemoteDisabled,
+    /// 远程图片下载失败
+    #[error("Failed to fetch image from {url}: {reason}")]
+    FetchFailed { url: String, reason: String },
+    /// 远程图片地址指向回环、内网或链路本地地址
+    #[error("Image URL {0} resolves to a private or loopback address")]
+    ForbiddenAddress(String),
+}
+
+impl ImageError {
+    /// 转换为 OpenAI 风格的错误 JSON
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "error": {
+                "message": self.to_string(),
+                "type": "invalid_request_error",
+                "code": "invalid_image"
+            }
+        })
+    }
+}
+
+/// 规整后的图片数据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedImage {
+    /// 格式名（png/jpeg/gif/webp）
+    pub format: &'static str,
+    /// base64 编码数据
+    pub data: String,
+    /// 解码后的字节数
+    pub size: usize,
+}
+
+impl DecodedImage {
+    /// MIME 类型
+    pub fn media_type(&self) -> String {
+        format!("image/{}", self.format)
+    }
+
+    /// 重新编码为 data URL
+    pub fn to_data_url(&self) -> String {
+        format!("data:{};base64,{}", self.media_type(), self.data)
+    }
+
+    /// 转换为 CodeWhisperer 图片载荷
+    pub fn to_cw_image(&self) -> CWImage {
+        CWImage {
+            format: self.format.to_string(),
+            source: CWImageSource {
+                bytes: self.data.clone(),
+            },
+        }
+    }
+}
+
+/// 判断目标 Provider 是否需要内联图片数据
+///
+/// OpenAI/Claude 等透传 Provider 自行处理 URL，无需预处理
+pub fn requires_inline_images(provider: &str) -> bool {
+    matches!(
+        provider.to_lowercase().as_str(),
+        "kiro" | "antigravity" | "gemini" | "vertex"
+    )
+}
+
+/// 解析 data URL，返回 (MIME 类型, 数据部分)
+pub fn parse_data_url(url: &str) -> Option<(String, String)> {
+    let rest = url.strip_prefix("data:")?;
+    let (meta, data) = rest.split_once(',')?;
+    let mime = meta.split(';').next()?.to_string();
+    Some((mime, data.to_string()))
+}
+
+/// 通过文件头识别图片格式
+pub fn detect_image_format(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0x89, b'P', b'N', b'G']) {
+        Some("png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("gif")
+    } else if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("webp")
+    } else {
+        None
+    }
+}
+
+/// 校验原始图片字节并编码
+fn decode_bytes(
+    bytes: &[u8],
+    declared: &str,
+    max_bytes: usize,
+) -> Result<DecodedImage, ImageError> {
+    if bytes.len() > max_bytes {
+        return Err(ImageError::TooLarge {
+            size: bytes.len(),
+            limit: max_bytes,
+        });
+    }
+    let format = detect_image_format(bytes).ok_or_else(|| {
+        let declared = declared.trim();
+        ImageError::UnsupportedFormat(if declared.is_empty() {
+            "unknown".to_string()
+        } else {
+            declared.to_string()
+        })
+    })?;
+    Ok(DecodedImage {
+        format,
+        data: BASE64_STANDARD.encode(bytes),
+        size: bytes.len(),
+    })
+}
+
+/// 解码 base64 图片数据
+pub fn decode_base64_image(
+    media_type: &str,
+    data: &str,
+    max_bytes: usize,
+) -> Result<DecodedImage, ImageError> {
+    // base64 长度约为原始数据的 4/3，提前拒绝明显超限的数据
+    if data.len() / 4 * 3 > max_bytes.saturating_add(3) {
+        return Err(ImageError::TooLarge {
+            size: data.len() / 4 * 3,
+            limit: max_bytes,
+        });
+    }
+    let cleaned: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    let bytes = BASE64_STANDARD
+        .decode(cleaned.as_bytes())
+        .map_err(|e| ImageError::InvalidData(e.to_string()))?;
+    decode_bytes(&bytes, media_type, max_bytes)
+}
+
+/// 解码 data URL 图片
+pub fn decode_data_url(url: &str, max_bytes: usize) -> Result<DecodedImage, ImageError> {
+    let (meta, data) = url
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(','))
+        .ok_or_else(|| ImageError::InvalidData("malformed data URL".to_string()))?;
+    if !meta.ends_with(";base64") {
+        return Err(ImageError::InvalidData(
+            "only base64-encoded data URLs are supported".to_string(),
+        ));
+    }
+    let mime = meta.split(';').next().unwrap_or("");
+    decode_base64_image(mime, data, max_bytes)
+}
+
+/// 判断地址是否禁止作为远程图片来源
+///
+/// 拒绝回环、私有网段（RFC 1918 / IPv6 ULA）、链路本地（含 169.254.169.254 元数据地址）、
+/// 运营商级 NAT、未指定和广播地址，防止客户端借代理访问内部服务
+pub fn is_forbidden_image_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b))
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_forbidden_image_ip(IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+/// 只返回公网地址的 DNS 解析器
+///
+/// 直连下载时由 reqwest 在建立连接前调用，解析结果与实际连接地址一致，
+/// 避免先校验后解析带来的 DNS 重绑定问题
+struct PublicOnlyResolver;
+
+impl Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| !is_forbidden_image_ip(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(ImageError::ForbiddenAddress(host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// 远程图片下载器
+///
+/// 客户端在启动时按全局 `proxy_url` 构建一次，所有请求共享连接池。
+/// 重定向由下载器逐跳处理，每一跳都会重新校验目标地址。
+#[derive(Debug, Clone)]
+pub struct ImageFetcher {
+    client: reqwest::Client,
+    /// 是否经由上游代理下载（此时 DNS 由代理解析，需在发送前自行校验）
+    proxied: bool,
+}
+
+impl Default for ImageFetcher {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl ImageFetcher {
+    /// 创建下载器，代理地址无效时回退为直连
+    pub fn new(proxy_url: Option<&str>) -> Self {
+        let proxy_url = proxy_url.filter(|url| !url.trim().is_empty());
+        let builder = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+        let (builder, proxied) = match proxy_url {
+            Some(url) => match ProxyClientFactory::parse_proxy_url(url)
+                .ok()
+                .and_then(|_| reqwest::Proxy::all(url).ok())
+            {
+                Some(proxy) => (builder.proxy(proxy), true),
+                None => {
+                    tracing::warn!("[IMAGE] 代理地址无效，远程图片改为直连下载: {}", url);
+                    (builder.no_proxy(), false)
+                }
+            },
+            None => (builder.no_proxy(), false),
+        };
+        let builder = if proxied {
+            builder
+        } else {
+            builder.dns_resolver(Arc::new(PublicOnlyResolver))
+        };
+        Self {
+            client: builder.build().unwrap_or_default(),
+            proxied,
+        }
+    }
+
+    /// 校验远程图片地址
+    ///
+    /// IP 字面量直接检查；域名在经由代理下载时预先解析检查，
+    /// 直连时由 `PublicOnlyResolver` 在连接时检查
+    async fn check_url(&self, url: &reqwest::Url) -> Result<(), ImageError> {
+        let forbidden = || ImageError::ForbiddenAddress(url.to_string());
+        match url.host() {
+            Some(url::Host::Ipv4(ip)) if is_forbidden_image_ip(IpAddr::V4(ip)) => Err(forbidden()),
+            Some(url::Host::Ipv6(ip)) if is_forbidden_image_ip(IpAddr::V6(ip)) => Err(forbidden()),
+            Some(url::Host::Domain(host)) if self.proxied => {
+                let port = url.port_or_known_default().unwrap_or(80);
+                let addrs = tokio::net::lookup_host((host, port)).await.map_err(|e| {
+                    ImageError::FetchFailed {
+                        url: url.to_string(),
+                        reason: e.to_string(),
+                    }
+                })?;
+                let mut resolved = false;
+                for addr in addrs {
+                    if is_forbidden_image_ip(addr.ip()) {
+                        return Err(forbidden());
+                    }
+                    resolved = true;
+                }
+                if resolved {
+                    Ok(())
+                } else {
+                    Err(forbidden())
+                }
+            }
+            Some(_) => Ok(()),
+            None => Err(ImageError::InvalidData(
+                "image URL must include a host".to_string(),
+            )),
+        }
+    }
+
+    /// 下载远程图片
+    pub async fn fetch(&self, url: &str, config: &ImageConfig) -> Result<DecodedImage, ImageError> {
+        let fetch_err = |reason: String| ImageError::FetchFailed {
+            url: url.to_string(),
+            reason,
+        };
+
+        let mut target = reqwest::Url::parse(url).map_err(|e| fetch_err(e.to_string()))?;
+        let mut redirects = 0;
+        let mut resp = loop {
+            self.check_url(&target).await?;
+            let resp = self
+                .client
+                .get(target.clone())
+                .timeout(Duration::from_secs(config.fetch_timeout_secs))
+                .send()
+                .await
+                .map_err(|e| match forbidden_source(&e) {
+                    Some(err) => err,
+                    None => fetch_err(e.to_string()),
+                })?;
+            if !resp.status().is_redirection() {
+                break resp;
+            }
+            redirects += 1;
+            if redirects > MAX_IMAGE_REDIRECTS {
+                return Err(fetch_err("too many redirects".to_string()));
+            }
+            let location = resp
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| fetch_err(format!("HTTP {} without Location", resp.status())))?;
+            target = target
+                .join(location)
+                .map_err(|e| fetch_err(e.to_string()))?;
+            if !matches!(target.scheme(), "http" | "https") {
+                return Err(fetch_err(format!("unsupported redirect to {}", target)));
+            }
+        };
+        if !resp.status().is_success() {
+            return Err(fetch_err(format!("HTTP {}", resp.status())));
+        }
+        if let Some(len) = resp.content_length() {
+            if len as usize > config.max_image_bytes {
+                return Err(ImageError::TooLarge {
+                    size: len as usize,
+                    limit: config.max_image_bytes,
+                });
+            }
+        }
+        let content_type = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+
+        // 分块读取，防止未声明 Content-Length 的超大响应
+        let mut bytes: Vec<u8> = Vec::new();
+        while let Some(chunk) = resp.chunk().await.map_err(|e| fetch_err(e.to_string()))? {
+            bytes.extend_from_slice(&chunk);
+            if bytes.len() > config.max_image_bytes {
+                return Err(ImageError::TooLarge {
+                    size: bytes.len(),
+                    limit: config.max_image_bytes,
+                });
+            }
+        }
+        decode_bytes(&bytes, &content_type, config.max_image_bytes)
+    }
+}
+
+/// 从 reqwest 错误链中取出解析器拒绝的地址错误
+fn forbidden_source(err: &reqwest::Error) -> Option<ImageError> {
+    let mut source = std::error::Error::source(err);
+    while let Some(e) = source {
+        if let Some(image_err @ ImageError::ForbiddenAddress(_)) = e.downcast_ref::<ImageError>() {
+            return Some(image_err.clone());
+        }
+        source = e.source();
+    }
+    None
+}
+
+/// 将任意图片引用（data URL 或 http URL）规整为内联图片
+async fn resolve_image_url(
+    fetcher: &ImageFetcher,
+    url: &str,
+    config: &ImageConfig,
+) -> Result<DecodedImage, ImageError> {
+    if url.starts_with("data:") {
+        decode_data_url(url, config.max_image_bytes)
+    } else if url.starts_with("http://") || url.starts_with("https://") {
+        if !config.fetch_remote {
+            return Err(ImageError::RemoteDisabled);
+        }
+        fetcher.fetch(url, config).await
+    } else {
+        Err(ImageError::InvalidData(
+            "image URL must be a data URL or http(s) URL".to_string(),
+        ))
+    }
+}
+
+fn request_has_images(request: &ChatCompletionRequest) -> bool {
+    request.messages.iter().any(|m| match &m.content {
+        Some(MessageContent::Parts(parts)) => parts
+            .iter()
+            .any(|p| matches!(p, ContentPart::ImageUrl { .. })),
+        _ => false,
+    })
+}
+
+/// 预处理 OpenAI 请求中的图片
+///
+/// 校验并下载所有图片，将 `image_url.url` 统一改写为规范化的 base64 data URL
+pub async fn prepare_request_images(
+    request: &mut ChatCompletionRequest,
+    config: &ImageConfig,
+    fetcher: &ImageFetcher,
+) -> Result<(), ImageError> {
+    if !request_has_images(request) {
+        return Ok(());
+    }
+    for msg in request.messages.iter_mut() {
+        if let Some(MessageContent::Parts(parts)) = msg.content.as_mut() {
+            for part in parts.iter_mut() {
+                if let ContentPart::ImageUrl { image_url } = part {
+                    let image = resolve_image_url(fetcher, &image_url.url, config).await?;
+                    image_url.url = image.to_data_url();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 预处理 Anthropic 请求中的图片
+///
+/// `url` 类型的图片源会被下载并改写为 `base64` 类型
+pub async fn prepare_anthropic_request_images(
+    request: &mut AnthropicMessagesRequest,
+    config: &ImageConfig,
+    fetcher: &ImageFetcher,
+) -> Result<(), ImageError> {
+    for msg in request.messages.iter_mut() {
+        let Some(blocks) = msg.content.as_array_mut() else {
+            continue;
+        };
+        for block in blocks.iter_mut() {
+            if block.get("type").and_then(|t| t.as_str()) != Some("image") {
+                continue;
+            }
+            let source = block.get("source").cloned().unwrap_or_default();
+            let image = match source.get("type").and_then(|t| t.as_str()) {
+                Some("base64") => decode_base64_image(
+                    source
+                        .get("media_type")
+                        .and_then(|m| m.as_str())
+                        .unwrap_or(""),
+                    source.get("data").and_then(|d| d.as_str()).unwrap_or(""),
+                    config.max_image_bytes,
+                )?,
+                Some("url") => {
+                    let url = source.get("url").and_then(|u| u.as_str()).unwrap_or("");
+                    resolve_image_url(fetcher, url, config).await?
+                }
+                other => {
+                    return Err(ImageError::InvalidData(format!(
+                        "unsupported image source type: {}",
+                        other.unwrap_or("missing")
+                    )))
+                }
+            };
+            block["source"] = serde_json::json!({
+                "type": "base64",
+                "media_type": image.media_type(),
+                "data": image.data,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// 提取消息中的内联图片，转换为 CodeWhisperer 格式
+///
+/// 无法解码的图片会被跳过（应已在 `prepare_request_images` 中拒绝）
+pub fn extract_cw_images(msg: &ChatMessage) -> Vec<CWImage> {
+    match &msg.content {
+        Some(MessageContent::Parts(parts)) => parts
+            .iter()
+            .filter_map(|p| match p {
+                ContentPart::ImageUrl { image_url } => {
+                    decode_data_url(&image_url.url, usize::MAX).ok()
+                }
+                _ => None,
+            })
+            .map(|img| img.to_cw_image())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::openai::ImageUrl;
+
+    const PNG_HEADER: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0];
+
+    fn png_data_url() -> String {
+        format!(
+            "data:image/png;base64,{}",
+            BASE64_STANDARD.encode(PNG_HEADER)
+        )
+    }
+
+    #[test]
+    fn test_detect_image_format() {
+        assert_eq!(detect_image_format(PNG_HEADER), Some("png"));
+        assert_eq!(detect_image_format(&[0xFF, 0xD8, 0xFF, 0xE0]), Some("jpeg"));
+        assert_eq!(detect_image_format(b"GIF89a...."), Some("gif"));
+        assert_eq!(detect_image_format(b"RIFF\0\0\0\0WEBPVP8 "), Some("webp"));
+        assert_eq!(detect_image_format(b"BM\0\0\0\0"), None);
+    }
+
+    #[test]
+    fn test_decode_data_url_uses_sniffed_format() {
+        // 声明为 jpeg，实际是 png
+        let url = format!(
+            "data:image/jpeg;base64,{}",
+            BASE64_STANDARD.encode(PNG_HEADER)
+        );
+        let image = decode_data_url(&url, 1024).unwrap();
+        assert_eq!(image.format, "png");
+        assert_eq!(image.size, PNG_HEADER.len());
+        assert!(image.to_data_url().starts_with("data:image/png;base64,"));
+    }
+
+    #[test]
+    fn test_decode_data_url_rejects_unsupported_and_oversized() {
+        let bmp = format!(
+            "data:image/bmp;base64,{}",
+            BASE64_STANDARD.encode(b"BM\0\0\0\0")
+        );
+        assert_eq!(
+            decode_data_url(&bmp, 1024),
+            Err(ImageError::UnsupportedFormat("image/bmp".to_string()))
+        );
+        assert!(matches!(
+            decode_data_url(&png_data_url(), 4),
+            Err(ImageError::TooLarge { .. })
+        ));
+        assert!(matches!(
+            decode_data_url("data:image/png,raw", 1024),
+            Err(ImageError::InvalidData(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_prepare_request_images_rejects_remote_when_disabled() {
+        let mut request = ChatCompletionRequest {
+            model: "claude-sonnet-4-5".to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: Some(MessageContent::Parts(vec![ContentPart::ImageUrl {
+                    image_url: ImageUrl {
+                        url: "https://example.com/cat.png".to_string(),
+                        detail: None,
+                    },
+                }])),
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            temperature: None,
+            max_tokens: None,
+            stream: false,
+            tools: None,
+            tool_choice: None,
+            response_format: None,
+        };
+        let result = prepare_request_images(
+            &mut request,
+            &ImageConfig::default(),
+            &ImageFetcher::default(),
+        )
+        .await;
+        assert_eq!(result, Err(ImageError::RemoteDisabled));
+    }
+
+    #[test]
+    fn test_is_forbidden_image_ip() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(is_forbidden_image_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["93.184.216.34", "8.8.8.8", "2606:4700::1111"] {
+            assert!(!is_forbidden_image_ip(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_rejects_private_addresses() {
+        let config = ImageConfig {
+            fetch_remote: true,
+            ..Default::default()
+        };
+        let fetcher = ImageFetcher::default();
+        for url in [
+            "http://127.0.0.1:9/cat.png",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://[::1]/cat.png",
+        ] {
+            assert!(
+                matches!(
+                    fetcher.fetch(url, &config).await,
+                    Err(ImageError::ForbiddenAddress(_))
+                ),
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prepare_anthropic_request_images_normalizes_media_type() {
+        let mut request: AnthropicMessagesRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{
+                "role": "user",
+                "content": [{
+                    "type": "image",
+                    "source": {
+                        "type": "base64",
+                        "media_type": "image/jpeg",
+                        "data": BASE64_STANDARD.encode(PNG_HEADER)
+                    }
+                }]
+            }]
+        }))
+        .unwrap();
+        prepare_anthropic_request_images(
+            &mut request,
+            &ImageConfig::default(),
+            &ImageFetcher::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            request.messages[0].content[0]["source"]["media_type"],
+            "image/png"
+        );
+    }
+
+    #[test]
+    fn test_extract_cw_images() {
+        let msg = ChatMessage {
+            role: "user".to_string(),
+            content: Some(MessageContent::Parts(vec![
+                ContentPart::Text {
+                    text: "what is this".to_string(),
+                },
+                ContentPart::ImageUrl {
+                    image_url: ImageUrl {
+                        url: png_data_url(),
+                        detail: None,
+                    },
+                },
+            ])),
+            tool_calls: None,
+            tool_call_id: None,
+        };
+        let images = extract_cw_images(&msg);
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].format, "png");
+        assert_eq!(images[0].source.bytes, BASE64_STANDARD.encode(PNG_HEADER));
+    }
+
+    #[test]
+    fn test_codewhisperer_request_carries_images() {
+        let request = ChatCompletionRequest {
+            model: "claude-sonnet-4-5".to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: Some(MessageContent::Parts(vec![
+                    ContentPart::Text {
+                        text: "describe".to_string(),
+                    },
+                    ContentPart::ImageUrl {
+                        image_url: ImageUrl {
+                            url: png_data_url(),
+                            detail: None,
+                        },
+                    },
+                ])),
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            temperature: None,
+            max_tokens: None,
+            stream: false,
+            tools: None,
+            tool_choice: None,
+            response_format: None,
+        };
+        let cw = crate::converter::openai_to_cw::convert_openai_to_codewhisperer(&request, None);
+        let current = cw.conversation_state.current_message.user_input_message;
+        assert_eq!(current.content, "describe");
+        assert_eq!(current.images.map(|i| i.len()), Some(1));
+    }
+}
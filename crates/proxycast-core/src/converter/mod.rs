@@ -1,16 +1,22 @@
 pub mod anthropic_to_openai;
 pub mod cw_to_openai;
+pub mod image;
 pub mod openai_to_antigravity;
 pub mod openai_to_cw;
 pub mod protocol_selector;
+pub mod structured_output;
 
 #[allow(unused_imports)]
 pub use anthropic_to_openai::*;
 #[allow(unused_imports)]
 pub use cw_to_openai::*;
 #[allow(unused_imports)]
+pub use image::*;
+#[allow(unused_imports)]
 pub use openai_to_antigravity::*;
 #[allow(unused_imports)]
 pub use openai_to_cw::*;
 #[allow(unused_imports)]
 pub use protocol_selector::*;
+#[allow(unused_imports)]
+pub use structured_output::*;
@@ -1,4 +1,6 @@
 //! OpenAI 格式转换为 Antigravity (Gemini) 格式
+use super::image::parse_data_url;
+use super::structured_output::{gemini_response_schema, ResponseFormat};
 use crate::models::openai::*;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -81,6 +83,10 @@ pub struct GeminiGenerationConfig {
     pub candidate_count: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thinking_config: Option<ThinkingConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_schema: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -250,6 +256,14 @@ pub fn convert_openai_to_antigravity_with_context(
         }
     }
 
+    // 结构化输出：映射到原生 responseMimeType / responseSchema
+    let response_format = ResponseFormat::from_request(request).filter(|f| f.is_json());
+    let response_schema = match &response_format {
+        // 无法转换的 schema 已在调用前以 400 拒绝
+        Some(ResponseFormat::JsonSchema { schema, .. }) => gemini_response_schema(schema).ok(),
+        _ => None,
+    };
+
     // 构建生成配置
     let generation_config = Some(GeminiGenerationConfig {
         temperature: request.temperature.or(Some(1.0)),
@@ -268,6 +282,8 @@ pub fn convert_openai_to_antigravity_with_context(
             include_thoughts: enable_thinking,
             thinking_budget: if enable_thinking { 1024 } else { 0 },
         }),
+        response_mime_type: response_format.map(|_| "application/json".to_string()),
+        response_schema,
     });
 
     // 转换工具
@@ -450,20 +466,6 @@ fn convert_assistant_content(msg: &ChatMessage, _contents: &[GeminiContent]) ->
     parts
 }
 
-/// 解析 data URL
-fn parse_data_url(url: &str) -> Option<(String, String)> {
-    if url.starts_with("data:") {
-        let parts: Vec<&str> = url.splitn(2, ',').collect();
-        if parts.len() == 2 {
-            let meta = parts[0].strip_prefix("data:")?;
-            let mime = meta.split(';').next()?.to_string();
-            let data = parts[1].to_string();
-            return Some((mime, data));
-        }
-    }
-    None
-}
-
 /// 将 Antigravity 响应转换为 OpenAI 格式
 pub fn convert_antigravity_to_openai_response(
     antigravity_resp: &serde_json::Value,
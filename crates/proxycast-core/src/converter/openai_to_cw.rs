@@ -1,4 +1,5 @@
 //! OpenAI 格式转换为 CodeWhisperer 格式
+use super::image::extract_cw_images;
 use crate::models::codewhisperer::*;
 use crate::models::openai::*;
 use std::collections::HashMap;
@@ -93,6 +94,7 @@ fn preprocess_messages(messages: &[&ChatMessage]) -> Vec<ProcessedMessage> {
                 result.push(ProcessedMessage {
                     role: "user".to_string(),
                     content,
+                    images: extract_cw_images(msg),
                     tool_calls: None,
                     tool_results: if tool_results.is_empty() {
                         None
@@ -114,6 +116,7 @@ fn preprocess_messages(messages: &[&ChatMessage]) -> Vec<ProcessedMessage> {
                     result.push(ProcessedMessage {
                         role: "user".to_string(),
                         content: "Tool results provided.".to_string(),
+                        images: Vec::new(),
                         tool_calls: None,
                         tool_results: Some(tool_results),
                     });
@@ -135,6 +138,7 @@ fn preprocess_messages(messages: &[&ChatMessage]) -> Vec<ProcessedMessage> {
                 result.push(ProcessedMessage {
                     role: "assistant".to_string(),
                     content,
+                    images: Vec::new(),
                     tool_calls,
                     tool_results: None,
                 });
@@ -152,6 +156,7 @@ fn preprocess_messages(messages: &[&ChatMessage]) -> Vec<ProcessedMessage> {
         result.push(ProcessedMessage {
             role: "user".to_string(),
             content: "Tool results provided.".to_string(),
+            images: Vec::new(),
             tool_calls: None,
             tool_results: Some(tool_results),
         });
@@ -164,6 +169,7 @@ fn preprocess_messages(messages: &[&ChatMessage]) -> Vec<ProcessedMessage> {
 struct ProcessedMessage {
     role: String,
     content: String,
+    images: Vec<CWImage>,
     tool_calls: Option<Vec<CWToolUse>>,
     tool_results: Option<Vec<CWToolResult>>,
 }
@@ -209,7 +215,7 @@ pub fn convert_openai_to_codewhisperer(
             content: combined,
             model_id: cw_model.clone(),
             origin: "AI_EDITOR".to_string(),
-            images: None,
+            images: non_empty_images(&messages[0].images),
             user_input_message_context: None,
         };
 
@@ -249,7 +255,7 @@ pub fn convert_openai_to_codewhisperer(
                     content,
                     model_id: cw_model.clone(),
                     origin: "AI_EDITOR".to_string(),
-                    images: None,
+                    images: non_empty_images(&msg.images),
                     user_input_message_context: None,
                 };
 
@@ -286,24 +292,29 @@ pub fn convert_openai_to_codewhisperer(
     let history = fix_history_alternation(history, &cw_model);
 
     // 构建当前消息
-    let (current_content, current_tool_results) = if let Some(last_msg) = messages.last() {
-        if last_msg.role == "assistant" {
-            ("Continue".to_string(), None)
-        } else {
-            let content = if last_msg.content.is_empty() {
-                if last_msg.tool_results.is_some() {
-                    "Tool results provided.".to_string()
-                } else {
-                    "Continue".to_string()
-                }
+    let (current_content, current_tool_results, current_images) =
+        if let Some(last_msg) = messages.last() {
+            if last_msg.role == "assistant" {
+                ("Continue".to_string(), None, None)
             } else {
-                last_msg.content.clone()
-            };
-            (content, last_msg.tool_results.clone())
-        }
-    } else {
-        ("Continue".to_string(), None)
-    };
+                let content = if last_msg.content.is_empty() {
+                    if last_msg.tool_results.is_some() {
+                        "Tool results provided.".to_string()
+                    } else {
+                        "Continue".to_string()
+                    }
+                } else {
+                    last_msg.content.clone()
+                };
+                (
+                    content,
+                    last_msg.tool_results.clone(),
+                    non_empty_images(&last_msg.images),
+                )
+            }
+        } else {
+            ("Continue".to_string(), None, None)
+        };
 
     // 构建 tools
     let tools = request.tools.as_ref().map(|tools| {
@@ -358,7 +369,7 @@ pub fn convert_openai_to_codewhisperer(
                     content: current_content,
                     model_id: cw_model,
                     origin: "AI_EDITOR".to_string(),
-                    images: None,
+                    images: current_images,
                     user_input_message_context,
                 },
             },
@@ -372,6 +383,14 @@ pub fn convert_openai_to_codewhisperer(
     }
 }
 
+fn non_empty_images(images: &[CWImage]) -> Option<Vec<CWImage>> {
+    if images.is_empty() {
+        None
+    } else {
+        Some(images.to_vec())
+    }
+}
+
 /// 修复历史记录，确保 user/assistant 严格交替
 fn fix_history_alternation(history: Vec<HistoryItem>, model_id: &str) -> Vec<HistoryItem> {
     if history.is_empty() {
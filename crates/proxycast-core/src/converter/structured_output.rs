@@ -0,0 +1,815 @@
+//! 结构化输出（JSON 模式）转换层
+//!
+//! 将 OpenAI `response_format` 映射到各后端：
+//! - Antigravity：原生 `responseMimeType` + `responseSchema`
+//! - Anthropic：追加并强制调用与 schema 同名的工具，再把工具参数作为输出
+//! - 其他（Kiro、Qwen 等）：在 system prompt 中注入 schema，由调用方校验输出并重试
+use crate::models::openai::{ChatCompletionRequest, ChatMessage, MessageContent, Tool};
+use serde_json::Value;
+
+/// 默认 schema 名称（`json_object` 模式或未提供 name 时使用）
+const DEFAULT_SCHEMA_NAME: &str = "json_response";
+
+/// 解析后的 `response_format`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResponseFormat {
+    /// 普通文本（无约束）
+    Text,
+    /// 任意 JSON 对象
+    JsonObject,
+    /// 指定 JSON Schema
+    JsonSchema {
+        name: String,
+        schema: Value,
+        strict: bool,
+    },
+}
+
+impl ResponseFormat {
+    /// 从请求中解析 `response_format`
+    pub fn from_request(request: &ChatCompletionRequest) -> Option<Self> {
+        request.response_format.as_ref().and_then(Self::from_value)
+    }
+
+    /// 从 JSON 值解析
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value.get("type").and_then(|t| t.as_str())? {
+            "text" => Some(Self::Text),
+            "json_object" => Some(Self::JsonObject),
+            "json_schema" => {
+                let spec = value.get("json_schema")?;
+                Some(Self::JsonSchema {
+                    name: spec
+                        .get("name")
+                        .and_then(|n| n.as_str())
+                        .unwrap_or(DEFAULT_SCHEMA_NAME)
+                        .to_string(),
+                    schema: spec
+                        .get("schema")
+                        .cloned()
+                        .unwrap_or_else(|| serde_json::json!({"type": "object"})),
+                    strict: spec
+                        .get("strict")
+                        .and_then(|s| s.as_bool())
+                        .unwrap_or(false),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// 是否要求 JSON 输出
+    pub fn is_json(&self) -> bool {
+        !matches!(self, Self::Text)
+    }
+
+    /// 获取 schema 名称
+    pub fn name(&self) -> &str {
+        match self {
+            Self::JsonSchema { name, .. } => name,
+            _ => DEFAULT_SCHEMA_NAME,
+        }
+    }
+
+    /// 获取 schema（`json_object` 模式为任意对象）
+    pub fn schema(&self) -> Value {
+        match self {
+            Self::JsonSchema { schema, .. } => schema.clone(),
+            _ => serde_json::json!({"type": "object"}),
+        }
+    }
+}
+
+/// 结构化输出实现策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructuredOutputStrategy {
+    /// 后端原生支持（OpenAI 兼容接口透传、Antigravity responseSchema）
+    Native,
+    /// Anthropic 工具强制调用
+    ToolForcing,
+    /// schema 注入 system prompt
+    PromptFallback,
+}
+
+/// 结构化输出错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum StructuredOutputError {
+    /// 多次尝试后输出仍未通过校验
+    #[error("Model output did not match the requested JSON schema after {attempts} attempt(s): {}", errors.join("; "))]
+    ValidationFailed { attempts: u32, errors: Vec<String> },
+    /// schema 无法转换为后端支持的形式
+    #[error("Unsupported JSON schema: {0}")]
+    UnsupportedSchema(String),
+}
+
+impl StructuredOutputError {
+    /// 获取对应的 HTTP 状态码
+    pub fn status_code(&self) -> u16 {
+        match self {
+            Self::ValidationFailed { .. } => 502,
+            Self::UnsupportedSchema(_) => 400,
+        }
+    }
+
+    /// 转换为 JSON 错误响应
+    pub fn to_json(&self) -> Value {
+        match self {
+            Self::ValidationFailed { attempts, errors } => serde_json::json!({
+                "error": {
+                    "message": self.to_string(),
+                    "type": "structured_output_error",
+                    "code": "json_validation_failed",
+                    "attempts": attempts,
+                    "validation_errors": errors
+                }
+            }),
+            Self::UnsupportedSchema(_) => serde_json::json!({
+                "error": {
+                    "message": self.to_string(),
+                    "type": "invalid_request_error",
+                    "code": "unsupported_schema"
+                }
+            }),
+        }
+    }
+}
+
+/// 构建注入 system prompt 的 schema 说明
+pub fn build_schema_instruction(format: &ResponseFormat) -> String {
+    match format {
+        ResponseFormat::Text => String::new(),
+        ResponseFormat::JsonObject => "You must respond with a single valid JSON object only. \
+Do not include markdown code fences, comments or any text outside the JSON."
+            .to_string(),
+        ResponseFormat::JsonSchema { name, schema, .. } => format!(
+            "You must respond with a single valid JSON value that conforms to the JSON Schema \
+named \"{name}\" below. Do not include markdown code fences, comments or any text outside the JSON.\n\n\
+JSON Schema:\n{}",
+            serde_json::to_string_pretty(schema).unwrap_or_default()
+        ),
+    }
+}
+
+/// 应用 system prompt 回退方案
+///
+/// 将 schema 说明追加到 system 消息（不存在则新建），并移除 `response_format`
+pub fn apply_prompt_fallback(request: &mut ChatCompletionRequest, format: &ResponseFormat) {
+    request.response_format = None;
+    let instruction = build_schema_instruction(format);
+    if instruction.is_empty() {
+        return;
+    }
+    if let Some(system) = request.messages.iter_mut().find(|m| m.role == "system") {
+        let existing = system.get_content_text();
+        system.content = Some(MessageContent::Text(if existing.is_empty() {
+            instruction
+        } else {
+            format!("{existing}\n\n{instruction}")
+        }));
+    } else {
+        request.messages.insert(
+            0,
+            ChatMessage {
+                role: "system".to_string(),
+                content: Some(MessageContent::Text(instruction)),
+                tool_calls: None,
+                tool_call_id: None,
+            },
+        );
+    }
+}
+
+/// 追加一轮纠错消息，用于校验失败后的重试
+pub fn append_retry_messages(request: &mut ChatCompletionRequest, output: &str, errors: &[String]) {
+    request.messages.push(ChatMessage {
+        role: "assistant".to_string(),
+        content: Some(MessageContent::Text(output.to_string())),
+        tool_calls: None,
+        tool_call_id: None,
+    });
+    request.messages.push(ChatMessage {
+        role: "user".to_string(),
+        content: Some(MessageContent::Text(format!(
+            "Your previous response was not valid for the required JSON schema:\n- {}\n\
+Respond again with only the corrected JSON.",
+            errors.join("\n- ")
+        ))),
+        tool_calls: None,
+        tool_call_id: None,
+    });
+}
+
+/// 构建 Anthropic 强制工具调用参数，返回 (tools, tool_choice)
+///
+/// 保留调用方的工具定义，在末尾追加与 schema 同名的工具并强制调用它
+pub fn anthropic_forced_tool(tools: Option<&[Tool]>, format: &ResponseFormat) -> (Value, Value) {
+    let name = format.name();
+    let mut anthropic_tools: Vec<Value> = tools
+        .unwrap_or_default()
+        .iter()
+        .filter(|t| t.function.name != name)
+        .map(|t| {
+            serde_json::json!({
+                "name": t.function.name,
+                "description": t.function.description,
+                "input_schema": t.function.parameters.clone()
+                    .unwrap_or_else(|| serde_json::json!({"type": "object"})),
+            })
+        })
+        .collect();
+    anthropic_tools.push(serde_json::json!({
+        "name": name,
+        "description": "Respond by calling this tool with the final answer as its input.",
+        "input_schema": format.schema(),
+    }));
+    let tool_choice = serde_json::json!({"type": "tool", "name": name});
+    (Value::Array(anthropic_tools), tool_choice)
+}
+
+/// 转换为 Gemini `responseSchema`
+///
+/// Gemini 不支持引用，先内联 `$defs`/`definitions` 中的定义，再移除不支持的关键字。
+/// 递归引用或指向外部文档的引用无法展开，返回 `UnsupportedSchema`。
+pub fn gemini_response_schema(schema: &Value) -> Result<Value, StructuredOutputError> {
+    let inlined = inline_refs(schema, schema, &mut Vec::new())?;
+    Ok(strip_gemini_keywords(&inlined))
+}
+
+/// 解析指向本地定义的 `$ref`（`#/$defs/...` 或 `#/definitions/...`）
+fn resolve_local_ref<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    let pointer = reference.strip_prefix('#')?;
+    if !pointer.starts_with("/$defs/") && !pointer.starts_with("/definitions/") {
+        return None;
+    }
+    root.pointer(pointer)
+}
+
+/// 递归展开 `$ref`，`stack` 记录当前展开路径上的引用用于检测循环
+fn inline_refs(
+    node: &Value,
+    root: &Value,
+    stack: &mut Vec<String>,
+) -> Result<Value, StructuredOutputError> {
+    match node {
+        Value::Object(map) => {
+            let Some(reference) = map.get("$ref").and_then(|r| r.as_str()) else {
+                return inline_entries(map.iter(), root, stack).map(Value::Object);
+            };
+            if stack.iter().any(|r| r == reference) {
+                return Err(StructuredOutputError::UnsupportedSchema(format!(
+                    "recursive $ref '{reference}' cannot be inlined"
+                )));
+            }
+            let target = resolve_local_ref(root, reference).ok_or_else(|| {
+                StructuredOutputError::UnsupportedSchema(format!(
+                    "$ref '{reference}' does not point to a definition in $defs or definitions"
+                ))
+            })?;
+            stack.push(reference.to_string());
+            let resolved = inline_refs(target, root, stack);
+            stack.pop();
+            let mut resolved = resolved?;
+
+            // 与 $ref 并列的关键字（如 description）覆盖被引用定义中的同名关键字
+            let siblings = inline_entries(map.iter().filter(|(k, _)| *k != "$ref"), root, stack)?;
+            if let Value::Object(resolved_map) = &mut resolved {
+                resolved_map.extend(siblings);
+            }
+            Ok(resolved)
+        }
+        Value::Array(arr) => arr
+            .iter()
+            .map(|item| inline_refs(item, root, stack))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        other => Ok(other.clone()),
+    }
+}
+
+/// 展开 schema 节点的各个关键字；`properties` 下的键是字段名，定义本身会被移除无需展开
+fn inline_entries<'a>(
+    entries: impl Iterator<Item = (&'a String, &'a Value)>,
+    root: &Value,
+    stack: &mut Vec<String>,
+) -> Result<serde_json::Map<String, Value>, StructuredOutputError> {
+    let mut out = serde_json::Map::new();
+    for (key, value) in entries {
+        let value = match (key.as_str(), value) {
+            ("$defs" | "definitions", _) => value.clone(),
+            ("properties", Value::Object(properties)) => {
+                let mut inlined = serde_json::Map::new();
+                for (name, prop) in properties {
+                    inlined.insert(name.clone(), inline_refs(prop, root, stack)?);
+                }
+                Value::Object(inlined)
+            }
+            _ => inline_refs(value, root, stack)?,
+        };
+        out.insert(key.clone(), value);
+    }
+    Ok(out)
+}
+
+/// 移除 Gemini 不支持的关键字
+///
+/// 只在 schema 节点上移除关键字；`properties` 下的键是字段名，
+/// 即使叫 `title` 或 `$id` 也要保留。
+fn strip_gemini_keywords(schema: &Value) -> Value {
+    const UNSUPPORTED_KEYS: &[&str] = &[
+        "$schema",
+        "$id",
+        "additionalProperties",
+        "title",
+        "$defs",
+        "definitions",
+    ];
+    match schema {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(k, _)| !UNSUPPORTED_KEYS.contains(&k.as_str()))
+                .map(|(k, v)| {
+                    let v = match (k.as_str(), v) {
+                        ("properties", Value::Object(properties)) => Value::Object(
+                            properties
+                                .iter()
+                                .map(|(name, prop)| (name.clone(), strip_gemini_keywords(prop)))
+                                .collect(),
+                        ),
+                        _ => strip_gemini_keywords(v),
+                    };
+                    (k.clone(), v)
+                })
+                .collect(),
+        ),
+        Value::Array(arr) => Value::Array(arr.iter().map(strip_gemini_keywords).collect()),
+        other => other.clone(),
+    }
+}
+
+/// 把非流式 Chat Completion 响应转换为 OpenAI SSE 事件流
+///
+/// 用于流式请求的结构化输出：先以非流式请求完成校验和重试，再按流式格式返回
+pub fn completion_to_sse(response: &Value) -> String {
+    let choice = &response["choices"][0];
+    let chunk = |delta: Value, finish_reason: Value| {
+        serde_json::json!({
+            "id": response["id"],
+            "object": "chat.completion.chunk",
+            "created": response["created"],
+            "model": response["model"],
+            "choices": [{
+                "index": 0,
+                "delta": delta,
+                "finish_reason": finish_reason
+            }]
+        })
+    };
+
+    let content = chunk(
+        serde_json::json!({
+            "role": "assistant",
+            "content": choice["message"]["content"]
+        }),
+        Value::Null,
+    );
+    let mut finish = chunk(
+        serde_json::json!({}),
+        match &choice["finish_reason"] {
+            Value::Null => Value::from("stop"),
+            reason => reason.clone(),
+        },
+    );
+    if let Some(usage) = response.get("usage") {
+        finish["usage"] = usage.clone();
+    }
+    format!("data: {}\n\ndata: {}\n\ndata: [DONE]\n\n", content, finish)
+}
+
+/// 从模型输出中提取 JSON 文本（去除 markdown 代码块和前后说明文字）
+pub fn extract_json_text(content: &str) -> &str {
+    let trimmed = content.trim();
+    if let Some(rest) = trimmed.strip_prefix("```") {
+        let rest = rest.strip_prefix("json").unwrap_or(rest);
+        if let Some(end) = rest.rfind("```") {
+            return rest[..end].trim();
+        }
+    }
+    let start = trimmed.find(['{', '[']);
+    let end = trimmed.rfind(['}', ']']);
+    match (start, end) {
+        (Some(s), Some(e)) if s < e => &trimmed[s..=e],
+        _ => trimmed,
+    }
+}
+
+/// 校验模型输出，成功时返回解析后的 JSON
+pub fn validate_output(content: &str, format: &ResponseFormat) -> Result<Value, Vec<String>> {
+    let text = extract_json_text(content);
+    let value: Value =
+        serde_json::from_str(text).map_err(|e| vec![format!("output is not valid JSON: {e}")])?;
+    let errors = match format {
+        ResponseFormat::Text => Vec::new(),
+        ResponseFormat::JsonObject if !value.is_object() => {
+            vec!["$: expected a JSON object".to_string()]
+        }
+        ResponseFormat::JsonObject => Vec::new(),
+        ResponseFormat::JsonSchema { schema, .. } => validate_schema(&value, schema),
+    };
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(errors)
+    }
+}
+
+/// 按 JSON Schema 子集校验值，返回错误列表（带 JSON 路径）
+///
+/// 支持 type、enum、const、properties、required、additionalProperties、items、
+/// min/maxItems、min/maxLength、minimum/maximum、anyOf/oneOf
+pub fn validate_schema(value: &Value, schema: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at(value, schema, "$", &mut errors);
+    errors
+}
+
+fn type_matches(value: &Value, ty: &str) -> bool {
+    match ty {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn validate_at(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+
+    if let Some(ty) = schema.get("type") {
+        let types: Vec<&str> = match ty {
+            Value::String(s) => vec![s.as_str()],
+            Value::Array(arr) => arr.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| type_matches(value, t)) {
+            errors.push(format!("{path}: expected type {}", types.join(" | ")));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !allowed.contains(value) {
+            errors.push(format!(
+                "{path}: value is not one of the allowed enum values"
+            ));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            errors.push(format!(
+                "{path}: value does not equal the required constant"
+            ));
+        }
+    }
+
+    for key in ["anyOf", "oneOf"] {
+        if let Some(options) = schema.get(key).and_then(|o| o.as_array()) {
+            let matched = options
+                .iter()
+                .filter(|option| validate_schema(value, option).is_empty())
+                .count();
+            let ok = if key == "oneOf" {
+                matched == 1
+            } else {
+                matched >= 1
+            };
+            if !ok {
+                errors.push(format!("{path}: value does not match {key} alternatives"));
+            }
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            let properties = schema.get("properties").and_then(|p| p.as_object());
+            if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+                for key in required.iter().filter_map(|k| k.as_str()) {
+                    if !map.contains_key(key) {
+                        errors.push(format!("{path}: missing required property '{key}'"));
+                    }
+                }
+            }
+            for (key, child) in map {
+                match properties.and_then(|p| p.get(key)) {
+                    Some(child_schema) => {
+                        validate_at(child, child_schema, &format!("{path}.{key}"), errors)
+                    }
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            errors.push(format!("{path}: unexpected property '{key}'"))
+                        }
+                        Some(extra @ Value::Object(_)) => {
+                            validate_at(child, extra, &format!("{path}.{key}"), errors)
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(|m| m.as_u64()) {
+                if (items.len() as u64) < min {
+                    errors.push(format!("{path}: expected at least {min} items"));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(|m| m.as_u64()) {
+                if items.len() as u64 > max {
+                    errors.push(format!("{path}: expected at most {max} items"));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_at(item, item_schema, &format!("{path}[{i}]"), errors);
+                }
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(|m| m.as_u64()) {
+                if len < min {
+                    errors.push(format!("{path}: string shorter than {min} characters"));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(|m| m.as_u64()) {
+                if len > max {
+                    errors.push(format!("{path}: string longer than {max} characters"));
+                }
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(|m| m.as_f64()) {
+                if n < min {
+                    errors.push(format!("{path}: value is less than minimum {min}"));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(|m| m.as_f64()) {
+                if n > max {
+                    errors.push(format!("{path}: value is greater than maximum {max}"));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn person_format() -> ResponseFormat {
+        ResponseFormat::from_value(&json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": "person",
+                "strict": true,
+                "schema": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string", "minLength": 1},
+                        "age": {"type": "integer", "minimum": 0},
+                        "tags": {"type": "array", "items": {"type": "string"}}
+                    },
+                    "required": ["name", "age"],
+                    "additionalProperties": false
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    fn empty_request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "claude-sonnet-4-5".to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: Some(MessageContent::Text("extract".to_string())),
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            temperature: None,
+            max_tokens: None,
+            stream: false,
+            tools: None,
+            tool_choice: None,
+            response_format: Some(json!({"type": "json_object"})),
+        }
+    }
+
+    #[test]
+    fn test_parse_response_format() {
+        let format = person_format();
+        assert_eq!(format.name(), "person");
+        assert!(format.is_json());
+        assert_eq!(
+            ResponseFormat::from_value(&json!({"type": "text"})),
+            Some(ResponseFormat::Text)
+        );
+        assert_eq!(ResponseFormat::from_value(&json!({"type": "xml"})), None);
+    }
+
+    #[test]
+    fn test_validate_output_accepts_fenced_json() {
+        let output = "Here you go:\n```json\n{\"name\": \"Ada\", \"age\": 36}\n```";
+        let value = validate_output(output, &person_format()).unwrap();
+        assert_eq!(value["name"], "Ada");
+    }
+
+    #[test]
+    fn test_validate_output_reports_paths() {
+        let errors = validate_output(
+            r#"{"age": -1, "tags": [1], "extra": true}"#,
+            &person_format(),
+        )
+        .unwrap_err();
+        assert!(errors.contains(&"$: missing required property 'name'".to_string()));
+        assert!(errors.contains(&"$.age: value is less than minimum 0".to_string()));
+        assert!(errors.contains(&"$.tags[0]: expected type string".to_string()));
+        assert!(errors.contains(&"$: unexpected property 'extra'".to_string()));
+        assert!(validate_output("not json", &person_format()).is_err());
+    }
+
+    #[test]
+    fn test_apply_prompt_fallback() {
+        let mut request = empty_request();
+        apply_prompt_fallback(&mut request, &person_format());
+        assert!(request.response_format.is_none());
+        assert_eq!(request.messages[0].role, "system");
+        assert!(request.messages[0]
+            .get_content_text()
+            .contains("\"person\""));
+
+        append_retry_messages(&mut request, "oops", &["$: bad".to_string()]);
+        assert_eq!(request.messages.len(), 4);
+        assert_eq!(request.messages[2].role, "assistant");
+    }
+
+    #[test]
+    fn test_gemini_response_schema_strips_unsupported_keys() {
+        let schema = gemini_response_schema(&person_format().schema()).unwrap();
+        assert!(schema.get("additionalProperties").is_none());
+        assert_eq!(schema["properties"]["name"]["type"], "string");
+    }
+
+    #[test]
+    fn test_gemini_response_schema_keeps_properties_named_like_keywords() {
+        let schema = gemini_response_schema(&json!({
+            "title": "Book",
+            "type": "object",
+            "properties": {
+                "title": {"type": "string", "title": "Title"},
+                "$id": {"type": "string"},
+                "author": {
+                    "type": "object",
+                    "additionalProperties": false,
+                    "properties": {"title": {"type": "string"}}
+                }
+            },
+            "required": ["title", "$id"]
+        }))
+        .unwrap();
+        assert!(schema.get("title").is_none());
+        assert_eq!(schema["properties"]["title"], json!({"type": "string"}));
+        assert_eq!(schema["properties"]["$id"]["type"], "string");
+        assert!(schema["properties"]["author"]
+            .get("additionalProperties")
+            .is_none());
+        assert_eq!(
+            schema["properties"]["author"]["properties"]["title"]["type"],
+            "string"
+        );
+        assert_eq!(schema["required"], json!(["title", "$id"]));
+    }
+
+    #[test]
+    fn test_gemini_response_schema_inlines_refs() {
+        let schema = gemini_response_schema(&json!({
+            "type": "object",
+            "properties": {
+                "home": {"$ref": "#/$defs/address", "description": "Home address"},
+                "work": {"$ref": "#/definitions/address"},
+                "past": {"type": "array", "items": {"$ref": "#/$defs/address"}}
+            },
+            "$defs": {
+                "address": {
+                    "type": "object",
+                    "properties": {"city": {"$ref": "#/$defs/city"}},
+                    "additionalProperties": false
+                },
+                "city": {"type": "string", "title": "City"}
+            },
+            "definitions": {"address": {"$ref": "#/$defs/address"}}
+        }))
+        .unwrap();
+        let address = json!({
+            "type": "object",
+            "properties": {"city": {"type": "string"}}
+        });
+        assert!(schema.get("$defs").is_none());
+        assert!(schema.get("definitions").is_none());
+        assert_eq!(schema["properties"]["work"], address);
+        assert_eq!(schema["properties"]["past"]["items"], address);
+        assert_eq!(schema["properties"]["home"]["description"], "Home address");
+        assert_eq!(
+            schema["properties"]["home"]["properties"]["city"]["type"],
+            "string"
+        );
+        assert!(!schema.to_string().contains("$ref"));
+    }
+
+    #[test]
+    fn test_gemini_response_schema_rejects_unresolvable_refs() {
+        let recursive = json!({
+            "$ref": "#/$defs/node",
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": {"children": {"type": "array", "items": {"$ref": "#/$defs/node"}}}
+                }
+            }
+        });
+        let missing = json!({"properties": {"a": {"$ref": "#/$defs/missing"}}});
+        let external = json!({"properties": {"a": {"$ref": "https://example.com/a.json"}}});
+        for schema in [recursive, missing, external] {
+            let error = gemini_response_schema(&schema).unwrap_err();
+            assert!(matches!(error, StructuredOutputError::UnsupportedSchema(_)));
+            assert_eq!(error.status_code(), 400);
+        }
+    }
+
+    #[test]
+    fn test_completion_to_sse() {
+        let sse = completion_to_sse(&json!({
+            "id": "chatcmpl-1",
+            "created": 1,
+            "model": "claude-sonnet-4",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "{\"name\":\"Ann\"}"},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 3, "completion_tokens": 4, "total_tokens": 7}
+        }));
+        let events: Vec<&str> = sse
+            .split("\n\n")
+            .filter_map(|event| event.strip_prefix("data: "))
+            .collect();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[2], "[DONE]");
+
+        let first: Value = serde_json::from_str(events[0]).unwrap();
+        assert_eq!(first["object"], "chat.completion.chunk");
+        assert_eq!(
+            first["choices"][0]["delta"]["content"],
+            "{\"name\":\"Ann\"}"
+        );
+        let last: Value = serde_json::from_str(events[1]).unwrap();
+        assert_eq!(last["choices"][0]["finish_reason"], "stop");
+        assert_eq!(last["usage"]["total_tokens"], 7);
+    }
+
+    #[test]
+    fn test_anthropic_forced_tool() {
+        let (tools, choice) = anthropic_forced_tool(None, &person_format());
+        assert_eq!(tools.as_array().unwrap().len(), 1);
+        assert_eq!(tools[0]["name"], "person");
+        assert_eq!(choice, json!({"type": "tool", "name": "person"}));
+    }
+
+    #[test]
+    fn test_anthropic_forced_tool_keeps_caller_tools() {
+        let caller: Vec<Tool> = serde_json::from_value(json!([{
+            "type": "function",
+            "function": {
+                "name": "lookup",
+                "description": "Look up a person",
+                "parameters": {"type": "object", "properties": {"id": {"type": "string"}}}
+            }
+        }]))
+        .unwrap();
+        let (tools, choice) = anthropic_forced_tool(Some(&caller), &person_format());
+        assert_eq!(tools[0]["name"], "lookup");
+        assert_eq!(
+            tools[0]["input_schema"]["properties"]["id"]["type"],
+            "string"
+        );
+        assert_eq!(tools[1]["name"], "person");
+        assert_eq!(choice["name"], "person");
+    }
+}
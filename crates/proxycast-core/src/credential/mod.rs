@@ -8,6 +8,7 @@ mod pool;
 mod quota;
 mod sync;
 mod types;
+mod usage_quota;
 
 pub use balancer::{BalanceStrategy, CooldownInfo, CredentialSelection, LoadBalancer};
 pub use health::{HealthCheckConfig, HealthCheckResult, HealthChecker, HealthStatus};
@@ -18,6 +19,10 @@ pub use quota::{
 };
 pub use sync::{CredentialSyncService, SyncError};
 pub use types::{Credential, CredentialData, CredentialStats, CredentialStatus};
+pub use usage_quota::{
+    KiroUsageQuotaProvider, QuotaForecast, UsageQuotaProvider, UsageQuotaTracker, UsageSnapshot,
+    LOW_QUOTA_COOLDOWN_REASON,
+};
 
 #[cfg(test)]
 mod tests;
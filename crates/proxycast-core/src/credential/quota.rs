@@ -4,6 +4,8 @@
 
 use crate::config::QuotaExceededConfig;
 use crate::resilience::{QUOTA_EXCEEDED_KEYWORDS, QUOTA_EXCEEDED_STATUS_CODES};
+use crate::services::event_bus::{EventBus, SystemEvent};
+use crate::services::shared_state::SharedState;
 use chrono::{DateTime, Duration, Utc};
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
@@ -35,17 +37,163 @@ pub struct QuotaManager {
     config: QuotaExceededConfig,
     /// 超限凭证记录（credential_id -> record）
     exceeded_credentials: DashMap<String, QuotaExceededRecord>,
+    /// 事件总线（发布冷却开始/解除）
+    event_bus: std::sync::RwLock<Option<Arc<EventBus>>>,
+    /// 多实例共享状态（写入冷却记录并定期同步其他副本的记录）
+    shared_state: std::sync::RwLock<Option<Arc<SharedState>>>,
 }
 
+/// 本地新写入、尚未同步到共享后端的记录在同步时保留的时长（秒）
+const SHARED_SYNC_GRACE_SECS: i64 = 10;
+
 impl QuotaManager {
     /// 创建新的配额管理器
     pub fn new(config: QuotaExceededConfig) -> Self {
         Self {
             config,
             exceeded_credentials: DashMap::new(),
+            event_bus: std::sync::RwLock::new(None),
+            shared_state: std::sync::RwLock::new(None),
+        }
+    }
+
+    /// 设置事件总线（传入 None 时停止发布）
+    pub fn set_event_bus(&self, event_bus: Option<Arc<EventBus>>) {
+        if let Ok(mut guard) = self.event_bus.write() {
+            *guard = event_bus;
+        }
+    }
+
+    /// 设置多实例共享状态（传入 None 时只维护进程内记录）
+    pub fn set_shared_state(&self, shared: Option<Arc<SharedState>>) {
+        if let Ok(mut guard) = self.shared_state.write() {
+            *guard = shared;
+        }
+    }
+
+    fn shared_state(&self) -> Option<Arc<SharedState>> {
+        self.shared_state
+            .read()
+            .ok()
+            .and_then(|guard| guard.clone())
+    }
+
+    /// 在后台把冷却记录写入共享后端
+    fn share_record(&self, record: &QuotaExceededRecord) {
+        let (Some(shared), Ok(handle)) =
+            (self.shared_state(), tokio::runtime::Handle::try_current())
+        else {
+            return;
+        };
+        let record = record.clone();
+        handle.spawn(async move {
+            if let Err(e) = shared.put_cooldown(&record).await {
+                tracing::warn!(
+                    credential_id = %record.credential_id,
+                    "写入共享冷却记录失败: {}",
+                    e
+                );
+            }
+        });
+    }
+
+    /// 在后台从共享后端移除冷却记录
+    fn unshare_record(&self, credential_id: &str) {
+        let (Some(shared), Ok(handle)) =
+            (self.shared_state(), tokio::runtime::Handle::try_current())
+        else {
+            return;
+        };
+        let credential_id = credential_id.to_string();
+        handle.spawn(async move {
+            if let Err(e) = shared.clear_cooldown(&credential_id).await {
+                tracing::warn!(credential_id = %credential_id, "移除共享冷却记录失败: {}", e);
+            }
+        });
+    }
+
+    /// 与共享后端同步冷却记录
+    ///
+    /// - 其他副本写入的冷却记录合并到本地（冷却结束时间取较晚者）
+    /// - 共享后端中已不存在的本地记录（被其他副本手动恢复）从本地移除，
+    ///   刚写入、可能尚未落到后端的记录除外
+    ///
+    /// 返回本地记录发生变化的数量；未配置共享状态时返回 0。
+    pub async fn sync_shared(&self) -> usize {
+        let Some(shared) = self.shared_state() else {
+            return 0;
+        };
+        let records = match shared.cooldowns().await {
+            Ok(records) => records,
+            Err(e) => {
+                tracing::warn!("同步共享冷却记录失败: {}", e);
+                return 0;
+            }
+        };
+
+        let mut changed = 0;
+        for record in &records {
+            let newer = self
+                .exceeded_credentials
+                .get(&record.credential_id)
+                .is_none_or(|local| local.cooldown_until < record.cooldown_until);
+            if newer {
+                self.insert_record(record);
+                changed += 1;
+            }
+        }
+
+        let grace_cutoff = Utc::now() - Duration::seconds(SHARED_SYNC_GRACE_SECS);
+        let cleared: Vec<String> = self
+            .exceeded_credentials
+            .iter()
+            .filter(|local| local.exceeded_at < grace_cutoff)
+            .filter(|local| {
+                !records
+                    .iter()
+                    .any(|record| record.credential_id == local.credential_id)
+            })
+            .map(|local| local.credential_id.clone())
+            .collect();
+        for credential_id in cleared {
+            if self.remove_record(&credential_id) {
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    fn publish(&self, event: SystemEvent) {
+        if let Some(bus) = self.event_bus.read().ok().and_then(|guard| guard.clone()) {
+            bus.publish(event);
+        }
+    }
+
+    /// 写入冷却记录，凭证从可用进入冷却时发布事件
+    fn insert_record(&self, record: &QuotaExceededRecord) {
+        let was_available = self.is_available(&record.credential_id);
+        self.exceeded_credentials
+            .insert(record.credential_id.clone(), record.clone());
+        if was_available && record.cooldown_until > Utc::now() {
+            self.publish(SystemEvent::QuotaCooldownStarted {
+                credential_id: record.credential_id.clone(),
+                cooldown_until: record.cooldown_until,
+                reason: record.reason.clone(),
+            });
         }
     }
 
+    /// 移除冷却记录并发布解除事件
+    fn remove_record(&self, credential_id: &str) -> bool {
+        let removed = self.exceeded_credentials.remove(credential_id).is_some();
+        if removed {
+            self.publish(SystemEvent::QuotaCooldownCleared {
+                credential_id: credential_id.to_string(),
+            });
+        }
+        removed
+    }
+
     /// 使用默认配置创建配额管理器
     pub fn with_defaults() -> Self {
         Self::new(QuotaExceededConfig::default())
@@ -85,8 +233,8 @@ impl QuotaManager {
             reason: reason.to_string(),
         };
 
-        self.exceeded_credentials
-            .insert(credential_id.to_string(), record.clone());
+        self.insert_record(&record);
+        self.share_record(&record);
 
         tracing::info!(
             credential_id = %credential_id,
@@ -113,7 +261,7 @@ impl QuotaManager {
                 if now >= record.cooldown_until {
                     // 冷却期已过，移除记录
                     drop(record); // 释放读锁
-                    self.exceeded_credentials.remove(credential_id);
+                    self.remove_record(credential_id);
                     true
                 } else {
                     false
@@ -162,7 +310,7 @@ impl QuotaManager {
 
         // 移除过期记录
         for id in expired_ids {
-            self.exceeded_credentials.remove(&id);
+            self.remove_record(&id);
             cleaned += 1;
             tracing::debug!(credential_id = %id, "凭证冷却期已过，已恢复可用");
         }
@@ -176,6 +324,8 @@ impl QuotaManager {
 
     /// 手动恢复凭证（移除冷却状态）
     ///
+    /// 配置共享状态时同时从共享后端移除，其他副本在下次同步时恢复该凭证。
+    ///
     /// # 参数
     /// - `credential_id`: 凭证 ID
     ///
@@ -183,7 +333,39 @@ impl QuotaManager {
     /// - `true`: 成功移除冷却状态
     /// - `false`: 凭证未处于冷却期
     pub fn restore_credential(&self, credential_id: &str) -> bool {
-        self.exceeded_credentials.remove(credential_id).is_some()
+        self.unshare_record(credential_id);
+        self.remove_record(credential_id)
+    }
+
+    /// 手动设置凭证的冷却期（覆盖已有记录）
+    ///
+    /// # 参数
+    /// - `credential_id`: 凭证 ID
+    /// - `until`: 冷却结束时间
+    /// - `reason`: 冷却原因
+    pub fn override_cooldown(
+        &self,
+        credential_id: &str,
+        until: DateTime<Utc>,
+        reason: &str,
+    ) -> QuotaExceededRecord {
+        let record = QuotaExceededRecord {
+            credential_id: credential_id.to_string(),
+            exceeded_at: Utc::now(),
+            cooldown_until: until,
+            reason: reason.to_string(),
+        };
+        self.insert_record(&record);
+        self.share_record(&record);
+
+        tracing::info!(
+            credential_id = %credential_id,
+            cooldown_until = %until,
+            reason = %reason,
+            "凭证冷却期已手动设置"
+        );
+
+        record
     }
 
     /// 获取所有处于冷却期的凭证 ID
@@ -1138,4 +1320,46 @@ mod unit_tests {
         assert!(retry_value > 0);
         assert!(retry_value <= 300);
     }
+
+    #[tokio::test]
+    async fn test_cooldowns_sync_between_replicas() {
+        use crate::config::SharedStateConfig;
+        use crate::services::shared_state::{MemoryBackend, SharedStateBackend};
+
+        let backend: Arc<dyn SharedStateBackend> = Arc::new(MemoryBackend::new());
+        let shared_config = SharedStateConfig::default();
+        let replica_a = QuotaManager::with_defaults();
+        let replica_b = QuotaManager::with_defaults();
+        replica_a.set_shared_state(Some(Arc::new(SharedState::new(
+            backend.clone(),
+            &shared_config,
+        ))));
+        replica_b.set_shared_state(Some(Arc::new(SharedState::new(backend, &shared_config))));
+
+        replica_a.mark_quota_exceeded("cred-1", "429");
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert_eq!(replica_b.sync_shared().await, 1);
+        assert!(!replica_b.is_available("cred-1"));
+        // 已同步的记录不重复计入
+        assert_eq!(replica_b.sync_shared().await, 0);
+
+        // A 手动恢复后，B 上较早的记录在同步时被移除
+        replica_a.restore_credential("cred-1");
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let mut stale = replica_b.get_record("cred-1").unwrap();
+        stale.exceeded_at = Utc::now() - Duration::seconds(SHARED_SYNC_GRACE_SECS + 1);
+        replica_b.insert_record(&stale);
+        assert_eq!(replica_b.sync_shared().await, 1);
+        assert!(replica_b.is_available("cred-1"));
+
+        // 刚写入的本地记录在宽限期内保留
+        replica_b.insert_record(&QuotaExceededRecord {
+            credential_id: "cred-2".to_string(),
+            exceeded_at: Utc::now(),
+            cooldown_until: Utc::now() + Duration::seconds(60),
+            reason: "local".to_string(),
+        });
+        assert_eq!(replica_b.sync_shared().await, 0);
+        assert!(!replica_b.is_available("cred-2"));
+    }
 }
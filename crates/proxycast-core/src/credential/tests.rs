@@ -1927,3 +1927,49 @@ proptest! {
         }
     }
 }
+
+#[test]
+fn test_quota_override_cooldown() {
+    let manager = QuotaManager::with_defaults();
+    let until = chrono::Utc::now() + chrono::Duration::minutes(30);
+
+    let record = manager.override_cooldown("cred-1", until, "manual");
+    assert_eq!(record.cooldown_until, until);
+    assert!(!manager.is_available("cred-1"));
+    assert_eq!(manager.get_cooldown_until("cred-1"), Some(until));
+
+    // 设置为过去的时间等同于解除冷却
+    manager.override_cooldown(
+        "cred-1",
+        chrono::Utc::now() - chrono::Duration::seconds(1),
+        "manual",
+    );
+    assert!(manager.is_available("cred-1"));
+}
+
+#[test]
+fn test_quota_manager_publishes_cooldown_events() {
+    use crate::services::event_bus::{EventBus, SystemEvent, TopicFilter};
+
+    let bus = Arc::new(EventBus::new(16));
+    let manager = QuotaManager::with_defaults();
+    manager.set_event_bus(Some(bus.clone()));
+
+    manager.mark_quota_exceeded("cred-1", "429");
+    // 已在冷却期内，重复标记不再发布
+    manager.mark_quota_exceeded("cred-1", "429");
+    assert!(manager.restore_credential("cred-1"));
+    assert!(!manager.restore_credential("cred-1"));
+
+    let events = bus.subscribe_after(Some(0), &TopicFilter::all()).replay;
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        &events[0].event,
+        SystemEvent::QuotaCooldownStarted { credential_id, reason, .. }
+            if credential_id == "cred-1" && reason == "429"
+    ));
+    assert!(matches!(
+        &events[1].event,
+        SystemEvent::QuotaCooldownCleared { credential_id } if credential_id == "cred-1"
+    ));
+}
@@ -0,0 +1,682 @@
+//! 上游用量配额跟踪
+//!
+//! 定期查询 Provider 暴露的用量接口（目前为 Kiro 的 getUsageLimits），用于：
+//! - 按剩余配额挑选凭证（最多剩余 / 按重置时间平均消耗）
+//! - 剩余比例低于阈值时预先冷却凭证
+//! - 预测凭证配额的耗尽时间
+
+use super::QuotaManager;
+use crate::config::{QuotaSelectionStrategy, UsageQuotaConfig};
+use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::database::DbConnection;
+use crate::models::provider_pool_model::{CredentialData, PoolProviderType, ProviderCredential};
+use crate::providers::kiro::{
+    generate_machine_id_from_credentials, get_kiro_version, KiroProvider,
+};
+use crate::services::provider_pool_service::CredentialSelector;
+use crate::services::token_cache_service::TokenCacheService;
+use crate::services::usage_service::get_usage_limits;
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tokio::task::JoinHandle;
+
+/// 因剩余配额不足而预冷却时使用的原因
+pub const LOW_QUOTA_COOLDOWN_REASON: &str = "usage quota below threshold";
+
+/// 一次用量查询结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UsageSnapshot {
+    /// 总额度
+    pub usage_limit: f64,
+    /// 已使用
+    pub current_usage: f64,
+    /// 下次重置时间
+    pub reset_at: Option<DateTime<Utc>>,
+    /// 查询时间
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl UsageSnapshot {
+    /// 剩余额度
+    pub fn remaining(&self) -> f64 {
+        (self.usage_limit - self.current_usage).max(0.0)
+    }
+
+    /// 剩余比例（总额度为 0 时为 None）
+    pub fn remaining_ratio(&self) -> Option<f64> {
+        (self.usage_limit > 0.0).then(|| self.remaining() / self.usage_limit)
+    }
+}
+
+/// 用量查询接口
+///
+/// 为新的 Provider 实现该 trait 后通过 [`UsageQuotaTracker::with_provider`] 注册
+#[async_trait]
+pub trait UsageQuotaProvider: Send + Sync {
+    /// 查询凭证当前用量，`access_token` 为令牌缓存中的有效 Token（如有）
+    async fn fetch(
+        &self,
+        credential: &ProviderCredential,
+        access_token: Option<&str>,
+    ) -> Result<UsageSnapshot, String>;
+}
+
+/// Kiro 用量查询（AWS Q getUsageLimits）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KiroUsageQuotaProvider;
+
+#[async_trait]
+impl UsageQuotaProvider for KiroUsageQuotaProvider {
+    async fn fetch(
+        &self,
+        credential: &ProviderCredential,
+        access_token: Option<&str>,
+    ) -> Result<UsageSnapshot, String> {
+        let CredentialData::KiroOAuth { creds_file_path } = &credential.credential else {
+            return Err("不是 Kiro OAuth 凭证".to_string());
+        };
+
+        let mut kiro = KiroProvider::new();
+        kiro.load_credentials_from_path(creds_file_path)
+            .await
+            .map_err(|e| format!("加载凭证失败: {}", e))?;
+
+        let token = access_token
+            .map(str::to_string)
+            .or_else(|| kiro.credentials.access_token.clone())
+            .ok_or_else(|| "缺少 accessToken".to_string())?;
+        let profile_arn = kiro.credentials.profile_arn.clone();
+        let machine_id = generate_machine_id_from_credentials(
+            profile_arn.as_deref(),
+            kiro.credentials.client_id.as_deref(),
+        );
+
+        let info = get_usage_limits(
+            &token,
+            &kiro.detect_auth_method(),
+            profile_arn.as_deref(),
+            &machine_id,
+            &get_kiro_version(),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+        Ok(UsageSnapshot {
+            usage_limit: info.usage_limit,
+            current_usage: info.current_usage,
+            reset_at: info
+                .next_reset
+                .and_then(|ts| DateTime::from_timestamp(ts, 0)),
+            fetched_at: Utc::now(),
+        })
+    }
+}
+
+/// 凭证剩余配额预测
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaForecast {
+    /// 凭证 UUID
+    pub credential_id: String,
+    /// Provider 类型
+    pub provider_type: String,
+    /// 总额度
+    pub usage_limit: Option<f64>,
+    /// 最近一次查询时的已使用量
+    pub current_usage: Option<f64>,
+    /// 最近一次查询时的剩余额度
+    pub remaining: Option<f64>,
+    /// 剩余比例
+    pub remaining_ratio: Option<f64>,
+    /// 扣除查询后本地已分配请求的预估剩余额度
+    pub estimated_remaining: Option<f64>,
+    /// 近两次查询之间的消耗速率（每小时）
+    pub burn_rate_per_hour: Option<f64>,
+    /// 按当前速率预计耗尽时间
+    pub exhausted_at: Option<DateTime<Utc>>,
+    /// 下次重置时间（接口未提供时按下个自然月估算）
+    pub reset_at: Option<DateTime<Utc>>,
+    /// 是否会在重置前耗尽
+    pub exhausts_before_reset: bool,
+    /// 平均消耗到重置所允许的每小时用量
+    pub hourly_allowance: Option<f64>,
+    /// 是否处于配额冷却期
+    pub cooling_down: bool,
+    /// 最近一次查询时间
+    pub fetched_at: Option<DateTime<Utc>>,
+    /// 最近一次查询错误
+    pub last_error: Option<String>,
+}
+
+/// 单个凭证的用量记录
+#[derive(Debug, Clone)]
+struct UsageEntry {
+    provider_type: PoolProviderType,
+    latest: Option<UsageSnapshot>,
+    previous: Option<UsageSnapshot>,
+    /// 最近一次查询后本地分配的请求数
+    picks_since_fetch: u64,
+    /// 上一个查询区间内本地分配的请求数
+    picks_last_interval: u64,
+    last_error: Option<String>,
+}
+
+impl UsageEntry {
+    fn new(provider_type: PoolProviderType) -> Self {
+        Self {
+            provider_type,
+            latest: None,
+            previous: None,
+            picks_since_fetch: 0,
+            picks_last_interval: 0,
+            last_error: None,
+        }
+    }
+
+    /// 单次请求的平均消耗（没有足够数据时按 1 计）
+    fn cost_per_pick(&self) -> f64 {
+        match (&self.previous, &self.latest) {
+            (Some(prev), Some(latest)) if self.picks_last_interval > 0 => {
+                let delta = latest.current_usage - prev.current_usage;
+                if delta > 0.0 {
+                    delta / self.picks_last_interval as f64
+                } else {
+                    1.0
+                }
+            }
+            _ => 1.0,
+        }
+    }
+
+    fn estimated_remaining(&self) -> Option<f64> {
+        self.latest.as_ref().map(|s| {
+            (s.remaining() - self.picks_since_fetch as f64 * self.cost_per_pick()).max(0.0)
+        })
+    }
+
+    fn burn_rate_per_hour(&self) -> Option<f64> {
+        let (prev, latest) = (self.previous.as_ref()?, self.latest.as_ref()?);
+        let hours = (latest.fetched_at - prev.fetched_at).num_seconds() as f64 / 3600.0;
+        let delta = latest.current_usage - prev.current_usage;
+        // 用量下降说明两次查询之间发生了重置
+        (hours > 0.0 && delta >= 0.0).then(|| delta / hours)
+    }
+}
+
+/// 没有重置时间时按下个自然月的第一天估算
+fn fallback_reset(now: DateTime<Utc>) -> DateTime<Utc> {
+    let (year, month) = if now.month() == 12 {
+        (now.year() + 1, 1)
+    } else {
+        (now.year(), now.month() + 1)
+    };
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0)
+        .single()
+        .unwrap_or(now + Duration::days(30))
+}
+
+/// 上游用量配额跟踪器
+pub struct UsageQuotaTracker {
+    config: RwLock<UsageQuotaConfig>,
+    providers: HashMap<PoolProviderType, Arc<dyn UsageQuotaProvider>>,
+    entries: DashMap<String, UsageEntry>,
+    quota_manager: Arc<QuotaManager>,
+}
+
+impl std::fmt::Debug for UsageQuotaTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UsageQuotaTracker")
+            .field("config", &self.config())
+            .field("providers", &self.providers.keys().collect::<Vec<_>>())
+            .field("entries", &self.entries.len())
+            .finish()
+    }
+}
+
+impl UsageQuotaTracker {
+    /// 创建跟踪器并注册 Kiro 用量查询
+    pub fn new(config: UsageQuotaConfig, quota_manager: Arc<QuotaManager>) -> Self {
+        Self {
+            config: RwLock::new(config),
+            providers: HashMap::new(),
+            entries: DashMap::new(),
+            quota_manager,
+        }
+        .with_provider(PoolProviderType::Kiro, Arc::new(KiroUsageQuotaProvider))
+    }
+
+    /// 注册（或替换）指定 Provider 的用量查询
+    pub fn with_provider(
+        mut self,
+        provider_type: PoolProviderType,
+        provider: Arc<dyn UsageQuotaProvider>,
+    ) -> Self {
+        self.providers.insert(provider_type, provider);
+        self
+    }
+
+    /// 获取当前配置
+    pub fn config(&self) -> UsageQuotaConfig {
+        self.config.read().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    /// 更新配置
+    pub fn set_config(&self, config: UsageQuotaConfig) {
+        if let Ok(mut guard) = self.config.write() {
+            *guard = config;
+        }
+    }
+
+    /// 是否支持查询指定 Provider 的用量
+    pub fn supports(&self, provider_type: PoolProviderType) -> bool {
+        self.providers.contains_key(&provider_type)
+    }
+
+    /// 记录一次用量查询结果，并按阈值设置或解除预冷却
+    pub fn record(
+        &self,
+        credential_id: &str,
+        provider_type: PoolProviderType,
+        snapshot: UsageSnapshot,
+    ) {
+        let config = self.config();
+        let ratio = snapshot.remaining_ratio();
+        let reset_at = snapshot.reset_at;
+
+        {
+            let mut entry = self
+                .entries
+                .entry(credential_id.to_string())
+                .or_insert_with(|| UsageEntry::new(provider_type));
+            entry.provider_type = provider_type;
+            entry.previous = entry.latest.take();
+            entry.latest = Some(snapshot);
+            entry.picks_last_interval = entry.picks_since_fetch;
+            entry.picks_since_fetch = 0;
+            entry.last_error = None;
+        }
+
+        let low = config.min_remaining_ratio > 0.0
+            && ratio.is_some_and(|r| r < config.min_remaining_ratio);
+        if low {
+            let now = Utc::now();
+            let until = reset_at.filter(|t| *t > now).unwrap_or_else(|| {
+                now + Duration::seconds(config.poll_interval_secs.max(1) as i64)
+            });
+            self.quota_manager
+                .override_cooldown(credential_id, until, LOW_QUOTA_COOLDOWN_REASON);
+        } else if self
+            .quota_manager
+            .get_record(credential_id)
+            .is_some_and(|r| r.reason == LOW_QUOTA_COOLDOWN_REASON)
+        {
+            self.quota_manager.restore_credential(credential_id);
+        }
+    }
+
+    /// 记录一次失败的用量查询（保留上次的结果）
+    pub fn record_error(
+        &self,
+        credential_id: &str,
+        provider_type: PoolProviderType,
+        error: String,
+    ) {
+        self.entries
+            .entry(credential_id.to_string())
+            .or_insert_with(|| UsageEntry::new(provider_type))
+            .last_error = Some(error);
+    }
+
+    /// 移除凭证的用量记录
+    pub fn forget(&self, credential_id: &str) {
+        self.entries.remove(credential_id);
+    }
+
+    fn build_forecast(&self, credential_id: &str, entry: &UsageEntry) -> QuotaForecast {
+        let now = Utc::now();
+        let latest = entry.latest.as_ref();
+        let estimated_remaining = entry.estimated_remaining();
+        let burn_rate = entry.burn_rate_per_hour();
+        let reset_at = latest.map(|s| s.reset_at.unwrap_or_else(|| fallback_reset(now)));
+
+        let exhausted_at = match (estimated_remaining, burn_rate) {
+            (Some(remaining), Some(rate)) if rate > 0.0 => {
+                Some(now + Duration::seconds((remaining / rate * 3600.0) as i64))
+            }
+            _ => None,
+        };
+        let hourly_allowance = match (estimated_remaining, reset_at) {
+            (Some(remaining), Some(reset)) => {
+                let hours = ((reset - now).num_seconds() as f64 / 3600.0).max(1.0 / 60.0);
+                Some(remaining / hours)
+            }
+            _ => None,
+        };
+
+        QuotaForecast {
+            credential_id: credential_id.to_string(),
+            provider_type: entry.provider_type.to_string(),
+            usage_limit: latest.map(|s| s.usage_limit),
+            current_usage: latest.map(|s| s.current_usage),
+            remaining: latest.map(UsageSnapshot::remaining),
+            remaining_ratio: latest.and_then(UsageSnapshot::remaining_ratio),
+            estimated_remaining,
+            burn_rate_per_hour: burn_rate,
+            exhausted_at,
+            reset_at,
+            exhausts_before_reset: matches!(
+                (exhausted_at, reset_at),
+                (Some(exhausted), Some(reset)) if exhausted < reset
+            ),
+            hourly_allowance,
+            cooling_down: !self.quota_manager.is_available(credential_id),
+            fetched_at: latest.map(|s| s.fetched_at),
+            last_error: entry.last_error.clone(),
+        }
+    }
+
+    /// 获取单个凭证的配额预测
+    pub fn forecast(&self, credential_id: &str) -> Option<QuotaForecast> {
+        let entry = self.entries.get(credential_id)?.clone();
+        Some(self.build_forecast(credential_id, &entry))
+    }
+
+    /// 获取所有凭证的配额预测（按剩余比例升序）
+    pub fn forecasts(&self) -> Vec<QuotaForecast> {
+        let entries: Vec<(String, UsageEntry)> = self
+            .entries
+            .iter()
+            .map(|e| (e.key().clone(), e.value().clone()))
+            .collect();
+        let mut forecasts: Vec<_> = entries
+            .iter()
+            .map(|(id, entry)| self.build_forecast(id, entry))
+            .collect();
+        forecasts.sort_by(|a, b| {
+            a.remaining_ratio
+                .unwrap_or(f64::MAX)
+                .total_cmp(&b.remaining_ratio.unwrap_or(f64::MAX))
+        });
+        forecasts
+    }
+
+    /// 查询所有支持的、未禁用凭证的用量，返回成功查询的凭证数
+    pub async fn poll_once(&self, db: &DbConnection, token_cache: &TokenCacheService) -> usize {
+        let credentials = {
+            let conn = match db.lock() {
+                Ok(conn) => conn,
+                Err(e) => {
+                    tracing::warn!("[USAGE_QUOTA] 数据库锁定失败: {}", e);
+                    return 0;
+                }
+            };
+            match ProviderPoolDao::get_all(&conn) {
+                Ok(creds) => creds,
+                Err(e) => {
+                    tracing::warn!("[USAGE_QUOTA] 查询凭证失败: {}", e);
+                    return 0;
+                }
+            }
+        };
+
+        let mut polled = 0;
+        for credential in credentials.iter().filter(|c| !c.is_disabled) {
+            let Some(provider) = self.providers.get(&credential.provider_type) else {
+                continue;
+            };
+            let token = token_cache.get_valid_token(db, &credential.uuid).await.ok();
+            match provider.fetch(credential, token.as_deref()).await {
+                Ok(snapshot) => {
+                    tracing::debug!(
+                        "[USAGE_QUOTA] {} 剩余 {:.1}/{:.1}",
+                        credential.uuid,
+                        snapshot.remaining(),
+                        snapshot.usage_limit
+                    );
+                    self.record(&credential.uuid, credential.provider_type, snapshot);
+                    polled += 1;
+                }
+                Err(e) => {
+                    tracing::warn!("[USAGE_QUOTA] 查询 {} 用量失败: {}", credential.uuid, e);
+                    self.record_error(&credential.uuid, credential.provider_type, e);
+                }
+            }
+        }
+        polled
+    }
+
+    /// 启动后台轮询任务（未启用时只等待，配置可在运行时切换）
+    pub fn spawn(
+        self: Arc<Self>,
+        db: DbConnection,
+        token_cache: Arc<TokenCacheService>,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let config = self.config();
+                if config.enabled {
+                    let polled = self.poll_once(&db, &token_cache).await;
+                    tracing::debug!("[USAGE_QUOTA] 本轮查询 {} 个凭证", polled);
+                }
+                let interval = config.poll_interval_secs.max(10);
+                tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+            }
+        })
+    }
+}
+
+impl CredentialSelector for UsageQuotaTracker {
+    fn is_selectable(&self, credential: &ProviderCredential) -> bool {
+        !self.config().enabled || self.quota_manager.is_available(&credential.uuid)
+    }
+
+    fn pick(&self, candidates: &[ProviderCredential]) -> Option<usize> {
+        let config = self.config();
+        if !config.enabled || config.strategy == QuotaSelectionStrategy::Default {
+            return None;
+        }
+        let strategy = config.strategy;
+
+        let now = Utc::now();
+        let mut best: Option<(usize, f64)> = None;
+        for (index, credential) in candidates.iter().enumerate() {
+            let Some(entry) = self.entries.get(&credential.uuid) else {
+                continue;
+            };
+            let (Some(latest), Some(remaining)) = (&entry.latest, entry.estimated_remaining())
+            else {
+                continue;
+            };
+            let key = match strategy {
+                QuotaSelectionStrategy::DrainEvenly => {
+                    let reset = latest.reset_at.unwrap_or_else(|| fallback_reset(now));
+                    let hours = ((reset - now).num_seconds() as f64 / 3600.0).max(1.0 / 60.0);
+                    remaining / hours
+                }
+                _ => remaining,
+            };
+            if best.is_none_or(|(_, k)| key > k) {
+                best = Some((index, key));
+            }
+        }
+
+        let (index, _) = best?;
+        if let Some(mut entry) = self.entries.get_mut(&candidates[index].uuid) {
+            entry.picks_since_fetch += 1;
+        }
+        Some(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kiro_credential() -> ProviderCredential {
+        ProviderCredential::new(
+            PoolProviderType::Kiro,
+            CredentialData::KiroOAuth {
+                creds_file_path: "/tmp/kiro.json".to_string(),
+            },
+        )
+    }
+
+    fn snapshot(limit: f64, used: f64, reset_in_hours: Option<i64>) -> UsageSnapshot {
+        UsageSnapshot {
+            usage_limit: limit,
+            current_usage: used,
+            reset_at: reset_in_hours.map(|h| Utc::now() + Duration::hours(h)),
+            fetched_at: Utc::now(),
+        }
+    }
+
+    fn new_tracker(strategy: QuotaSelectionStrategy) -> UsageQuotaTracker {
+        UsageQuotaTracker::new(
+            UsageQuotaConfig {
+                enabled: true,
+                strategy,
+                ..Default::default()
+            },
+            Arc::new(QuotaManager::with_defaults()),
+        )
+    }
+
+    #[test]
+    fn test_most_remaining_prefers_emptier_account() {
+        let tracker = new_tracker(QuotaSelectionStrategy::MostRemaining);
+        let creds = vec![kiro_credential(), kiro_credential(), kiro_credential()];
+        tracker.record(
+            &creds[0].uuid,
+            PoolProviderType::Kiro,
+            snapshot(100.0, 98.0, None),
+        );
+        tracker.record(
+            &creds[1].uuid,
+            PoolProviderType::Kiro,
+            snapshot(100.0, 10.0, None),
+        );
+        // creds[2] 没有用量数据，不参与挑选
+
+        assert_eq!(tracker.pick(&creds), Some(1));
+    }
+
+    #[test]
+    fn test_local_picks_spread_load_between_polls() {
+        let tracker = new_tracker(QuotaSelectionStrategy::MostRemaining);
+        let creds = vec![kiro_credential(), kiro_credential()];
+        tracker.record(
+            &creds[0].uuid,
+            PoolProviderType::Kiro,
+            snapshot(100.0, 50.0, None),
+        );
+        tracker.record(
+            &creds[1].uuid,
+            PoolProviderType::Kiro,
+            snapshot(100.0, 48.0, None),
+        );
+
+        let picks: Vec<_> = (0..4).map(|_| tracker.pick(&creds).unwrap()).collect();
+        assert_eq!(picks, vec![1, 1, 0, 1]);
+    }
+
+    #[test]
+    fn test_drain_evenly_prefers_sooner_reset() {
+        let tracker = new_tracker(QuotaSelectionStrategy::DrainEvenly);
+        let creds = vec![kiro_credential(), kiro_credential()];
+        // 剩余更多但还有 20 天才重置
+        tracker.record(
+            &creds[0].uuid,
+            PoolProviderType::Kiro,
+            snapshot(100.0, 20.0, Some(480)),
+        );
+        // 剩余较少但 1 天后重置
+        tracker.record(
+            &creds[1].uuid,
+            PoolProviderType::Kiro,
+            snapshot(100.0, 60.0, Some(24)),
+        );
+
+        assert_eq!(tracker.pick(&creds), Some(1));
+        assert_eq!(
+            new_tracker(QuotaSelectionStrategy::Default).pick(&creds),
+            None
+        );
+    }
+
+    #[test]
+    fn test_low_quota_triggers_preemptive_cooldown() {
+        let tracker = new_tracker(QuotaSelectionStrategy::MostRemaining);
+        let cred = kiro_credential();
+
+        tracker.record(
+            &cred.uuid,
+            PoolProviderType::Kiro,
+            snapshot(100.0, 98.0, Some(10)),
+        );
+        assert!(!tracker.is_selectable(&cred));
+        let record = tracker.quota_manager.get_record(&cred.uuid).unwrap();
+        assert_eq!(record.reason, LOW_QUOTA_COOLDOWN_REASON);
+
+        // 重置后恢复
+        tracker.record(
+            &cred.uuid,
+            PoolProviderType::Kiro,
+            snapshot(100.0, 0.0, Some(720)),
+        );
+        assert!(tracker.is_selectable(&cred));
+
+        // 其他原因的冷却不会被用量恢复解除
+        tracker
+            .quota_manager
+            .mark_quota_exceeded(&cred.uuid, "429 from upstream");
+        tracker.record(
+            &cred.uuid,
+            PoolProviderType::Kiro,
+            snapshot(100.0, 1.0, Some(720)),
+        );
+        assert!(!tracker.is_selectable(&cred));
+    }
+
+    #[test]
+    fn test_forecast_burn_rate_and_exhaustion() {
+        let tracker = new_tracker(QuotaSelectionStrategy::MostRemaining);
+        let id = "cred-1";
+        let now = Utc::now();
+        tracker.record(
+            id,
+            PoolProviderType::Kiro,
+            UsageSnapshot {
+                usage_limit: 100.0,
+                current_usage: 40.0,
+                reset_at: Some(now + Duration::hours(100)),
+                fetched_at: now - Duration::hours(2),
+            },
+        );
+        tracker.record(
+            id,
+            PoolProviderType::Kiro,
+            UsageSnapshot {
+                usage_limit: 100.0,
+                current_usage: 60.0,
+                reset_at: Some(now + Duration::hours(100)),
+                fetched_at: now,
+            },
+        );
+
+        let forecast = tracker.forecast(id).unwrap();
+        assert_eq!(forecast.remaining, Some(40.0));
+        assert_eq!(forecast.burn_rate_per_hour, Some(10.0));
+        let exhausted = forecast.exhausted_at.unwrap();
+        assert!((exhausted - now - Duration::hours(4)).num_seconds().abs() < 5);
+        assert!(forecast.exhausts_before_reset);
+        assert!(forecast.hourly_allowance.unwrap() < 1.0);
+
+        tracker.record_error(id, PoolProviderType::Kiro, "timeout".to_string());
+        let forecast = tracker.forecasts().pop().unwrap();
+        assert_eq!(forecast.last_error.as_deref(), Some("timeout"));
+        assert_eq!(forecast.remaining, Some(40.0));
+    }
+}
@@ -0,0 +1,992 @@
+//! 对话谱系重建
+//!
+//! `SessionManager::detect_session` 基于时间窗口和客户端做启发式分组，经常把一次
+//! Agent 运行拆散或把多次运行合并。本模块按对话谱系把 Flow 连接成树：
+//! - 消息前缀哈希：后一次请求的消息列表是前一次请求的延伸
+//! - tool_use / tool_result ID 链：工具结果回指产生该调用的响应
+//! - Claude Code 会话标识（请求头或 `metadata.user_id`），不同会话之间不会连接
+//! - `/compact` 边界：新请求的首条用户消息包含此前某次响应生成的摘要
+//!
+//! 用户编辑后重试会从同一个父节点分出新的分支，并统计每个对话的 Token、费用、
+//! 延迟和工具调用总量。
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+use super::dataset::fnv1a;
+use super::models::{FlowState, LLMFlow, MessageRole};
+
+/// Claude Code 会话 ID 请求头
+pub const CLAUDE_CODE_SESSION_HEADER: &str = "x-claude-code-session-id";
+
+/// 识别摘要时要求响应内容的最少字符数，避免短回复误匹配
+const MIN_COMPACT_SUMMARY_CHARS: usize = 40;
+
+// ============================================================================
+// 费用
+// ============================================================================
+
+/// 单个模型的价格（美元 / 百万 Token）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    /// 输入价格
+    pub input_per_mtok: f64,
+    /// 输出价格
+    pub output_per_mtok: f64,
+}
+
+impl ModelPrice {
+    /// 创建价格
+    pub fn new(input_per_mtok: f64, output_per_mtok: f64) -> Self {
+        Self {
+            input_per_mtok,
+            output_per_mtok,
+        }
+    }
+
+    /// 计算费用
+    pub fn cost(&self, input_tokens: u32, output_tokens: u32) -> f64 {
+        (input_tokens as f64 * self.input_per_mtok + output_tokens as f64 * self.output_per_mtok)
+            / 1_000_000.0
+    }
+}
+
+/// 模型价格表
+///
+/// 键为模型名片段（不区分大小写），匹配时取最长的片段，
+/// 因此 `gpt-4o-mini` 不会被 `gpt-4o` 的价格覆盖
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelPricing {
+    /// 模型名片段 -> 价格
+    pub models: HashMap<String, ModelPrice>,
+}
+
+impl Default for ModelPricing {
+    fn default() -> Self {
+        let table = [
+            ("claude-opus-4", 15.0, 75.0),
+            ("claude-opus-4-5", 5.0, 25.0),
+            ("claude-sonnet-4", 3.0, 15.0),
+            ("claude-3-7-sonnet", 3.0, 15.0),
+            ("claude-3-5-sonnet", 3.0, 15.0),
+            ("claude-haiku-4-5", 1.0, 5.0),
+            ("claude-3-5-haiku", 0.8, 4.0),
+            ("gpt-4o", 2.5, 10.0),
+            ("gpt-4o-mini", 0.15, 0.6),
+            ("gpt-4.1", 2.0, 8.0),
+            ("gpt-4.1-mini", 0.4, 1.6),
+            ("gemini-2.5-pro", 1.25, 10.0),
+            ("gemini-2.5-flash", 0.3, 2.5),
+        ];
+        Self {
+            models: table
+                .into_iter()
+                .map(|(name, input, output)| (name.to_string(), ModelPrice::new(input, output)))
+                .collect(),
+        }
+    }
+}
+
+impl ModelPricing {
+    /// 空价格表
+    pub fn empty() -> Self {
+        Self {
+            models: HashMap::new(),
+        }
+    }
+
+    /// 设置（或覆盖）某个模型片段的价格
+    pub fn with_price(mut self, pattern: impl Into<String>, price: ModelPrice) -> Self {
+        self.models.insert(pattern.into().to_lowercase(), price);
+        self
+    }
+
+    /// 查找模型价格
+    pub fn price_for(&self, model: &str) -> Option<&ModelPrice> {
+        let model = model.to_lowercase();
+        self.models
+            .iter()
+            .filter(|(pattern, _)| model.contains(&pattern.to_lowercase()))
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map(|(_, price)| price)
+    }
+}
+
+/// 重建选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationOptions {
+    /// 价格表
+    #[serde(default)]
+    pub pricing: ModelPricing,
+    /// 识别 `/compact` 边界时最多回看的 Flow 数
+    #[serde(default = "default_compact_lookback")]
+    pub compact_lookback: usize,
+}
+
+fn default_compact_lookback() -> usize {
+    50
+}
+
+impl Default for ConversationOptions {
+    fn default() -> Self {
+        Self {
+            pricing: ModelPricing::default(),
+            compact_lookback: default_compact_lookback(),
+        }
+    }
+}
+
+// ============================================================================
+// 结果
+// ============================================================================
+
+/// 节点与父节点的连接方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversationLink {
+    /// 对话起点
+    Root,
+    /// 请求消息是父节点请求消息的延伸
+    Prefix,
+    /// 请求中的 tool_result 回指父节点响应中的 tool_use
+    ToolResult,
+    /// `/compact` 之后以父节点生成的摘要继续对话
+    Compact,
+}
+
+/// 对话树节点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationNode {
+    /// Flow ID
+    pub flow_id: String,
+    /// 父节点 Flow ID
+    pub parent_id: Option<String>,
+    /// 与父节点的连接方式
+    pub link: ConversationLink,
+    /// 深度（根节点为 0）
+    pub depth: usize,
+    /// 模型名称
+    pub model: String,
+    /// 请求开始时间
+    pub created_at: DateTime<Utc>,
+    /// 请求消息数
+    pub message_count: usize,
+    /// 输入 Token 数
+    pub input_tokens: u32,
+    /// 输出 Token 数
+    pub output_tokens: u32,
+    /// 延迟（毫秒）
+    pub latency_ms: u64,
+    /// 响应中的工具调用数
+    pub tool_calls: usize,
+    /// 费用（美元），模型不在价格表中时为 None
+    pub cost_usd: Option<f64>,
+    /// 是否失败
+    pub error: bool,
+    /// 子节点（按时间排序，多于一个即为分支）
+    pub children: Vec<ConversationNode>,
+}
+
+/// 对话汇总
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConversationTotals {
+    /// Flow 数
+    pub flows: usize,
+    /// 输入 Token 总数
+    pub input_tokens: u64,
+    /// 输出 Token 总数
+    pub output_tokens: u64,
+    /// Token 总数
+    pub total_tokens: u64,
+    /// 总费用（美元，不含未定价的 Flow）
+    pub cost_usd: f64,
+    /// 未定价的 Flow 数
+    pub unpriced_flows: usize,
+    /// 总延迟（毫秒）
+    pub latency_ms: u64,
+    /// 工具调用总数
+    pub tool_calls: usize,
+    /// 失败的 Flow 数
+    pub errors: usize,
+    /// 分支数（每个节点多出的子节点数之和）
+    pub branches: usize,
+    /// `/compact` 边界数
+    pub compactions: usize,
+    /// 最大深度
+    pub max_depth: usize,
+}
+
+/// 重建出的对话
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    /// 对话 ID（根节点 Flow ID）
+    pub id: String,
+    /// Claude Code 会话 ID
+    pub session_key: Option<String>,
+    /// 开始时间
+    pub started_at: DateTime<Utc>,
+    /// 结束时间
+    pub ended_at: DateTime<Utc>,
+    /// 使用过的模型
+    pub models: Vec<String>,
+    /// 汇总
+    pub totals: ConversationTotals,
+    /// 根节点
+    pub root: ConversationNode,
+}
+
+impl Conversation {
+    /// 按深度优先顺序列出所有 Flow ID
+    pub fn flow_ids(&self) -> Vec<String> {
+        let mut ids = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            ids.push(node.flow_id.clone());
+            stack.extend(node.children.iter().rev());
+        }
+        ids
+    }
+
+    /// 是否包含指定 Flow
+    pub fn contains(&self, flow_id: &str) -> bool {
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            if node.flow_id == flow_id {
+                return true;
+            }
+            stack.extend(node.children.iter());
+        }
+        false
+    }
+}
+
+// ============================================================================
+// 重建器
+// ============================================================================
+
+/// 从单个 Flow 中提取的谱系特征
+struct Lineage {
+    /// Claude Code 会话 ID（没有时为空字符串）
+    session_key: String,
+    /// 逐条消息的滚动前缀哈希，`prefix_hashes[k]` 对应前 k+1 条消息
+    prefix_hashes: Vec<u64>,
+    /// 最后一条助手消息之后出现的 tool_result ID
+    tool_result_ids: Vec<String>,
+    /// 响应中的工具调用 ID
+    call_ids: Vec<String>,
+    /// 首条用户消息文本
+    first_user_text: String,
+    /// 响应文本（已去除首尾空白）
+    response_text: String,
+}
+
+/// 同一会话内已处理 Flow 的索引
+#[derive(Default)]
+struct PartitionIndex {
+    /// 完整请求哈希 -> 最近的 Flow
+    by_hash: HashMap<u64, usize>,
+    /// 工具调用 ID -> Flow
+    by_call: HashMap<String, usize>,
+    /// 按时间顺序的成员
+    members: Vec<usize>,
+}
+
+/// 对话谱系重建器
+pub struct ConversationReconstructor {
+    options: ConversationOptions,
+}
+
+impl ConversationReconstructor {
+    /// 创建重建器
+    pub fn new(options: ConversationOptions) -> Self {
+        Self { options }
+    }
+
+    /// 把 Flow 重建为对话树，按开始时间排序
+    pub fn reconstruct(&self, flows: &[LLMFlow]) -> Vec<Conversation> {
+        let mut seen = HashSet::new();
+        let mut flows: Vec<&LLMFlow> = flows.iter().filter(|f| seen.insert(&f.id)).collect();
+        flows.sort_by(|a, b| {
+            a.timestamps
+                .request_start
+                .cmp(&b.timestamps.request_start)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+
+        let lineages: Vec<Lineage> = flows.iter().map(|f| extract_lineage(f)).collect();
+        let mut parents: Vec<Option<(usize, ConversationLink)>> = vec![None; flows.len()];
+        let mut partitions: HashMap<&str, PartitionIndex> = HashMap::new();
+
+        for (i, lineage) in lineages.iter().enumerate() {
+            let index = partitions.entry(lineage.session_key.as_str()).or_default();
+            parents[i] = self.find_parent(lineage, index, &lineages);
+
+            if let Some(hash) = lineage.prefix_hashes.last() {
+                index.by_hash.insert(*hash, i);
+            }
+            for id in &lineage.call_ids {
+                index.by_call.insert(id.clone(), i);
+            }
+            index.members.push(i);
+        }
+
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); flows.len()];
+        for (i, parent) in parents.iter().enumerate() {
+            if let Some((p, _)) = parent {
+                children[*p].push(i);
+            }
+        }
+
+        (0..flows.len())
+            .filter(|i| parents[*i].is_none())
+            .map(|root| {
+                let mut totals = ConversationTotals::default();
+                let mut models = Vec::new();
+                let mut ended_at = flows[root].timestamps.request_start;
+                let node = self.build_node(
+                    root,
+                    0,
+                    &flows,
+                    &parents,
+                    &children,
+                    &mut totals,
+                    &mut models,
+                    &mut ended_at,
+                );
+                let session_key = &lineages[root].session_key;
+                Conversation {
+                    id: flows[root].id.clone(),
+                    session_key: (!session_key.is_empty()).then(|| session_key.clone()),
+                    started_at: flows[root].timestamps.request_start,
+                    ended_at,
+                    models,
+                    totals,
+                    root: node,
+                }
+            })
+            .collect()
+    }
+
+    /// 依次按 tool_result 链、消息前缀、`/compact` 摘要查找父节点
+    fn find_parent(
+        &self,
+        lineage: &Lineage,
+        index: &PartitionIndex,
+        lineages: &[Lineage],
+    ) -> Option<(usize, ConversationLink)> {
+        if let Some(parent) = lineage
+            .tool_result_ids
+            .iter()
+            .filter_map(|id| index.by_call.get(id).copied())
+            .max()
+        {
+            return Some((parent, ConversationLink::ToolResult));
+        }
+
+        // 只接受严格前缀：完全相同的请求（重试）会成为原请求的兄弟节点
+        let len = lineage.prefix_hashes.len();
+        if let Some(parent) = lineage.prefix_hashes[..len.saturating_sub(1)]
+            .iter()
+            .rev()
+            .find_map(|hash| index.by_hash.get(hash).copied())
+        {
+            return Some((parent, ConversationLink::Prefix));
+        }
+
+        if lineage.first_user_text.is_empty() {
+            return None;
+        }
+        index
+            .members
+            .iter()
+            .rev()
+            .take(self.options.compact_lookback)
+            .copied()
+            .find(|candidate| {
+                let summary = &lineages[*candidate].response_text;
+                summary.chars().count() >= MIN_COMPACT_SUMMARY_CHARS
+                    && lineage.first_user_text.contains(summary.as_str())
+            })
+            .map(|parent| (parent, ConversationLink::Compact))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build_node(
+        &self,
+        i: usize,
+        depth: usize,
+        flows: &[&LLMFlow],
+        parents: &[Option<(usize, ConversationLink)>],
+        children: &[Vec<usize>],
+        totals: &mut ConversationTotals,
+        models: &mut Vec<String>,
+        ended_at: &mut DateTime<Utc>,
+    ) -> ConversationNode {
+        let flow = flows[i];
+        let (parent_id, link) = match parents[i] {
+            Some((p, link)) => (Some(flows[p].id.clone()), link),
+            None => (None, ConversationLink::Root),
+        };
+
+        let (input_tokens, output_tokens) = flow
+            .response
+            .as_ref()
+            .map(|r| (r.usage.input_tokens, r.usage.output_tokens))
+            .unwrap_or_default();
+        let cost_usd = self
+            .options
+            .pricing
+            .price_for(&flow.request.model)
+            .map(|price| price.cost(input_tokens, output_tokens));
+        let tool_calls = response_call_ids(flow).len();
+        let error = flow.error.is_some() || flow.state == FlowState::Failed;
+
+        totals.flows += 1;
+        totals.input_tokens += input_tokens as u64;
+        totals.output_tokens += output_tokens as u64;
+        totals.total_tokens += input_tokens as u64 + output_tokens as u64;
+        match cost_usd {
+            Some(cost) => totals.cost_usd += cost,
+            None => totals.unpriced_flows += 1,
+        }
+        totals.latency_ms += flow.timestamps.duration_ms;
+        totals.tool_calls += tool_calls;
+        totals.errors += error as usize;
+        totals.branches += children[i].len().saturating_sub(1);
+        totals.compactions += (link == ConversationLink::Compact) as usize;
+        totals.max_depth = totals.max_depth.max(depth);
+        if !flow.request.model.is_empty() && !models.contains(&flow.request.model) {
+            models.push(flow.request.model.clone());
+        }
+        let finished = flow
+            .timestamps
+            .response_end
+            .unwrap_or(flow.timestamps.request_start);
+        if finished > *ended_at {
+            *ended_at = finished;
+        }
+
+        ConversationNode {
+            flow_id: flow.id.clone(),
+            parent_id,
+            link,
+            depth,
+            model: flow.request.model.clone(),
+            created_at: flow.timestamps.request_start,
+            message_count: request_messages(flow).len(),
+            input_tokens,
+            output_tokens,
+            latency_ms: flow.timestamps.duration_ms,
+            tool_calls,
+            cost_usd,
+            error,
+            children: children[i]
+                .iter()
+                .map(|c| {
+                    self.build_node(
+                        *c,
+                        depth + 1,
+                        flows,
+                        parents,
+                        children,
+                        totals,
+                        models,
+                        ended_at,
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Default for ConversationReconstructor {
+    fn default() -> Self {
+        Self::new(ConversationOptions::default())
+    }
+}
+
+// ============================================================================
+// 特征提取
+// ============================================================================
+
+fn extract_lineage(flow: &LLMFlow) -> Lineage {
+    let messages = request_messages(flow);
+
+    let mut prefix_hashes = Vec::with_capacity(messages.len());
+    let mut hash = 0u64;
+    for message in &messages {
+        let mut normalized = message.clone();
+        normalize_message(&mut normalized);
+        hash = fnv1a(normalized.to_string().as_bytes(), hash);
+        prefix_hashes.push(hash);
+    }
+
+    let response_text = flow
+        .response
+        .as_ref()
+        .map(|r| r.content.trim().to_string())
+        .unwrap_or_default();
+
+    Lineage {
+        session_key: session_key(flow).unwrap_or_default(),
+        prefix_hashes,
+        tool_result_ids: trailing_tool_result_ids(&messages),
+        call_ids: response_call_ids(flow),
+        first_user_text: messages
+            .iter()
+            .find(|m| m.get("role").and_then(Value::as_str) == Some("user"))
+            .map(message_text)
+            .unwrap_or_default(),
+        response_text,
+    }
+}
+
+/// 请求中的原始消息
+///
+/// 优先使用请求体（捕获的 `request.messages` 会丢弃 tool_use / tool_result 块），
+/// 请求体中没有消息时退回到解析后的消息
+fn request_messages(flow: &LLMFlow) -> Vec<Value> {
+    let body = &flow.request.body;
+    if let Some(messages) = body
+        .get("messages")
+        .or_else(|| body.get("contents"))
+        .and_then(Value::as_array)
+    {
+        return messages.clone();
+    }
+    flow.request
+        .messages
+        .iter()
+        .filter(|m| m.role != MessageRole::System)
+        .filter_map(|m| serde_json::to_value(m).ok())
+        .collect()
+}
+
+/// Claude Code 会话 ID，来自请求头或 `metadata.user_id` 中的 `_session_` 段
+pub fn session_key(flow: &LLMFlow) -> Option<String> {
+    if let Some((_, value)) = flow
+        .request
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(CLAUDE_CODE_SESSION_HEADER))
+    {
+        if !value.is_empty() {
+            return Some(value.clone());
+        }
+    }
+    flow.request
+        .body
+        .pointer("/metadata/user_id")
+        .and_then(Value::as_str)
+        .and_then(|user_id| user_id.split_once("_session_"))
+        .map(|(_, session)| session.to_string())
+        .filter(|session| !session.is_empty())
+}
+
+/// 去除不影响语义的字段，并统一字符串与文本块两种内容写法
+fn normalize_message(message: &mut Value) {
+    strip_cache_control(message);
+    if let Some(text) = message
+        .get("content")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+    {
+        message["content"] = serde_json::json!([{"type": "text", "text": text}]);
+    }
+}
+
+fn strip_cache_control(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.remove("cache_control");
+            map.values_mut().for_each(strip_cache_control);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_cache_control),
+        _ => {}
+    }
+}
+
+fn message_text(message: &Value) -> String {
+    match message.get("content") {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(blocks)) => blocks
+            .iter()
+            .filter_map(|b| b.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => message
+            .get("parts")
+            .and_then(Value::as_array)
+            .map(|parts| {
+                parts
+                    .iter()
+                    .filter_map(|p| p.get("text").and_then(Value::as_str))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .unwrap_or_default(),
+    }
+}
+
+/// 最后一条助手消息之后的 tool_result ID（Anthropic `tool_use_id` / OpenAI `tool_call_id`）
+fn trailing_tool_result_ids(messages: &[Value]) -> Vec<String> {
+    let start = messages
+        .iter()
+        .rposition(|m| {
+            matches!(
+                m.get("role").and_then(Value::as_str),
+                Some("assistant") | Some("model")
+            )
+        })
+        .map(|i| i + 1)
+        .unwrap_or(0);
+
+    let mut ids = Vec::new();
+    for message in &messages[start..] {
+        if let Some(id) = message.get("tool_call_id").and_then(Value::as_str) {
+            ids.push(id.to_string());
+        }
+        if let Some(id) = message
+            .pointer("/tool_result/tool_call_id")
+            .and_then(Value::as_str)
+        {
+            ids.push(id.to_string());
+        }
+        if let Some(blocks) = message.get("content").and_then(Value::as_array) {
+            ids.extend(
+                blocks
+                    .iter()
+                    .filter(|b| b.get("type").and_then(Value::as_str) == Some("tool_result"))
+                    .filter_map(|b| b.get("tool_use_id").and_then(Value::as_str))
+                    .map(str::to_string),
+            );
+        }
+    }
+    ids
+}
+
+/// 响应中的工具调用 ID（去重）
+fn response_call_ids(flow: &LLMFlow) -> Vec<String> {
+    let Some(response) = &flow.response else {
+        return Vec::new();
+    };
+    let mut ids: Vec<String> = response.tool_calls.iter().map(|c| c.id.clone()).collect();
+
+    let body = &response.body;
+    if let Some(blocks) = body.get("content").and_then(Value::as_array) {
+        ids.extend(
+            blocks
+                .iter()
+                .filter(|b| b.get("type").and_then(Value::as_str) == Some("tool_use"))
+                .filter_map(|b| b.get("id").and_then(Value::as_str))
+                .map(str::to_string),
+        );
+    }
+    if let Some(calls) = body
+        .pointer("/choices/0/message/tool_calls")
+        .and_then(Value::as_array)
+    {
+        ids.extend(
+            calls
+                .iter()
+                .filter_map(|c| c.get("id").and_then(Value::as_str))
+                .map(str::to_string),
+        );
+    }
+
+    let mut seen = HashSet::new();
+    ids.retain(|id| !id.is_empty() && seen.insert(id.clone()));
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow_monitor::models::{
+        FlowMetadata, FlowType, FunctionCall, LLMRequest, LLMResponse, ToolCall,
+    };
+    use crate::ProviderType;
+    use chrono::Duration;
+    use serde_json::json;
+
+    fn flow(id: &str, seq: i64, messages: Value, answer: &str) -> LLMFlow {
+        let request = LLMRequest {
+            path: "/v1/messages".to_string(),
+            model: "claude-sonnet-4-5".to_string(),
+            body: json!({"model": "claude-sonnet-4-5", "messages": messages}),
+            ..Default::default()
+        };
+        let metadata = FlowMetadata {
+            provider: ProviderType::Claude,
+            ..Default::default()
+        };
+        let mut flow = LLMFlow::new(id.to_string(), FlowType::ChatCompletions, request, metadata);
+        let start = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+            + Duration::seconds(seq);
+        flow.timestamps.request_start = start;
+        flow.timestamps.duration_ms = 100;
+        flow.state = FlowState::Completed;
+        let mut usage = crate::flow_monitor::models::TokenUsage {
+            input_tokens: 1000,
+            output_tokens: 100,
+            ..Default::default()
+        };
+        usage.calculate_total();
+        flow.response = Some(LLMResponse {
+            content: answer.to_string(),
+            usage,
+            ..Default::default()
+        });
+        flow
+    }
+
+    fn with_tool_call(mut flow: LLMFlow, call_id: &str) -> LLMFlow {
+        if let Some(response) = flow.response.as_mut() {
+            response.tool_calls.push(ToolCall {
+                id: call_id.to_string(),
+                tool_type: "function".to_string(),
+                function: FunctionCall {
+                    name: "read_file".to_string(),
+                    arguments: "{}".to_string(),
+                },
+            });
+        }
+        flow
+    }
+
+    fn with_session(mut flow: LLMFlow, session: &str) -> LLMFlow {
+        flow.request
+            .headers
+            .insert("X-Claude-Code-Session-Id".to_string(), session.to_string());
+        flow
+    }
+
+    fn reconstruct(flows: &[LLMFlow]) -> Vec<Conversation> {
+        ConversationReconstructor::default().reconstruct(flows)
+    }
+
+    #[test]
+    fn test_prefix_chain() {
+        let flows = vec![
+            flow("a", 0, json!([{"role": "user", "content": "hi"}]), "hello"),
+            flow(
+                "b",
+                1,
+                json!([
+                    {"role": "user", "content": [{"type": "text", "text": "hi", "cache_control": {"type": "ephemeral"}}]},
+                    {"role": "assistant", "content": "hello"},
+                    {"role": "user", "content": "more"},
+                ]),
+                "sure",
+            ),
+            flow(
+                "other",
+                2,
+                json!([{"role": "user", "content": "unrelated"}]),
+                "ok",
+            ),
+        ];
+
+        let conversations = reconstruct(&flows);
+        assert_eq!(conversations.len(), 2);
+        let first = &conversations[0];
+        assert_eq!(first.id, "a");
+        assert_eq!(first.root.children.len(), 1);
+        assert_eq!(first.root.children[0].flow_id, "b");
+        assert_eq!(first.root.children[0].link, ConversationLink::Prefix);
+        assert_eq!(first.root.children[0].depth, 1);
+        assert_eq!(first.flow_ids(), vec!["a", "b"]);
+        assert_eq!(first.totals.flows, 2);
+        assert_eq!(first.totals.max_depth, 1);
+        assert_eq!(conversations[1].id, "other");
+    }
+
+    #[test]
+    fn test_edit_and_retry_branch() {
+        let base = json!([{"role": "user", "content": "hi"}]);
+        let flows = vec![
+            flow("a", 0, base.clone(), "hello"),
+            flow(
+                "b",
+                1,
+                json!([
+                    {"role": "user", "content": "hi"},
+                    {"role": "assistant", "content": "hello"},
+                    {"role": "user", "content": "write a poem"},
+                ]),
+                "roses",
+            ),
+            flow(
+                "b-edit",
+                2,
+                json!([
+                    {"role": "user", "content": "hi"},
+                    {"role": "assistant", "content": "hello"},
+                    {"role": "user", "content": "write a haiku"},
+                ]),
+                "petals",
+            ),
+            flow("a-retry", 3, base, "hello again"),
+        ];
+
+        let conversations = reconstruct(&flows);
+        assert_eq!(conversations.len(), 2);
+        let tree = &conversations[0];
+        let children: Vec<_> = tree.root.children.iter().map(|c| &c.flow_id).collect();
+        assert_eq!(children, vec!["b", "b-edit"]);
+        assert_eq!(tree.totals.branches, 1);
+        // 单条消息的重试没有更短的前缀，只能作为新对话的起点
+        assert_eq!(conversations[1].id, "a-retry");
+    }
+
+    #[test]
+    fn test_tool_result_chain() {
+        let flows = vec![
+            with_tool_call(
+                flow("a", 0, json!([{"role": "user", "content": "read it"}]), ""),
+                "toolu_1",
+            ),
+            // tool_result 链优先于前缀匹配
+            flow(
+                "b",
+                1,
+                json!([
+                    {"role": "user", "content": "read it"},
+                    {"role": "assistant", "content": [{"type": "tool_use", "id": "toolu_1", "name": "read_file", "input": {"path": "x"}}]},
+                    {"role": "user", "content": [{"type": "tool_result", "tool_use_id": "toolu_1", "content": "data"}]},
+                ]),
+                "done",
+            ),
+        ];
+
+        let conversations = reconstruct(&flows);
+        assert_eq!(conversations.len(), 1);
+        let child = &conversations[0].root.children[0];
+        assert_eq!(child.flow_id, "b");
+        assert_eq!(child.link, ConversationLink::ToolResult);
+        assert_eq!(conversations[0].totals.tool_calls, 1);
+    }
+
+    #[test]
+    fn test_compact_boundary() {
+        let summary = "The user is refactoring the parser module and has finished the lexer.";
+        let flows = vec![
+            flow(
+                "a",
+                0,
+                json!([{"role": "user", "content": "let's refactor"}]),
+                "ok",
+            ),
+            flow(
+                "compact",
+                1,
+                json!([
+                    {"role": "user", "content": "let's refactor"},
+                    {"role": "assistant", "content": "ok"},
+                    {"role": "user", "content": "Summarize this conversation"},
+                ]),
+                summary,
+            ),
+            flow(
+                "after",
+                2,
+                json!([{
+                    "role": "user",
+                    "content": format!("This session is being continued from a previous conversation. Summary: {summary}"),
+                }]),
+                "continuing",
+            ),
+        ];
+
+        let conversations = reconstruct(&flows);
+        assert_eq!(conversations.len(), 1);
+        let compact = &conversations[0].root.children[0];
+        assert_eq!(compact.flow_id, "compact");
+        assert_eq!(compact.children[0].flow_id, "after");
+        assert_eq!(compact.children[0].link, ConversationLink::Compact);
+        assert_eq!(conversations[0].totals.compactions, 1);
+        assert_eq!(conversations[0].totals.max_depth, 2);
+    }
+
+    #[test]
+    fn test_session_key_separates_conversations() {
+        let base = json!([{"role": "user", "content": "hi"}]);
+        let next = json!([
+            {"role": "user", "content": "hi"},
+            {"role": "assistant", "content": "hello"},
+            {"role": "user", "content": "again"},
+        ]);
+        let mut from_metadata = flow("b", 1, next.clone(), "x");
+        from_metadata.request.body["metadata"] =
+            json!({"user_id": "user_abc_account_def_session_s2"});
+        let flows = vec![
+            with_session(flow("a", 0, base, "hello"), "s1"),
+            from_metadata,
+            with_session(flow("c", 2, next, "y"), "s1"),
+        ];
+
+        assert_eq!(session_key(&flows[1]).as_deref(), Some("s2"));
+        let conversations = reconstruct(&flows);
+        assert_eq!(conversations.len(), 2);
+        assert_eq!(conversations[0].session_key.as_deref(), Some("s1"));
+        assert_eq!(conversations[0].flow_ids(), vec!["a", "c"]);
+        assert_eq!(conversations[1].session_key.as_deref(), Some("s2"));
+    }
+
+    #[test]
+    fn test_totals_and_pricing() {
+        let mut failed = flow(
+            "b",
+            1,
+            json!([
+                {"role": "user", "content": "hi"},
+                {"role": "assistant", "content": "hello"},
+                {"role": "user", "content": "more"},
+            ]),
+            "",
+        );
+        failed.state = FlowState::Failed;
+        failed.request.model = "local-model".to_string();
+        let flows = vec![
+            flow("a", 0, json!([{"role": "user", "content": "hi"}]), "hello"),
+            failed,
+        ];
+
+        let conversations = reconstruct(&flows);
+        let totals = &conversations[0].totals;
+        assert_eq!(totals.flows, 2);
+        assert_eq!(totals.input_tokens, 2000);
+        assert_eq!(totals.output_tokens, 200);
+        assert_eq!(totals.total_tokens, 2200);
+        assert_eq!(totals.latency_ms, 200);
+        assert_eq!(totals.errors, 1);
+        assert_eq!(totals.unpriced_flows, 1);
+        // claude-sonnet-4-5: 1000 * 3 / 1M + 100 * 15 / 1M
+        assert!((totals.cost_usd - 0.0045).abs() < 1e-9);
+        assert_eq!(
+            conversations[0].models,
+            vec!["claude-sonnet-4-5", "local-model"]
+        );
+    }
+
+    #[test]
+    fn test_pricing_longest_match() {
+        let pricing = ModelPricing::default();
+        assert_eq!(
+            pricing
+                .price_for("gpt-4o-mini-2024")
+                .unwrap()
+                .input_per_mtok,
+            0.15
+        );
+        assert_eq!(pricing.price_for("GPT-4o").unwrap().input_per_mtok, 2.5);
+        assert!(pricing.price_for("llama-3").is_none());
+
+        let custom = ModelPricing::empty().with_price("llama", ModelPrice::new(0.1, 0.2));
+        assert!((custom.price_for("llama-3").unwrap().cost(1_000_000, 0) - 0.1).abs() < 1e-9);
+    }
+}
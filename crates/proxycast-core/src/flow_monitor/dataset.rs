@@ -0,0 +1,897 @@
+//! 数据集导出
+//!
+//! 把精选的 Flow 导出为微调和评测数据集，与 `exporter` 的原始审计格式互补：
+//! - OpenAI Chat 微调 JSONL（`messages` + `tools`）
+//! - Anthropic 风格的消息记录（`system` + `messages` + `tools`）
+//! - 评测格式（`prompt` + `expected_output`）
+//!
+//! 支持过滤表达式、质量过滤、去重、训练/验证集划分，默认对输出应用脱敏。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use thiserror::Error;
+
+use super::exporter::{default_redaction_rules, RedactionRule, Redactor};
+use super::filter_parser::FilterParser;
+use super::models::{FlowState, FlowType, LLMFlow, MessageRole};
+use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
+use crate::converter::image::parse_data_url;
+use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::openai::{
+    ChatCompletionRequest, ChatMessage, ContentPart, FunctionCall, FunctionDef, MessageContent,
+    Tool, ToolCall,
+};
+
+// ============================================================================
+// 选项
+// ============================================================================
+
+/// 数据集格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DatasetFormat {
+    /// OpenAI Chat 微调格式
+    #[default]
+    OpenaiChat,
+    /// Anthropic 风格消息记录
+    AnthropicMessages,
+    /// 评测格式（prompt / expected_output）
+    Eval,
+}
+
+/// 质量过滤条件
+///
+/// `starred_only` 与 `tags` 同时设置时，收藏或带有任一标签的 Flow 都会被保留
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetQualityFilter {
+    /// 仅导出已完成的 Flow
+    #[serde(default = "default_true")]
+    pub completed_only: bool,
+    /// 排除带错误的 Flow
+    #[serde(default = "default_true")]
+    pub exclude_errors: bool,
+    /// 仅导出收藏的 Flow
+    #[serde(default)]
+    pub starred_only: bool,
+    /// 仅导出带有任一标签的 Flow
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 最终输出的最少字符数（有工具调用时不检查）
+    #[serde(default)]
+    pub min_output_chars: usize,
+}
+
+impl Default for DatasetQualityFilter {
+    fn default() -> Self {
+        Self {
+            completed_only: true,
+            exclude_errors: true,
+            starred_only: false,
+            tags: Vec::new(),
+            min_output_chars: 0,
+        }
+    }
+}
+
+impl DatasetQualityFilter {
+    /// 检查 Flow 是否满足质量条件
+    pub fn matches(&self, flow: &LLMFlow) -> bool {
+        if self.completed_only && flow.state != FlowState::Completed {
+            return false;
+        }
+        if self.exclude_errors && flow.error.is_some() {
+            return false;
+        }
+        if self.starred_only || !self.tags.is_empty() {
+            let starred = self.starred_only && flow.annotations.starred;
+            let tagged = self.tags.iter().any(|t| flow.annotations.tags.contains(t));
+            if !starred && !tagged {
+                return false;
+            }
+        }
+        if self.min_output_chars > 0 {
+            let Some(response) = &flow.response else {
+                return false;
+            };
+            if response.tool_calls.is_empty()
+                && response.content.chars().count() < self.min_output_chars
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// 数据集导出选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetOptions {
+    /// 数据集格式
+    #[serde(default)]
+    pub format: DatasetFormat,
+    /// 过滤表达式（如 `~m claude* & ~p kiro`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter_expr: Option<String>,
+    /// 质量过滤条件
+    #[serde(default)]
+    pub quality: DatasetQualityFilter,
+    /// 是否按对话内容去重
+    #[serde(default = "default_true")]
+    pub dedup: bool,
+    /// 验证集比例（0.0 - 1.0）
+    #[serde(default)]
+    pub validation_ratio: f64,
+    /// 划分随机种子（相同种子和 Flow ID 得到相同划分）
+    #[serde(default)]
+    pub seed: u64,
+    /// 是否脱敏
+    #[serde(default = "default_true")]
+    pub redact: bool,
+    /// 脱敏规则（为空时使用默认规则）
+    #[serde(default)]
+    pub redaction_rules: Vec<RedactionRule>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for DatasetOptions {
+    fn default() -> Self {
+        Self {
+            format: DatasetFormat::default(),
+            filter_expr: None,
+            quality: DatasetQualityFilter::default(),
+            dedup: true,
+            validation_ratio: 0.0,
+            seed: 0,
+            redact: true,
+            redaction_rules: Vec::new(),
+        }
+    }
+}
+
+// ============================================================================
+// 导出结果
+// ============================================================================
+
+/// 数据集导出错误
+#[derive(Debug, Error)]
+pub enum DatasetError {
+    #[error("无效的过滤表达式: {0}")]
+    InvalidFilter(String),
+}
+
+/// 数据集导出统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DatasetStats {
+    /// 输入 Flow 数
+    pub total: usize,
+    /// 未匹配过滤表达式的数量
+    pub skipped_filter: usize,
+    /// 未通过质量过滤的数量
+    pub skipped_quality: usize,
+    /// 无法转换（无输出）的数量
+    pub skipped_unconvertible: usize,
+    /// 重复的数量
+    pub skipped_duplicate: usize,
+    /// 训练集样本数
+    pub train: usize,
+    /// 验证集样本数
+    pub validation: usize,
+}
+
+/// 数据集导出结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetExport {
+    /// 数据集格式
+    pub format: DatasetFormat,
+    /// 训练集样本
+    pub train: Vec<Value>,
+    /// 验证集样本
+    pub validation: Vec<Value>,
+    /// 统计信息
+    pub stats: DatasetStats,
+}
+
+impl DatasetExport {
+    /// 训练集 JSONL
+    pub fn train_jsonl(&self) -> String {
+        to_jsonl(&self.train)
+    }
+
+    /// 验证集 JSONL
+    pub fn validation_jsonl(&self) -> String {
+        to_jsonl(&self.validation)
+    }
+}
+
+fn to_jsonl(records: &[Value]) -> String {
+    records
+        .iter()
+        .filter_map(|r| serde_json::to_string(r).ok())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// ============================================================================
+// 数据集导出器
+// ============================================================================
+
+/// 数据集导出器
+pub struct DatasetExporter {
+    options: DatasetOptions,
+    redactor: Option<Redactor>,
+}
+
+impl DatasetExporter {
+    /// 创建新的数据集导出器
+    pub fn new(options: DatasetOptions) -> Self {
+        let redactor = options.redact.then(|| {
+            if options.redaction_rules.is_empty() {
+                Redactor::new(&default_redaction_rules())
+            } else {
+                Redactor::new(&options.redaction_rules)
+            }
+        });
+        Self { options, redactor }
+    }
+
+    /// 导出数据集
+    ///
+    /// Flow 按给定顺序处理，去重时保留最先出现的样本
+    pub fn export(&self, flows: &[LLMFlow]) -> Result<DatasetExport, DatasetError> {
+        let filter = match &self.options.filter_expr {
+            Some(expr) if !expr.trim().is_empty() => {
+                let parsed = FilterParser::parse(expr)
+                    .map_err(|e| DatasetError::InvalidFilter(e.to_string()))?;
+                Some(FilterParser::compile(&parsed))
+            }
+            _ => None,
+        };
+
+        let mut stats = DatasetStats {
+            total: flows.len(),
+            ..Default::default()
+        };
+        let mut seen = HashSet::new();
+        let mut train = Vec::new();
+        let mut validation = Vec::new();
+
+        for flow in flows {
+            if filter.as_ref().is_some_and(|f| !f(flow)) {
+                stats.skipped_filter += 1;
+                continue;
+            }
+            if !self.options.quality.matches(flow) {
+                stats.skipped_quality += 1;
+                continue;
+            }
+            let Some((messages, tools)) = transcript(flow) else {
+                stats.skipped_unconvertible += 1;
+                continue;
+            };
+            if self.options.dedup {
+                let key = serde_json::to_string(&(&messages, &tools)).unwrap_or_default();
+                if !seen.insert(fnv1a(key.as_bytes(), 0)) {
+                    stats.skipped_duplicate += 1;
+                    continue;
+                }
+            }
+
+            let record = match self.options.format {
+                DatasetFormat::OpenaiChat => openai_record(&messages, tools.as_deref()),
+                DatasetFormat::AnthropicMessages => anthropic_record(&messages, tools.as_deref()),
+                DatasetFormat::Eval => eval_record(flow, &messages, tools.as_deref()),
+            };
+            let record = match &self.redactor {
+                Some(redactor) => redactor.redact_json(&record),
+                None => record,
+            };
+
+            if self.is_validation(&flow.id) {
+                validation.push(record);
+            } else {
+                train.push(record);
+            }
+        }
+
+        stats.train = train.len();
+        stats.validation = validation.len();
+        Ok(DatasetExport {
+            format: self.options.format,
+            train,
+            validation,
+            stats,
+        })
+    }
+
+    /// 按 Flow ID 和种子确定性地划分验证集
+    fn is_validation(&self, flow_id: &str) -> bool {
+        let ratio = self.options.validation_ratio.clamp(0.0, 1.0);
+        if ratio <= 0.0 {
+            return false;
+        }
+        let bucket = fnv1a(flow_id.as_bytes(), self.options.seed) % 10_000;
+        (bucket as f64) < ratio * 10_000.0
+    }
+}
+
+/// FNV-1a 哈希（跨版本稳定，用于去重和划分）
+pub(super) fn fnv1a(bytes: &[u8], seed: u64) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64 ^ seed;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+// ============================================================================
+// 对话重建
+// ============================================================================
+
+/// 以 OpenAI Chat 消息为中间格式重建完整对话（含最终的助手回复）
+///
+/// 优先使用原始请求体以保留工具调用和工具结果，无法解析时回退到结构化消息
+fn transcript(flow: &LLMFlow) -> Option<(Vec<ChatMessage>, Option<Vec<Tool>>)> {
+    let response = flow.response.as_ref()?;
+    if response.content.is_empty() && response.tool_calls.is_empty() {
+        return None;
+    }
+
+    let (mut messages, tools) = request_messages(flow);
+    if messages.is_empty() {
+        return None;
+    }
+
+    let tool_calls: Vec<ToolCall> = response
+        .tool_calls
+        .iter()
+        .map(|tc| ToolCall {
+            id: tc.id.clone(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: tc.function.name.clone(),
+                arguments: tc.function.arguments.clone(),
+            },
+        })
+        .collect();
+    messages.push(ChatMessage {
+        role: "assistant".to_string(),
+        content: (!response.content.is_empty())
+            .then(|| MessageContent::Text(response.content.clone())),
+        tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+        tool_call_id: None,
+    });
+
+    Some((messages, tools.filter(|t| !t.is_empty())))
+}
+
+/// 提取请求中的消息和工具定义
+fn request_messages(flow: &LLMFlow) -> (Vec<ChatMessage>, Option<Vec<Tool>>) {
+    let body = &flow.request.body;
+    match flow.flow_type {
+        FlowType::ChatCompletions => {
+            if let Ok(request) = serde_json::from_value::<ChatCompletionRequest>(body.clone()) {
+                return (request.messages, request.tools);
+            }
+        }
+        FlowType::AnthropicMessages => {
+            if let Ok(request) = serde_json::from_value::<AnthropicMessagesRequest>(body.clone()) {
+                let request = convert_anthropic_to_openai(&request);
+                return (request.messages, request.tools);
+            }
+        }
+        _ => {}
+    }
+
+    let mut messages = Vec::new();
+    let has_system = flow
+        .request
+        .messages
+        .iter()
+        .any(|m| m.role == MessageRole::System);
+    if let (false, Some(system)) = (has_system, &flow.request.system_prompt) {
+        messages.push(text_message("system", system.clone()));
+    }
+    for m in &flow.request.messages {
+        let role = match m.role {
+            MessageRole::System => "system",
+            MessageRole::User => "user",
+            MessageRole::Assistant => "assistant",
+            MessageRole::Tool | MessageRole::Function => "tool",
+        };
+        let mut message = text_message(role, m.content.get_all_text());
+        message.tool_calls = m.tool_calls.as_ref().map(|calls| {
+            calls
+                .iter()
+                .map(|tc| ToolCall {
+                    id: tc.id.clone(),
+                    call_type: "function".to_string(),
+                    function: FunctionCall {
+                        name: tc.function.name.clone(),
+                        arguments: tc.function.arguments.clone(),
+                    },
+                })
+                .collect()
+        });
+        if let Some(result) = &m.tool_result {
+            message.tool_call_id = Some(result.tool_call_id.clone());
+            message.content = Some(MessageContent::Text(result.content.clone()));
+        }
+        messages.push(message);
+    }
+
+    let tools = flow.request.tools.as_ref().map(|tools| {
+        tools
+            .iter()
+            .map(|t| Tool {
+                tool_type: "function".to_string(),
+                function: FunctionDef {
+                    name: t.function.name.clone(),
+                    description: t.function.description.clone(),
+                    parameters: t.function.parameters.clone(),
+                },
+            })
+            .collect()
+    });
+    (messages, tools)
+}
+
+fn text_message(role: &str, text: String) -> ChatMessage {
+    ChatMessage {
+        role: role.to_string(),
+        content: Some(MessageContent::Text(text)),
+        tool_calls: None,
+        tool_call_id: None,
+    }
+}
+
+// ============================================================================
+// 格式转换
+// ============================================================================
+
+/// OpenAI Chat 微调样本
+fn openai_record(messages: &[ChatMessage], tools: Option<&[Tool]>) -> Value {
+    let mut record = json!({ "messages": messages });
+    if let Some(tools) = tools {
+        record["tools"] = json!(tools);
+    }
+    record
+}
+
+/// Anthropic 风格消息记录
+///
+/// 系统消息合并为 `system`，工具调用转为 `tool_use`，工具结果转为 user 消息中的
+/// `tool_result`；相邻的同角色消息合并，保证 user / assistant 交替
+fn anthropic_record(messages: &[ChatMessage], tools: Option<&[Tool]>) -> Value {
+    let mut system = Vec::new();
+    let mut turns: Vec<(&'static str, Vec<Value>)> = Vec::new();
+
+    for message in messages {
+        let (role, blocks) = match message.role.as_str() {
+            "system" => {
+                system.push(message.get_content_text());
+                continue;
+            }
+            "assistant" => {
+                let mut blocks = content_blocks(message.content.as_ref());
+                for tc in message.tool_calls.iter().flatten() {
+                    let input = serde_json::from_str::<Value>(&tc.function.arguments)
+                        .ok()
+                        .filter(Value::is_object)
+                        .unwrap_or_else(|| json!({}));
+                    blocks.push(json!({
+                        "type": "tool_use",
+                        "id": tc.id,
+                        "name": tc.function.name,
+                        "input": input,
+                    }));
+                }
+                ("assistant", blocks)
+            }
+            "tool" | "function" => (
+                "user",
+                vec![json!({
+                    "type": "tool_result",
+                    "tool_use_id": message.tool_call_id.clone().unwrap_or_default(),
+                    "content": message.get_content_text(),
+                })],
+            ),
+            _ => ("user", content_blocks(message.content.as_ref())),
+        };
+        if blocks.is_empty() {
+            continue;
+        }
+        match turns.last_mut() {
+            Some((last_role, last_blocks)) if *last_role == role => last_blocks.extend(blocks),
+            _ => turns.push((role, blocks)),
+        }
+    }
+
+    let messages: Vec<Value> = turns
+        .into_iter()
+        .map(|(role, content)| json!({ "role": role, "content": content }))
+        .collect();
+    let mut record = json!({ "messages": messages });
+    let system = system.join("\n");
+    if !system.is_empty() {
+        record["system"] = json!(system);
+    }
+    if let Some(tools) = tools {
+        record["tools"] = tools
+            .iter()
+            .map(|t| {
+                json!({
+                    "name": t.function.name,
+                    "description": t.function.description,
+                    "input_schema": t.function.parameters.clone()
+                        .unwrap_or_else(|| json!({"type": "object"})),
+                })
+            })
+            .collect();
+    }
+    record
+}
+
+/// 把 OpenAI 消息内容转换为 Anthropic 内容块
+fn content_blocks(content: Option<&MessageContent>) -> Vec<Value> {
+    match content {
+        Some(MessageContent::Text(text)) if !text.is_empty() => {
+            vec![json!({"type": "text", "text": text})]
+        }
+        Some(MessageContent::Parts(parts)) => parts
+            .iter()
+            .map(|part| match part {
+                ContentPart::Text { text } => json!({"type": "text", "text": text}),
+                ContentPart::ImageUrl { image_url } => match parse_data_url(&image_url.url) {
+                    Some((media_type, data)) => json!({
+                        "type": "image",
+                        "source": {"type": "base64", "media_type": media_type, "data": data},
+                    }),
+                    None => json!({
+                        "type": "image",
+                        "source": {"type": "url", "url": image_url.url},
+                    }),
+                },
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 评测样本：最后一条助手回复作为期望输出，其余消息作为 prompt
+fn eval_record(flow: &LLMFlow, messages: &[ChatMessage], tools: Option<&[Tool]>) -> Value {
+    let (expected, prompt) = messages
+        .split_last()
+        .expect("transcript always ends with an assistant message");
+    let mut record = json!({
+        "id": flow.id,
+        "prompt": prompt,
+        "expected_output": expected.get_content_text(),
+        "metadata": {
+            "model": flow.request.model,
+            "provider": flow.metadata.provider.to_string(),
+            "tags": flow.annotations.tags,
+        },
+    });
+    if let Some(tool_calls) = &expected.tool_calls {
+        record["expected_tool_calls"] = json!(tool_calls);
+    }
+    if let Some(tools) = tools {
+        record["tools"] = json!(tools);
+    }
+    record
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow_monitor::models::{
+        FlowError, FlowErrorType, FlowMetadata, FunctionCall as FlowFunctionCall, LLMRequest,
+        LLMResponse, ToolCall as FlowToolCall,
+    };
+    use crate::ProviderType;
+
+    fn openai_flow(id: &str, user: &str, answer: &str) -> LLMFlow {
+        let body = json!({
+            "model": "gpt-4o",
+            "messages": [
+                {"role": "system", "content": "You are helpful."},
+                {"role": "user", "content": user},
+            ],
+            "tools": [{
+                "type": "function",
+                "function": {"name": "search", "parameters": {"type": "object"}},
+            }],
+        });
+        let request = LLMRequest {
+            path: "/v1/chat/completions".to_string(),
+            model: "gpt-4o".to_string(),
+            body,
+            ..Default::default()
+        };
+        let metadata = FlowMetadata {
+            provider: ProviderType::OpenAI,
+            ..Default::default()
+        };
+        let mut flow = LLMFlow::new(id.to_string(), FlowType::ChatCompletions, request, metadata);
+        flow.state = FlowState::Completed;
+        flow.response = Some(LLMResponse {
+            content: answer.to_string(),
+            ..Default::default()
+        });
+        flow
+    }
+
+    fn anthropic_flow(id: &str) -> LLMFlow {
+        let body = json!({
+            "model": "claude-sonnet-4-5",
+            "max_tokens": 1024,
+            "system": "Be brief.",
+            "messages": [
+                {"role": "user", "content": "What's the weather?"},
+                {"role": "assistant", "content": [
+                    {"type": "tool_use", "id": "toolu_1", "name": "weather", "input": {"city": "Paris"}},
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": "Sunny"},
+                ]},
+            ],
+            "tools": [{"name": "weather", "input_schema": {"type": "object"}}],
+        });
+        let request = LLMRequest {
+            path: "/v1/messages".to_string(),
+            model: "claude-sonnet-4-5".to_string(),
+            body,
+            ..Default::default()
+        };
+        let metadata = FlowMetadata {
+            provider: ProviderType::Claude,
+            ..Default::default()
+        };
+        let mut flow = LLMFlow::new(
+            id.to_string(),
+            FlowType::AnthropicMessages,
+            request,
+            metadata,
+        );
+        flow.state = FlowState::Completed;
+        flow.response = Some(LLMResponse {
+            content: "It's sunny in Paris.".to_string(),
+            ..Default::default()
+        });
+        flow
+    }
+
+    fn export(flows: &[LLMFlow], options: DatasetOptions) -> DatasetExport {
+        DatasetExporter::new(options).export(flows).unwrap()
+    }
+
+    #[test]
+    fn test_openai_chat_record() {
+        let result = export(
+            &[openai_flow("f1", "Hi", "Hello!")],
+            DatasetOptions::default(),
+        );
+        assert_eq!(result.stats.train, 1);
+
+        let record = &result.train[0];
+        let messages = record["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(messages[2]["role"], "assistant");
+        assert_eq!(messages[2]["content"], "Hello!");
+        assert_eq!(record["tools"][0]["function"]["name"], "search");
+    }
+
+    #[test]
+    fn test_response_tool_calls_become_assistant_tool_calls() {
+        let mut flow = openai_flow("f1", "Find rust docs", "");
+        flow.response.as_mut().unwrap().tool_calls = vec![FlowToolCall {
+            id: "call_1".to_string(),
+            tool_type: "function".to_string(),
+            function: FlowFunctionCall {
+                name: "search".to_string(),
+                arguments: r#"{"q":"rust"}"#.to_string(),
+            },
+        }];
+
+        let result = export(std::slice::from_ref(&flow), DatasetOptions::default());
+        let last = result.train[0]["messages"]
+            .as_array()
+            .unwrap()
+            .last()
+            .unwrap()
+            .clone();
+        assert!(last.get("content").is_none());
+        assert_eq!(last["tool_calls"][0]["function"]["name"], "search");
+
+        let result = export(
+            &[flow],
+            DatasetOptions {
+                format: DatasetFormat::AnthropicMessages,
+                ..Default::default()
+            },
+        );
+        let last = result.train[0]["messages"]
+            .as_array()
+            .unwrap()
+            .last()
+            .unwrap()
+            .clone();
+        assert_eq!(last["content"][0]["type"], "tool_use");
+        assert_eq!(last["content"][0]["input"]["q"], "rust");
+    }
+
+    #[test]
+    fn test_anthropic_flow_round_trips_tool_use() {
+        let flow = anthropic_flow("a1");
+
+        let openai = export(std::slice::from_ref(&flow), DatasetOptions::default());
+        let messages = openai.train[0]["messages"].as_array().unwrap();
+        let roles: Vec<&str> = messages
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, ["system", "user", "assistant", "tool", "assistant"]);
+        assert_eq!(messages[3]["tool_call_id"], "toolu_1");
+
+        let anthropic = export(
+            &[flow],
+            DatasetOptions {
+                format: DatasetFormat::AnthropicMessages,
+                ..Default::default()
+            },
+        );
+        let record = &anthropic.train[0];
+        assert_eq!(record["system"], "Be brief.");
+        let messages = record["messages"].as_array().unwrap();
+        let roles: Vec<&str> = messages
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, ["user", "assistant", "user", "assistant"]);
+        assert_eq!(messages[1]["content"][0]["input"]["city"], "Paris");
+        assert_eq!(messages[2]["content"][0]["type"], "tool_result");
+        assert_eq!(record["tools"][0]["name"], "weather");
+    }
+
+    #[test]
+    fn test_eval_record() {
+        let result = export(
+            &[openai_flow("f1", "2+2?", "4")],
+            DatasetOptions {
+                format: DatasetFormat::Eval,
+                ..Default::default()
+            },
+        );
+        let record = &result.train[0];
+        assert_eq!(record["id"], "f1");
+        assert_eq!(record["prompt"].as_array().unwrap().len(), 2);
+        assert_eq!(record["expected_output"], "4");
+        assert_eq!(record["metadata"]["provider"], "openai");
+    }
+
+    #[test]
+    fn test_quality_filters() {
+        let mut failed = openai_flow("failed", "a", "b");
+        failed.state = FlowState::Failed;
+        failed.error = Some(FlowError::new(FlowErrorType::ServerError, "boom"));
+        let mut starred = openai_flow("starred", "c", "d");
+        starred.annotations.starred = true;
+        let mut tagged = openai_flow("tagged", "e", "f");
+        tagged.annotations.tags = vec!["golden".to_string()];
+        let plain = openai_flow("plain", "g", "h");
+        let flows = [failed, starred, tagged, plain];
+
+        let result = export(&flows, DatasetOptions::default());
+        assert_eq!(result.stats.skipped_quality, 1);
+        assert_eq!(result.stats.train, 3);
+
+        let result = export(
+            &flows,
+            DatasetOptions {
+                quality: DatasetQualityFilter {
+                    starred_only: true,
+                    tags: vec!["golden".to_string()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        assert_eq!(result.stats.train, 2);
+        assert_eq!(result.stats.skipped_quality, 2);
+
+        let result = export(
+            &flows,
+            DatasetOptions {
+                quality: DatasetQualityFilter {
+                    min_output_chars: 5,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        assert_eq!(result.stats.train, 0);
+    }
+
+    #[test]
+    fn test_filter_expression_and_dedup() {
+        let flows = [
+            openai_flow("f1", "same", "answer"),
+            openai_flow("f2", "same", "answer"),
+            anthropic_flow("a1"),
+        ];
+
+        let result = export(&flows, DatasetOptions::default());
+        assert_eq!(result.stats.skipped_duplicate, 1);
+        assert_eq!(result.stats.train, 2);
+
+        let result = export(
+            &flows,
+            DatasetOptions {
+                filter_expr: Some("~m claude".to_string()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(result.stats.skipped_filter, 2);
+        assert_eq!(result.stats.train, 1);
+
+        let err = DatasetExporter::new(DatasetOptions {
+            filter_expr: Some("~unknown (".to_string()),
+            ..Default::default()
+        })
+        .export(&flows);
+        assert!(matches!(err, Err(DatasetError::InvalidFilter(_))));
+    }
+
+    #[test]
+    fn test_train_validation_split_is_deterministic() {
+        let flows: Vec<LLMFlow> = (0..200)
+            .map(|i| openai_flow(&format!("flow-{}", i), &format!("q{}", i), "a"))
+            .collect();
+        let options = DatasetOptions {
+            validation_ratio: 0.25,
+            seed: 7,
+            ..Default::default()
+        };
+
+        let first = export(&flows, options.clone());
+        let second = export(&flows, options);
+        assert_eq!(first.stats, second.stats);
+        assert_eq!(first.stats.train + first.stats.validation, 200);
+        assert!(first.stats.validation > 20 && first.stats.validation < 80);
+        assert_eq!(
+            first.validation_jsonl().lines().count(),
+            first.stats.validation
+        );
+    }
+
+    #[test]
+    fn test_redaction_applied_by_default() {
+        let flow = openai_flow("f1", "my key is sk-abcdefghijklmnopqrstuvwxyz123456", "ok");
+
+        let redacted = export(std::slice::from_ref(&flow), DatasetOptions::default());
+        assert!(!redacted
+            .train_jsonl()
+            .contains("sk-abcdefghijklmnopqrstuvwxyz123456"));
+
+        let raw = export(
+            &[flow],
+            DatasetOptions {
+                redact: false,
+                ..Default::default()
+            },
+        );
+        assert!(raw
+            .train_jsonl()
+            .contains("sk-abcdefghijklmnopqrstuvwxyz123456"));
+    }
+}
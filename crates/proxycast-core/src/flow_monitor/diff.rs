@@ -12,6 +12,7 @@
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashSet;
 
 use super::models::{LLMFlow, Message, MessageContent, TokenUsage};
 
@@ -918,6 +919,46 @@ impl FlowDiff {
     }
 }
 
+// ============================================================================
+// 输出相似度
+// ============================================================================
+
+/// 将输出文本切分为词元集合
+///
+/// ASCII 字母数字按单词切分（忽略大小写），其他文字（如中文）按单个字符切分
+fn output_tokens(text: &str) -> HashSet<String> {
+    let mut tokens = HashSet::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            word.push(c.to_ascii_lowercase());
+            continue;
+        }
+        if !word.is_empty() {
+            tokens.insert(std::mem::take(&mut word));
+        }
+        if c.is_alphanumeric() {
+            tokens.insert(c.to_string());
+        }
+    }
+    if !word.is_empty() {
+        tokens.insert(word);
+    }
+    tokens
+}
+
+/// 计算两段输出的相似度（词元集合的 Jaccard 系数，0.0 - 1.0）
+pub fn output_similarity(left: &str, right: &str) -> f64 {
+    let left = output_tokens(left);
+    let right = output_tokens(right);
+    if left.is_empty() && right.is_empty() {
+        return 1.0;
+    }
+    let intersection = left.intersection(&right).count();
+    let union = left.union(&right).count();
+    intersection as f64 / union as f64
+}
+
 // ============================================================================
 // 单元测试
 // ============================================================================
@@ -1131,6 +1172,16 @@ mod tests {
         assert_eq!(diffs[0].diff_type, DiffType::Unchanged);
         assert_eq!(diffs[1].diff_type, DiffType::Removed);
     }
+
+    #[test]
+    fn test_output_similarity() {
+        assert_eq!(output_similarity("Hello world", "hello, WORLD!"), 1.0);
+        assert_eq!(output_similarity("foo bar", "baz qux"), 0.0);
+        assert_eq!(output_similarity("", ""), 1.0);
+        assert!((output_similarity("a b c d", "a b c e") - 0.6).abs() < 1e-9);
+        // 中文按字符切分
+        assert!((output_similarity("你好世界", "你好") - 0.5).abs() < 1e-9);
+    }
 }
 
 // ============================================================================
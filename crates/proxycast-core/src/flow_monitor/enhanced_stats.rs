@@ -6,9 +6,10 @@
 
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
+use super::diff::output_similarity;
 use super::memory_store::{FlowFilter, FlowMemoryStore, TimeRange};
 use super::models::{FlowState, LLMFlow};
 use tokio::sync::RwLock;
@@ -132,6 +133,88 @@ impl Default for ReportFormat {
     }
 }
 
+/// 影子 Flow 标签
+pub const SHADOW_FLOW_TAG: &str = "shadow";
+
+/// 镜像规则标签前缀（`mirror:<rule>`）
+pub const MIRROR_RULE_TAG_PREFIX: &str = "mirror:";
+
+/// 影子 Flow 指向主 Flow 的标签前缀（`shadow-of:<flow_id>`）
+pub const SHADOW_OF_TAG_PREFIX: &str = "shadow-of:";
+
+/// 生成影子 Flow 的关联标签
+pub fn shadow_flow_tags(rule: &str, primary_flow_id: &str) -> Vec<String> {
+    vec![
+        SHADOW_FLOW_TAG.to_string(),
+        format!("{}{}", MIRROR_RULE_TAG_PREFIX, rule),
+        format!("{}{}", SHADOW_OF_TAG_PREFIX, primary_flow_id),
+    ]
+}
+
+/// 从影子 Flow 的标签中解析 (镜像规则, 主 Flow ID)
+pub fn parse_shadow_link(flow: &LLMFlow) -> Option<(String, String)> {
+    let tags = &flow.annotations.tags;
+    if !tags.iter().any(|t| t == SHADOW_FLOW_TAG) {
+        return None;
+    }
+    let rule = tags
+        .iter()
+        .find_map(|t| t.strip_prefix(MIRROR_RULE_TAG_PREFIX))?;
+    let primary_id = tags
+        .iter()
+        .find_map(|t| t.strip_prefix(SHADOW_OF_TAG_PREFIX))?;
+    Some((rule.to_string(), primary_id.to_string()))
+}
+
+/// 镜像对中一侧（主请求或影子请求）的聚合指标
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MirrorSideStats {
+    /// 请求数
+    pub requests: u64,
+    /// 失败数
+    pub errors: u64,
+    /// 错误率
+    pub error_rate: f64,
+    /// 平均延迟（毫秒，仅统计成功请求）
+    pub avg_latency_ms: f64,
+    /// P95 延迟（毫秒，仅统计成功请求）
+    pub p95_latency_ms: u64,
+    /// 平均输入 Token 数
+    pub avg_input_tokens: f64,
+    /// 平均输出 Token 数
+    pub avg_output_tokens: f64,
+    /// Token 总数
+    pub total_tokens: u64,
+}
+
+/// 单条镜像规则的对比报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirrorRuleReport {
+    /// 镜像规则名称
+    pub rule: String,
+    /// 影子目标 Provider
+    pub target_provider: String,
+    /// 镜像对数量
+    pub pairs: u64,
+    /// 主请求指标
+    pub primary: MirrorSideStats,
+    /// 影子请求指标
+    pub shadow: MirrorSideStats,
+    /// 平均输出相似度（0.0 - 1.0，无可比较的镜像对时为空）
+    pub avg_similarity: Option<f64>,
+    /// 参与相似度计算的镜像对数量（双方均成功且有输出）
+    pub compared_pairs: u64,
+}
+
+/// 影子流量镜像报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirrorReport {
+    /// 按规则聚合的报告
+    pub rules: Vec<MirrorRuleReport>,
+    /// 时间范围
+    pub time_range: StatsTimeRange,
+}
+
 // ============================================================================
 // 增强统计服务
 // ============================================================================
@@ -282,6 +365,53 @@ impl EnhancedStatsService {
         }
     }
 
+    /// 获取影子流量镜像报告
+    ///
+    /// 以时间范围内的影子 Flow 为准，按 `shadow-of:` 标签找到对应的主 Flow，
+    /// 按镜像规则聚合双方的延迟、Token 用量、错误率和输出相似度。
+    /// 主 Flow 已被内存存储驱逐的镜像对会被跳过。
+    pub async fn get_mirror_report(&self, time_range: &StatsTimeRange) -> MirrorReport {
+        let filter = FlowFilter {
+            tags: Some(vec![SHADOW_FLOW_TAG.to_string()]),
+            ..Default::default()
+        };
+        let shadows = self.get_flows_in_range(&filter, time_range).await;
+
+        let pairs: Vec<(String, LLMFlow, LLMFlow)> = {
+            let store = self.memory_store.read().await;
+            shadows
+                .into_iter()
+                .filter_map(|shadow| {
+                    let (rule, primary_id) = parse_shadow_link(&shadow)?;
+                    let primary = store.get(&primary_id)?.read().ok()?.clone();
+                    Some((rule, primary, shadow))
+                })
+                .collect()
+        };
+
+        MirrorReport {
+            rules: self.calculate_mirror_report(&pairs),
+            time_range: time_range.clone(),
+        }
+    }
+
+    /// 导出影子流量镜像报告
+    pub async fn export_mirror_report(
+        &self,
+        time_range: &StatsTimeRange,
+        format: &ReportFormat,
+    ) -> String {
+        let report = self.get_mirror_report(time_range).await;
+
+        match format {
+            ReportFormat::Json => {
+                serde_json::to_string_pretty(&report).unwrap_or_else(|_| "{}".to_string())
+            }
+            ReportFormat::Markdown => self.export_mirror_markdown(&report),
+            ReportFormat::Csv => self.export_mirror_csv(&report),
+        }
+    }
+
     // ========================================================================
     // 内部方法
     // ========================================================================
@@ -481,6 +611,145 @@ impl EnhancedStatsService {
         flows.len() as f64 / duration_secs
     }
 
+    /// 按镜像规则聚合 (规则, 主 Flow, 影子 Flow) 对
+    fn calculate_mirror_report(
+        &self,
+        pairs: &[(String, LLMFlow, LLMFlow)],
+    ) -> Vec<MirrorRuleReport> {
+        let mut by_rule: BTreeMap<&str, Vec<(&LLMFlow, &LLMFlow)>> = BTreeMap::new();
+        for (rule, primary, shadow) in pairs {
+            by_rule
+                .entry(rule.as_str())
+                .or_default()
+                .push((primary, shadow));
+        }
+
+        by_rule
+            .into_iter()
+            .map(|(rule, pairs)| {
+                let primaries: Vec<&LLMFlow> = pairs.iter().map(|(p, _)| *p).collect();
+                let shadows: Vec<&LLMFlow> = pairs.iter().map(|(_, s)| *s).collect();
+
+                let similarities: Vec<f64> = pairs
+                    .iter()
+                    .filter(|(p, s)| is_successful(p) && is_successful(s))
+                    .filter_map(|(p, s)| {
+                        let left = p.response.as_ref()?.content.as_str();
+                        let right = s.response.as_ref()?.content.as_str();
+                        if left.is_empty() || right.is_empty() {
+                            return None;
+                        }
+                        Some(output_similarity(left, right))
+                    })
+                    .collect();
+                let avg_similarity = (!similarities.is_empty())
+                    .then(|| similarities.iter().sum::<f64>() / similarities.len() as f64);
+
+                MirrorRuleReport {
+                    rule: rule.to_string(),
+                    target_provider: shadows
+                        .first()
+                        .map(|f| f.metadata.provider.to_string())
+                        .unwrap_or_default(),
+                    pairs: pairs.len() as u64,
+                    primary: calculate_side_stats(&primaries),
+                    shadow: calculate_side_stats(&shadows),
+                    avg_similarity,
+                    compared_pairs: similarities.len() as u64,
+                }
+            })
+            .collect()
+    }
+
+    /// 镜像报告导出为 Markdown 格式
+    fn export_mirror_markdown(&self, report: &MirrorReport) -> String {
+        let mut md = String::new();
+
+        md.push_str("# 影子流量镜像报告\n\n");
+        md.push_str(&format!(
+            "**时间范围**: {} - {}\n\n",
+            report.time_range.start.format("%Y-%m-%d %H:%M:%S"),
+            report.time_range.end.format("%Y-%m-%d %H:%M:%S")
+        ));
+
+        for rule in &report.rules {
+            md.push_str(&format!(
+                "## {}（影子目标: {}）\n\n",
+                rule.rule, rule.target_provider
+            ));
+            md.push_str(&format!("**镜像对**: {}\n\n", rule.pairs));
+            match rule.avg_similarity {
+                Some(similarity) => md.push_str(&format!(
+                    "**平均输出相似度**: {:.1}%（{} 对）\n\n",
+                    similarity * 100.0,
+                    rule.compared_pairs
+                )),
+                None => md.push_str("**平均输出相似度**: -\n\n"),
+            }
+            md.push_str("| 指标 | 主请求 | 影子请求 |\n");
+            md.push_str("|------|--------|----------|\n");
+            let (p, s) = (&rule.primary, &rule.shadow);
+            md.push_str(&format!(
+                "| 错误率 | {:.1}% | {:.1}% |\n",
+                p.error_rate * 100.0,
+                s.error_rate * 100.0
+            ));
+            md.push_str(&format!(
+                "| 平均延迟 | {:.0}ms | {:.0}ms |\n",
+                p.avg_latency_ms, s.avg_latency_ms
+            ));
+            md.push_str(&format!(
+                "| P95 延迟 | {}ms | {}ms |\n",
+                p.p95_latency_ms, s.p95_latency_ms
+            ));
+            md.push_str(&format!(
+                "| 平均输入 Token | {:.1} | {:.1} |\n",
+                p.avg_input_tokens, s.avg_input_tokens
+            ));
+            md.push_str(&format!(
+                "| 平均输出 Token | {:.1} | {:.1} |\n",
+                p.avg_output_tokens, s.avg_output_tokens
+            ));
+            md.push_str(&format!(
+                "| Token 总数 | {} | {} |\n\n",
+                p.total_tokens, s.total_tokens
+            ));
+        }
+
+        md
+    }
+
+    /// 镜像报告导出为 CSV 格式
+    fn export_mirror_csv(&self, report: &MirrorReport) -> String {
+        let mut csv = String::new();
+
+        csv.push_str("Rule,TargetProvider,Side,Requests,ErrorRate,AvgLatencyMs,P95LatencyMs,AvgInputTokens,AvgOutputTokens,TotalTokens,AvgSimilarity\n");
+        for rule in &report.rules {
+            let similarity = rule
+                .avg_similarity
+                .map(|s| format!("{:.4}", s))
+                .unwrap_or_default();
+            for (side, stats) in [("primary", &rule.primary), ("shadow", &rule.shadow)] {
+                csv.push_str(&format!(
+                    "{},{},{},{},{:.4},{:.1},{},{:.1},{:.1},{},{}\n",
+                    rule.rule,
+                    rule.target_provider,
+                    side,
+                    stats.requests,
+                    stats.error_rate,
+                    stats.avg_latency_ms,
+                    stats.p95_latency_ms,
+                    stats.avg_input_tokens,
+                    stats.avg_output_tokens,
+                    stats.total_tokens,
+                    similarity
+                ));
+            }
+        }
+
+        csv
+    }
+
     /// 导出为 JSON 格式
     fn export_json(&self, stats: &EnhancedStats) -> String {
         serde_json::to_string_pretty(stats).unwrap_or_else(|_| "{}".to_string())
@@ -614,6 +883,58 @@ fn parse_interval(interval: &str) -> Duration {
     Duration::hours(1)
 }
 
+/// Flow 是否成功完成
+fn is_successful(flow: &LLMFlow) -> bool {
+    flow.state == FlowState::Completed && flow.error.is_none()
+}
+
+/// 计算镜像对一侧的聚合指标
+fn calculate_side_stats(flows: &[&LLMFlow]) -> MirrorSideStats {
+    let requests = flows.len() as u64;
+    if requests == 0 {
+        return MirrorSideStats::default();
+    }
+
+    let successful: Vec<&LLMFlow> = flows.iter().copied().filter(|f| is_successful(f)).collect();
+    let errors = requests - successful.len() as u64;
+
+    let mut latencies: Vec<u64> = successful
+        .iter()
+        .map(|f| f.timestamps.duration_ms)
+        .collect();
+    latencies.sort_unstable();
+    let (avg_latency_ms, p95_latency_ms) = if latencies.is_empty() {
+        (0.0, 0)
+    } else {
+        let avg = latencies.iter().sum::<u64>() as f64 / latencies.len() as f64;
+        let idx = ((latencies.len() as f64 * 0.95).ceil() as usize).saturating_sub(1);
+        (avg, latencies[idx.min(latencies.len() - 1)])
+    };
+
+    let usages: Vec<_> = successful
+        .iter()
+        .filter_map(|f| f.response.as_ref().map(|r| &r.usage))
+        .collect();
+    let avg = |values: Vec<u32>| {
+        if values.is_empty() {
+            0.0
+        } else {
+            values.iter().map(|v| *v as f64).sum::<f64>() / values.len() as f64
+        }
+    };
+
+    MirrorSideStats {
+        requests,
+        errors,
+        error_rate: errors as f64 / requests as f64,
+        avg_latency_ms,
+        p95_latency_ms,
+        avg_input_tokens: avg(usages.iter().map(|u| u.input_tokens).collect()),
+        avg_output_tokens: avg(usages.iter().map(|u| u.output_tokens).collect()),
+        total_tokens: usages.iter().map(|u| u.total_tokens as u64).sum(),
+    }
+}
+
 /// 默认延迟桶边界（毫秒）
 fn default_latency_buckets() -> Vec<u64> {
     vec![100, 500, 1000, 2000, 5000, 10000]
@@ -671,6 +992,131 @@ mod tests {
         assert_eq!(format, ReportFormat::Json);
     }
 
+    fn mirror_flow(
+        id: &str,
+        provider: crate::ProviderType,
+        content: &str,
+        duration_ms: u64,
+        failed: bool,
+    ) -> LLMFlow {
+        use crate::flow_monitor::models::{
+            FlowError, FlowErrorType, FlowMetadata, FlowType, LLMRequest, LLMResponse, TokenUsage,
+        };
+
+        let metadata = FlowMetadata {
+            provider,
+            ..Default::default()
+        };
+        let mut flow = LLMFlow::new(
+            id.to_string(),
+            FlowType::ChatCompletions,
+            LLMRequest::default(),
+            metadata,
+        );
+        flow.timestamps.duration_ms = duration_ms;
+        if failed {
+            flow.state = FlowState::Failed;
+            flow.error = Some(FlowError::new(FlowErrorType::ServerError, "boom"));
+        } else {
+            flow.state = FlowState::Completed;
+            flow.response = Some(LLMResponse {
+                content: content.to_string(),
+                usage: TokenUsage {
+                    input_tokens: 10,
+                    output_tokens: 20,
+                    total_tokens: 30,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+        }
+        flow
+    }
+
+    #[test]
+    fn test_parse_shadow_link() {
+        let mut flow = mirror_flow("s1", crate::ProviderType::Qwen, "x", 10, false);
+        assert!(parse_shadow_link(&flow).is_none());
+
+        flow.annotations.tags = shadow_flow_tags("kiro-vs-qwen", "p1");
+        assert_eq!(
+            parse_shadow_link(&flow),
+            Some(("kiro-vs-qwen".to_string(), "p1".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mirror_report_aggregates_pairs_per_rule() {
+        use crate::ProviderType;
+
+        let store = Arc::new(RwLock::new(FlowMemoryStore::new(100)));
+        {
+            let mut store = store.write().await;
+            let pairs = [
+                (
+                    "p1",
+                    "s1",
+                    "qwen",
+                    "the answer is 42",
+                    "the answer is 42",
+                    false,
+                ),
+                ("p2", "s2", "qwen", "red green blue", "red green", false),
+                ("p3", "s3", "qwen", "ok", "", true),
+                ("p4", "s4", "antigravity", "hello", "hello", false),
+            ];
+            for (i, (pid, sid, rule, primary, shadow, shadow_failed)) in
+                pairs.into_iter().enumerate()
+            {
+                let provider = if rule == "qwen" {
+                    ProviderType::Qwen
+                } else {
+                    ProviderType::Antigravity
+                };
+                store.add(mirror_flow(pid, ProviderType::Kiro, primary, 100, false));
+                let mut flow =
+                    mirror_flow(sid, provider, shadow, 200 + i as u64 * 100, shadow_failed);
+                flow.annotations.tags = shadow_flow_tags(rule, pid);
+                store.add(flow);
+            }
+            // 主 Flow 不存在的影子 Flow 会被跳过
+            let mut orphan = mirror_flow("s5", ProviderType::Qwen, "x", 10, false);
+            orphan.annotations.tags = shadow_flow_tags("qwen", "missing");
+            store.add(orphan);
+        }
+
+        let service = EnhancedStatsService::new(store);
+        let report = service.get_mirror_report(&StatsTimeRange::default()).await;
+        assert_eq!(report.rules.len(), 2);
+
+        let antigravity = &report.rules[0];
+        assert_eq!(antigravity.rule, "antigravity");
+        assert_eq!(antigravity.target_provider, "antigravity");
+        assert_eq!(antigravity.pairs, 1);
+        assert_eq!(antigravity.avg_similarity, Some(1.0));
+
+        let qwen = &report.rules[1];
+        assert_eq!(qwen.rule, "qwen");
+        assert_eq!(qwen.target_provider, "qwen");
+        assert_eq!(qwen.pairs, 3);
+        assert_eq!(qwen.primary.errors, 0);
+        assert_eq!(qwen.primary.avg_latency_ms, 100.0);
+        assert_eq!(qwen.primary.total_tokens, 90);
+        assert_eq!(qwen.shadow.errors, 1);
+        assert!((qwen.shadow.error_rate - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(qwen.shadow.avg_latency_ms, 250.0);
+        assert_eq!(qwen.shadow.p95_latency_ms, 300);
+        assert_eq!(qwen.shadow.avg_output_tokens, 20.0);
+        assert_eq!(qwen.compared_pairs, 2);
+        let expected = (1.0 + 2.0 / 3.0) / 2.0;
+        assert!((qwen.avg_similarity.unwrap() - expected).abs() < 1e-9);
+
+        let csv = service
+            .export_mirror_report(&StatsTimeRange::default(), &ReportFormat::Csv)
+            .await;
+        assert_eq!(csv.lines().count(), 5);
+    }
+
     #[test]
     fn test_stats_time_range_default() {
         let range = StatsTimeRange::default();
@@ -2,14 +2,21 @@
 //!
 //! 该模块实现 LLM Flow 的文件持久化存储，支持 JSONL 格式写入、
 //! SQLite 索引、文件轮转和自动清理功能。
+//!
+//! 存储分为两层：热存储为按日期目录轮转的 JSONL 文件；启用 `compress_old` 后，
+//! 超过保留天数的日期目录被压缩为冷存储归档（由独立 gzip 块组成，块位置记录在
+//! SQLite 块索引中）。查询、按 ID 获取和全文搜索对两层透明。
 
 use chrono::{DateTime, NaiveDate, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File, OpenOptions};
-use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
 
 use super::memory_store::FlowFilter;
@@ -54,10 +61,13 @@ pub struct RotationConfig {
     pub rotate_daily: bool,
     /// 单个文件最大大小（字节）
     pub max_file_size: u64,
-    /// 保留天数
+    /// 保留天数（启用 `compress_old` 时为热存储保留天数，超过后归档而不是删除）
     pub retention_days: u32,
-    /// 是否压缩旧文件
+    /// 是否压缩旧文件（归档为冷存储）
     pub compress_old: bool,
+    /// 冷存储归档保留天数（0 表示永久保留）
+    #[serde(default)]
+    pub archive_retention_days: u32,
 }
 
 impl Default for RotationConfig {
@@ -66,7 +76,8 @@ impl Default for RotationConfig {
             rotate_daily: true,
             max_file_size: 100 * 1024 * 1024, // 100MB
             retention_days: 7,
-            compress_old: false, // 暂不实现压缩
+            compress_old: false,
+            archive_retention_days: 0,
         }
     }
 }
@@ -82,6 +93,49 @@ pub struct CleanupResult {
     pub bytes_freed: u64,
 }
 
+/// 归档结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ArchiveResult {
+    /// 归档的 JSONL 文件数
+    pub files_archived: usize,
+    /// 新建的归档文件数
+    pub archives_created: usize,
+    /// 归档的 Flow 数
+    pub flows_archived: usize,
+    /// 归档前大小（字节）
+    pub bytes_before: u64,
+    /// 归档后大小（字节）
+    pub bytes_after: u64,
+}
+
+/// 单层存储用量
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StorageTierUsage {
+    /// 文件数
+    pub files: usize,
+    /// 磁盘占用（字节）
+    pub bytes: u64,
+    /// Flow 数
+    pub flows: usize,
+}
+
+/// 分层存储用量
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StorageUsage {
+    /// 热存储（JSONL）
+    pub hot: StorageTierUsage,
+    /// 冷存储（压缩归档）
+    pub cold: StorageTierUsage,
+    /// 冷存储块数
+    pub cold_blocks: usize,
+    /// 冷存储解压后大小（字节）
+    pub cold_uncompressed_bytes: u64,
+    /// 索引数据库大小（字节）
+    pub index_bytes: u64,
+    /// 总磁盘占用（字节）
+    pub total_bytes: u64,
+}
+
 // ============================================================================
 // 索引记录
 // ============================================================================
@@ -225,6 +279,67 @@ impl FlowWriter {
     }
 }
 
+// ============================================================================
+// 冷存储归档
+// ============================================================================
+
+/// 归档文件名前缀
+const ARCHIVE_FILE_PREFIX: &str = "flows_archive_";
+
+/// 归档文件扩展名
+const ARCHIVE_FILE_EXTENSION: &str = "gz";
+
+/// 单个归档块最多包含的 Flow 数
+const ARCHIVE_BLOCK_MAX_FLOWS: usize = 256;
+
+/// 单个归档块最大解压后大小（字节）
+const ARCHIVE_BLOCK_MAX_BYTES: usize = 1024 * 1024;
+
+/// Flow 在存储中的位置
+///
+/// 热存储中 `file_offset` 为行偏移；冷存储中 `file_offset` 为压缩块偏移，
+/// `archive_offset` 为解压后块内的行偏移。
+struct FlowLocation {
+    file_path: String,
+    file_offset: i64,
+    archive_offset: Option<i64>,
+}
+
+/// 最近解压的归档块（顺序读取同一块时避免重复解压）
+struct CachedBlock {
+    file_path: String,
+    block_offset: i64,
+    data: Arc<Vec<u8>>,
+}
+
+/// 归档块元数据
+struct ArchiveBlock {
+    offset: i64,
+    compressed_size: i64,
+    uncompressed_size: i64,
+    entries: Vec<ArchiveEntry>,
+}
+
+/// 归档块中的一行
+struct ArchiveEntry {
+    id: String,
+    /// 原 JSONL 文件路径（提交时确认索引未被并发修改）
+    file_path: String,
+    /// 原 JSONL 文件中的偏移
+    file_offset: i64,
+    /// 块内行偏移
+    archive_offset: i64,
+}
+
+fn is_archive_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == ARCHIVE_FILE_EXTENSION)
+}
+
+fn is_jsonl_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "jsonl")
+}
+
 // ============================================================================
 // Flow 文件存储
 // ============================================================================
@@ -245,6 +360,10 @@ pub struct FlowFileStore {
     rotation_config: RotationConfig,
     /// SQLite 连接
     index_db: Mutex<Connection>,
+    /// 最近解压的归档块
+    block_cache: Mutex<Option<CachedBlock>>,
+    /// 串行化归档任务
+    archive_lock: Mutex<()>,
 }
 
 impl FlowFileStore {
@@ -273,6 +392,8 @@ impl FlowFileStore {
             current_file_index: Mutex::new(1),
             rotation_config: config,
             index_db: Mutex::new(conn),
+            block_cache: Mutex::new(None),
+            archive_lock: Mutex::new(()),
         })
     }
 
@@ -333,9 +454,30 @@ impl FlowFileStore {
                 request_text,
                 model
             );
+
+            -- 冷存储块索引
+            CREATE TABLE IF NOT EXISTS flow_archive_blocks (
+                archive_path TEXT NOT NULL,
+                block_offset INTEGER NOT NULL,
+                compressed_size INTEGER NOT NULL,
+                uncompressed_size INTEGER NOT NULL,
+                flow_count INTEGER NOT NULL,
+                PRIMARY KEY (archive_path, block_offset)
+            );
             "#,
         )?;
 
+        // 旧版本索引没有块内偏移列
+        let has_archive_offset = conn
+            .prepare("SELECT 1 FROM pragma_table_info('flow_index') WHERE name = 'archive_offset'")?
+            .exists([])?;
+        if !has_archive_offset {
+            conn.execute(
+                "ALTER TABLE flow_index ADD COLUMN archive_offset INTEGER",
+                [],
+            )?;
+        }
+
         Ok(())
     }
 
@@ -541,24 +683,57 @@ impl FlowFileStore {
 
     /// 根据 ID 获取 Flow
     pub fn get(&self, id: &str) -> Result<Option<LLMFlow>> {
-        let conn = self.index_db.lock().unwrap();
+        let location = {
+            let conn = self.index_db.lock().unwrap();
+            conn.query_row(
+                "SELECT file_path, file_offset, archive_offset FROM flow_index WHERE id = ?1",
+                params![id],
+                |row| {
+                    Ok(FlowLocation {
+                        file_path: row.get(0)?,
+                        file_offset: row.get(1)?,
+                        archive_offset: row.get(2)?,
+                    })
+                },
+            )
+            .optional()?
+        };
+
+        match location {
+            Some(location) => self.read_flow(&location),
+            None => Ok(None),
+        }
+    }
 
-        let result: Option<(String, i64)> = conn
+    /// 检查索引中是否已存在该 Flow
+    pub fn contains(&self, id: &str) -> Result<bool> {
+        let conn = self.index_db.lock().unwrap();
+        let exists: Option<i32> = conn
             .query_row(
-                "SELECT file_path, file_offset FROM flow_index WHERE id = ?1",
+                "SELECT 1 FROM flow_index WHERE id = ?1",
                 params![id],
-                |row| Ok((row.get(0)?, row.get(1)?)),
+                |row| row.get(0),
             )
             .optional()?;
+        Ok(exists.is_some())
+    }
 
-        match result {
-            Some((file_path, file_offset)) => self.read_flow_from_file(&file_path, file_offset),
+    /// 按位置读取 Flow（热存储或冷存储）
+    fn read_flow(&self, location: &FlowLocation) -> Result<Option<LLMFlow>> {
+        let line = match location.archive_offset {
+            Some(archive_offset) => {
+                self.read_archived_line(&location.file_path, location.file_offset, archive_offset)?
+            }
+            None => Self::read_line_at(&location.file_path, location.file_offset)?,
+        };
+        match line {
+            Some(line) => Ok(Some(serde_json::from_str(&line)?)),
             None => Ok(None),
         }
     }
 
-    /// 从文件读取 Flow
-    fn read_flow_from_file(&self, file_path: &str, file_offset: i64) -> Result<Option<LLMFlow>> {
+    /// 从 JSONL 文件读取指定偏移量的一行
+    fn read_line_at(file_path: &str, file_offset: i64) -> Result<Option<String>> {
         let path = Path::new(file_path);
         if !path.exists() {
             return Ok(None);
@@ -578,8 +753,58 @@ impl FlowFileStore {
             return Ok(None);
         }
 
-        let mut flow: LLMFlow = serde_json::from_str(&line)?;
-        Ok(Some(flow))
+        Ok(Some(line))
+    }
+
+    /// 从归档块读取指定块内偏移量的一行
+    fn read_archived_line(
+        &self,
+        file_path: &str,
+        block_offset: i64,
+        archive_offset: i64,
+    ) -> Result<Option<String>> {
+        let Some(block) = self.read_archive_block(file_path, block_offset)? else {
+            return Ok(None);
+        };
+        let Some(rest) = block.get(archive_offset as usize..) else {
+            return Ok(None);
+        };
+        let end = rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+        if end == 0 {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(&rest[..end]).into_owned()))
+    }
+
+    /// 解压归档块（带单块缓存）
+    fn read_archive_block(
+        &self,
+        file_path: &str,
+        block_offset: i64,
+    ) -> Result<Option<Arc<Vec<u8>>>> {
+        if let Some(cached) = self.block_cache.lock().unwrap().as_ref() {
+            if cached.file_path == file_path && cached.block_offset == block_offset {
+                return Ok(Some(cached.data.clone()));
+            }
+        }
+
+        let path = Path::new(file_path);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(block_offset as u64))?;
+        // 每个块是独立的 gzip 成员，GzDecoder 只解压当前成员
+        let mut data = Vec::new();
+        GzDecoder::new(BufReader::new(file)).read_to_end(&mut data)?;
+
+        let data = Arc::new(data);
+        *self.block_cache.lock().unwrap() = Some(CachedBlock {
+            file_path: file_path.to_string(),
+            block_offset,
+            data: data.clone(),
+        });
+        Ok(Some(data))
     }
 
     /// 查询 Flow（从索引）
@@ -589,8 +814,8 @@ impl FlowFileStore {
 
         // 读取 Flow
         let mut flows = Vec::new();
-        for (file_path, file_offset) in file_locations {
-            if let Some(flow) = self.read_flow_from_file(&file_path, file_offset)? {
+        for location in file_locations {
+            if let Some(flow) = self.read_flow(&location)? {
                 // 再次用内存过滤器验证（处理复杂条件）
                 if filter.matches(&flow) {
                     flows.push(flow);
@@ -607,7 +832,7 @@ impl FlowFileStore {
         filter: &FlowFilter,
         limit: usize,
         offset: usize,
-    ) -> Result<Vec<(String, i64)>> {
+    ) -> Result<Vec<FlowLocation>> {
         let conn = self.index_db.lock().unwrap();
 
         // 构建查询条件
@@ -674,7 +899,7 @@ impl FlowFileStore {
         };
 
         let sql = format!(
-            "SELECT file_path, file_offset FROM flow_index {} ORDER BY created_at DESC LIMIT ? OFFSET ?",
+            "SELECT file_path, file_offset, archive_offset FROM flow_index {} ORDER BY created_at DESC LIMIT ? OFFSET ?",
             where_clause
         );
 
@@ -686,7 +911,11 @@ impl FlowFileStore {
             params_vec.iter().map(|p| p.as_ref()).collect();
         let mut stmt = conn.prepare(&sql)?;
         let rows = stmt.query_map(params_refs.as_slice(), |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            Ok(FlowLocation {
+                file_path: row.get(0)?,
+                file_offset: row.get(1)?,
+                archive_offset: row.get(2)?,
+            })
         })?;
 
         let mut results = Vec::new();
@@ -847,8 +1076,16 @@ impl FlowFileStore {
                 params![before.to_rfc3339()],
             )?;
 
+            for file_path in &file_paths {
+                conn.execute(
+                    "DELETE FROM flow_archive_blocks WHERE archive_path = ?1",
+                    params![file_path],
+                )?;
+            }
+
             file_paths
         }; // conn 在这里被释放
+        *self.block_cache.lock().unwrap() = None;
 
         // 删除文件
         for file_path in file_paths {
@@ -875,15 +1112,18 @@ impl FlowFileStore {
             for entry in entries.flatten() {
                 let path = entry.path();
                 if path.is_dir() {
-                    // 检查目录是否为空（除了 .sqlite 文件）
+                    // 检查目录是否为空（除了 .sqlite 文件，JSONL 和归档都算数据）
                     if let Ok(mut dir_entries) = fs::read_dir(&path) {
-                        let has_jsonl = dir_entries.any(|e| {
+                        let has_data = dir_entries.any(|e| {
                             e.ok()
-                                .map(|e| e.path().extension().map_or(false, |ext| ext == "jsonl"))
+                                .map(|e| {
+                                    let path = e.path();
+                                    is_jsonl_file(&path) || is_archive_file(&path)
+                                })
                                 .unwrap_or(false)
                         });
 
-                        if !has_jsonl {
+                        if !has_data {
                             // 删除目录中的所有文件
                             if let Ok(files) = fs::read_dir(&path) {
                                 for file in files.flatten() {
@@ -901,10 +1141,311 @@ impl FlowFileStore {
     }
 
     /// 根据保留天数清理
+    ///
+    /// 启用 `compress_old` 时，超过 `retention_days` 的热存储文件被归档而不是删除，
+    /// 归档数据按 `archive_retention_days` 清理（0 表示永久保留）。
     pub fn cleanup_by_retention(&self) -> Result<CleanupResult> {
         let retention_days = self.rotation_config.retention_days;
         let before = Utc::now() - chrono::Duration::days(retention_days as i64);
-        self.cleanup(before)
+        if !self.rotation_config.compress_old {
+            return self.cleanup(before);
+        }
+
+        let archived = self.archive(before)?;
+        if archived.files_archived > 0 {
+            tracing::info!(
+                "[FLOW_STORE] 已归档 {} 个文件（{} 个 Flow），{} -> {} 字节",
+                archived.files_archived,
+                archived.flows_archived,
+                archived.bytes_before,
+                archived.bytes_after
+            );
+        }
+        match self.rotation_config.archive_retention_days {
+            0 => Ok(CleanupResult::default()),
+            days => self.cleanup(Utc::now() - chrono::Duration::days(days as i64)),
+        }
+    }
+
+    /// 启动按保留天数定期清理的后台任务（启动时立即执行一次）
+    ///
+    /// 清理涉及文件 IO 和 SQLite，在阻塞线程池中执行
+    pub fn spawn_retention_cleanup(
+        self: Arc<Self>,
+        interval: std::time::Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let store = self.clone();
+                match tokio::task::spawn_blocking(move || store.cleanup_by_retention()).await {
+                    Ok(Ok(result)) if result.files_deleted > 0 => {
+                        tracing::info!(
+                            "[FLOW_STORE] 保留期清理删除 {} 个文件（{} 个 Flow），释放 {} 字节",
+                            result.files_deleted,
+                            result.flows_deleted,
+                            result.bytes_freed
+                        );
+                    }
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => tracing::warn!("[FLOW_STORE] 保留期清理失败: {}", e),
+                    Err(e) => tracing::warn!("[FLOW_STORE] 保留期清理任务异常: {}", e),
+                }
+            }
+        })
+    }
+
+    /// 把早于指定时间的日期目录中的 JSONL 文件压缩归档
+    ///
+    /// 只归档整个日期都早于 `before` 的目录，跳过当前写入中的文件。每个目录生成一个
+    /// 归档文件，由若干独立 gzip 块拼接而成（整体仍是合法的 gzip 文件）；
+    /// 已被覆盖写入的旧行不会进入归档。
+    pub fn archive(&self, before: DateTime<Utc>) -> Result<ArchiveResult> {
+        let _archiving = self.archive_lock.lock().unwrap();
+        let mut result = ArchiveResult::default();
+        let cutoff = before.date_naive();
+        let active_file = self
+            .current_writer
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|w| w.path().to_path_buf());
+
+        let mut date_dirs: Vec<(NaiveDate, PathBuf)> = fs::read_dir(&self.base_dir)?
+            .flatten()
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().to_string();
+                let date = NaiveDate::parse_from_str(&name, "%Y-%m-%d").ok()?;
+                (date < cutoff).then(|| (date, e.path()))
+            })
+            .collect();
+        date_dirs.sort();
+
+        for (_, dir) in date_dirs {
+            let mut files: Vec<PathBuf> = fs::read_dir(&dir)?
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| is_jsonl_file(p))
+                .filter(|p| active_file.as_deref() != Some(p.as_path()))
+                .collect();
+            if files.is_empty() {
+                continue;
+            }
+            files.sort();
+            self.archive_dir(&dir, &files, &mut result)?;
+        }
+
+        Ok(result)
+    }
+
+    /// 把一个日期目录中的 JSONL 文件合并为一个归档文件
+    ///
+    /// 读取、压缩和落盘期间不持有索引锁，只在最后的索引事务中加锁；期间被并发
+    /// 覆盖写入或清理的 Flow 不会更新索引（归档中对应的行成为无引用的旧数据）。
+    fn archive_dir(&self, dir: &Path, files: &[PathBuf], result: &mut ArchiveResult) -> Result<()> {
+        let archive_path = (1..)
+            .map(|i| {
+                dir.join(format!(
+                    "{}{:03}.jsonl.{}",
+                    ARCHIVE_FILE_PREFIX, i, ARCHIVE_FILE_EXTENSION
+                ))
+            })
+            .find(|p| !p.exists())
+            .expect("无限序列总能找到可用的归档文件名");
+        let archive_path_str = archive_path.to_string_lossy().to_string();
+
+        let mut sources: Vec<(String, Vec<(String, i64)>)> = Vec::new();
+        {
+            let conn = self.index_db.lock().unwrap();
+            let mut stmt = conn.prepare(
+                "SELECT id, file_offset FROM flow_index
+                 WHERE file_path = ?1 AND archive_offset IS NULL ORDER BY file_offset",
+            )?;
+            for file in files {
+                let file_path = file.to_string_lossy().to_string();
+                let rows = stmt
+                    .query_map(params![file_path], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect::<std::result::Result<_, _>>()?;
+                sources.push((file_path, rows));
+            }
+        }
+
+        let mut blocks: Vec<ArchiveBlock> = Vec::new();
+        let mut writer = BufWriter::new(File::create(&archive_path)?);
+        let mut archive_size: u64 = 0;
+        let mut block = Vec::new();
+        let mut entries = Vec::new();
+
+        for (file_path, rows) in sources {
+            for (id, file_offset) in rows {
+                let Some(line) = Self::read_line_at(&file_path, file_offset)? else {
+                    continue;
+                };
+                let line = line.trim_end_matches('\n');
+                entries.push(ArchiveEntry {
+                    id,
+                    file_path: file_path.clone(),
+                    file_offset,
+                    archive_offset: block.len() as i64,
+                });
+                block.extend_from_slice(line.as_bytes());
+                block.push(b'\n');
+
+                if entries.len() >= ARCHIVE_BLOCK_MAX_FLOWS
+                    || block.len() >= ARCHIVE_BLOCK_MAX_BYTES
+                {
+                    blocks.push(Self::write_block(
+                        &mut writer,
+                        &mut archive_size,
+                        &block,
+                        std::mem::take(&mut entries),
+                    )?);
+                    block.clear();
+                }
+            }
+        }
+        if !entries.is_empty() {
+            blocks.push(Self::write_block(
+                &mut writer,
+                &mut archive_size,
+                &block,
+                entries,
+            )?);
+        }
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        drop(writer);
+
+        // 索引更新成功后再删除原文件
+        let mut flows_archived = 0;
+        {
+            let mut conn = self.index_db.lock().unwrap();
+            let tx = conn.transaction()?;
+            for block in &blocks {
+                let mut flow_count = 0;
+                for entry in &block.entries {
+                    flow_count += tx.execute(
+                        "UPDATE flow_index SET file_path = ?2, file_offset = ?3, archive_offset = ?4
+                         WHERE id = ?1 AND file_path = ?5 AND file_offset = ?6
+                           AND archive_offset IS NULL",
+                        params![
+                            entry.id,
+                            archive_path_str,
+                            block.offset,
+                            entry.archive_offset,
+                            entry.file_path,
+                            entry.file_offset
+                        ],
+                    )?;
+                }
+                tx.execute(
+                    "INSERT INTO flow_archive_blocks (
+                        archive_path, block_offset, compressed_size, uncompressed_size, flow_count
+                    ) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        archive_path_str,
+                        block.offset,
+                        block.compressed_size,
+                        block.uncompressed_size,
+                        flow_count as i64
+                    ],
+                )?;
+                flows_archived += flow_count;
+            }
+            tx.commit()?;
+        }
+
+        for file in files {
+            result.bytes_before += fs::metadata(file).map(|m| m.len()).unwrap_or(0);
+            fs::remove_file(file)?;
+            result.files_archived += 1;
+        }
+        if blocks.is_empty() {
+            // 文件中没有仍然有效的 Flow
+            fs::remove_file(&archive_path)?;
+        } else {
+            result.archives_created += 1;
+            result.bytes_after += archive_size;
+        }
+        result.flows_archived += flows_archived;
+
+        Ok(())
+    }
+
+    /// 压缩并写入一个归档块
+    fn write_block(
+        writer: &mut BufWriter<File>,
+        archive_size: &mut u64,
+        data: &[u8],
+        entries: Vec<ArchiveEntry>,
+    ) -> Result<ArchiveBlock> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        let compressed = encoder.finish()?;
+        writer.write_all(&compressed)?;
+
+        let block = ArchiveBlock {
+            offset: *archive_size as i64,
+            compressed_size: compressed.len() as i64,
+            uncompressed_size: data.len() as i64,
+            entries,
+        };
+        *archive_size += compressed.len() as u64;
+        Ok(block)
+    }
+
+    /// 获取分层存储用量
+    pub fn storage_usage(&self) -> Result<StorageUsage> {
+        let mut usage = StorageUsage::default();
+
+        if let Ok(dirs) = fs::read_dir(&self.base_dir) {
+            for dir in dirs.flatten().filter(|e| e.path().is_dir()) {
+                for file in fs::read_dir(dir.path())?.flatten() {
+                    let path = file.path();
+                    let bytes = file.metadata().map(|m| m.len()).unwrap_or(0);
+                    if is_archive_file(&path) {
+                        usage.cold.files += 1;
+                        usage.cold.bytes += bytes;
+                    } else if is_jsonl_file(&path) {
+                        usage.hot.files += 1;
+                        usage.hot.bytes += bytes;
+                    }
+                }
+            }
+        }
+
+        {
+            let conn = self.index_db.lock().unwrap();
+            let (hot, cold): (i64, i64) = conn.query_row(
+                "SELECT
+                    COALESCE(SUM(archive_offset IS NULL), 0),
+                    COALESCE(SUM(archive_offset IS NOT NULL), 0)
+                 FROM flow_index",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            usage.hot.flows = hot as usize;
+            usage.cold.flows = cold as usize;
+
+            let (blocks, uncompressed): (i64, i64) = conn.query_row(
+                "SELECT COUNT(*), COALESCE(SUM(uncompressed_size), 0) FROM flow_archive_blocks",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            usage.cold_blocks = blocks as usize;
+            usage.cold_uncompressed_bytes = uncompressed as u64;
+        }
+
+        usage.index_bytes = ["global_index.sqlite", "global_index.sqlite-wal"]
+            .iter()
+            .filter_map(|name| fs::metadata(self.base_dir.join(name)).ok())
+            .map(|m| m.len())
+            .sum();
+        usage.total_bytes = usage.hot.bytes + usage.cold.bytes + usage.index_bytes;
+
+        Ok(usage)
     }
 }
 
@@ -966,6 +1507,9 @@ mod tests {
         let retrieved = retrieved.unwrap();
         assert_eq!(retrieved.id, "test-1");
         assert_eq!(retrieved.request.model, "gpt-4");
+
+        assert!(store.contains("test-1").unwrap());
+        assert!(!store.contains("test-2").unwrap());
     }
 
     #[test]
@@ -1073,6 +1617,122 @@ mod tests {
         assert_eq!(store.count().unwrap(), 0);
     }
 
+    #[test]
+    fn test_file_store_archive_reads_transparently() {
+        let temp_dir = TempDir::new().unwrap();
+        let store =
+            FlowFileStore::new(temp_dir.path().to_path_buf(), RotationConfig::default()).unwrap();
+
+        for i in 0..5 {
+            let provider = if i % 2 == 0 {
+                ProviderType::OpenAI
+            } else {
+                ProviderType::Claude
+            };
+            let mut flow = create_test_flow(&format!("flow-{}", i), "gpt-4", provider);
+            flow.response = Some(crate::flow_monitor::models::LLMResponse {
+                content: format!("archived answer number{}", i),
+                ..Default::default()
+            });
+            store.write(&flow).unwrap();
+        }
+        // 覆盖写入的旧行不应进入归档
+        let mut updated = store.get("flow-0").unwrap().unwrap();
+        updated.request.model = "gpt-4o".to_string();
+        store.write(&updated).unwrap();
+        store.rotate().unwrap();
+
+        let result = store
+            .archive(Utc::now() + chrono::Duration::days(2))
+            .unwrap();
+        assert_eq!(result.files_archived, 1);
+        assert_eq!(result.archives_created, 1);
+        assert_eq!(result.flows_archived, 5);
+        assert!(result.bytes_after < result.bytes_before);
+
+        let usage = store.storage_usage().unwrap();
+        assert_eq!((usage.hot.files, usage.hot.flows), (0, 0));
+        assert_eq!((usage.cold.files, usage.cold.flows), (1, 5));
+        assert_eq!(usage.cold_blocks, 1);
+
+        // 按 ID 获取、查询和全文搜索都能读取归档数据
+        assert_eq!(
+            store.get("flow-0").unwrap().unwrap().request.model,
+            "gpt-4o"
+        );
+        assert_eq!(store.get("flow-3").unwrap().unwrap().id, "flow-3");
+        let filter = FlowFilter {
+            providers: Some(vec![ProviderType::OpenAI]),
+            ..Default::default()
+        };
+        assert_eq!(store.query(&filter, 100, 0).unwrap().len(), 3);
+        let hits = store.search("number3", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "flow-3");
+
+        // 新写入的 Flow 进入热存储
+        store
+            .write(&create_test_flow("flow-new", "gpt-4", ProviderType::OpenAI))
+            .unwrap();
+        let usage = store.storage_usage().unwrap();
+        assert_eq!((usage.hot.flows, usage.cold.flows), (1, 5));
+        assert_eq!(
+            store.query(&FlowFilter::default(), 100, 0).unwrap().len(),
+            6
+        );
+
+        // 清理同时删除归档文件和块索引
+        let cleanup = store
+            .cleanup(Utc::now() + chrono::Duration::days(1))
+            .unwrap();
+        assert_eq!(cleanup.flows_deleted, 6);
+        let usage = store.storage_usage().unwrap();
+        assert_eq!((usage.cold.files, usage.cold_blocks), (0, 0));
+    }
+
+    #[test]
+    fn test_file_store_archive_splits_blocks() {
+        let temp_dir = TempDir::new().unwrap();
+        let store =
+            FlowFileStore::new(temp_dir.path().to_path_buf(), RotationConfig::default()).unwrap();
+
+        let total = ARCHIVE_BLOCK_MAX_FLOWS + 10;
+        for i in 0..total {
+            let flow = create_test_flow(&format!("flow-{}", i), "gpt-4", ProviderType::OpenAI);
+            store.write(&flow).unwrap();
+        }
+        store.rotate().unwrap();
+        store
+            .archive(Utc::now() + chrono::Duration::days(2))
+            .unwrap();
+
+        assert_eq!(store.storage_usage().unwrap().cold_blocks, 2);
+        for id in ["flow-0", "flow-255", "flow-256", "flow-265"] {
+            assert_eq!(store.get(id).unwrap().unwrap().id, id);
+        }
+
+        // 重新打开已有索引
+        drop(store);
+        let store =
+            FlowFileStore::new(temp_dir.path().to_path_buf(), RotationConfig::default()).unwrap();
+        assert_eq!(store.get("flow-265").unwrap().unwrap().id, "flow-265");
+
+        // 归档文件整体是合法的 gzip 流
+        let archive = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .flatten()
+            .filter(|e| e.path().is_dir())
+            .flat_map(|e| fs::read_dir(e.path()).unwrap().flatten())
+            .map(|e| e.path())
+            .find(|p| is_archive_file(p))
+            .unwrap();
+        let mut text = String::new();
+        flate2::read::MultiGzDecoder::new(File::open(archive).unwrap())
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text.lines().count(), total);
+    }
+
     #[test]
     fn test_index_record_from_flow() {
         let flow = create_test_flow("test-1", "gpt-4", ProviderType::OpenAI);
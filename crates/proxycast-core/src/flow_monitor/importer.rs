@@ -0,0 +1,1450 @@
+//! Flow 导入
+//!
+//! 把外部抓包导入 Flow 文件存储，便于本地分析、对比和重放：
+//! - HAR（浏览器开发者工具、mitmproxy 或本项目导出的 HAR），只导入 OpenAI / Anthropic /
+//!   Gemini 形态的请求，SSE 响应通过 `StreamRebuilder` 重建
+//! - JSONL（`FlowExporter::export_jsonl` 导出的 `LLMFlow`）
+//!
+//! 导入的 Flow 带 `imported` 和 `import:<来源>` 标签，按 Flow ID 去重。
+
+use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+use super::file_store::{FileStoreError, FlowFileStore};
+use super::models::{
+    ContentPart, FlowAnnotations, FlowError, FlowErrorType, FlowMetadata, FlowState, FlowType,
+    FunctionCall, FunctionDefinition, ImageUrl, LLMFlow, LLMRequest, LLMResponse, Message,
+    MessageContent, MessageRole, RequestParameters, StopReason, ThinkingContent, TokenUsage,
+    ToolCall, ToolDefinition, ToolResult,
+};
+use super::stream_rebuilder::{StreamFormat, StreamRebuilder};
+use crate::ProviderType;
+
+/// 导入 Flow 的标签
+pub const IMPORTED_FLOW_TAG: &str = "imported";
+
+/// 导入来源标签前缀（`import:<来源>`）
+pub const IMPORT_SOURCE_TAG_PREFIX: &str = "import:";
+
+/// 导入结果中最多保留的错误信息条数
+const MAX_IMPORT_ERRORS: usize = 50;
+
+// ============================================================================
+// 错误与选项
+// ============================================================================
+
+/// 导入错误
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("JSON 解析错误: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("无效的 HAR 文件: {0}")]
+    InvalidHar(String),
+
+    #[error("文件存储错误: {0}")]
+    FileStore(#[from] FileStoreError),
+}
+
+/// 导入格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    /// HTTP Archive
+    Har,
+    /// 每行一个 `LLMFlow`
+    Jsonl,
+}
+
+impl ImportFormat {
+    /// 根据内容推断格式（顶层带 `log` 的 JSON 对象视为 HAR）
+    pub fn detect(data: &str) -> Self {
+        let trimmed = data.trim_start();
+        if trimmed.starts_with('{')
+            && serde_json::from_str::<Value>(trimmed).is_ok_and(|v| v.get("log").is_some())
+        {
+            ImportFormat::Har
+        } else {
+            ImportFormat::Jsonl
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ImportFormat::Har => "har",
+            ImportFormat::Jsonl => "jsonl",
+        }
+    }
+}
+
+/// 导入选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportOptions {
+    /// 来源名称（用于 `import:<来源>` 标签，默认为格式名）
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// 导入结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImportResult {
+    /// 导入格式
+    pub format: ImportFormat,
+    /// 读取的条目数（HAR entry 或 JSONL 行）
+    pub total: usize,
+    /// 成功导入的数量
+    pub imported: usize,
+    /// 因 Flow ID 重复而跳过的数量
+    pub skipped_duplicate: usize,
+    /// 非 LLM 请求而跳过的数量
+    pub skipped_unsupported: usize,
+    /// 解析失败的数量
+    pub failed: usize,
+    /// 错误信息（最多保留 50 条）
+    pub errors: Vec<String>,
+    /// 导入的 Flow ID
+    pub flow_ids: Vec<String>,
+}
+
+impl ImportResult {
+    fn new(format: ImportFormat) -> Self {
+        Self {
+            format,
+            total: 0,
+            imported: 0,
+            skipped_duplicate: 0,
+            skipped_unsupported: 0,
+            failed: 0,
+            errors: Vec::new(),
+            flow_ids: Vec::new(),
+        }
+    }
+
+    fn record_error(&mut self, error: String) {
+        self.failed += 1;
+        if self.errors.len() < MAX_IMPORT_ERRORS {
+            self.errors.push(error);
+        }
+    }
+}
+
+// ============================================================================
+// 导入器
+// ============================================================================
+
+/// Flow 导入器
+pub struct FlowImporter {
+    options: ImportOptions,
+}
+
+impl FlowImporter {
+    /// 创建新的导入器
+    pub fn new(options: ImportOptions) -> Self {
+        Self { options }
+    }
+
+    /// 导入到文件存储
+    ///
+    /// 已存在于存储中的 Flow ID 会被跳过
+    pub fn import(
+        &self,
+        store: &FlowFileStore,
+        data: &str,
+        format: ImportFormat,
+    ) -> Result<ImportResult, ImportError> {
+        let (flows, mut result) = self.parse(data, format)?;
+        result.flow_ids.clear();
+        result.imported = 0;
+
+        for flow in flows {
+            if store.contains(&flow.id)? {
+                result.skipped_duplicate += 1;
+                continue;
+            }
+            store.write(&flow)?;
+            result.imported += 1;
+            result.flow_ids.push(flow.id);
+        }
+
+        Ok(result)
+    }
+
+    /// 解析为 Flow 列表（不写入存储）
+    ///
+    /// 返回的结果中 `imported` / `flow_ids` 为解析成功且在本次数据中不重复的 Flow
+    pub fn parse(
+        &self,
+        data: &str,
+        format: ImportFormat,
+    ) -> Result<(Vec<LLMFlow>, ImportResult), ImportError> {
+        let mut result = ImportResult::new(format);
+        let parsed = match format {
+            ImportFormat::Har => self.parse_har(data, &mut result)?,
+            ImportFormat::Jsonl => self.parse_jsonl(data, &mut result),
+        };
+
+        let source = self
+            .options
+            .source
+            .clone()
+            .filter(|s| !s.trim().is_empty())
+            .unwrap_or_else(|| format.as_str().to_string());
+        let source_tag = format!("{}{}", IMPORT_SOURCE_TAG_PREFIX, source.trim());
+
+        let mut seen = HashSet::new();
+        let mut flows = Vec::with_capacity(parsed.len());
+        for mut flow in parsed {
+            if !seen.insert(flow.id.clone()) {
+                result.skipped_duplicate += 1;
+                continue;
+            }
+            for tag in [IMPORTED_FLOW_TAG.to_string(), source_tag.clone()] {
+                if !flow.annotations.tags.contains(&tag) {
+                    flow.annotations.tags.push(tag);
+                }
+            }
+            result.flow_ids.push(flow.id.clone());
+            flows.push(flow);
+        }
+        result.imported = flows.len();
+
+        Ok((flows, result))
+    }
+
+    fn parse_jsonl(&self, data: &str, result: &mut ImportResult) -> Vec<LLMFlow> {
+        let mut flows = Vec::new();
+        for (index, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            result.total += 1;
+            match serde_json::from_str::<LLMFlow>(line) {
+                Ok(flow) => flows.push(flow),
+                Err(e) => result.record_error(format!("第 {} 行: {}", index + 1, e)),
+            }
+        }
+        flows
+    }
+
+    fn parse_har(
+        &self,
+        data: &str,
+        result: &mut ImportResult,
+    ) -> Result<Vec<LLMFlow>, ImportError> {
+        let har: Value = serde_json::from_str(data)?;
+        let entries = har
+            .pointer("/log/entries")
+            .and_then(Value::as_array)
+            .ok_or_else(|| ImportError::InvalidHar("缺少 log.entries".to_string()))?;
+
+        let mut flows = Vec::new();
+        for (index, entry) in entries.iter().enumerate() {
+            result.total += 1;
+            match har_entry_to_flow(entry) {
+                Ok(Some(flow)) => flows.push(flow),
+                Ok(None) => result.skipped_unsupported += 1,
+                Err(e) => result.record_error(format!("条目 {}: {}", index, e)),
+            }
+        }
+        Ok(flows)
+    }
+}
+
+// ============================================================================
+// HAR 转换
+// ============================================================================
+
+/// 把 HAR 条目转换为 Flow
+///
+/// 非 LLM 请求（路径不匹配或请求体不是 JSON）返回 `Ok(None)`
+fn har_entry_to_flow(entry: &Value) -> Result<Option<LLMFlow>, String> {
+    let har_request = entry.get("request").ok_or("缺少 request")?;
+    let url_str = har_request
+        .get("url")
+        .and_then(Value::as_str)
+        .ok_or("缺少 request.url")?;
+    let url = url::Url::parse(url_str).map_err(|e| format!("无效的 URL {}: {}", url_str, e))?;
+
+    let path = url.path();
+    let Some(flow_type) = detect_flow_type(path) else {
+        return Ok(None);
+    };
+    let body_text = har_request
+        .pointer("/postData/text")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let Ok(body) = serde_json::from_str::<Value>(body_text) else {
+        return Ok(None);
+    };
+    if !body.is_object() {
+        return Ok(None);
+    }
+
+    let started = entry
+        .get("startedDateTime")
+        .and_then(Value::as_str)
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now);
+    let llm_extension = entry.get("_llm");
+
+    let headers = har_headers(har_request);
+    let path_and_query = match url.query() {
+        Some(query) => format!("{}?{}", path, query),
+        None => path.to_string(),
+    };
+    let mut request = build_request(&flow_type, &path_and_query, &body);
+    request.method = har_request
+        .get("method")
+        .and_then(Value::as_str)
+        .unwrap_or("POST")
+        .to_string();
+    request.size_bytes = body_text.len();
+    request.timestamp = started;
+    if url.query().is_some_and(|q| q.contains("alt=sse")) || path.contains(":streamGenerateContent")
+    {
+        request.parameters.stream = true;
+    }
+
+    let provider = llm_extension
+        .and_then(|ext| ext.get("provider"))
+        .and_then(Value::as_str)
+        .and_then(|p| p.parse::<ProviderType>().ok())
+        .unwrap_or_else(|| detect_provider(url.host_str().unwrap_or_default(), &flow_type));
+    let mut metadata = FlowMetadata {
+        provider,
+        ..Default::default()
+    };
+    metadata.client_info.user_agent = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("user-agent"))
+        .map(|(_, v)| v.clone());
+    metadata.routing_info.target_url = Some(url.origin().ascii_serialization());
+    request.headers = headers;
+
+    let id = llm_extension
+        .and_then(|ext| ext.get("flow_id"))
+        .and_then(Value::as_str)
+        .map(String::from)
+        .unwrap_or_else(|| {
+            let digest = md5::compute(format!(
+                "{}\n{}\n{}",
+                started.to_rfc3339(),
+                url_str,
+                body_text
+            ));
+            format!("har-{:x}", digest)
+        });
+
+    let mut flow = LLMFlow::new(id, flow_type.clone(), request, metadata);
+
+    // 时间
+    let time_ms = entry
+        .get("time")
+        .and_then(Value::as_f64)
+        .unwrap_or(0.0)
+        .max(0.0) as u64;
+    let wait_ms = entry
+        .pointer("/timings/wait")
+        .and_then(Value::as_f64)
+        .filter(|w| *w >= 0.0)
+        .map(|w| w as u64);
+    flow.timestamps.created = started;
+    flow.timestamps.request_start = started;
+    flow.timestamps.request_end = Some(started);
+    flow.timestamps.response_end = Some(started + Duration::milliseconds(time_ms as i64));
+    flow.timestamps.duration_ms = time_ms;
+    if let Some(wait) = wait_ms {
+        flow.timestamps.response_start = Some(started + Duration::milliseconds(wait as i64));
+        flow.timestamps.ttfb_ms = Some(wait);
+    }
+
+    // 响应
+    let har_response = entry.get("response");
+    let status = har_response
+        .and_then(|r| r.get("status"))
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as u16;
+    let response_text = har_response.map(har_response_text).unwrap_or_default();
+    if (200..300).contains(&status) {
+        let mut response = parse_response_body(&flow_type, &response_text);
+        response.status_code = status;
+        let status_text = har_status_text(har_response);
+        if !status_text.is_empty() {
+            response.status_text = status_text;
+        }
+        response.headers = har_response.map(har_headers).unwrap_or_default();
+        response.size_bytes = response_text.len();
+        response.timestamp_start = flow.timestamps.response_start.unwrap_or(started);
+        response.timestamp_end = flow.timestamps.response_end.unwrap_or(started);
+        if let Some(info) = response.stream_info.as_mut() {
+            info.first_chunk_latency_ms = wait_ms.unwrap_or(0);
+        }
+        flow.response = Some(response);
+        flow.state = FlowState::Completed;
+    } else {
+        let error = if status == 0 {
+            FlowError::new(FlowErrorType::Network, "请求未收到响应")
+        } else {
+            FlowError::new(
+                FlowErrorType::from_status_code(status),
+                format!("HTTP {} {}", status, har_status_text(har_response)),
+            )
+            .with_status_code(status)
+            .with_raw_response(response_text)
+        };
+        flow.error = Some(error);
+        flow.state = FlowState::Failed;
+    }
+
+    // 本项目导出的 HAR 带有标注
+    if let Some(annotations) = llm_extension
+        .and_then(|ext| ext.get("annotations"))
+        .and_then(|a| serde_json::from_value::<FlowAnnotations>(a.clone()).ok())
+    {
+        flow.annotations = annotations;
+    }
+
+    Ok(Some(flow))
+}
+
+/// 根据路径识别 Flow 类型
+fn detect_flow_type(path: &str) -> Option<FlowType> {
+    if path.ends_with("/chat/completions") {
+        Some(FlowType::ChatCompletions)
+    } else if path.ends_with("/messages") {
+        Some(FlowType::AnthropicMessages)
+    } else if path.ends_with(":generateContent") || path.ends_with(":streamGenerateContent") {
+        Some(FlowType::GeminiGenerateContent)
+    } else if path.ends_with("/embeddings") {
+        Some(FlowType::Embeddings)
+    } else {
+        None
+    }
+}
+
+/// 根据主机名推断提供商，无法识别时按 Flow 类型推断
+fn detect_provider(host: &str, flow_type: &FlowType) -> ProviderType {
+    if host.ends_with("anthropic.com") {
+        ProviderType::Claude
+    } else if host.ends_with("openai.com") {
+        ProviderType::OpenAI
+    } else if host.contains("aiplatform.googleapis.com") {
+        ProviderType::Vertex
+    } else if host.ends_with("googleapis.com") {
+        ProviderType::Gemini
+    } else {
+        match flow_type {
+            FlowType::AnthropicMessages => ProviderType::Claude,
+            FlowType::GeminiGenerateContent => ProviderType::Gemini,
+            _ => ProviderType::OpenAI,
+        }
+    }
+}
+
+/// 提取 HAR 头部（排除认证相关的敏感头）
+fn har_headers(message: &Value) -> HashMap<String, String> {
+    message
+        .get("headers")
+        .and_then(Value::as_array)
+        .map(|headers| {
+            headers
+                .iter()
+                .filter_map(|h| {
+                    let name = h.get("name")?.as_str()?;
+                    let value = h.get("value")?.as_str()?;
+                    let lower = name.to_lowercase();
+                    let sensitive = lower.contains("authorization")
+                        || lower.contains("api-key")
+                        || lower == "cookie"
+                        || lower == "set-cookie";
+                    (!sensitive).then(|| (name.to_string(), value.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn har_status_text(response: Option<&Value>) -> String {
+    response
+        .and_then(|r| r.get("statusText"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// 提取 HAR 响应体（处理 base64 编码）
+fn har_response_text(response: &Value) -> String {
+    let Some(content) = response.get("content") else {
+        return String::new();
+    };
+    let text = content
+        .get("text")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if content.get("encoding").and_then(Value::as_str) == Some("base64") {
+        if let Ok(bytes) = BASE64_STANDARD.decode(text) {
+            return String::from_utf8_lossy(&bytes).into_owned();
+        }
+    }
+    text.to_string()
+}
+
+// ============================================================================
+// 请求解析
+// ============================================================================
+
+/// 从请求体构建 `LLMRequest`
+fn build_request(flow_type: &FlowType, path: &str, body: &Value) -> LLMRequest {
+    let (messages, system_prompt, tools) = match flow_type {
+        FlowType::AnthropicMessages => parse_anthropic_messages(body),
+        FlowType::GeminiGenerateContent => parse_gemini_contents(body),
+        _ => parse_openai_messages(body),
+    };
+
+    let model = body
+        .get("model")
+        .and_then(Value::as_str)
+        .map(String::from)
+        .or_else(|| gemini_model_from_path(path))
+        .unwrap_or_default();
+
+    let generation = body.get("generationConfig");
+    let number = |key: &str, gemini_key: &str| {
+        body.get(key)
+            .or_else(|| generation.and_then(|g| g.get(gemini_key)))
+            .and_then(Value::as_f64)
+    };
+    let stop = body
+        .get("stop")
+        .or_else(|| body.get("stop_sequences"))
+        .or_else(|| generation.and_then(|g| g.get("stopSequences")))
+        .and_then(|v| match v {
+            Value::String(s) => Some(vec![s.clone()]),
+            Value::Array(items) => Some(
+                items
+                    .iter()
+                    .filter_map(|s| s.as_str().map(String::from))
+                    .collect(),
+            ),
+            _ => None,
+        });
+    let parameters = RequestParameters {
+        temperature: number("temperature", "temperature").map(|v| v as f32),
+        top_p: number("top_p", "topP").map(|v| v as f32),
+        max_tokens: number("max_tokens", "maxOutputTokens")
+            .or_else(|| body.get("max_completion_tokens").and_then(Value::as_f64))
+            .map(|v| v as u32),
+        stop,
+        stream: body.get("stream").and_then(Value::as_bool).unwrap_or(false),
+        extra: HashMap::new(),
+    };
+
+    LLMRequest {
+        method: "POST".to_string(),
+        path: path.to_string(),
+        headers: HashMap::new(),
+        body: body.clone(),
+        messages,
+        system_prompt,
+        tools,
+        model,
+        original_model: None,
+        parameters,
+        size_bytes: 0,
+        timestamp: Utc::now(),
+    }
+}
+
+type ParsedMessages = (Vec<Message>, Option<String>, Option<Vec<ToolDefinition>>);
+
+fn message(role: MessageRole, content: MessageContent) -> Message {
+    Message {
+        role,
+        content,
+        tool_calls: None,
+        tool_result: None,
+        name: None,
+    }
+}
+
+fn tool_definition(
+    name: &str,
+    description: Option<&Value>,
+    parameters: Option<&Value>,
+) -> ToolDefinition {
+    ToolDefinition {
+        tool_type: "function".to_string(),
+        function: FunctionDefinition {
+            name: name.to_string(),
+            description: description.and_then(Value::as_str).map(String::from),
+            parameters: parameters.cloned(),
+        },
+    }
+}
+
+fn tool_call(id: &str, name: &str, arguments: String) -> ToolCall {
+    ToolCall {
+        id: id.to_string(),
+        tool_type: "function".to_string(),
+        function: FunctionCall {
+            name: name.to_string(),
+            arguments,
+        },
+    }
+}
+
+/// 把 JSON 值转换为字符串（字符串原样返回，其他值序列化）
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn parse_openai_messages(body: &Value) -> ParsedMessages {
+    let messages: Vec<Message> = body
+        .get("messages")
+        .and_then(Value::as_array)
+        .map(|messages| {
+            messages
+                .iter()
+                .map(|m| {
+                    let role = match m.get("role").and_then(Value::as_str).unwrap_or("user") {
+                        "system" | "developer" => MessageRole::System,
+                        "assistant" => MessageRole::Assistant,
+                        "tool" => MessageRole::Tool,
+                        "function" => MessageRole::Function,
+                        _ => MessageRole::User,
+                    };
+                    let content = match m.get("content") {
+                        Some(Value::Array(parts)) => MessageContent::MultiModal(
+                            parts
+                                .iter()
+                                .filter_map(|p| match p.get("type").and_then(Value::as_str) {
+                                    Some("text") => Some(ContentPart::Text {
+                                        text: value_to_string(p.get("text")?),
+                                    }),
+                                    Some("image_url") => Some(ContentPart::ImageUrl {
+                                        image_url: ImageUrl {
+                                            url: value_to_string(p.pointer("/image_url/url")?),
+                                            detail: p
+                                                .pointer("/image_url/detail")
+                                                .and_then(Value::as_str)
+                                                .map(String::from),
+                                        },
+                                    }),
+                                    _ => None,
+                                })
+                                .collect(),
+                        ),
+                        Some(value) => MessageContent::Text(value_to_string(value)),
+                        None => MessageContent::Text(String::new()),
+                    };
+                    let mut msg = message(role, content);
+                    msg.name = m.get("name").and_then(Value::as_str).map(String::from);
+                    msg.tool_calls = m.get("tool_calls").and_then(Value::as_array).map(|calls| {
+                        calls
+                            .iter()
+                            .map(|c| {
+                                tool_call(
+                                    c.get("id").and_then(Value::as_str).unwrap_or_default(),
+                                    c.pointer("/function/name")
+                                        .and_then(Value::as_str)
+                                        .unwrap_or_default(),
+                                    c.pointer("/function/arguments")
+                                        .map(value_to_string)
+                                        .unwrap_or_default(),
+                                )
+                            })
+                            .collect()
+                    });
+                    if let Some(id) = m.get("tool_call_id").and_then(Value::as_str) {
+                        msg.tool_result = Some(ToolResult {
+                            tool_call_id: id.to_string(),
+                            content: msg.content.get_all_text(),
+                            is_error: false,
+                        });
+                    }
+                    msg
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let system_prompt = messages
+        .iter()
+        .find(|m| m.role == MessageRole::System)
+        .map(|m| m.content.get_all_text());
+    let tools = body.get("tools").and_then(Value::as_array).map(|tools| {
+        tools
+            .iter()
+            .filter_map(|t| {
+                let function = t.get("function")?;
+                Some(tool_definition(
+                    function.get("name")?.as_str()?,
+                    function.get("description"),
+                    function.get("parameters"),
+                ))
+            })
+            .collect()
+    });
+
+    (messages, system_prompt, tools)
+}
+
+fn parse_anthropic_messages(body: &Value) -> ParsedMessages {
+    let system_prompt = match body.get("system") {
+        Some(Value::String(s)) => Some(s.clone()),
+        Some(Value::Array(blocks)) => Some(
+            blocks
+                .iter()
+                .filter_map(|b| b.get("text").and_then(Value::as_str))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        _ => None,
+    };
+
+    let mut messages = Vec::new();
+    if let Some(system) = &system_prompt {
+        messages.push(message(
+            MessageRole::System,
+            MessageContent::Text(system.clone()),
+        ));
+    }
+    for m in body
+        .get("messages")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let role = match m.get("role").and_then(Value::as_str) {
+            Some("assistant") => MessageRole::Assistant,
+            _ => MessageRole::User,
+        };
+        let blocks = match m.get("content") {
+            Some(Value::String(text)) => {
+                messages.push(message(role, MessageContent::Text(text.clone())));
+                continue;
+            }
+            Some(Value::Array(blocks)) => blocks,
+            _ => continue,
+        };
+
+        let mut parts = Vec::new();
+        let mut tool_calls = Vec::new();
+        let mut tool_result = None;
+        for block in blocks {
+            match block.get("type").and_then(Value::as_str) {
+                Some("text") => parts.push(ContentPart::Text {
+                    text: block.get("text").map(value_to_string).unwrap_or_default(),
+                }),
+                Some("image") => {
+                    let source = block.get("source");
+                    let field = |key: &str| {
+                        source
+                            .and_then(|s| s.get(key))
+                            .and_then(Value::as_str)
+                            .map(String::from)
+                    };
+                    parts.push(ContentPart::Image {
+                        media_type: field("media_type"),
+                        data: field("data"),
+                        url: field("url"),
+                    });
+                }
+                Some("tool_use") => tool_calls.push(tool_call(
+                    block.get("id").and_then(Value::as_str).unwrap_or_default(),
+                    block
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default(),
+                    block
+                        .get("input")
+                        .map(Value::to_string)
+                        .unwrap_or_else(|| "{}".to_string()),
+                )),
+                Some("tool_result") => {
+                    let content = match block.get("content") {
+                        Some(Value::Array(items)) => items
+                            .iter()
+                            .filter_map(|i| i.get("text").and_then(Value::as_str))
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                        Some(value) => value_to_string(value),
+                        None => String::new(),
+                    };
+                    tool_result = Some(ToolResult {
+                        tool_call_id: block
+                            .get("tool_use_id")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                        content,
+                        is_error: block
+                            .get("is_error")
+                            .and_then(Value::as_bool)
+                            .unwrap_or(false),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let content = match parts.as_slice() {
+            [ContentPart::Text { text }] => MessageContent::Text(text.clone()),
+            _ => MessageContent::MultiModal(parts),
+        };
+        let role = if tool_result.is_some() && role == MessageRole::User {
+            MessageRole::Tool
+        } else {
+            role
+        };
+        let mut msg = message(role, content);
+        msg.tool_calls = (!tool_calls.is_empty()).then_some(tool_calls);
+        msg.tool_result = tool_result;
+        messages.push(msg);
+    }
+
+    let tools = body.get("tools").and_then(Value::as_array).map(|tools| {
+        tools
+            .iter()
+            .filter_map(|t| {
+                Some(tool_definition(
+                    t.get("name")?.as_str()?,
+                    t.get("description"),
+                    t.get("input_schema"),
+                ))
+            })
+            .collect()
+    });
+
+    (messages, system_prompt, tools)
+}
+
+fn parse_gemini_contents(body: &Value) -> ParsedMessages {
+    let part_texts = |parts: Option<&Value>| -> String {
+        parts
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|p| p.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let system_prompt = body
+        .get("systemInstruction")
+        .or_else(|| body.get("system_instruction"))
+        .map(|s| part_texts(s.get("parts")))
+        .filter(|s| !s.is_empty());
+
+    let mut messages = Vec::new();
+    if let Some(system) = &system_prompt {
+        messages.push(message(
+            MessageRole::System,
+            MessageContent::Text(system.clone()),
+        ));
+    }
+    for content in body
+        .get("contents")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let role = match content.get("role").and_then(Value::as_str) {
+            Some("model") => MessageRole::Assistant,
+            _ => MessageRole::User,
+        };
+        let parts = content.get("parts").and_then(Value::as_array);
+        let mut msg = message(role, MessageContent::Text(part_texts(content.get("parts"))));
+
+        let tool_calls: Vec<ToolCall> = parts
+            .into_iter()
+            .flatten()
+            .filter_map(|p| p.get("functionCall"))
+            .map(|call| {
+                let name = call.get("name").and_then(Value::as_str).unwrap_or_default();
+                tool_call(
+                    name,
+                    name,
+                    call.get("args")
+                        .map(Value::to_string)
+                        .unwrap_or_else(|| "{}".to_string()),
+                )
+            })
+            .collect();
+        msg.tool_calls = (!tool_calls.is_empty()).then_some(tool_calls);
+
+        if let Some(response) = parts
+            .into_iter()
+            .flatten()
+            .find_map(|p| p.get("functionResponse"))
+        {
+            msg.role = MessageRole::Tool;
+            msg.tool_result = Some(ToolResult {
+                tool_call_id: response
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                content: response
+                    .get("response")
+                    .map(Value::to_string)
+                    .unwrap_or_default(),
+                is_error: false,
+            });
+        }
+        messages.push(msg);
+    }
+
+    let tools: Vec<ToolDefinition> = body
+        .get("tools")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|t| {
+            t.get("functionDeclarations")
+                .or_else(|| t.get("function_declarations"))
+                .and_then(Value::as_array)
+        })
+        .flatten()
+        .filter_map(|f| {
+            Some(tool_definition(
+                f.get("name")?.as_str()?,
+                f.get("description"),
+                f.get("parameters"),
+            ))
+        })
+        .collect();
+
+    (
+        messages,
+        system_prompt,
+        (!tools.is_empty()).then_some(tools),
+    )
+}
+
+/// 从 Gemini 路径（`.../models/{model}:generateContent`）提取模型名
+fn gemini_model_from_path(path: &str) -> Option<String> {
+    let segment = path.rsplit('/').next()?;
+    let model = segment.split(':').next()?;
+    (!model.is_empty() && segment.contains(':')).then(|| model.to_string())
+}
+
+// ============================================================================
+// 响应解析
+// ============================================================================
+
+/// 从响应体构建 `LLMResponse`，SSE 响应通过 `StreamRebuilder` 重建
+pub fn parse_response_body(flow_type: &FlowType, text: &str) -> LLMResponse {
+    if is_sse(text) {
+        return rebuild_sse(flow_type, text);
+    }
+
+    let body: Value = serde_json::from_str(text).unwrap_or(Value::Null);
+    // Gemini 非 SSE 流式响应是 JSON 数组
+    if let (FlowType::GeminiGenerateContent, Value::Array(chunks)) = (flow_type, &body) {
+        let mut rebuilder = StreamRebuilder::new(StreamFormat::Gemini);
+        for chunk in chunks {
+            let _ = rebuilder.process_event(None, &chunk.to_string());
+        }
+        return rebuilder.finish();
+    }
+
+    let mut response = match flow_type {
+        FlowType::AnthropicMessages => parse_anthropic_response(&body),
+        FlowType::GeminiGenerateContent => parse_gemini_response(&body),
+        _ => parse_openai_response(&body),
+    };
+    response.usage.calculate_total();
+    response.body = body;
+    response
+}
+
+fn is_sse(text: &str) -> bool {
+    let trimmed = text.trim_start();
+    trimmed.starts_with("data:") || trimmed.starts_with("event:") || trimmed.starts_with(':')
+}
+
+/// 按 SSE 事件块重建流式响应
+fn rebuild_sse(flow_type: &FlowType, text: &str) -> LLMResponse {
+    let format = match flow_type {
+        FlowType::AnthropicMessages => StreamFormat::Anthropic,
+        FlowType::GeminiGenerateContent => StreamFormat::Gemini,
+        _ => StreamFormat::OpenAI,
+    };
+    let mut rebuilder = StreamRebuilder::new(format);
+
+    let normalized = text.replace("\r\n", "\n");
+    for block in normalized.split("\n\n") {
+        let mut event = None;
+        let mut data = Vec::new();
+        for line in block.lines() {
+            if let Some(value) = line.strip_prefix("event:") {
+                event = Some(value.trim());
+            } else if let Some(value) = line.strip_prefix("data:") {
+                data.push(value.strip_prefix(' ').unwrap_or(value));
+            }
+        }
+        if data.is_empty() {
+            continue;
+        }
+        // 单个事件解析失败不影响其余事件
+        let _ = rebuilder.process_event(event, &data.join("\n"));
+    }
+
+    rebuilder.finish()
+}
+
+fn parse_stop_reason(reason: &str) -> StopReason {
+    match reason {
+        "stop" | "STOP" | "stop_sequence" => StopReason::Stop,
+        "length" | "max_tokens" | "MAX_TOKENS" => StopReason::Length,
+        "tool_calls" | "tool_use" => StopReason::ToolCalls,
+        "content_filter" | "SAFETY" => StopReason::ContentFilter,
+        "function_call" => StopReason::FunctionCall,
+        "end_turn" => StopReason::EndTurn,
+        other => StopReason::Other(other.to_string()),
+    }
+}
+
+fn token_count(value: Option<&Value>) -> Option<u32> {
+    value.and_then(Value::as_u64).map(|v| v as u32)
+}
+
+fn thinking(text: String) -> Option<ThinkingContent> {
+    (!text.is_empty()).then_some(ThinkingContent {
+        text,
+        tokens: None,
+        signature: None,
+    })
+}
+
+fn parse_openai_response(body: &Value) -> LLMResponse {
+    let message = body.pointer("/choices/0/message");
+    let field = |key: &str| message.and_then(|m| m.get(key));
+
+    let tool_calls = field("tool_calls")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|c| {
+            tool_call(
+                c.get("id").and_then(Value::as_str).unwrap_or_default(),
+                c.pointer("/function/name")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default(),
+                c.pointer("/function/arguments")
+                    .map(value_to_string)
+                    .unwrap_or_default(),
+            )
+        })
+        .collect();
+
+    let usage = body.get("usage");
+    let usage_field = |pointer: &str| token_count(usage.and_then(|u| u.pointer(pointer)));
+    LLMResponse {
+        content: field("content").map(value_to_string).unwrap_or_default(),
+        thinking: thinking(
+            field("reasoning_content")
+                .map(value_to_string)
+                .unwrap_or_default(),
+        ),
+        tool_calls,
+        usage: TokenUsage {
+            input_tokens: usage_field("/prompt_tokens").unwrap_or(0),
+            output_tokens: usage_field("/completion_tokens").unwrap_or(0),
+            cache_read_tokens: usage_field("/prompt_tokens_details/cached_tokens"),
+            thinking_tokens: usage_field("/completion_tokens_details/reasoning_tokens"),
+            ..Default::default()
+        },
+        stop_reason: body
+            .pointer("/choices/0/finish_reason")
+            .and_then(Value::as_str)
+            .map(parse_stop_reason),
+        ..Default::default()
+    }
+}
+
+fn parse_anthropic_response(body: &Value) -> LLMResponse {
+    let mut content = String::new();
+    let mut thinking_text = String::new();
+    let mut tool_calls = Vec::new();
+    for block in body
+        .get("content")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        match block.get("type").and_then(Value::as_str) {
+            Some("text") => {
+                content.push_str(block.get("text").and_then(Value::as_str).unwrap_or(""))
+            }
+            Some("thinking") => {
+                thinking_text.push_str(block.get("thinking").and_then(Value::as_str).unwrap_or(""))
+            }
+            Some("tool_use") => tool_calls.push(tool_call(
+                block.get("id").and_then(Value::as_str).unwrap_or_default(),
+                block
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default(),
+                block
+                    .get("input")
+                    .map(Value::to_string)
+                    .unwrap_or_else(|| "{}".to_string()),
+            )),
+            _ => {}
+        }
+    }
+
+    let usage = body.get("usage");
+    let usage_field = |key: &str| token_count(usage.and_then(|u| u.get(key)));
+    LLMResponse {
+        content,
+        thinking: thinking(thinking_text),
+        tool_calls,
+        usage: TokenUsage {
+            input_tokens: usage_field("input_tokens").unwrap_or(0),
+            output_tokens: usage_field("output_tokens").unwrap_or(0),
+            cache_read_tokens: usage_field("cache_read_input_tokens"),
+            cache_write_tokens: usage_field("cache_creation_input_tokens"),
+            ..Default::default()
+        },
+        stop_reason: body
+            .get("stop_reason")
+            .and_then(Value::as_str)
+            .map(parse_stop_reason),
+        ..Default::default()
+    }
+}
+
+fn parse_gemini_response(body: &Value) -> LLMResponse {
+    let mut content = String::new();
+    let mut thinking_text = String::new();
+    let mut tool_calls = Vec::new();
+    for part in body
+        .pointer("/candidates/0/content/parts")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        if let Some(text) = part.get("text").and_then(Value::as_str) {
+            if part.get("thought").and_then(Value::as_bool) == Some(true) {
+                thinking_text.push_str(text);
+            } else {
+                content.push_str(text);
+            }
+        }
+        if let Some(call) = part.get("functionCall") {
+            let name = call.get("name").and_then(Value::as_str).unwrap_or_default();
+            tool_calls.push(tool_call(
+                name,
+                name,
+                call.get("args")
+                    .map(Value::to_string)
+                    .unwrap_or_else(|| "{}".to_string()),
+            ));
+        }
+    }
+
+    let usage = body.get("usageMetadata");
+    let usage_field = |key: &str| token_count(usage.and_then(|u| u.get(key)));
+    LLMResponse {
+        content,
+        thinking: thinking(thinking_text),
+        tool_calls,
+        usage: TokenUsage {
+            input_tokens: usage_field("promptTokenCount").unwrap_or(0),
+            output_tokens: usage_field("candidatesTokenCount").unwrap_or(0),
+            cache_read_tokens: usage_field("cachedContentTokenCount"),
+            thinking_tokens: usage_field("thoughtsTokenCount"),
+            ..Default::default()
+        },
+        stop_reason: body
+            .pointer("/candidates/0/finishReason")
+            .and_then(Value::as_str)
+            .map(parse_stop_reason),
+        ..Default::default()
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    use crate::flow_monitor::exporter::FlowExporter;
+    use crate::flow_monitor::file_store::RotationConfig;
+
+    fn har(entries: Vec<Value>) -> String {
+        json!({"log": {"version": "1.2", "creator": {"name": "test", "version": "1"}, "entries": entries}})
+            .to_string()
+    }
+
+    fn har_entry(url: &str, request: Value, status: u16, mime: &str, response: &str) -> Value {
+        json!({
+            "startedDateTime": "2026-01-02T03:04:05.000Z",
+            "time": 1500.0,
+            "request": {
+                "method": "POST",
+                "url": url,
+                "headers": [
+                    {"name": "Authorization", "value": "Bearer sk-secret"},
+                    {"name": "User-Agent", "value": "curl/8.0"},
+                ],
+                "postData": {"mimeType": "application/json", "text": request.to_string()},
+            },
+            "response": {
+                "status": status,
+                "statusText": "",
+                "headers": [{"name": "Content-Type", "value": mime}],
+                "content": {"size": response.len(), "mimeType": mime, "text": response},
+            },
+            "timings": {"send": 0, "wait": 300, "receive": 1200},
+        })
+    }
+
+    fn importer() -> FlowImporter {
+        FlowImporter::new(ImportOptions::default())
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(ImportFormat::detect(&har(vec![])), ImportFormat::Har);
+        assert_eq!(
+            ImportFormat::detect("{\"id\":\"a\"}\n{\"id\":\"b\"}"),
+            ImportFormat::Jsonl
+        );
+    }
+
+    #[test]
+    fn test_import_openai_har_entry() {
+        let request = json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "system", "content": "Be brief."}, {"role": "user", "content": "Hi"}],
+            "temperature": 0.2,
+        });
+        let response = json!({
+            "choices": [{"message": {"role": "assistant", "content": "Hello!"}, "finish_reason": "stop"}],
+            "usage": {"prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15},
+        });
+        let data = har(vec![
+            har_entry(
+                "https://api.openai.com/v1/chat/completions",
+                request,
+                200,
+                "application/json",
+                &response.to_string(),
+            ),
+            json!({
+                "startedDateTime": "2026-01-02T03:04:05.000Z",
+                "time": 10.0,
+                "request": {"method": "GET", "url": "https://example.com/index.html", "headers": []},
+                "response": {"status": 200, "headers": [], "content": {"size": 0, "mimeType": "text/html"}},
+            }),
+        ]);
+
+        let (flows, result) = importer().parse(&data, ImportFormat::Har).unwrap();
+        assert_eq!(result.total, 2);
+        assert_eq!(result.imported, 1);
+        assert_eq!(result.skipped_unsupported, 1);
+
+        let flow = &flows[0];
+        assert!(flow.id.starts_with("har-"));
+        assert_eq!(flow.flow_type, FlowType::ChatCompletions);
+        assert_eq!(flow.metadata.provider, ProviderType::OpenAI);
+        assert_eq!(flow.state, FlowState::Completed);
+        assert_eq!(flow.request.model, "gpt-4o");
+        assert_eq!(flow.request.system_prompt.as_deref(), Some("Be brief."));
+        assert_eq!(flow.request.parameters.temperature, Some(0.2));
+        assert!(!flow.request.headers.contains_key("Authorization"));
+        assert_eq!(flow.timestamps.duration_ms, 1500);
+        assert_eq!(flow.timestamps.ttfb_ms, Some(300));
+
+        let response = flow.response.as_ref().unwrap();
+        assert_eq!(response.content, "Hello!");
+        assert_eq!(response.usage.total_tokens, 15);
+        assert_eq!(response.stop_reason, Some(StopReason::Stop));
+        assert!(flow.annotations.tags.contains(&"imported".to_string()));
+        assert!(flow.annotations.tags.contains(&"import:har".to_string()));
+    }
+
+    #[test]
+    fn test_import_anthropic_sse_entry() {
+        let request = json!({
+            "model": "claude-sonnet-4-5",
+            "max_tokens": 1024,
+            "stream": true,
+            "system": "Be brief.",
+            "messages": [{"role": "user", "content": "Weather in Paris?"}],
+            "tools": [{"name": "weather", "input_schema": {"type": "object"}}],
+        });
+        let sse = concat!(
+            "event: message_start\r\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"model\":\"claude-sonnet-4-5\",\"usage\":{\"input_tokens\":20}}}\r\n\r\n",
+            "event: content_block_start\r\n",
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\r\n\r\n",
+            "event: content_block_delta\r\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Sunny\"}}\r\n\r\n",
+            "event: content_block_delta\r\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\" today.\"}}\r\n\r\n",
+            "event: message_delta\r\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":4}}\r\n\r\n",
+            "event: message_stop\r\n",
+            "data: {\"type\":\"message_stop\"}\r\n\r\n",
+        );
+        let data = har(vec![har_entry(
+            "https://api.anthropic.com/v1/messages",
+            request,
+            200,
+            "text/event-stream",
+            sse,
+        )]);
+
+        let (flows, _) = importer().parse(&data, ImportFormat::Har).unwrap();
+        let flow = &flows[0];
+        assert_eq!(flow.flow_type, FlowType::AnthropicMessages);
+        assert_eq!(flow.metadata.provider, ProviderType::Claude);
+        assert!(flow.request.parameters.stream);
+        assert_eq!(
+            flow.request.tools.as_ref().unwrap()[0].function.name,
+            "weather"
+        );
+
+        let response = flow.response.as_ref().unwrap();
+        assert_eq!(response.content, "Sunny today.");
+        assert_eq!(response.stop_reason, Some(StopReason::EndTurn));
+        assert!(response.stream_info.is_some());
+    }
+
+    #[test]
+    fn test_import_gemini_entry_and_error_status() {
+        let request = json!({
+            "contents": [{"role": "user", "parts": [{"text": "Hi"}]}],
+            "systemInstruction": {"parts": [{"text": "Be brief."}]},
+        });
+        let response = json!({
+            "candidates": [{"content": {"role": "model", "parts": [{"text": "Hello"}]}, "finishReason": "STOP"}],
+            "usageMetadata": {"promptTokenCount": 5, "candidatesTokenCount": 1},
+        });
+        let data = har(vec![
+            har_entry(
+                "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-pro:generateContent",
+                request.clone(),
+                200,
+                "application/json",
+                &response.to_string(),
+            ),
+            har_entry(
+                "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse",
+                request,
+                429,
+                "application/json",
+                "{\"error\":{\"code\":429}}",
+            ),
+        ]);
+
+        let (flows, result) = importer().parse(&data, ImportFormat::Har).unwrap();
+        assert_eq!(result.imported, 2);
+
+        let ok = &flows[0];
+        assert_eq!(ok.flow_type, FlowType::GeminiGenerateContent);
+        assert_eq!(ok.metadata.provider, ProviderType::Gemini);
+        assert_eq!(ok.request.model, "gemini-2.5-pro");
+        assert_eq!(ok.request.system_prompt.as_deref(), Some("Be brief."));
+        assert_eq!(ok.response.as_ref().unwrap().content, "Hello");
+
+        let failed = &flows[1];
+        assert!(failed.request.parameters.stream);
+        assert_eq!(failed.state, FlowState::Failed);
+        let error = failed.error.as_ref().unwrap();
+        assert_eq!(error.error_type, FlowErrorType::RateLimit);
+        assert_eq!(error.status_code, Some(429));
+    }
+
+    #[test]
+    fn test_import_jsonl_with_dedup_and_source_tag() {
+        let request = LLMRequest {
+            model: "gpt-4o".to_string(),
+            ..Default::default()
+        };
+        let flow = LLMFlow::new(
+            "flow-1".to_string(),
+            FlowType::ChatCompletions,
+            request,
+            FlowMetadata::default(),
+        );
+        let line = serde_json::to_string(&flow).unwrap();
+        let data = format!("{}\n\nnot json\n{}\n", line, line);
+
+        let importer = FlowImporter::new(ImportOptions {
+            source: Some("alice".to_string()),
+        });
+        let (flows, result) = importer.parse(&data, ImportFormat::Jsonl).unwrap();
+        assert_eq!(result.total, 3);
+        assert_eq!(result.imported, 1);
+        assert_eq!(result.skipped_duplicate, 1);
+        assert_eq!(result.failed, 1);
+        assert!(result.errors[0].starts_with("第 3 行"));
+        assert!(flows[0]
+            .annotations
+            .tags
+            .contains(&"import:alice".to_string()));
+    }
+
+    #[test]
+    fn test_import_into_file_store_skips_existing() {
+        let temp_dir = TempDir::new().unwrap();
+        let store =
+            FlowFileStore::new(temp_dir.path().to_path_buf(), RotationConfig::default()).unwrap();
+
+        let mut flow = LLMFlow::new(
+            "exported-1".to_string(),
+            FlowType::ChatCompletions,
+            LLMRequest {
+                path: "/v1/chat/completions".to_string(),
+                model: "gpt-4o".to_string(),
+                body: json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "Hi"}]}),
+                ..Default::default()
+            },
+            FlowMetadata::default(),
+        );
+        flow.state = FlowState::Completed;
+        flow.response = Some(LLMResponse {
+            content: "Hello".to_string(),
+            body: json!({"choices": [{"message": {"role": "assistant", "content": "Hello"}}]}),
+            ..Default::default()
+        });
+        flow.annotations.starred = true;
+        let data =
+            serde_json::to_string(&FlowExporter::with_defaults().export_har(&[flow])).unwrap();
+
+        let result = importer().import(&store, &data, ImportFormat::Har).unwrap();
+        assert_eq!(result.imported, 1);
+        assert_eq!(result.flow_ids, vec!["exported-1".to_string()]);
+
+        let stored = store.get("exported-1").unwrap().unwrap();
+        assert!(stored.annotations.starred);
+        assert!(stored.annotations.tags.contains(&"imported".to_string()));
+        assert_eq!(stored.response.unwrap().content, "Hello");
+        assert_eq!(store.search("Hello", 10).unwrap().len(), 1);
+
+        let again = importer().import(&store, &data, ImportFormat::Har).unwrap();
+        assert_eq!(again.imported, 0);
+        assert_eq!(again.skipped_duplicate, 1);
+    }
+}
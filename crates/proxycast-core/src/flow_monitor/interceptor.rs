@@ -14,11 +14,14 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::{broadcast, oneshot, RwLock};
-use tokio::time::{timeout, Duration};
+use tokio::time::{timeout, Duration, Instant};
 
 use super::filter_parser::{FilterExpr, FilterParser};
 use super::models::{LLMFlow, LLMRequest, LLMResponse};
 
+/// 暂存动作的保留时间，等待方一直未到达（如请求已提前结束）时过期清理
+const EARLY_ACTION_TTL: Duration = Duration::from_secs(60);
+
 // ============================================================================
 // 配置结构
 // ============================================================================
@@ -293,6 +296,8 @@ pub struct FlowInterceptor {
     filter: RwLock<Option<Arc<dyn Fn(&LLMFlow) -> bool + Send + Sync>>>,
     /// 等待中的拦截
     pending_intercepts: RwLock<HashMap<String, PendingIntercept>>,
+    /// 在 `wait_for_action` 之前到达的动作（如脚本化客户端收到事件后立即操作）
+    early_actions: RwLock<HashMap<String, (InterceptAction, Instant)>>,
     /// 事件发送器
     event_sender: broadcast::Sender<InterceptEvent>,
 }
@@ -307,6 +312,7 @@ impl FlowInterceptor {
             config: RwLock::new(config),
             filter: RwLock::new(filter),
             pending_intercepts: RwLock::new(HashMap::new()),
+            early_actions: RwLock::new(HashMap::new()),
             event_sender,
         }
     }
@@ -463,9 +469,12 @@ impl FlowInterceptor {
             }
 
             // 发送动作
-            if let Some(sender) = intercept.action_sender {
-                let _ = sender.send(InterceptAction::Continue(modified.clone()));
-            }
+            self.deliver_action(
+                flow_id,
+                intercept.action_sender,
+                InterceptAction::Continue(modified.clone()),
+            )
+            .await;
 
             // 发送事件
             let _ = self.event_sender.send(InterceptEvent::FlowContinued {
@@ -488,9 +497,8 @@ impl FlowInterceptor {
             intercept.flow.state = InterceptState::Cancelled;
 
             // 发送动作
-            if let Some(sender) = intercept.action_sender {
-                let _ = sender.send(InterceptAction::Cancel);
-            }
+            self.deliver_action(flow_id, intercept.action_sender, InterceptAction::Cancel)
+                .await;
 
             // 发送事件
             let _ = self.event_sender.send(InterceptEvent::FlowCancelled {
@@ -503,6 +511,26 @@ impl FlowInterceptor {
         }
     }
 
+    /// 将动作交给等待方，尚未开始等待时暂存
+    async fn deliver_action(
+        &self,
+        flow_id: &str,
+        sender: Option<oneshot::Sender<InterceptAction>>,
+        action: InterceptAction,
+    ) {
+        match sender {
+            Some(sender) => {
+                let _ = sender.send(action);
+            }
+            None => {
+                let now = Instant::now();
+                let mut early = self.early_actions.write().await;
+                early.retain(|_, (_, stored_at)| now.duration_since(*stored_at) < EARLY_ACTION_TTL);
+                early.insert(flow_id.to_string(), (action, now));
+            }
+        }
+    }
+
     /// 等待用户操作
     ///
     /// 此方法会阻塞直到用户执行操作或超时。
@@ -521,8 +549,15 @@ impl FlowInterceptor {
             if let Some(intercept) = pending.get_mut(flow_id) {
                 intercept.action_sender = Some(tx);
             } else {
-                // Flow 不存在，返回取消
-                return InterceptAction::Cancel;
+                // 动作已先于等待到达；否则 Flow 不存在，返回取消
+                return self
+                    .early_actions
+                    .write()
+                    .await
+                    .remove(flow_id)
+                    .filter(|(_, stored_at)| stored_at.elapsed() < EARLY_ACTION_TTL)
+                    .map(|(action, _)| action)
+                    .unwrap_or(InterceptAction::Cancel);
             }
         }
 
@@ -871,6 +906,60 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_action_before_wait_is_not_lost() {
+        let interceptor = FlowInterceptor::default();
+
+        // 客户端在 wait_for_action 之前就已继续
+        interceptor
+            .intercept_request("flow-1", create_test_request("gpt-4"))
+            .await;
+        let modified = create_test_request("gpt-4-turbo");
+        interceptor
+            .continue_flow("flow-1", Some(ModifiedData::Request(modified)))
+            .await
+            .unwrap();
+
+        match interceptor.wait_for_action("flow-1").await {
+            InterceptAction::Continue(Some(ModifiedData::Request(req))) => {
+                assert_eq!(req.model, "gpt-4-turbo");
+            }
+            other => panic!("Expected Continue, got {:?}", other),
+        }
+
+        // 暂存的动作只消费一次
+        assert!(matches!(
+            interceptor.wait_for_action("flow-1").await,
+            InterceptAction::Cancel
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unclaimed_early_actions_expire() {
+        let interceptor = FlowInterceptor::default();
+
+        // 等待方从未到达的暂存动作（单调时钟起点晚于 TTL 时无法构造过期时刻，跳过）
+        let Some(expired) = Instant::now().checked_sub(EARLY_ACTION_TTL + Duration::from_secs(1))
+        else {
+            return;
+        };
+        interceptor
+            .early_actions
+            .write()
+            .await
+            .insert("stale".to_string(), (InterceptAction::Cancel, expired));
+
+        interceptor
+            .intercept_request("flow-2", create_test_request("gpt-4"))
+            .await;
+        interceptor.cancel_flow("flow-2").await.unwrap();
+
+        // 新动作暂存时清理过期条目
+        let early = interceptor.early_actions.read().await;
+        assert!(!early.contains_key("stale"));
+        assert!(early.contains_key("flow-2"));
+    }
+
     #[tokio::test]
     async fn test_cancel_flow() {
         let interceptor = FlowInterceptor::default();
@@ -13,15 +13,19 @@
 //! - `exporter`: 导出服务，支持 HAR、JSON、JSONL、Markdown、CSV 格式
 //! - `monitor`: 核心监控服务
 //! - `filter_parser`: 高级过滤表达式解析器，支持类似 mitmproxy 的语法
+//! - `conversation`: 按消息前缀、工具调用链和 `/compact` 边界重建对话树
 
 pub mod batch_ops;
 pub mod bookmark;
 pub mod code_exporter;
+pub mod conversation;
+pub mod dataset;
 pub mod diff;
 pub mod enhanced_stats;
 pub mod exporter;
 pub mod file_store;
 pub mod filter_parser;
+pub mod importer;
 pub mod interceptor;
 pub mod memory_store;
 pub mod models;
@@ -31,6 +35,7 @@ pub mod quick_filter;
 pub mod replayer;
 pub mod session;
 pub mod stream_rebuilder;
+pub mod suite;
 
 // 重新导出核心类型
 pub use models::{
@@ -75,7 +80,8 @@ pub use memory_store::{FlowFilter, FlowMemoryStore, LatencyRange, TimeRange, Tok
 
 // 重新导出文件存储
 pub use file_store::{
-    CleanupResult, FileStoreError, FlowFileStore, FlowIndexRecord, FtsSearchResult, RotationConfig,
+    ArchiveResult, CleanupResult, FileStoreError, FlowFileStore, FlowIndexRecord, FtsSearchResult,
+    RotationConfig, StorageTierUsage, StorageUsage,
 };
 
 // 重新导出查询服务
@@ -90,6 +96,18 @@ pub use exporter::{
     HarEntry, HarLlmExtension, HarLog, RedactionRule, Redactor,
 };
 
+// 重新导出导入服务
+pub use importer::{
+    parse_response_body, FlowImporter, ImportError, ImportFormat, ImportOptions, ImportResult,
+    IMPORTED_FLOW_TAG, IMPORT_SOURCE_TAG_PREFIX,
+};
+
+// 重新导出数据集导出服务
+pub use dataset::{
+    DatasetError, DatasetExport, DatasetExporter, DatasetFormat, DatasetOptions,
+    DatasetQualityFilter, DatasetStats,
+};
+
 // 重新导出监控服务
 pub use monitor::{
     FlowEvent, FlowMonitor, FlowMonitorConfig, FlowSummary, FlowUpdate, RequestRateTracker,
@@ -115,7 +133,23 @@ pub use replayer::{
 
 // 重新导出差异对比器
 pub use diff::{
-    DiffConfig, DiffItem, DiffType, FlowDiff, FlowDiffResult, MessageDiffItem, TokenDiff,
+    output_similarity, DiffConfig, DiffItem, DiffType, FlowDiff, FlowDiffResult, MessageDiffItem,
+    TokenDiff,
+};
+
+// 重新导出回归测试套件
+pub use suite::{
+    regression_flow_tags, resolve_suite_flows, run_suite, validate_target, AssertionResult,
+    FlowSuite, SuiteAssertion, SuiteCaseResult, SuiteCaseStatus, SuiteDefinition, SuiteError,
+    SuiteExecution, SuiteExecutor, SuiteReport, SuiteStore, SuiteTarget, REGRESSION_FLOW_TAG,
+    SUITE_TAG_PREFIX,
+};
+
+// 重新导出对话谱系重建
+pub use conversation::{
+    session_key, Conversation, ConversationLink, ConversationNode, ConversationOptions,
+    ConversationReconstructor, ConversationTotals, ModelPrice, ModelPricing,
+    CLAUDE_CODE_SESSION_HEADER,
 };
 
 // 重新导出会话管理器
@@ -137,8 +171,9 @@ pub use bookmark::{BookmarkError, BookmarkExport, BookmarkManager, FlowBookmark}
 
 // 重新导出增强统计服务
 pub use enhanced_stats::{
-    Distribution, EnhancedStats, EnhancedStatsService, ReportFormat, StatsTimeRange,
-    TimeSeriesPoint, TrendData,
+    parse_shadow_link, shadow_flow_tags, Distribution, EnhancedStats, EnhancedStatsService,
+    MirrorReport, MirrorRuleReport, MirrorSideStats, ReportFormat, StatsTimeRange, TimeSeriesPoint,
+    TrendData,
 };
 
 // 重新导出批量操作服务
@@ -1096,6 +1096,37 @@ impl FlowMonitor {
         Some(flow_id)
     }
 
+    /// 为一次重试启动新的 Flow
+    ///
+    /// 沿用上一次尝试的请求路径、请求头和元数据（重试次数加一），
+    /// 请求内容替换为本次重试实际发送的请求。上一次尝试的 Flow 已不存在时返回 `None`。
+    pub async fn start_retry_flow(
+        &self,
+        previous_flow_id: &str,
+        mut request: LLMRequest,
+    ) -> Option<String> {
+        let active = self
+            .active_flows
+            .read()
+            .await
+            .get(previous_flow_id)
+            .map(|active| active.flow.clone());
+        let previous = match active {
+            Some(flow) => flow,
+            None => {
+                let stored = self.memory_store.read().await.get(previous_flow_id)?;
+                let flow = stored.read().ok()?.clone();
+                flow
+            }
+        };
+
+        request.path = previous.request.path.clone();
+        request.headers = previous.request.headers.clone();
+        let mut metadata = previous.metadata;
+        metadata.retry_count += 1;
+        self.start_flow(request, metadata).await
+    }
+
     /// 根据路径确定 Flow 类型
     fn determine_flow_type(path: &str) -> FlowType {
         let path_lower = path.to_lowercase();
@@ -1599,6 +1630,37 @@ mod tests {
         assert_eq!(monitor.memory_flow_count().await, 1);
     }
 
+    #[tokio::test]
+    async fn test_start_retry_flow() {
+        let monitor = FlowMonitor::new(FlowMonitorConfig::default(), None);
+
+        let mut request = create_test_request("gpt-4", "/v1/chat/completions");
+        request
+            .headers
+            .insert("user-agent".to_string(), "test".to_string());
+        let metadata = create_test_metadata(ProviderType::OpenAI);
+        let first = monitor.start_flow(request, metadata).await.unwrap();
+        monitor.complete_flow(&first, None).await;
+
+        let retry = monitor
+            .start_retry_flow(&first, create_test_request("gpt-4", ""))
+            .await
+            .unwrap();
+        assert_ne!(retry, first);
+        let active = monitor.active_flows.read().await;
+        let flow = &active.get(&retry).unwrap().flow;
+        assert_eq!(flow.metadata.retry_count, 1);
+        assert_eq!(flow.metadata.credential_id.as_deref(), Some("test-cred"));
+        assert_eq!(flow.request.path, "/v1/chat/completions");
+        assert_eq!(flow.request.headers["user-agent"], "test");
+        drop(active);
+
+        assert!(monitor
+            .start_retry_flow("missing", create_test_request("gpt-4", ""))
+            .await
+            .is_none());
+    }
+
     #[tokio::test]
     async fn test_fail_flow() {
         let config = FlowMonitorConfig::default();
@@ -10,9 +10,12 @@ use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::RwLock;
 
+use super::conversation::{
+    session_key, Conversation, ConversationOptions, ConversationReconstructor,
+};
 use super::file_store::{FileStoreError, FlowFileStore};
 use super::filter_parser::{FilterParseError, FilterParser};
-use super::memory_store::{FlowFilter, FlowMemoryStore};
+use super::memory_store::{FlowFilter, FlowMemoryStore, TimeRange};
 use super::models::{FlowState, LLMFlow};
 
 // ============================================================================
@@ -595,8 +598,29 @@ impl FlowQueryService {
         Self::calculate_stats(&flows)
     }
 
+    /// 获取指定时间之后的统计信息（合并内存和文件存储）
+    pub async fn get_stats_since(&self, since: DateTime<Utc>) -> Result<FlowStats, FileStoreError> {
+        let filter = FlowFilter {
+            time_range: Some(TimeRange::new(Some(since), None)),
+            ..Default::default()
+        };
+
+        let mut flows = {
+            let store = self.memory_store.read().await;
+            store.query(&filter)
+        };
+        let memory_ids: std::collections::HashSet<_> = flows.iter().map(|f| f.id.clone()).collect();
+        for flow in self.file_store.query(&filter, i64::MAX as usize, 0)? {
+            if !memory_ids.contains(&flow.id) {
+                flows.push(flow);
+            }
+        }
+
+        Ok(Self::calculate_stats(&flows))
+    }
+
     /// 计算统计信息
-    fn calculate_stats(flows: &[LLMFlow]) -> FlowStats {
+    pub fn calculate_stats(flows: &[LLMFlow]) -> FlowStats {
         if flows.is_empty() {
             return FlowStats::default();
         }
@@ -763,6 +787,98 @@ impl FlowQueryService {
         let store = self.memory_store.read().await;
         store.get_recent(limit)
     }
+
+    /// 获取最近的 Flow（合并内存和文件存储）
+    ///
+    /// # 参数
+    /// - `filter_expr`: 可选的过滤表达式，在最近 `limit` 个 Flow 中筛选
+    /// - `limit`: 最多返回的 Flow 数
+    ///
+    /// # 返回
+    /// 按创建时间降序排列的 Flow
+    pub async fn recent_flows(
+        &self,
+        filter_expr: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<LLMFlow>, QueryWithExpressionError> {
+        let filter_fn = match filter_expr.filter(|e| !e.trim().is_empty()) {
+            Some(expr) => Some(FilterParser::compile(&FilterParser::parse(expr)?)),
+            None => None,
+        };
+
+        let mut flows = self.collect_recent(limit).await?;
+        if let Some(filter_fn) = filter_fn {
+            flows.retain(|f| filter_fn(f));
+        }
+        Self::sort_flows(&mut flows, FlowSortBy::CreatedAt, true);
+        flows.truncate(limit);
+        Ok(flows)
+    }
+
+    /// 按对话谱系重建最近的 Flow
+    ///
+    /// # 参数
+    /// - `filter_expr`: 可选的过滤表达式，先过滤再重建
+    /// - `limit`: 参与重建的最近 Flow 数
+    /// - `options`: 重建选项（价格表等）
+    ///
+    /// # 返回
+    /// 按最近活动时间降序排列的对话
+    pub async fn reconstruct_conversations(
+        &self,
+        filter_expr: Option<&str>,
+        limit: usize,
+        options: ConversationOptions,
+    ) -> Result<Vec<Conversation>, QueryWithExpressionError> {
+        let flows = self.recent_flows(filter_expr, limit).await?;
+        let mut conversations = ConversationReconstructor::new(options).reconstruct(&flows);
+        conversations.sort_by_key(|c| std::cmp::Reverse(c.ended_at));
+        Ok(conversations)
+    }
+
+    /// 获取包含指定 Flow 的对话
+    ///
+    /// 在同一会话（或无会话标识）的最近 `limit` 个 Flow 中重建
+    pub async fn get_conversation(
+        &self,
+        flow_id: &str,
+        limit: usize,
+        options: ConversationOptions,
+    ) -> Result<Option<Conversation>, FileStoreError> {
+        let Some(target) = self.get_flow(flow_id).await? else {
+            return Ok(None);
+        };
+        let key = session_key(&target);
+
+        let mut flows = self.collect_recent(limit).await?;
+        flows.retain(|f| f.id != target.id && session_key(f) == key);
+        Self::sort_flows(&mut flows, FlowSortBy::CreatedAt, true);
+        flows.truncate(limit);
+        flows.push(target);
+
+        Ok(ConversationReconstructor::new(options)
+            .reconstruct(&flows)
+            .into_iter()
+            .find(|c| c.contains(flow_id)))
+    }
+
+    /// 合并内存和文件中最近的 Flow（以 ID 去重）
+    async fn collect_recent(&self, limit: usize) -> Result<Vec<LLMFlow>, FileStoreError> {
+        let mut all_flows = {
+            let store = self.memory_store.read().await;
+            store.query(&FlowFilter::default())
+        };
+        let file_flows = self.file_store.query(&FlowFilter::default(), limit, 0)?;
+
+        let memory_ids: std::collections::HashSet<_> =
+            all_flows.iter().map(|f| f.id.clone()).collect();
+        for flow in file_flows {
+            if !memory_ids.contains(&flow.id) {
+                all_flows.push(flow);
+            }
+        }
+        Ok(all_flows)
+    }
 }
 
 // ============================================================================
@@ -996,6 +1112,101 @@ mod tests {
         assert!(!result.has_next);
         assert!(!result.has_prev);
     }
+
+    #[tokio::test]
+    async fn test_reconstruct_conversations_across_stores() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_store = Arc::new(
+            FlowFileStore::new(temp_dir.path().to_path_buf(), Default::default()).unwrap(),
+        );
+        let memory_store = Arc::new(RwLock::new(FlowMemoryStore::new(100)));
+
+        let mut first = create_test_flow("a", "gpt-4o", ProviderType::OpenAI, FlowState::Completed);
+        first.request.body = serde_json::json!({"messages": [{"role": "user", "content": "hi"}]});
+        first.response = Some(LLMResponse {
+            content: "hello".to_string(),
+            usage: TokenUsage {
+                input_tokens: 10,
+                output_tokens: 5,
+                total_tokens: 15,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let mut second =
+            create_test_flow("b", "gpt-4o", ProviderType::OpenAI, FlowState::Completed);
+        second.timestamps.request_start =
+            first.timestamps.request_start + chrono::Duration::seconds(1);
+        second.request.body = serde_json::json!({"messages": [
+            {"role": "user", "content": "hi"},
+            {"role": "assistant", "content": "hello"},
+            {"role": "user", "content": "again"},
+        ]});
+        file_store.write(&first).unwrap();
+        memory_store.write().await.add(second);
+
+        let service = FlowQueryService::new(memory_store, file_store);
+        let conversations = service
+            .reconstruct_conversations(None, 100, ConversationOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(conversations.len(), 1);
+        assert_eq!(conversations[0].flow_ids(), vec!["a", "b"]);
+        assert_eq!(conversations[0].totals.total_tokens, 15);
+
+        let conversation = service
+            .get_conversation("b", 100, ConversationOptions::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(conversation.id, "a");
+
+        assert!(service
+            .reconstruct_conversations(Some("~m claude"), 100, ConversationOptions::default())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_recent_flows_and_stats_since_across_stores() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_store = Arc::new(
+            FlowFileStore::new(temp_dir.path().to_path_buf(), Default::default()).unwrap(),
+        );
+        let memory_store = Arc::new(RwLock::new(FlowMemoryStore::new(100)));
+
+        let mut old = create_test_flow("old", "gpt-4o", ProviderType::OpenAI, FlowState::Failed);
+        old.timestamps.created = Utc::now() - chrono::Duration::days(2);
+        let archived = create_test_flow(
+            "archived",
+            "claude-3",
+            ProviderType::Claude,
+            FlowState::Completed,
+        );
+        let live = create_test_flow("live", "gpt-4o", ProviderType::OpenAI, FlowState::Completed);
+        file_store.write(&old).unwrap();
+        file_store.write(&archived).unwrap();
+        memory_store.write().await.add(live);
+
+        let service = FlowQueryService::new(memory_store, file_store);
+        let all = service.recent_flows(None, 10).await.unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all.last().unwrap().id, "old");
+
+        let gpt = service.recent_flows(Some("~m gpt"), 10).await.unwrap();
+        let ids: Vec<_> = gpt.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&"live") && ids.contains(&"old"));
+        assert!(service.recent_flows(Some("~m ("), 10).await.is_err());
+
+        let stats = service
+            .get_stats_since(Utc::now() - chrono::Duration::hours(24))
+            .await
+            .unwrap();
+        assert_eq!(stats.total_requests, 2);
+        assert_eq!(stats.failed_requests, 0);
+    }
 }
 
 // ============================================================================
@@ -14,6 +14,7 @@ use std::sync::Mutex;
 use thiserror::Error;
 use uuid::Uuid;
 
+use super::dataset::{DatasetError, DatasetExport, DatasetExporter, DatasetOptions};
 use super::exporter::{ExportFormat, ExportOptions, FlowExporter};
 use super::models::LLMFlow;
 
@@ -38,6 +39,9 @@ pub enum SessionError {
 
     #[error("IO 错误: {0}")]
     Io(#[from] std::io::Error),
+
+    #[error("数据集导出错误: {0}")]
+    Dataset(#[from] DatasetError),
 }
 
 pub type Result<T> = std::result::Result<T, SessionError>;
@@ -781,6 +785,34 @@ impl SessionManager {
         })
     }
 
+    /// 把会话导出为数据集
+    ///
+    /// 只导出 `flows` 中属于该会话的 Flow，并按加入会话的顺序排列
+    ///
+    /// # Arguments
+    /// * `session_id` - 会话 ID
+    /// * `flows` - 候选 Flow 列表
+    /// * `options` - 数据集导出选项
+    pub fn export_session_dataset(
+        &self,
+        session_id: &str,
+        flows: &[LLMFlow],
+        options: &DatasetOptions,
+    ) -> Result<DatasetExport> {
+        if self.get_session(session_id)?.is_none() {
+            return Err(SessionError::SessionNotFound(session_id.to_string()));
+        }
+
+        let by_id: HashMap<&str, &LLMFlow> = flows.iter().map(|f| (f.id.as_str(), f)).collect();
+        let session_flows: Vec<LLMFlow> = self
+            .get_session_flow_ids(session_id)?
+            .iter()
+            .filter_map(|id| by_id.get(id.as_str()).map(|f| (*f).clone()))
+            .collect();
+
+        Ok(DatasetExporter::new(options.clone()).export(&session_flows)?)
+    }
+
     /// 获取会话数量
     pub fn session_count(&self) -> Result<usize> {
         let conn = self.db.lock().unwrap();
@@ -933,6 +965,53 @@ mod tests {
         assert!(!manager.is_flow_in_session(&session.id, "flow-2").unwrap());
     }
 
+    #[test]
+    fn test_export_session_dataset() {
+        use crate::flow_monitor::models::{
+            FlowMetadata, FlowState, FlowType, LLMRequest, LLMResponse,
+        };
+
+        let manager = create_test_manager();
+        let session = manager.create_session("Golden", None).unwrap();
+        manager.add_flow(&session.id, "flow-1").unwrap();
+
+        let flows: Vec<LLMFlow> = ["flow-1", "flow-2"]
+            .iter()
+            .map(|id| {
+                let request = LLMRequest {
+                    body: serde_json::json!({
+                        "model": "gpt-4o",
+                        "messages": [{"role": "user", "content": *id}],
+                    }),
+                    ..Default::default()
+                };
+                let mut flow = LLMFlow::new(
+                    id.to_string(),
+                    FlowType::ChatCompletions,
+                    request,
+                    FlowMetadata::default(),
+                );
+                flow.state = FlowState::Completed;
+                flow.response = Some(LLMResponse {
+                    content: "ok".to_string(),
+                    ..Default::default()
+                });
+                flow
+            })
+            .collect();
+
+        let export = manager
+            .export_session_dataset(&session.id, &flows, &DatasetOptions::default())
+            .unwrap();
+        assert_eq!(export.stats.total, 1);
+        assert_eq!(export.train.len(), 1);
+        assert_eq!(export.train[0]["messages"][0]["content"], "flow-1");
+
+        let result =
+            manager.export_session_dataset("non-existent", &flows, &DatasetOptions::default());
+        assert!(matches!(result, Err(SessionError::SessionNotFound(_))));
+    }
+
     #[test]
     fn test_get_sessions_for_flow() {
         let manager = create_test_manager();
@@ -0,0 +1,1293 @@
+//! Flow 回归测试套件
+//!
+//! 把一组已保存的 Flow（按 ID 列表或过滤表达式选取）连同断言保存为命名套件，
+//! 在指定的 Provider / 模型 / 凭证上重新执行，并生成通过/失败报告
+//! （JSON、JUnit XML、Markdown），用于确认路由或模型变更没有让 Agent 回归。
+//!
+//! 套件中的请求由服务器层实现的 [`SuiteExecutor`] 送入正常的请求处理管道，
+//! 每次执行都会记录一个带 `regression`、`suite:<id>` 标签的新 Flow。
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::diff::output_similarity;
+use super::filter_parser::FilterParser;
+use super::memory_store::{FlowFilter, FlowMemoryStore};
+use super::models::{FlowState, LLMFlow, LLMResponse};
+use crate::converter::structured_output::{extract_json_text, validate_schema};
+use crate::ProviderType;
+
+/// 回归执行产生的 Flow 标签
+pub const REGRESSION_FLOW_TAG: &str = "regression";
+
+/// 回归 Flow 所属套件的标签前缀（`suite:<id>`）
+pub const SUITE_TAG_PREFIX: &str = "suite:";
+
+/// 按过滤表达式选取 Flow 时的默认数量上限
+const DEFAULT_FILTER_LIMIT: usize = 50;
+
+/// 生成回归 Flow 的标签
+pub fn regression_flow_tags(suite_id: &str) -> Vec<String> {
+    vec![
+        REGRESSION_FLOW_TAG.to_string(),
+        format!("{}{}", SUITE_TAG_PREFIX, suite_id),
+    ]
+}
+
+// ============================================================================
+// 错误
+// ============================================================================
+
+/// 回归套件错误
+#[derive(Debug, Error)]
+pub enum SuiteError {
+    #[error("数据库错误: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("序列化错误: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("IO 错误: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("套件不存在: {0}")]
+    SuiteNotFound(String),
+
+    #[error("Flow 不存在: {0}")]
+    FlowNotFound(String),
+
+    #[error("无效的套件定义: {0}")]
+    InvalidSuite(String),
+
+    #[error("无效的过滤表达式: {0}")]
+    InvalidFilter(String),
+
+    #[error("锁错误")]
+    LockPoisoned,
+}
+
+// ============================================================================
+// 套件定义
+// ============================================================================
+
+/// 套件断言
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SuiteAssertion {
+    /// 响应内容包含指定文本
+    Contains {
+        value: String,
+        #[serde(default)]
+        ignore_case: bool,
+    },
+    /// 响应内容匹配正则表达式
+    Regex { pattern: String },
+    /// 响应内容（提取 JSON 后）符合 JSON Schema
+    JsonSchema { schema: Value },
+    /// 调用了指定名称的工具
+    ToolCalled { name: String },
+    /// 输出 Token 数不超过上限
+    MaxOutputTokens { max: u32 },
+    /// 总 Token 数不超过上限
+    MaxTotalTokens { max: u32 },
+    /// 延迟不超过上限（毫秒）
+    MaxLatencyMs { max: u64 },
+    /// 与原始 Flow 输出的相似度不低于阈值（0.0 - 1.0）
+    Similarity { min: f64 },
+}
+
+impl SuiteAssertion {
+    /// 断言的简短描述（用于报告）
+    pub fn label(&self) -> String {
+        match self {
+            SuiteAssertion::Contains { value, ignore_case } => {
+                let suffix = if *ignore_case { ", ignore_case" } else { "" };
+                format!("contains({:?}{})", value, suffix)
+            }
+            SuiteAssertion::Regex { pattern } => format!("regex(/{}/)", pattern),
+            SuiteAssertion::JsonSchema { .. } => "json_schema".to_string(),
+            SuiteAssertion::ToolCalled { name } => format!("tool_called({})", name),
+            SuiteAssertion::MaxOutputTokens { max } => format!("output_tokens <= {}", max),
+            SuiteAssertion::MaxTotalTokens { max } => format!("total_tokens <= {}", max),
+            SuiteAssertion::MaxLatencyMs { max } => format!("latency_ms <= {}", max),
+            SuiteAssertion::Similarity { min } => format!("similarity >= {:.2}", min),
+        }
+    }
+
+    /// 校验断言参数
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            SuiteAssertion::Regex { pattern } => Regex::new(pattern)
+                .map(|_| ())
+                .map_err(|e| format!("无效的正则表达式 '{}': {}", pattern, e)),
+            SuiteAssertion::JsonSchema { schema } if !schema.is_object() => {
+                Err("json_schema 断言的 schema 必须是对象".to_string())
+            }
+            SuiteAssertion::Similarity { min } if !(0.0..=1.0).contains(min) => {
+                Err(format!("相似度阈值必须在 0.0 - 1.0 之间: {}", min))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 对一次执行结果求值
+    ///
+    /// # Arguments
+    /// * `response` - 本次执行得到的响应
+    /// * `latency_ms` - 本次执行的延迟
+    /// * `original` - 套件中的原始 Flow（相似度断言的基准）
+    pub fn evaluate(
+        &self,
+        response: &LLMResponse,
+        latency_ms: u64,
+        original: &LLMFlow,
+    ) -> AssertionResult {
+        let outcome = self.check(response, latency_ms, original);
+        let (passed, message) = match outcome {
+            Ok(message) => (true, message),
+            Err(message) => (false, message),
+        };
+        AssertionResult {
+            assertion: self.clone(),
+            label: self.label(),
+            passed,
+            message,
+        }
+    }
+
+    fn check(
+        &self,
+        response: &LLMResponse,
+        latency_ms: u64,
+        original: &LLMFlow,
+    ) -> Result<String, String> {
+        let content = &response.content;
+        match self {
+            SuiteAssertion::Contains { value, ignore_case } => {
+                let found = if *ignore_case {
+                    content.to_lowercase().contains(&value.to_lowercase())
+                } else {
+                    content.contains(value.as_str())
+                };
+                if found {
+                    Ok(format!("输出包含 {:?}", value))
+                } else {
+                    Err(format!("输出不包含 {:?}", value))
+                }
+            }
+            SuiteAssertion::Regex { pattern } => {
+                let re = Regex::new(pattern).map_err(|e| format!("无效的正则表达式: {}", e))?;
+                match re.find(content) {
+                    Some(m) => Ok(format!("匹配 {:?}", m.as_str())),
+                    None => Err(format!("输出不匹配 /{}/", pattern)),
+                }
+            }
+            SuiteAssertion::JsonSchema { schema } => {
+                let value: Value = serde_json::from_str(extract_json_text(content))
+                    .map_err(|e| format!("输出不是有效的 JSON: {}", e))?;
+                let errors = validate_schema(&value, schema);
+                if errors.is_empty() {
+                    Ok("输出符合 JSON Schema".to_string())
+                } else {
+                    Err(errors.join("; "))
+                }
+            }
+            SuiteAssertion::ToolCalled { name } => {
+                let called: Vec<&str> = response
+                    .tool_calls
+                    .iter()
+                    .map(|c| c.function.name.as_str())
+                    .collect();
+                if called.contains(&name.as_str()) {
+                    Ok(format!("调用了工具 {}", name))
+                } else if called.is_empty() {
+                    Err(format!("未调用工具 {}（没有任何工具调用）", name))
+                } else {
+                    Err(format!(
+                        "未调用工具 {}（实际调用: {}）",
+                        name,
+                        called.join(", ")
+                    ))
+                }
+            }
+            SuiteAssertion::MaxOutputTokens { max } => compare_max(
+                "输出 Token",
+                response.usage.output_tokens as u64,
+                *max as u64,
+            ),
+            SuiteAssertion::MaxTotalTokens { max } => {
+                let total = if response.usage.total_tokens > 0 {
+                    response.usage.total_tokens
+                } else {
+                    response.usage.input_tokens + response.usage.output_tokens
+                };
+                compare_max("总 Token", total as u64, *max as u64)
+            }
+            SuiteAssertion::MaxLatencyMs { max } => compare_max("延迟 (ms)", latency_ms, *max),
+            SuiteAssertion::Similarity { min } => {
+                let baseline = original
+                    .response
+                    .as_ref()
+                    .map(|r| r.content.as_str())
+                    .ok_or_else(|| "原始 Flow 没有响应，无法比较相似度".to_string())?;
+                let similarity = output_similarity(baseline, content);
+                if similarity >= *min {
+                    Ok(format!("相似度 {:.3}", similarity))
+                } else {
+                    Err(format!("相似度 {:.3} 低于阈值 {:.3}", similarity, min))
+                }
+            }
+        }
+    }
+}
+
+fn compare_max(name: &str, actual: u64, max: u64) -> Result<String, String> {
+    if actual <= max {
+        Ok(format!("{} {} <= {}", name, actual, max))
+    } else {
+        Err(format!("{} {} 超过上限 {}", name, actual, max))
+    }
+}
+
+/// 套件执行目标
+///
+/// 未设置的字段沿用原始请求（模型）或服务器默认值（Provider、凭证选择）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SuiteTarget {
+    /// Provider 类型（如 `openai`、`claude`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// 覆盖请求中的模型
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// 指定凭证 UUID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_id: Option<String>,
+}
+
+impl SuiteTarget {
+    /// 用 `other` 中已设置的字段覆盖当前目标
+    pub fn overridden_by(&self, other: &SuiteTarget) -> SuiteTarget {
+        SuiteTarget {
+            provider: other.provider.clone().or_else(|| self.provider.clone()),
+            model: other.model.clone().or_else(|| self.model.clone()),
+            credential_id: other
+                .credential_id
+                .clone()
+                .or_else(|| self.credential_id.clone()),
+        }
+    }
+
+    /// 目标描述（用于报告）
+    pub fn describe(&self) -> String {
+        format!(
+            "{} / {} / {}",
+            self.provider.as_deref().unwrap_or("默认 Provider"),
+            self.model.as_deref().unwrap_or("原始模型"),
+            self.credential_id.as_deref().unwrap_or("自动选择凭证"),
+        )
+    }
+}
+
+/// 套件定义（创建和更新时由用户提供）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SuiteDefinition {
+    /// 套件名称
+    pub name: String,
+    /// 描述
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 按 ID 选取的 Flow（按给定顺序执行）
+    #[serde(default)]
+    pub flow_ids: Vec<String>,
+    /// 过滤表达式，在内存中已完成的 Flow 上求值（不包含回归 Flow）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// 过滤表达式选取的数量上限（默认 50，取最近的 Flow）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// 对每个 Flow 生效的断言
+    #[serde(default)]
+    pub assertions: Vec<SuiteAssertion>,
+    /// 仅对指定 Flow 生效的附加断言（Flow ID -> 断言）
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub flow_assertions: HashMap<String, Vec<SuiteAssertion>>,
+    /// 默认执行目标
+    #[serde(default)]
+    pub target: SuiteTarget,
+}
+
+impl SuiteDefinition {
+    /// 校验套件定义
+    pub fn validate(&self) -> Result<(), SuiteError> {
+        if self.name.trim().is_empty() {
+            return Err(SuiteError::InvalidSuite("套件名称不能为空".to_string()));
+        }
+        if self.flow_ids.is_empty() && self.filter.is_none() {
+            return Err(SuiteError::InvalidSuite(
+                "需要指定 flow_ids 或 filter".to_string(),
+            ));
+        }
+        if let Some(filter) = &self.filter {
+            FilterParser::parse(filter).map_err(|e| SuiteError::InvalidFilter(e.to_string()))?;
+        }
+        for assertion in self
+            .assertions
+            .iter()
+            .chain(self.flow_assertions.values().flatten())
+        {
+            assertion.validate().map_err(SuiteError::InvalidSuite)?;
+        }
+        validate_target(&self.target)
+    }
+
+    /// 获取某个 Flow 生效的全部断言
+    pub fn assertions_for(&self, flow_id: &str) -> Vec<SuiteAssertion> {
+        let mut assertions = self.assertions.clone();
+        if let Some(extra) = self.flow_assertions.get(flow_id) {
+            assertions.extend(extra.iter().cloned());
+        }
+        assertions
+    }
+}
+
+/// 校验执行目标中的 Provider
+pub fn validate_target(target: &SuiteTarget) -> Result<(), SuiteError> {
+    if let Some(provider) = &target.provider {
+        provider
+            .parse::<ProviderType>()
+            .map_err(|e| SuiteError::InvalidSuite(format!("无效的 provider: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// 已保存的套件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlowSuite {
+    /// 套件 ID
+    pub id: String,
+    /// 套件定义
+    #[serde(flatten)]
+    pub definition: SuiteDefinition,
+    /// 创建时间
+    pub created_at: DateTime<Utc>,
+    /// 更新时间
+    pub updated_at: DateTime<Utc>,
+}
+
+// ============================================================================
+// 存储
+// ============================================================================
+
+/// 回归套件 SQLite 存储
+pub struct SuiteStore {
+    db: Mutex<Connection>,
+}
+
+impl SuiteStore {
+    /// 打开（或创建）套件数据库
+    ///
+    /// # Arguments
+    /// * `db_path` - SQLite 数据库路径
+    pub fn new(db_path: PathBuf) -> Result<Self, SuiteError> {
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Self::from_connection(Connection::open(&db_path)?)
+    }
+
+    /// 创建内存数据库存储（数据库文件不可用时回退使用）
+    pub fn in_memory() -> Result<Self, SuiteError> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// 从现有连接创建存储
+    pub fn from_connection(conn: Connection) -> Result<Self, SuiteError> {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS flow_suites (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                definition TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            "#,
+        )?;
+        Ok(Self {
+            db: Mutex::new(conn),
+        })
+    }
+
+    /// 默认数据库路径：`~/.proxycast/flow_suites.db`
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".proxycast").join("flow_suites.db"))
+    }
+
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>, SuiteError> {
+        self.db.lock().map_err(|_| SuiteError::LockPoisoned)
+    }
+
+    /// 创建套件
+    pub fn create(&self, definition: SuiteDefinition) -> Result<FlowSuite, SuiteError> {
+        definition.validate()?;
+        let now = Utc::now();
+        let suite = FlowSuite {
+            id: format!("suite_{}", Uuid::new_v4().simple()),
+            definition,
+            created_at: now,
+            updated_at: now,
+        };
+        self.conn()?.execute(
+            "INSERT INTO flow_suites (id, name, definition, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                suite.id,
+                suite.definition.name,
+                serde_json::to_string(&suite.definition)?,
+                now.timestamp_millis(),
+                now.timestamp_millis()
+            ],
+        )?;
+        Ok(suite)
+    }
+
+    /// 获取套件
+    pub fn get(&self, id: &str) -> Result<Option<FlowSuite>, SuiteError> {
+        let conn = self.conn()?;
+        let row = conn
+            .query_row(
+                "SELECT id, definition, created_at, updated_at FROM flow_suites WHERE id = ?1",
+                params![id],
+                Self::read_row,
+            )
+            .optional()?;
+        row.map(Self::row_to_suite).transpose()
+    }
+
+    /// 列出套件（按名称排序）
+    pub fn list(&self) -> Result<Vec<FlowSuite>, SuiteError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, definition, created_at, updated_at FROM flow_suites ORDER BY name, id",
+        )?;
+        let rows = stmt
+            .query_map([], Self::read_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter().map(Self::row_to_suite).collect()
+    }
+
+    /// 更新套件定义
+    pub fn update(&self, id: &str, definition: SuiteDefinition) -> Result<FlowSuite, SuiteError> {
+        definition.validate()?;
+        let now = Utc::now();
+        let updated = self.conn()?.execute(
+            "UPDATE flow_suites SET name = ?2, definition = ?3, updated_at = ?4 WHERE id = ?1",
+            params![
+                id,
+                definition.name,
+                serde_json::to_string(&definition)?,
+                now.timestamp_millis()
+            ],
+        )?;
+        if updated == 0 {
+            return Err(SuiteError::SuiteNotFound(id.to_string()));
+        }
+        self.get(id)?
+            .ok_or_else(|| SuiteError::SuiteNotFound(id.to_string()))
+    }
+
+    /// 删除套件
+    pub fn delete(&self, id: &str) -> Result<bool, SuiteError> {
+        Ok(self
+            .conn()?
+            .execute("DELETE FROM flow_suites WHERE id = ?1", params![id])?
+            > 0)
+    }
+
+    fn read_row(row: &Row) -> rusqlite::Result<(String, String, i64, i64)> {
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+    }
+
+    fn row_to_suite(
+        (id, definition, created_at, updated_at): (String, String, i64, i64),
+    ) -> Result<FlowSuite, SuiteError> {
+        Ok(FlowSuite {
+            id,
+            definition: serde_json::from_str(&definition)?,
+            created_at: DateTime::from_timestamp_millis(created_at).unwrap_or_default(),
+            updated_at: DateTime::from_timestamp_millis(updated_at).unwrap_or_default(),
+        })
+    }
+}
+
+// ============================================================================
+// Flow 选取
+// ============================================================================
+
+/// 解析套件要执行的 Flow
+///
+/// 先按 `flow_ids` 顺序取（内存中没有时通过 `fallback` 查找，如文件存储），
+/// 再追加过滤表达式在内存中选出的 Flow（按时间正序，跳过回归 Flow 和重复项）。
+pub fn resolve_suite_flows<F>(
+    definition: &SuiteDefinition,
+    memory: &FlowMemoryStore,
+    fallback: F,
+) -> Result<Vec<LLMFlow>, SuiteError>
+where
+    F: Fn(&str) -> Option<LLMFlow>,
+{
+    let mut flows = Vec::new();
+    let mut seen = HashSet::new();
+
+    for id in &definition.flow_ids {
+        if !seen.insert(id.clone()) {
+            continue;
+        }
+        let flow = memory
+            .get(id)
+            .and_then(|f| f.read().ok().map(|f| f.clone()))
+            .or_else(|| fallback(id))
+            .ok_or_else(|| SuiteError::FlowNotFound(id.clone()))?;
+        flows.push(flow);
+    }
+
+    if let Some(filter) = &definition.filter {
+        let parsed =
+            FilterParser::parse(filter).map_err(|e| SuiteError::InvalidFilter(e.to_string()))?;
+        let predicate = FilterParser::compile(&parsed);
+        let limit = definition.limit.unwrap_or(DEFAULT_FILTER_LIMIT);
+        // 内存存储按时间倒序返回，取最近的 limit 个后按时间正序执行
+        let mut selected: Vec<LLMFlow> = memory
+            .query(&FlowFilter::default())
+            .into_iter()
+            .filter(|f| f.state == FlowState::Completed)
+            .filter(|f| !f.annotations.tags.iter().any(|t| t == REGRESSION_FLOW_TAG))
+            .filter(|f| !seen.contains(&f.id))
+            .filter(|f| predicate(f))
+            .take(limit)
+            .collect();
+        selected.reverse();
+        flows.extend(selected);
+    }
+
+    Ok(flows)
+}
+
+// ============================================================================
+// 执行
+// ============================================================================
+
+/// 单个 Flow 的执行结果
+#[derive(Debug, Clone)]
+pub struct SuiteExecution {
+    /// 本次执行记录的 Flow ID
+    pub run_flow_id: Option<String>,
+    /// 执行时使用的模型
+    pub model: String,
+    /// 成功时的响应，失败时的错误信息
+    pub response: Result<LLMResponse, String>,
+    /// 延迟（毫秒）
+    pub latency_ms: u64,
+}
+
+/// 套件执行器
+///
+/// 由服务器层实现，负责把原始 Flow 的请求按目标改写后送入正常的请求处理管道
+#[async_trait]
+pub trait SuiteExecutor: Send + Sync {
+    /// 执行单个 Flow
+    async fn execute(
+        &self,
+        suite: &FlowSuite,
+        flow: &LLMFlow,
+        target: &SuiteTarget,
+    ) -> SuiteExecution;
+}
+
+/// 用例状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuiteCaseStatus {
+    /// 全部断言通过
+    Passed,
+    /// 有断言失败
+    Failed,
+    /// 执行出错（未得到响应）
+    Error,
+}
+
+impl SuiteCaseStatus {
+    fn label(&self) -> &'static str {
+        match self {
+            SuiteCaseStatus::Passed => "通过",
+            SuiteCaseStatus::Failed => "失败",
+            SuiteCaseStatus::Error => "错误",
+        }
+    }
+}
+
+/// 单个断言的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssertionResult {
+    /// 断言
+    pub assertion: SuiteAssertion,
+    /// 断言描述
+    pub label: String,
+    /// 是否通过
+    pub passed: bool,
+    /// 说明
+    pub message: String,
+}
+
+/// 单个用例（Flow）的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuiteCaseResult {
+    /// 原始 Flow ID
+    pub flow_id: String,
+    /// 本次执行记录的 Flow ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_flow_id: Option<String>,
+    /// 执行时使用的模型
+    pub model: String,
+    /// 状态
+    pub status: SuiteCaseStatus,
+    /// 执行错误
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 延迟（毫秒）
+    pub latency_ms: u64,
+    /// 断言结果
+    pub assertions: Vec<AssertionResult>,
+}
+
+/// 套件执行报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuiteReport {
+    /// 套件 ID
+    pub suite_id: String,
+    /// 套件名称
+    pub suite_name: String,
+    /// 实际执行目标
+    pub target: SuiteTarget,
+    /// 开始时间
+    pub started_at: DateTime<Utc>,
+    /// 总耗时（毫秒）
+    pub duration_ms: u64,
+    /// 用例总数
+    pub total: usize,
+    /// 通过数
+    pub passed: usize,
+    /// 失败数
+    pub failed: usize,
+    /// 错误数
+    pub errors: usize,
+    /// 用例结果
+    pub cases: Vec<SuiteCaseResult>,
+}
+
+/// 按顺序执行套件中的 Flow 并求值断言
+///
+/// # Arguments
+/// * `suite` - 套件
+/// * `target` - 实际执行目标（通常为套件目标叠加本次运行的覆盖）
+/// * `flows` - 要执行的原始 Flow（见 [`resolve_suite_flows`]）
+/// * `executor` - 执行器
+pub async fn run_suite(
+    suite: &FlowSuite,
+    target: &SuiteTarget,
+    flows: &[LLMFlow],
+    executor: &dyn SuiteExecutor,
+) -> SuiteReport {
+    let started_at = Utc::now();
+    let mut cases = Vec::with_capacity(flows.len());
+
+    for flow in flows {
+        let execution = executor.execute(suite, flow, target).await;
+        let case = match execution.response {
+            Ok(response) => {
+                let assertions: Vec<AssertionResult> = suite
+                    .definition
+                    .assertions_for(&flow.id)
+                    .iter()
+                    .map(|a| a.evaluate(&response, execution.latency_ms, flow))
+                    .collect();
+                let status = if assertions.iter().all(|a| a.passed) {
+                    SuiteCaseStatus::Passed
+                } else {
+                    SuiteCaseStatus::Failed
+                };
+                SuiteCaseResult {
+                    flow_id: flow.id.clone(),
+                    run_flow_id: execution.run_flow_id,
+                    model: execution.model,
+                    status,
+                    error: None,
+                    latency_ms: execution.latency_ms,
+                    assertions,
+                }
+            }
+            Err(error) => SuiteCaseResult {
+                flow_id: flow.id.clone(),
+                run_flow_id: execution.run_flow_id,
+                model: execution.model,
+                status: SuiteCaseStatus::Error,
+                error: Some(error),
+                latency_ms: execution.latency_ms,
+                assertions: Vec::new(),
+            },
+        };
+        cases.push(case);
+    }
+
+    let count = |status| cases.iter().filter(|c| c.status == status).count();
+    SuiteReport {
+        suite_id: suite.id.clone(),
+        suite_name: suite.definition.name.clone(),
+        target: target.clone(),
+        started_at,
+        duration_ms: (Utc::now() - started_at).num_milliseconds().max(0) as u64,
+        total: cases.len(),
+        passed: count(SuiteCaseStatus::Passed),
+        failed: count(SuiteCaseStatus::Failed),
+        errors: count(SuiteCaseStatus::Error),
+        cases,
+    }
+}
+
+// ============================================================================
+// 报告
+// ============================================================================
+
+impl SuiteReport {
+    /// 是否全部通过
+    pub fn success(&self) -> bool {
+        self.failed == 0 && self.errors == 0
+    }
+
+    /// 导出为 JUnit XML（可直接被 CI 解析）
+    pub fn to_junit_xml(&self) -> String {
+        let seconds = |ms: u64| format!("{:.3}", ms as f64 / 1000.0);
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuites name=\"proxycast\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{}\">\n",
+            self.total,
+            self.failed,
+            self.errors,
+            seconds(self.duration_ms)
+        ));
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" id=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{}\" timestamp=\"{}\">\n",
+            xml_escape(&self.suite_name),
+            xml_escape(&self.suite_id),
+            self.total,
+            self.failed,
+            self.errors,
+            seconds(self.duration_ms),
+            self.started_at.format("%Y-%m-%dT%H:%M:%S")
+        ));
+
+        xml.push_str("    <properties>\n");
+        let properties = [
+            ("provider", self.target.provider.as_deref()),
+            ("model", self.target.model.as_deref()),
+            ("credential_id", self.target.credential_id.as_deref()),
+        ];
+        for (name, value) in properties {
+            if let Some(value) = value {
+                xml.push_str(&format!(
+                    "      <property name=\"{}\" value=\"{}\"/>\n",
+                    name,
+                    xml_escape(value)
+                ));
+            }
+        }
+        xml.push_str("    </properties>\n");
+
+        for case in &self.cases {
+            xml.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}\" time=\"{}\">\n",
+                xml_escape(&self.suite_name),
+                xml_escape(&case.flow_id),
+                seconds(case.latency_ms)
+            ));
+            match case.status {
+                SuiteCaseStatus::Passed => {}
+                SuiteCaseStatus::Failed => {
+                    let failed: Vec<&AssertionResult> =
+                        case.assertions.iter().filter(|a| !a.passed).collect();
+                    let details: Vec<String> = failed
+                        .iter()
+                        .map(|a| format!("{}: {}", a.label, a.message))
+                        .collect();
+                    xml.push_str(&format!(
+                        "      <failure type=\"assertion\" message=\"{} 个断言失败\">{}</failure>\n",
+                        failed.len(),
+                        xml_escape(&details.join("\n"))
+                    ));
+                }
+                SuiteCaseStatus::Error => {
+                    xml.push_str(&format!(
+                        "      <error type=\"execution\" message=\"{}\"/>\n",
+                        xml_escape(case.error.as_deref().unwrap_or_default())
+                    ));
+                }
+            }
+            xml.push_str(&format!(
+                "      <system-out>model={} run_flow_id={}</system-out>\n",
+                xml_escape(&case.model),
+                xml_escape(case.run_flow_id.as_deref().unwrap_or("-"))
+            ));
+            xml.push_str("    </testcase>\n");
+        }
+
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+
+    /// 导出为 Markdown
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        md.push_str(&format!("# 回归套件报告: {}\n\n", self.suite_name));
+        md.push_str(&format!("**套件 ID**: {}\n\n", self.suite_id));
+        md.push_str(&format!("**执行目标**: {}\n\n", self.target.describe()));
+        md.push_str(&format!(
+            "**开始时间**: {}\n\n",
+            self.started_at.format("%Y-%m-%d %H:%M:%S UTC")
+        ));
+        md.push_str(&format!(
+            "**结果**: {}（通过 {} / 失败 {} / 错误 {}，共 {}，耗时 {} ms）\n\n",
+            if self.success() { "通过" } else { "失败" },
+            self.passed,
+            self.failed,
+            self.errors,
+            self.total,
+            self.duration_ms
+        ));
+
+        md.push_str("## 用例\n\n");
+        md.push_str("| Flow | 模型 | 状态 | 延迟 (ms) | 断言 | 回归 Flow |\n");
+        md.push_str("|------|------|------|-----------|------|-----------|\n");
+        for case in &self.cases {
+            let passed = case.assertions.iter().filter(|a| a.passed).count();
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | {}/{} | {} |\n",
+                case.flow_id,
+                case.model,
+                case.status.label(),
+                case.latency_ms,
+                passed,
+                case.assertions.len(),
+                case.run_flow_id.as_deref().unwrap_or("-")
+            ));
+        }
+        md.push('\n');
+
+        let problems: Vec<&SuiteCaseResult> = self
+            .cases
+            .iter()
+            .filter(|c| c.status != SuiteCaseStatus::Passed)
+            .collect();
+        if !problems.is_empty() {
+            md.push_str("## 失败详情\n\n");
+            for case in problems {
+                md.push_str(&format!("### {}\n\n", case.flow_id));
+                if let Some(error) = &case.error {
+                    md.push_str(&format!("- 执行错误: {}\n", error.replace('\n', " ")));
+                }
+                for assertion in case.assertions.iter().filter(|a| !a.passed) {
+                    md.push_str(&format!(
+                        "- `{}`: {}\n",
+                        assertion.label,
+                        assertion.message.replace('\n', " ")
+                    ));
+                }
+                md.push('\n');
+            }
+        }
+        md
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c if (c as u32) < 0x20 && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow_monitor::models::{
+        FlowMetadata, FlowType, FunctionCall, LLMRequest, TokenUsage, ToolCall,
+    };
+    use serde_json::json;
+
+    fn completed_flow(id: &str, answer: &str) -> LLMFlow {
+        let request = LLMRequest {
+            path: "/v1/chat/completions".to_string(),
+            model: "gpt-4o".to_string(),
+            body: json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "hi"}]}),
+            ..Default::default()
+        };
+        let mut flow = LLMFlow::new(
+            id.to_string(),
+            FlowType::ChatCompletions,
+            request,
+            FlowMetadata::default(),
+        );
+        flow.state = FlowState::Completed;
+        flow.response = Some(LLMResponse {
+            content: answer.to_string(),
+            ..Default::default()
+        });
+        flow
+    }
+
+    fn response(content: &str) -> LLMResponse {
+        LLMResponse {
+            content: content.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn suite(assertions: Vec<SuiteAssertion>) -> FlowSuite {
+        FlowSuite {
+            id: "suite_1".to_string(),
+            definition: SuiteDefinition {
+                name: "agent <smoke>".to_string(),
+                flow_ids: vec!["f1".to_string(), "f2".to_string()],
+                assertions,
+                ..Default::default()
+            },
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    struct MockExecutor;
+
+    #[async_trait]
+    impl SuiteExecutor for MockExecutor {
+        async fn execute(
+            &self,
+            _suite: &FlowSuite,
+            flow: &LLMFlow,
+            target: &SuiteTarget,
+        ) -> SuiteExecution {
+            let model = target.model.clone().unwrap_or(flow.request.model.clone());
+            let response = match flow.id.as_str() {
+                "f1" => Ok(response("The weather in Paris is sunny")),
+                "f2" => Ok(response("I cannot help with that")),
+                _ => Err("upstream returned 503".to_string()),
+            };
+            SuiteExecution {
+                run_flow_id: Some(format!("run-{}", flow.id)),
+                model,
+                response,
+                latency_ms: 120,
+            }
+        }
+    }
+
+    #[test]
+    fn test_assertion_serde_format() {
+        let assertion: SuiteAssertion =
+            serde_json::from_value(json!({"type": "tool_called", "name": "search"})).unwrap();
+        assert_eq!(
+            assertion,
+            SuiteAssertion::ToolCalled {
+                name: "search".to_string()
+            }
+        );
+        let value = serde_json::to_value(SuiteAssertion::MaxLatencyMs { max: 500 }).unwrap();
+        assert_eq!(value, json!({"type": "max_latency_ms", "max": 500}));
+    }
+
+    #[test]
+    fn test_text_assertions() {
+        let original = completed_flow("f1", "sunny");
+        let resp = response("Result: {\"city\": \"Paris\", \"temp\": 21}");
+
+        let contains = SuiteAssertion::Contains {
+            value: "paris".to_string(),
+            ignore_case: true,
+        };
+        assert!(contains.evaluate(&resp, 0, &original).passed);
+        let contains = SuiteAssertion::Contains {
+            value: "paris".to_string(),
+            ignore_case: false,
+        };
+        assert!(!contains.evaluate(&resp, 0, &original).passed);
+
+        let regex = SuiteAssertion::Regex {
+            pattern: r#""temp": \d+"#.to_string(),
+        };
+        assert!(regex.evaluate(&resp, 0, &original).passed);
+
+        let schema = SuiteAssertion::JsonSchema {
+            schema: json!({
+                "type": "object",
+                "required": ["city", "temp"],
+                "properties": {"temp": {"type": "integer"}},
+            }),
+        };
+        assert!(schema.evaluate(&resp, 0, &original).passed);
+        let strict = SuiteAssertion::JsonSchema {
+            schema: json!({"type": "object", "required": ["humidity"]}),
+        };
+        let result = strict.evaluate(&resp, 0, &original);
+        assert!(!result.passed);
+        assert!(result.message.contains("humidity"));
+    }
+
+    #[test]
+    fn test_tool_token_and_latency_assertions() {
+        let original = completed_flow("f1", "");
+        let mut resp = response("");
+        resp.tool_calls = vec![ToolCall {
+            id: "call_1".to_string(),
+            tool_type: "function".to_string(),
+            function: FunctionCall {
+                name: "search".to_string(),
+                arguments: "{}".to_string(),
+            },
+        }];
+        resp.usage = TokenUsage {
+            input_tokens: 100,
+            output_tokens: 40,
+            ..Default::default()
+        };
+
+        let tool = |name: &str| SuiteAssertion::ToolCalled {
+            name: name.to_string(),
+        };
+        assert!(tool("search").evaluate(&resp, 0, &original).passed);
+        let missing = tool("weather").evaluate(&resp, 0, &original);
+        assert!(!missing.passed);
+        assert!(missing.message.contains("search"));
+
+        assert!(
+            SuiteAssertion::MaxOutputTokens { max: 40 }
+                .evaluate(&resp, 0, &original)
+                .passed
+        );
+        assert!(
+            !SuiteAssertion::MaxTotalTokens { max: 120 }
+                .evaluate(&resp, 0, &original)
+                .passed
+        );
+        assert!(
+            SuiteAssertion::MaxLatencyMs { max: 500 }
+                .evaluate(&resp, 499, &original)
+                .passed
+        );
+        assert!(
+            !SuiteAssertion::MaxLatencyMs { max: 500 }
+                .evaluate(&resp, 501, &original)
+                .passed
+        );
+    }
+
+    #[test]
+    fn test_similarity_assertion_uses_original_output() {
+        let original = completed_flow("f1", "The weather in Paris is sunny");
+        let similar = response("The weather in Paris is sunny today");
+        let different = response("I cannot help with that");
+        let assertion = SuiteAssertion::Similarity { min: 0.7 };
+
+        assert!(assertion.evaluate(&similar, 0, &original).passed);
+        assert!(!assertion.evaluate(&different, 0, &original).passed);
+
+        let mut no_response = original.clone();
+        no_response.response = None;
+        assert!(!assertion.evaluate(&similar, 0, &no_response).passed);
+    }
+
+    #[test]
+    fn test_definition_validation() {
+        let valid = SuiteDefinition {
+            name: "smoke".to_string(),
+            filter: Some("~m gpt".to_string()),
+            ..Default::default()
+        };
+        assert!(valid.validate().is_ok());
+
+        let no_source = SuiteDefinition {
+            name: "smoke".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            no_source.validate(),
+            Err(SuiteError::InvalidSuite(_))
+        ));
+
+        let bad_regex = SuiteDefinition {
+            assertions: vec![SuiteAssertion::Regex {
+                pattern: "(".to_string(),
+            }],
+            ..valid.clone()
+        };
+        assert!(matches!(
+            bad_regex.validate(),
+            Err(SuiteError::InvalidSuite(_))
+        ));
+
+        let bad_provider = SuiteDefinition {
+            target: SuiteTarget {
+                provider: Some("nope".to_string()),
+                ..Default::default()
+            },
+            ..valid
+        };
+        assert!(matches!(
+            bad_provider.validate(),
+            Err(SuiteError::InvalidSuite(_))
+        ));
+    }
+
+    #[test]
+    fn test_store_crud() {
+        let store = SuiteStore::in_memory().unwrap();
+        let definition = SuiteDefinition {
+            name: "smoke".to_string(),
+            flow_ids: vec!["f1".to_string()],
+            assertions: vec![SuiteAssertion::Similarity { min: 0.5 }],
+            ..Default::default()
+        };
+        let created = store.create(definition.clone()).unwrap();
+        assert!(created.id.starts_with("suite_"));
+        assert_eq!(
+            store.get(&created.id).unwrap().unwrap().definition,
+            definition
+        );
+
+        let mut changed = definition;
+        changed.name = "renamed".to_string();
+        changed.target.model = Some("gpt-4o-mini".to_string());
+        let updated = store.update(&created.id, changed.clone()).unwrap();
+        assert_eq!(updated.definition, changed);
+        assert_eq!(store.list().unwrap().len(), 1);
+
+        assert!(matches!(
+            store.update("missing", changed),
+            Err(SuiteError::SuiteNotFound(_))
+        ));
+        assert!(store.delete(&created.id).unwrap());
+        assert!(store.get(&created.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_resolve_flows_by_ids_and_filter() {
+        let mut memory = FlowMemoryStore::new(100);
+        memory.add(completed_flow("m1", "a"));
+        let mut regression = completed_flow("m2", "b");
+        regression.annotations.tags = regression_flow_tags("suite_1");
+        memory.add(regression);
+        memory.add(completed_flow("m3", "c"));
+
+        let definition = SuiteDefinition {
+            name: "smoke".to_string(),
+            flow_ids: vec!["m3".to_string(), "archived".to_string()],
+            filter: Some("~m gpt".to_string()),
+            ..Default::default()
+        };
+        let flows = resolve_suite_flows(&definition, &memory, |id| {
+            (id == "archived").then(|| completed_flow(id, "d"))
+        })
+        .unwrap();
+        let ids: Vec<&str> = flows.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(ids, vec!["m3", "archived", "m1"]);
+
+        let missing = SuiteDefinition {
+            flow_ids: vec!["nope".to_string()],
+            filter: None,
+            ..definition
+        };
+        assert!(matches!(
+            resolve_suite_flows(&missing, &memory, |_| None),
+            Err(SuiteError::FlowNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_run_suite_report() {
+        let mut suite = suite(vec![SuiteAssertion::Contains {
+            value: "sunny".to_string(),
+            ignore_case: false,
+        }]);
+        suite.definition.flow_assertions.insert(
+            "f1".to_string(),
+            vec![SuiteAssertion::MaxLatencyMs { max: 1000 }],
+        );
+        let flows = vec![
+            completed_flow("f1", "sunny"),
+            completed_flow("f2", "sunny"),
+            completed_flow("f3", "sunny"),
+        ];
+        let target = SuiteTarget {
+            model: Some("gpt-4o-mini".to_string()),
+            ..Default::default()
+        };
+
+        let report = run_suite(&suite, &target, &flows, &MockExecutor).await;
+        assert_eq!(
+            (report.total, report.passed, report.failed, report.errors),
+            (3, 1, 1, 1)
+        );
+        assert!(!report.success());
+        assert_eq!(report.cases[0].assertions.len(), 2);
+        assert_eq!(report.cases[0].model, "gpt-4o-mini");
+        assert_eq!(report.cases[1].status, SuiteCaseStatus::Failed);
+        assert_eq!(report.cases[2].status, SuiteCaseStatus::Error);
+
+        let xml = report.to_junit_xml();
+        assert!(xml.contains("tests=\"3\" failures=\"1\" errors=\"1\""));
+        assert!(xml.contains("name=\"agent &lt;smoke&gt;\""));
+        assert!(xml.contains("<failure type=\"assertion\""));
+        assert!(xml.contains("<error type=\"execution\" message=\"upstream returned 503\"/>"));
+        assert!(xml.contains("<property name=\"model\" value=\"gpt-4o-mini\"/>"));
+
+        let md = report.to_markdown();
+        assert!(md.contains("# 回归套件报告: agent <smoke>"));
+        assert!(md.contains("| f1 | gpt-4o-mini | 通过 | 120 | 2/2 | run-f1 |"));
+        assert!(md.contains("### f2"));
+        assert!(md.contains("- 执行错误: upstream returned 503"));
+    }
+
+    #[test]
+    fn test_target_override() {
+        let base = SuiteTarget {
+            provider: Some("openai".to_string()),
+            model: Some("gpt-4o".to_string()),
+            credential_id: None,
+        };
+        let merged = base.overridden_by(&SuiteTarget {
+            model: Some("gpt-4o-mini".to_string()),
+            credential_id: Some("cred-1".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(merged.provider.as_deref(), Some("openai"));
+        assert_eq!(merged.model.as_deref(), Some("gpt-4o-mini"));
+        assert_eq!(merged.credential_id.as_deref(), Some("cred-1"));
+    }
+}
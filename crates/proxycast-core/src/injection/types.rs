@@ -2,6 +2,7 @@
 //!
 //! 定义注入规则、注入模式和注入器
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// 允许注入的参数白名单
@@ -29,7 +30,7 @@ const BLOCKED_OVERRIDE_PARAMS: &[&str] = &[
 ];
 
 /// 注入模式
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum InjectionMode {
     /// 合并模式：不覆盖已有参数
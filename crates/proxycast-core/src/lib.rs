@@ -9,6 +9,7 @@
 //! - 路由和弹性处理
 
 // 核心模块
+pub mod batch;
 pub mod config;
 pub mod converter;
 pub mod credential;
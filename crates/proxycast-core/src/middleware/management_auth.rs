@@ -288,6 +288,46 @@ where
     }
 }
 
+/// 校验管理密钥
+///
+/// 供无法挂载 [`ManagementAuthLayer`] 的入口使用（如 WebSocket 连接上的拦截事件订阅），
+/// 规则与认证层一致：未配置 secret_key、非本机访问受限或密钥不匹配时返回 `false`，
+/// 错误的密钥同样计入失败限速。未提供密钥时直接返回 `false`，不计入失败。
+pub fn verify_management_key(
+    config: &RemoteManagementConfig,
+    provided: Option<&str>,
+    client_addr: Option<SocketAddr>,
+) -> bool {
+    type Auth = ManagementAuthService<()>;
+
+    let Some(provided) = provided else {
+        return false;
+    };
+    let client_id = client_addr
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    if !Auth::check_rate_limit(&client_id) {
+        return false;
+    }
+    let Some(secret_key) = config.secret_key.as_deref().filter(|key| !key.is_empty()) else {
+        return false;
+    };
+    if !config.allow_remote && !Auth::is_localhost(client_addr.as_ref()) {
+        return false;
+    }
+    if Auth::secret_key_matches(provided, secret_key) {
+        Auth::record_success(&client_id);
+        true
+    } else {
+        tracing::warn!(
+            "[MANAGEMENT_AUTH] Invalid secret_key from {:?}",
+            client_addr
+        );
+        Auth::record_failure(&client_id);
+        false
+    }
+}
+
 /// 创建错误响应
 fn create_error_response(status: StatusCode, message: &str) -> Response<Body> {
     let body = serde_json::json!({
@@ -340,4 +380,31 @@ mod tests {
         };
         let _layer = ManagementAuthLayer::new(config);
     }
+
+    #[test]
+    fn test_verify_management_key() {
+        let config = RemoteManagementConfig {
+            allow_remote: false,
+            secret_key: Some("test-secret".to_string()),
+            disable_control_panel: false,
+        };
+        let local = "127.0.0.9:8080".parse::<SocketAddr>().ok();
+        let remote = "192.168.7.9:8080".parse::<SocketAddr>().ok();
+
+        assert!(verify_management_key(&config, Some("test-secret"), local));
+        assert!(!verify_management_key(&config, Some("wrong"), local));
+        assert!(!verify_management_key(&config, None, local));
+        // 不允许远程访问时即使密钥正确也拒绝
+        assert!(!verify_management_key(&config, Some("test-secret"), remote));
+
+        let disabled = RemoteManagementConfig {
+            secret_key: None,
+            ..config
+        };
+        assert!(!verify_management_key(
+            &disabled,
+            Some("test-secret"),
+            local
+        ));
+    }
 }
@@ -52,6 +52,40 @@ pub struct CurlExample {
     pub command: String,
 }
 
+impl RouteListResponse {
+    /// 创建路由列表，并在最前面加入指向默认 Provider 的 `default` 路由
+    pub fn new(base_url: &str, default_provider: &str, routes: Vec<RouteInfo>) -> Self {
+        let default_route = RouteInfo {
+            selector: "default".to_string(),
+            provider_type: default_provider.to_string(),
+            credential_count: 1,
+            endpoints: vec![
+                RouteEndpoint {
+                    path: "/v1/messages".to_string(),
+                    protocol: "claude".to_string(),
+                    url: format!("{}/v1/messages", base_url),
+                },
+                RouteEndpoint {
+                    path: "/v1/chat/completions".to_string(),
+                    protocol: "openai".to_string(),
+                    url: format!("{}/v1/chat/completions", base_url),
+                },
+            ],
+            tags: vec!["默认".to_string()],
+            enabled: true,
+        };
+
+        let mut all_routes = vec![default_route];
+        all_routes.extend(routes);
+
+        Self {
+            base_url: base_url.to_string(),
+            default_provider: default_provider.to_string(),
+            routes: all_routes,
+        }
+    }
+}
+
 impl RouteInfo {
     /// 创建新的路由信息
     pub fn new(selector: String, provider_type: String) -> Self {
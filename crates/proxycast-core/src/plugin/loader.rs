@@ -1,12 +1,19 @@
 //! 插件加载器
 
+use super::native::NativePlugin;
+use super::script::{
+    warm_up_token_estimator, Script, ScriptError, ScriptHost, ScriptLimits, ScriptOutcome,
+    ScriptWorker,
+};
 use super::types::{
     HookResult, Plugin, PluginConfig, PluginContext, PluginError, PluginManifest, PluginType,
+    StreamHookEvent,
 };
+use crate::flow_monitor::LLMResponse;
 use async_trait::async_trait;
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 use tokio::fs;
 
 pub struct PluginLoader {
@@ -68,7 +75,7 @@ impl PluginLoader {
         let manifest = self.load_manifest(plugin_dir).await?;
         match manifest.plugin_type {
             PluginType::Script => self.load_script_plugin(plugin_dir, manifest, config).await,
-            PluginType::Native => Err(PluginError::LoadError("原生插件暂不支持".to_string())),
+            PluginType::Native => self.load_native_plugin(plugin_dir, manifest).await,
         }
     }
 
@@ -76,21 +83,83 @@ impl PluginLoader {
         &self,
         plugin_dir: &Path,
         manifest: PluginManifest,
-        _config: &PluginConfig,
+        config: &PluginConfig,
     ) -> Result<Arc<dyn Plugin>, PluginError> {
-        let config_path = plugin_dir.join("config.json");
-        let plugin_settings = if config_path.exists() {
-            let content = fs::read_to_string(&config_path)
+        let plugin_settings = Self::load_settings(plugin_dir).await?;
+
+        let mut plugin = if is_script_entry(&manifest.entry) {
+            let entry = Path::new(&manifest.entry);
+            if entry
+                .components()
+                .any(|c| !matches!(c, Component::Normal(_)))
+            {
+                return Err(PluginError::InvalidManifest(format!(
+                    "入口文件必须位于插件目录内: {}",
+                    manifest.entry
+                )));
+            }
+            let source = fs::read_to_string(plugin_dir.join(entry))
                 .await
-                .map_err(|e| PluginError::LoadError(format!("无法读取配置文件: {}", e)))?;
-            serde_json::from_str(&content).unwrap_or_default()
+                .map_err(|e| PluginError::LoadError(format!("无法读取脚本文件: {}", e)))?;
+            let script = Script::compile(&source)
+                .map_err(|e| PluginError::LoadError(format!("{}: {}", manifest.entry, e)))?;
+            if source.contains("tokens.estimate") {
+                tokio::task::spawn_blocking(warm_up_token_estimator);
+            }
+            ScriptPlugin::with_script(manifest, plugin_settings, script)
         } else {
-            serde_json::Value::Object(serde_json::Map::new())
+            ScriptPlugin::new(manifest, plugin_settings)
         };
-        let plugin = ScriptPlugin::new(manifest, plugin_settings);
+        plugin.apply_config(config);
         Ok(Arc::new(plugin))
     }
 
+    /// 加载原生插件，`entry` 为插件目录内的可执行文件或 PATH 中的命令
+    async fn load_native_plugin(
+        &self,
+        plugin_dir: &Path,
+        manifest: PluginManifest,
+    ) -> Result<Arc<dyn Plugin>, PluginError> {
+        let entry = Path::new(&manifest.entry);
+        let mut components = entry.components();
+        let program = match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None) if !plugin_dir.join(entry).exists() => {
+                entry.to_path_buf()
+            }
+            _ if entry
+                .components()
+                .all(|c| matches!(c, Component::Normal(_))) =>
+            {
+                plugin_dir.join(entry)
+            }
+            _ => {
+                return Err(PluginError::InvalidManifest(format!(
+                    "入口文件必须位于插件目录内: {}",
+                    manifest.entry
+                )))
+            }
+        };
+        let plugin_settings = Self::load_settings(plugin_dir).await?;
+        Ok(Arc::new(NativePlugin::new(
+            manifest,
+            plugin_settings,
+            program,
+            plugin_dir.to_path_buf(),
+        )))
+    }
+
+    /// 读取插件目录下的 config.json
+    async fn load_settings(plugin_dir: &Path) -> Result<serde_json::Value, PluginError> {
+        let config_path = plugin_dir.join("config.json");
+        if !config_path.exists() {
+            return Ok(serde_json::Value::Object(serde_json::Map::new()));
+        }
+        let content = fs::read_to_string(&config_path)
+            .await
+            .map_err(|e| PluginError::LoadError(format!("无法读取配置文件: {}", e)))?;
+        Ok(serde_json::from_str(&content).unwrap_or_default())
+    }
+
     pub async fn load_all(
         &self,
         configs: &HashMap<String, PluginConfig>,
@@ -121,14 +190,117 @@ impl PluginLoader {
     }
 }
 
+/// 入口文件是否为脚本（`.lua`）
+fn is_script_entry(entry: &str) -> bool {
+    Path::new(entry)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("lua"))
+}
+
 pub struct ScriptPlugin {
     manifest: PluginManifest,
     settings: serde_json::Value,
+    /// 嵌入式脚本（为空时使用 JSON 配置的注入转换）
+    script: Option<Script>,
+    /// 脚本宿主环境（插件级 KV 存储在各次调用间共享）
+    host: ScriptHost,
+    /// 持有常驻 Lua 状态的执行线程（首次调用钩子时启动）
+    worker: OnceLock<ScriptWorker>,
+    /// 单次钩子执行超时 (毫秒)
+    timeout_ms: u64,
 }
 
 impl ScriptPlugin {
     pub fn new(manifest: PluginManifest, settings: serde_json::Value) -> Self {
-        Self { manifest, settings }
+        let host = ScriptHost::new(manifest.name.clone(), settings.clone());
+        Self {
+            manifest,
+            settings,
+            script: None,
+            host,
+            worker: OnceLock::new(),
+            timeout_ms: PluginConfig::default().timeout_ms,
+        }
+    }
+
+    /// 创建由嵌入式脚本驱动的插件
+    pub fn with_script(
+        manifest: PluginManifest,
+        settings: serde_json::Value,
+        script: Script,
+    ) -> Self {
+        Self {
+            script: Some(script),
+            ..Self::new(manifest, settings)
+        }
+    }
+
+    /// 应用插件配置：超时时间和覆盖 config.json 的设置项
+    fn apply_config(&mut self, config: &PluginConfig) {
+        self.timeout_ms = config.timeout_ms;
+        if let (Some(base), Some(overrides)) =
+            (self.settings.as_object_mut(), config.settings.as_object())
+        {
+            for (key, value) in overrides {
+                base.insert(key.clone(), value.clone());
+            }
+            self.host = ScriptHost::new(self.manifest.name.clone(), self.settings.clone());
+            // 已启动的执行线程持有旧的宿主环境，丢弃后按新设置重新启动
+            self.worker = OnceLock::new();
+        }
+    }
+
+    /// 在插件的执行线程中运行脚本钩子
+    async fn run_script(
+        &self,
+        script: &Script,
+        hook: &'static str,
+        ctx: &mut PluginContext,
+        payload: &mut serde_json::Value,
+    ) -> Result<HookResult, PluginError> {
+        let start = std::time::Instant::now();
+        let execution_error = |message: String| PluginError::ExecutionError {
+            plugin_name: self.manifest.name.clone(),
+            message,
+        };
+        let worker = match self.worker.get() {
+            Some(worker) => worker,
+            None => {
+                let worker =
+                    ScriptWorker::spawn(&self.manifest.name, script.clone(), self.host.clone())
+                        .map_err(|e| execution_error(format!("无法启动脚本执行线程: {}", e)))?;
+                self.worker.get_or_init(|| worker)
+            }
+        };
+        let limits = ScriptLimits::with_timeout_ms(self.timeout_ms);
+
+        let (outcome, task_ctx, task_payload) = worker
+            .call_hook(hook, ctx.clone(), payload.clone(), limits)
+            .await
+            .map_err(|e| execution_error(e.to_string()))?;
+
+        let duration_ms = start.elapsed().as_millis() as u64;
+        match outcome {
+            Ok(ScriptOutcome::Continue { modified }) => {
+                *ctx = task_ctx;
+                if modified {
+                    *payload = task_payload;
+                }
+                Ok(HookResult::success(modified, duration_ms))
+            }
+            Ok(ScriptOutcome::Rejected {
+                status_code,
+                message,
+            }) => {
+                *ctx = task_ctx;
+                Ok(HookResult::rejected(status_code, message, duration_ms))
+            }
+            Err(ScriptError::Timeout { timeout_ms }) => Err(PluginError::Timeout {
+                plugin_name: self.manifest.name.clone(),
+                timeout_ms,
+            }),
+            Err(e) => Err(execution_error(e.to_string())),
+        }
     }
 
     fn apply_request_transforms(&self, request: &mut serde_json::Value) -> bool {
@@ -190,15 +362,19 @@ impl Plugin for ScriptPlugin {
         &self.manifest
     }
 
-    async fn init(&mut self, _config: &PluginConfig) -> Result<(), PluginError> {
+    async fn init(&mut self, config: &PluginConfig) -> Result<(), PluginError> {
+        self.apply_config(config);
         Ok(())
     }
 
     async fn on_request(
         &self,
-        _ctx: &mut PluginContext,
+        ctx: &mut PluginContext,
         request: &mut serde_json::Value,
     ) -> Result<HookResult, PluginError> {
+        if let Some(script) = &self.script {
+            return self.run_script(script, "on_request", ctx, request).await;
+        }
         let start = std::time::Instant::now();
         let modified = self.apply_request_transforms(request);
         Ok(HookResult::success(
@@ -209,9 +385,12 @@ impl Plugin for ScriptPlugin {
 
     async fn on_response(
         &self,
-        _ctx: &mut PluginContext,
+        ctx: &mut PluginContext,
         response: &mut serde_json::Value,
     ) -> Result<HookResult, PluginError> {
+        if let Some(script) = &self.script {
+            return self.run_script(script, "on_response", ctx, response).await;
+        }
         let start = std::time::Instant::now();
         let modified = self.apply_response_transforms(response);
         Ok(HookResult::success(
@@ -222,9 +401,13 @@ impl Plugin for ScriptPlugin {
 
     async fn on_error(
         &self,
-        _ctx: &mut PluginContext,
-        _error: &str,
+        ctx: &mut PluginContext,
+        error: &str,
     ) -> Result<HookResult, PluginError> {
+        if let Some(script) = &self.script {
+            let mut payload = serde_json::Value::String(error.to_string());
+            return self.run_script(script, "on_error", ctx, &mut payload).await;
+        }
         let start = std::time::Instant::now();
         Ok(HookResult::success(
             false,
@@ -232,6 +415,48 @@ impl Plugin for ScriptPlugin {
         ))
     }
 
+    async fn on_stream_event(
+        &self,
+        ctx: &mut PluginContext,
+        event: &mut StreamHookEvent,
+    ) -> Result<HookResult, PluginError> {
+        let Some(script) = self
+            .script
+            .as_ref()
+            .filter(|s| s.defines("on_stream_event"))
+        else {
+            return Ok(HookResult::success(false, 0));
+        };
+        let mut payload = serde_json::to_value(&*event)?;
+        let result = self
+            .run_script(script, "on_stream_event", ctx, &mut payload)
+            .await?;
+        if result.modified {
+            *event = serde_json::from_value(payload).map_err(|e| PluginError::ExecutionError {
+                plugin_name: self.manifest.name.clone(),
+                message: format!("流式事件格式无效: {}", e),
+            })?;
+        }
+        Ok(result)
+    }
+
+    async fn on_stream_end(
+        &self,
+        ctx: &mut PluginContext,
+        response: &LLMResponse,
+    ) -> Result<HookResult, PluginError> {
+        let Some(script) = self.script.as_ref().filter(|s| s.defines("on_stream_end")) else {
+            return Ok(HookResult::success(false, 0));
+        };
+        // 响应已发送给客户端，脚本对其的修改不会生效
+        let mut payload = serde_json::to_value(response)?;
+        let mut result = self
+            .run_script(script, "on_stream_end", ctx, &mut payload)
+            .await?;
+        result.modified = false;
+        Ok(result)
+    }
+
     async fn shutdown(&mut self) -> Result<(), PluginError> {
         Ok(())
     }
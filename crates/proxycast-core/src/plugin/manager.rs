@@ -14,7 +14,10 @@ use tokio::time::timeout;
 use super::loader::PluginLoader;
 use super::types::{
     HookResult, PluginConfig, PluginContext, PluginError, PluginInfo, PluginInstance, PluginStatus,
+    StreamHookEvent,
 };
+use crate::flow_monitor::LLMResponse;
+use crate::services::event_bus::{EventBus, SystemEvent};
 
 /// 插件管理器配置
 #[derive(Debug, Clone)]
@@ -47,6 +50,8 @@ pub struct PluginManager {
     configs: DashMap<String, PluginConfig>,
     /// 管理器配置
     config: PluginManagerConfig,
+    /// 事件总线（发布钩子执行失败）
+    event_bus: std::sync::RwLock<Option<Arc<EventBus>>>,
 }
 
 impl PluginManager {
@@ -57,9 +62,35 @@ impl PluginManager {
             plugins: DashMap::new(),
             configs: DashMap::new(),
             config,
+            event_bus: std::sync::RwLock::new(None),
         }
     }
 
+    /// 设置事件总线（传入 None 时停止发布）
+    pub fn set_event_bus(&self, event_bus: Option<Arc<EventBus>>) {
+        if let Ok(mut guard) = self.event_bus.write() {
+            *guard = event_bus;
+        }
+    }
+
+    /// 生成钩子失败结果，并发布插件错误事件
+    fn hook_failure(
+        &self,
+        plugin_name: &str,
+        hook: &str,
+        message: String,
+        timeout_ms: u64,
+    ) -> HookResult {
+        if let Some(bus) = self.event_bus.read().ok().and_then(|guard| guard.clone()) {
+            bus.publish(SystemEvent::PluginError {
+                plugin: plugin_name.to_string(),
+                hook: hook.to_string(),
+                message: message.clone(),
+            });
+        }
+        HookResult::failure(message, timeout_ms)
+    }
+
     /// 使用默认配置创建
     pub fn with_defaults() -> Self {
         Self::new(
@@ -87,7 +118,7 @@ impl PluginManager {
             let name = plugin.name().to_string();
             let config = configs.get(&name).cloned().unwrap_or_default();
 
-            let mut instance = PluginInstance::new(plugin.clone(), path, config.clone());
+            let mut instance = PluginInstance::new(plugin, path, config.clone());
 
             // 初始化插件
             if let Err(e) = Arc::get_mut(&mut instance.plugin)
@@ -132,8 +163,7 @@ impl PluginManager {
             return Err(PluginError::LoadError(format!("插件 {} 已加载", name)));
         }
 
-        let mut instance =
-            PluginInstance::new(plugin.clone(), plugin_dir.to_path_buf(), config.clone());
+        let mut instance = PluginInstance::new(plugin, plugin_dir.to_path_buf(), config.clone());
 
         // 初始化插件
         if let Err(e) = Arc::get_mut(&mut instance.plugin)
@@ -169,6 +199,16 @@ impl PluginManager {
         Ok(())
     }
 
+    /// 卸载所有插件（服务停止时调用，关闭原生插件进程）
+    pub async fn unload_all(&self) {
+        let names: Vec<String> = self.plugins.iter().map(|r| r.key().clone()).collect();
+        for name in names {
+            if let Err(e) = self.unload(&name).await {
+                tracing::warn!("插件 {} 卸载失败: {}", name, e);
+            }
+        }
+    }
+
     /// 启用插件
     pub async fn enable(&self, name: &str) -> Result<(), PluginError> {
         let instance = self
@@ -280,11 +320,15 @@ impl PluginManager {
                 Ok(Ok(result)) => result,
                 Ok(Err(e)) => {
                     tracing::warn!("插件 {} on_request 执行失败: {}", plugin_name, e);
-                    HookResult::failure(e.to_string(), timeout_ms)
+                    self.hook_failure(&plugin_name, "on_request", e.to_string(), timeout_ms)
                 }
                 Err(_) => {
                     tracing::warn!("插件 {} on_request 执行超时", plugin_name);
-                    HookResult::failure(format!("执行超时 ({}ms)", timeout_ms), timeout_ms)
+                    let error = PluginError::Timeout {
+                        plugin_name: plugin_name.clone(),
+                        timeout_ms,
+                    };
+                    self.hook_failure(&plugin_name, "on_request", error.to_string(), timeout_ms)
                 }
             };
 
@@ -296,7 +340,12 @@ impl PluginManager {
                     .record_execution(result.success, result.error.clone());
             }
 
+            // 请求被拒绝后不再执行后续插件
+            let rejected = result.rejection.is_some();
             results.push(result);
+            if rejected {
+                break;
+            }
         }
 
         results
@@ -334,11 +383,15 @@ impl PluginManager {
                 Ok(Ok(result)) => result,
                 Ok(Err(e)) => {
                     tracing::warn!("插件 {} on_response 执行失败: {}", plugin_name, e);
-                    HookResult::failure(e.to_string(), timeout_ms)
+                    self.hook_failure(&plugin_name, "on_response", e.to_string(), timeout_ms)
                 }
                 Err(_) => {
                     tracing::warn!("插件 {} on_response 执行超时", plugin_name);
-                    HookResult::failure(format!("执行超时 ({}ms)", timeout_ms), timeout_ms)
+                    let error = PluginError::Timeout {
+                        plugin_name: plugin_name.clone(),
+                        timeout_ms,
+                    };
+                    self.hook_failure(&plugin_name, "on_response", error.to_string(), timeout_ms)
                 }
             };
 
@@ -384,11 +437,15 @@ impl PluginManager {
                 Ok(Ok(result)) => result,
                 Ok(Err(e)) => {
                     tracing::warn!("插件 {} on_error 执行失败: {}", plugin_name, e);
-                    HookResult::failure(e.to_string(), timeout_ms)
+                    self.hook_failure(&plugin_name, "on_error", e.to_string(), timeout_ms)
                 }
                 Err(_) => {
                     tracing::warn!("插件 {} on_error 执行超时", plugin_name);
-                    HookResult::failure(format!("执行超时 ({}ms)", timeout_ms), timeout_ms)
+                    let error = PluginError::Timeout {
+                        plugin_name: plugin_name.clone(),
+                        timeout_ms,
+                    };
+                    self.hook_failure(&plugin_name, "on_error", error.to_string(), timeout_ms)
                 }
             };
 
@@ -406,6 +463,129 @@ impl PluginManager {
         results
     }
 
+    /// 执行流式事件钩子 (带隔离)
+    ///
+    /// 某个插件拒绝后不再执行后续插件，调用方应中止流。
+    pub async fn run_on_stream_event(
+        &self,
+        ctx: &mut PluginContext,
+        event: &mut StreamHookEvent,
+    ) -> Vec<HookResult> {
+        if !self.config.enabled {
+            return Vec::new();
+        }
+
+        let mut results = Vec::new();
+
+        for entry in self.plugins.iter() {
+            let instance = entry.value().read().await;
+            if !instance.is_enabled() {
+                continue;
+            }
+
+            let timeout_ms = instance.config.timeout_ms;
+            let plugin = instance.plugin.clone();
+            let plugin_name = plugin.name().to_string();
+
+            let result = match timeout(
+                Duration::from_millis(timeout_ms),
+                plugin.on_stream_event(ctx, event),
+            )
+            .await
+            {
+                Ok(Ok(result)) => result,
+                Ok(Err(e)) => {
+                    tracing::warn!("插件 {} on_stream_event 执行失败: {}", plugin_name, e);
+                    self.hook_failure(&plugin_name, "on_stream_event", e.to_string(), timeout_ms)
+                }
+                Err(_) => {
+                    tracing::warn!("插件 {} on_stream_event 执行超时", plugin_name);
+                    let error = PluginError::Timeout {
+                        plugin_name: plugin_name.clone(),
+                        timeout_ms,
+                    };
+                    self.hook_failure(
+                        &plugin_name,
+                        "on_stream_event",
+                        error.to_string(),
+                        timeout_ms,
+                    )
+                }
+            };
+
+            drop(instance);
+            if let Some(inst) = self.plugins.get(&plugin_name) {
+                let mut inst = inst.write().await;
+                inst.state
+                    .record_execution(result.success, result.error.clone());
+            }
+
+            let rejected = result.rejection.is_some();
+            results.push(result);
+            if rejected {
+                break;
+            }
+        }
+
+        results
+    }
+
+    /// 执行流结束钩子 (带隔离)
+    pub async fn run_on_stream_end(
+        &self,
+        ctx: &mut PluginContext,
+        response: &LLMResponse,
+    ) -> Vec<HookResult> {
+        if !self.config.enabled {
+            return Vec::new();
+        }
+
+        let mut results = Vec::new();
+
+        for entry in self.plugins.iter() {
+            let instance = entry.value().read().await;
+            if !instance.is_enabled() {
+                continue;
+            }
+
+            let timeout_ms = instance.config.timeout_ms;
+            let plugin = instance.plugin.clone();
+            let plugin_name = plugin.name().to_string();
+
+            let result = match timeout(
+                Duration::from_millis(timeout_ms),
+                plugin.on_stream_end(ctx, response),
+            )
+            .await
+            {
+                Ok(Ok(result)) => result,
+                Ok(Err(e)) => {
+                    tracing::warn!("插件 {} on_stream_end 执行失败: {}", plugin_name, e);
+                    self.hook_failure(&plugin_name, "on_stream_end", e.to_string(), timeout_ms)
+                }
+                Err(_) => {
+                    tracing::warn!("插件 {} on_stream_end 执行超时", plugin_name);
+                    let error = PluginError::Timeout {
+                        plugin_name: plugin_name.clone(),
+                        timeout_ms,
+                    };
+                    self.hook_failure(&plugin_name, "on_stream_end", error.to_string(), timeout_ms)
+                }
+            };
+
+            drop(instance);
+            if let Some(inst) = self.plugins.get(&plugin_name) {
+                let mut inst = inst.write().await;
+                inst.state
+                    .record_execution(result.success, result.error.clone());
+            }
+
+            results.push(result);
+        }
+
+        results
+    }
+
     /// 获取已加载插件数量
     pub fn count(&self) -> usize {
         self.plugins.len()
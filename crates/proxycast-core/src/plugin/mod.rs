@@ -3,18 +3,24 @@
 //! 提供插件扩展功能，支持：
 //! - 插件加载和初始化
 //! - 请求前/响应后钩子
+//! - 流式事件和流结束钩子
 //! - 插件隔离和错误处理
 //! - 插件配置管理
+//! - 嵌入式脚本运行时（沙箱化的 Lua 5.4）
+//! - 原生插件（独立进程，stdio JSON-RPC，崩溃自动重启）
 
 mod loader;
 mod manager;
+mod native;
+pub mod script;
 mod types;
 
-pub use loader::PluginLoader;
-pub use manager::PluginManager;
+pub use loader::{PluginLoader, ScriptPlugin};
+pub use manager::{PluginManager, PluginManagerConfig};
+pub use native::{NativePlugin, NATIVE_PROTOCOL_VERSION};
 pub use types::{
-    HookResult, Plugin, PluginConfig, PluginContext, PluginError, PluginInfo, PluginManifest,
-    PluginState, PluginStatus, PluginType,
+    HookRejection, HookResult, Plugin, PluginConfig, PluginContext, PluginError, PluginInfo,
+    PluginManifest, PluginState, PluginStatus, PluginType, StreamHookEvent,
 };
 
 #[cfg(test)]
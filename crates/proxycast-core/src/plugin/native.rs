@@ -0,0 +1,1080 @@
+//! 原生插件 - 独立进程，通过 stdio 上的 JSON-RPC 2.0 通信
+//!
+//! 消息使用与 LSP 相同的长度前缀分帧：`Content-Length: <字节数>\r\n\r\n<JSON>`。
+//!
+//! 宿主调用的方法：
+//! - `initialize` `{protocol_version, plugin, settings}`
+//! - `on_request` / `on_response` `{context, payload}`
+//! - `on_error` `{context, error}`
+//! - `on_stream_event` `{context, event}` / `on_stream_end` `{context, response}`
+//!   （仅在清单 `hooks` 中声明时调用）
+//! - `ping`（健康检查）
+//! - `shutdown`（通知，无需响应）
+//!
+//! 钩子返回 `null` 表示不做修改，或返回
+//! `{"payload": ..., "metadata": {...}, "reject": {"status_code": 403, "message": "..."}}`
+//! 中的任意字段（`on_stream_event` 用 `event` 代替 `payload`）。插件可发送 `log` 通知 `{level, message}` 输出日志。
+//!
+//! 插件进程崩溃或健康检查失败时按指数退避自动重启，期间钩子调用返回错误，
+//! 由插件管理器隔离，不影响主请求流程。
+
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::process::{Child, Command};
+use tokio::sync::oneshot;
+
+use super::types::{
+    HookResult, Plugin, PluginConfig, PluginContext, PluginError, PluginManifest, StreamHookEvent,
+};
+use crate::flow_monitor::LLMResponse;
+
+/// 原生插件协议版本
+pub const NATIVE_PROTOCOL_VERSION: u32 = 1;
+
+/// 单条消息最大字节数
+const MAX_FRAME_BYTES: usize = 64 * 1024 * 1024;
+/// 关闭时等待插件进程自行退出的时长
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+type BoxedReader = Box<dyn AsyncRead + Send + Unpin>;
+type BoxedWriter = Box<dyn AsyncWrite + Send + Unpin>;
+type PendingCalls = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, CallError>>>>>;
+
+// ============================================================================
+// 分帧
+// ============================================================================
+
+/// 读取一条 `Content-Length` 帧，流结束时返回 `None`
+async fn read_frame<R: AsyncBufRead + Unpin>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((key, value)) = header.split_once(':') {
+            if key.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let length = content_length.unwrap_or_default();
+    if length > MAX_FRAME_BYTES {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("消息过大: {} 字节", length),
+        ));
+    }
+    let mut body = vec![0u8; length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(body))
+}
+
+/// 写入一条 `Content-Length` 帧
+async fn write_frame<W: AsyncWrite + Unpin + ?Sized>(
+    writer: &mut W,
+    message: &Value,
+) -> io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    let header = format!("Content-Length: {}\r\n\r\n", body.len());
+    writer.write_all(header.as_bytes()).await?;
+    writer.write_all(&body).await?;
+    writer.flush().await
+}
+
+// ============================================================================
+// 连接
+// ============================================================================
+
+/// 调用错误
+#[derive(Debug)]
+enum CallError {
+    /// 等待响应超时
+    Timeout,
+    /// 连接已断开 (进程退出或管道错误)
+    Closed(String),
+    /// 插件返回了 JSON-RPC 错误
+    Remote(String),
+}
+
+/// 插件进程的传输通道
+struct Transport {
+    reader: BoxedReader,
+    writer: BoxedWriter,
+    child: Option<Child>,
+}
+
+/// 与插件进程的一条 JSON-RPC 连接
+struct Connection {
+    writer: tokio::sync::Mutex<BoxedWriter>,
+    pending: PendingCalls,
+    alive: Arc<AtomicBool>,
+    next_id: AtomicU64,
+    started_at: Instant,
+    child: Mutex<Option<Child>>,
+}
+
+impl Connection {
+    /// 建立连接并启动读取任务
+    fn start(plugin_name: &str, transport: Transport) -> Arc<Self> {
+        let pending: PendingCalls = Arc::new(Mutex::new(HashMap::new()));
+        let alive = Arc::new(AtomicBool::new(true));
+        tokio::spawn(read_loop(
+            plugin_name.to_string(),
+            BufReader::new(transport.reader),
+            pending.clone(),
+            alive.clone(),
+        ));
+        Arc::new(Self {
+            writer: tokio::sync::Mutex::new(transport.writer),
+            pending,
+            alive,
+            next_id: AtomicU64::new(1),
+            started_at: Instant::now(),
+            child: Mutex::new(transport.child),
+        })
+    }
+
+    fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::SeqCst)
+    }
+
+    /// 发起调用并等待响应
+    async fn call(
+        &self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, CallError> {
+        if !self.is_alive() {
+            return Err(CallError::Closed("插件进程已退出".to_string()));
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().insert(id, tx);
+        // 调用结束或被外层取消（如插件管理器的超时）时移除等待项
+        let _guard = PendingGuard {
+            pending: &self.pending,
+            id,
+        };
+        // 读取任务可能在插入前已退出并清空了等待队列
+        if !self.is_alive() {
+            return Err(CallError::Closed("插件进程已退出".to_string()));
+        }
+
+        let message = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        let exchange = async {
+            self.send(&message)
+                .await
+                .map_err(|e| CallError::Closed(e.to_string()))?;
+            rx.await
+                .unwrap_or_else(|_| Err(CallError::Closed("插件进程已退出".to_string())))
+        };
+        tokio::time::timeout(timeout, exchange)
+            .await
+            .unwrap_or(Err(CallError::Timeout))
+    }
+
+    /// 发送通知 (无需响应)
+    async fn notify(&self, method: &str, params: Value) -> io::Result<()> {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+            .await
+    }
+
+    async fn send(&self, message: &Value) -> io::Result<()> {
+        let mut writer = self.writer.lock().await;
+        let result = write_frame(&mut *writer, message).await;
+        if result.is_err() {
+            self.alive.store(false, Ordering::SeqCst);
+        }
+        result
+    }
+
+    /// 关闭插件进程的标准输入，使其读取循环结束
+    async fn close_input(&self) {
+        *self.writer.lock().await = Box::new(tokio::io::sink());
+    }
+
+    /// 终止插件进程
+    fn kill(&self) {
+        self.alive.store(false, Ordering::SeqCst);
+        if let Some(child) = self.child.lock().as_mut() {
+            let _ = child.start_kill();
+        }
+        fail_pending(&self.pending, "插件进程已终止");
+    }
+
+    /// 等待插件进程退出，超时返回 false
+    async fn wait_exit(&self, grace: Duration) -> bool {
+        let deadline = Instant::now() + grace;
+        loop {
+            let exited = match self.child.lock().as_mut() {
+                Some(child) => !matches!(child.try_wait(), Ok(None)),
+                None => true,
+            };
+            if exited {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+}
+
+/// 等待项守卫，离开作用域时从等待队列中移除对应的调用
+struct PendingGuard<'a> {
+    pending: &'a PendingCalls,
+    id: u64,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.pending.lock().remove(&self.id);
+    }
+}
+
+fn fail_pending(pending: &PendingCalls, reason: &str) {
+    let waiters: Vec<_> = pending.lock().drain().collect();
+    for (_, tx) in waiters {
+        let _ = tx.send(Err(CallError::Closed(reason.to_string())));
+    }
+}
+
+/// 读取插件输出，分发响应和通知
+async fn read_loop<R: AsyncBufRead + Unpin>(
+    plugin_name: String,
+    mut reader: R,
+    pending: PendingCalls,
+    alive: Arc<AtomicBool>,
+) {
+    loop {
+        match read_frame(&mut reader).await {
+            Ok(Some(body)) => match serde_json::from_slice::<Value>(&body) {
+                Ok(message) => handle_message(&plugin_name, message, &pending),
+                Err(e) => {
+                    tracing::warn!("[PLUGIN] {} 发送了无效的 JSON-RPC 消息: {}", plugin_name, e)
+                }
+            },
+            Ok(None) => break,
+            Err(e) => {
+                tracing::warn!("[PLUGIN] {} 输出读取失败: {}", plugin_name, e);
+                break;
+            }
+        }
+    }
+    alive.store(false, Ordering::SeqCst);
+    fail_pending(&pending, "插件进程已退出");
+}
+
+fn handle_message(plugin_name: &str, message: Value, pending: &PendingCalls) {
+    let method = message.get("method").and_then(Value::as_str);
+    if method.is_none() {
+        let Some(id) = message.get("id").and_then(Value::as_u64) else {
+            return;
+        };
+        let Some(tx) = pending.lock().remove(&id) else {
+            return;
+        };
+        let result = match message.get("error") {
+            Some(error) => Err(CallError::Remote(
+                match error.get("message").and_then(Value::as_str) {
+                    Some(text) => match error.get("code").and_then(Value::as_i64) {
+                        Some(code) => format!("{} (code {})", text, code),
+                        None => text.to_string(),
+                    },
+                    None => error.to_string(),
+                },
+            )),
+            None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+        };
+        let _ = tx.send(result);
+        return;
+    }
+
+    match method {
+        Some("log") => {
+            let params = message.get("params");
+            let level = params
+                .and_then(|p| p.get("level"))
+                .and_then(Value::as_str)
+                .unwrap_or("info");
+            let text = params
+                .and_then(|p| p.get("message"))
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            match level {
+                "debug" => tracing::debug!("[PLUGIN] {}: {}", plugin_name, text),
+                "warn" => tracing::warn!("[PLUGIN] {}: {}", plugin_name, text),
+                "error" => tracing::error!("[PLUGIN] {}: {}", plugin_name, text),
+                _ => tracing::info!("[PLUGIN] {}: {}", plugin_name, text),
+            }
+        }
+        Some(other) => {
+            tracing::debug!("[PLUGIN] {} 发送了不支持的方法: {}", plugin_name, other);
+        }
+        None => {}
+    }
+}
+
+// ============================================================================
+// 进程监管
+// ============================================================================
+
+/// 进程监管策略
+#[derive(Debug, Clone)]
+struct SupervisorPolicy {
+    /// 健康检查间隔
+    ping_interval: Duration,
+    /// 重启退避初始值
+    backoff_base: Duration,
+    /// 重启退避上限
+    backoff_max: Duration,
+    /// 进程稳定运行超过该时长后重置退避
+    stable_uptime: Duration,
+}
+
+impl Default for SupervisorPolicy {
+    fn default() -> Self {
+        Self {
+            ping_interval: Duration::from_secs(15),
+            backoff_base: Duration::from_millis(500),
+            backoff_max: Duration::from_secs(30),
+            stable_uptime: Duration::from_secs(60),
+        }
+    }
+}
+
+impl SupervisorPolicy {
+    /// 第 `failures` 次连续失败后的重启等待时间
+    fn backoff(&self, failures: u32) -> Duration {
+        let factor = 1u32 << failures.saturating_sub(1).min(16);
+        self.backoff_base
+            .saturating_mul(factor)
+            .min(self.backoff_max)
+    }
+}
+
+type Launcher = Box<dyn Fn() -> io::Result<Transport> + Send + Sync>;
+
+#[derive(Default)]
+struct SupervisorState {
+    conn: Option<Arc<Connection>>,
+    failures: u32,
+    retry_at: Option<Instant>,
+    last_error: Option<String>,
+}
+
+/// 插件进程监管器：按需启动、崩溃重启 (指数退避)、定期健康检查
+struct Supervisor {
+    plugin_name: String,
+    launcher: Launcher,
+    init_params: Value,
+    timeout: Duration,
+    policy: SupervisorPolicy,
+    state: tokio::sync::Mutex<SupervisorState>,
+    shutting_down: AtomicBool,
+}
+
+impl Supervisor {
+    fn new(
+        plugin_name: String,
+        launcher: Launcher,
+        init_params: Value,
+        timeout: Duration,
+        policy: SupervisorPolicy,
+    ) -> Arc<Self> {
+        let supervisor = Arc::new(Self {
+            plugin_name,
+            launcher,
+            init_params,
+            timeout,
+            policy,
+            state: tokio::sync::Mutex::new(SupervisorState::default()),
+            shutting_down: AtomicBool::new(false),
+        });
+        Self::spawn_health_check(&supervisor);
+        supervisor
+    }
+
+    fn spawn_health_check(supervisor: &Arc<Self>) {
+        let weak = Arc::downgrade(supervisor);
+        let interval = supervisor.policy.ping_interval;
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(supervisor) = weak.upgrade() else {
+                    break;
+                };
+                if supervisor.shutting_down.load(Ordering::SeqCst) {
+                    break;
+                }
+                supervisor.health_check().await;
+            }
+        });
+    }
+
+    /// 健康检查：进程已退出时按退避重启，无响应时终止进程
+    async fn health_check(&self) {
+        let Ok(conn) = self.connection().await else {
+            return;
+        };
+        if let Err(e) = conn.call("ping", Value::Null, self.timeout).await {
+            tracing::warn!(
+                "[PLUGIN] {} 健康检查失败，终止进程: {:?}",
+                self.plugin_name,
+                e
+            );
+            conn.kill();
+        }
+    }
+
+    /// 获取可用连接，必要时启动插件进程
+    async fn connection(&self) -> Result<Arc<Connection>, String> {
+        let mut state = self.state.lock().await;
+        if let Some(conn) = &state.conn {
+            if conn.is_alive() {
+                return Ok(conn.clone());
+            }
+        }
+        if let Some(conn) = state.conn.take() {
+            conn.kill();
+            self.record_failure(&mut state, conn.started_at.elapsed(), "插件进程已退出");
+        }
+        if self.shutting_down.load(Ordering::SeqCst) {
+            return Err("插件正在关闭".to_string());
+        }
+        if let Some(retry_at) = state.retry_at {
+            let now = Instant::now();
+            if now < retry_at {
+                return Err(format!(
+                    "插件进程不可用，{}ms 后重试: {}",
+                    (retry_at - now).as_millis(),
+                    state.last_error.as_deref().unwrap_or("未知错误")
+                ));
+            }
+        }
+
+        match self.launch().await {
+            Ok(conn) => {
+                state.conn = Some(conn.clone());
+                state.retry_at = None;
+                Ok(conn)
+            }
+            Err(e) => {
+                self.record_failure(&mut state, Duration::ZERO, &e);
+                Err(e)
+            }
+        }
+    }
+
+    async fn launch(&self) -> Result<Arc<Connection>, String> {
+        let transport = (self.launcher)().map_err(|e| format!("启动插件进程失败: {}", e))?;
+        let conn = Connection::start(&self.plugin_name, transport);
+        match conn
+            .call("initialize", self.init_params.clone(), self.timeout)
+            .await
+        {
+            Ok(_) => {
+                tracing::info!("[PLUGIN] {} 进程已启动", self.plugin_name);
+                Ok(conn)
+            }
+            Err(e) => {
+                conn.kill();
+                Err(format!("插件初始化失败: {:?}", e))
+            }
+        }
+    }
+
+    fn record_failure(&self, state: &mut SupervisorState, uptime: Duration, error: &str) {
+        if uptime >= self.policy.stable_uptime {
+            state.failures = 0;
+        }
+        state.failures += 1;
+        let delay = self.policy.backoff(state.failures);
+        state.retry_at = Some(Instant::now() + delay);
+        state.last_error = Some(error.to_string());
+        tracing::warn!(
+            "[PLUGIN] {} {}，{}ms 后重启 (连续失败 {} 次)",
+            self.plugin_name,
+            error,
+            delay.as_millis(),
+            state.failures
+        );
+    }
+
+    /// 通知插件退出，超时后强制终止
+    async fn shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        let conn = self.state.lock().await.conn.take();
+        if let Some(conn) = conn {
+            if conn.is_alive() && conn.notify("shutdown", Value::Null).await.is_ok() {
+                conn.close_input().await;
+                conn.wait_exit(SHUTDOWN_GRACE).await;
+            }
+            conn.kill();
+        }
+    }
+}
+
+// ============================================================================
+// 原生插件
+// ============================================================================
+
+/// 原生插件 - 由独立进程实现钩子
+pub struct NativePlugin {
+    manifest: PluginManifest,
+    settings: Value,
+    program: PathBuf,
+    work_dir: PathBuf,
+    timeout_ms: u64,
+    supervisor: Option<Arc<Supervisor>>,
+}
+
+impl NativePlugin {
+    /// 创建原生插件，进程在 `init` 时启动
+    pub fn new(
+        manifest: PluginManifest,
+        settings: Value,
+        program: PathBuf,
+        work_dir: PathBuf,
+    ) -> Self {
+        Self {
+            manifest,
+            settings,
+            program,
+            work_dir,
+            timeout_ms: PluginConfig::default().timeout_ms,
+            supervisor: None,
+        }
+    }
+
+    fn process_launcher(&self) -> Launcher {
+        let program = self.program.clone();
+        let args = self.manifest.args.clone();
+        let work_dir = self.work_dir.clone();
+        let plugin_name = self.manifest.name.clone();
+        Box::new(move || {
+            let mut child = Command::new(&program)
+                .args(&args)
+                .current_dir(&work_dir)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()?;
+            let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+                return Err(io::Error::other("无法获取插件进程的标准输入输出"));
+            };
+            if let Some(stderr) = child.stderr.take() {
+                let plugin_name = plugin_name.clone();
+                tokio::spawn(async move {
+                    let mut lines = BufReader::new(stderr).lines();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        tracing::info!("[PLUGIN] {} stderr: {}", plugin_name, line);
+                    }
+                });
+            }
+            Ok(Transport {
+                reader: Box::new(stdout),
+                writer: Box::new(stdin),
+                child: Some(child),
+            })
+        })
+    }
+
+    fn start_supervisor(&mut self, launcher: Launcher, policy: SupervisorPolicy) {
+        let init_params = json!({
+            "protocol_version": NATIVE_PROTOCOL_VERSION,
+            "plugin": self.manifest.name,
+            "settings": self.settings,
+        });
+        self.supervisor = Some(Supervisor::new(
+            self.manifest.name.clone(),
+            launcher,
+            init_params,
+            Duration::from_millis(self.timeout_ms),
+            policy,
+        ));
+    }
+
+    fn execution_error(&self, message: impl Into<String>) -> PluginError {
+        PluginError::ExecutionError {
+            plugin_name: self.manifest.name.clone(),
+            message: message.into(),
+        }
+    }
+
+    /// 调用插件钩子并应用返回结果
+    async fn invoke(
+        &self,
+        method: &str,
+        ctx: &mut PluginContext,
+        params: Value,
+        writable: Option<(&str, &mut Value)>,
+    ) -> Result<HookResult, PluginError> {
+        let start = Instant::now();
+        let supervisor = self
+            .supervisor
+            .as_ref()
+            .ok_or_else(|| self.execution_error("插件未初始化"))?;
+        let conn = supervisor
+            .connection()
+            .await
+            .map_err(|e| self.execution_error(e))?;
+
+        let result = conn
+            .call(method, params, Duration::from_millis(self.timeout_ms))
+            .await
+            .map_err(|e| match e {
+                CallError::Timeout => PluginError::Timeout {
+                    plugin_name: self.manifest.name.clone(),
+                    timeout_ms: self.timeout_ms,
+                },
+                CallError::Closed(message) | CallError::Remote(message) => {
+                    self.execution_error(message)
+                }
+            })?;
+        let duration_ms = start.elapsed().as_millis() as u64;
+
+        let reply = match result {
+            Value::Null => return Ok(HookResult::success(false, duration_ms)),
+            Value::Object(reply) => reply,
+            other => {
+                return Err(self.execution_error(format!("{} 返回了无效结果: {}", method, other)))
+            }
+        };
+
+        if let Some(Value::Object(metadata)) = reply.get("metadata") {
+            for (key, value) in metadata {
+                ctx.metadata.insert(key.clone(), value.clone());
+            }
+        }
+        if let Some(reject) = reply.get("reject").filter(|v| !v.is_null()) {
+            let status_code = reject
+                .get("status_code")
+                .and_then(Value::as_u64)
+                .and_then(|code| u16::try_from(code).ok())
+                .filter(|code| (400..=599).contains(code))
+                .unwrap_or(403);
+            let message = reject
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("请求被插件拒绝")
+                .to_string();
+            return Ok(HookResult::rejected(status_code, message, duration_ms));
+        }
+
+        let mut modified = false;
+        if let Some((key, target)) = writable {
+            if let Some(new_value) = reply.get(key).filter(|v| *v != target) {
+                *target = new_value.clone();
+                modified = true;
+            }
+        }
+        Ok(HookResult::success(modified, duration_ms))
+    }
+
+    /// 流式钩子调用频繁，仅在清单声明时调用
+    fn handles(&self, hook: &str) -> bool {
+        self.manifest.hooks.iter().any(|h| h == hook)
+    }
+
+    fn hook_params(ctx: &PluginContext, key: &str, value: Value) -> Value {
+        json!({
+            "context": ctx,
+            key: value,
+        })
+    }
+}
+
+#[async_trait]
+impl Plugin for NativePlugin {
+    fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    fn version(&self) -> &str {
+        &self.manifest.version
+    }
+
+    fn manifest(&self) -> &PluginManifest {
+        &self.manifest
+    }
+
+    async fn init(&mut self, config: &PluginConfig) -> Result<(), PluginError> {
+        self.timeout_ms = config.timeout_ms;
+        if let (Some(base), Some(overrides)) =
+            (self.settings.as_object_mut(), config.settings.as_object())
+        {
+            for (key, value) in overrides {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+        if let Some(old) = self.supervisor.take() {
+            old.shutdown().await;
+        }
+
+        let launcher = self.process_launcher();
+        self.start_supervisor(launcher, SupervisorPolicy::default());
+        // 首次启动失败不阻止加载，由监管器按退避重试
+        if let Some(supervisor) = &self.supervisor {
+            if let Err(e) = supervisor.connection().await {
+                tracing::warn!("[PLUGIN] {} 首次启动失败: {}", self.manifest.name, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn on_request(
+        &self,
+        ctx: &mut PluginContext,
+        request: &mut Value,
+    ) -> Result<HookResult, PluginError> {
+        let params = Self::hook_params(ctx, "payload", request.clone());
+        self.invoke("on_request", ctx, params, Some(("payload", request)))
+            .await
+    }
+
+    async fn on_response(
+        &self,
+        ctx: &mut PluginContext,
+        response: &mut Value,
+    ) -> Result<HookResult, PluginError> {
+        let params = Self::hook_params(ctx, "payload", response.clone());
+        self.invoke("on_response", ctx, params, Some(("payload", response)))
+            .await
+    }
+
+    async fn on_error(
+        &self,
+        ctx: &mut PluginContext,
+        error: &str,
+    ) -> Result<HookResult, PluginError> {
+        let params = Self::hook_params(ctx, "error", Value::String(error.to_string()));
+        self.invoke("on_error", ctx, params, None).await
+    }
+
+    async fn on_stream_event(
+        &self,
+        ctx: &mut PluginContext,
+        event: &mut StreamHookEvent,
+    ) -> Result<HookResult, PluginError> {
+        if !self.handles("on_stream_event") {
+            return Ok(HookResult::success(false, 0));
+        }
+        let mut value = serde_json::to_value(&*event)?;
+        let params = Self::hook_params(ctx, "event", value.clone());
+        let result = self
+            .invoke("on_stream_event", ctx, params, Some(("event", &mut value)))
+            .await?;
+        if result.modified {
+            *event = serde_json::from_value(value)
+                .map_err(|e| self.execution_error(format!("流式事件格式无效: {}", e)))?;
+        }
+        Ok(result)
+    }
+
+    async fn on_stream_end(
+        &self,
+        ctx: &mut PluginContext,
+        response: &LLMResponse,
+    ) -> Result<HookResult, PluginError> {
+        if !self.handles("on_stream_end") {
+            return Ok(HookResult::success(false, 0));
+        }
+        let params = Self::hook_params(ctx, "response", serde_json::to_value(response)?);
+        self.invoke("on_stream_end", ctx, params, None).await
+    }
+
+    async fn shutdown(&mut self) -> Result<(), PluginError> {
+        if let Some(supervisor) = self.supervisor.take() {
+            supervisor.shutdown().await;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::PluginType;
+    use crate::ProviderType;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::io::{duplex, DuplexStream};
+
+    fn manifest() -> PluginManifest {
+        PluginManifest {
+            name: "policy".to_string(),
+            version: "1.0.0".to_string(),
+            description: String::new(),
+            author: None,
+            homepage: None,
+            license: None,
+            entry: "policy".to_string(),
+            args: vec![],
+            plugin_type: PluginType::Native,
+            config_schema: None,
+            hooks: vec![],
+            min_proxycast_version: None,
+        }
+    }
+
+    fn ctx() -> PluginContext {
+        PluginContext::new(
+            "req-1".to_string(),
+            ProviderType::Kiro,
+            "claude-sonnet-4-5".to_string(),
+        )
+    }
+
+    fn test_policy() -> SupervisorPolicy {
+        SupervisorPolicy {
+            ping_interval: Duration::from_secs(3600),
+            backoff_base: Duration::from_millis(50),
+            backoff_max: Duration::from_millis(200),
+            stable_uptime: Duration::from_secs(3600),
+        }
+    }
+
+    /// 进程内模拟插件：按 `handler` 返回结果，返回 `None` 时不响应
+    async fn fake_plugin<F>(stream: DuplexStream, handler: F)
+    where
+        F: Fn(&str, &Value) -> Option<Value> + Send + 'static,
+    {
+        let (read, mut write) = tokio::io::split(stream);
+        let mut reader = BufReader::new(read);
+        while let Ok(Some(body)) = read_frame(&mut reader).await {
+            let request: Value = serde_json::from_slice(&body).unwrap();
+            let method = request["method"].as_str().unwrap_or_default().to_string();
+            if method == "crash" {
+                return;
+            }
+            if let Some(result) = handler(&method, &request["params"]) {
+                let response = json!({"jsonrpc": "2.0", "id": request["id"], "result": result});
+                if write_frame(&mut write, &response).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn fake_launcher<F>(launches: Arc<AtomicUsize>, handler: F) -> Launcher
+    where
+        F: Fn(&str, &Value) -> Option<Value> + Clone + Send + Sync + 'static,
+    {
+        Box::new(move || {
+            launches.fetch_add(1, Ordering::SeqCst);
+            let (host, plugin) = duplex(64 * 1024);
+            tokio::spawn(fake_plugin(plugin, handler.clone()));
+            let (reader, writer) = tokio::io::split(host);
+            Ok(Transport {
+                reader: Box::new(reader),
+                writer: Box::new(writer),
+                child: None,
+            })
+        })
+    }
+
+    fn plugin_with<F>(timeout_ms: u64, launches: Arc<AtomicUsize>, handler: F) -> NativePlugin
+    where
+        F: Fn(&str, &Value) -> Option<Value> + Clone + Send + Sync + 'static,
+    {
+        let mut plugin = NativePlugin::new(
+            manifest(),
+            json!({"limit": 1}),
+            PathBuf::from("policy"),
+            PathBuf::from("."),
+        );
+        plugin.timeout_ms = timeout_ms;
+        plugin.start_supervisor(fake_launcher(launches, handler), test_policy());
+        plugin
+    }
+
+    fn policy_handler(method: &str, params: &Value) -> Option<Value> {
+        match method {
+            "initialize" => Some(json!({"ok": params["settings"]["limit"] == 1})),
+            "on_request" if params["payload"]["prompt"] == "forbidden" => {
+                Some(json!({"reject": {"status_code": 451, "message": "blocked"}}))
+            }
+            "on_request" => {
+                let mut payload = params["payload"].clone();
+                payload["max_tokens"] = json!(256);
+                Some(json!({
+                    "payload": payload,
+                    "metadata": {"model_seen": params["context"]["model"]}
+                }))
+            }
+            _ => Some(Value::Null),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hooks_rewrite_and_reject() {
+        let launches = Arc::new(AtomicUsize::new(0));
+        let plugin = plugin_with(1000, launches.clone(), policy_handler);
+        let mut ctx = ctx();
+
+        let mut request = json!({"prompt": "hi", "max_tokens": 4096});
+        let result = plugin.on_request(&mut ctx, &mut request).await.unwrap();
+        assert!(result.modified);
+        assert_eq!(request["max_tokens"], 256);
+        assert_eq!(
+            ctx.get_metadata("model_seen"),
+            Some(&json!("claude-sonnet-4-5"))
+        );
+
+        let mut blocked = json!({"prompt": "forbidden"});
+        let result = plugin.on_request(&mut ctx, &mut blocked).await.unwrap();
+        let rejection = result.rejection.unwrap();
+        assert_eq!(rejection.status_code, 451);
+        assert_eq!(rejection.message, "blocked");
+
+        let mut response = json!({"ok": true});
+        let result = plugin.on_response(&mut ctx, &mut response).await.unwrap();
+        assert!(!result.modified);
+        assert!(plugin.on_error(&mut ctx, "boom").await.unwrap().success);
+        assert_eq!(launches.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_maps_to_plugin_timeout() {
+        let launches = Arc::new(AtomicUsize::new(0));
+        let plugin = plugin_with(100, launches, |method: &str, _: &Value| match method {
+            "on_request" => None,
+            _ => Some(Value::Null),
+        });
+        let mut request = json!({});
+        let err = plugin
+            .on_request(&mut ctx(), &mut request)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            PluginError::Timeout {
+                timeout_ms: 100,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_call_removes_pending_entry() {
+        let launches = Arc::new(AtomicUsize::new(0));
+        let plugin = plugin_with(1000, launches, |method: &str, _: &Value| match method {
+            "on_request" => None,
+            _ => Some(Value::Null),
+        });
+        let conn = plugin
+            .supervisor
+            .clone()
+            .unwrap()
+            .connection()
+            .await
+            .unwrap();
+
+        // 外层超时先于调用自身的超时触发
+        let call = conn.call("on_request", Value::Null, Duration::from_secs(5));
+        assert!(tokio::time::timeout(Duration::from_millis(50), call)
+            .await
+            .is_err());
+        assert!(conn.pending.lock().is_empty());
+
+        let err = conn
+            .call("on_request", Value::Null, Duration::from_millis(50))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CallError::Timeout));
+        assert!(conn.pending.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_crash_restarts_with_backoff() {
+        let launches = Arc::new(AtomicUsize::new(0));
+        let plugin = plugin_with(1000, launches.clone(), policy_handler);
+        let supervisor = plugin.supervisor.clone().unwrap();
+
+        // 崩溃：进行中的调用立即失败，而不是等到超时
+        let conn = supervisor.connection().await.unwrap();
+        let err = conn
+            .call("crash", Value::Null, Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CallError::Closed(_)));
+
+        // 退避期间调用直接失败
+        let mut request = json!({"prompt": "hi"});
+        let err = plugin
+            .on_request(&mut ctx(), &mut request)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, PluginError::ExecutionError { .. }));
+        assert_eq!(launches.load(Ordering::SeqCst), 1);
+
+        // 退避结束后自动重启
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        let result = plugin.on_request(&mut ctx(), &mut request).await.unwrap();
+        assert!(result.modified);
+        assert_eq!(launches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unresponsive_process_is_killed_by_health_check() {
+        let launches = Arc::new(AtomicUsize::new(0));
+        let plugin = plugin_with(50, launches.clone(), |method: &str, _: &Value| {
+            (method != "ping").then_some(Value::Null)
+        });
+        let supervisor = plugin.supervisor.clone().unwrap();
+        let conn = supervisor.connection().await.unwrap();
+        supervisor.health_check().await;
+        assert!(!conn.is_alive());
+    }
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let policy = SupervisorPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2), Duration::from_secs(1));
+        assert_eq!(policy.backoff(4), Duration::from_secs(4));
+        assert_eq!(policy.backoff(20), Duration::from_secs(30));
+    }
+
+    #[tokio::test]
+    async fn test_frame_round_trip() {
+        let (mut a, b) = duplex(1024);
+        let message = json!({"jsonrpc": "2.0", "id": 1, "result": {"text": "你好"}});
+        write_frame(&mut a, &message).await.unwrap();
+        drop(a);
+        let mut reader = BufReader::new(b);
+        let body = read_frame(&mut reader).await.unwrap().unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&body).unwrap(), message);
+        assert!(read_frame(&mut reader).await.unwrap().is_none());
+    }
+}
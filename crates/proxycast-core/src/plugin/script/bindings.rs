@@ -0,0 +1,254 @@
+//! 脚本宿主 API 绑定
+//!
+//! 在 Lua 标准库子集（`string`/`table`/`math`/`utf8`）之上安装宿主能力：
+//! `settings`、`json.*`、`log.*`、`kv.*`、`tokens.estimate`、`reject`，
+//! 以及 `string.trim/split/replace/starts_with/ends_with` 便捷函数。
+//! 不暴露文件、网络和进程访问。
+
+use super::ScriptHost;
+use mlua::{Error, Lua, LuaSerdeExt, Result, Table, Value, Variadic};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// 单个插件 KV 存储的最大键数量
+const MAX_KV_ENTRIES: usize = 10_000;
+
+/// 从基础库中移除的全局函数（代码加载与垃圾回收控制）
+const REMOVED_GLOBALS: &[&str] = &["dofile", "loadfile", "load", "collectgarbage"];
+
+/// 脚本调用 `reject()` 记录的拒绝信息
+#[derive(Debug, Clone)]
+pub(super) struct Rejection {
+    pub status_code: u16,
+    pub message: String,
+}
+
+/// 当前执行位置的错误（附带脚本行号，与 Lua `error()` 的格式一致）
+fn host_error(lua: &Lua, message: impl std::fmt::Display) -> Error {
+    match lua.inspect_stack(1).map(|debug| debug.curr_line()) {
+        Some(line) if line > 0 => {
+            Error::runtime(format!("{}:{}: {}", super::CHUNK_NAME, line, message))
+        }
+        _ => Error::runtime(message),
+    }
+}
+
+/// 按 `tostring` 规则拼接参数（用于 `print` 与 `log.*`）
+fn display(lua: &Lua, values: Variadic<Value>) -> Result<String> {
+    let tostring: mlua::Function = lua.globals().get("tostring")?;
+    let parts = values
+        .into_iter()
+        .map(|v| tostring.call::<_, String>(v))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(parts.join("\t"))
+}
+
+/// 将 Lua 值转换为 JSON
+pub(super) fn to_json(lua: &Lua, value: Value) -> Result<serde_json::Value> {
+    lua.from_value(value)
+}
+
+/// 整数结果以 Lua 整数返回，保证序列化为 JSON 整数
+fn number<'lua>(n: f64) -> Value<'lua> {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        Value::Integer(n as i64)
+    } else {
+        Value::Number(n)
+    }
+}
+
+/// 安装宿主 API
+pub(super) fn install(
+    lua: &Lua,
+    host: &ScriptHost,
+    rejection: Rc<RefCell<Option<Rejection>>>,
+) -> Result<()> {
+    let globals = lua.globals();
+    for name in REMOVED_GLOBALS {
+        globals.set(*name, Value::Nil)?;
+    }
+    globals.set("settings", lua.to_value(host.settings())?)?;
+
+    let log_host = host.clone();
+    globals.set(
+        "print",
+        lua.create_function(move |lua, args: Variadic<Value>| {
+            log_host.log("info", &display(lua, args)?);
+            Ok(())
+        })?,
+    )?;
+
+    globals.set(
+        "reject",
+        lua.create_function(move |lua, (status, message): (Value, Value)| {
+            let status_code = match status {
+                Value::Integer(n) if (100..=599).contains(&n) => n as u16,
+                Value::Number(n) if n.fract() == 0.0 && (100.0..=599.0).contains(&n) => n as u16,
+                other => {
+                    return Err(host_error(
+                        lua,
+                        format!(
+                            "无效的 HTTP 状态码: {}",
+                            display(lua, Variadic::from_iter([other]))?
+                        ),
+                    ))
+                }
+            };
+            let message = match message {
+                Value::Nil => "Request rejected by plugin".to_string(),
+                other => display(lua, Variadic::from_iter([other]))?,
+            };
+            *rejection.borrow_mut() = Some(Rejection {
+                status_code,
+                message,
+            });
+            Err::<(), _>(Error::runtime("request rejected by plugin"))
+        })?,
+    )?;
+
+    install_string_extensions(lua, &globals.get::<_, Table>("string")?)?;
+    globals.set("json", json_table(lua)?)?;
+    globals.set("log", log_table(lua, host)?)?;
+    globals.set("kv", kv_table(lua, host)?)?;
+    globals.set("tokens", tokens_table(lua, host)?)?;
+    Ok(())
+}
+
+/// `string` 库的便捷扩展（字符串方法调用同样可用，如 `s:trim()`）
+fn install_string_extensions(lua: &Lua, string: &Table) -> Result<()> {
+    string.set(
+        "trim",
+        lua.create_function(|_, s: String| Ok(s.trim().to_string()))?,
+    )?;
+    string.set(
+        "split",
+        lua.create_function(|lua, (s, sep): (String, String)| {
+            if sep.is_empty() {
+                lua.create_sequence_from(s.chars().map(|c| c.to_string()))
+            } else {
+                lua.create_sequence_from(s.split(sep.as_str()).map(str::to_string))
+            }
+        })?,
+    )?;
+    string.set(
+        "replace",
+        lua.create_function(|_, (s, from, to): (String, String, String)| {
+            Ok(if from.is_empty() {
+                s
+            } else {
+                s.replace(&from, &to)
+            })
+        })?,
+    )?;
+    string.set(
+        "starts_with",
+        lua.create_function(|_, (s, prefix): (String, String)| Ok(s.starts_with(&prefix)))?,
+    )?;
+    string.set(
+        "ends_with",
+        lua.create_function(|_, (s, suffix): (String, String)| Ok(s.ends_with(&suffix)))?,
+    )?;
+    Ok(())
+}
+
+/// `json.encode/decode` 与 `json.null`
+fn json_table<'lua>(lua: &'lua Lua) -> Result<Table<'lua>> {
+    let json = lua.create_table()?;
+    json.set("null", lua.null())?;
+    json.set(
+        "encode",
+        lua.create_function(|lua, value: Value| Ok(to_json(lua, value)?.to_string()))?,
+    )?;
+    json.set(
+        "decode",
+        lua.create_function(|lua, text: String| {
+            let value: serde_json::Value = serde_json::from_str(&text)
+                .map_err(|e| host_error(lua, format!("json.decode 失败: {}", e)))?;
+            lua.to_value(&value)
+        })?,
+    )?;
+    Ok(json)
+}
+
+/// `log.debug/info/warn/error`
+fn log_table<'lua>(lua: &'lua Lua, host: &ScriptHost) -> Result<Table<'lua>> {
+    let log = lua.create_table()?;
+    for level in ["debug", "info", "warn", "error"] {
+        let host = host.clone();
+        log.set(
+            level,
+            lua.create_function(move |lua, args: Variadic<Value>| {
+                host.log(level, &display(lua, args)?);
+                Ok(())
+            })?,
+        )?;
+    }
+    Ok(log)
+}
+
+/// `kv.get/set/delete/incr`（插件级共享存储）
+fn kv_table<'lua>(lua: &'lua Lua, host: &ScriptHost) -> Result<Table<'lua>> {
+    let kv = lua.create_table()?;
+
+    let get_host = host.clone();
+    kv.set(
+        "get",
+        lua.create_function(move |lua, key: String| match get_host.kv_get(&key) {
+            Some(value) => lua.to_value(&value),
+            None => Ok(Value::Nil),
+        })?,
+    )?;
+
+    let set_host = host.clone();
+    kv.set(
+        "set",
+        lua.create_function(move |lua, (key, value): (String, Value)| {
+            if value.is_nil() {
+                set_host.kv_delete(&key);
+                return Ok(());
+            }
+            let value = to_json(lua, value)?;
+            if !set_host.kv_set(&key, value, MAX_KV_ENTRIES) {
+                return Err(host_error(lua, "KV 存储键数量超过限制"));
+            }
+            Ok(())
+        })?,
+    )?;
+
+    let delete_host = host.clone();
+    kv.set(
+        "delete",
+        lua.create_function(move |_, key: String| Ok(delete_host.kv_delete(&key)))?,
+    )?;
+
+    let incr_host = host.clone();
+    kv.set(
+        "incr",
+        lua.create_function(move |lua, (key, delta): (String, Option<f64>)| {
+            match incr_host.kv_incr(&key, delta.unwrap_or(1.0), MAX_KV_ENTRIES) {
+                Some(n) => Ok(number(n)),
+                None => Err(host_error(lua, "kv.incr 的目标不是数字或键数量超过限制")),
+            }
+        })?,
+    )?;
+
+    Ok(kv)
+}
+
+/// `tokens.estimate(text_or_table, model?)`
+fn tokens_table<'lua>(lua: &'lua Lua, host: &ScriptHost) -> Result<Table<'lua>> {
+    let tokens = lua.create_table()?;
+    let host = host.clone();
+    tokens.set(
+        "estimate",
+        lua.create_function(move |lua, (value, model): (Value, Option<String>)| {
+            let text = match value {
+                Value::String(s) => s.to_str()?.to_string(),
+                Value::Nil => String::new(),
+                other => to_json(lua, other)?.to_string(),
+            };
+            Ok(host.estimate_tokens(&text, model.as_deref()))
+        })?,
+    )?;
+    Ok(tokens)
+}
@@ -0,0 +1,866 @@
+//! 嵌入式脚本运行时
+//!
+//! 脚本插件运行在嵌入的 Lua 5.4（mlua，随构建编译）中：
+//! - 沙箱：只加载 `string`/`table`/`math`/`utf8` 标准库，移除 `load`/`dofile` 等代码加载函数，
+//!   不提供 `io`、`os`、`require`、`debug`，无文件、网络和进程访问
+//! - 值与 JSON 对应：表 ↔ 对象/数组，`json.null` ↔ null，数组下标从 1 开始
+//! - 钩子：脚本定义全局函数 `on_request(ctx, request)`、`on_response(ctx, response)`、
+//!   `on_error(ctx, error)`，可直接修改参数或返回新的表替换负载
+//! - 宿主 API 见 [`bindings`]：`log.*`、`kv.*`（插件级共享存储）、`tokens.estimate`、`reject(status, message)`
+//! - 限制：执行时间受 `PluginConfig::timeout_ms` 约束（按指令计数钩子检查），内存占用有上限
+//!
+//! 每个脚本插件在专用线程（[`ScriptWorker`]）中持有一个常驻 Lua 状态，顶层代码只在创建状态时执行一次，
+//! 全局变量在调用之间保留；执行出错后状态被丢弃并在下次调用时重建。跨状态持久的数据应使用 `kv`。
+
+mod bindings;
+
+use crate::telemetry::TokenEstimator;
+use bindings::Rejection;
+use dashmap::DashMap;
+use mlua::{HookTriggers, Lua, LuaOptions, LuaSerdeExt, StdLib, Value};
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::sync::{mpsc, Arc, OnceLock};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::oneshot;
+
+use super::types::PluginContext;
+
+/// 错误消息中的脚本名（`script:行号: 消息`）
+const CHUNK_NAME: &str = "script";
+
+/// 超时检查间隔（虚拟机指令数）
+const HOOK_INSTRUCTIONS: u32 = 1000;
+
+/// 宿主会调用的钩子函数名
+const HOOK_NAMES: &[&str] = &[
+    "on_request",
+    "on_response",
+    "on_error",
+    "on_stream_event",
+    "on_stream_end",
+];
+
+/// 脚本错误
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ScriptError {
+    #[error("脚本语法错误 (第 {line} 行): {message}")]
+    Syntax { line: u32, message: String },
+
+    #[error("脚本运行错误 (第 {line} 行): {message}")]
+    Runtime { line: u32, message: String },
+
+    #[error("脚本执行超时: 超过 {timeout_ms}ms")]
+    Timeout { timeout_ms: u64 },
+
+    #[error("脚本执行线程已退出")]
+    WorkerStopped,
+}
+
+/// 脚本执行限制
+#[derive(Debug, Clone)]
+pub struct ScriptLimits {
+    /// 单次钩子调用的最长执行时间
+    pub timeout: Duration,
+    /// Lua 状态的最大内存占用（字节）
+    pub max_memory: usize,
+}
+
+impl ScriptLimits {
+    /// 使用插件超时配置创建限制
+    pub fn with_timeout_ms(timeout_ms: u64) -> Self {
+        Self {
+            timeout: Duration::from_millis(timeout_ms),
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_millis(5000),
+            max_memory: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// 钩子执行结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptOutcome {
+    /// 继续处理
+    Continue {
+        /// 负载是否被修改
+        modified: bool,
+    },
+    /// 脚本调用 `reject()` 拒绝请求
+    Rejected { status_code: u16, message: String },
+}
+
+/// 脚本宿主环境
+///
+/// 提供插件名称（用于日志）、插件配置和插件级 KV 存储。
+/// 克隆后共享同一个 KV 存储。
+#[derive(Debug, Clone)]
+pub struct ScriptHost {
+    plugin_name: String,
+    settings: serde_json::Value,
+    kv: Arc<DashMap<String, serde_json::Value>>,
+}
+
+impl ScriptHost {
+    /// 创建宿主环境
+    pub fn new(plugin_name: impl Into<String>, settings: serde_json::Value) -> Self {
+        Self {
+            plugin_name: plugin_name.into(),
+            settings,
+            kv: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// 插件配置（脚本中的全局 `settings`）
+    pub fn settings(&self) -> &serde_json::Value {
+        &self.settings
+    }
+
+    /// 读取 KV 存储
+    pub fn kv_get(&self, key: &str) -> Option<serde_json::Value> {
+        self.kv.get(key).map(|v| v.value().clone())
+    }
+
+    /// 写入 KV 存储，超过键数量上限时返回 `false`
+    fn kv_set(&self, key: &str, value: serde_json::Value, max_entries: usize) -> bool {
+        if self.kv.len() >= max_entries && !self.kv.contains_key(key) {
+            return false;
+        }
+        self.kv.insert(key.to_string(), value);
+        true
+    }
+
+    fn kv_delete(&self, key: &str) -> bool {
+        self.kv.remove(key).is_some()
+    }
+
+    /// 原子递增数值，目标不是数字时返回 `None`
+    fn kv_incr(&self, key: &str, delta: f64, max_entries: usize) -> Option<f64> {
+        if self.kv.len() >= max_entries && !self.kv.contains_key(key) {
+            return None;
+        }
+        let mut entry = self
+            .kv
+            .entry(key.to_string())
+            .or_insert(serde_json::Value::from(0));
+        let current = entry.value().as_f64()?;
+        let next = current + delta;
+        *entry.value_mut() = if next.fract() == 0.0 {
+            serde_json::Value::from(next as i64)
+        } else {
+            serde_json::Value::from(next)
+        };
+        Some(next)
+    }
+
+    fn log(&self, level: &str, message: &str) {
+        match level {
+            "debug" => tracing::debug!("[PLUGIN:{}] {}", self.plugin_name, message),
+            "warn" => tracing::warn!("[PLUGIN:{}] {}", self.plugin_name, message),
+            "error" => tracing::error!("[PLUGIN:{}] {}", self.plugin_name, message),
+            _ => tracing::info!("[PLUGIN:{}] {}", self.plugin_name, message),
+        }
+    }
+
+    fn estimate_tokens(&self, text: &str, model: Option<&str>) -> u32 {
+        match token_estimator() {
+            Some(estimator) => estimator.estimate(text, model),
+            None => text.chars().count().div_ceil(4) as u32,
+        }
+    }
+}
+
+/// 共享的 Token 估算器（初始化失败时退化为按字符估算）
+fn token_estimator() -> Option<&'static TokenEstimator> {
+    static ESTIMATOR: OnceLock<Option<TokenEstimator>> = OnceLock::new();
+    ESTIMATOR
+        .get_or_init(|| TokenEstimator::new().ok())
+        .as_ref()
+}
+
+/// 预先初始化 Token 估算器，避免首次调用占用脚本执行时间
+pub fn warm_up_token_estimator() {
+    let _ = token_estimator();
+}
+
+/// 已编译的脚本
+#[derive(Debug, Clone)]
+pub struct Script {
+    source: Arc<str>,
+    /// 顶层代码执行后定义的钩子（首次成功执行前未知）
+    hooks: Arc<OnceLock<HashSet<&'static str>>>,
+}
+
+impl Script {
+    /// 编译脚本源码（只检查语法，顶层代码在首次钩子调用时执行）
+    pub fn compile(source: &str) -> Result<Self, ScriptError> {
+        let lua = Lua::new_with(StdLib::NONE, LuaOptions::default()).map_err(script_error)?;
+        lua.load(source)
+            .set_name(format!("={}", CHUNK_NAME))
+            .into_function()
+            .map_err(script_error)?;
+        Ok(Self {
+            source: source.into(),
+            hooks: Arc::new(OnceLock::new()),
+        })
+    }
+
+    /// 脚本是否定义了全局函数 `name`
+    ///
+    /// 用于在高频钩子（如流式事件）上跳过未实现该钩子的脚本。
+    /// 顶层代码首次成功执行前无法确定，此时返回 `true`。
+    pub fn defines(&self, name: &str) -> bool {
+        self.hooks.get().is_none_or(|hooks| hooks.contains(name))
+    }
+}
+
+/// 沙箱化的 Lua 状态及其每次调用需要重置的宿主状态
+struct Sandbox {
+    lua: Lua,
+    rejection: Rc<RefCell<Option<Rejection>>>,
+    timed_out: Rc<Cell<bool>>,
+    deadline: Rc<Cell<Instant>>,
+}
+
+impl Sandbox {
+    /// 创建 Lua 状态并安装宿主 API
+    fn new(host: &ScriptHost) -> mlua::Result<Self> {
+        let libs = StdLib::STRING | StdLib::TABLE | StdLib::MATH | StdLib::UTF8;
+        let lua = Lua::new_with(libs, LuaOptions::default())?;
+        let sandbox = Self {
+            lua,
+            rejection: Rc::new(RefCell::new(None)),
+            timed_out: Rc::new(Cell::new(false)),
+            deadline: Rc::new(Cell::new(Instant::now())),
+        };
+
+        let deadline = sandbox.deadline.clone();
+        let timed_out = sandbox.timed_out.clone();
+        sandbox.lua.set_hook(
+            HookTriggers::new().every_nth_instruction(HOOK_INSTRUCTIONS),
+            move |_, _| {
+                if Instant::now() >= deadline.get() {
+                    timed_out.set(true);
+                    return Err(mlua::Error::runtime("script timeout"));
+                }
+                Ok(())
+            },
+        );
+        bindings::install(&sandbox.lua, host, sandbox.rejection.clone())?;
+        Ok(sandbox)
+    }
+
+    /// 执行脚本顶层代码，记录脚本定义的钩子
+    fn load(&self, script: &Script) -> mlua::Result<()> {
+        self.lua
+            .load(&*script.source)
+            .set_name(format!("={}", CHUNK_NAME))
+            .exec()?;
+        let globals = self.lua.globals();
+        let _ = script.hooks.get_or_init(|| {
+            HOOK_NAMES
+                .iter()
+                .copied()
+                .filter(|name| matches!(globals.raw_get(*name), Ok(Value::Function(_))))
+                .collect()
+        });
+        Ok(())
+    }
+
+    /// 调用钩子函数，返回负载是否被修改
+    fn run(
+        &self,
+        hook: &str,
+        ctx: &mut PluginContext,
+        payload: &mut serde_json::Value,
+    ) -> mlua::Result<bool> {
+        let lua = &self.lua;
+        let func = match lua.globals().get::<_, Value>(hook)? {
+            Value::Function(func) => func,
+            _ => return Ok(false),
+        };
+
+        let ctx_json = serde_json::json!({
+            "request_id": ctx.request_id,
+            "provider": ctx.provider.to_string(),
+            "model": ctx.model,
+            "metadata": ctx.metadata,
+            "timestamp": ctx.timestamp.to_rfc3339(),
+        });
+        let ctx_value = lua.to_value(&ctx_json)?;
+        let payload_value = lua.to_value(payload)?;
+
+        let returned: Value = func.call((ctx_value.clone(), payload_value.clone()))?;
+        let result = match returned {
+            Value::Table(_) => bindings::to_json(lua, returned)?,
+            _ => bindings::to_json(lua, payload_value)?,
+        };
+
+        if let Value::Table(ctx_table) = ctx_value {
+            let metadata: Value = ctx_table.get("metadata")?;
+            if let serde_json::Value::Object(map) = bindings::to_json(lua, metadata)? {
+                ctx.metadata = map.into_iter().collect();
+            }
+        }
+
+        let modified = result != *payload;
+        if modified {
+            *payload = result;
+        }
+        Ok(modified)
+    }
+}
+
+/// 脚本的常驻 Lua 状态
+///
+/// 首次调用时创建沙箱并执行顶层代码，之后的调用复用同一状态，全局变量在调用之间保留。
+/// 执行出错（超时、内存超限、运行错误）后丢弃状态，下次调用时重新创建，
+/// 避免执行到一半的全局修改影响后续调用。Lua 状态不能跨线程移动，
+/// 由 [`ScriptWorker`] 在专用线程中持有。
+pub struct ScriptState {
+    script: Script,
+    host: ScriptHost,
+    sandbox: Option<Sandbox>,
+}
+
+impl ScriptState {
+    /// 创建脚本状态（沙箱在首次调用时创建）
+    pub fn new(script: Script, host: ScriptHost) -> Self {
+        Self {
+            script,
+            host,
+            sandbox: None,
+        }
+    }
+
+    /// 调用钩子函数
+    ///
+    /// 脚本未定义该钩子时返回 `Continue { modified: false }`。
+    /// 钩子返回表时用其替换负载，否则以钩子对参数的原地修改为准；
+    /// `ctx.metadata` 的修改会写回 [`PluginContext`]。
+    pub fn call_hook(
+        &mut self,
+        hook: &str,
+        ctx: &mut PluginContext,
+        payload: &mut serde_json::Value,
+        limits: &ScriptLimits,
+    ) -> Result<ScriptOutcome, ScriptError> {
+        let fresh = self.sandbox.is_none();
+        let sandbox = match &mut self.sandbox {
+            Some(sandbox) => sandbox,
+            slot => slot.insert(Sandbox::new(&self.host).map_err(script_error)?),
+        };
+
+        sandbox.deadline.set(Instant::now() + limits.timeout);
+        let result = sandbox
+            .lua
+            .set_memory_limit(limits.max_memory)
+            .and_then(|_| {
+                if fresh {
+                    sandbox.load(&self.script)
+                } else {
+                    Ok(())
+                }
+            })
+            .and_then(|()| sandbox.run(hook, ctx, payload));
+        let rejection = sandbox.rejection.take();
+        let timed_out = sandbox.timed_out.replace(false);
+
+        // reject() 以错误中断执行，即使脚本用 pcall 捕获也以拒绝为准
+        if let Some(Rejection {
+            status_code,
+            message,
+        }) = rejection
+        {
+            return Ok(ScriptOutcome::Rejected {
+                status_code,
+                message,
+            });
+        }
+        match result {
+            Ok(modified) => Ok(ScriptOutcome::Continue { modified }),
+            Err(e) => {
+                self.sandbox = None;
+                if timed_out {
+                    Err(ScriptError::Timeout {
+                        timeout_ms: limits.timeout.as_millis() as u64,
+                    })
+                } else {
+                    Err(script_error(e))
+                }
+            }
+        }
+    }
+}
+
+/// 钩子调用任务
+struct Job {
+    hook: &'static str,
+    ctx: PluginContext,
+    payload: serde_json::Value,
+    limits: ScriptLimits,
+    reply: oneshot::Sender<JobResult>,
+}
+
+type JobResult = (
+    Result<ScriptOutcome, ScriptError>,
+    PluginContext,
+    serde_json::Value,
+);
+
+/// 持有 [`ScriptState`] 的专用执行线程
+///
+/// 每个脚本插件一个线程，钩子调用按顺序在同一个 Lua 状态上执行。
+/// 所有句柄被丢弃后线程退出。
+#[derive(Debug, Clone)]
+pub struct ScriptWorker {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl ScriptWorker {
+    /// 启动执行线程
+    pub fn spawn(name: &str, script: Script, host: ScriptHost) -> std::io::Result<Self> {
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        std::thread::Builder::new()
+            .name(format!("plugin-{}", name))
+            .spawn(move || {
+                let mut state = ScriptState::new(script, host);
+                while let Ok(mut job) = receiver.recv() {
+                    // 调用方已超时放弃时不再执行
+                    if job.reply.is_closed() {
+                        continue;
+                    }
+                    let outcome =
+                        state.call_hook(job.hook, &mut job.ctx, &mut job.payload, &job.limits);
+                    let _ = job.reply.send((outcome, job.ctx, job.payload));
+                }
+            })?;
+        Ok(Self { jobs })
+    }
+
+    /// 在执行线程中调用钩子，返回执行结果及（可能被修改的）上下文和负载
+    pub async fn call_hook(
+        &self,
+        hook: &'static str,
+        ctx: PluginContext,
+        payload: serde_json::Value,
+        limits: ScriptLimits,
+    ) -> Result<JobResult, ScriptError> {
+        let (reply, result) = oneshot::channel();
+        self.jobs
+            .send(Job {
+                hook,
+                ctx,
+                payload,
+                limits,
+                reply,
+            })
+            .map_err(|_| ScriptError::WorkerStopped)?;
+        result.await.map_err(|_| ScriptError::WorkerStopped)
+    }
+}
+
+/// 转换 Lua 错误，从 `script:行号: 消息` 中提取行号
+fn script_error(err: mlua::Error) -> ScriptError {
+    let mut err = &err;
+    while let mlua::Error::CallbackError { cause, .. } = err {
+        err = cause.as_ref();
+    }
+    let (is_syntax, message) = match err {
+        mlua::Error::SyntaxError { message, .. } => (true, message.clone()),
+        mlua::Error::RuntimeError(message) => (false, message.clone()),
+        other => (false, other.to_string()),
+    };
+    let first_line = message.lines().next().unwrap_or_default();
+    let (line, message) = match split_location(first_line) {
+        Some((line, rest)) => (line, rest.to_string()),
+        None => (0, first_line.to_string()),
+    };
+    if is_syntax {
+        ScriptError::Syntax { line, message }
+    } else {
+        ScriptError::Runtime { line, message }
+    }
+}
+
+/// 拆分 `script:行号: 消息`
+fn split_location(message: &str) -> Option<(u32, &str)> {
+    let rest = message.strip_prefix(CHUNK_NAME)?.strip_prefix(':')?;
+    let (line, rest) = rest.split_once(':')?;
+    Some((line.parse().ok()?, rest.trim_start()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProviderType;
+    use serde_json::json;
+
+    fn ctx() -> PluginContext {
+        PluginContext::new(
+            "req-1".to_string(),
+            ProviderType::Kiro,
+            "claude-sonnet-4-5".to_string(),
+        )
+    }
+
+    fn run(
+        source: &str,
+        hook: &str,
+        payload: &mut serde_json::Value,
+    ) -> Result<ScriptOutcome, ScriptError> {
+        let host = ScriptHost::new("test", json!({"prefix": "[bot] "}));
+        ScriptState::new(Script::compile(source)?, host).call_hook(
+            hook,
+            &mut ctx(),
+            payload,
+            &ScriptLimits::default(),
+        )
+    }
+
+    #[test]
+    fn test_rewrite_payload_in_place() {
+        let source = r#"
+            function on_request(ctx, req)
+              req.max_tokens = math.min(req.max_tokens or 4096, 1024)
+              for i, msg in ipairs(req.messages) do
+                if msg.role == "user" then
+                  msg.content = settings.prefix .. msg.content:trim()
+                end
+              end
+              table.insert(req.messages, { role = "system", content = "be brief" })
+            end
+        "#;
+        let mut payload = json!({
+            "model": "m",
+            "max_tokens": 8192,
+            "temperature": null,
+            "messages": [{"role": "user", "content": "  hi  "}]
+        });
+        let outcome = run(source, "on_request", &mut payload).unwrap();
+        assert_eq!(outcome, ScriptOutcome::Continue { modified: true });
+        assert_eq!(
+            payload,
+            json!({
+                "model": "m",
+                "max_tokens": 1024,
+                "temperature": null,
+                "messages": [
+                    {"role": "user", "content": "[bot] hi"},
+                    {"role": "system", "content": "be brief"}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_returned_table_replaces_payload() {
+        let source = r#"
+            function on_response(ctx, resp)
+              return { wrapped = resp, tags = {} }
+            end
+        "#;
+        let mut payload = json!({"ok": true, "items": []});
+        run(source, "on_response", &mut payload).unwrap();
+        assert_eq!(
+            payload,
+            json!({"wrapped": {"ok": true, "items": []}, "tags": {}})
+        );
+    }
+
+    #[test]
+    fn test_missing_hook_is_noop() {
+        let mut payload = json!({"a": 1});
+        let outcome = run("local x = 1", "on_request", &mut payload).unwrap();
+        assert_eq!(outcome, ScriptOutcome::Continue { modified: false });
+
+        let script = Script::compile("function on_stream_event(ctx, e) end").unwrap();
+        assert!(script.defines("on_stream_end"));
+        let host = ScriptHost::new("test", json!({}));
+        ScriptState::new(script.clone(), host)
+            .call_hook(
+                "on_stream_event",
+                &mut ctx(),
+                &mut json!({}),
+                &ScriptLimits::default(),
+            )
+            .unwrap();
+        assert!(script.defines("on_stream_event"));
+        assert!(!script.defines("on_stream_end"));
+    }
+
+    #[test]
+    fn test_reject_request() {
+        let source = r#"
+            function on_request(ctx, req)
+              if string.find(req.prompt, "forbidden") then
+                reject(403, "blocked for model " .. ctx.model)
+              end
+            end
+        "#;
+        let mut payload = json!({"prompt": "a forbidden word"});
+        let outcome = run(source, "on_request", &mut payload).unwrap();
+        assert_eq!(
+            outcome,
+            ScriptOutcome::Rejected {
+                status_code: 403,
+                message: "blocked for model claude-sonnet-4-5".to_string()
+            }
+        );
+
+        // pcall 捕获 reject() 的错误后仍以拒绝为准
+        let outcome = run(
+            "function on_request() pcall(reject, 429) end",
+            "on_request",
+            &mut json!({}),
+        )
+        .unwrap();
+        assert!(matches!(
+            outcome,
+            ScriptOutcome::Rejected {
+                status_code: 429,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_metadata_write_back() {
+        let source = r#"
+            function on_request(ctx, req)
+              ctx.metadata.tenant = "acme"
+              ctx.metadata.estimated = tokens.estimate("hello world") > 0
+            end
+        "#;
+        let host = ScriptHost::new("test", json!({}));
+        let mut context = ctx();
+        let mut payload = json!({});
+        ScriptState::new(Script::compile(source).unwrap(), host)
+            .call_hook(
+                "on_request",
+                &mut context,
+                &mut payload,
+                &ScriptLimits::default(),
+            )
+            .unwrap();
+        assert_eq!(context.get_metadata("tenant"), Some(&json!("acme")));
+        assert_eq!(context.get_metadata("estimated"), Some(&json!(true)));
+    }
+
+    #[test]
+    fn test_kv_store_persists_between_calls() {
+        let source = r#"
+            function on_request(ctx, req)
+              req.count = kv.incr("requests")
+              kv.set("last_model", ctx.model)
+            end
+        "#;
+        let script = Script::compile(source).unwrap();
+        let host = ScriptHost::new("test", json!({}));
+        for expected in 1..=3 {
+            // 每次使用新的 Lua 状态，KV 存储仍然共享
+            let mut payload = json!({});
+            ScriptState::new(script.clone(), host.clone())
+                .call_hook(
+                    "on_request",
+                    &mut ctx(),
+                    &mut payload,
+                    &ScriptLimits::default(),
+                )
+                .unwrap();
+            assert_eq!(payload["count"], json!(expected));
+        }
+        assert_eq!(host.kv_get("last_model"), Some(json!("claude-sonnet-4-5")));
+    }
+
+    #[test]
+    fn test_state_is_reused_between_calls() {
+        let source = r#"
+            loads = (loads or 0) + 1
+            local calls = 0
+            function on_request(ctx, req)
+              calls = calls + 1
+              req.loads = loads
+              req.calls = calls
+              if req.fail then error("boom") end
+            end
+        "#;
+        let host = ScriptHost::new("test", json!({}));
+        let mut state = ScriptState::new(Script::compile(source).unwrap(), host);
+        let mut call = |payload: serde_json::Value| {
+            let mut payload = payload;
+            let outcome = state.call_hook(
+                "on_request",
+                &mut ctx(),
+                &mut payload,
+                &ScriptLimits::default(),
+            );
+            (outcome, payload)
+        };
+
+        for expected in 1..=3 {
+            let (_, payload) = call(json!({}));
+            assert_eq!(payload["loads"], json!(1));
+            assert_eq!(payload["calls"], json!(expected));
+        }
+
+        // 出错后丢弃状态，下次调用重新执行顶层代码
+        let (outcome, _) = call(json!({"fail": true}));
+        assert!(matches!(outcome, Err(ScriptError::Runtime { .. })));
+        let (_, payload) = call(json!({}));
+        assert_eq!(payload["loads"], json!(1));
+        assert_eq!(payload["calls"], json!(1));
+    }
+
+    #[tokio::test]
+    async fn test_worker_runs_hooks_on_one_state() {
+        let source = r#"
+            local calls = 0
+            function on_request(ctx, req)
+              calls = calls + 1
+              ctx.metadata.calls = calls
+              if req.block then reject(451, "blocked") end
+            end
+        "#;
+        let worker = ScriptWorker::spawn(
+            "test",
+            Script::compile(source).unwrap(),
+            ScriptHost::new("test", json!({})),
+        )
+        .unwrap();
+
+        let (outcome, context, _) = worker
+            .call_hook("on_request", ctx(), json!({}), ScriptLimits::default())
+            .await
+            .unwrap();
+        assert_eq!(outcome, Ok(ScriptOutcome::Continue { modified: false }));
+        assert_eq!(context.get_metadata("calls"), Some(&json!(1)));
+
+        let (outcome, _, _) = worker
+            .call_hook(
+                "on_request",
+                ctx(),
+                json!({"block": true}),
+                ScriptLimits::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome,
+            Ok(ScriptOutcome::Rejected {
+                status_code: 451,
+                message: "blocked".to_string()
+            })
+        );
+
+        // 拒绝不会丢弃状态
+        let (_, context, _) = worker
+            .call_hook("on_request", ctx(), json!({}), ScriptLimits::default())
+            .await
+            .unwrap();
+        assert_eq!(context.get_metadata("calls"), Some(&json!(3)));
+    }
+
+    #[test]
+    fn test_closures_and_recursion() {
+        let source = r#"
+            local function counter()
+              local n = 0
+              return function() n = n + 1; return n end
+            end
+            local function fib(n)
+              if n < 2 then return n end
+              return fib(n - 1) + fib(n - 2)
+            end
+            function on_request(ctx, req)
+              local c = counter()
+              c(); c()
+              req.calls = c()
+              req.fib = fib(15)
+              req.joined = table.concat(string.split("a,b,c", ","), "-")
+              req.power = -2 ^ 2
+            end
+        "#;
+        let mut payload = json!({});
+        run(source, "on_request", &mut payload).unwrap();
+        assert_eq!(payload["calls"], json!(3));
+        assert_eq!(payload["fib"], json!(610));
+        assert_eq!(payload["joined"], json!("a-b-c"));
+        // Lua 5.4 中 `^` 总是产生浮点数
+        assert_eq!(payload["power"], json!(-4.0));
+    }
+
+    #[test]
+    fn test_timeout_is_enforced() {
+        let host = ScriptHost::new("test", json!({}));
+        let script = Script::compile("function on_request() while true do end end").unwrap();
+        let started = std::time::Instant::now();
+        let err = ScriptState::new(script, host)
+            .call_hook(
+                "on_request",
+                &mut ctx(),
+                &mut json!({}),
+                &ScriptLimits::with_timeout_ms(50),
+            )
+            .unwrap_err();
+        assert_eq!(err, ScriptError::Timeout { timeout_ms: 50 });
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_resource_limits() {
+        let deep = run(
+            "local function f() return 1 + f() end function on_request() f() end",
+            "on_request",
+            &mut json!({}),
+        );
+        assert!(matches!(deep, Err(ScriptError::Runtime { .. })));
+
+        let cyclic = run(
+            "function on_request(ctx, req) req.self = req end",
+            "on_request",
+            &mut json!({}),
+        );
+        assert!(matches!(cyclic, Err(ScriptError::Runtime { .. })));
+
+        let huge = run(
+            "function on_request() local s = 'x' while true do s = s .. s end end",
+            "on_request",
+            &mut json!({}),
+        );
+        assert!(matches!(huge, Err(ScriptError::Runtime { .. })));
+    }
+
+    #[test]
+    fn test_errors_report_line_numbers() {
+        let err = Script::compile("local x = \n  (1 +").unwrap_err();
+        assert!(matches!(err, ScriptError::Syntax { line: 2, .. }));
+
+        let err = run(
+            "function on_request(ctx, req)\n  return req.missing.field\nend",
+            "on_request",
+            &mut json!({}),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ScriptError::Runtime { line: 2, .. }));
+    }
+
+    #[test]
+    fn test_no_host_escape() {
+        for global in ["io", "os", "require", "load", "dofile"] {
+            let source = format!("function on_request(ctx, req) req.t = type({}) end", global);
+            let mut payload = json!({});
+            run(&source, "on_request", &mut payload).unwrap();
+            assert_eq!(payload["t"], json!("nil"));
+        }
+    }
+}
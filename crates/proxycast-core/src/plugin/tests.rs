@@ -14,6 +14,7 @@ fn test_plugin_manifest_validation() {
         homepage: None,
         license: Some("MIT".to_string()),
         entry: "config.json".to_string(),
+        args: vec![],
         plugin_type: PluginType::Script,
         config_schema: None,
         hooks: vec!["on_request".to_string()],
@@ -145,6 +146,7 @@ fn test_plugin_manifest_serde() {
         homepage: None,
         license: None,
         entry: "config.json".to_string(),
+        args: vec![],
         plugin_type: PluginType::Script,
         config_schema: Some(serde_json::json!({
             "type": "object",
@@ -167,6 +169,144 @@ fn test_plugin_manifest_serde() {
     assert_eq!(parsed.hooks.len(), 2);
 }
 
+/// 在临时目录中写入脚本插件
+fn write_script_plugin(root: &std::path::Path, name: &str, entry: &str, source: &str) {
+    let dir = root.join(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    let manifest = serde_json::json!({
+        "name": name,
+        "version": "1.0.0",
+        "entry": entry,
+        "hooks": ["on_request", "on_response"]
+    });
+    std::fs::write(dir.join("manifest.json"), manifest.to_string()).unwrap();
+    std::fs::write(dir.join(entry.trim_start_matches("../")), source).unwrap();
+}
+
+#[tokio::test]
+async fn test_script_plugin_rewrites_and_rejects() {
+    use crate::plugin::manager::{PluginManager, PluginManagerConfig};
+
+    let temp = tempfile::tempdir().unwrap();
+    write_script_plugin(
+        temp.path(),
+        "guard",
+        "main.lua",
+        r#"
+            function on_request(ctx, req)
+              if req.blocked then
+                reject(429, "quota for " .. ctx.model)
+              end
+              req.max_tokens = 256
+              ctx.metadata.guarded = true
+            end
+        "#,
+    );
+
+    let manager = PluginManager::new(temp.path().to_path_buf(), PluginManagerConfig::default());
+    let loaded = manager.load_all().await.unwrap();
+    assert_eq!(loaded, vec!["guard".to_string()]);
+
+    let mut ctx = PluginContext::new(
+        "req-1".to_string(),
+        ProviderType::Kiro,
+        "claude-sonnet-4-5".to_string(),
+    );
+    let mut request = serde_json::json!({"max_tokens": 4096});
+    let results = manager.run_on_request(&mut ctx, &mut request).await;
+    assert!(results[0].success && results[0].modified);
+    assert_eq!(request["max_tokens"], 256);
+    assert_eq!(ctx.get_metadata("guarded"), Some(&serde_json::json!(true)));
+
+    let mut blocked = serde_json::json!({"blocked": true});
+    let results = manager.run_on_request(&mut ctx, &mut blocked).await;
+    assert_eq!(
+        results[0].rejection,
+        Some(HookRejection {
+            status_code: 429,
+            message: "quota for claude-sonnet-4-5".to_string()
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_script_plugin_load_errors() {
+    let temp = tempfile::tempdir().unwrap();
+    let loader = PluginLoader::new(temp.path().to_path_buf());
+    let config = PluginConfig::default();
+
+    write_script_plugin(temp.path(), "broken", "main.lua", "function on_request(");
+    let err = loader
+        .load(&temp.path().join("broken"), &config)
+        .await
+        .err();
+    assert!(matches!(err, Some(PluginError::LoadError(_))));
+
+    write_script_plugin(temp.path(), "escape", "../escape.lua", "");
+    let err = loader
+        .load(&temp.path().join("escape"), &config)
+        .await
+        .err();
+    assert!(matches!(err, Some(PluginError::InvalidManifest(_))));
+}
+
+/// 用 sh 实现的最小原生插件：拒绝所有请求
+#[cfg(unix)]
+const SH_NATIVE_PLUGIN: &str = r#"
+while IFS= read -r header; do
+  len=$(printf '%s' "$header" | tr -d '\r' | sed 's/Content-Length: //')
+  IFS= read -r blank
+  body=$(dd bs=1 count="$len" 2>/dev/null)
+  id=$(printf '%s' "$body" | sed 's/.*"id":\([0-9]*\).*/\1/')
+  result='null'
+  case "$body" in
+    *'"method":"on_request"'*) result='{"reject":{"status_code":403,"message":"denied"}}' ;;
+  esac
+  reply="{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":$result}"
+  printf 'Content-Length: %s\r\n\r\n%s' "${#reply}" "$reply"
+done
+"#;
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_native_plugin_process() {
+    use crate::plugin::manager::{PluginManager, PluginManagerConfig};
+
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("deny");
+    std::fs::create_dir_all(&dir).unwrap();
+    let manifest = serde_json::json!({
+        "name": "deny",
+        "version": "1.0.0",
+        "plugin_type": "native",
+        "entry": "sh",
+        "args": ["plugin.sh"],
+        "hooks": ["on_request"]
+    });
+    std::fs::write(dir.join("manifest.json"), manifest.to_string()).unwrap();
+    std::fs::write(dir.join("plugin.sh"), SH_NATIVE_PLUGIN).unwrap();
+
+    let manager = PluginManager::new(temp.path().to_path_buf(), PluginManagerConfig::default());
+    assert_eq!(manager.load_all().await.unwrap(), vec!["deny".to_string()]);
+
+    let mut ctx = PluginContext::new(
+        "req-1".to_string(),
+        ProviderType::Kiro,
+        "claude-sonnet-4-5".to_string(),
+    );
+    let mut request = serde_json::json!({"messages": []});
+    let results = manager.run_on_request(&mut ctx, &mut request).await;
+    assert_eq!(
+        results[0].rejection,
+        Some(HookRejection {
+            status_code: 403,
+            message: "denied".to_string()
+        })
+    );
+
+    manager.unload("deny").await.unwrap();
+}
+
 // Property-based tests
 use proptest::prelude::*;
 
@@ -11,6 +11,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use thiserror::Error;
 
+use crate::flow_monitor::LLMResponse;
 use crate::ProviderType;
 
 /// 插件错误类型
@@ -101,6 +102,9 @@ pub struct PluginManifest {
     /// 入口文件 (相对于插件目录)
     #[serde(default = "default_entry")]
     pub entry: String,
+    /// 启动参数 (仅原生插件，entry 为可执行文件)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
     /// 插件类型
     #[serde(default)]
     pub plugin_type: PluginType,
@@ -136,11 +140,11 @@ impl PluginManifest {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum PluginType {
-    /// 脚本插件 (JSON 配置驱动)
+    /// 脚本插件 (JSON 配置或嵌入式 Lua 脚本)
     #[default]
     #[serde(alias = "lua")]
     Script,
-    /// 原生 Rust 插件 (预留)
+    /// 原生插件 (独立进程，通过 stdio 上的 JSON-RPC 通信)
     Native,
 }
 
@@ -200,6 +204,53 @@ pub struct HookResult {
     pub error: Option<String>,
     /// 执行时间 (毫秒)
     pub duration_ms: u64,
+    /// 插件拒绝请求时的状态码和原因
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rejection: Option<HookRejection>,
+}
+
+/// 插件拒绝请求
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HookRejection {
+    /// 返回给客户端的 HTTP 状态码
+    pub status_code: u16,
+    /// 拒绝原因
+    pub message: String,
+}
+
+/// 标准化的流式事件 - 传递给 `on_stream_event` 钩子
+///
+/// 与具体的 SSE 格式 (Anthropic / OpenAI) 无关，插件修改后会写回原事件。
+/// `index` 为内容块（或工具调用）索引，只读。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamHookEvent {
+    /// 文本增量
+    TextDelta { index: u32, text: String },
+    /// 思维链增量
+    ThinkingDelta { index: u32, thinking: String },
+    /// 工具调用增量 (`id`/`name` 仅在工具调用开始时出现)
+    ToolCallDelta {
+        index: u32,
+        #[serde(default)]
+        id: Option<String>,
+        #[serde(default)]
+        name: Option<String>,
+        #[serde(default)]
+        arguments: String,
+    },
+    /// Token 用量
+    Usage {
+        #[serde(default)]
+        input_tokens: Option<u64>,
+        #[serde(default)]
+        output_tokens: Option<u64>,
+    },
+    /// 停止
+    Stop {
+        #[serde(default)]
+        reason: Option<String>,
+    },
 }
 
 impl HookResult {
@@ -210,6 +261,7 @@ impl HookResult {
             modified,
             error: None,
             duration_ms,
+            rejection: None,
         }
     }
 
@@ -220,12 +272,27 @@ impl HookResult {
             modified: false,
             error: Some(error),
             duration_ms,
+            rejection: None,
+        }
+    }
+
+    /// 创建拒绝请求结果
+    pub fn rejected(status_code: u16, message: String, duration_ms: u64) -> Self {
+        Self {
+            success: true,
+            modified: false,
+            error: None,
+            duration_ms,
+            rejection: Some(HookRejection {
+                status_code,
+                message,
+            }),
         }
     }
 }
 
 /// 插件配置
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginConfig {
     /// 插件特定配置
     #[serde(default)]
@@ -246,6 +313,16 @@ fn default_timeout() -> u64 {
     5000 // 5 秒
 }
 
+impl Default for PluginConfig {
+    fn default() -> Self {
+        Self {
+            settings: serde_json::Value::Null,
+            enabled: default_enabled(),
+            timeout_ms: default_timeout(),
+        }
+    }
+}
+
 impl PluginConfig {
     /// 创建默认配置
     pub fn new() -> Self {
@@ -376,6 +453,24 @@ pub trait Plugin: Send + Sync {
         error: &str,
     ) -> Result<HookResult, PluginError>;
 
+    /// 流式事件钩子 - 每个标准化流式事件调用一次 (默认不处理)
+    async fn on_stream_event(
+        &self,
+        _ctx: &mut PluginContext,
+        _event: &mut StreamHookEvent,
+    ) -> Result<HookResult, PluginError> {
+        Ok(HookResult::success(false, 0))
+    }
+
+    /// 流结束钩子 - 参数为由流重建器组装的完整响应 (默认不处理)
+    async fn on_stream_end(
+        &self,
+        _ctx: &mut PluginContext,
+        _response: &LLMResponse,
+    ) -> Result<HookResult, PluginError> {
+        Ok(HookResult::success(false, 0))
+    }
+
     /// 关闭插件
     async fn shutdown(&mut self) -> Result<(), PluginError>;
 }
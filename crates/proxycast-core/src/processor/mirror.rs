@@ -0,0 +1,144 @@
+//! 影子流量镜像
+//!
+//! 按模型模式和采样率挑选需要镜像的请求。请求的异步复制、影子 Flow 的记录
+//! 由服务器处理器负责，这里只负责规则匹配和采样决策。
+
+use crate::config::{MirrorConfig, MirrorRule};
+use crate::router::matches_model_pattern;
+use parking_lot::RwLock;
+use rand::Rng;
+
+/// 影子流量镜像器
+pub struct ShadowMirror {
+    config: RwLock<MirrorConfig>,
+}
+
+impl ShadowMirror {
+    /// 创建新的镜像器
+    pub fn new(config: MirrorConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+        }
+    }
+
+    /// 获取当前配置
+    pub fn config(&self) -> MirrorConfig {
+        self.config.read().clone()
+    }
+
+    /// 更新配置
+    pub fn set_config(&self, config: MirrorConfig) {
+        *self.config.write() = config;
+    }
+
+    /// 是否启用镜像
+    pub fn is_enabled(&self) -> bool {
+        let config = self.config.read();
+        config.enabled && config.rules.iter().any(|r| r.enabled)
+    }
+
+    /// 为模型选择镜像规则（按采样率随机决定）
+    ///
+    /// 返回 `None` 表示不镜像：未启用、没有匹配规则或未被采样
+    pub fn select(&self, model: &str) -> Option<MirrorRule> {
+        self.select_with_sample(model, rand::thread_rng().gen::<f64>())
+    }
+
+    /// 使用给定的采样值（`[0, 1)`）选择镜像规则
+    ///
+    /// 命中第一条启用且模式匹配的规则后，仅当 `sample < sample_rate` 时镜像
+    pub fn select_with_sample(&self, model: &str, sample: f64) -> Option<MirrorRule> {
+        let config = self.config.read();
+        if !config.enabled {
+            return None;
+        }
+        let rule = config
+            .rules
+            .iter()
+            .find(|r| r.enabled && matches_model_pattern(&r.model_pattern, model))?;
+        (sample < rule.sample_rate.clamp(0.0, 1.0)).then(|| rule.clone())
+    }
+}
+
+impl Default for ShadowMirror {
+    fn default() -> Self {
+        Self::new(MirrorConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, pattern: &str, sample_rate: f64) -> MirrorRule {
+        MirrorRule {
+            name: name.to_string(),
+            model_pattern: pattern.to_string(),
+            sample_rate,
+            target_provider: "antigravity".to_string(),
+            target_model: None,
+            enabled: true,
+        }
+    }
+
+    fn mirror(rules: Vec<MirrorRule>) -> ShadowMirror {
+        ShadowMirror::new(MirrorConfig {
+            enabled: true,
+            rules,
+        })
+    }
+
+    #[test]
+    fn test_disabled_mirror_never_selects() {
+        let mirror = ShadowMirror::new(MirrorConfig {
+            enabled: false,
+            rules: vec![rule("all", "*", 1.0)],
+        });
+        assert!(!mirror.is_enabled());
+        assert!(mirror
+            .select_with_sample("claude-sonnet-4-5", 0.0)
+            .is_none());
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let mut disabled = rule("disabled", "claude-*", 1.0);
+        disabled.enabled = false;
+        let mirror = mirror(vec![
+            disabled,
+            rule("sonnet", "claude-sonnet-*", 1.0),
+            rule("claude", "claude-*", 1.0),
+        ]);
+
+        let selected = mirror.select_with_sample("claude-sonnet-4-5", 0.5).unwrap();
+        assert_eq!(selected.name, "sonnet");
+        let selected = mirror.select_with_sample("claude-opus-4-5", 0.5).unwrap();
+        assert_eq!(selected.name, "claude");
+        assert!(mirror.select_with_sample("gpt-4o", 0.5).is_none());
+    }
+
+    #[test]
+    fn test_sample_rate_applies_to_matched_rule() {
+        let mirror = mirror(vec![
+            rule("sampled", "claude-*", 0.25),
+            rule("all", "*", 1.0),
+        ]);
+
+        assert!(mirror
+            .select_with_sample("claude-sonnet-4-5", 0.1)
+            .is_some());
+        // 未被采样时不会回退到后续规则
+        assert!(mirror
+            .select_with_sample("claude-sonnet-4-5", 0.3)
+            .is_none());
+        assert!(mirror.select_with_sample("gpt-4o", 0.99).is_some());
+    }
+
+    #[test]
+    fn test_zero_sample_rate_never_mirrors() {
+        let mirror = mirror(vec![rule("off", "*", 0.0)]);
+        assert!(mirror
+            .select_with_sample("claude-sonnet-4-5", 0.0)
+            .is_none());
+    }
+}
@@ -12,16 +12,24 @@
 //! 5. Provider 调用 (ProviderStep) - 包含重试和故障转移
 //! 6. 插件后置钩子 (PluginPostStep)
 //! 7. 统计记录 (TelemetryStep)
+//!
+//! 优先级排队 (FairQueue) 不是管道步骤：它在 Provider 调用层
+//! （`call_provider_openai` / `call_provider_anthropic`）中、实际请求上游之前获取槽位，
+//! 上游饱和时按类别加权公平放行，槽位随响应体一起释放。
 
 mod context;
 mod error;
+mod mirror;
+mod queue;
 mod steps;
 
 pub use context::RequestContext;
 pub use error::ProcessError;
+pub use mirror::ShadowMirror;
+pub use queue::{FairQueue, QueueClassMetrics, QueueError, QueueMetrics, QueuePermit};
 pub use steps::{
     AuthStep, InjectionStep, PipelineStep, PluginPostStep, PluginPreStep, ProviderStep,
-    RoutingStep, TelemetryStep,
+    RoutingStep, StepError, TelemetryStep,
 };
 
 use crate::injection::Injector;
@@ -0,0 +1,644 @@
+//! 请求优先级队列
+//!
+//! 在调用上游 Provider 之前按优先级类别排队。凭证池饱和时，
+//! 使用加权公平队列（WFQ）在各类别之间分配并发槽位，避免后台 Agent
+//! 请求挤占交互式补全请求。
+
+use crate::config::RequestQueueConfig;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// 排队错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueueError {
+    /// 超过最大排队等待时间
+    Timeout {
+        /// 优先级类别
+        class: String,
+        /// 已等待时间（毫秒）
+        waited_ms: u64,
+        /// 建议的重试等待秒数（用于 Retry-After 头）
+        retry_after_secs: u64,
+    },
+}
+
+impl std::fmt::Display for QueueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueueError::Timeout {
+                class, waited_ms, ..
+            } => write!(f, "请求排队超时: class={} waited={}ms", class, waited_ms),
+        }
+    }
+}
+
+impl std::error::Error for QueueError {}
+
+/// 实现 IntoResponse 以便在 axum 处理器中直接返回 503 响应
+impl axum::response::IntoResponse for QueueError {
+    fn into_response(self) -> axum::response::Response {
+        use axum::http::{header, StatusCode};
+        use axum::Json;
+
+        let QueueError::Timeout {
+            class,
+            waited_ms,
+            retry_after_secs,
+        } = &self;
+        let json_body = serde_json::json!({
+            "error": {
+                "message": self.to_string(),
+                "type": "queue_timeout",
+                "code": 503,
+                "priority_class": class,
+                "waited_ms": waited_ms,
+                "retry_after_seconds": retry_after_secs
+            }
+        });
+
+        let mut response = (StatusCode::SERVICE_UNAVAILABLE, Json(json_body)).into_response();
+        if let Ok(value) = retry_after_secs.to_string().parse() {
+            response.headers_mut().insert(header::RETRY_AFTER, value);
+        }
+        response
+    }
+}
+
+/// 队列许可
+///
+/// 持有期间占用一个上游并发槽位，drop 时归还并唤醒下一个排队请求
+pub struct QueuePermit {
+    queue: Option<Arc<FairQueue>>,
+    class: String,
+    waited: Duration,
+}
+
+impl QueuePermit {
+    /// 许可所属的优先级类别
+    pub fn class(&self) -> &str {
+        &self.class
+    }
+
+    /// 获得许可前的排队时间
+    pub fn waited(&self) -> Duration {
+        self.waited
+    }
+}
+
+impl std::fmt::Debug for QueuePermit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QueuePermit")
+            .field("class", &self.class)
+            .field("waited", &self.waited)
+            .finish()
+    }
+}
+
+impl Drop for QueuePermit {
+    fn drop(&mut self) {
+        if let Some(queue) = self.queue.take() {
+            queue.release();
+        }
+    }
+}
+
+/// 单个类别的排队指标
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct QueueClassMetrics {
+    /// 类别名称
+    pub name: String,
+    /// 权重
+    pub weight: u32,
+    /// 当前排队数
+    pub queued: usize,
+    /// 已放行请求数
+    pub admitted: u64,
+    /// 排队超时数
+    pub timed_out: u64,
+    /// 平均排队时间（毫秒）
+    pub avg_wait_ms: u64,
+    /// 最大排队时间（毫秒）
+    pub max_wait_ms: u64,
+    /// 当前最久排队请求的等待时间（毫秒）
+    pub oldest_wait_ms: u64,
+}
+
+/// 队列指标快照
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct QueueMetrics {
+    /// 是否启用
+    pub enabled: bool,
+    /// 上游并发槽位数
+    pub capacity: usize,
+    /// 正在执行的请求数
+    pub in_flight: usize,
+    /// 排队总数
+    pub queued: usize,
+    /// 各类别指标
+    pub classes: Vec<QueueClassMetrics>,
+}
+
+struct Waiter {
+    id: u64,
+    tag: f64,
+    enqueued_at: Instant,
+    tx: oneshot::Sender<QueuePermit>,
+}
+
+#[derive(Default)]
+struct ClassState {
+    last_finish: f64,
+    waiting: VecDeque<Waiter>,
+    admitted: u64,
+    timed_out: u64,
+    total_wait_ms: u64,
+    max_wait_ms: u64,
+}
+
+impl ClassState {
+    fn record_admitted(&mut self, waited: Duration) {
+        let ms = waited.as_millis() as u64;
+        self.admitted += 1;
+        self.total_wait_ms += ms;
+        self.max_wait_ms = self.max_wait_ms.max(ms);
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    in_flight: usize,
+    virtual_time: f64,
+    next_id: u64,
+    classes: HashMap<String, ClassState>,
+}
+
+impl QueueState {
+    fn queued(&self) -> usize {
+        self.classes.values().map(|c| c.waiting.len()).sum()
+    }
+
+    /// 队首虚拟完成时间最小的类别
+    fn next_class(&self) -> Option<String> {
+        self.classes
+            .iter()
+            .filter_map(|(name, class)| class.waiting.front().map(|w| (name, w.tag, w.id)))
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(a.2.cmp(&b.2)))
+            .map(|(name, _, _)| name.clone())
+    }
+}
+
+/// 加权公平队列
+///
+/// 每个排队请求获得虚拟完成时间 `max(V, 类别上次完成时间) + 1 / weight`，
+/// 槽位空闲时放行虚拟完成时间最小的请求。权重越大的类别获得越多的槽位份额，
+/// 但低权重类别不会被饿死。
+pub struct FairQueue {
+    config: RwLock<RequestQueueConfig>,
+    state: Mutex<QueueState>,
+}
+
+impl FairQueue {
+    /// 创建新的公平队列
+    pub fn new(config: RequestQueueConfig) -> Arc<Self> {
+        Arc::new(Self {
+            config: RwLock::new(config),
+            state: Mutex::new(QueueState::default()),
+        })
+    }
+
+    /// 获取当前配置
+    pub fn config(&self) -> RequestQueueConfig {
+        self.config.read().clone()
+    }
+
+    /// 更新配置（扩容时立即放行排队请求）
+    pub fn set_config(self: &Arc<Self>, config: RequestQueueConfig) {
+        *self.config.write() = config;
+        let mut state = self.state.lock();
+        self.dispatch(&mut state);
+    }
+
+    /// 是否启用排队
+    pub fn is_enabled(&self) -> bool {
+        self.config.read().enabled
+    }
+
+    /// 最大排队等待时间
+    pub fn max_wait(&self) -> Duration {
+        Duration::from_millis(self.config.read().max_wait_ms)
+    }
+
+    /// 确定请求的优先级类别
+    ///
+    /// 优先级：API Key 绑定 > 请求头 > 客户端类型 > 默认类别。
+    /// 映射到不存在的类别时忽略该来源，继续尝试下一个。
+    pub fn classify(
+        &self,
+        api_key: Option<&str>,
+        header_class: Option<&str>,
+        client_key: &str,
+    ) -> String {
+        let config = self.config.read();
+        let known = |name: &str| config.classes.iter().any(|c| c.name == name);
+
+        api_key
+            .and_then(|key| config.api_key_classes.get(key))
+            .map(String::as_str)
+            .filter(|name| known(name))
+            .or_else(|| header_class.map(str::trim).filter(|name| known(name)))
+            .or_else(|| {
+                config
+                    .client_classes
+                    .get(client_key)
+                    .map(String::as_str)
+                    .filter(|name| known(name))
+            })
+            .unwrap_or(&config.default_class)
+            .to_string()
+    }
+
+    /// 等待获取上游并发槽位
+    ///
+    /// 在 `deadline` 之前未获得槽位时返回 [`QueueError::Timeout`]
+    pub async fn acquire(
+        self: &Arc<Self>,
+        class: &str,
+        deadline: Instant,
+    ) -> Result<QueuePermit, QueueError> {
+        let started = Instant::now();
+        let (id, mut rx) = {
+            let capacity = self.capacity();
+            let weight = self.weight(class);
+            let mut state = self.state.lock();
+            if state.in_flight < capacity && state.queued() == 0 {
+                state.in_flight += 1;
+                state
+                    .classes
+                    .entry(class.to_string())
+                    .or_default()
+                    .record_admitted(Duration::ZERO);
+                return Ok(self.permit(class, Duration::ZERO));
+            }
+
+            let (tx, rx) = oneshot::channel();
+            let id = state.next_id;
+            state.next_id += 1;
+            let virtual_time = state.virtual_time;
+            let entry = state.classes.entry(class.to_string()).or_default();
+            let tag = entry.last_finish.max(virtual_time) + 1.0 / weight as f64;
+            entry.last_finish = tag;
+            entry.waiting.push_back(Waiter {
+                id,
+                tag,
+                enqueued_at: started,
+                tx,
+            });
+            (id, rx)
+        };
+
+        match tokio::time::timeout_at(deadline.into(), &mut rx).await {
+            Ok(Ok(permit)) => Ok(permit),
+            _ => {
+                let removed = {
+                    let mut state = self.state.lock();
+                    let entry = state.classes.entry(class.to_string()).or_default();
+                    let before = entry.waiting.len();
+                    entry.waiting.retain(|w| w.id != id);
+                    let removed = entry.waiting.len() != before;
+                    if removed {
+                        entry.timed_out += 1;
+                    }
+                    removed
+                };
+                // 超时与放行并发发生时，许可已在通道中
+                if !removed {
+                    if let Ok(permit) = rx.try_recv() {
+                        return Ok(permit);
+                    }
+                }
+                Err(QueueError::Timeout {
+                    class: class.to_string(),
+                    waited_ms: started.elapsed().as_millis() as u64,
+                    retry_after_secs: self.max_wait().as_secs().max(1),
+                })
+            }
+        }
+    }
+
+    /// 获取指标快照
+    pub fn metrics(&self) -> QueueMetrics {
+        let config = self.config.read().clone();
+        let state = self.state.lock();
+        let now = Instant::now();
+
+        let mut names: Vec<String> = config.classes.iter().map(|c| c.name.clone()).collect();
+        for name in state.classes.keys() {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+
+        let classes = names
+            .into_iter()
+            .map(|name| {
+                let weight = config
+                    .classes
+                    .iter()
+                    .find(|c| c.name == name)
+                    .map(|c| c.weight.max(1))
+                    .unwrap_or(1);
+                match state.classes.get(&name) {
+                    Some(class) => QueueClassMetrics {
+                        weight,
+                        queued: class.waiting.len(),
+                        admitted: class.admitted,
+                        timed_out: class.timed_out,
+                        avg_wait_ms: class.total_wait_ms.checked_div(class.admitted).unwrap_or(0),
+                        max_wait_ms: class.max_wait_ms,
+                        oldest_wait_ms: class
+                            .waiting
+                            .front()
+                            .map(|w| now.duration_since(w.enqueued_at).as_millis() as u64)
+                            .unwrap_or(0),
+                        name,
+                    },
+                    None => QueueClassMetrics {
+                        name,
+                        weight,
+                        queued: 0,
+                        admitted: 0,
+                        timed_out: 0,
+                        avg_wait_ms: 0,
+                        max_wait_ms: 0,
+                        oldest_wait_ms: 0,
+                    },
+                }
+            })
+            .collect();
+
+        QueueMetrics {
+            enabled: config.enabled,
+            capacity: config.max_concurrent.max(1),
+            in_flight: state.in_flight,
+            queued: state.queued(),
+            classes,
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        self.config.read().max_concurrent.max(1)
+    }
+
+    fn weight(&self, class: &str) -> u32 {
+        self.config
+            .read()
+            .classes
+            .iter()
+            .find(|c| c.name == class)
+            .map(|c| c.weight.max(1))
+            .unwrap_or(1)
+    }
+
+    fn permit(self: &Arc<Self>, class: &str, waited: Duration) -> QueuePermit {
+        QueuePermit {
+            queue: Some(self.clone()),
+            class: class.to_string(),
+            waited,
+        }
+    }
+
+    fn release(self: &Arc<Self>) {
+        let mut state = self.state.lock();
+        state.in_flight = state.in_flight.saturating_sub(1);
+        self.dispatch(&mut state);
+    }
+
+    /// 在有空闲槽位时按虚拟完成时间放行排队请求
+    fn dispatch(self: &Arc<Self>, state: &mut QueueState) {
+        let capacity = self.capacity();
+        while state.in_flight < capacity {
+            let Some(name) = state.next_class() else {
+                break;
+            };
+            let Some(waiter) = state
+                .classes
+                .get_mut(&name)
+                .and_then(|c| c.waiting.pop_front())
+            else {
+                break;
+            };
+            state.virtual_time = state.virtual_time.max(waiter.tag);
+
+            let waited = waiter.enqueued_at.elapsed();
+            state.in_flight += 1;
+            match waiter.tx.send(self.permit(&name, waited)) {
+                Ok(()) => {
+                    if let Some(class) = state.classes.get_mut(&name) {
+                        class.record_admitted(waited);
+                    }
+                }
+                Err(mut permit) => {
+                    // 等待方已放弃，收回槽位（避免在持锁时触发 release）
+                    permit.queue = None;
+                    state.in_flight -= 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PriorityClassConfig;
+
+    fn config(max_concurrent: usize) -> RequestQueueConfig {
+        RequestQueueConfig {
+            enabled: true,
+            max_concurrent,
+            max_wait_ms: 1_000,
+            classes: vec![
+                PriorityClassConfig {
+                    name: "interactive".to_string(),
+                    weight: 3,
+                },
+                PriorityClassConfig {
+                    name: "background".to_string(),
+                    weight: 1,
+                },
+            ],
+            default_class: "background".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn deadline() -> Instant {
+        Instant::now() + Duration::from_secs(5)
+    }
+
+    #[test]
+    fn test_classify_precedence() {
+        let mut cfg = config(1);
+        cfg.api_key_classes
+            .insert("agent-key".to_string(), "background".to_string());
+        cfg.client_classes
+            .insert("cursor".to_string(), "interactive".to_string());
+        let queue = FairQueue::new(cfg);
+
+        assert_eq!(
+            queue.classify(Some("agent-key"), Some("interactive"), "cursor"),
+            "background"
+        );
+        assert_eq!(
+            queue.classify(None, Some("interactive"), "other"),
+            "interactive"
+        );
+        assert_eq!(
+            queue.classify(None, Some("unknown"), "cursor"),
+            "interactive"
+        );
+        assert_eq!(queue.classify(None, None, "other"), "background");
+    }
+
+    #[test]
+    fn test_classify_unknown_key_class_falls_through() {
+        let mut cfg = config(1);
+        cfg.api_key_classes
+            .insert("stale-key".to_string(), "removed".to_string());
+        cfg.client_classes
+            .insert("cursor".to_string(), "interactive".to_string());
+        cfg.client_classes
+            .insert("legacy".to_string(), "removed".to_string());
+        let queue = FairQueue::new(cfg);
+
+        assert_eq!(
+            queue.classify(Some("stale-key"), Some("interactive"), "other"),
+            "interactive"
+        );
+        assert_eq!(
+            queue.classify(Some("stale-key"), None, "cursor"),
+            "interactive"
+        );
+        assert_eq!(
+            queue.classify(Some("stale-key"), None, "legacy"),
+            "background"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_acquire_immediately_when_idle() {
+        let queue = FairQueue::new(config(2));
+        let permit = queue.acquire("interactive", deadline()).await.unwrap();
+        assert_eq!(permit.class(), "interactive");
+        assert_eq!(queue.metrics().in_flight, 1);
+        drop(permit);
+        assert_eq!(queue.metrics().in_flight, 0);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_returns_retry_after() {
+        let queue = FairQueue::new(config(1));
+        let _held = queue.acquire("background", deadline()).await.unwrap();
+
+        let err = queue
+            .acquire("interactive", Instant::now() + Duration::from_millis(20))
+            .await
+            .unwrap_err();
+        let QueueError::Timeout {
+            class,
+            retry_after_secs,
+            ..
+        } = err;
+        assert_eq!(class, "interactive");
+        assert_eq!(retry_after_secs, 1);
+
+        let metrics = queue.metrics();
+        assert_eq!(metrics.queued, 0);
+        let interactive = metrics
+            .classes
+            .iter()
+            .find(|c| c.name == "interactive")
+            .unwrap();
+        assert_eq!(interactive.timed_out, 1);
+    }
+
+    #[tokio::test]
+    async fn test_weighted_fair_ordering() {
+        let queue = FairQueue::new(config(1));
+        let held = queue.acquire("background", deadline()).await.unwrap();
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut handles = Vec::new();
+        // 先排入 4 个后台请求，再排入 4 个交互请求
+        for class in ["background"; 4].into_iter().chain(["interactive"; 4]) {
+            let task_queue = queue.clone();
+            let order = order.clone();
+            handles.push(tokio::spawn(async move {
+                let permit = task_queue.acquire(class, deadline()).await.unwrap();
+                order.lock().push(class);
+                tokio::task::yield_now().await;
+                drop(permit);
+            }));
+            // 确保按顺序入队
+            while queue.metrics().queued < handles.len() {
+                tokio::task::yield_now().await;
+            }
+        }
+
+        drop(held);
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        let order = order.lock().clone();
+        assert_eq!(order.len(), 8);
+        // 权重 3:1，交互请求虽然后入队，也应在前 5 个放行中占 3 个以上
+        let early_interactive = order[..5].iter().filter(|c| **c == "interactive").count();
+        assert!(early_interactive >= 3, "order: {:?}", order);
+        // 后台请求不会被饿死
+        assert!(order[..5].contains(&"background"), "order: {:?}", order);
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_waiter_does_not_leak_slot() {
+        let queue = FairQueue::new(config(1));
+        let held = queue.acquire("background", deadline()).await.unwrap();
+
+        let waiter = {
+            let queue = queue.clone();
+            tokio::spawn(async move { queue.acquire("interactive", deadline()).await })
+        };
+        while queue.metrics().queued == 0 {
+            tokio::task::yield_now().await;
+        }
+        waiter.abort();
+        let _ = waiter.await;
+
+        drop(held);
+        assert_eq!(queue.metrics().in_flight, 0);
+        assert!(queue.acquire("background", deadline()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_set_config_expands_capacity() {
+        let queue = FairQueue::new(config(1));
+        let _held = queue.acquire("background", deadline()).await.unwrap();
+
+        let waiter = {
+            let queue = queue.clone();
+            tokio::spawn(async move { queue.acquire("interactive", deadline()).await })
+        };
+        while queue.metrics().queued == 0 {
+            tokio::task::yield_now().await;
+        }
+        queue.set_config(config(2));
+
+        let permit = waiter.await.unwrap().unwrap();
+        assert_eq!(permit.class(), "interactive");
+        assert_eq!(queue.metrics().in_flight, 2);
+    }
+}
@@ -59,6 +59,19 @@ impl PipelineStep for PluginPreStep {
                     }))
                     .collect::<Vec<_>>()),
             );
+
+            // 插件拒绝请求时中止管道
+            if let Some(rejection) = results.iter().find_map(|r| r.rejection.clone()) {
+                tracing::info!(
+                    "[PLUGIN] request rejected: status={} message={}",
+                    rejection.status_code,
+                    rejection.message
+                );
+                return Err(StepError::Rejected {
+                    status_code: rejection.status_code,
+                    message: rejection.message,
+                });
+            }
         }
 
         Ok(())
@@ -8,6 +8,7 @@ use crate::resilience::{
     Failover, FailoverConfig, FailoverManager, Retrier, RetryConfig, TimeoutConfig,
     TimeoutController, TimeoutError,
 };
+use crate::services::event_bus::{EventBus, SystemEvent};
 use crate::services::provider_pool_service::ProviderPoolService;
 use crate::ProviderType;
 use async_trait::async_trait;
@@ -89,6 +90,8 @@ pub struct ProviderStep {
     timeout: Arc<TimeoutController>,
     /// 凭证池服务
     pool_service: Arc<ProviderPoolService>,
+    /// 事件总线（发布故障转移事件）
+    event_bus: Option<Arc<EventBus>>,
 }
 
 impl ProviderStep {
@@ -104,6 +107,7 @@ impl ProviderStep {
             failover,
             timeout,
             pool_service,
+            event_bus: None,
         }
     }
 
@@ -114,6 +118,7 @@ impl ProviderStep {
             failover: Arc::new(Failover::new(FailoverConfig::default())),
             timeout: Arc::new(TimeoutController::with_defaults()),
             pool_service,
+            event_bus: None,
         }
     }
 
@@ -129,9 +134,16 @@ impl ProviderStep {
             failover: Arc::new(Failover::new(failover_config)),
             timeout: Arc::new(TimeoutController::new(timeout_config)),
             pool_service,
+            event_bus: None,
         }
     }
 
+    /// 设置事件总线
+    pub fn with_event_bus(mut self, event_bus: Arc<EventBus>) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+
     /// 获取重试器
     pub fn retrier(&self) -> &Retrier {
         &self.retrier
@@ -444,6 +456,14 @@ impl ProviderStep {
                                 new_provider,
                                 failover_result.failure_type
                             );
+                            if let Some(bus) = &self.event_bus {
+                                bus.publish(SystemEvent::ProviderSwitched {
+                                    request_id: ctx.request_id.clone(),
+                                    from: current_provider,
+                                    to: new_provider,
+                                    failure_type: format!("{:?}", failover_result.failure_type),
+                                });
+                            }
                             current_provider = new_provider;
                             continue 'failover;
                         }
@@ -656,6 +676,59 @@ mod tests {
         assert_eq!(new_provider.unwrap(), ProviderType::Gemini);
     }
 
+    #[tokio::test]
+    async fn test_execute_with_resilience_publishes_switch_event() {
+        use crate::services::event_bus::TopicFilter;
+
+        let bus = Arc::new(EventBus::new(8));
+        let step = ProviderStep::with_config(
+            RetryConfig::default(),
+            FailoverConfig::new(true, true),
+            TimeoutConfig::default(),
+            Arc::new(ProviderPoolService::new()),
+        )
+        .with_event_bus(bus.clone());
+        let mut ctx = RequestContext::new("test-model".to_string());
+        ctx.set_provider(ProviderType::Kiro);
+
+        let available = vec![ProviderType::Kiro, ProviderType::Gemini];
+        let result = step
+            .execute_with_resilience(
+                &mut ctx,
+                |provider| async move {
+                    if provider == ProviderType::Kiro {
+                        Err(ProviderCallError::failover("quota exceeded", Some(429)))
+                    } else {
+                        Ok(ProviderCallResult {
+                            response: serde_json::json!({"content": "ok"}),
+                            status_code: 200,
+                            latency_ms: 1,
+                            credential_id: None,
+                        })
+                    }
+                },
+                &available,
+            )
+            .await;
+        assert!(result.is_ok());
+
+        let events = bus.subscribe_after(Some(0), &TopicFilter::all()).replay;
+        assert_eq!(events.len(), 1);
+        match &events[0].event {
+            SystemEvent::ProviderSwitched {
+                request_id,
+                from,
+                to,
+                ..
+            } => {
+                assert_eq!(request_id, &ctx.request_id);
+                assert_eq!(*from, ProviderType::Kiro);
+                assert_eq!(*to, ProviderType::Gemini);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
     #[tokio::test]
     async fn test_handle_failover_no_alternative() {
         let pool_service = Arc::new(ProviderPoolService::new());
@@ -32,6 +32,10 @@ pub enum StepError {
         message: String,
     },
 
+    /// 插件拒绝请求
+    #[error("请求被插件拒绝 ({status_code}): {message}")]
+    Rejected { status_code: u16, message: String },
+
     /// 遥测错误
     #[error("遥测错误: {0}")]
     Telemetry(String),
@@ -54,6 +58,7 @@ impl StepError {
             StepError::Injection(_) => 400,
             StepError::Provider(_) => 502,
             StepError::Plugin { .. } => 500,
+            StepError::Rejected { status_code, .. } => *status_code,
             StepError::Telemetry(_) => 500,
             StepError::Timeout { .. } => 408,
             StepError::Internal(_) => 500,
@@ -1,4 +1,5 @@
 //! Claude Custom Provider (自定义 Claude API)
+use crate::converter::structured_output::{anthropic_forced_tool, ResponseFormat};
 use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::{ChatCompletionRequest, ContentPart, MessageContent};
 use reqwest::Client;
@@ -167,6 +168,14 @@ impl ClaudeCustomProvider {
             anthropic_body["system"] = serde_json::json!(sys);
         }
 
+        // 结构化输出：通过强制工具调用实现 JSON Schema 约束（保留调用方的工具）
+        let response_format = ResponseFormat::from_request(request).filter(|f| f.is_json());
+        if let Some(format) = &response_format {
+            let (tools, tool_choice) = anthropic_forced_tool(request.tools.as_deref(), format);
+            anthropic_body["tools"] = tools;
+            anthropic_body["tool_choice"] = tool_choice;
+        }
+
         let api_key = self
             .config
             .api_key
@@ -209,11 +218,21 @@ impl ClaudeCustomProvider {
         let anthropic_resp: serde_json::Value = resp.json().await?;
 
         // 转换回 OpenAI 格式
-        let content = anthropic_resp["content"]
-            .as_array()
-            .and_then(|arr| arr.first())
-            .and_then(|block| block["text"].as_str())
-            .unwrap_or("");
+        let forced_tool_output = response_format.as_ref().and_then(|format| {
+            anthropic_resp["content"].as_array().and_then(|arr| {
+                arr.iter()
+                    .find(|block| block["type"] == "tool_use" && block["name"] == format.name())
+                    .map(|block| block["input"].to_string())
+            })
+        });
+        let content = match &forced_tool_output {
+            Some(output) => output.as_str(),
+            None => anthropic_resp["content"]
+                .as_array()
+                .and_then(|arr| arr.first())
+                .and_then(|block| block["text"].as_str())
+                .unwrap_or(""),
+        };
 
         Ok(serde_json::json!({
             "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
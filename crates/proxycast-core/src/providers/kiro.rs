@@ -158,7 +158,7 @@ fn get_raw_machine_id() -> Option<String> {
 /// 获取 Kiro IDE 版本号
 ///
 /// 尝试从 Kiro.app 的 Info.plist 读取实际版本，失败时使用默认值
-fn get_kiro_version() -> String {
+pub(crate) fn get_kiro_version() -> String {
     use std::process::Command;
 
     if cfg!(target_os = "macos") {
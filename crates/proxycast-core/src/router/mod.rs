@@ -25,7 +25,7 @@ pub use amp_router::{AmpRouteMatch, AmpRouter};
 pub use mapper::{ModelInfo, ModelMapper};
 pub use provider_router::ProviderRouter;
 pub use route_registry::{RegisteredRoute, RouteRegistry, RouteType};
-pub use rules::{RouteResult, Router, RoutingRule};
+pub use rules::{matches_model_pattern, RouteResult, Router, RoutingRule};
 
 #[cfg(test)]
 mod tests;
@@ -42,28 +42,7 @@ impl RoutingRule {
             return false;
         }
 
-        let pattern = &self.pattern;
-
-        // 精确匹配
-        if !pattern.contains('*') {
-            return pattern == model;
-        }
-
-        // 通配符匹配
-        let parts: Vec<&str> = pattern.split('*').collect();
-
-        match parts.as_slice() {
-            // 前缀匹配: `claude-*`
-            [prefix, ""] => model.starts_with(prefix),
-            // 后缀匹配: `*-preview`
-            ["", suffix] => model.ends_with(suffix),
-            // 包含匹配: `*flash*`
-            ["", middle, ""] => model.contains(middle),
-            // 前缀+后缀匹配: `claude-*-preview`
-            [prefix, suffix] => model.starts_with(prefix) && model.ends_with(suffix),
-            // 其他复杂模式暂不支持
-            _ => false,
-        }
+        matches_model_pattern(&self.pattern, model)
     }
 
     /// 检查是否为精确匹配规则
@@ -72,6 +51,33 @@ impl RoutingRule {
     }
 }
 
+/// 检查模型是否匹配通配符模式
+///
+/// 支持精确、前缀（`claude-*`）、后缀（`*-preview`）、包含（`*flash*`）
+/// 和前缀+后缀（`claude-*-preview`）匹配
+pub fn matches_model_pattern(pattern: &str, model: &str) -> bool {
+    // 精确匹配
+    if !pattern.contains('*') {
+        return pattern == model;
+    }
+
+    // 通配符匹配
+    let parts: Vec<&str> = pattern.split('*').collect();
+
+    match parts.as_slice() {
+        // 前缀匹配: `claude-*`
+        [prefix, ""] => model.starts_with(prefix),
+        // 后缀匹配: `*-preview`
+        ["", suffix] => model.ends_with(suffix),
+        // 包含匹配: `*flash*`
+        ["", middle, ""] => model.contains(middle),
+        // 前缀+后缀匹配: `claude-*-preview`
+        [prefix, suffix] => model.starts_with(prefix) && model.ends_with(suffix),
+        // 其他复杂模式暂不支持
+        _ => false,
+    }
+}
+
 /// 路由规则比较器 - 用于排序
 impl Ord for RoutingRule {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
@@ -21,9 +21,18 @@ use axum::{
     Json,
 };
 use chrono::Utc;
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
+use crate::converter::image::{
+    prepare_anthropic_request_images, prepare_request_images, requires_inline_images,
+};
+use crate::converter::structured_output::ResponseFormat;
+use crate::credential::AllCredentialsExhaustedError;
 use crate::flow_monitor::{
     ClientInfo, FlowError, FlowErrorType, FlowMetadata, FlowType, InterceptAction, InterceptType,
     LLMFlow, LLMRequest, LLMResponse, Message, MessageContent, MessageRole, RequestParameters,
@@ -31,24 +40,33 @@ use crate::flow_monitor::{
 };
 use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::ChatCompletionRequest;
-use crate::processor::RequestContext;
+use crate::models::provider_pool_model::ProviderCredential;
+use crate::plugin::{PluginContext, PluginManager};
+use crate::processor::{PipelineStep, PluginPostStep, PluginPreStep, RequestContext, StepError};
 use crate::server::client_detector::ClientType;
 use crate::server::{record_request_telemetry, record_token_usage, AppState};
 use crate::server_utils::{
     build_anthropic_response, build_anthropic_stream_response, message_content_len,
     parse_cw_response, safe_truncate,
 };
+use crate::services::event_bus::SystemEvent;
 use crate::streaming::StreamFormat as StreamingFormat;
+use crate::streaming::{sse_event_blocks, with_plugin_hooks, StreamPluginHooks};
 use crate::ProviderType;
 
-use super::{call_provider_anthropic, call_provider_openai};
+use super::{
+    acquire_upstream_slot, call_provider_anthropic, call_provider_openai,
+    call_provider_openai_structured, call_provider_openai_structured_stream,
+    capture_anthropic_output, capture_openai_output, lock_kiro_refresh, mirror_anthropic_request,
+    mirror_openai_request, upstream_queue_class,
+};
 
 // ============================================================================
 // Flow 捕获辅助函数
 // ============================================================================
 
 /// 从 OpenAI 格式请求构建 LLMRequest
-fn build_llm_request_from_openai(
+pub(super) fn build_llm_request_from_openai(
     request: &ChatCompletionRequest,
     path: &str,
     headers: &HeaderMap,
@@ -150,7 +168,7 @@ fn build_llm_request_from_openai(
 }
 
 /// 从 Anthropic 格式请求构建 LLMRequest
-fn build_llm_request_from_anthropic(
+pub(super) fn build_llm_request_from_anthropic(
     request: &AnthropicMessagesRequest,
     path: &str,
     headers: &HeaderMap,
@@ -263,7 +281,7 @@ fn build_llm_request_from_anthropic(
 }
 
 /// 构建 FlowMetadata
-fn build_flow_metadata(
+pub(super) fn build_flow_metadata(
     provider: ProviderType,
     credential_id: Option<&str>,
     credential_name: Option<&str>,
@@ -299,7 +317,11 @@ fn build_flow_metadata(
 }
 
 /// 从响应构建 LLMResponse
-fn build_llm_response(status_code: u16, content: &str, usage: Option<(u32, u32)>) -> LLMResponse {
+pub(super) fn build_llm_response(
+    status_code: u16,
+    content: &str,
+    usage: Option<(u32, u32)>,
+) -> LLMResponse {
     let now = Utc::now();
     let (input_tokens, output_tokens) = usage.unwrap_or((0, 0));
 
@@ -367,6 +389,257 @@ async fn select_provider_for_client(headers: &HeaderMap, state: &AppState) -> (S
     (selected_provider, client_type)
 }
 
+// ============================================================================
+// 上游排队辅助函数
+// ============================================================================
+
+/// 从凭证池选择凭证，优先选择未处于配额冷却期的凭证
+fn select_pool_credential(
+    state: &AppState,
+    provider: &str,
+    model: &str,
+) -> Option<ProviderCredential> {
+    let db = state.db.as_ref()?;
+    let quota = &state.quota_manager;
+    state
+        .pool_service
+        .select_credential_filtered(db, provider, Some(model), |cred| {
+            quota.is_available(&cred.uuid)
+        })
+        .ok()
+        .flatten()
+        .or_else(|| {
+            state
+                .pool_service
+                .select_credential(db, provider, Some(model))
+                .ok()
+                .flatten()
+        })
+}
+
+/// 等待凭证从配额冷却中恢复
+///
+/// 匹配的凭证全部处于冷却期时，若最早恢复时间早于 `deadline` 则在队列中等待，
+/// 否则返回 [`AllCredentialsExhaustedError`]。凭证池中没有匹配凭证时直接返回，
+/// 交由后续的回退逻辑处理。
+async fn wait_for_quota_recovery(
+    state: &AppState,
+    provider: &str,
+    model: &str,
+    deadline: std::time::Instant,
+) -> Result<(), AllCredentialsExhaustedError> {
+    let Some(db) = &state.db else {
+        return Ok(());
+    };
+    let quota = &state.quota_manager;
+    loop {
+        let available =
+            state
+                .pool_service
+                .select_credential_filtered(db, provider, Some(model), |cred| {
+                    quota.is_available(&cred.uuid)
+                });
+        if !matches!(available, Ok(None)) {
+            return Ok(());
+        }
+        if !matches!(
+            state
+                .pool_service
+                .select_credential(db, provider, Some(model)),
+            Ok(Some(_))
+        ) {
+            return Ok(());
+        }
+
+        let recovery = quota.earliest_recovery();
+        let wait = recovery
+            .and_then(|time| (time - Utc::now()).to_std().ok())
+            .unwrap_or_default()
+            .max(std::time::Duration::from_millis(50));
+        match recovery {
+            Some(_) if std::time::Instant::now() + wait <= deadline => {
+                tokio::time::sleep(wait).await;
+            }
+            _ => {
+                state.event_bus.publish(SystemEvent::QuotaExhausted {
+                    provider: provider.to_string(),
+                    model: model.to_string(),
+                    earliest_recovery: recovery,
+                });
+                return Err(AllCredentialsExhaustedError::new(recovery));
+            }
+        }
+    }
+}
+
+/// 选择上游凭证
+///
+/// 排队启用且凭证全部处于配额冷却期时，若即将恢复则先在最大等待时间内等待恢复。
+/// 返回选中的凭证池凭证（没有可用凭证时为 `None`，交由回退逻辑处理）。
+async fn select_upstream_credential(
+    state: &AppState,
+    provider: &str,
+    model: &str,
+    request_id: &str,
+) -> Result<Option<ProviderCredential>, Response> {
+    let queue = &state.request_queue;
+    let config = queue.config();
+    if !config.enabled {
+        return Ok(state.db.as_ref().and_then(|db| {
+            state
+                .pool_service
+                .select_credential(db, provider, Some(model))
+                .ok()
+                .flatten()
+        }));
+    }
+
+    if config.wait_for_quota_recovery {
+        let deadline = std::time::Instant::now() + queue.max_wait();
+        if let Err(e) = wait_for_quota_recovery(state, provider, model, deadline).await {
+            state
+                .logs
+                .write()
+                .await
+                .add("warn", &format!("[QUEUE] request_id={} {}", request_id, e));
+            return Err(e.into_response());
+        }
+    }
+
+    Ok(select_pool_credential(state, provider, model))
+}
+
+// ============================================================================
+// 插件钩子辅助函数
+// ============================================================================
+
+/// 插件步骤记录的执行结果中是否有钩子修改了负载
+fn plugin_results_modified(ctx: &RequestContext, key: &str) -> bool {
+    ctx.get_metadata(key)
+        .and_then(|results| results.as_array())
+        .is_some_and(|results| results.iter().any(|r| r["modified"] == true))
+}
+
+/// 执行插件前置钩子（PluginPreStep），插件可改写请求
+///
+/// 没有已加载的插件时跳过，避免序列化请求。插件拒绝请求时返回
+/// [`StepError::Rejected`] 并清除插件上下文，被拒绝的请求不再执行后置钩子。
+async fn run_plugin_pre_hooks<T>(
+    state: &AppState,
+    ctx: &mut RequestContext,
+    request: &mut T,
+) -> Result<(), StepError>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let plugins = &state.processor.plugins;
+    if plugins.count() == 0 {
+        return Ok(());
+    }
+    let mut payload =
+        serde_json::to_value(&*request).map_err(|e| StepError::Internal(e.to_string()))?;
+    if let Err(e) = PluginPreStep::new(plugins.clone())
+        .execute(ctx, &mut payload)
+        .await
+    {
+        ctx.plugin_ctx = None;
+        return Err(e);
+    }
+    if plugin_results_modified(ctx, "plugin_pre_results") {
+        match serde_json::from_value(payload) {
+            Ok(updated) => *request = updated,
+            Err(e) => tracing::warn!(
+                "[PLUGIN] request_id={} 插件改写后的请求格式无效，已忽略: {}",
+                ctx.request_id,
+                e
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// 插件前置钩子失败时返回给客户端的状态码和错误消息
+fn plugin_rejection(error: &StepError) -> (StatusCode, String) {
+    let status =
+        StatusCode::from_u16(error.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let message = match error {
+        StepError::Rejected { message, .. } => message.clone(),
+        other => other.to_string(),
+    };
+    (status, message)
+}
+
+/// 执行插件后置钩子（PluginPostStep）
+///
+/// 只处理执行过前置钩子的请求：失败响应触发 `on_error`，成功的流式响应按 `format`
+/// 挂载流式钩子，成功的非流式 JSON 响应交给 `on_response`，有插件修改时按修改后的
+/// 负载重建响应体。
+async fn run_plugin_post_hooks(
+    plugins: &Arc<PluginManager>,
+    ctx: &mut RequestContext,
+    response: Response,
+    format: StreamingFormat,
+) -> Response {
+    let Some(plugin_ctx) = &ctx.plugin_ctx else {
+        return response;
+    };
+    if ctx.is_stream && response.status().is_success() {
+        return attach_stream_plugin_hooks(plugins, plugin_ctx.clone(), response, format);
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": {"message": e.to_string()}})),
+            )
+                .into_response()
+        }
+    };
+    let step = PluginPostStep::new(plugins.clone());
+
+    if !parts.status.is_success() {
+        step.run_on_error(ctx, &String::from_utf8_lossy(&bytes))
+            .await;
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+
+    let Ok(mut payload) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    if let Err(e) = step.execute(ctx, &mut payload).await {
+        tracing::warn!(
+            "[PLUGIN] request_id={} 后置钩子执行失败: {}",
+            ctx.request_id,
+            e
+        );
+    }
+    if !plugin_results_modified(ctx, "plugin_post_results") {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(payload.to_string()))
+}
+
+/// 为流式响应体挂载插件流式钩子（`on_stream_event` / `on_stream_end`）
+///
+/// 插件拒绝时输出 SSE 错误事件并结束流。
+fn attach_stream_plugin_hooks(
+    plugins: &Arc<PluginManager>,
+    plugin_ctx: PluginContext,
+    response: Response,
+    format: StreamingFormat,
+) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let hooks = StreamPluginHooks::new(plugins.clone(), plugin_ctx, format);
+    let stream = with_plugin_hooks(sse_event_blocks(body.into_data_stream()), hooks)
+        .map(|item| Ok::<_, std::convert::Infallible>(item.unwrap_or_else(|e| e.to_sse_error())));
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
 // ============================================================================
 // 拦截检查辅助函数
 // ============================================================================
@@ -618,7 +891,20 @@ pub async fn verify_api_key_anthropic(
 pub async fn chat_completions(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(mut request): Json<ChatCompletionRequest>,
+    Json(request): Json<ChatCompletionRequest>,
+) -> Response {
+    let plugins = state.processor.plugins.clone();
+    // 创建请求上下文
+    let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    let response = handle_chat_completions(state, headers, request, &mut ctx).await;
+    run_plugin_post_hooks(&plugins, &mut ctx, response, StreamingFormat::OpenAiSse).await
+}
+
+async fn handle_chat_completions(
+    state: AppState,
+    headers: HeaderMap,
+    mut request: ChatCompletionRequest,
+    ctx: &mut RequestContext,
 ) -> Response {
     if let Err(e) = verify_api_key(&headers, &state.api_key).await {
         state
@@ -629,9 +915,6 @@ pub async fn chat_completions(
         return e.into_response();
     }
 
-    // 创建请求上下文
-    let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
-
     state.logs.write().await.add(
         "info",
         &format!(
@@ -641,7 +924,7 @@ pub async fn chat_completions(
     );
 
     // 使用 RequestProcessor 解析模型别名和路由
-    let provider = state.processor.resolve_and_route(&mut ctx).await;
+    let provider = state.processor.resolve_and_route(ctx).await;
 
     // 更新请求中的模型名为解析后的模型
     if ctx.resolved_model != ctx.original_model {
@@ -676,9 +959,29 @@ pub async fn chat_completions(
         }
     }
 
+    // 执行插件前置钩子（插件可改写或拒绝请求）
+    if let Err(e) = run_plugin_pre_hooks(&state, ctx, &mut request).await {
+        let (status, message) = plugin_rejection(&e);
+        state.logs.write().await.add(
+            "warn",
+            &format!("[PLUGIN] request_id={} rejected: {}", ctx.request_id, e),
+        );
+        return (
+            status,
+            Json(serde_json::json!({
+                "error": {
+                    "message": message,
+                    "type": "plugin_rejected"
+                }
+            })),
+        )
+            .into_response();
+    }
+
     // 根据客户端类型选择 Provider
     // **Validates: Requirements 3.1, 3.3, 3.4**
     let (selected_provider, client_type) = select_provider_for_client(&headers, &state).await;
+    let queue_class = upstream_queue_class(&state, &headers);
 
     // 记录客户端检测和 Provider 选择结果
     state.logs.write().await.add(
@@ -689,6 +992,20 @@ pub async fn chat_completions(
         ),
     );
 
+    // 目标 Provider 需要内联图片时，校验并规整图片输入
+    if requires_inline_images(&selected_provider) {
+        let image_config = state.image_config.read().await.clone();
+        if let Err(e) =
+            prepare_request_images(&mut request, &image_config, &state.image_fetcher).await
+        {
+            state.logs.write().await.add(
+                "warn",
+                &format!("[IMAGE] request_id={} rejected: {}", ctx.request_id, e),
+            );
+            return (StatusCode::BAD_REQUEST, Json(e.to_json())).into_response();
+        }
+    }
+
     // 记录路由结果
     state.logs.write().await.add(
         "info",
@@ -698,14 +1015,17 @@ pub async fn chat_completions(
         ),
     );
 
-    // 尝试从凭证池中选择凭证
-    let credential = match &state.db {
-        Some(db) => state
-            .pool_service
-            .select_credential(db, &selected_provider, Some(&request.model))
-            .ok()
-            .flatten(),
-        None => None,
+    // 从凭证池中选择凭证（上游槽位在请求拦截之后、调用 Provider 时获取）
+    let credential = match select_upstream_credential(
+        &state,
+        &selected_provider,
+        &request.model,
+        &ctx.request_id,
+    )
+    .await
+    {
+        Ok(credential) => credential,
+        Err(response) => return response,
     };
 
     // 如果找到凭证池中的凭证，使用它
@@ -744,6 +1064,30 @@ pub async fn chat_completions(
                         // 从修改后的 LLMRequest 更新 ChatCompletionRequest
                         if let Ok(updated) = serde_json::from_value(modified.body.clone()) {
                             request = updated;
+                            // 修改后的请求可能引入新的图片，重新校验并规整
+                            if requires_inline_images(&selected_provider) {
+                                let image_config = state.image_config.read().await.clone();
+                                if let Err(e) = prepare_request_images(
+                                    &mut request,
+                                    &image_config,
+                                    &state.image_fetcher,
+                                )
+                                .await
+                                {
+                                    state.logs.write().await.add(
+                                        "warn",
+                                        &format!(
+                                            "[IMAGE] request_id={} rejected after intercept: {}",
+                                            ctx.request_id, e
+                                        ),
+                                    );
+                                    let error =
+                                        FlowError::new(FlowErrorType::BadRequest, e.to_string());
+                                    state.flow_monitor.fail_flow(fid, error).await;
+                                    return (StatusCode::BAD_REQUEST, Json(e.to_json()))
+                                        .into_response();
+                                }
+                            }
                         }
                     }
                 }
@@ -762,7 +1106,51 @@ pub async fn chat_completions(
             }
         }
 
-        let response = call_provider_openai(&state, &cred, &request, flow_id.as_deref()).await;
+        // 影子流量镜像：异步复制到影子目标，客户端只收到主响应
+        let mirrored = mirror_openai_request(
+            &state,
+            &request,
+            &headers,
+            flow_id.as_deref(),
+            &ctx.request_id,
+        );
+
+        // 结构化输出：校验输出并重试，非原生策略的流式请求校验后再按 SSE 返回
+        let response_format = ResponseFormat::from_request(&request).filter(|f| f.is_json());
+        let response = match &response_format {
+            Some(format) if !request.stream => {
+                call_provider_openai_structured(
+                    &state,
+                    &cred,
+                    &request,
+                    flow_id.as_deref(),
+                    format,
+                    &queue_class,
+                )
+                .await
+            }
+            Some(format) => {
+                call_provider_openai_structured_stream(
+                    &state,
+                    &cred,
+                    &request,
+                    flow_id.as_deref(),
+                    format,
+                    &queue_class,
+                )
+                .await
+            }
+            None => {
+                call_provider_openai(&state, &cred, &request, flow_id.as_deref(), &queue_class)
+                    .await
+            }
+        };
+        let (response, primary_output) = match flow_id.as_deref() {
+            Some(fid) if mirrored && response.status().is_success() => {
+                capture_openai_output(&state, response, fid).await
+            }
+            _ => (response, None),
+        };
 
         // 记录请求统计
         let is_success = response.status().is_success();
@@ -771,7 +1159,7 @@ pub async fn chat_completions(
         } else {
             crate::telemetry::RequestStatus::Failed
         };
-        record_request_telemetry(&state, &ctx, status, None);
+        record_request_telemetry(&state, ctx, status, None);
 
         // 如果成功，记录估算的 Token 使用量
         let estimated_input_tokens = request
@@ -790,7 +1178,7 @@ pub async fn chat_completions(
         if is_success {
             record_token_usage(
                 &state,
-                &ctx,
+                ctx,
                 Some(estimated_input_tokens),
                 Some(estimated_output_tokens),
             );
@@ -800,11 +1188,21 @@ pub async fn chat_completions(
         // **Validates: Requirements 2.1, 2.5**
         if let Some(fid) = flow_id {
             if is_success {
-                let llm_response = build_llm_response(
-                    200,
-                    "", // 内容在 provider_calls 中处理
-                    Some((estimated_input_tokens, estimated_output_tokens)),
-                );
+                let llm_response = match primary_output {
+                    // 镜像请求需要主响应的输出用于对比
+                    Some(output) => build_llm_response(
+                        200,
+                        &output.content,
+                        output
+                            .usage
+                            .or(Some((estimated_input_tokens, estimated_output_tokens))),
+                    ),
+                    None => build_llm_response(
+                        200,
+                        "", // 内容在 provider_calls 中处理
+                        Some((estimated_input_tokens, estimated_output_tokens)),
+                    ),
+                };
 
                 // 检查是否需要拦截响应
                 if let Some(modified_response) = check_response_intercept(
@@ -941,10 +1339,35 @@ pub async fn chat_completions(
         }
     }
 
+    // 影子流量镜像：异步复制到影子目标，客户端只收到主响应
+    mirror_openai_request(
+        &state,
+        &request,
+        &headers,
+        flow_id.as_deref(),
+        &ctx.request_id,
+    );
+
+    // 按优先级排队获取上游槽位（旧模式直接调用 Kiro，整体缓冲响应，处理器返回时归还槽位）
+    let _queue_permit =
+        match acquire_upstream_slot(&state, &queue_class, flow_id.as_deref()).await {
+            Ok(permit) => permit,
+            Err(response) => return response,
+        };
+
     // 检查是否需要刷新 token（无 token 或即将过期）
     {
-        let _guard = state.kiro_refresh_lock.lock().await;
+        let guard = match lock_kiro_refresh(&state, flow_id.as_deref()).await {
+            Ok(guard) => guard,
+            Err(response) => return response,
+        };
         let mut kiro = state.kiro.write().await;
+        // 其他副本已刷新时，内存中的 refresh_token 可能已被轮换，先重新加载凭证文件
+        if guard.peer_refreshed() {
+            if let Err(e) = kiro.load_credentials().await {
+                tracing::warn!("[AUTH] 重新加载其他副本刷新后的凭证失败: {}", e);
+            }
+        }
         let needs_refresh =
             kiro.credentials.access_token.is_none() || kiro.is_token_expiring_soon();
         if needs_refresh {
@@ -967,6 +1390,7 @@ pub async fn chat_completions(
                     Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {e}")}})),
                 ).into_response();
             }
+            guard.mark_refreshed().await;
         }
     }
 
@@ -1051,14 +1475,14 @@ pub async fn chat_completions(
                         // 记录成功请求统计
                         record_request_telemetry(
                             &state,
-                            &ctx,
+                            ctx,
                             crate::telemetry::RequestStatus::Success,
                             None,
                         );
                         // 记录 Token 使用量
                         record_token_usage(
                             &state,
-                            &ctx,
+                            ctx,
                             Some(estimated_input_tokens),
                             Some(estimated_output_tokens),
                         );
@@ -1133,7 +1557,7 @@ pub async fn chat_completions(
                         // 记录失败请求统计
                         record_request_telemetry(
                             &state,
-                            &ctx,
+                            ctx,
                             crate::telemetry::RequestStatus::Failed,
                             Some(e.to_string()),
                         );
@@ -1152,7 +1576,10 @@ pub async fn chat_completions(
             } else if status.as_u16() == 403 || status.as_u16() == 402 {
                 // Token 过期或账户问题，尝试重新加载凭证并刷新
                 drop(kiro);
-                let _guard = state.kiro_refresh_lock.lock().await;
+                let guard = match lock_kiro_refresh(&state, flow_id.as_deref()).await {
+                    Ok(guard) => guard,
+                    Err(response) => return response,
+                };
                 let mut kiro = state.kiro.write().await;
                 state.logs.write().await.add(
                     "warn",
@@ -1172,6 +1599,7 @@ pub async fn chat_completions(
 
                 match kiro.refresh_token().await {
                     Ok(_) => {
+                        guard.mark_refreshed().await;
                         state
                             .logs
                             .write()
@@ -1414,7 +1842,20 @@ pub async fn chat_completions(
 pub async fn anthropic_messages(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(mut request): Json<AnthropicMessagesRequest>,
+    Json(request): Json<AnthropicMessagesRequest>,
+) -> Response {
+    let plugins = state.processor.plugins.clone();
+    // 创建请求上下文
+    let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    let response = handle_anthropic_messages(state, headers, request, &mut ctx).await;
+    run_plugin_post_hooks(&plugins, &mut ctx, response, StreamingFormat::AnthropicSse).await
+}
+
+async fn handle_anthropic_messages(
+    state: AppState,
+    headers: HeaderMap,
+    mut request: AnthropicMessagesRequest,
+    ctx: &mut RequestContext,
 ) -> Response {
     // 使用 Anthropic 格式的认证验证（优先检查 x-api-key）
     if let Err(e) = verify_api_key_anthropic(&headers, &state.api_key).await {
@@ -1426,9 +1867,6 @@ pub async fn anthropic_messages(
         return e.into_response();
     }
 
-    // 创建请求上下文
-    let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
-
     // 详细记录请求信息
     let msg_count = request.messages.len();
     let has_tools = request.tools.as_ref().map(|t| t.len()).unwrap_or(0);
@@ -1442,7 +1880,7 @@ pub async fn anthropic_messages(
     );
 
     // 使用 RequestProcessor 解析模型别名和路由
-    let provider = state.processor.resolve_and_route(&mut ctx).await;
+    let provider = state.processor.resolve_and_route(ctx).await;
 
     // 更新请求中的模型名为解析后的模型
     if ctx.resolved_model != ctx.original_model {
@@ -1503,9 +1941,30 @@ pub async fn anthropic_messages(
         }
     }
 
+    // 执行插件前置钩子（插件可改写或拒绝请求）
+    if let Err(e) = run_plugin_pre_hooks(&state, ctx, &mut request).await {
+        let (status, message) = plugin_rejection(&e);
+        state.logs.write().await.add(
+            "warn",
+            &format!("[PLUGIN] request_id={} rejected: {}", ctx.request_id, e),
+        );
+        return (
+            status,
+            Json(serde_json::json!({
+                "type": "error",
+                "error": {
+                    "type": "plugin_rejected",
+                    "message": message
+                }
+            })),
+        )
+            .into_response();
+    }
+
     // 根据客户端类型选择 Provider
     // **Validates: Requirements 3.1, 3.3, 3.4**
     let (selected_provider, client_type) = select_provider_for_client(&headers, &state).await;
+    let queue_class = upstream_queue_class(&state, &headers);
 
     // 记录客户端检测和 Provider 选择结果
     state.logs.write().await.add(
@@ -1516,6 +1975,21 @@ pub async fn anthropic_messages(
         ),
     );
 
+    // 目标 Provider 需要内联图片时，校验并规整图片输入
+    if requires_inline_images(&selected_provider) {
+        let image_config = state.image_config.read().await.clone();
+        if let Err(e) =
+            prepare_anthropic_request_images(&mut request, &image_config, &state.image_fetcher)
+                .await
+        {
+            state.logs.write().await.add(
+                "warn",
+                &format!("[IMAGE] request_id={} rejected: {}", ctx.request_id, e),
+            );
+            return (StatusCode::BAD_REQUEST, Json(e.to_json())).into_response();
+        }
+    }
+
     // 记录路由结果
     state.logs.write().await.add(
         "info",
@@ -1525,17 +1999,17 @@ pub async fn anthropic_messages(
         ),
     );
 
-    // 尝试从凭证池中选择凭证
-    let credential = match &state.db {
-        Some(db) => {
-            // 根据选择的 Provider 配置选择凭证
-            state
-                .pool_service
-                .select_credential(db, &selected_provider, Some(&request.model))
-                .ok()
-                .flatten()
-        }
-        None => None,
+    // 从凭证池中选择凭证（上游槽位在请求拦截之后、调用 Provider 时获取）
+    let credential = match select_upstream_credential(
+        &state,
+        &selected_provider,
+        &request.model,
+        &ctx.request_id,
+    )
+    .await
+    {
+        Ok(credential) => credential,
+        Err(response) => return response,
     };
 
     // 如果找到凭证池中的凭证，使用它
@@ -1574,6 +2048,30 @@ pub async fn anthropic_messages(
                         // 从修改后的 LLMRequest 更新 AnthropicMessagesRequest
                         if let Ok(updated) = serde_json::from_value(modified.body.clone()) {
                             request = updated;
+                            // 修改后的请求可能引入新的图片，重新校验并规整
+                            if requires_inline_images(&selected_provider) {
+                                let image_config = state.image_config.read().await.clone();
+                                if let Err(e) = prepare_anthropic_request_images(
+                                    &mut request,
+                                    &image_config,
+                                    &state.image_fetcher,
+                                )
+                                .await
+                                {
+                                    state.logs.write().await.add(
+                                        "warn",
+                                        &format!(
+                                            "[IMAGE] request_id={} rejected after intercept: {}",
+                                            ctx.request_id, e
+                                        ),
+                                    );
+                                    let error =
+                                        FlowError::new(FlowErrorType::BadRequest, e.to_string());
+                                    state.flow_monitor.fail_flow(fid, error).await;
+                                    return (StatusCode::BAD_REQUEST, Json(e.to_json()))
+                                        .into_response();
+                                }
+                            }
                         }
                     }
                 }
@@ -1596,7 +2094,29 @@ pub async fn anthropic_messages(
             }
         }
 
-        let response = call_provider_anthropic(&state, &cred, &request, flow_id.as_deref()).await;
+        // 影子流量镜像：异步复制到影子目标，客户端只收到主响应
+        let mirrored = mirror_anthropic_request(
+            &state,
+            &request,
+            &headers,
+            flow_id.as_deref(),
+            &ctx.request_id,
+        );
+
+        let response = call_provider_anthropic(
+            &state,
+            &cred,
+            &request,
+            flow_id.as_deref(),
+            &queue_class,
+        )
+        .await;
+        let (response, primary_output) = match flow_id.as_deref() {
+            Some(fid) if mirrored && response.status().is_success() => {
+                capture_anthropic_output(&state, response, fid).await
+            }
+            _ => (response, None),
+        };
 
         // 记录请求统计
         let is_success = response.status().is_success();
@@ -1605,7 +2125,7 @@ pub async fn anthropic_messages(
         } else {
             crate::telemetry::RequestStatus::Failed
         };
-        record_request_telemetry(&state, &ctx, status, None);
+        record_request_telemetry(&state, ctx, status, None);
 
         // 估算 Token 使用量
         let estimated_input_tokens = request
@@ -1629,7 +2149,7 @@ pub async fn anthropic_messages(
         if is_success {
             record_token_usage(
                 &state,
-                &ctx,
+                ctx,
                 Some(estimated_input_tokens),
                 Some(estimated_output_tokens),
             );
@@ -1639,11 +2159,21 @@ pub async fn anthropic_messages(
         // **Validates: Requirements 2.1, 2.5**
         if let Some(fid) = flow_id {
             if is_success {
-                let llm_response = build_llm_response(
-                    200,
-                    "",
-                    Some((estimated_input_tokens, estimated_output_tokens)),
-                );
+                let llm_response = match primary_output {
+                    // 镜像请求需要主响应的输出用于对比
+                    Some(output) => build_llm_response(
+                        200,
+                        &output.content,
+                        output
+                            .usage
+                            .or(Some((estimated_input_tokens, estimated_output_tokens))),
+                    ),
+                    None => build_llm_response(
+                        200,
+                        "",
+                        Some((estimated_input_tokens, estimated_output_tokens)),
+                    ),
+                };
 
                 // 检查是否需要拦截响应
                 if let Some(modified_response) = check_response_intercept(
@@ -1779,10 +2309,35 @@ pub async fn anthropic_messages(
         }
     }
 
+    // 影子流量镜像：异步复制到影子目标，客户端只收到主响应
+    mirror_anthropic_request(
+        &state,
+        &request,
+        &headers,
+        flow_id.as_deref(),
+        &ctx.request_id,
+    );
+
+    // 按优先级排队获取上游槽位（旧模式直接调用 Kiro，整体缓冲响应，处理器返回时归还槽位）
+    let _queue_permit =
+        match acquire_upstream_slot(&state, &queue_class, flow_id.as_deref()).await {
+            Ok(permit) => permit,
+            Err(response) => return response,
+        };
+
     // 检查是否需要刷新 token（无 token 或即将过期）
     {
-        let _guard = state.kiro_refresh_lock.lock().await;
+        let guard = match lock_kiro_refresh(&state, flow_id.as_deref()).await {
+            Ok(guard) => guard,
+            Err(response) => return response,
+        };
         let mut kiro = state.kiro.write().await;
+        // 其他副本已刷新时，内存中的 refresh_token 可能已被轮换，先重新加载凭证文件
+        if guard.peer_refreshed() {
+            if let Err(e) = kiro.load_credentials().await {
+                tracing::warn!("[AUTH] 重新加载其他副本刷新后的凭证失败: {}", e);
+            }
+        }
         let needs_refresh =
             kiro.credentials.access_token.is_none() || kiro.is_token_expiring_soon();
         if needs_refresh {
@@ -1810,6 +2365,7 @@ pub async fn anthropic_messages(
                 )
                     .into_response();
             }
+            guard.mark_refreshed().await;
             state
                 .logs
                 .write()
@@ -2047,7 +2603,10 @@ pub async fn anthropic_messages(
             } else if status.as_u16() == 403 || status.as_u16() == 402 {
                 // Token 过期或账户问题，尝试重新加载凭证并刷新
                 drop(kiro);
-                let _guard = state.kiro_refresh_lock.lock().await;
+                let guard = match lock_kiro_refresh(&state, flow_id.as_deref()).await {
+                    Ok(guard) => guard,
+                    Err(response) => return response,
+                };
                 let mut kiro = state.kiro.write().await;
                 state.logs.write().await.add(
                     "warn",
@@ -2067,6 +2626,7 @@ pub async fn anthropic_messages(
 
                 match kiro.refresh_token().await {
                     Ok(_) => {
+                        guard.mark_refreshed().await;
                         state.logs.write().await.add(
                             "info",
                             "[AUTH] Token refreshed successfully, retrying request...",
@@ -0,0 +1,136 @@
+//! 数据库备份管理 API 处理器
+//!
+//! - `GET /v0/management/backups`：列出备份目录中的备份
+//! - `POST /v0/management/backups`：立即备份数据库
+//! - `POST /v0/management/backups/restore`：从备份恢复数据库
+//!
+//! 备份通过 SQLite 在线备份接口读写运行中的连接，无需停止服务器。
+//! 恢复只接受备份目录（`~/.proxycast/backups`）内的文件。
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+use crate::server::AppState;
+use crate::services::backup_service::BackupService;
+
+/// 恢复请求体
+#[derive(Debug, Deserialize)]
+pub struct RestoreBackupRequest {
+    /// 备份文件路径（相对路径按备份目录解析）
+    pub path: PathBuf,
+}
+
+fn backup_error(status: StatusCode, error_type: &str, message: String) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {"type": error_type, "message": message}
+        })),
+    )
+        .into_response()
+}
+
+fn backup_unavailable(message: String) -> Response {
+    backup_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "backup_unavailable",
+        message,
+    )
+}
+
+/// GET /v0/management/backups - 列出备份
+pub async fn management_list_backups() -> Response {
+    let service = match BackupService::with_defaults() {
+        Ok(service) => service,
+        Err(e) => return backup_unavailable(e),
+    };
+    match service.list_backups() {
+        Ok(backups) => Json(serde_json::json!({
+            "backup_dir": service.backup_dir(),
+            "backups": backups,
+        }))
+        .into_response(),
+        Err(e) => backup_error(StatusCode::INTERNAL_SERVER_ERROR, "backup_failed", e),
+    }
+}
+
+/// POST /v0/management/backups - 立即备份数据库
+pub async fn management_create_backup(State(state): State<AppState>) -> Response {
+    let Some(db) = state.db.clone() else {
+        return backup_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "数据库连接不可用".to_string(),
+        );
+    };
+    let service = match BackupService::with_defaults() {
+        Ok(service) => service,
+        Err(e) => return backup_unavailable(e),
+    };
+
+    let result =
+        tokio::task::spawn_blocking(move || service.backup_database_with_connection(&db)).await;
+    match result {
+        Ok(Ok(path)) => {
+            tracing::info!("[BACKUP] 已备份数据库到 {}", path.display());
+            (
+                StatusCode::CREATED,
+                Json(serde_json::json!({ "path": path })),
+            )
+                .into_response()
+        }
+        Ok(Err(e)) => backup_error(StatusCode::INTERNAL_SERVER_ERROR, "backup_failed", e),
+        Err(e) => backup_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            e.to_string(),
+        ),
+    }
+}
+
+/// POST /v0/management/backups/restore - 从备份恢复数据库
+pub async fn management_restore_backup(
+    State(state): State<AppState>,
+    Json(request): Json<RestoreBackupRequest>,
+) -> Response {
+    let Some(db) = state.db.clone() else {
+        return backup_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "数据库连接不可用".to_string(),
+        );
+    };
+    let service = match BackupService::with_defaults() {
+        Ok(service) => service,
+        Err(e) => return backup_unavailable(e),
+    };
+    let path = if request.path.is_absolute() {
+        request.path
+    } else {
+        service.backup_dir().join(request.path)
+    };
+
+    let restored = path.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        service.restore_database_with_connection(&db, &restored)
+    })
+    .await;
+    match result {
+        Ok(Ok(())) => {
+            tracing::info!("[BACKUP] 已从 {} 恢复数据库", path.display());
+            Json(serde_json::json!({ "restored": path })).into_response()
+        }
+        Ok(Err(e)) => backup_error(StatusCode::BAD_REQUEST, "restore_failed", e),
+        Err(e) => backup_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            e.to_string(),
+        ),
+    }
+}
@@ -0,0 +1,875 @@
+//! 批处理 API 处理器
+//!
+//! 提供 Anthropic Message Batches 和 OpenAI Batch API 兼容端点：
+//! - `POST/GET /v1/messages/batches`、`GET /v1/messages/batches/:id`、
+//!   `GET /v1/messages/batches/:id/results`、`POST /v1/messages/batches/:id/cancel`
+//! - `POST/GET /v1/files`、`GET/DELETE /v1/files/:id`、`GET /v1/files/:id/content`
+//! - `POST/GET /v1/batches`、`GET /v1/batches/:id`、`POST /v1/batches/:id/cancel`
+//!
+//! 以及 [`AppStateBatchExecutor`]：把批处理请求交给正常的请求处理管道执行。
+
+use async_trait::async_trait;
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, Multipart, Path, Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::batch::{
+    parse_anthropic_batch_requests, parse_openai_batch_input, BatchError, BatchExecution,
+    BatchExecutor, BatchKind, BATCH_FILE_PURPOSE,
+};
+use crate::converter::image::{
+    prepare_anthropic_request_images, prepare_request_images, requires_inline_images,
+};
+use crate::converter::structured_output::ResponseFormat;
+use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::openai::ChatCompletionRequest;
+use crate::models::provider_pool_model::ProviderCredential;
+use crate::processor::RequestContext;
+use crate::server::{record_request_telemetry, AppState};
+
+use super::{
+    background_queue_class, call_provider_anthropic, call_provider_openai,
+    call_provider_openai_structured, verify_api_key, verify_api_key_anthropic,
+};
+
+/// 列表分页参数
+#[derive(Debug, Deserialize)]
+pub struct BatchListQuery {
+    pub limit: Option<usize>,
+    /// Anthropic 分页游标
+    pub after_id: Option<String>,
+    /// OpenAI 分页游标
+    pub after: Option<String>,
+    /// 文件用途过滤（仅 `/v1/files`）
+    pub purpose: Option<String>,
+}
+
+impl BatchListQuery {
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(20).clamp(1, 1000)
+    }
+}
+
+// ============================================================================
+// 响应辅助函数
+// ============================================================================
+
+fn status_of(e: &BatchError) -> StatusCode {
+    StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+fn openai_error(e: BatchError) -> Response {
+    (status_of(&e), Json(e.to_openai_json())).into_response()
+}
+
+fn anthropic_error(e: BatchError) -> Response {
+    (status_of(&e), Json(e.to_anthropic_json())).into_response()
+}
+
+/// 批处理端点未启用时返回 404
+async fn check_enabled(state: &AppState, kind: BatchKind) -> Result<(), Response> {
+    if state.batch_manager.config().await.enabled {
+        return Ok(());
+    }
+    let e = BatchError::InvalidRequest("Batch API is disabled".to_string());
+    let body = match kind {
+        BatchKind::Anthropic => e.to_anthropic_json(),
+        BatchKind::OpenAi => e.to_openai_json(),
+    };
+    Err((StatusCode::NOT_FOUND, Json(body)).into_response())
+}
+
+/// 分页列表响应
+fn list_response(data: Vec<Value>, limit: usize, id_of: impl Fn(&Value) -> Value) -> Value {
+    json!({
+        "object": "list",
+        "has_more": data.len() == limit,
+        "first_id": data.first().map(&id_of),
+        "last_id": data.last().map(&id_of),
+        "data": data,
+    })
+}
+
+// ============================================================================
+// Anthropic Message Batches
+// ============================================================================
+
+/// POST /v1/messages/batches
+pub async fn create_message_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<Value>,
+) -> Response {
+    if let Err(e) = verify_api_key_anthropic(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    if let Err(resp) = check_enabled(&state, BatchKind::Anthropic).await {
+        return resp;
+    }
+
+    let max_requests = state.batch_manager.config().await.max_requests_per_batch;
+    let result = parse_anthropic_batch_requests(&payload, max_requests).and_then(|requests| {
+        state.batch_manager.store().create_batch(
+            BatchKind::Anthropic,
+            "/v1/messages",
+            None,
+            None,
+            requests,
+        )
+    });
+    match result {
+        Ok(job) => {
+            state.batch_manager.notify_new_work();
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[BATCH] created {} with {} requests",
+                    job.id,
+                    job.request_counts.total()
+                ),
+            );
+            Json(job.to_anthropic_json(&state.base_url)).into_response()
+        }
+        Err(e) => anthropic_error(e),
+    }
+}
+
+/// GET /v1/messages/batches
+pub async fn list_message_batches(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<BatchListQuery>,
+) -> Response {
+    if let Err(e) = verify_api_key_anthropic(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    if let Err(resp) = check_enabled(&state, BatchKind::Anthropic).await {
+        return resp;
+    }
+
+    let limit = query.limit();
+    match state.batch_manager.store().list_batches(
+        BatchKind::Anthropic,
+        limit,
+        query.after_id.as_deref(),
+    ) {
+        Ok(jobs) => {
+            let data = jobs
+                .iter()
+                .map(|job| job.to_anthropic_json(&state.base_url))
+                .collect();
+            let mut body = list_response(data, limit, |v| v["id"].clone());
+            if let Some(obj) = body.as_object_mut() {
+                obj.remove("object");
+            }
+            Json(body).into_response()
+        }
+        Err(e) => anthropic_error(e),
+    }
+}
+
+/// GET /v1/messages/batches/:id
+pub async fn get_message_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = verify_api_key_anthropic(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    if let Err(resp) = check_enabled(&state, BatchKind::Anthropic).await {
+        return resp;
+    }
+
+    match state.batch_manager.store().get_batch(&id) {
+        Ok(Some(job)) if job.kind == BatchKind::Anthropic => {
+            Json(job.to_anthropic_json(&state.base_url)).into_response()
+        }
+        Ok(_) => anthropic_error(BatchError::BatchNotFound(id)),
+        Err(e) => anthropic_error(e),
+    }
+}
+
+/// GET /v1/messages/batches/:id/results
+///
+/// 返回 JSONL，每行对应一个已结束的请求；任务未结束时只包含已完成的请求
+pub async fn get_message_batch_results(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = verify_api_key_anthropic(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    if let Err(resp) = check_enabled(&state, BatchKind::Anthropic).await {
+        return resp;
+    }
+
+    let store = state.batch_manager.store();
+    match store.get_batch(&id) {
+        Ok(Some(job)) if job.kind == BatchKind::Anthropic => {}
+        Ok(_) => return anthropic_error(BatchError::BatchNotFound(id)),
+        Err(e) => return anthropic_error(e),
+    }
+    match store.get_items(&id) {
+        Ok(items) => {
+            let body: String = items
+                .iter()
+                .filter(|item| item.status.is_terminal())
+                .map(|item| format!("{}\n", item.to_anthropic_result()))
+                .collect();
+            ([(header::CONTENT_TYPE, "application/x-jsonl")], body).into_response()
+        }
+        Err(e) => anthropic_error(e),
+    }
+}
+
+/// POST /v1/messages/batches/:id/cancel
+pub async fn cancel_message_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = verify_api_key_anthropic(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    if let Err(resp) = check_enabled(&state, BatchKind::Anthropic).await {
+        return resp;
+    }
+
+    let store = state.batch_manager.store();
+    match store.get_batch(&id) {
+        Ok(Some(job)) if job.kind == BatchKind::Anthropic => {}
+        Ok(_) => return anthropic_error(BatchError::BatchNotFound(id)),
+        Err(e) => return anthropic_error(e),
+    }
+    match store.cancel_batch(&id) {
+        Ok(job) => Json(job.to_anthropic_json(&state.base_url)).into_response(),
+        Err(e) => anthropic_error(e),
+    }
+}
+
+// ============================================================================
+// OpenAI Files
+// ============================================================================
+
+/// 上传的文件内容
+struct FileUpload {
+    purpose: Option<String>,
+    filename: String,
+    content: Vec<u8>,
+}
+
+/// 读取 multipart/form-data 上传（`purpose` + `file` 字段）
+async fn read_multipart_upload(mut multipart: Multipart) -> Result<FileUpload, BatchError> {
+    let malformed = |e: axum::extract::multipart::MultipartError| {
+        BatchError::InvalidRequest(format!("Malformed multipart body: {}", e.body_text()))
+    };
+    let mut purpose = None;
+    let mut file = None;
+    while let Some(field) = multipart.next_field().await.map_err(malformed)? {
+        match field.name() {
+            Some("purpose") => {
+                purpose = Some(field.text().await.map_err(malformed)?.trim().to_string());
+            }
+            Some("file") => {
+                let filename = field
+                    .file_name()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| "upload.jsonl".to_string());
+                file = Some((filename, field.bytes().await.map_err(malformed)?.to_vec()));
+            }
+            _ => {}
+        }
+    }
+    let (filename, content) = file.ok_or_else(|| {
+        BatchError::InvalidRequest("Missing required parameter: 'file'".to_string())
+    })?;
+    Ok(FileUpload {
+        purpose,
+        filename,
+        content,
+    })
+}
+
+/// POST /v1/files
+///
+/// 支持 multipart/form-data（`purpose` + `file`），也接受直接上传的 JSONL 请求体
+/// （通过查询参数 `purpose` 指定用途）
+pub async fn upload_file(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<BatchListQuery>,
+    request: Request,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    if let Err(resp) = check_enabled(&state, BatchKind::OpenAi).await {
+        return resp;
+    }
+
+    let is_multipart = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("multipart/form-data"));
+
+    let upload = if is_multipart {
+        let multipart = match Multipart::from_request(request, &()).await {
+            Ok(multipart) => multipart,
+            Err(rejection) => return rejection.into_response(),
+        };
+        match read_multipart_upload(multipart).await {
+            Ok(upload) => upload,
+            Err(e) => return openai_error(e),
+        }
+    } else {
+        let body = match Bytes::from_request(request, &()).await {
+            Ok(body) => body,
+            Err(rejection) => return rejection.into_response(),
+        };
+        FileUpload {
+            purpose: query.purpose.clone(),
+            filename: "upload.jsonl".to_string(),
+            content: body.to_vec(),
+        }
+    };
+    let FileUpload {
+        purpose,
+        filename,
+        content,
+    } = upload;
+
+    let purpose = purpose.unwrap_or_else(|| BATCH_FILE_PURPOSE.to_string());
+    if purpose != BATCH_FILE_PURPOSE {
+        return openai_error(BatchError::InvalidRequest(format!(
+            "Unsupported purpose `{}`, only `{}` is supported",
+            purpose, BATCH_FILE_PURPOSE
+        )));
+    }
+
+    match state
+        .batch_manager
+        .store()
+        .create_file(&filename, &purpose, &content)
+    {
+        Ok(file) => Json(file.to_openai_json()).into_response(),
+        Err(e) => openai_error(e),
+    }
+}
+
+/// GET /v1/files
+pub async fn list_files(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<BatchListQuery>,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    if let Err(resp) = check_enabled(&state, BatchKind::OpenAi).await {
+        return resp;
+    }
+
+    match state
+        .batch_manager
+        .store()
+        .list_files(query.purpose.as_deref())
+    {
+        Ok(files) => Json(json!({
+            "object": "list",
+            "data": files.iter().map(|f| f.to_openai_json()).collect::<Vec<_>>(),
+        }))
+        .into_response(),
+        Err(e) => openai_error(e),
+    }
+}
+
+/// GET /v1/files/:id
+pub async fn get_file(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    if let Err(resp) = check_enabled(&state, BatchKind::OpenAi).await {
+        return resp;
+    }
+
+    match state.batch_manager.store().get_file(&id) {
+        Ok(Some(file)) => Json(file.to_openai_json()).into_response(),
+        Ok(None) => openai_error(BatchError::FileNotFound(id)),
+        Err(e) => openai_error(e),
+    }
+}
+
+/// GET /v1/files/:id/content
+pub async fn get_file_content(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    if let Err(resp) = check_enabled(&state, BatchKind::OpenAi).await {
+        return resp;
+    }
+
+    match state.batch_manager.store().get_file_content(&id) {
+        Ok(Some(content)) => (
+            [(header::CONTENT_TYPE, "application/octet-stream")],
+            content,
+        )
+            .into_response(),
+        Ok(None) => openai_error(BatchError::FileNotFound(id)),
+        Err(e) => openai_error(e),
+    }
+}
+
+/// DELETE /v1/files/:id
+pub async fn delete_file(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    if let Err(resp) = check_enabled(&state, BatchKind::OpenAi).await {
+        return resp;
+    }
+
+    match state.batch_manager.store().delete_file(&id) {
+        Ok(true) => Json(json!({"id": id, "object": "file", "deleted": true})).into_response(),
+        Ok(false) => openai_error(BatchError::FileNotFound(id)),
+        Err(e) => openai_error(e),
+    }
+}
+
+// ============================================================================
+// OpenAI Batches
+// ============================================================================
+
+/// POST /v1/batches
+pub async fn create_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<Value>,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    if let Err(resp) = check_enabled(&state, BatchKind::OpenAi).await {
+        return resp;
+    }
+
+    let Some(input_file_id) = payload.get("input_file_id").and_then(|v| v.as_str()) else {
+        return openai_error(BatchError::InvalidRequest(
+            "Missing required parameter: 'input_file_id'".to_string(),
+        ));
+    };
+    let endpoint = payload
+        .get("endpoint")
+        .and_then(|v| v.as_str())
+        .unwrap_or("/v1/chat/completions");
+    if let Some(window) = payload.get("completion_window").and_then(|v| v.as_str()) {
+        if window != "24h" {
+            return openai_error(BatchError::InvalidRequest(format!(
+                "Unsupported completion_window `{}`, only `24h` is supported",
+                window
+            )));
+        }
+    }
+
+    let store = state.batch_manager.store();
+    let content = match store.get_file_content(input_file_id) {
+        Ok(Some(content)) => content,
+        Ok(None) => return openai_error(BatchError::FileNotFound(input_file_id.to_string())),
+        Err(e) => return openai_error(e),
+    };
+    let max_requests = state.batch_manager.config().await.max_requests_per_batch;
+    let result =
+        parse_openai_batch_input(&String::from_utf8_lossy(&content), endpoint, max_requests)
+            .and_then(|requests| {
+                store.create_batch(
+                    BatchKind::OpenAi,
+                    endpoint,
+                    Some(input_file_id),
+                    payload.get("metadata").cloned().filter(|m| !m.is_null()),
+                    requests,
+                )
+            });
+    match result {
+        Ok(job) => {
+            state.batch_manager.notify_new_work();
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[BATCH] created {} with {} requests",
+                    job.id,
+                    job.request_counts.total()
+                ),
+            );
+            Json(job.to_openai_json()).into_response()
+        }
+        Err(e) => openai_error(e),
+    }
+}
+
+/// GET /v1/batches
+pub async fn list_batches(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<BatchListQuery>,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    if let Err(resp) = check_enabled(&state, BatchKind::OpenAi).await {
+        return resp;
+    }
+
+    let limit = query.limit();
+    match state
+        .batch_manager
+        .store()
+        .list_batches(BatchKind::OpenAi, limit, query.after.as_deref())
+    {
+        Ok(jobs) => {
+            let data = jobs.iter().map(|job| job.to_openai_json()).collect();
+            Json(list_response(data, limit, |v| v["id"].clone())).into_response()
+        }
+        Err(e) => openai_error(e),
+    }
+}
+
+/// GET /v1/batches/:id
+pub async fn get_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    if let Err(resp) = check_enabled(&state, BatchKind::OpenAi).await {
+        return resp;
+    }
+
+    match state.batch_manager.store().get_batch(&id) {
+        Ok(Some(job)) if job.kind == BatchKind::OpenAi => {
+            Json(job.to_openai_json()).into_response()
+        }
+        Ok(_) => openai_error(BatchError::BatchNotFound(id)),
+        Err(e) => openai_error(e),
+    }
+}
+
+/// POST /v1/batches/:id/cancel
+pub async fn cancel_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+    if let Err(resp) = check_enabled(&state, BatchKind::OpenAi).await {
+        return resp;
+    }
+
+    let store = state.batch_manager.store();
+    match store.get_batch(&id) {
+        Ok(Some(job)) if job.kind == BatchKind::OpenAi => {}
+        Ok(_) => return openai_error(BatchError::BatchNotFound(id)),
+        Err(e) => return openai_error(e),
+    }
+    match store.cancel_batch(&id) {
+        Ok(job) => Json(job.to_openai_json()).into_response(),
+        Err(e) => openai_error(e),
+    }
+}
+
+// ============================================================================
+// 批处理执行器
+// ============================================================================
+
+/// 基于 AppState 的批处理执行器
+///
+/// 与 `/v1/chat/completions`、`/v1/messages` 使用相同的模型别名解析、参数注入、
+/// 图片预处理和 Provider 调用；凭证从凭证池中选择并跳过处于配额冷却期的凭证。
+pub struct AppStateBatchExecutor {
+    state: AppState,
+}
+
+impl AppStateBatchExecutor {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// 应用参数注入
+    async fn inject<T>(&self, request: T, model: &str) -> T
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        if !*self.state.injection_enabled.read().await {
+            return request;
+        }
+        let injector = self.state.processor.injector.read().await;
+        let mut payload = serde_json::to_value(&request).unwrap_or_default();
+        let result = injector.inject(model, &mut payload);
+        if result.has_injections() {
+            if let Ok(updated) = serde_json::from_value(payload) {
+                return updated;
+            }
+        }
+        request
+    }
+
+    /// 从凭证池选择未处于配额冷却期的凭证
+    ///
+    /// 凭证池查询是同步的 SQLite 调用，在阻塞线程池中执行。
+    /// 所有匹配凭证都在冷却期时返回 [`BatchExecution::Deferred`]
+    async fn select_credential(
+        &self,
+        kind: BatchKind,
+        provider: &str,
+        model: &str,
+    ) -> Result<ProviderCredential, BatchExecution> {
+        let state = &self.state;
+        let Some(db) = state.db.clone() else {
+            return Err(error_execution(
+                kind,
+                503,
+                "Credential pool is not available",
+            ));
+        };
+        let pool_service = state.pool_service.clone();
+        let quota = state.quota_manager.clone();
+        let provider = provider.to_string();
+        let model = model.to_string();
+        let selected = tokio::task::spawn_blocking(move || {
+            let selected =
+                pool_service.select_credential_filtered(&db, &provider, Some(&model), |cred| {
+                    quota.is_available(&cred.uuid)
+                });
+            match selected {
+                Ok(Some(cred)) => Ok(cred),
+                Ok(None) => {
+                    // 存在可用但处于冷却期的凭证时推迟执行
+                    if let Ok(Some(_)) =
+                        pool_service.select_credential(&db, &provider, Some(&model))
+                    {
+                        Err(BatchExecution::Deferred {
+                            until: quota.earliest_recovery(),
+                        })
+                    } else {
+                        Err(error_execution(
+                            kind,
+                            503,
+                            &format!("No available credentials for provider `{}`", provider),
+                        ))
+                    }
+                }
+                Err(e) => Err(error_execution(kind, 500, &e)),
+            }
+        })
+        .await;
+        selected.unwrap_or_else(|e| Err(error_execution(kind, 500, &e.to_string())))
+    }
+
+    async fn execute_openai(&self, body: &Value) -> BatchExecution {
+        let state = &self.state;
+        let mut request: ChatCompletionRequest = match serde_json::from_value(body.clone()) {
+            Ok(request) => request,
+            Err(e) => return error_execution(BatchKind::OpenAi, 400, &e.to_string()),
+        };
+        request.stream = false;
+
+        let mut ctx = RequestContext::new(request.model.clone()).with_stream(false);
+        state.processor.resolve_and_route(&mut ctx).await;
+        request.model = ctx.resolved_model.clone();
+        let mut request = self.inject(request, &ctx.resolved_model).await;
+
+        let provider = state.default_provider.read().await.clone();
+        if requires_inline_images(&provider) {
+            let image_config = state.image_config.read().await.clone();
+            if let Err(e) =
+                prepare_request_images(&mut request, &image_config, &state.image_fetcher).await
+            {
+                return completed(400, e.to_json(), None);
+            }
+        }
+
+        let cred = match self
+            .select_credential(BatchKind::OpenAi, &provider, &request.model)
+            .await
+        {
+            Ok(cred) => cred,
+            Err(execution) => return execution,
+        };
+
+        let queue_class = background_queue_class(state);
+        let response = match ResponseFormat::from_request(&request).filter(|f| f.is_json()) {
+            Some(format) => {
+                call_provider_openai_structured(state, &cred, &request, None, &format, &queue_class)
+                    .await
+            }
+            None => call_provider_openai(state, &cred, &request, None, &queue_class).await,
+        };
+        self.finish(&ctx, response, cred.uuid).await
+    }
+
+    async fn execute_anthropic(&self, body: &Value) -> BatchExecution {
+        let state = &self.state;
+        let mut request: AnthropicMessagesRequest = match serde_json::from_value(body.clone()) {
+            Ok(request) => request,
+            Err(e) => return error_execution(BatchKind::Anthropic, 400, &e.to_string()),
+        };
+        request.stream = false;
+
+        let mut ctx = RequestContext::new(request.model.clone()).with_stream(false);
+        state.processor.resolve_and_route(&mut ctx).await;
+        request.model = ctx.resolved_model.clone();
+        let mut request = self.inject(request, &ctx.resolved_model).await;
+
+        let provider = state.default_provider.read().await.clone();
+        if requires_inline_images(&provider) {
+            let image_config = state.image_config.read().await.clone();
+            if let Err(e) =
+                prepare_anthropic_request_images(&mut request, &image_config, &state.image_fetcher)
+                    .await
+            {
+                return completed(400, e.to_json(), None);
+            }
+        }
+
+        let cred = match self
+            .select_credential(BatchKind::Anthropic, &provider, &request.model)
+            .await
+        {
+            Ok(cred) => cred,
+            Err(execution) => return execution,
+        };
+
+        let queue_class = background_queue_class(state);
+        let response = call_provider_anthropic(state, &cred, &request, None, &queue_class).await;
+        self.finish(&ctx, response, cred.uuid).await
+    }
+
+    /// 记录统计并读取响应体
+    async fn finish(
+        &self,
+        ctx: &RequestContext,
+        response: Response,
+        credential_id: String,
+    ) -> BatchExecution {
+        let status = response.status();
+        let telemetry_status = if status.is_success() {
+            crate::telemetry::RequestStatus::Success
+        } else {
+            crate::telemetry::RequestStatus::Failed
+        };
+        record_request_telemetry(&self.state, ctx, telemetry_status, None);
+
+        let body = match axum::body::to_bytes(response.into_body(), usize::MAX).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned())),
+            Err(e) => Value::String(e.to_string()),
+        };
+        completed(status.as_u16(), body, Some(credential_id))
+    }
+}
+
+#[async_trait]
+impl BatchExecutor for AppStateBatchExecutor {
+    async fn execute(&self, kind: BatchKind, body: &Value) -> BatchExecution {
+        match kind {
+            BatchKind::OpenAi => self.execute_openai(body).await,
+            BatchKind::Anthropic => self.execute_anthropic(body).await,
+        }
+    }
+}
+
+fn completed(status: u16, body: Value, credential_id: Option<String>) -> BatchExecution {
+    BatchExecution::Completed {
+        status,
+        body,
+        credential_id,
+    }
+}
+
+/// 按批处理类型构造错误结果
+fn error_execution(kind: BatchKind, status: u16, message: &str) -> BatchExecution {
+    let body = match kind {
+        BatchKind::Anthropic => json!({
+            "type": "error",
+            "error": {"type": "api_error", "message": message}
+        }),
+        BatchKind::OpenAi => json!({"error": {"message": message}}),
+    };
+    completed(status, body, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    async fn multipart(body: &'static str) -> Multipart {
+        let request = Request::builder()
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=abc123")
+            .body(Body::from(body))
+            .unwrap();
+        Multipart::from_request(request, &()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_read_multipart_upload() {
+        let upload = read_multipart_upload(
+            multipart(concat!(
+                "--abc123\r\n",
+                "Content-Disposition: form-data; name=\"purpose\"\r\n\r\n",
+                "batch\r\n",
+                "--abc123\r\n",
+                "Content-Disposition: form-data; name=\"file\"; filename=\"input.jsonl\"\r\n",
+                "Content-Type: application/octet-stream\r\n\r\n",
+                "{\"a\":1}\n{\"b\":2}\n\r\n",
+                "--abc123--\r\n"
+            ))
+            .await,
+        )
+        .await
+        .unwrap();
+        assert_eq!(upload.purpose.as_deref(), Some("batch"));
+        assert_eq!(upload.filename, "input.jsonl");
+        assert_eq!(upload.content, b"{\"a\":1}\n{\"b\":2}\n");
+    }
+
+    #[tokio::test]
+    async fn test_read_multipart_upload_requires_file() {
+        let result = read_multipart_upload(
+            multipart(concat!(
+                "--abc123\r\n",
+                "Content-Disposition: form-data; name=\"purpose\"\r\n\r\n",
+                "batch\r\n",
+                "--abc123--\r\n"
+            ))
+            .await,
+        )
+        .await;
+        assert!(matches!(result, Err(BatchError::InvalidRequest(_))));
+
+        let result = read_multipart_upload(multipart("garbage").await).await;
+        assert!(matches!(result, Err(BatchError::InvalidRequest(_))));
+    }
+}
@@ -0,0 +1,553 @@
+//! 通用凭证管理API处理器
+//!
+//! 为所有 `PoolProviderType` 提供 `/api/credentials/{provider}/...` 端点：
+//! - 带健康分数的凭证列表与详情
+//! - 智能选择凭证
+//! - 强制刷新 Token、查看 Token 过期时间
+//! - 测试调用（健康检查）
+//! - 启用/禁用、重置计数器
+//! - 手动设置/解除配额冷却
+//!
+//! 健康分数由 `AppState::health_scorers` 中按 Provider 注册的评分器计算。
+//! 端点与管理 API 一起挂载在 `ManagementAuthLayer` 之下，使用管理密钥认证。
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::credential::{QuotaExceededRecord, QuotaForecast};
+use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::database::DbConnection;
+use crate::models::provider_pool_model::{
+    get_oauth_creds_path, CachedTokenInfo, HealthCheckResult, PoolProviderType, ProviderCredential,
+};
+use crate::server::handlers::ApiError;
+use crate::server::AppState;
+use crate::services::credential_health::{HealthScoreInput, AVAILABLE_HEALTH_SCORE};
+use crate::services::token_cache_service::TokenCacheService;
+
+/// 凭证概要（含健康分数）
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolCredentialSummary {
+    /// 凭证UUID
+    pub uuid: String,
+    /// 凭证名称
+    pub name: String,
+    /// Provider 类型
+    pub provider_type: String,
+    /// 是否健康
+    pub is_healthy: bool,
+    /// 是否手动禁用
+    pub is_disabled: bool,
+    /// 是否可用（健康分数超过阈值）
+    pub available: bool,
+    /// 健康状态分数 (0-100)
+    pub health_score: f64,
+    /// 使用的评分器
+    pub scorer: &'static str,
+    /// 使用次数
+    pub usage_count: u64,
+    /// 错误计数
+    pub error_count: u32,
+    /// 最后使用时间
+    pub last_used: Option<DateTime<Utc>>,
+    /// 最后错误信息
+    pub last_error: Option<String>,
+    /// Token过期时间
+    pub expires_at: Option<DateTime<Utc>>,
+    /// 配额冷却结束时间
+    pub cooldown_until: Option<DateTime<Utc>>,
+    /// 上游剩余配额预测（仅支持用量查询的 Provider）
+    pub quota: Option<QuotaForecast>,
+}
+
+/// 凭证列表响应
+#[derive(Debug, Serialize)]
+pub struct PoolCredentialListResponse {
+    /// Provider 类型
+    pub provider: String,
+    /// 凭证列表（按健康分数降序）
+    pub credentials: Vec<PoolCredentialSummary>,
+    /// 总凭证数
+    pub total: usize,
+    /// 可用凭证数
+    pub available: usize,
+    /// 系统状态
+    pub status: String,
+}
+
+/// Token 过期信息
+#[derive(Debug, Serialize)]
+pub struct PoolCredentialTokenInfo {
+    /// 凭证UUID
+    pub uuid: String,
+    /// 是否支持刷新 Token
+    pub refreshable: bool,
+    /// 是否有缓存的 access_token
+    pub has_access_token: bool,
+    /// Token过期时间
+    pub expires_at: Option<DateTime<Utc>>,
+    /// 距离过期的秒数（已过期为负数）
+    pub expires_in_seconds: Option<i64>,
+    /// 最后刷新时间
+    pub last_refresh: Option<DateTime<Utc>>,
+    /// 连续刷新失败次数
+    pub refresh_error_count: u32,
+    /// 最后刷新错误信息
+    pub last_refresh_error: Option<String>,
+}
+
+/// 选择凭证请求参数
+#[derive(Debug, Default, Deserialize)]
+pub struct PoolSelectRequest {
+    /// 指定模型（可选）
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// 刷新凭证响应
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PoolRefreshResponse {
+    /// 凭证UUID
+    pub uuid: String,
+    /// 刷新是否成功
+    pub success: bool,
+    /// 新的过期时间
+    pub new_expires_at: Option<DateTime<Utc>>,
+    /// 错误信息（如果有）
+    pub error: Option<String>,
+}
+
+/// 冷却覆盖请求
+///
+/// `until` 与 `seconds` 二选一，均未提供时使用配额管理器的默认冷却时长。
+#[derive(Debug, Default, Deserialize)]
+pub struct CooldownOverrideRequest {
+    /// 冷却结束时间
+    #[serde(default)]
+    pub until: Option<DateTime<Utc>>,
+    /// 冷却秒数
+    #[serde(default)]
+    pub seconds: Option<i64>,
+    /// 冷却原因
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// 解除冷却响应
+#[derive(Debug, Serialize)]
+pub struct CooldownClearResponse {
+    /// 凭证UUID
+    pub uuid: String,
+    /// 是否确实解除了冷却
+    pub restored: bool,
+}
+
+fn api_error(error: &str, message: String, status_code: u16) -> ApiError {
+    ApiError {
+        error: error.to_string(),
+        message,
+        status_code,
+    }
+}
+
+fn database(state: &AppState) -> Result<&DbConnection, ApiError> {
+    state
+        .db
+        .as_ref()
+        .ok_or_else(|| api_error("database_unavailable", "数据库连接不可用".to_string(), 503))
+}
+
+fn parse_provider(provider: &str) -> Result<PoolProviderType, ApiError> {
+    provider
+        .parse()
+        .map_err(|e: String| api_error("invalid_provider", e, 400))
+}
+
+/// 凭证查询错误
+#[derive(Debug, thiserror::Error)]
+pub enum CredentialLookupError {
+    #[error("数据库锁定失败: {0}")]
+    Lock(String),
+
+    #[error("查询凭证失败: {0}")]
+    Query(#[from] rusqlite::Error),
+
+    #[error("未找到凭证: {0}")]
+    NotFound(String),
+}
+
+/// 按 UUID 读取凭证池中的凭证
+///
+/// 指定 `provider_type` 时类型不匹配视为不存在。管理 API 与命令行直连模式共用。
+pub fn find_pool_credential(
+    db: &DbConnection,
+    provider_type: Option<PoolProviderType>,
+    uuid: &str,
+) -> Result<ProviderCredential, CredentialLookupError> {
+    let conn = db
+        .lock()
+        .map_err(|e| CredentialLookupError::Lock(e.to_string()))?;
+    ProviderPoolDao::get_by_uuid(&conn, uuid)?
+        .filter(|cred| provider_type.is_none_or(|t| cred.provider_type == t))
+        .ok_or_else(|| CredentialLookupError::NotFound(uuid.to_string()))
+}
+
+/// 读取指定 Provider 下的凭证，类型不匹配视为不存在
+fn load_credential(
+    db: &DbConnection,
+    provider_type: PoolProviderType,
+    uuid: &str,
+) -> Result<ProviderCredential, ApiError> {
+    find_pool_credential(db, Some(provider_type), uuid).map_err(|e| match e {
+        CredentialLookupError::Lock(_) => api_error("database_lock_error", e.to_string(), 500),
+        CredentialLookupError::Query(_) => api_error("database_query_error", e.to_string(), 500),
+        CredentialLookupError::NotFound(_) => api_error(
+            "credential_not_found",
+            format!("未找到 {} 凭证: {}", provider_type, uuid),
+            404,
+        ),
+    })
+}
+
+fn cache_status(
+    state: &AppState,
+    db: &DbConnection,
+    uuid: &str,
+) -> Result<Option<CachedTokenInfo>, ApiError> {
+    state
+        .token_cache
+        .get_cache_status(db, uuid)
+        .map_err(|e| api_error("cache_query_error", format!("获取缓存状态失败: {}", e), 500))
+}
+
+fn summarize(
+    state: &AppState,
+    credential: &ProviderCredential,
+    cache: Option<&CachedTokenInfo>,
+) -> PoolCredentialSummary {
+    let cooldown_until = state.quota_manager.get_cooldown_until(&credential.uuid);
+    let input = HealthScoreInput::new(credential, cache).with_cooldown(cooldown_until);
+    let health_score = state.health_scorers.score(&input);
+
+    PoolCredentialSummary {
+        uuid: credential.uuid.clone(),
+        name: credential
+            .name
+            .clone()
+            .unwrap_or_else(|| "未命名".to_string()),
+        provider_type: credential.provider_type.to_string(),
+        is_healthy: credential.is_healthy,
+        is_disabled: credential.is_disabled,
+        available: health_score > AVAILABLE_HEALTH_SCORE,
+        health_score,
+        scorer: state
+            .health_scorers
+            .scorer_for(credential.provider_type)
+            .name(),
+        usage_count: credential.usage_count,
+        error_count: credential.error_count,
+        last_used: credential.last_used,
+        last_error: credential
+            .last_error_message
+            .clone()
+            .or_else(|| cache.and_then(|c| c.last_refresh_error.clone())),
+        expires_at: cache.and_then(|c| c.expiry_time),
+        cooldown_until: input.in_cooldown().then_some(cooldown_until).flatten(),
+        quota: state.usage_quota.forecast(&credential.uuid),
+    }
+}
+
+fn summarize_by_uuid(
+    state: &AppState,
+    db: &DbConnection,
+    provider_type: PoolProviderType,
+    uuid: &str,
+) -> Result<PoolCredentialSummary, ApiError> {
+    let credential = load_credential(db, provider_type, uuid)?;
+    let cache = cache_status(state, db, uuid)?;
+    Ok(summarize(state, &credential, cache.as_ref()))
+}
+
+/// GET /api/credentials/{provider} - 带健康分数的凭证列表
+pub async fn list_pool_credentials(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+) -> Result<Json<PoolCredentialListResponse>, ApiError> {
+    let provider_type = parse_provider(&provider)?;
+    let db = database(&state)?;
+
+    let credentials = {
+        let conn = db
+            .lock()
+            .map_err(|e| api_error("database_lock_error", format!("数据库锁定失败: {}", e), 500))?;
+        ProviderPoolDao::get_by_type(&conn, &provider_type)
+            .map_err(|e| api_error("database_query_error", format!("查询凭证失败: {}", e), 500))?
+    };
+
+    let mut summaries = Vec::with_capacity(credentials.len());
+    for credential in &credentials {
+        let cache = cache_status(&state, db, &credential.uuid)?;
+        summaries.push(summarize(&state, credential, cache.as_ref()));
+    }
+    summaries.sort_by(|a, b| b.health_score.total_cmp(&a.health_score));
+
+    let available = summaries.iter().filter(|s| s.available).count();
+    Ok(Json(PoolCredentialListResponse {
+        provider: provider_type.to_string(),
+        total: summaries.len(),
+        available,
+        status: if available > 0 { "healthy" } else { "degraded" }.to_string(),
+        credentials: summaries,
+    }))
+}
+
+/// POST /api/credentials/{provider}/select - 智能选择凭证（跳过冷却中的凭证）
+pub async fn select_pool_credential(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    request: Option<Json<PoolSelectRequest>>,
+) -> Result<Json<PoolCredentialSummary>, ApiError> {
+    let provider_type = parse_provider(&provider)?;
+    let db = database(&state)?;
+    let request = request.map(|Json(r)| r).unwrap_or_default();
+
+    let quota = state.quota_manager.clone();
+    let credential = state
+        .pool_service
+        .select_credential_filtered(
+            db,
+            &provider_type.to_string(),
+            request.model.as_deref(),
+            |c| quota.is_available(&c.uuid),
+        )
+        .map_err(|e| api_error("selection_error", format!("凭证选择失败: {}", e), 500))?
+        .ok_or_else(|| {
+            api_error(
+                "no_available_credentials",
+                format!("没有可用的 {} 凭证", provider_type),
+                503,
+            )
+        })?;
+
+    let cache = cache_status(&state, db, &credential.uuid)?;
+    Ok(Json(summarize(&state, &credential, cache.as_ref())))
+}
+
+/// GET /api/credentials/{provider}/{uuid} - 凭证详情
+pub async fn get_pool_credential(
+    State(state): State<AppState>,
+    Path((provider, uuid)): Path<(String, String)>,
+) -> Result<Json<PoolCredentialSummary>, ApiError> {
+    let provider_type = parse_provider(&provider)?;
+    let db = database(&state)?;
+    summarize_by_uuid(&state, db, provider_type, &uuid).map(Json)
+}
+
+/// GET /api/credentials/{provider}/{uuid}/token - Token 过期信息
+pub async fn get_pool_credential_token(
+    State(state): State<AppState>,
+    Path((provider, uuid)): Path<(String, String)>,
+) -> Result<Json<PoolCredentialTokenInfo>, ApiError> {
+    let provider_type = parse_provider(&provider)?;
+    let db = database(&state)?;
+    let credential = load_credential(db, provider_type, &uuid)?;
+    let cache = cache_status(&state, db, &uuid)?.unwrap_or_default();
+
+    Ok(Json(PoolCredentialTokenInfo {
+        uuid,
+        refreshable: get_oauth_creds_path(&credential.credential).is_some(),
+        has_access_token: cache.access_token.is_some(),
+        expires_at: cache.expiry_time,
+        expires_in_seconds: cache
+            .expiry_time
+            .map(|expiry| (expiry - Utc::now()).num_seconds()),
+        last_refresh: cache.last_refresh,
+        refresh_error_count: cache.refresh_error_count,
+        last_refresh_error: cache.last_refresh_error,
+    }))
+}
+
+/// POST /api/credentials/{provider}/{uuid}/refresh - 强制刷新 Token
+pub async fn refresh_pool_credential(
+    State(state): State<AppState>,
+    Path((provider, uuid)): Path<(String, String)>,
+) -> Result<Json<PoolRefreshResponse>, ApiError> {
+    let provider_type = parse_provider(&provider)?;
+    let db = database(&state)?;
+    let credential = load_credential(db, provider_type, &uuid)?;
+
+    if get_oauth_creds_path(&credential.credential).is_none() {
+        return Err(api_error(
+            "refresh_not_supported",
+            format!("{} 凭证不支持 Token 刷新", provider_type),
+            400,
+        ));
+    }
+
+    tracing::info!("[CREDENTIAL_API] 刷新凭证: {} ({})", uuid, provider_type);
+
+    let result = if TokenCacheService::supports_refresh(provider_type) {
+        let events =
+            (provider_type == PoolProviderType::Kiro).then(|| state.kiro_event_service.clone());
+        state
+            .token_cache
+            .refresh_and_cache_with_events(db, &uuid, true, events)
+            .await
+    } else {
+        state.pool_service.refresh_credential_token(db, &uuid).await
+    };
+
+    let response = match result {
+        Ok(_) => PoolRefreshResponse {
+            new_expires_at: cache_status(&state, db, &uuid)?.and_then(|c| c.expiry_time),
+            uuid,
+            success: true,
+            error: None,
+        },
+        Err(e) => {
+            tracing::warn!("[CREDENTIAL_API] 凭证刷新失败: {}: {}", uuid, e);
+            PoolRefreshResponse {
+                uuid,
+                success: false,
+                new_expires_at: None,
+                error: Some(e),
+            }
+        }
+    };
+    Ok(Json(response))
+}
+
+/// POST /api/credentials/{provider}/{uuid}/test - 发起一次测试调用
+pub async fn test_pool_credential(
+    State(state): State<AppState>,
+    Path((provider, uuid)): Path<(String, String)>,
+) -> Result<Json<HealthCheckResult>, ApiError> {
+    let provider_type = parse_provider(&provider)?;
+    let db = database(&state)?;
+    load_credential(db, provider_type, &uuid)?;
+
+    state
+        .pool_service
+        .check_credential_health(db, &uuid)
+        .await
+        .map(Json)
+        .map_err(|e| api_error("test_failed", e, 500))
+}
+
+async fn set_disabled(
+    state: AppState,
+    provider: String,
+    uuid: String,
+    disabled: bool,
+) -> Result<Json<PoolCredentialSummary>, ApiError> {
+    let provider_type = parse_provider(&provider)?;
+    let db = database(&state)?;
+    load_credential(db, provider_type, &uuid)?;
+
+    let credential = state
+        .pool_service
+        .update_credential(db, &uuid, None, Some(disabled), None, None, None, None)
+        .map_err(|e| api_error("update_failed", e, 500))?;
+    tracing::info!(
+        "[CREDENTIAL_API] 凭证 {} 已{}",
+        uuid,
+        if disabled { "禁用" } else { "启用" }
+    );
+
+    let cache = cache_status(&state, db, &uuid)?;
+    Ok(Json(summarize(&state, &credential, cache.as_ref())))
+}
+
+/// POST /api/credentials/{provider}/{uuid}/enable - 启用凭证
+pub async fn enable_pool_credential(
+    State(state): State<AppState>,
+    Path((provider, uuid)): Path<(String, String)>,
+) -> Result<Json<PoolCredentialSummary>, ApiError> {
+    set_disabled(state, provider, uuid, false).await
+}
+
+/// POST /api/credentials/{provider}/{uuid}/disable - 禁用凭证
+pub async fn disable_pool_credential(
+    State(state): State<AppState>,
+    Path((provider, uuid)): Path<(String, String)>,
+) -> Result<Json<PoolCredentialSummary>, ApiError> {
+    set_disabled(state, provider, uuid, true).await
+}
+
+/// POST /api/credentials/{provider}/{uuid}/reset - 重置使用/错误/刷新失败计数
+pub async fn reset_pool_credential(
+    State(state): State<AppState>,
+    Path((provider, uuid)): Path<(String, String)>,
+) -> Result<Json<PoolCredentialSummary>, ApiError> {
+    let provider_type = parse_provider(&provider)?;
+    let db = database(&state)?;
+    load_credential(db, provider_type, &uuid)?;
+
+    {
+        let conn = db
+            .lock()
+            .map_err(|e| api_error("database_lock_error", format!("数据库锁定失败: {}", e), 500))?;
+        ProviderPoolDao::reset_counters(&conn, &uuid)
+            .and_then(|_| ProviderPoolDao::reset_token_refresh_errors(&conn, &uuid))
+            .map_err(|e| api_error("update_failed", format!("重置计数失败: {}", e), 500))?;
+    }
+    tracing::info!("[CREDENTIAL_API] 已重置凭证计数: {}", uuid);
+
+    summarize_by_uuid(&state, db, provider_type, &uuid).map(Json)
+}
+
+/// PUT /api/credentials/{provider}/{uuid}/cooldown - 手动设置配额冷却
+pub async fn set_pool_credential_cooldown(
+    State(state): State<AppState>,
+    Path((provider, uuid)): Path<(String, String)>,
+    request: Option<Json<CooldownOverrideRequest>>,
+) -> Result<Json<QuotaExceededRecord>, ApiError> {
+    let provider_type = parse_provider(&provider)?;
+    let db = database(&state)?;
+    load_credential(db, provider_type, &uuid)?;
+    let request = request.map(|Json(r)| r).unwrap_or_default();
+
+    let until = match (request.until, request.seconds) {
+        (Some(_), Some(_)) => {
+            return Err(api_error(
+                "invalid_request",
+                "until 与 seconds 只能指定一个".to_string(),
+                400,
+            ))
+        }
+        (Some(until), None) => until,
+        (None, Some(seconds)) if seconds > 0 => Utc::now() + Duration::seconds(seconds),
+        (None, Some(_)) => {
+            return Err(api_error(
+                "invalid_request",
+                "seconds 必须为正数".to_string(),
+                400,
+            ))
+        }
+        (None, None) => Utc::now() + state.quota_manager.cooldown_duration(),
+    };
+
+    let reason = request
+        .reason
+        .unwrap_or_else(|| "manual override".to_string());
+    Ok(Json(
+        state.quota_manager.override_cooldown(&uuid, until, &reason),
+    ))
+}
+
+/// DELETE /api/credentials/{provider}/{uuid}/cooldown - 解除配额冷却
+pub async fn clear_pool_credential_cooldown(
+    State(state): State<AppState>,
+    Path((provider, uuid)): Path<(String, String)>,
+) -> Result<Json<CooldownClearResponse>, ApiError> {
+    let provider_type = parse_provider(&provider)?;
+    let db = database(&state)?;
+    load_credential(db, provider_type, &uuid)?;
+
+    let restored = state.quota_manager.restore_credential(&uuid);
+    Ok(Json(CooldownClearResponse { uuid, restored }))
+}
@@ -0,0 +1,100 @@
+//! 数据集导出管理 API 处理器
+//!
+//! `POST /v0/management/flows/dataset`：把内存中的 Flow 按过滤表达式、质量条件
+//! 导出为微调 / 评测数据集。可通过 `flow_ids` 限定范围（例如某个会话的 Flow）。
+
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::flow_monitor::{DatasetExporter, DatasetOptions, FlowFilter, LLMFlow};
+use crate::server::AppState;
+
+/// 数据集导出请求体
+#[derive(Debug, Default, Deserialize)]
+pub struct DatasetExportRequest {
+    /// 仅导出这些 Flow（按给定顺序）
+    #[serde(default)]
+    pub flow_ids: Option<Vec<String>>,
+    /// 导出选项
+    #[serde(flatten)]
+    pub options: DatasetOptions,
+}
+
+/// 数据集导出查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct DatasetExportQuery {
+    /// `train` 或 `validation`：直接返回对应划分的 JSONL
+    #[serde(default)]
+    pub split: Option<String>,
+}
+
+/// POST /v0/management/flows/dataset - 导出数据集
+///
+/// 默认返回 JSON（统计信息和两个划分）；带 `?split=train|validation` 时返回 JSONL。
+pub async fn management_export_dataset(
+    State(state): State<AppState>,
+    Query(query): Query<DatasetExportQuery>,
+    Json(request): Json<DatasetExportRequest>,
+) -> Response {
+    let flows: Vec<LLMFlow> = {
+        let store = state.flow_monitor.memory_store();
+        let store = store.read().await;
+        let mut flows = store.query(&FlowFilter::default());
+        // 内存存储按时间倒序返回，数据集按时间正序排列
+        flows.reverse();
+        match request.flow_ids {
+            Some(ids) => {
+                let order: HashMap<&str, usize> = ids
+                    .iter()
+                    .enumerate()
+                    .map(|(i, id)| (id.as_str(), i))
+                    .collect();
+                let mut selected: Vec<LLMFlow> = flows
+                    .into_iter()
+                    .filter(|f| order.contains_key(f.id.as_str()))
+                    .collect();
+                selected.sort_by_key(|f| order[f.id.as_str()]);
+                selected
+            }
+            None => flows,
+        }
+    };
+
+    let export = match DatasetExporter::new(request.options).export(&flows) {
+        Ok(export) => export,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": {"type": "invalid_filter", "message": e.to_string()}
+                })),
+            )
+                .into_response()
+        }
+    };
+
+    let body = match query.split.as_deref() {
+        None => return Json(export).into_response(),
+        Some("train") => export.train_jsonl(),
+        Some("validation") => export.validation_jsonl(),
+        Some(other) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": {
+                        "type": "invalid_request",
+                        "message": format!("未知的数据集划分: {}", other),
+                    }
+                })),
+            )
+                .into_response()
+        }
+    };
+    ([(header::CONTENT_TYPE, "application/x-ndjson")], body).into_response()
+}
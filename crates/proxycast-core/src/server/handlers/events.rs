@@ -0,0 +1,159 @@
+//! 管理事件流 API 处理器
+//!
+//! - `GET /v0/management/events`：以 Server-Sent Events 推送统一事件总线上的事件
+//!
+//! 查询参数 `topics` 为逗号分隔的主题列表（flow、kiro、pool、quota、config、failover、plugin），
+//! 省略时订阅全部主题。断线重连时通过 `Last-Event-ID` 请求头（或 `last_event_id` 查询参数）
+//! 从重放缓冲区补齐错过的事件。
+
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::server::AppState;
+use crate::services::event_bus::{BusEvent, TopicFilter};
+
+/// SSE 保活注释的发送间隔
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// 事件流查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct EventStreamQuery {
+    /// 逗号分隔的主题列表
+    #[serde(default)]
+    pub topics: Option<String>,
+    /// 续传起点（`Last-Event-ID` 请求头优先）
+    #[serde(default)]
+    pub last_event_id: Option<u64>,
+}
+
+fn events_error(status: StatusCode, error_type: &str, message: String) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {"type": error_type, "message": message}
+        })),
+    )
+        .into_response()
+}
+
+/// 将总线事件编码为 SSE 帧
+fn sse_frame(event: &BusEvent) -> String {
+    let data = serde_json::to_string(event).unwrap_or_else(|_| "{}".to_string());
+    format!(
+        "id: {}\nevent: {}\ndata: {}\n\n",
+        event.id,
+        event.event.event_type(),
+        data
+    )
+}
+
+/// 编码事件缺失通知（不带 `id`，不影响客户端的续传位置）
+fn gap_frame(reason: &str, missed: Option<u64>) -> String {
+    let data = serde_json::json!({ "reason": reason, "missed": missed });
+    format!("event: gap\ndata: {}\n\n", data)
+}
+
+/// GET /v0/management/events - 管理事件流（SSE）
+pub async fn management_event_stream(
+    State(state): State<AppState>,
+    Query(query): Query<EventStreamQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let filter = match query.topics.as_deref() {
+        Some(spec) => match TopicFilter::parse(spec) {
+            Ok(filter) => filter,
+            Err(e) => return events_error(StatusCode::BAD_REQUEST, "invalid_request", e),
+        },
+        None => TopicFilter::all(),
+    };
+
+    let last_event_id = match headers.get("last-event-id") {
+        Some(value) => match value
+            .to_str()
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+        {
+            Some(id) => Some(id),
+            None => {
+                return events_error(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_request",
+                    "Last-Event-ID 必须是非负整数".to_string(),
+                )
+            }
+        },
+        None => query.last_event_id,
+    };
+
+    let subscription = state.event_bus.subscribe_after(last_event_id, &filter);
+    tracing::info!(
+        "[EVENTS] 新订阅: last_event_id={:?}, replay={}, truncated={}",
+        last_event_id,
+        subscription.replay.len(),
+        subscription.truncated
+    );
+
+    let stream = async_stream::stream! {
+        // 告知客户端断线后的重连间隔
+        yield Ok::<_, std::convert::Infallible>("retry: 3000\n\n".to_string());
+
+        if subscription.truncated {
+            yield Ok(gap_frame("replay_truncated", None));
+        }
+        let mut last_sent = last_event_id.unwrap_or(0);
+        for event in &subscription.replay {
+            last_sent = event.id;
+            yield Ok(sse_frame(event));
+        }
+
+        let mut receiver = subscription.receiver;
+        let mut keepalive = tokio::time::interval(KEEPALIVE_INTERVAL);
+        keepalive.reset();
+        loop {
+            tokio::select! {
+                received = receiver.recv() => match received {
+                    Ok(event) => {
+                        // 重放阶段已发送过的事件不再重复发送
+                        if event.id <= last_sent || !filter.matches(event.topic) {
+                            continue;
+                        }
+                        last_sent = event.id;
+                        yield Ok(sse_frame(&event));
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!("[EVENTS] 订阅者落后，丢弃 {} 个事件", missed);
+                        yield Ok(gap_frame("lagged", Some(missed)));
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = keepalive.tick() => {
+                    yield Ok(": keepalive\n\n".to_string());
+                }
+            }
+        }
+    };
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::CONNECTION, "keep-alive")
+        .header("X-Accel-Buffering", "no")
+        .body(Body::from_stream(stream))
+        .unwrap_or_else(|_| {
+            events_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+                "Failed to build event stream".to_string(),
+            )
+        })
+}
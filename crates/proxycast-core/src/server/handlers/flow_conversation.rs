@@ -0,0 +1,157 @@
+//! Flow 对话谱系 API 处理器
+//!
+//! - `GET /v0/management/flows/conversations`：把最近的 Flow 重建为对话树
+//! - `GET /v0/management/flows/:id/conversation`：包含指定 Flow 的对话树
+//!
+//! 启用文件存储时同时查询内存和文件，否则只使用内存中的 Flow。
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::flow_monitor::{
+    session_key, ConversationOptions, ConversationReconstructor, FilterParser, FlowFilter,
+    FlowQueryService, QueryWithExpressionError,
+};
+use crate::server::AppState;
+
+/// 对话查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct FlowConversationQuery {
+    /// 过滤表达式（仅用于列表接口）
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// 参与重建的最近 Flow 数
+    #[serde(default = "default_conversation_limit")]
+    pub limit: usize,
+}
+
+fn default_conversation_limit() -> usize {
+    500
+}
+
+fn conversation_error(status: StatusCode, error_type: &str, message: String) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {"type": error_type, "message": message}
+        })),
+    )
+        .into_response()
+}
+
+/// GET /v0/management/flows/conversations - 重建对话树
+pub async fn management_list_conversations(
+    State(state): State<AppState>,
+    Query(query): Query<FlowConversationQuery>,
+) -> Response {
+    let filter = query.filter.as_deref().filter(|f| !f.trim().is_empty());
+    let options = ConversationOptions::default();
+
+    let result = match state.flow_monitor.file_store() {
+        Some(file_store) => {
+            FlowQueryService::new(state.flow_monitor.memory_store(), file_store)
+                .reconstruct_conversations(filter, query.limit, options)
+                .await
+        }
+        None => {
+            let filter_fn = match filter.map(FilterParser::parse).transpose() {
+                Ok(expr) => expr.map(|e| FilterParser::compile(&e)),
+                Err(e) => {
+                    return conversation_error(
+                        StatusCode::BAD_REQUEST,
+                        "invalid_filter",
+                        e.to_string(),
+                    )
+                }
+            };
+            let mut flows = state
+                .flow_monitor
+                .memory_store()
+                .read()
+                .await
+                .query(&FlowFilter::default());
+            if let Some(filter_fn) = filter_fn {
+                flows.retain(|f| filter_fn(f));
+            }
+            flows.truncate(query.limit);
+            let mut conversations = ConversationReconstructor::new(options).reconstruct(&flows);
+            conversations.sort_by_key(|c| std::cmp::Reverse(c.ended_at));
+            Ok(conversations)
+        }
+    };
+
+    match result {
+        Ok(conversations) => Json(serde_json::json!({
+            "total": conversations.len(),
+            "conversations": conversations,
+        }))
+        .into_response(),
+        Err(QueryWithExpressionError::ParseError(e)) => {
+            conversation_error(StatusCode::BAD_REQUEST, "invalid_filter", e.to_string())
+        }
+        Err(e) => conversation_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            e.to_string(),
+        ),
+    }
+}
+
+/// GET /v0/management/flows/:id/conversation - 包含指定 Flow 的对话树
+pub async fn management_get_flow_conversation(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<FlowConversationQuery>,
+) -> Response {
+    let options = ConversationOptions::default();
+
+    let result = match state.flow_monitor.file_store() {
+        Some(file_store) => {
+            FlowQueryService::new(state.flow_monitor.memory_store(), file_store)
+                .get_conversation(&id, query.limit, options)
+                .await
+        }
+        None => {
+            let flows = state
+                .flow_monitor
+                .memory_store()
+                .read()
+                .await
+                .query(&FlowFilter::default());
+            let key = flows.iter().find(|f| f.id == id).map(session_key);
+            let flows: Vec<_> = match key {
+                Some(key) => flows
+                    .into_iter()
+                    .filter(|f| session_key(f) == key)
+                    .enumerate()
+                    .filter(|(i, f)| *i < query.limit || f.id == id)
+                    .map(|(_, f)| f)
+                    .collect(),
+                None => Vec::new(),
+            };
+            Ok(ConversationReconstructor::new(options)
+                .reconstruct(&flows)
+                .into_iter()
+                .find(|c| c.contains(&id)))
+        }
+    };
+
+    match result {
+        Ok(Some(conversation)) => Json(conversation).into_response(),
+        Ok(None) => conversation_error(
+            StatusCode::NOT_FOUND,
+            "flow_not_found",
+            format!("Flow 不存在: {}", id),
+        ),
+        Err(e) => conversation_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            e.to_string(),
+        ),
+    }
+}
@@ -0,0 +1,85 @@
+//! Flow 导入管理 API 处理器
+//!
+//! `POST /v0/management/flows/import`：把 HAR 或 JSONL 抓包导入 Flow 文件存储。
+//! 请求体为原始文件内容，导入后可通过查询、搜索、对比和重放使用。
+
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::flow_monitor::{FlowImporter, ImportError, ImportFormat, ImportOptions};
+use crate::server::AppState;
+
+/// Flow 导入查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct FlowImportQuery {
+    /// `har` 或 `jsonl`，省略时根据内容推断
+    #[serde(default)]
+    pub format: Option<ImportFormat>,
+    /// 来源名称（标签 `import:<来源>`）
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+fn import_error(status: StatusCode, error_type: &str, message: String) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {"type": error_type, "message": message}
+        })),
+    )
+        .into_response()
+}
+
+/// POST /v0/management/flows/import - 导入 HAR / JSONL
+pub async fn management_import_flows(
+    State(state): State<AppState>,
+    Query(query): Query<FlowImportQuery>,
+    body: Bytes,
+) -> Response {
+    let Some(store) = state.flow_monitor.file_store() else {
+        return import_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "file_store_unavailable",
+            "Flow 文件存储未启用".to_string(),
+        );
+    };
+    let data = match String::from_utf8(body.to_vec()) {
+        Ok(data) => data,
+        Err(e) => {
+            return import_error(
+                StatusCode::BAD_REQUEST,
+                "invalid_request",
+                format!("请求体不是 UTF-8 文本: {}", e),
+            )
+        }
+    };
+
+    let format = query.format.unwrap_or_else(|| ImportFormat::detect(&data));
+    let importer = FlowImporter::new(ImportOptions {
+        source: query.source,
+    });
+    let result = tokio::task::spawn_blocking(move || importer.import(&store, &data, format)).await;
+
+    match result {
+        Ok(Ok(result)) => Json(result).into_response(),
+        Ok(Err(e @ (ImportError::Json(_) | ImportError::InvalidHar(_)))) => {
+            import_error(StatusCode::BAD_REQUEST, "invalid_request", e.to_string())
+        }
+        Ok(Err(e)) => import_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            e.to_string(),
+        ),
+        Err(e) => import_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            e.to_string(),
+        ),
+    }
+}
@@ -0,0 +1,216 @@
+//! Flow 查询与导出管理 API 处理器
+//!
+//! - `GET /v0/management/flows`：按过滤表达式查询最近的 Flow
+//! - `GET /v0/management/flows/export`：导出为 HAR（或 JSON / JSONL / Markdown / CSV）
+//! - `GET /v0/management/flows/stats`：指定时间之后的请求统计
+//!
+//! 启用文件存储时合并内存缓存与文件存储，否则只查询内存缓存。
+
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+
+use crate::flow_monitor::{
+    ExportFormat, ExportOptions, ExportResult, FilterParser, FlowExporter, FlowFilter,
+    FlowQueryService, LLMFlow, QueryWithExpressionError, TimeRange,
+};
+use crate::server::AppState;
+
+/// Flow 查询参数
+#[derive(Debug, Deserialize)]
+pub struct FlowQueryParams {
+    /// 过滤表达式（见 `FilterParser`）
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// 最多返回的 Flow 数
+    #[serde(default = "default_flow_limit")]
+    pub limit: usize,
+}
+
+/// Flow 导出参数
+#[derive(Debug, Deserialize)]
+pub struct FlowExportParams {
+    /// 导出格式（默认 HAR）
+    #[serde(default)]
+    pub format: Option<ExportFormat>,
+    /// 过滤表达式
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// 最多导出的 Flow 数
+    #[serde(default = "default_flow_limit")]
+    pub limit: usize,
+    /// 是否脱敏 API Key 等敏感数据
+    #[serde(default)]
+    pub redact: bool,
+}
+
+/// Flow 统计参数
+#[derive(Debug, Deserialize)]
+pub struct FlowStatsParams {
+    /// 统计起始时间（默认最近 24 小时）
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+}
+
+fn default_flow_limit() -> usize {
+    100
+}
+
+fn flow_query_error(status: StatusCode, error_type: &str, message: String) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {"type": error_type, "message": message}
+        })),
+    )
+        .into_response()
+}
+
+/// 最近的 Flow（按创建时间降序）
+async fn recent_flows(
+    state: &AppState,
+    filter: Option<&str>,
+    limit: usize,
+) -> Result<Vec<LLMFlow>, Response> {
+    let result = match state.flow_monitor.file_store() {
+        Some(file_store) => {
+            FlowQueryService::new(state.flow_monitor.memory_store(), file_store)
+                .recent_flows(filter, limit)
+                .await
+        }
+        None => {
+            let filter_fn = match filter
+                .filter(|f| !f.trim().is_empty())
+                .map(FilterParser::parse)
+                .transpose()
+            {
+                Ok(expr) => expr.map(|e| FilterParser::compile(&e)),
+                Err(e) => {
+                    return Err(flow_query_error(
+                        StatusCode::BAD_REQUEST,
+                        "invalid_filter",
+                        e.to_string(),
+                    ))
+                }
+            };
+            let mut flows = state
+                .flow_monitor
+                .memory_store()
+                .read()
+                .await
+                .query(&FlowFilter::default());
+            if let Some(filter_fn) = filter_fn {
+                flows.retain(|f| filter_fn(f));
+            }
+            flows.sort_by_key(|f| std::cmp::Reverse(f.timestamps.created));
+            flows.truncate(limit);
+            Ok(flows)
+        }
+    };
+
+    result.map_err(|e| match e {
+        QueryWithExpressionError::ParseError(e) => {
+            flow_query_error(StatusCode::BAD_REQUEST, "invalid_filter", e.to_string())
+        }
+        e => flow_query_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            e.to_string(),
+        ),
+    })
+}
+
+/// GET /v0/management/flows - 按过滤表达式查询 Flow
+pub async fn management_query_flows(
+    State(state): State<AppState>,
+    Query(params): Query<FlowQueryParams>,
+) -> Response {
+    match recent_flows(&state, params.filter.as_deref(), params.limit).await {
+        Ok(flows) => Json(serde_json::json!({
+            "total": flows.len(),
+            "flows": flows,
+        }))
+        .into_response(),
+        Err(response) => response,
+    }
+}
+
+/// GET /v0/management/flows/export - 导出 Flow
+pub async fn management_export_flows(
+    State(state): State<AppState>,
+    Query(params): Query<FlowExportParams>,
+) -> Response {
+    let mut flows = match recent_flows(&state, params.filter.as_deref(), params.limit).await {
+        Ok(flows) => flows,
+        Err(response) => return response,
+    };
+    // 导出文件按时间正序排列
+    flows.reverse();
+
+    let format = params.format.unwrap_or(ExportFormat::HAR);
+    let exporter = FlowExporter::new(ExportOptions {
+        format,
+        redact_sensitive: params.redact,
+        ..Default::default()
+    });
+    let content_type = match format {
+        ExportFormat::HAR | ExportFormat::JSON => "application/json",
+        ExportFormat::JSONL => "application/x-ndjson",
+        ExportFormat::Markdown => "text/markdown; charset=utf-8",
+        ExportFormat::CSV => "text/csv; charset=utf-8",
+    };
+    let body = match exporter.export(&flows) {
+        result @ (ExportResult::Har(_) | ExportResult::Json(_)) => result.to_string_pretty(),
+        ExportResult::Text(text) => text,
+    };
+    ([(header::CONTENT_TYPE, content_type)], body).into_response()
+}
+
+/// GET /v0/management/flows/stats - 请求统计
+pub async fn management_get_flow_stats(
+    State(state): State<AppState>,
+    Query(params): Query<FlowStatsParams>,
+) -> Response {
+    let since = params
+        .since
+        .unwrap_or_else(|| Utc::now() - Duration::hours(24));
+
+    let result = match state.flow_monitor.file_store() {
+        Some(file_store) => {
+            FlowQueryService::new(state.flow_monitor.memory_store(), file_store)
+                .get_stats_since(since)
+                .await
+        }
+        None => {
+            let filter = FlowFilter {
+                time_range: Some(TimeRange::new(Some(since), None)),
+                ..Default::default()
+            };
+            let flows = state
+                .flow_monitor
+                .memory_store()
+                .read()
+                .await
+                .query(&filter);
+            Ok(FlowQueryService::calculate_stats(&flows))
+        }
+    };
+
+    match result {
+        Ok(stats) => Json(serde_json::json!({
+            "since": since,
+            "stats": stats,
+        }))
+        .into_response(),
+        Err(e) => flow_query_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            e.to_string(),
+        ),
+    }
+}
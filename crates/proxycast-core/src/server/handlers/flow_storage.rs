@@ -0,0 +1,107 @@
+//! Flow 存储管理 API 处理器
+//!
+//! - `GET /v0/management/flows/storage`：热存储（JSONL）和冷存储（压缩归档）用量
+//! - `POST /v0/management/flows/storage/archive`：立即把旧文件压缩归档
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::server::AppState;
+
+/// 归档查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct FlowArchiveQuery {
+    /// 归档早于该天数的日期目录（默认使用存储的保留天数）
+    #[serde(default)]
+    pub older_than_days: Option<u32>,
+}
+
+fn storage_error(status: StatusCode, error_type: &str, message: String) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {"type": error_type, "message": message}
+        })),
+    )
+        .into_response()
+}
+
+fn file_store_unavailable() -> Response {
+    storage_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "file_store_unavailable",
+        "Flow 文件存储未启用".to_string(),
+    )
+}
+
+/// GET /v0/management/flows/storage - 分层存储用量
+pub async fn management_get_flow_storage(State(state): State<AppState>) -> Response {
+    let Some(store) = state.flow_monitor.file_store() else {
+        return file_store_unavailable();
+    };
+    let result = tokio::task::spawn_blocking(move || {
+        store.storage_usage().map(|usage| {
+            serde_json::json!({
+                "usage": usage,
+                "rotation": store.rotation_config(),
+            })
+        })
+    })
+    .await;
+
+    match result {
+        Ok(Ok(body)) => Json(body).into_response(),
+        Ok(Err(e)) => storage_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            e.to_string(),
+        ),
+        Err(e) => storage_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            e.to_string(),
+        ),
+    }
+}
+
+/// POST /v0/management/flows/storage/archive - 立即归档旧文件
+pub async fn management_archive_flows(
+    State(state): State<AppState>,
+    Query(query): Query<FlowArchiveQuery>,
+) -> Response {
+    let Some(store) = state.flow_monitor.file_store() else {
+        return file_store_unavailable();
+    };
+    let days = query
+        .older_than_days
+        .unwrap_or(store.rotation_config().retention_days);
+    let before = Utc::now() - chrono::Duration::days(days as i64);
+    let result = tokio::task::spawn_blocking(move || store.archive(before)).await;
+
+    match result {
+        Ok(Ok(result)) => {
+            tracing::info!(
+                "[FLOW_STORE] 手动归档 {} 个文件（{} 个 Flow）",
+                result.files_archived,
+                result.flows_archived
+            );
+            Json(result).into_response()
+        }
+        Ok(Err(e)) => storage_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            e.to_string(),
+        ),
+        Err(e) => storage_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            e.to_string(),
+        ),
+    }
+}
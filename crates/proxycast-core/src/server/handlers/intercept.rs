@@ -0,0 +1,179 @@
+//! Flow 拦截器管理 API 处理器
+//!
+//! 为无界面部署提供断点调试能力：配置拦截规则、查看等待中的 Flow、
+//! 编辑后继续或取消。实时事件通过 WebSocket `subscribe_intercept_events` 推送。
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::flow_monitor::{
+    InterceptConfig, InterceptType, InterceptorError, LLMRequest, LLMResponse, ModifiedData,
+};
+use crate::server::AppState;
+
+/// 继续 Flow 的请求体
+///
+/// 两个字段都为空时按原样继续；字段须与 Flow 的拦截类型一致。
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContinueInterceptRequest {
+    /// 修改后的请求（拦截请求时）
+    #[serde(default)]
+    pub request: Option<LLMRequest>,
+    /// 修改后的响应（拦截响应时）
+    #[serde(default)]
+    pub response: Option<LLMResponse>,
+}
+
+fn intercept_error(status: StatusCode, error_type: &str, message: String) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {"type": error_type, "message": message}
+        })),
+    )
+        .into_response()
+}
+
+fn interceptor_error(e: InterceptorError) -> Response {
+    let (status, error_type) = match &e {
+        InterceptorError::FlowNotFound(_) => (StatusCode::NOT_FOUND, "not_found"),
+        InterceptorError::InvalidFilterExpr(_) => (StatusCode::BAD_REQUEST, "invalid_filter"),
+        InterceptorError::AlreadyCompleted(_) => (StatusCode::CONFLICT, "already_completed"),
+        InterceptorError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+    };
+    intercept_error(status, error_type, e.to_string())
+}
+
+/// GET /v0/management/intercept/config - 获取拦截配置
+pub async fn management_get_intercept_config(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.flow_interceptor.config().await)
+}
+
+/// PUT /v0/management/intercept/config - 更新拦截配置
+///
+/// `filter_expr` 使用 Flow Monitor 过滤表达式语法（如 `~m claude* & ~p kiro`）。
+pub async fn management_update_intercept_config(
+    State(state): State<AppState>,
+    Json(config): Json<InterceptConfig>,
+) -> Response {
+    match state.flow_interceptor.update_config(config).await {
+        Ok(()) => {
+            let config = state.flow_interceptor.config().await;
+            tracing::info!(
+                "[INTERCEPT] 配置已更新: enabled={}, filter={:?}",
+                config.enabled,
+                config.filter_expr
+            );
+            Json(config).into_response()
+        }
+        Err(e) => interceptor_error(e),
+    }
+}
+
+/// GET /v0/management/intercept/flows - 获取等待中的 Flow
+pub async fn management_list_intercepted_flows(State(state): State<AppState>) -> impl IntoResponse {
+    let mut flows = state.flow_interceptor.list_intercepted_flows().await;
+    flows.sort_by_key(|f| f.intercepted_at);
+    Json(serde_json::json!({
+        "flows": flows,
+        "total": flows.len(),
+    }))
+}
+
+/// GET /v0/management/intercept/flows/:id - 获取单个等待中的 Flow
+pub async fn management_get_intercepted_flow(
+    State(state): State<AppState>,
+    Path(flow_id): Path<String>,
+) -> Response {
+    match state.flow_interceptor.get_intercepted_flow(&flow_id).await {
+        Some(flow) => Json(flow).into_response(),
+        None => interceptor_error(InterceptorError::FlowNotFound(flow_id)),
+    }
+}
+
+/// POST /v0/management/intercept/flows/:id/edit - 标记 Flow 为编辑中
+pub async fn management_edit_intercepted_flow(
+    State(state): State<AppState>,
+    Path(flow_id): Path<String>,
+) -> Response {
+    if let Err(e) = state.flow_interceptor.set_editing(&flow_id).await {
+        return interceptor_error(e);
+    }
+    match state.flow_interceptor.get_intercepted_flow(&flow_id).await {
+        Some(flow) => Json(flow).into_response(),
+        None => interceptor_error(InterceptorError::FlowNotFound(flow_id)),
+    }
+}
+
+/// POST /v0/management/intercept/flows/:id/continue - 继续 Flow（可带修改）
+pub async fn management_continue_intercepted_flow(
+    State(state): State<AppState>,
+    Path(flow_id): Path<String>,
+    body: Bytes,
+) -> Response {
+    let request: ContinueInterceptRequest = if body.iter().all(u8::is_ascii_whitespace) {
+        ContinueInterceptRequest::default()
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(request) => request,
+            Err(e) => {
+                return intercept_error(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_request",
+                    format!("无效的请求体: {}", e),
+                )
+            }
+        }
+    };
+
+    let Some(flow) = state.flow_interceptor.get_intercepted_flow(&flow_id).await else {
+        return interceptor_error(InterceptorError::FlowNotFound(flow_id));
+    };
+
+    let modified = match (flow.intercept_type, request.request, request.response) {
+        (_, None, None) => None,
+        (InterceptType::Request, Some(req), None) => Some(ModifiedData::Request(req)),
+        (InterceptType::Response, None, Some(resp)) => Some(ModifiedData::Response(resp)),
+        (intercept_type, _, _) => {
+            let expected = match intercept_type {
+                InterceptType::Request => "request",
+                InterceptType::Response => "response",
+            };
+            return intercept_error(
+                StatusCode::BAD_REQUEST,
+                "invalid_request",
+                format!(
+                    "Flow '{}' 拦截的是 {}，只能修改 `{}`",
+                    flow_id, expected, expected
+                ),
+            );
+        }
+    };
+
+    match state
+        .flow_interceptor
+        .continue_flow(&flow_id, modified)
+        .await
+    {
+        Ok(()) => Json(serde_json::json!({"success": true, "flow_id": flow_id})).into_response(),
+        Err(e) => interceptor_error(e),
+    }
+}
+
+/// POST /v0/management/intercept/flows/:id/cancel - 取消 Flow
+pub async fn management_cancel_intercepted_flow(
+    State(state): State<AppState>,
+    Path(flow_id): Path<String>,
+) -> Response {
+    match state.flow_interceptor.cancel_flow(&flow_id).await {
+        Ok(()) => Json(serde_json::json!({"success": true, "flow_id": flow_id})).into_response(),
+        Err(e) => interceptor_error(e),
+    }
+}
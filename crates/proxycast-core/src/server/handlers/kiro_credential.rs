@@ -18,6 +18,7 @@ use serde::{Deserialize, Serialize};
 use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::models::provider_pool_model::{CachedTokenInfo, PoolProviderType, ProviderCredential};
 use crate::server::AppState;
+use crate::services::credential_health::{HealthScoreInput, AVAILABLE_HEALTH_SCORE};
 
 /// 可用凭证信息
 #[derive(Debug, Clone, Serialize)]
@@ -157,9 +158,9 @@ pub async fn get_available_credentials(
             })?;
 
         // 计算健康状态分数
-        let health_score = calculate_health_score(&credential, cache_status.as_ref());
+        let health_score = health_score(&state, credential, cache_status.as_ref());
 
-        let is_available = health_score > 50.0; // 健康分数大于50认为可用
+        let is_available = health_score > AVAILABLE_HEALTH_SCORE;
         if is_available {
             available_count += 1;
         }
@@ -471,7 +472,7 @@ pub async fn get_credential_status(
         })?;
 
     // 计算健康分数
-    let health_score = calculate_health_score(&credential, cache_status.as_ref());
+    let health_score = health_score(&state, &credential, cache_status.as_ref());
 
     let mut status = serde_json::Map::new();
     status.insert(
@@ -510,7 +511,7 @@ pub async fn get_credential_status(
     );
     status.insert(
         "is_available".to_string(),
-        serde_json::Value::Bool(health_score > 50.0),
+        serde_json::Value::Bool(health_score > AVAILABLE_HEALTH_SCORE),
     );
 
     if let Some(cache) = cache_status {
@@ -570,83 +571,13 @@ pub async fn get_credential_status(
 
 /// 计算凭证健康分数
 ///
-/// 基于凭证的基本状态、缓存状态、错误计数等因素综合计算健康分数
-/// 分数范围: 0-100，分数越高表示凭证越健康
-fn calculate_health_score(
+/// 使用 Kiro 注册的评分器（默认基于 Token 状态与配额冷却），分数范围 0-100
+fn health_score(
+    state: &AppState,
     credential: &ProviderCredential,
     cache_status: Option<&CachedTokenInfo>,
 ) -> f64 {
-    let mut score = 0.0;
-
-    // 1. 基础健康状态 (40分)
-    if credential.is_healthy {
-        score += 40.0;
-    } else {
-        score -= 20.0; // 不健康严重扣分
-    }
-
-    // 2. 错误计数影响 (20分)
-    let error_count = credential.error_count;
-    if error_count == 0 {
-        score += 20.0;
-    } else if error_count <= 2 {
-        score += 10.0; // 少量错误，轻微扣分
-    } else {
-        score -= error_count as f64 * 5.0; // 错误越多扣分越多
-    }
-
-    // 3. Token缓存状态 (25分)
-    if let Some(cache) = cache_status {
-        if cache.access_token.is_some() {
-            score += 15.0; // 有缓存token
-
-            // 检查过期时间
-            if let Some(expiry_time) = cache.expiry_time {
-                let now = chrono::Utc::now();
-                let time_until_expiry = expiry_time - now;
-
-                if time_until_expiry > chrono::Duration::hours(1) {
-                    score += 10.0; // 距离过期还有较长时间
-                } else if time_until_expiry > chrono::Duration::minutes(30) {
-                    score += 5.0; // 距离过期还有一些时间
-                } else if time_until_expiry <= chrono::Duration::zero() {
-                    score -= 10.0; // 已过期
-                }
-            }
-        } else {
-            score -= 5.0; // 没有缓存token
-        }
-
-        // 刷新错误计数影响
-        if cache.refresh_error_count == 0 {
-            // 无刷新错误，不加分不减分
-        } else if cache.refresh_error_count <= 2 {
-            score -= cache.refresh_error_count as f64 * 2.0; // 少量刷新错误
-        } else {
-            score -= cache.refresh_error_count as f64 * 5.0; // 大量刷新错误严重扣分
-        }
-    } else {
-        score -= 10.0; // 完全没有缓存状态
-    }
-
-    // 4. 使用活跃度 (15分)
-    if let Some(last_used) = credential.last_used {
-        let now = chrono::Utc::now();
-        let time_since_used = now - last_used;
-
-        if time_since_used <= chrono::Duration::hours(1) {
-            score += 15.0; // 最近1小时内使用过
-        } else if time_since_used <= chrono::Duration::hours(24) {
-            score += 10.0; // 最近24小时内使用过
-        } else if time_since_used <= chrono::Duration::days(7) {
-            score += 5.0; // 最近一周内使用过
-        } else {
-            score += 0.0; // 很久未使用，不扣分也不加分
-        }
-    } else {
-        score -= 5.0; // 从未使用过
-    }
-
-    // 确保分数在0-100范围内
-    score.max(0.0).min(100.0)
+    let input = HealthScoreInput::new(credential, cache_status)
+        .with_cooldown(state.quota_manager.get_cooldown_until(&credential.uuid));
+    state.health_scorers.score(&input)
 }
@@ -2,10 +2,16 @@
 //!
 //! 提供服务器状态查询、凭证管理、配置管理等功能
 
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::models::provider_pool_model::{CredentialData, PoolProviderType, ProviderCredential};
 use crate::server::AppState;
 
 // ============ Types ============
@@ -54,7 +60,7 @@ pub struct CredentialsListResponse {
 }
 
 /// 添加凭证请求
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AddCredentialRequest {
     /// Provider 类型
     pub provider_type: String,
@@ -74,6 +80,86 @@ pub struct AddCredentialRequest {
     pub proxy_url: Option<String>,
 }
 
+impl AddCredentialRequest {
+    /// 校验请求并构造凭证（API Key 类型需要 `api_key`，OAuth 类型需要 `token_file`）
+    pub fn to_credential(&self) -> Result<ProviderCredential, String> {
+        if self.id.is_empty() {
+            return Err("Credential ID is required".to_string());
+        }
+        if self.provider_type.is_empty() {
+            return Err("Provider type is required".to_string());
+        }
+
+        let provider_type: PoolProviderType = self
+            .provider_type
+            .parse()
+            .map_err(|_| format!("Invalid provider type: {}", self.provider_type))?;
+
+        let api_key = |label: &str| {
+            self.api_key
+                .clone()
+                .ok_or_else(|| format!("API key is required for {} provider", label))
+        };
+        let token_file = |label: &str| {
+            self.token_file
+                .clone()
+                .ok_or_else(|| format!("Token file is required for {} provider", label))
+        };
+
+        // 根据 provider 类型创建凭证数据
+        let credential_data = match provider_type {
+            PoolProviderType::OpenAI => CredentialData::OpenAIKey {
+                api_key: api_key("OpenAI")?,
+                base_url: self.base_url.clone(),
+            },
+            PoolProviderType::Claude => CredentialData::ClaudeKey {
+                api_key: api_key("Claude")?,
+                base_url: self.base_url.clone(),
+            },
+            PoolProviderType::Vertex => CredentialData::VertexKey {
+                api_key: api_key("Vertex")?,
+                base_url: self.base_url.clone(),
+                model_aliases: std::collections::HashMap::new(),
+            },
+            PoolProviderType::Kiro => CredentialData::KiroOAuth {
+                creds_file_path: token_file("Kiro")?,
+            },
+            PoolProviderType::Gemini => CredentialData::GeminiOAuth {
+                creds_file_path: token_file("Gemini")?,
+                project_id: None,
+            },
+            PoolProviderType::Qwen => CredentialData::QwenOAuth {
+                creds_file_path: token_file("Qwen")?,
+            },
+            PoolProviderType::Antigravity => CredentialData::AntigravityOAuth {
+                creds_file_path: token_file("Antigravity")?,
+                project_id: None,
+            },
+            PoolProviderType::GeminiApiKey => CredentialData::GeminiApiKey {
+                api_key: api_key("Gemini API Key")?,
+                base_url: self.base_url.clone(),
+                excluded_models: Vec::new(),
+            },
+            PoolProviderType::Codex => CredentialData::CodexOAuth {
+                creds_file_path: token_file("Codex")?,
+                api_base_url: self.base_url.clone(),
+            },
+            PoolProviderType::ClaudeOAuth => CredentialData::ClaudeOAuth {
+                creds_file_path: token_file("Claude OAuth")?,
+            },
+            // 默认使用 OAuth 类型，Cookie 类型需要通过其他方式添加
+            PoolProviderType::IFlow => CredentialData::IFlowOAuth {
+                creds_file_path: token_file("iFlow")?,
+            },
+        };
+
+        let mut credential = ProviderCredential::new(provider_type, credential_data);
+        credential.uuid = self.id.clone();
+        credential.name = Some(self.id.clone());
+        Ok(credential)
+    }
+}
+
 /// 添加凭证响应
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AddCredentialResponse {
@@ -85,6 +171,15 @@ pub struct AddCredentialResponse {
     pub id: Option<String>,
 }
 
+/// 删除凭证响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteCredentialResponse {
+    /// 是否成功
+    pub success: bool,
+    /// 消息
+    pub message: String,
+}
+
 /// 配置响应（简化版，不包含敏感信息）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ManagementConfigResponse {
@@ -163,13 +258,18 @@ pub async fn management_status(State(state): State<AppState>) -> impl IntoRespon
         requests,
         uptime_secs: 0, // TODO: Track actual uptime
         version: env!("CARGO_PKG_VERSION").to_string(),
-        tls_enabled: false,
+        tls_enabled: state.base_url.starts_with("https://"),
         default_provider,
     };
 
     Json(response)
 }
 
+/// GET /v0/management/queue - 获取请求优先级队列指标
+pub async fn management_queue_status(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.request_queue.metrics())
+}
+
 /// GET /v0/management/credentials - 获取凭证列表
 pub async fn management_list_credentials(State(state): State<AppState>) -> impl IntoResponse {
     let mut credentials = Vec::new();
@@ -199,243 +299,20 @@ pub async fn management_add_credential(
     State(state): State<AppState>,
     Json(request): Json<AddCredentialRequest>,
 ) -> impl IntoResponse {
-    use crate::models::provider_pool_model::{
-        CredentialData, PoolProviderType, ProviderCredential,
-    };
-
-    // 验证请求
-    if request.id.is_empty() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(AddCredentialResponse {
-                success: false,
-                message: "Credential ID is required".to_string(),
-                id: None,
-            }),
-        );
-    }
-
-    if request.provider_type.is_empty() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(AddCredentialResponse {
-                success: false,
-                message: "Provider type is required".to_string(),
-                id: None,
-            }),
-        );
-    }
-
-    // 解析 provider 类型
-    let provider_type: PoolProviderType = match request.provider_type.parse() {
-        Ok(pt) => pt,
-        Err(_) => {
+    let credential = match request.to_credential() {
+        Ok(credential) => credential,
+        Err(message) => {
             return (
                 StatusCode::BAD_REQUEST,
                 Json(AddCredentialResponse {
                     success: false,
-                    message: format!("Invalid provider type: {}", request.provider_type),
+                    message,
                     id: None,
                 }),
             );
         }
     };
 
-    // 根据 provider 类型创建凭证数据
-    let credential_data = match provider_type {
-        PoolProviderType::OpenAI => {
-            if let Some(api_key) = request.api_key {
-                CredentialData::OpenAIKey {
-                    api_key,
-                    base_url: request.base_url,
-                }
-            } else {
-                return (
-                    StatusCode::BAD_REQUEST,
-                    Json(AddCredentialResponse {
-                        success: false,
-                        message: "API key is required for OpenAI provider".to_string(),
-                        id: None,
-                    }),
-                );
-            }
-        }
-        PoolProviderType::Claude => {
-            if let Some(api_key) = request.api_key {
-                CredentialData::ClaudeKey {
-                    api_key,
-                    base_url: request.base_url,
-                }
-            } else {
-                return (
-                    StatusCode::BAD_REQUEST,
-                    Json(AddCredentialResponse {
-                        success: false,
-                        message: "API key is required for Claude provider".to_string(),
-                        id: None,
-                    }),
-                );
-            }
-        }
-        PoolProviderType::Vertex => {
-            if let Some(api_key) = request.api_key {
-                CredentialData::VertexKey {
-                    api_key,
-                    base_url: request.base_url,
-                    model_aliases: std::collections::HashMap::new(),
-                }
-            } else {
-                return (
-                    StatusCode::BAD_REQUEST,
-                    Json(AddCredentialResponse {
-                        success: false,
-                        message: "API key is required for Vertex provider".to_string(),
-                        id: None,
-                    }),
-                );
-            }
-        }
-        PoolProviderType::Kiro => {
-            if let Some(token_file) = request.token_file {
-                CredentialData::KiroOAuth {
-                    creds_file_path: token_file,
-                }
-            } else {
-                return (
-                    StatusCode::BAD_REQUEST,
-                    Json(AddCredentialResponse {
-                        success: false,
-                        message: "Token file is required for Kiro provider".to_string(),
-                        id: None,
-                    }),
-                );
-            }
-        }
-        PoolProviderType::Gemini => {
-            if let Some(token_file) = request.token_file {
-                CredentialData::GeminiOAuth {
-                    creds_file_path: token_file,
-                    project_id: None,
-                }
-            } else {
-                return (
-                    StatusCode::BAD_REQUEST,
-                    Json(AddCredentialResponse {
-                        success: false,
-                        message: "Token file is required for Gemini provider".to_string(),
-                        id: None,
-                    }),
-                );
-            }
-        }
-        PoolProviderType::Qwen => {
-            if let Some(token_file) = request.token_file {
-                CredentialData::QwenOAuth {
-                    creds_file_path: token_file,
-                }
-            } else {
-                return (
-                    StatusCode::BAD_REQUEST,
-                    Json(AddCredentialResponse {
-                        success: false,
-                        message: "Token file is required for Qwen provider".to_string(),
-                        id: None,
-                    }),
-                );
-            }
-        }
-        PoolProviderType::Antigravity => {
-            if let Some(token_file) = request.token_file {
-                CredentialData::AntigravityOAuth {
-                    creds_file_path: token_file,
-                    project_id: None,
-                }
-            } else {
-                return (
-                    StatusCode::BAD_REQUEST,
-                    Json(AddCredentialResponse {
-                        success: false,
-                        message: "Token file is required for Antigravity provider".to_string(),
-                        id: None,
-                    }),
-                );
-            }
-        }
-        PoolProviderType::GeminiApiKey => {
-            if let Some(api_key) = request.api_key {
-                CredentialData::GeminiApiKey {
-                    api_key,
-                    base_url: request.base_url,
-                    excluded_models: Vec::new(),
-                }
-            } else {
-                return (
-                    StatusCode::BAD_REQUEST,
-                    Json(AddCredentialResponse {
-                        success: false,
-                        message: "API key is required for Gemini API Key provider".to_string(),
-                        id: None,
-                    }),
-                );
-            }
-        }
-        PoolProviderType::Codex => {
-            if let Some(token_file) = request.token_file {
-                CredentialData::CodexOAuth {
-                    creds_file_path: token_file,
-                    api_base_url: request.base_url.clone(),
-                }
-            } else {
-                return (
-                    StatusCode::BAD_REQUEST,
-                    Json(AddCredentialResponse {
-                        success: false,
-                        message: "Token file is required for Codex provider".to_string(),
-                        id: None,
-                    }),
-                );
-            }
-        }
-        PoolProviderType::ClaudeOAuth => {
-            if let Some(token_file) = request.token_file {
-                CredentialData::ClaudeOAuth {
-                    creds_file_path: token_file,
-                }
-            } else {
-                return (
-                    StatusCode::BAD_REQUEST,
-                    Json(AddCredentialResponse {
-                        success: false,
-                        message: "Token file is required for Claude OAuth provider".to_string(),
-                        id: None,
-                    }),
-                );
-            }
-        }
-        PoolProviderType::IFlow => {
-            if let Some(token_file) = request.token_file {
-                // 默认使用 OAuth 类型，Cookie 类型需要通过其他方式添加
-                CredentialData::IFlowOAuth {
-                    creds_file_path: token_file,
-                }
-            } else {
-                return (
-                    StatusCode::BAD_REQUEST,
-                    Json(AddCredentialResponse {
-                        success: false,
-                        message: "Token file is required for iFlow provider".to_string(),
-                        id: None,
-                    }),
-                );
-            }
-        }
-    };
-
-    // 创建凭证
-    let mut credential = ProviderCredential::new(provider_type, credential_data);
-    credential.uuid = request.id.clone();
-    credential.name = Some(request.id.clone());
-
     // 添加凭证到数据库
     if let Some(ref db) = state.db {
         if let Ok(conn) = db.lock() {
@@ -480,6 +357,53 @@ pub async fn management_add_credential(
     )
 }
 
+/// DELETE /v0/management/credentials/:uuid - 删除凭证
+pub async fn management_delete_credential(
+    State(state): State<AppState>,
+    Path(uuid): Path<String>,
+) -> impl IntoResponse {
+    let Some(ref db) = state.db else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(DeleteCredentialResponse {
+                success: false,
+                message: "Database not available".to_string(),
+            }),
+        );
+    };
+
+    match state.pool_service.delete_credential(db, &uuid) {
+        Ok(true) => {
+            state.quota_manager.restore_credential(&uuid);
+            tracing::info!("[MANAGEMENT] Deleted credential: {}", uuid);
+            (
+                StatusCode::OK,
+                Json(DeleteCredentialResponse {
+                    success: true,
+                    message: "Credential deleted successfully".to_string(),
+                }),
+            )
+        }
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(DeleteCredentialResponse {
+                success: false,
+                message: format!("Credential not found: {}", uuid),
+            }),
+        ),
+        Err(e) => {
+            tracing::error!("[MANAGEMENT] Failed to delete credential: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(DeleteCredentialResponse {
+                    success: false,
+                    message: format!("Failed to delete credential: {}", e),
+                }),
+            )
+        }
+    }
+}
+
 /// GET /v0/management/config - 获取配置
 pub async fn management_get_config(State(state): State<AppState>) -> impl IntoResponse {
     let default_provider = state.default_provider.read().await.clone();
@@ -491,7 +415,7 @@ pub async fn management_get_config(State(state): State<AppState>) -> impl IntoRe
         server: ManagementServerConfigInfo {
             host: "0.0.0.0".to_string(),
             port: 8999,
-            tls_enabled: false,
+            tls_enabled: state.base_url.starts_with("https://"),
         },
         routing: ManagementRoutingConfigInfo {
             default_provider,
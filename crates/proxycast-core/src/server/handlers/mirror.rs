@@ -0,0 +1,583 @@
+//! 影子流量镜像处理器
+//!
+//! 把命中镜像规则的请求异步复制到影子目标，并记录与主 Flow 关联的影子 Flow
+//! （标签 `shadow`、`mirror:<rule>`、`shadow-of:<flow_id>`）。客户端只会收到主响应；
+//! 单个镜像对可通过 Flow 对比逐项比较，按规则的聚合结果通过
+//! `GET /v0/management/mirror/report` 查看。
+
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum::body::BodyDataStream;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::config::{MirrorConfig, MirrorRule};
+use crate::flow_monitor::{
+    shadow_flow_tags, EnhancedStatsService, FlowAnnotations, FlowError, FlowErrorType, LLMRequest,
+    FlowMonitor, ReportFormat, StatsTimeRange, StreamFormat, StreamRebuilder,
+};
+use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::openai::ChatCompletionRequest;
+use crate::models::provider_pool_model::ProviderCredential;
+use crate::server::AppState;
+use crate::streaming::plugin_hooks::parse_sse_block;
+use crate::ProviderType;
+
+use super::api::{
+    build_flow_metadata, build_llm_request_from_anthropic, build_llm_request_from_openai,
+    build_llm_response,
+};
+use super::{background_queue_class, call_provider_anthropic, call_provider_openai};
+
+/// 从响应体中提取的输出
+#[derive(Debug, Clone, Default)]
+pub struct MirrorOutput {
+    /// 输出文本
+    pub content: String,
+    /// Token 用量 (输入, 输出)
+    pub usage: Option<(u32, u32)>,
+    /// 原始响应体
+    pub body: Value,
+}
+
+/// 从 OpenAI 格式响应体提取输出
+fn openai_output(body: Value) -> MirrorOutput {
+    let content = body["choices"][0]["message"]["content"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let usage = body["usage"]["prompt_tokens"]
+        .as_u64()
+        .zip(body["usage"]["completion_tokens"].as_u64())
+        .map(|(input, output)| (input as u32, output as u32));
+    MirrorOutput {
+        content,
+        usage,
+        body,
+    }
+}
+
+/// 从 Anthropic 格式响应体提取输出
+fn anthropic_output(body: Value) -> MirrorOutput {
+    let content = body["content"]
+        .as_array()
+        .map(|blocks| {
+            blocks
+                .iter()
+                .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("")
+        })
+        .unwrap_or_default();
+    let usage = body["usage"]["input_tokens"]
+        .as_u64()
+        .zip(body["usage"]["output_tokens"].as_u64())
+        .map(|(input, output)| (input as u32, output as u32));
+    MirrorOutput {
+        content,
+        usage,
+        body,
+    }
+}
+
+/// 读取非流式响应体并提取输出，返回内容不变的重建响应
+async fn capture_output(
+    response: Response,
+    extract: fn(Value) -> MirrorOutput,
+) -> (Response, Option<MirrorOutput>) {
+    let (parts, body) = response.into_parts();
+    match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => {
+            let output = serde_json::from_slice(&bytes).ok().map(extract);
+            (Response::from_parts(parts, Body::from(bytes)), output)
+        }
+        Err(e) => {
+            tracing::warn!("[MIRROR] 读取主响应体失败: {}", e);
+            (Response::from_parts(parts, Body::empty()), None)
+        }
+    }
+}
+
+/// 主请求流式响应的输出记录器
+///
+/// 把 SSE 事件块（以空行分隔，可能被任意切分到多个数据块中）送入 `StreamRebuilder`，
+/// 流完整结束后把重建的输出写回主 Flow。
+struct StreamCapture {
+    flow_monitor: Arc<FlowMonitor>,
+    flow_id: String,
+    pending: Vec<u8>,
+    rebuilder: StreamRebuilder,
+}
+
+impl StreamCapture {
+    fn feed(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+        while let Some(end) = self.pending.windows(2).position(|w| w == b"\n\n") {
+            let block: Vec<u8> = self.pending.drain(..end + 2).collect();
+            self.process_block(&String::from_utf8_lossy(&block));
+        }
+    }
+
+    fn process_block(&mut self, block: &str) {
+        let (event, Some(data)) = parse_sse_block(block) else {
+            return;
+        };
+        if let Err(e) = self.rebuilder.process_event(event, &data) {
+            tracing::debug!("[MIRROR] 主响应流重建失败: {}", e);
+        }
+    }
+
+    fn output(mut self) -> MirrorOutput {
+        if !self.pending.is_empty() {
+            let block = std::mem::take(&mut self.pending);
+            self.process_block(&String::from_utf8_lossy(&block));
+        }
+        let response = self.rebuilder.finish();
+        let usage = Some((response.usage.input_tokens, response.usage.output_tokens))
+            .filter(|&(input, output)| input > 0 || output > 0);
+        MirrorOutput {
+            content: response.content,
+            usage,
+            body: response.body,
+        }
+    }
+
+    /// 主 Flow 在响应返回前已以空内容完成，这里更新内存存储中的记录
+    /// （镜像报告从内存存储读取主 Flow）
+    async fn finish(self) {
+        let flow_monitor = self.flow_monitor.clone();
+        let flow_id = self.flow_id.clone();
+        let output = self.output();
+        let store = flow_monitor.memory_store();
+        let store = store.read().await;
+        store.update(&flow_id, |flow| {
+            if let Some(response) = &mut flow.response {
+                response.size_bytes = output.content.len();
+                response.content = output.content;
+                response.body = output.body;
+                if let Some((input, output)) = output.usage {
+                    response.usage.input_tokens = input;
+                    response.usage.output_tokens = output;
+                    response.usage.calculate_total();
+                }
+            }
+        });
+    }
+}
+
+/// 记录主请求的流式输出：数据块即时原样转发，流完整结束后更新主 Flow；
+/// 客户端中途断开或上游出错时不更新
+fn capture_stream_output(
+    state: &AppState,
+    response: Response,
+    format: StreamFormat,
+    flow_id: &str,
+) -> Response {
+    let (parts, body) = response.into_parts();
+    let capture = StreamCapture {
+        flow_monitor: state.flow_monitor.clone(),
+        flow_id: flow_id.to_string(),
+        pending: Vec::new(),
+        rebuilder: StreamRebuilder::new(format),
+    };
+    let body = stream::unfold(
+        Some((body.into_data_stream(), capture)),
+        |state: Option<(BodyDataStream, StreamCapture)>| async move {
+            let (mut body, mut capture) = state?;
+            match body.next().await {
+                Some(Ok(chunk)) => {
+                    capture.feed(&chunk);
+                    Some((Ok(chunk), Some((body, capture))))
+                }
+                Some(Err(e)) => Some((Err(e), None)),
+                None => {
+                    capture.finish().await;
+                    None
+                }
+            }
+        },
+    );
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+/// 响应是否为 SSE 流
+fn is_event_stream(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"))
+}
+
+/// 捕获主请求的输出（OpenAI 格式），用于与影子输出比较
+///
+/// 非流式响应直接返回提取的输出；流式响应返回 `None`，输出在流结束后写回主 Flow。
+pub async fn capture_openai_output(
+    state: &AppState,
+    response: Response,
+    flow_id: &str,
+) -> (Response, Option<MirrorOutput>) {
+    if is_event_stream(&response) {
+        let response = capture_stream_output(state, response, StreamFormat::OpenAI, flow_id);
+        return (response, None);
+    }
+    capture_output(response, openai_output).await
+}
+
+/// 捕获主请求的输出（Anthropic 格式），用于与影子输出比较
+///
+/// 非流式响应直接返回提取的输出；流式响应返回 `None`，输出在流结束后写回主 Flow。
+pub async fn capture_anthropic_output(
+    state: &AppState,
+    response: Response,
+    flow_id: &str,
+) -> (Response, Option<MirrorOutput>) {
+    if is_event_stream(&response) {
+        let response = capture_stream_output(state, response, StreamFormat::Anthropic, flow_id);
+        return (response, None);
+    }
+    capture_output(response, anthropic_output).await
+}
+
+/// 影子请求
+enum ShadowRequest {
+    OpenAi(ChatCompletionRequest),
+    Anthropic(AnthropicMessagesRequest),
+}
+
+impl ShadowRequest {
+    fn model(&self) -> &str {
+        match self {
+            ShadowRequest::OpenAi(r) => &r.model,
+            ShadowRequest::Anthropic(r) => &r.model,
+        }
+    }
+
+    fn llm_request(&self, headers: &HeaderMap) -> LLMRequest {
+        match self {
+            ShadowRequest::OpenAi(r) => {
+                build_llm_request_from_openai(r, "/v1/chat/completions", headers)
+            }
+            ShadowRequest::Anthropic(r) => {
+                build_llm_request_from_anthropic(r, "/v1/messages", headers)
+            }
+        }
+    }
+
+    async fn call(&self, state: &AppState, cred: &ProviderCredential) -> Response {
+        match self {
+            ShadowRequest::OpenAi(r) => {
+                call_provider_openai(state, cred, r, None, &background_queue_class(state)).await
+            }
+            ShadowRequest::Anthropic(r) => {
+                call_provider_anthropic(state, cred, r, None, &background_queue_class(state)).await
+            }
+        }
+    }
+
+    fn extract(&self) -> fn(Value) -> MirrorOutput {
+        match self {
+            ShadowRequest::OpenAi(_) => openai_output,
+            ShadowRequest::Anthropic(_) => anthropic_output,
+        }
+    }
+}
+
+/// 按镜像规则异步复制 OpenAI 格式请求
+///
+/// 返回是否已发出影子请求（用于决定是否捕获主响应的输出）
+pub fn mirror_openai_request(
+    state: &AppState,
+    request: &ChatCompletionRequest,
+    headers: &HeaderMap,
+    primary_flow_id: Option<&str>,
+    request_id: &str,
+) -> bool {
+    let (Some(primary_flow_id), Some(rule)) =
+        (primary_flow_id, state.shadow_mirror.select(&request.model))
+    else {
+        return false;
+    };
+    let mut shadow = request.clone();
+    shadow.stream = false;
+    if let Some(model) = &rule.target_model {
+        shadow.model = model.clone();
+    }
+    spawn_shadow(
+        state,
+        rule,
+        ShadowRequest::OpenAi(shadow),
+        headers,
+        primary_flow_id,
+        request_id,
+    );
+    true
+}
+
+/// 按镜像规则异步复制 Anthropic 格式请求
+///
+/// 返回是否已发出影子请求（用于决定是否捕获主响应的输出）
+pub fn mirror_anthropic_request(
+    state: &AppState,
+    request: &AnthropicMessagesRequest,
+    headers: &HeaderMap,
+    primary_flow_id: Option<&str>,
+    request_id: &str,
+) -> bool {
+    let (Some(primary_flow_id), Some(rule)) =
+        (primary_flow_id, state.shadow_mirror.select(&request.model))
+    else {
+        return false;
+    };
+    let mut shadow = request.clone();
+    shadow.stream = false;
+    if let Some(model) = &rule.target_model {
+        shadow.model = model.clone();
+    }
+    spawn_shadow(
+        state,
+        rule,
+        ShadowRequest::Anthropic(shadow),
+        headers,
+        primary_flow_id,
+        request_id,
+    );
+    true
+}
+
+/// 在后台执行影子请求并记录影子 Flow
+fn spawn_shadow(
+    state: &AppState,
+    rule: MirrorRule,
+    request: ShadowRequest,
+    headers: &HeaderMap,
+    primary_flow_id: &str,
+    request_id: &str,
+) {
+    let state = state.clone();
+    let headers = headers.clone();
+    let primary_flow_id = primary_flow_id.to_string();
+    let request_id = request_id.to_string();
+
+    tokio::spawn(async move {
+        let provider = match rule.target_provider.parse::<ProviderType>() {
+            Ok(provider) => provider,
+            Err(e) => {
+                tracing::warn!("[MIRROR] 规则 {} 的影子目标无效: {}", rule.name, e);
+                return;
+            }
+        };
+        let model = request.model().to_string();
+
+        let credential = match &state.db {
+            Some(db) => {
+                let quota = &state.quota_manager;
+                state
+                    .pool_service
+                    .select_credential_filtered(db, &rule.target_provider, Some(&model), |cred| {
+                        quota.is_available(&cred.uuid)
+                    })
+                    .ok()
+                    .flatten()
+            }
+            None => None,
+        };
+
+        let mut metadata = build_flow_metadata(
+            provider,
+            credential.as_ref().map(|c| c.uuid.as_str()),
+            credential.as_ref().and_then(|c| c.name.as_deref()),
+            &headers,
+            &request_id,
+        );
+        metadata.routing_info.route_rule = Some(format!("mirror:{}", rule.name));
+
+        let Some(shadow_flow_id) = state
+            .flow_monitor
+            .start_flow(request.llm_request(&headers), metadata)
+            .await
+        else {
+            return;
+        };
+
+        match credential {
+            Some(cred) => {
+                let response = request.call(&state, &cred).await;
+                let status = response.status();
+                let (_, output) = capture_output(response, request.extract()).await;
+                if status.is_success() {
+                    let output = output.unwrap_or_default();
+                    let mut llm_response =
+                        build_llm_response(status.as_u16(), &output.content, output.usage);
+                    llm_response.body = output.body;
+                    state
+                        .flow_monitor
+                        .complete_flow(&shadow_flow_id, Some(llm_response))
+                        .await;
+                } else {
+                    let message = output
+                        .and_then(|o| o.body["error"]["message"].as_str().map(str::to_string))
+                        .unwrap_or_else(|| "Shadow request failed".to_string());
+                    let error =
+                        FlowError::new(FlowErrorType::from_status_code(status.as_u16()), message)
+                            .with_status_code(status.as_u16());
+                    state.flow_monitor.fail_flow(&shadow_flow_id, error).await;
+                }
+            }
+            None => {
+                let error = FlowError::new(
+                    FlowErrorType::ServerError,
+                    format!(
+                        "No available '{}' credential for shadow model {}",
+                        rule.target_provider, model
+                    ),
+                );
+                state.flow_monitor.fail_flow(&shadow_flow_id, error).await;
+            }
+        }
+
+        let annotations = FlowAnnotations {
+            comment: Some(format!("镜像自 Flow: {}", primary_flow_id)),
+            tags: shadow_flow_tags(&rule.name, &primary_flow_id),
+            ..Default::default()
+        };
+        state
+            .flow_monitor
+            .update_annotations(&shadow_flow_id, annotations)
+            .await;
+
+        state.logs.write().await.add(
+            "info",
+            &format!(
+                "[MIRROR] request_id={} rule={} primary_flow={} shadow_flow={} target={}/{}",
+                request_id, rule.name, primary_flow_id, shadow_flow_id, rule.target_provider, model
+            ),
+        );
+    });
+}
+
+/// 镜像报告查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct MirrorReportQuery {
+    /// 开始时间（默认 24 小时前）
+    pub start: Option<DateTime<Utc>>,
+    /// 结束时间（默认当前时间）
+    pub end: Option<DateTime<Utc>>,
+    /// 报告格式：json（默认）、markdown、csv
+    pub format: Option<ReportFormat>,
+}
+
+/// GET /v0/management/mirror/config - 获取镜像配置
+pub async fn management_get_mirror_config(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.shadow_mirror.config())
+}
+
+/// PUT /v0/management/mirror/config - 更新镜像配置
+pub async fn management_update_mirror_config(
+    State(state): State<AppState>,
+    Json(config): Json<MirrorConfig>,
+) -> Response {
+    if let Some(rule) = config
+        .rules
+        .iter()
+        .find(|r| r.target_provider.parse::<ProviderType>().is_err())
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": {
+                    "type": "invalid_request",
+                    "message": format!("规则 '{}' 的 target_provider 无效: {}", rule.name, rule.target_provider)
+                }
+            })),
+        )
+            .into_response();
+    }
+    tracing::info!(
+        "[MIRROR] 配置已更新: enabled={}, rules={}",
+        config.enabled,
+        config.rules.len()
+    );
+    state.shadow_mirror.set_config(config);
+    Json(state.shadow_mirror.config()).into_response()
+}
+
+/// GET /v0/management/mirror/report - 按镜像规则聚合的对比报告
+pub async fn management_get_mirror_report(
+    State(state): State<AppState>,
+    Query(query): Query<MirrorReportQuery>,
+) -> Response {
+    let default_range = StatsTimeRange::default();
+    let time_range = StatsTimeRange {
+        start: query.start.unwrap_or(default_range.start),
+        end: query.end.unwrap_or(default_range.end),
+    };
+    let service = EnhancedStatsService::new(state.flow_monitor.memory_store());
+
+    let format = query.format.unwrap_or_default();
+    let content_type = match format {
+        ReportFormat::Json => {
+            return Json(service.get_mirror_report(&time_range).await).into_response()
+        }
+        ReportFormat::Markdown => "text/markdown; charset=utf-8",
+        ReportFormat::Csv => "text/csv; charset=utf-8",
+    };
+    let body = service.export_mirror_report(&time_range, &format).await;
+    ([(header::CONTENT_TYPE, content_type)], body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow_monitor::{FlowMetadata, FlowMonitorConfig, FlowType, LLMFlow, LLMResponse};
+
+    #[tokio::test]
+    async fn test_stream_capture_updates_primary_flow() {
+        let flow_monitor = Arc::new(FlowMonitor::new(FlowMonitorConfig::default(), None));
+        let mut flow = LLMFlow::new(
+            "primary".to_string(),
+            FlowType::ChatCompletions,
+            LLMRequest::default(),
+            FlowMetadata::default(),
+        );
+        flow.response = Some(LLMResponse::default());
+        flow_monitor.memory_store().write().await.add(flow);
+
+        let mut capture = StreamCapture {
+            flow_monitor: flow_monitor.clone(),
+            flow_id: "primary".to_string(),
+            pending: Vec::new(),
+            rebuilder: StreamRebuilder::new(StreamFormat::OpenAI),
+        };
+        let sse = concat!(
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"你好\"}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"，世界\"},\"finish_reason\":\"stop\"}],",
+            "\"usage\":{\"prompt_tokens\":12,\"completion_tokens\":3}}\n\n",
+            "data: [DONE]\n\n"
+        )
+        .as_bytes();
+        // 在多字节字符和事件块中间切分
+        for chunk in sse.chunks(7) {
+            capture.feed(chunk);
+        }
+        capture.finish().await;
+
+        let store = flow_monitor.memory_store();
+        let store = store.read().await;
+        let flow = store.get("primary").unwrap();
+        let flow = flow.read().unwrap();
+        let response = flow.response.as_ref().unwrap();
+        assert_eq!(response.content, "你好，世界");
+        assert_eq!(response.usage.input_tokens, 12);
+        assert_eq!(response.usage.output_tokens, 3);
+        assert_eq!(response.usage.total_tokens, 15);
+    }
+}
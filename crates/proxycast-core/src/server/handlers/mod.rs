@@ -3,13 +3,43 @@
 //! 将 server 中的各类处理器拆分到独立文件
 
 pub mod api;
+pub mod backup;
+pub mod batch;
+pub mod credential;
+pub mod dataset;
+pub mod events;
+pub mod flow_conversation;
+pub mod flow_import;
+pub mod flow_query;
+pub mod flow_storage;
+pub mod intercept;
 pub mod kiro_credential;
 pub mod management;
+pub mod mirror;
+pub mod notification;
 pub mod provider_calls;
+pub mod shared_state;
+pub mod suite;
+pub mod usage_quota;
 pub mod websocket;
 
 pub use api::*;
+pub use backup::*;
+pub use batch::*;
+pub use credential::*;
+pub use dataset::*;
+pub use events::*;
+pub use flow_conversation::*;
+pub use flow_import::*;
+pub use flow_query::*;
+pub use flow_storage::*;
+pub use intercept::*;
 pub use kiro_credential::*;
 pub use management::*;
+pub use mirror::*;
+pub use notification::*;
 pub use provider_calls::*;
+pub use shared_state::*;
+pub use suite::*;
+pub use usage_quota::*;
 pub use websocket::*;
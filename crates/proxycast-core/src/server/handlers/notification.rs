@@ -0,0 +1,94 @@
+//! 运维告警通知管理 API 处理器
+//!
+//! - `GET /v0/management/notifications/config`：获取通知 Sink 配置
+//! - `PUT /v0/management/notifications/config`：更新通知 Sink 配置
+//! - `GET /v0/management/notifications/dead-letters`：投递统计与最近的死信记录
+//! - `POST /v0/management/notifications/test`：向指定 Sink 发送测试通知
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::config::NotificationsConfig;
+use crate::server::AppState;
+use crate::services::notification_service::validate_notifications_config;
+
+fn notification_error(status: StatusCode, error_type: &str, message: String) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {"type": error_type, "message": message}
+        })),
+    )
+        .into_response()
+}
+
+/// 测试发送请求
+#[derive(Debug, Deserialize)]
+pub struct TestNotificationRequest {
+    /// Sink 名称
+    pub sink: String,
+}
+
+/// GET /v0/management/notifications/config - 获取通知配置
+pub async fn management_get_notifications_config(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    Json(state.notifications.config())
+}
+
+/// PUT /v0/management/notifications/config - 更新通知配置
+pub async fn management_update_notifications_config(
+    State(state): State<AppState>,
+    Json(config): Json<NotificationsConfig>,
+) -> Response {
+    if let Err(e) = validate_notifications_config(&config) {
+        return notification_error(StatusCode::BAD_REQUEST, "invalid_request", e);
+    }
+    tracing::info!(
+        "[NOTIFY] 配置已更新: enabled={}, sinks={}",
+        config.enabled,
+        config.sinks.len()
+    );
+    state.notifications.set_config(config);
+    Json(state.notifications.config()).into_response()
+}
+
+/// GET /v0/management/notifications/dead-letters - 投递统计与死信记录
+pub async fn management_get_notification_dead_letters(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "stats": state.notifications.stats(),
+        "dead_letter_path": state.notifications.dead_letter_path(),
+        "dead_letters": state.notifications.dead_letters(),
+    }))
+}
+
+/// POST /v0/management/notifications/test - 发送测试通知
+pub async fn management_test_notification_sink(
+    State(state): State<AppState>,
+    Json(request): Json<TestNotificationRequest>,
+) -> Response {
+    if !state
+        .notifications
+        .config()
+        .sinks
+        .iter()
+        .any(|s| s.name == request.sink)
+    {
+        return notification_error(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("Sink 不存在: {}", request.sink),
+        );
+    }
+    match state.notifications.send_test(&request.sink).await {
+        Ok(()) => Json(serde_json::json!({ "success": true })).into_response(),
+        Err(e) => notification_error(StatusCode::BAD_GATEWAY, "delivery_failed", e),
+    }
+}
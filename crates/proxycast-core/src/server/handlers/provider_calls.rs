@@ -16,7 +16,7 @@
 
 use axum::{
     body::Body,
-    http::{header, StatusCode},
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -26,35 +26,195 @@ use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use crate::converter::openai_to_antigravity::{
     convert_antigravity_to_openai_response, convert_openai_to_antigravity_with_context,
 };
+use crate::converter::structured_output::{
+    append_retry_messages, apply_prompt_fallback, completion_to_sse, gemini_response_schema,
+    validate_output, ResponseFormat, StructuredOutputError, StructuredOutputStrategy,
+};
+use crate::flow_monitor::models::{FlowError, FlowErrorType};
 use crate::flow_monitor::stream_rebuilder::StreamFormat;
 use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::ChatCompletionRequest;
 use crate::models::provider_pool_model::{CredentialData, ProviderCredential};
+use crate::plugin::PluginContext;
+use crate::processor::QueuePermit;
 use crate::providers::{
     AntigravityProvider, ClaudeCustomProvider, KiroProvider, OpenAICustomProvider, VertexProvider,
 };
+use crate::server::client_detector::ClientType;
 use crate::server::AppState;
 use crate::server_utils::{
     build_anthropic_response, build_anthropic_stream_response, parse_cw_response, safe_truncate,
     CWParsedResponse,
 };
+use crate::services::shared_state::RefreshLockGuard;
 use crate::streaming::{
-    StreamConfig, StreamContext, StreamError, StreamFormat as StreamingFormat, StreamManager,
-    StreamResponse,
+    with_plugin_hooks, StreamConfig, StreamContext, StreamError, StreamFormat as StreamingFormat,
+    StreamManager, StreamPluginHooks, StreamResponse,
 };
+use crate::ProviderType;
+use futures::stream::BoxStream;
+
+use super::api::{build_llm_request_from_openai, build_llm_response};
+
+// ============================================================================
+// 上游优先级队列
+// ============================================================================
+
+/// 后台任务（批处理、影子流量、回归套件）请求的优先级类别名
+const BACKGROUND_QUEUE_CLASS: &str = "background";
+
+/// 确定客户端请求的优先级类别
+///
+/// 按 API Key 绑定、优先级请求头、客户端类型（User-Agent）依次匹配，
+/// 都未命中时使用默认类别
+pub fn upstream_queue_class(state: &AppState, headers: &HeaderMap) -> String {
+    let queue = &state.request_queue;
+    let priority_header = queue.config().priority_header;
+    let api_key = headers
+        .get("x-api-key")
+        .or_else(|| headers.get("authorization"))
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.strip_prefix("Bearer ").unwrap_or(s));
+    let header_class = headers
+        .get(priority_header.as_str())
+        .and_then(|v| v.to_str().ok());
+    let user_agent = headers
+        .get("user-agent")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    let client_type = ClientType::from_user_agent(user_agent);
+    queue.classify(api_key, header_class, client_type.config_key())
+}
+
+/// 后台任务使用的优先级类别（未配置 `background` 类别时回退为默认类别）
+pub fn background_queue_class(state: &AppState) -> String {
+    state
+        .request_queue
+        .classify(None, Some(BACKGROUND_QUEUE_CLASS), "")
+}
+
+/// 获取上游调用槽位
+///
+/// 所有 `call_provider_*` 调用在发往上游之前经过这里进入加权公平队列，
+/// 请求拦截（断点暂停）发生在调用之前，因此暂停的请求不占用槽位。
+/// 未启用排队时返回 `None`；排队超时返回 503 响应并标记 Flow 失败。
+pub async fn acquire_upstream_slot(
+    state: &AppState,
+    queue_class: &str,
+    flow_id: Option<&str>,
+) -> Result<Option<QueuePermit>, Response> {
+    let queue = &state.request_queue;
+    if !queue.is_enabled() {
+        return Ok(None);
+    }
+
+    let deadline = std::time::Instant::now() + queue.max_wait();
+    let permit = match queue.acquire(queue_class, deadline).await {
+        Ok(permit) => permit,
+        Err(e) => {
+            state.logs.write().await.add(
+                "warn",
+                &format!("[QUEUE] flow_id={} {}", flow_id.unwrap_or("-"), e),
+            );
+            let message = e.to_string();
+            let response = e.into_response();
+            if let Some(fid) = flow_id {
+                let status = response.status().as_u16();
+                let error = FlowError::new(FlowErrorType::from_status_code(status), &message)
+                    .with_status_code(status);
+                state.flow_monitor.fail_flow(fid, error).await;
+            }
+            return Err(response);
+        }
+    };
+    if !permit.waited().is_zero() {
+        state.logs.write().await.add(
+            "info",
+            &format!(
+                "[QUEUE] flow_id={} class={} waited_ms={}",
+                flow_id.unwrap_or("-"),
+                permit.class(),
+                permit.waited().as_millis()
+            ),
+        );
+    }
+
+    Ok(Some(permit))
+}
+
+/// 获取 Kiro Token 刷新锁
+///
+/// 其他副本持有刷新锁超过 `lock_wait_secs` 时返回 503 响应并标记 Flow 失败，
+/// 避免用即将被轮换的 refresh_token 并发刷新。
+pub async fn lock_kiro_refresh<'a>(
+    state: &'a AppState,
+    flow_id: Option<&str>,
+) -> Result<RefreshLockGuard<'a>, Response> {
+    let e = match state.kiro_refresh_lock.lock().await {
+        Ok(guard) => return Ok(guard),
+        Err(e) => e,
+    };
+    let message = format!("Token refresh in progress on another replica: {e}");
+    state.logs.write().await.add(
+        "warn",
+        &format!("[AUTH] flow_id={} {}", flow_id.unwrap_or("-"), message),
+    );
+    if let Some(fid) = flow_id {
+        let error = FlowError::new(FlowErrorType::Authentication, &message)
+            .with_status_code(StatusCode::SERVICE_UNAVAILABLE.as_u16());
+        state.flow_monitor.fail_flow(fid, error).await;
+    }
+    Err((
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(serde_json::json!({"error": {"message": message}})),
+    )
+        .into_response())
+}
+
+/// 将队列许可绑定到响应体，流式响应结束后才归还上游槽位
+pub fn hold_queue_permit(response: Response, permit: Option<QueuePermit>) -> Response {
+    let Some(permit) = permit else {
+        return response;
+    };
+    response.map(|body| {
+        Body::from_stream(body.into_data_stream().map(move |chunk| {
+            let _ = &permit;
+            chunk
+        }))
+    })
+}
 
 /// 根据凭证调用 Provider (Anthropic 格式)
 ///
+/// 调用前按 `queue_class` 获取上游槽位，槽位随响应体一起释放。
+///
 /// # 参数
 /// - `state`: 应用状态
 /// - `credential`: 凭证信息
 /// - `request`: Anthropic 格式请求
 /// - `flow_id`: Flow ID（可选，用于流式响应处理）
+/// - `queue_class`: 上游优先级队列类别
 pub async fn call_provider_anthropic(
     state: &AppState,
     credential: &ProviderCredential,
     request: &AnthropicMessagesRequest,
     flow_id: Option<&str>,
+    queue_class: &str,
+) -> Response {
+    let permit = match acquire_upstream_slot(state, queue_class, flow_id).await {
+        Ok(permit) => permit,
+        Err(response) => return response,
+    };
+    let response = dispatch_provider_anthropic(state, credential, request, flow_id).await;
+    hold_queue_permit(response, permit)
+}
+
+/// 按凭证类型分发 Anthropic 格式请求
+async fn dispatch_provider_anthropic(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &AnthropicMessagesRequest,
+    flow_id: Option<&str>,
 ) -> Response {
     // 如果是流式请求且有 flow_id，设置流式状态
     if request.stream {
@@ -685,16 +845,36 @@ pub async fn call_provider_anthropic(
 
 /// 根据凭证调用 Provider (OpenAI 格式)
 ///
+/// 调用前按 `queue_class` 获取上游槽位，槽位随响应体一起释放。
+///
 /// # 参数
 /// - `state`: 应用状态
 /// - `credential`: 凭证信息
 /// - `request`: OpenAI 格式请求
 /// - `flow_id`: Flow ID（可选，用于流式响应处理）
+/// - `queue_class`: 上游优先级队列类别
 pub async fn call_provider_openai(
     state: &AppState,
     credential: &ProviderCredential,
     request: &ChatCompletionRequest,
     flow_id: Option<&str>,
+    queue_class: &str,
+) -> Response {
+    let permit = match acquire_upstream_slot(state, queue_class, flow_id).await {
+        Ok(permit) => permit,
+        Err(response) => return response,
+    };
+    let response = dispatch_provider_openai(state, credential, request).await;
+    hold_queue_permit(response, permit)
+}
+
+/// 按凭证类型分发 OpenAI 格式请求
+///
+/// OpenAI 格式的各 Provider 调用均为非流式，Flow 由调用方完成，无需 Flow ID
+async fn dispatch_provider_openai(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
 ) -> Response {
     let _start_time = std::time::Instant::now();
     match &credential.credential {
@@ -970,6 +1150,236 @@ pub async fn call_provider_openai(
     }
 }
 
+// ============================================================================
+// 结构化输出支持
+// ============================================================================
+
+/// 获取凭证对应的结构化输出策略
+///
+/// 只有请求转换器确实映射了 schema 的凭证才视为原生支持：OpenAI 兼容接口透传
+/// `response_format`，Antigravity 转换器映射为 `responseSchema`。Vertex 等其余
+/// Gemini 系凭证不转发 schema，走 system prompt 回退。
+pub fn structured_output_strategy(credential: &ProviderCredential) -> StructuredOutputStrategy {
+    match &credential.credential {
+        CredentialData::OpenAIKey { .. } | CredentialData::AntigravityOAuth { .. } => {
+            StructuredOutputStrategy::Native
+        }
+        CredentialData::ClaudeKey { .. } => StructuredOutputStrategy::ToolForcing,
+        _ => StructuredOutputStrategy::PromptFallback,
+    }
+}
+
+/// 按凭证的结构化输出策略预处理请求
+///
+/// 仅回退方案需要改写请求，原生和工具强制方案由各 Provider 转换器处理
+pub fn prepare_structured_request(
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+    format: &ResponseFormat,
+) -> ChatCompletionRequest {
+    let mut prepared = request.clone();
+    if structured_output_strategy(credential) == StructuredOutputStrategy::PromptFallback {
+        apply_prompt_fallback(&mut prepared, format);
+    }
+    prepared
+}
+
+/// 检查 schema 能否转换为凭证后端的原生格式（目前只有 Antigravity 需要转换）
+fn check_native_schema(
+    credential: &ProviderCredential,
+    format: &ResponseFormat,
+) -> Result<(), StructuredOutputError> {
+    match (&credential.credential, format) {
+        (CredentialData::AntigravityOAuth { .. }, ResponseFormat::JsonSchema { schema, .. }) => {
+            gemini_response_schema(schema).map(|_| ())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 把结构化输出错误转换为 HTTP 响应
+fn structured_output_error_response(error: &StructuredOutputError) -> Response {
+    (
+        StatusCode::from_u16(error.status_code()).unwrap_or(StatusCode::BAD_GATEWAY),
+        Json(error.to_json()),
+    )
+        .into_response()
+}
+
+/// 带结构化输出校验的 Provider 调用 (OpenAI 格式，非流式)
+///
+/// 校验模型输出是否符合 `response_format`，失败时追加纠错消息重试，
+/// 超过 `StructuredOutputConfig::max_retries` 后返回 `StructuredOutputError`。
+/// 每次尝试都会重新排队获取上游槽位。
+pub async fn call_provider_openai_structured(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+    flow_id: Option<&str>,
+    format: &ResponseFormat,
+    queue_class: &str,
+) -> Response {
+    if let Err(error) = check_native_schema(credential, format) {
+        return structured_output_error_response(&error);
+    }
+
+    let max_retries = state.structured_output_config.read().await.max_retries;
+    let mut attempt_request = prepare_structured_request(credential, request, format);
+    let mut last_errors = Vec::new();
+
+    for attempt in 0..=max_retries {
+        // 首次尝试沿用调用方的 Flow（由调用方完成），每次重试各自记录一个 Flow
+        let retry_flow_id = match flow_id {
+            Some(fid) if attempt > 0 => {
+                let llm_request =
+                    build_llm_request_from_openai(&attempt_request, "", &HeaderMap::new());
+                state.flow_monitor.start_retry_flow(fid, llm_request).await
+            }
+            _ => None,
+        };
+        let attempt_flow_id = if attempt == 0 {
+            flow_id
+        } else {
+            retry_flow_id.as_deref()
+        };
+
+        let response = call_provider_openai(
+            state,
+            credential,
+            &attempt_request,
+            attempt_flow_id,
+            queue_class,
+        )
+        .await;
+        if !response.status().is_success() {
+            if let Some(rid) = &retry_flow_id {
+                let status = response.status().as_u16();
+                let error =
+                    FlowError::new(FlowErrorType::from_status_code(status), "Request failed")
+                        .with_status_code(status);
+                state.flow_monitor.fail_flow(rid, error).await;
+            }
+            return response;
+        }
+
+        let (parts, body) = response.into_parts();
+        let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+            Ok(b) => b,
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                )
+                    .into_response();
+            }
+        };
+        let mut json: serde_json::Value = match serde_json::from_slice(&bytes) {
+            Ok(v) => v,
+            Err(_) => return Response::from_parts(parts, Body::from(bytes)),
+        };
+        let content = json["choices"][0]["message"]["content"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        if let Some(rid) = &retry_flow_id {
+            state
+                .flow_monitor
+                .complete_flow(rid, Some(build_llm_response(200, &content, None)))
+                .await;
+        }
+
+        match validate_output(&content, format) {
+            Ok(value) => {
+                json["choices"][0]["message"]["content"] = serde_json::json!(value.to_string());
+                return Json(json).into_response();
+            }
+            Err(errors) => {
+                tracing::warn!(
+                    "[STRUCTURED] 输出未通过 schema 校验: model={} attempt={} errors={:?}",
+                    request.model,
+                    attempt + 1,
+                    errors
+                );
+                append_retry_messages(&mut attempt_request, &content, &errors);
+                last_errors = errors;
+            }
+        }
+    }
+
+    let error = StructuredOutputError::ValidationFailed {
+        attempts: max_retries + 1,
+        errors: last_errors,
+    };
+    structured_output_error_response(&error)
+}
+
+/// 带结构化输出校验的 Provider 调用 (OpenAI 格式，流式)
+///
+/// 原生策略由上游保证输出符合 schema，直接流式转发。其余策略无法在已发出的流上
+/// 校验和重试，因此先以非流式请求完成校验，再把通过校验的结果按 SSE 格式返回；
+/// 这类请求在输出完整生成前不会收到任何数据块。
+pub async fn call_provider_openai_structured_stream(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+    flow_id: Option<&str>,
+    format: &ResponseFormat,
+    queue_class: &str,
+) -> Response {
+    if structured_output_strategy(credential) == StructuredOutputStrategy::Native {
+        if let Err(error) = check_native_schema(credential, format) {
+            return structured_output_error_response(&error);
+        }
+        return call_provider_openai(state, credential, request, flow_id, queue_class).await;
+    }
+
+    let mut buffered = request.clone();
+    buffered.stream = false;
+    let response =
+        call_provider_openai_structured(state, credential, &buffered, flow_id, format, queue_class)
+            .await;
+    if !response.status().is_success() {
+        return response;
+    }
+
+    let bytes = match axum::body::to_bytes(response.into_body(), usize::MAX).await {
+        Ok(b) => b,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": {"message": e.to_string()}})),
+            )
+                .into_response();
+        }
+    };
+    let json: serde_json::Value = match serde_json::from_slice(&bytes) {
+        Ok(v) => v,
+        Err(e) => {
+            return (
+                StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({"error": {"message": e.to_string()}})),
+            )
+                .into_response();
+        }
+    };
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .body(Body::from(completion_to_sse(&json)))
+        .unwrap_or_else(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(
+                    serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
+                ),
+            )
+                .into_response()
+        })
+}
+
 // ============================================================================
 // 流式传输支持
 // ============================================================================
@@ -997,6 +1407,39 @@ pub fn get_stream_format_for_credential(credential: &ProviderCredential) -> Stre
     }
 }
 
+/// 创建流式插件钩子
+///
+/// 没有已加载的插件时返回 `None`，避免为每个事件解析 JSON。
+/// `provider` 为实际处理请求的凭证类型，而不是默认 Provider。
+fn stream_plugin_hooks(
+    state: &AppState,
+    flow_id: Option<&str>,
+    provider: ProviderType,
+    target_format: StreamingFormat,
+    model: &str,
+) -> Option<StreamPluginHooks> {
+    let plugins = state.processor.plugins.clone();
+    if plugins.count() == 0 {
+        return None;
+    }
+    let request_id = flow_id
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let ctx = PluginContext::new(request_id, provider, model.to_string());
+    Some(StreamPluginHooks::new(plugins, ctx, target_format))
+}
+
+/// 为流挂载插件钩子（如果有）
+fn attach_plugin_hooks(
+    stream: BoxStream<'static, Result<String, StreamError>>,
+    hooks: Option<StreamPluginHooks>,
+) -> BoxStream<'static, Result<String, StreamError>> {
+    match hooks {
+        Some(hooks) => with_plugin_hooks(stream, hooks),
+        None => stream,
+    }
+}
+
 /// 处理流式响应
 ///
 /// 使用 StreamManager 处理流式响应，集成 Flow Monitor。
@@ -1004,6 +1447,7 @@ pub fn get_stream_format_for_credential(credential: &ProviderCredential) -> Stre
 /// # 参数
 /// - `state`: 应用状态
 /// - `flow_id`: Flow ID（用于 Flow Monitor 集成）
+/// - `provider`: 处理请求的凭证类型（`ProviderCredential::provider_type`）
 /// - `source_stream`: 源字节流
 /// - `source_format`: 源流格式
 /// - `target_format`: 目标流格式
@@ -1018,6 +1462,7 @@ pub fn get_stream_format_for_credential(credential: &ProviderCredential) -> Stre
 pub async fn handle_streaming_response(
     state: &AppState,
     flow_id: Option<&str>,
+    provider: ProviderType,
     source_stream: StreamResponse,
     source_format: StreamingFormat,
     target_format: StreamingFormat,
@@ -1034,6 +1479,8 @@ pub async fn handle_streaming_response(
         model,
     );
 
+    let plugin_hooks = stream_plugin_hooks(state, flow_id, provider, target_format, model);
+
     // 获取 flow_id 的克隆用于回调
     let flow_id_for_callback = flow_id.map(|s| s.to_string());
     let flow_monitor = state.flow_monitor.clone();
@@ -1072,6 +1519,7 @@ pub async fn handle_streaming_response(
         };
 
         let stream = manager.handle_stream_with_callback(context, source_stream, on_chunk);
+        let stream = attach_plugin_hooks(Box::pin(stream), plugin_hooks);
 
         // 转换为 Body 流
         let body_stream = stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
@@ -1085,6 +1533,7 @@ pub async fn handle_streaming_response(
     } else {
         // 没有 flow_id，使用普通流式处理
         let stream = manager.handle_stream(context, source_stream);
+        let stream = attach_plugin_hooks(Box::pin(stream), plugin_hooks);
 
         let body_stream = stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
             match result {
@@ -1122,6 +1571,7 @@ pub async fn handle_streaming_response(
 /// # 参数
 /// - `state`: 应用状态
 /// - `flow_id`: Flow ID
+/// - `provider`: 处理请求的凭证类型（`ProviderCredential::provider_type`）
 /// - `source_stream`: 源字节流
 /// - `source_format`: 源流格式
 /// - `target_format`: 目标流格式
@@ -1137,14 +1587,13 @@ pub async fn handle_streaming_response(
 pub async fn handle_streaming_response_with_timeout(
     state: &AppState,
     flow_id: Option<&str>,
+    provider: ProviderType,
     source_stream: StreamResponse,
     source_format: StreamingFormat,
     target_format: StreamingFormat,
     model: &str,
     timeout_ms: u64,
 ) -> Response {
-    use futures::stream::BoxStream;
-
     // 创建带超时配置的流式管理器
     let config = StreamConfig::new()
         .with_timeout_ms(timeout_ms)
@@ -1160,6 +1609,8 @@ pub async fn handle_streaming_response_with_timeout(
         model,
     );
 
+    let plugin_hooks = stream_plugin_hooks(state, flow_id, provider, target_format, model);
+
     // 获取 flow_id 的克隆用于回调
     let flow_id_for_callback = flow_id.map(|s| s.to_string());
     let flow_monitor = state.flow_monitor.clone();
@@ -1201,6 +1652,8 @@ pub async fn handle_streaming_response_with_timeout(
             Box::pin(crate::streaming::with_timeout(stream, &config))
         };
 
+    let timeout_stream = attach_plugin_hooks(timeout_stream, plugin_hooks);
+
     // 转换为 Body 流
     let body_stream = timeout_stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
         match result {
@@ -1255,6 +1708,7 @@ pub fn response_to_stream(response: reqwest::Response) -> StreamResponse {
 /// # 参数
 /// - `state`: 应用状态
 /// - `flow_id`: Flow ID
+/// - `provider`: 处理请求的凭证类型（`ProviderCredential::provider_type`）
 /// - `source_stream`: 源字节流
 /// - `source_format`: 源流格式
 /// - `target_format`: 目标流格式
@@ -1269,6 +1723,7 @@ pub fn response_to_stream(response: reqwest::Response) -> StreamResponse {
 pub async fn handle_streaming_with_disconnect_detection(
     state: &AppState,
     flow_id: Option<&str>,
+    provider: ProviderType,
     source_stream: StreamResponse,
     source_format: StreamingFormat,
     target_format: StreamingFormat,
@@ -1292,6 +1747,7 @@ pub async fn handle_streaming_with_disconnect_detection(
     let flow_id_for_callback = flow_id.map(|s| s.to_string());
     let flow_id_for_cancel = flow_id.map(|s| s.to_string());
     let flow_monitor = state.flow_monitor.clone();
+    let plugin_hooks = stream_plugin_hooks(state, flow_id, provider, target_format, model);
     let flow_monitor_for_cancel = state.flow_monitor.clone();
 
     // 创建带回调的流式处理
@@ -1333,6 +1789,8 @@ pub async fn handle_streaming_with_disconnect_detection(
         Box::pin(manager.handle_stream(context, source_stream))
     };
 
+    let managed_stream = attach_plugin_hooks(managed_stream, plugin_hooks);
+
     // 如果有取消令牌，创建一个可取消的流
     let body_stream = if let Some(token) = cancel_token {
         // 创建一个可取消的流
@@ -0,0 +1,41 @@
+//! 多实例共享状态管理 API 处理器
+//!
+//! - `GET /v0/management/shared-state`：共享后端、实例标识、集群计数器与冷却记录
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::server::AppState;
+
+/// GET /v0/management/shared-state - 获取共享状态快照
+pub async fn management_get_shared_state(State(state): State<AppState>) -> Response {
+    let Some(shared) = &state.shared_state else {
+        return Json(serde_json::json!({
+            "enabled": false,
+            "backend": "memory",
+        }))
+        .into_response();
+    };
+    match shared.snapshot().await {
+        Ok(snapshot) => Json(serde_json::json!({
+            "enabled": true,
+            "backend": snapshot.backend,
+            "node_id": snapshot.node_id,
+            "key_prefix": snapshot.key_prefix,
+            "counters": snapshot.counters,
+            "cooldowns": snapshot.cooldowns,
+        }))
+        .into_response(),
+        Err(e) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({
+                "error": {"type": "shared_state_unavailable", "message": e.to_string()}
+            })),
+        )
+            .into_response(),
+    }
+}
@@ -0,0 +1,433 @@
+//! Flow 回归套件管理 API 处理器
+//!
+//! 套件的增删改查，以及 `POST /v0/management/suites/:id/run`：在指定的
+//! Provider / 模型 / 凭证上重新执行套件中的 Flow，返回 JSON、JUnit XML 或
+//! Markdown 报告。每次执行记录为带 `regression`、`suite:<id>` 标签的新 Flow，
+//! 可与原始 Flow 逐项对比。
+
+use async_trait::async_trait;
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use tokio::time::Instant;
+
+use crate::flow_monitor::{
+    parse_response_body, regression_flow_tags, resolve_suite_flows, run_suite, validate_target,
+    FlowAnnotations, FlowError, FlowErrorType, FlowSuite, FlowType, LLMFlow, LLMRequest,
+    LLMResponse, SuiteDefinition, SuiteError, SuiteExecution, SuiteExecutor, SuiteTarget,
+};
+use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::openai::ChatCompletionRequest;
+use crate::models::provider_pool_model::ProviderCredential;
+use crate::processor::RequestContext;
+use crate::server::AppState;
+use crate::ProviderType;
+
+use super::api::{
+    build_flow_metadata, build_llm_request_from_anthropic, build_llm_request_from_openai,
+};
+use super::{background_queue_class, call_provider_anthropic, call_provider_openai};
+
+/// 套件运行查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct SuiteRunQuery {
+    /// 报告格式：`json`（默认）、`junit`、`markdown`
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+/// 套件运行请求体（可选）：覆盖套件的执行目标
+#[derive(Debug, Default, Deserialize)]
+pub struct SuiteRunRequest {
+    #[serde(default)]
+    pub target: SuiteTarget,
+}
+
+fn suite_error(status: StatusCode, error_type: &str, message: String) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {"type": error_type, "message": message}
+        })),
+    )
+        .into_response()
+}
+
+impl IntoResponse for SuiteError {
+    fn into_response(self) -> Response {
+        let (status, error_type) = match &self {
+            SuiteError::SuiteNotFound(_) | SuiteError::FlowNotFound(_) => {
+                (StatusCode::NOT_FOUND, "not_found")
+            }
+            SuiteError::InvalidSuite(_) | SuiteError::InvalidFilter(_) => {
+                (StatusCode::BAD_REQUEST, "invalid_request")
+            }
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+        };
+        suite_error(status, error_type, self.to_string())
+    }
+}
+
+/// GET /v0/management/suites - 列出套件
+pub async fn management_list_suites(State(state): State<AppState>) -> Response {
+    match state.flow_suites.list() {
+        Ok(suites) => Json(serde_json::json!({ "suites": suites })).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// POST /v0/management/suites - 创建套件
+pub async fn management_create_suite(
+    State(state): State<AppState>,
+    Json(definition): Json<SuiteDefinition>,
+) -> Response {
+    match state.flow_suites.create(definition) {
+        Ok(suite) => (StatusCode::CREATED, Json(suite)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// GET /v0/management/suites/:id - 获取套件
+pub async fn management_get_suite(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    match state.flow_suites.get(&id) {
+        Ok(Some(suite)) => Json(suite).into_response(),
+        Ok(None) => SuiteError::SuiteNotFound(id).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// PUT /v0/management/suites/:id - 更新套件
+pub async fn management_update_suite(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(definition): Json<SuiteDefinition>,
+) -> Response {
+    match state.flow_suites.update(&id, definition) {
+        Ok(suite) => Json(suite).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// DELETE /v0/management/suites/:id - 删除套件
+pub async fn management_delete_suite(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    match state.flow_suites.delete(&id) {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => SuiteError::SuiteNotFound(id).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// POST /v0/management/suites/:id/run - 运行套件
+///
+/// 请求体可选，`target` 中已设置的字段覆盖套件默认目标。
+/// 默认返回 JSON 报告；`?format=junit|markdown` 返回对应文本报告。
+pub async fn management_run_suite(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<SuiteRunQuery>,
+    body: Option<Json<SuiteRunRequest>>,
+) -> Response {
+    let content_type = match query.format.as_deref() {
+        None | Some("json") => None,
+        Some("junit") => Some("application/xml; charset=utf-8"),
+        Some("markdown") => Some("text/markdown; charset=utf-8"),
+        Some(other) => {
+            return suite_error(
+                StatusCode::BAD_REQUEST,
+                "invalid_request",
+                format!("未知的报告格式: {}", other),
+            )
+        }
+    };
+
+    let suite = match state.flow_suites.get(&id) {
+        Ok(Some(suite)) => suite,
+        Ok(None) => return SuiteError::SuiteNotFound(id).into_response(),
+        Err(e) => return e.into_response(),
+    };
+    let overrides = body.map(|Json(b)| b.target).unwrap_or_default();
+    let target = suite.definition.target.overridden_by(&overrides);
+    if let Err(e) = validate_target(&target) {
+        return e.into_response();
+    }
+
+    let flows = {
+        let memory = state.flow_monitor.memory_store();
+        let memory = memory.read().await;
+        let file_store = state.flow_monitor.file_store();
+        resolve_suite_flows(&suite.definition, &memory, |flow_id| {
+            file_store
+                .as_ref()
+                .and_then(|store| store.get(flow_id).ok().flatten())
+        })
+    };
+    let flows = match flows {
+        Ok(flows) => flows,
+        Err(e) => return e.into_response(),
+    };
+
+    let executor = AppStateSuiteExecutor::new(state.clone());
+    let report = run_suite(&suite, &target, &flows, &executor).await;
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[SUITE] suite={} target={} total={} passed={} failed={} errors={}",
+            suite.id,
+            target.describe(),
+            report.total,
+            report.passed,
+            report.failed,
+            report.errors
+        ),
+    );
+
+    match content_type {
+        None => Json(report).into_response(),
+        Some(content_type) => {
+            let body = if query.format.as_deref() == Some("junit") {
+                report.to_junit_xml()
+            } else {
+                report.to_markdown()
+            };
+            ([(header::CONTENT_TYPE, content_type)], body).into_response()
+        }
+    }
+}
+
+/// 基于 AppState 的套件执行器
+///
+/// 按原始 Flow 的请求体重建非流式请求，经过路由解析后发往目标 Provider，
+/// 并把本次执行记录为回归 Flow。
+pub struct AppStateSuiteExecutor {
+    state: AppState,
+}
+
+/// 回归请求
+enum SuiteRequest {
+    OpenAi(ChatCompletionRequest),
+    Anthropic(AnthropicMessagesRequest),
+}
+
+impl SuiteRequest {
+    fn from_flow(flow: &LLMFlow) -> Result<Self, String> {
+        let body = flow.request.body.clone();
+        match flow.flow_type {
+            FlowType::ChatCompletions => serde_json::from_value(body)
+                .map(SuiteRequest::OpenAi)
+                .map_err(|e| format!("无法解析 OpenAI 请求: {}", e)),
+            FlowType::AnthropicMessages => serde_json::from_value(body)
+                .map(SuiteRequest::Anthropic)
+                .map_err(|e| format!("无法解析 Anthropic 请求: {}", e)),
+            ref other => Err(format!("不支持回归执行的 Flow 类型: {:?}", other)),
+        }
+    }
+
+    fn model(&self) -> &str {
+        match self {
+            SuiteRequest::OpenAi(r) => &r.model,
+            SuiteRequest::Anthropic(r) => &r.model,
+        }
+    }
+
+    fn prepare(&mut self, model: String) {
+        match self {
+            SuiteRequest::OpenAi(r) => {
+                r.stream = false;
+                r.model = model;
+            }
+            SuiteRequest::Anthropic(r) => {
+                r.stream = false;
+                r.model = model;
+            }
+        }
+    }
+
+    fn flow_type(&self) -> FlowType {
+        match self {
+            SuiteRequest::OpenAi(_) => FlowType::ChatCompletions,
+            SuiteRequest::Anthropic(_) => FlowType::AnthropicMessages,
+        }
+    }
+
+    fn llm_request(&self, headers: &HeaderMap) -> LLMRequest {
+        match self {
+            SuiteRequest::OpenAi(r) => {
+                build_llm_request_from_openai(r, "/v1/chat/completions", headers)
+            }
+            SuiteRequest::Anthropic(r) => {
+                build_llm_request_from_anthropic(r, "/v1/messages", headers)
+            }
+        }
+    }
+
+    async fn call(&self, state: &AppState, cred: &ProviderCredential) -> Response {
+        match self {
+            SuiteRequest::OpenAi(r) => {
+                call_provider_openai(state, cred, r, None, &background_queue_class(state)).await
+            }
+            SuiteRequest::Anthropic(r) => {
+                call_provider_anthropic(state, cred, r, None, &background_queue_class(state)).await
+            }
+        }
+    }
+}
+
+impl AppStateSuiteExecutor {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// 选择凭证：指定凭证时直接使用，否则从凭证池选择未处于配额冷却期的凭证
+    fn select_credential(
+        &self,
+        target: &SuiteTarget,
+        provider: &str,
+        model: &str,
+    ) -> Result<ProviderCredential, String> {
+        let state = &self.state;
+        let db = state
+            .db
+            .as_ref()
+            .ok_or_else(|| "Credential pool is not available".to_string())?;
+        match &target.credential_id {
+            Some(uuid) => state
+                .pool_service
+                .get_by_uuid(db, uuid)?
+                .ok_or_else(|| format!("Credential not found: {}", uuid)),
+            None => {
+                let quota = &state.quota_manager;
+                state
+                    .pool_service
+                    .select_credential_filtered(db, provider, Some(model), |cred| {
+                        quota.is_available(&cred.uuid)
+                    })?
+                    .ok_or_else(|| format!("No available credentials for provider `{}`", provider))
+            }
+        }
+    }
+
+    /// 发送请求并把结果转换为响应
+    async fn send(
+        &self,
+        request: &SuiteRequest,
+        cred: &ProviderCredential,
+    ) -> Result<LLMResponse, String> {
+        let response = request.call(&self.state, cred).await;
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .map_err(|e| e.to_string())?;
+        let text = String::from_utf8_lossy(&bytes);
+        if !status.is_success() {
+            let message = serde_json::from_str::<serde_json::Value>(&text)
+                .ok()
+                .and_then(|body| body["error"]["message"].as_str().map(str::to_string))
+                .unwrap_or_else(|| text.into_owned());
+            return Err(format!("HTTP {}: {}", status.as_u16(), message));
+        }
+        let mut llm_response = parse_response_body(&request.flow_type(), &text);
+        llm_response.status_code = status.as_u16();
+        llm_response.size_bytes = bytes.len();
+        Ok(llm_response)
+    }
+}
+
+#[async_trait]
+impl SuiteExecutor for AppStateSuiteExecutor {
+    async fn execute(
+        &self,
+        suite: &FlowSuite,
+        flow: &LLMFlow,
+        target: &SuiteTarget,
+    ) -> SuiteExecution {
+        let state = &self.state;
+        let mut request = match SuiteRequest::from_flow(flow) {
+            Ok(request) => request,
+            Err(e) => {
+                return SuiteExecution {
+                    run_flow_id: None,
+                    model: flow.request.model.clone(),
+                    response: Err(e),
+                    latency_ms: 0,
+                }
+            }
+        };
+
+        let requested_model = target
+            .model
+            .clone()
+            .unwrap_or_else(|| request.model().to_string());
+        let mut ctx = RequestContext::new(requested_model).with_stream(false);
+        let routed_provider = state.processor.resolve_and_route(&mut ctx).await;
+        request.prepare(ctx.resolved_model.clone());
+
+        let provider = match &target.provider {
+            Some(provider) => provider.clone(),
+            None => state.default_provider.read().await.clone(),
+        };
+        let credential = self.select_credential(target, &provider, request.model());
+
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let headers = HeaderMap::new();
+        let cred = credential.as_ref().ok();
+        let metadata = build_flow_metadata(
+            provider.parse::<ProviderType>().unwrap_or(routed_provider),
+            cred.map(|c| c.uuid.as_str()),
+            cred.and_then(|c| c.name.as_deref()),
+            &headers,
+            &request_id,
+        );
+        let run_flow_id = state
+            .flow_monitor
+            .start_flow(request.llm_request(&headers), metadata)
+            .await;
+
+        let started = Instant::now();
+        let response = match &credential {
+            Ok(cred) => self.send(&request, cred).await,
+            Err(e) => Err(e.clone()),
+        };
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        if let Some(run_flow_id) = &run_flow_id {
+            match &response {
+                Ok(llm_response) => {
+                    state
+                        .flow_monitor
+                        .complete_flow(run_flow_id, Some(llm_response.clone()))
+                        .await;
+                }
+                Err(message) => {
+                    let error = FlowError::new(FlowErrorType::ServerError, message.clone());
+                    state.flow_monitor.fail_flow(run_flow_id, error).await;
+                }
+            }
+            let annotations = FlowAnnotations {
+                comment: Some(format!("回归自 Flow: {}", flow.id)),
+                tags: regression_flow_tags(&suite.id),
+                ..Default::default()
+            };
+            state
+                .flow_monitor
+                .update_annotations(run_flow_id, annotations)
+                .await;
+        }
+
+        SuiteExecution {
+            run_flow_id,
+            model: ctx.resolved_model,
+            response,
+            latency_ms,
+        }
+    }
+}
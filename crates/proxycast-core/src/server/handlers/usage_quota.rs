@@ -0,0 +1,72 @@
+//! 上游用量配额管理 API 处理器
+//!
+//! - `GET /v0/management/credentials/quota`：用量配额配置与各凭证的剩余配额预测
+//! - `PUT /v0/management/credentials/quota/config`：更新轮询与选择策略
+//! - `POST /v0/management/credentials/quota/poll`：立即查询一次用量
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::config::UsageQuotaConfig;
+use crate::server::AppState;
+
+fn usage_quota_error(status: StatusCode, error_type: &str, message: String) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {"type": error_type, "message": message}
+        })),
+    )
+        .into_response()
+}
+
+/// GET /v0/management/credentials/quota - 剩余配额预测
+pub async fn management_get_usage_quota(State(state): State<AppState>) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "config": state.usage_quota.config(),
+        "forecasts": state.usage_quota.forecasts(),
+    }))
+}
+
+/// PUT /v0/management/credentials/quota/config - 更新用量配额配置
+pub async fn management_update_usage_quota_config(
+    State(state): State<AppState>,
+    Json(config): Json<UsageQuotaConfig>,
+) -> Response {
+    if !(0.0..=1.0).contains(&config.min_remaining_ratio) {
+        return usage_quota_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            "min_remaining_ratio 必须在 0.0 - 1.0 之间".to_string(),
+        );
+    }
+    tracing::info!(
+        "[USAGE_QUOTA] 更新配置: enabled={}, strategy={:?}",
+        config.enabled,
+        config.strategy
+    );
+    state.usage_quota.set_config(config.clone());
+    Json(config).into_response()
+}
+
+/// POST /v0/management/credentials/quota/poll - 立即查询用量
+pub async fn management_poll_usage_quota(State(state): State<AppState>) -> Response {
+    let Some(db) = state.db.as_ref() else {
+        return usage_quota_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_unavailable",
+            "数据库连接不可用".to_string(),
+        );
+    };
+
+    let polled = state.usage_quota.poll_once(db, &state.token_cache).await;
+    Json(serde_json::json!({
+        "polled": polled,
+        "forecasts": state.usage_quota.forecasts(),
+    }))
+    .into_response()
+}
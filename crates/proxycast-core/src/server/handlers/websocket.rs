@@ -6,13 +6,15 @@ use axum::{
     body::Body,
     extract::{
         ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
-        Query, State,
+        ConnectInfo, Query, State,
     },
-    http::HeaderMap,
-    response::IntoResponse,
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
 };
+use futures::stream::SplitSink;
 use futures::{SinkExt, StreamExt as FuturesStreamExt};
 use serde::Deserialize;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -20,6 +22,7 @@ use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use crate::converter::openai_to_antigravity::{
     convert_antigravity_to_openai_response, convert_openai_to_antigravity_with_context,
 };
+use crate::middleware::verify_management_key;
 use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::ChatCompletionRequest;
 use crate::models::provider_pool_model::ProviderCredential;
@@ -27,12 +30,32 @@ use crate::processor::RequestContext;
 use crate::providers::{
     AntigravityProvider, ClaudeCustomProvider, KiroProvider, OpenAICustomProvider,
 };
+use crate::server::handlers::provider_calls::{
+    acquire_upstream_slot, call_provider_anthropic, call_provider_openai, upstream_queue_class,
+    CancellableStream,
+};
 use crate::server::AppState;
 use crate::server_utils::parse_cw_response;
+use crate::streaming::StreamError;
 use crate::websocket::{
-    WsApiRequest, WsApiResponse, WsEndpoint, WsError, WsFlowEvent, WsMessage as WsProtoMessage,
+    InFlightGuard, InFlightRequests, MessageProcessor, StreamForwarder, WsApiRequest,
+    WsApiResponse, WsEndpoint, WsError, WsFlowEvent, WsInterceptEvent, WsMessage as WsProtoMessage,
 };
 
+/// WebSocket 发送端（多个任务共享）
+type WsSender = Arc<Mutex<SplitSink<WebSocket, WsMessage>>>;
+
+/// 发送协议消息，连接已关闭时返回 false
+async fn send_ws_message(sender: &WsSender, msg: &WsProtoMessage) -> bool {
+    let text = serde_json::to_string(msg).unwrap_or_default();
+    sender
+        .lock()
+        .await
+        .send(WsMessage::Text(text))
+        .await
+        .is_ok()
+}
+
 /// WebSocket 查询参数
 #[derive(Debug, Deserialize, Default)]
 pub struct WsQueryParams {
@@ -40,6 +63,8 @@ pub struct WsQueryParams {
     pub api_key: Option<String>,
     /// Token（通过 URL 参数传递，与 api_key 等效）
     pub token: Option<String>,
+    /// 管理密钥（订阅拦截事件需要，无法设置 `X-Management-Key` 请求头时使用）
+    pub management_key: Option<String>,
 }
 
 /// WebSocket 升级处理器
@@ -47,6 +72,7 @@ pub async fn ws_upgrade_handler(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
     Query(params): Query<WsQueryParams>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     headers: HeaderMap,
 ) -> impl IntoResponse {
     // 验证 API 密钥：优先从 header 获取，其次从 URL 参数获取
@@ -88,7 +114,30 @@ pub async fn ws_upgrade_handler(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
-    ws.on_upgrade(move |socket| handle_websocket(socket, state, client_info, authenticated))
+    // 拦截事件包含完整的请求/响应内容，与 HTTP 拦截 API 一样要求管理密钥
+    let management_key = headers
+        .get("x-management-key")
+        .and_then(|v| v.to_str().ok())
+        .or(params.management_key.as_deref());
+    let management = verify_management_key(
+        &state.management_config,
+        management_key,
+        connect_info.map(|ConnectInfo(addr)| addr),
+    );
+
+    // 连接上的 API 请求共用同一优先级类别
+    let queue_class = upstream_queue_class(&state, &headers);
+
+    ws.on_upgrade(move |socket| {
+        handle_websocket(
+            socket,
+            state,
+            client_info,
+            authenticated,
+            management,
+            queue_class,
+        )
+    })
 }
 
 /// 处理 WebSocket 连接
@@ -97,6 +146,8 @@ pub async fn handle_websocket(
     state: AppState,
     client_info: Option<String>,
     authenticated: bool,
+    management: bool,
+    queue_class: String,
 ) {
     let conn_id = uuid::Uuid::new_v4().to_string();
 
@@ -183,6 +234,66 @@ pub async fn handle_websocket(
         }
     });
 
+    // 拦截事件订阅状态
+    let intercept_subscribed = Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+    // 启动拦截事件转发任务
+    let intercept_sender = sender.clone();
+    let intercept_subscribed_clone = intercept_subscribed.clone();
+    let flow_interceptor = state.flow_interceptor.clone();
+    let conn_id_clone = conn_id.clone();
+
+    let intercept_task = tokio::spawn(async move {
+        let mut intercept_receiver = flow_interceptor.subscribe();
+
+        loop {
+            match intercept_receiver.recv().await {
+                Ok(event) => {
+                    if !intercept_subscribed_clone.load(std::sync::atomic::Ordering::Relaxed) {
+                        continue;
+                    }
+
+                    let ws_event: WsInterceptEvent = event.into();
+                    let ws_msg = WsProtoMessage::InterceptEvent(ws_event);
+
+                    if let Ok(msg_text) = serde_json::to_string(&ws_msg) {
+                        let mut sender_guard = intercept_sender.lock().await;
+                        if sender_guard.send(WsMessage::Text(msg_text)).await.is_err() {
+                            tracing::debug!(
+                                "[WS] Intercept event send failed for connection {}",
+                                &conn_id_clone[..8]
+                            );
+                            break;
+                        }
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!(
+                        "[WS] Intercept event receiver lagged by {} messages for connection {}",
+                        n,
+                        &conn_id_clone[..8]
+                    );
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    let queue_class: Arc<str> = queue_class.into();
+
+    let subscriptions = WsSubscriptions {
+        flow: flow_subscribed,
+        intercept: intercept_subscribed,
+        management,
+    };
+
+    // 进行中的 API 请求（支持取消、并发限制与流式额度）
+    let ws_config = state.ws_manager.config();
+    let inflight = Arc::new(InFlightRequests::new(
+        ws_config.max_concurrent_requests,
+        std::time::Duration::from_secs(ws_config.credit_wait_timeout_secs),
+    ));
+
     // 消息处理循环
     while let Some(msg) = receiver.next().await {
         match msg {
@@ -192,16 +303,18 @@ pub async fn handle_websocket(
 
                 match serde_json::from_str::<WsProtoMessage>(&text) {
                     Ok(ws_msg) => {
-                        let response =
-                            handle_ws_message(&state, &conn_id, ws_msg, &flow_subscribed).await;
+                        let response = handle_ws_message(
+                            &state,
+                            &conn_id,
+                            ws_msg,
+                            &subscriptions,
+                            &sender,
+                            &inflight,
+                            &queue_class,
+                        )
+                        .await;
                         if let Some(resp) = response {
-                            let resp_text = serde_json::to_string(&resp).unwrap_or_default();
-                            let mut sender_guard = sender.lock().await;
-                            if sender_guard
-                                .send(WsMessage::Text(resp_text.into()))
-                                .await
-                                .is_err()
-                            {
+                            if !send_ws_message(&sender, &resp).await {
                                 break;
                             }
                         }
@@ -261,8 +374,19 @@ pub async fn handle_websocket(
         }
     }
 
-    // 取消 Flow 事件转发任务
+    // 取消仍在进行的上游请求，避免连接断开后继续占用上游
+    if !inflight.is_empty() {
+        tracing::info!(
+            "[WS] Connection {} closed with {} in-flight requests, cancelling",
+            &conn_id[..8],
+            inflight.len()
+        );
+    }
+    inflight.cancel_all();
+
+    // 取消事件转发任务
     flow_task.abort();
+    intercept_task.abort();
 
     // 清理连接
     state.ws_manager.unregister(&conn_id);
@@ -272,13 +396,27 @@ pub async fn handle_websocket(
     );
 }
 
+/// 连接的事件订阅状态
+struct WsSubscriptions {
+    /// 是否订阅 Flow 事件
+    flow: Arc<std::sync::atomic::AtomicBool>,
+    /// 是否订阅拦截事件
+    intercept: Arc<std::sync::atomic::AtomicBool>,
+    /// 连接是否携带有效的管理密钥（订阅拦截事件需要）
+    management: bool,
+}
+
 /// 处理 WebSocket 消息
 async fn handle_ws_message(
     state: &AppState,
     conn_id: &str,
     msg: WsProtoMessage,
-    flow_subscribed: &Arc<std::sync::atomic::AtomicBool>,
+    subscriptions: &WsSubscriptions,
+    sender: &WsSender,
+    inflight: &Arc<InFlightRequests>,
+    queue_class: &Arc<str>,
 ) -> Option<WsProtoMessage> {
+    let flow_subscribed = &subscriptions.flow;
     match msg {
         WsProtoMessage::Ping { timestamp } => Some(WsProtoMessage::Pong { timestamp }),
         WsProtoMessage::Pong { .. } => None,
@@ -338,9 +476,42 @@ async fn handle_ws_message(
                 ),
             );
 
-            // 处理 API 请求
-            let response = handle_ws_api_request(state, &request).await;
-            Some(response)
+            let guard = match inflight.begin(&request.request_id, request.stream_credits) {
+                Ok(guard) => guard,
+                Err(e) => return Some(WsProtoMessage::Error(e)),
+            };
+
+            // 在独立任务中处理 API 请求，使连接可以继续接收取消与额度消息
+            let state = state.clone();
+            let sender = sender.clone();
+            let queue_class = queue_class.clone();
+            tokio::spawn(async move {
+                let cancel_token = guard.cancel_token().clone();
+                let response = tokio::select! {
+                    _ = cancel_token.cancelled() => {
+                        tracing::info!("[WS] Request {} cancelled", guard.request_id());
+                        WsProtoMessage::Error(WsError::cancelled(guard.request_id()))
+                    }
+                    response = handle_ws_api_request(&state, &request, &guard, &sender, &queue_class) => response,
+                };
+                send_ws_message(&sender, &response).await;
+            });
+            None
+        }
+        WsProtoMessage::Cancel { request_id } => {
+            if inflight.cancel(&request_id) {
+                None
+            } else {
+                Some(WsProtoMessage::Error(WsError::invalid_request(
+                    Some(request_id.clone()),
+                    format!("No in-flight request with id {}", request_id),
+                )))
+            }
+        }
+        WsProtoMessage::Credit(credit) => {
+            // 请求可能刚好结束，忽略未知请求的额度
+            inflight.grant(&credit.request_id, credit.credits);
+            None
         }
         WsProtoMessage::Response(_)
         | WsProtoMessage::StreamChunk(_)
@@ -375,11 +546,58 @@ async fn handle_ws_message(
                 "KiroCredentialEvent messages are server-to-client only",
             )))
         }
+        WsProtoMessage::SubscribeInterceptEvents => {
+            // 拦截事件包含完整的请求/响应内容，仅允许携带管理密钥的连接订阅
+            if !subscriptions.management {
+                return Some(WsProtoMessage::Error(WsError::unauthorized(
+                    "Intercept events require the management key",
+                )));
+            }
+            subscriptions
+                .intercept
+                .store(true, std::sync::atomic::Ordering::Relaxed);
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[WS] Connection {} subscribed to intercept events",
+                    &conn_id[..8]
+                ),
+            );
+            Some(WsProtoMessage::Response(WsApiResponse {
+                request_id: "subscribe_intercept_events".to_string(),
+                payload: serde_json::json!({
+                    "status": "subscribed",
+                    "message": "Successfully subscribed to intercept events",
+                    "pending": state.flow_interceptor.list_intercepted_flows().await
+                }),
+            }))
+        }
+        WsProtoMessage::UnsubscribeInterceptEvents => {
+            subscriptions
+                .intercept
+                .store(false, std::sync::atomic::Ordering::Relaxed);
+            Some(WsProtoMessage::Response(WsApiResponse {
+                request_id: "unsubscribe_intercept_events".to_string(),
+                payload: serde_json::json!({
+                    "status": "unsubscribed",
+                    "message": "Successfully unsubscribed from intercept events"
+                }),
+            }))
+        }
+        WsProtoMessage::InterceptEvent(_) => Some(WsProtoMessage::Error(WsError::invalid_message(
+            "InterceptEvent messages are server-to-client only",
+        ))),
     }
 }
 
 /// 处理 WebSocket API 请求
-async fn handle_ws_api_request(state: &AppState, request: &WsApiRequest) -> WsProtoMessage {
+async fn handle_ws_api_request(
+    state: &AppState,
+    request: &WsApiRequest,
+    guard: &InFlightGuard,
+    sender: &WsSender,
+    queue_class: &str,
+) -> WsProtoMessage {
     match request.endpoint {
         WsEndpoint::Models => {
             // 返回模型列表
@@ -403,7 +621,8 @@ async fn handle_ws_api_request(state: &AppState, request: &WsApiRequest) -> WsPr
             // 解析 ChatCompletionRequest
             match serde_json::from_value::<ChatCompletionRequest>(request.payload.clone()) {
                 Ok(chat_request) => {
-                    handle_ws_chat_completions(state, &request.request_id, chat_request).await
+                    handle_ws_chat_completions(state, guard, sender, chat_request, queue_class)
+                        .await
                 }
                 Err(e) => WsProtoMessage::Error(WsError::invalid_request(
                     Some(request.request_id.clone()),
@@ -415,7 +634,14 @@ async fn handle_ws_api_request(state: &AppState, request: &WsApiRequest) -> WsPr
             // 解析 AnthropicMessagesRequest
             match serde_json::from_value::<AnthropicMessagesRequest>(request.payload.clone()) {
                 Ok(messages_request) => {
-                    handle_ws_anthropic_messages(state, &request.request_id, messages_request).await
+                    handle_ws_anthropic_messages(
+                        state,
+                        guard,
+                        sender,
+                        messages_request,
+                        queue_class,
+                    )
+                    .await
                 }
                 Err(e) => WsProtoMessage::Error(WsError::invalid_request(
                     Some(request.request_id.clone()),
@@ -429,8 +655,10 @@ async fn handle_ws_api_request(state: &AppState, request: &WsApiRequest) -> WsPr
 /// 处理 WebSocket chat completions 请求
 async fn handle_ws_chat_completions(
     state: &AppState,
-    request_id: &str,
+    guard: &InFlightGuard,
+    sender: &WsSender,
     mut request: ChatCompletionRequest,
+    queue_class: &str,
 ) -> WsProtoMessage {
     // 创建请求上下文
     let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
@@ -469,6 +697,22 @@ async fn handle_ws_chat_completions(
         None => None,
     };
 
+    let request_id = guard.request_id();
+
+    // 流式请求复用 HTTP 调用链，将 SSE 输出转为流式消息
+    if request.stream {
+        if let Some(cred) = &credential {
+            let response = call_provider_openai(state, cred, &request, None, queue_class).await;
+            return forward_ws_stream(guard, sender, response).await;
+        }
+    }
+
+    // 非流式请求整体缓冲响应，处理器返回时归还上游槽位
+    let _queue_permit = match acquire_upstream_slot(state, queue_class, None).await {
+        Ok(permit) => permit,
+        Err(response) => return forward_ws_stream(guard, sender, response).await,
+    };
+
     // 如果找到凭证，使用它调用 API
     if let Some(cred) = credential {
         // 简化实现：直接调用 provider 并返回结果
@@ -562,8 +806,10 @@ async fn handle_ws_chat_completions(
 /// 处理 WebSocket anthropic messages 请求
 async fn handle_ws_anthropic_messages(
     state: &AppState,
-    request_id: &str,
+    guard: &InFlightGuard,
+    sender: &WsSender,
     mut request: AnthropicMessagesRequest,
+    queue_class: &str,
 ) -> WsProtoMessage {
     // 创建请求上下文
     let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
@@ -602,6 +848,22 @@ async fn handle_ws_anthropic_messages(
         None => None,
     };
 
+    let request_id = guard.request_id();
+
+    // 流式请求复用 HTTP 调用链，将 SSE 输出转为流式消息
+    if request.stream {
+        if let Some(cred) = &credential {
+            let response = call_provider_anthropic(state, cred, &request, None, queue_class).await;
+            return forward_ws_stream(guard, sender, response).await;
+        }
+    }
+
+    // 非流式请求整体缓冲响应，处理器返回时归还上游槽位
+    let _queue_permit = match acquire_upstream_slot(state, queue_class, None).await {
+        Ok(permit) => permit,
+        Err(response) => return forward_ws_stream(guard, sender, response).await,
+    };
+
     // 如果找到凭证，使用它调用 API
     if let Some(cred) = credential {
         match call_provider_anthropic_for_ws(state, &cred, &request).await {
@@ -668,6 +930,86 @@ async fn handle_ws_anthropic_messages(
     }
 }
 
+/// 将 Provider 的 HTTP 响应转发为 WebSocket 流式消息
+///
+/// SSE 响应逐块发送 `stream_chunk`，返回 `stream_end` 作为最终消息；
+/// 启用额度控制时，额度耗尽即停止读取上游。取消令牌触发后流立即结束。
+/// 非 SSE 响应（上游错误或 Provider 不支持流式）按普通响应返回。
+async fn forward_ws_stream(
+    guard: &InFlightGuard,
+    sender: &WsSender,
+    response: Response,
+) -> WsProtoMessage {
+    let request_id = guard.request_id();
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("text/event-stream"));
+
+    if !is_sse {
+        let status = response.status();
+        let body = match axum::body::to_bytes(response.into_body(), usize::MAX).await {
+            Ok(body) => body,
+            Err(e) => {
+                return WsProtoMessage::Error(WsError::internal(
+                    Some(request_id.to_string()),
+                    e.to_string(),
+                ))
+            }
+        };
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap_or_else(|_| {
+            serde_json::Value::String(String::from_utf8_lossy(&body).into_owned())
+        });
+        if status.is_success() {
+            return WsProtoMessage::Response(WsApiResponse {
+                request_id: request_id.to_string(),
+                payload,
+            });
+        }
+        let message = payload
+            .pointer("/error/message")
+            .and_then(|m| m.as_str())
+            .map(|m| m.to_string())
+            .unwrap_or_else(|| payload.to_string());
+        return WsProtoMessage::Error(WsError::upstream(
+            Some(request_id.to_string()),
+            format!("Upstream error ({}): {}", status.as_u16(), message),
+        ));
+    }
+
+    let source = response.into_body().into_data_stream().map(|chunk| {
+        chunk
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .map_err(|e| StreamError::Network(e.to_string()))
+    });
+    let source = CancellableStream::new(source, guard.cancel_token().clone());
+
+    let forwarder = StreamForwarder::new(request_id.to_string()).with_credits(guard.credits());
+    let (tx, mut rx) = forwarder.create_channel();
+
+    // 流式块直接发送，结束消息作为最终响应返回
+    let pump = async {
+        let mut end = None;
+        while let Some(msg) = rx.recv().await {
+            if matches!(msg, WsProtoMessage::StreamEnd(_)) {
+                end = Some(msg);
+            } else if !send_ws_message(sender, &msg).await {
+                break;
+            }
+        }
+        end
+    };
+
+    let (result, end) = tokio::join!(forwarder.forward_string_stream(source, tx), pump);
+    match result {
+        Ok(total_chunks) => {
+            end.unwrap_or_else(|| MessageProcessor::create_stream_end(request_id, total_chunks))
+        }
+        Err(e) => WsProtoMessage::Error(e),
+    }
+}
+
 /// WebSocket 专用的 OpenAI 格式 Provider 调用
 pub async fn call_provider_openai_for_ws(
     state: &AppState,
@@ -1,23 +1,29 @@
 //! HTTP API 服务器
 
 pub mod client_detector;
+pub mod tls;
 
+use crate::batch::{BatchManager, BatchRunner, BatchStore};
 use crate::config::{
     Config, ConfigChangeEvent, ConfigChangeKind, ConfigManager, EndpointProvidersConfig,
-    FileWatcher, HotReloadManager, ReloadResult,
+    FileWatcher, HotReloadManager, ImageConfig, ReloadResult, RemoteManagementConfig,
+    StructuredOutputConfig,
 };
 use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
-use crate::credential::CredentialSyncService;
+use crate::converter::image::ImageFetcher;
+use crate::credential::{
+    create_shared_quota_manager, CredentialSyncService, QuotaManager, UsageQuotaTracker,
+};
 use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::database::DbConnection;
-use crate::flow_monitor::{FlowInterceptor, FlowMonitor, FlowMonitorConfig};
+use crate::flow_monitor::{FlowInterceptor, FlowMonitor, FlowMonitorConfig, SuiteStore};
 use crate::injection::Injector;
 use crate::logger::LogStore;
 use crate::models::anthropic::*;
 use crate::models::openai::*;
 use crate::models::provider_pool_model::CredentialData;
-use crate::models::route_model::{RouteInfo, RouteListResponse};
-use crate::processor::{RequestContext, RequestProcessor};
+use crate::models::route_model::RouteListResponse;
+use crate::processor::{FairQueue, RequestContext, RequestProcessor, ShadowMirror};
 use crate::providers::antigravity::AntigravityProvider;
 use crate::providers::claude_custom::ClaudeCustomProvider;
 use crate::providers::gemini::GeminiProvider;
@@ -28,8 +34,12 @@ use crate::server_utils::{
     build_anthropic_response, build_anthropic_stream_response, build_gemini_native_request, health,
     models, parse_cw_response,
 };
+use crate::services::credential_health::CredentialHealthScorers;
+use crate::services::event_bus::{flow_event_to_system, EventBus, SystemEvent};
 use crate::services::kiro_event_service::KiroEventService;
+use crate::services::notification_service::NotificationService;
 use crate::services::provider_pool_service::ProviderPoolService;
+use crate::services::shared_state::{RefreshLock, SharedState};
 use crate::services::token_cache_service::TokenCacheService;
 use crate::websocket::{WsConfig, WsConnectionManager, WsStats};
 use axum::{
@@ -364,9 +374,9 @@ pub struct AppState {
     pub default_provider: Arc<RwLock<String>>,
     pub kiro: Arc<RwLock<KiroProvider>>,
     pub logs: Arc<RwLock<LogStore>>,
-    pub kiro_refresh_lock: Arc<tokio::sync::Mutex<()>>,
-    pub gemini_refresh_lock: Arc<tokio::sync::Mutex<()>>,
-    pub qwen_refresh_lock: Arc<tokio::sync::Mutex<()>>,
+    pub kiro_refresh_lock: Arc<RefreshLock>,
+    pub gemini_refresh_lock: Arc<RefreshLock>,
+    pub qwen_refresh_lock: Arc<RefreshLock>,
     pub pool_service: Arc<ProviderPoolService>,
     pub token_cache: Arc<TokenCacheService>,
     pub db: Option<DbConnection>,
@@ -394,6 +404,34 @@ pub struct AppState {
     pub endpoint_providers: Arc<RwLock<EndpointProvidersConfig>>,
     /// Kiro 事件服务
     pub kiro_event_service: Arc<KiroEventService>,
+    /// 多模态图片输入配置
+    pub image_config: Arc<RwLock<ImageConfig>>,
+    /// 远程图片下载器（按全局代理构建）
+    pub image_fetcher: Arc<ImageFetcher>,
+    /// 结构化输出配置
+    pub structured_output_config: Arc<RwLock<StructuredOutputConfig>>,
+    /// 配额管理器（记录配额超限凭证的冷却状态）
+    pub quota_manager: Arc<QuotaManager>,
+    /// 批处理任务管理器
+    pub batch_manager: Arc<BatchManager>,
+    /// 上游请求优先级队列
+    pub request_queue: Arc<FairQueue>,
+    /// 影子流量镜像器
+    pub shadow_mirror: Arc<ShadowMirror>,
+    /// Flow 回归套件存储
+    pub flow_suites: Arc<SuiteStore>,
+    /// 按 Provider 注册的凭证健康评分器
+    pub health_scorers: Arc<CredentialHealthScorers>,
+    /// 上游用量配额跟踪器
+    pub usage_quota: Arc<UsageQuotaTracker>,
+    /// 统一事件总线
+    pub event_bus: Arc<EventBus>,
+    /// 运维告警通知服务
+    pub notifications: Arc<NotificationService>,
+    /// 多实例共享状态（未配置共享后端时为 None）
+    pub shared_state: Option<Arc<SharedState>>,
+    /// 管理 API 认证配置（供 WebSocket 拦截事件订阅校验管理密钥）
+    pub management_config: Arc<RemoteManagementConfig>,
 }
 
 /// 启动配置文件监控
@@ -410,10 +448,11 @@ pub struct AppState {
 async fn start_config_watcher(
     config_path: PathBuf,
     hot_reload_manager: Option<Arc<HotReloadManager>>,
-    processor: Arc<RequestProcessor>,
+    reload_targets: ReloadTargets,
     logs: Arc<RwLock<LogStore>>,
     db: Option<DbConnection>,
     config_manager: Option<Arc<std::sync::RwLock<ConfigManager>>>,
+    event_bus: Arc<EventBus>,
 ) -> Option<FileWatcher> {
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<ConfigChangeEvent>();
 
@@ -436,7 +475,6 @@ async fn start_config_watcher(
 
     // 启动事件处理任务
     let hot_reload_manager_clone = hot_reload_manager.clone();
-    let processor_clone = processor.clone();
     let logs_clone = logs.clone();
     let db_clone = db.clone();
     let config_manager_clone = config_manager.clone();
@@ -457,6 +495,23 @@ async fn start_config_watcher(
             // 执行热重载
             if let Some(ref manager) = hot_reload_manager_clone {
                 let result = manager.reload();
+                event_bus.publish(match &result {
+                    ReloadResult::Success { .. } => SystemEvent::ConfigReloaded {
+                        success: true,
+                        rolled_back: false,
+                        error: None,
+                    },
+                    ReloadResult::RolledBack { error, .. } => SystemEvent::ConfigReloaded {
+                        success: false,
+                        rolled_back: true,
+                        error: Some(error.clone()),
+                    },
+                    ReloadResult::Failed { error, .. } => SystemEvent::ConfigReloaded {
+                        success: false,
+                        rolled_back: false,
+                        error: Some(error.clone()),
+                    },
+                });
                 match &result {
                     ReloadResult::Success { .. } => {
                         tracing::info!("[HOT_RELOAD] 配置热重载成功");
@@ -465,9 +520,9 @@ async fn start_config_watcher(
                             .await
                             .add("info", "[HOT_RELOAD] 配置热重载成功");
 
-                        // 更新处理器中的组件
+                        // 更新处理器与运行时配置
                         let new_config = manager.config();
-                        update_processor_config(&processor_clone, &new_config).await;
+                        reload_targets.apply(&new_config).await;
 
                         // 同步凭证池
                         if let (Some(ref db), Some(ref cfg_manager)) =
@@ -532,6 +587,53 @@ async fn start_config_watcher(
     Some(watcher)
 }
 
+/// 热重载时需要刷新的运行时组件
+///
+/// 除 RequestProcessor 外，其余配置段由请求处理器按请求读取，
+/// 热重载成功后在此统一替换，无需重启服务器。
+#[derive(Clone)]
+struct ReloadTargets {
+    processor: Arc<RequestProcessor>,
+    endpoint_providers: Arc<RwLock<EndpointProvidersConfig>>,
+    image_config: Arc<RwLock<ImageConfig>>,
+    structured_output_config: Arc<RwLock<StructuredOutputConfig>>,
+    request_queue: Arc<FairQueue>,
+    shadow_mirror: Arc<ShadowMirror>,
+    usage_quota: Arc<UsageQuotaTracker>,
+    notifications: Arc<NotificationService>,
+}
+
+impl ReloadTargets {
+    fn from_state(state: &AppState) -> Self {
+        Self {
+            processor: state.processor.clone(),
+            endpoint_providers: state.endpoint_providers.clone(),
+            image_config: state.image_config.clone(),
+            structured_output_config: state.structured_output_config.clone(),
+            request_queue: state.request_queue.clone(),
+            shadow_mirror: state.shadow_mirror.clone(),
+            usage_quota: state.usage_quota.clone(),
+            notifications: state.notifications.clone(),
+        }
+    }
+
+    /// 应用热重载后的配置
+    async fn apply(&self, config: &Config) {
+        update_processor_config(&self.processor, config).await;
+
+        *self.endpoint_providers.write().await = config.endpoint_providers.clone();
+        *self.image_config.write().await = config.images.clone();
+        *self.structured_output_config.write().await = config.structured_output.clone();
+        self.request_queue.set_config(config.request_queue.clone());
+        self.shadow_mirror.set_config(config.mirror.clone());
+        self.usage_quota.set_config(config.usage_quota.clone());
+        self.notifications.set_config(config.notifications.clone());
+
+        // 批处理执行器与共享状态后端在启动时创建，变更需重启生效
+        tracing::info!("[HOT_RELOAD] 运行时配置更新完成");
+    }
+}
+
 /// 更新处理器配置
 ///
 /// 当配置热重载成功后，更新 RequestProcessor 中的各个组件。
@@ -676,7 +778,12 @@ async fn run_server(
     config: Option<Config>,
     config_path: Option<PathBuf>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let base_url = format!("http://{}:{}", host, port);
+    let tls_config = config
+        .as_ref()
+        .map(|c| c.server.tls.clone())
+        .unwrap_or_default();
+    let scheme = if tls_config.enable { "https" } else { "http" };
+    let base_url = format!("{}://{}:{}", scheme, host, port);
 
     // 创建请求处理器（使用共享的遥测实例或默认实例）
     let processor = match (shared_stats, shared_tokens) {
@@ -745,15 +852,178 @@ async fn run_server(
     // 创建 Kiro 事件服务
     let kiro_event_service = Arc::new(KiroEventService::new());
 
+    // 初始化图片输入配置
+    let image_config = Arc::new(RwLock::new(
+        config
+            .as_ref()
+            .map(|c| c.images.clone())
+            .unwrap_or_default(),
+    ));
+    let image_fetcher = Arc::new(ImageFetcher::new(
+        config.as_ref().and_then(|c| c.proxy_url.as_deref()),
+    ));
+
+    // 初始化结构化输出配置
+    let structured_output_config = Arc::new(RwLock::new(
+        config
+            .as_ref()
+            .map(|c| c.structured_output.clone())
+            .unwrap_or_default(),
+    ));
+
+    // 初始化配额管理器
+    let quota_manager = create_shared_quota_manager(
+        config
+            .as_ref()
+            .map(|c| c.quota_exceeded.clone())
+            .unwrap_or_default(),
+    );
+
+    // 初始化上游用量配额跟踪器，并接入凭证池选择
+    let usage_quota = Arc::new(UsageQuotaTracker::new(
+        config
+            .as_ref()
+            .map(|c| c.usage_quota.clone())
+            .unwrap_or_default(),
+        quota_manager.clone(),
+    ));
+    pool_service.set_selector(Some(usage_quota.clone()));
+
+    // 初始化统一事件总线，接入各子系统
+    let event_bus = Arc::new(EventBus::default());
+    pool_service.set_event_bus(Some(event_bus.clone()));
+    quota_manager.set_event_bus(Some(event_bus.clone()));
+    processor.plugins.set_event_bus(Some(event_bus.clone()));
+    token_cache.set_event_bus(Some(event_bus.clone()));
+
+    // 从插件目录加载脚本插件并启动原生插件进程，API 处理器在 Provider 调用前后执行插件钩子
+    match processor.plugins.load_all().await {
+        Ok(names) if !names.is_empty() => {
+            tracing::info!(
+                "[PLUGIN] 已加载 {} 个插件: {}",
+                names.len(),
+                names.join(", ")
+            );
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!(
+            "[PLUGIN] 加载插件目录 {} 失败: {}",
+            processor.plugins.plugins_dir().display(),
+            e
+        ),
+    }
+    let event_bridges = [
+        event_bus.forward(flow_monitor.subscribe(), flow_event_to_system),
+        event_bus.forward(kiro_event_service.subscribe(), |event| {
+            Some(SystemEvent::Kiro { event })
+        }),
+    ];
+
+    // 初始化运维告警通知，订阅事件总线
+    let notifications = Arc::new(NotificationService::new(
+        config
+            .as_ref()
+            .map(|c| c.notifications.clone())
+            .unwrap_or_default(),
+    ));
+    let notification_task = notifications.start(&event_bus);
+
+    // 初始化批处理任务队列
+    let batch_config = config.as_ref().map(|c| c.batch.clone()).unwrap_or_default();
+    let batch_store = match BatchStore::default_path().map(BatchStore::new) {
+        Some(Ok(store)) => store,
+        Some(Err(e)) => {
+            tracing::warn!("[BATCH] 打开批处理数据库失败，使用内存存储: {}", e);
+            BatchStore::in_memory()?
+        }
+        None => BatchStore::in_memory()?,
+    };
+    let batch_enabled = batch_config.enabled;
+    let batch_manager = Arc::new(BatchManager::new(batch_store, batch_config));
+
+    // 初始化上游请求优先级队列
+    let request_queue = FairQueue::new(
+        config
+            .as_ref()
+            .map(|c| c.request_queue.clone())
+            .unwrap_or_default(),
+    );
+
+    // 初始化影子流量镜像器
+    let shadow_mirror = Arc::new(ShadowMirror::new(
+        config
+            .as_ref()
+            .map(|c| c.mirror.clone())
+            .unwrap_or_default(),
+    ));
+
+    // 初始化 Flow 回归套件存储
+    let flow_suites = match SuiteStore::default_path().map(SuiteStore::new) {
+        Some(Ok(store)) => store,
+        Some(Err(e)) => {
+            tracing::warn!("[SUITE] 打开回归套件数据库失败，使用内存存储: {}", e);
+            SuiteStore::in_memory()?
+        }
+        None => SuiteStore::in_memory()?,
+    };
+
+    // 初始化多实例共享状态，接入刷新锁、配额冷却、Token 缓存与请求计数
+    let shared_state = match SharedState::open(
+        &config
+            .as_ref()
+            .map(|c| c.shared_state.clone())
+            .unwrap_or_default(),
+    ) {
+        Ok(shared) => shared.map(Arc::new),
+        Err(e) => {
+            tracing::error!(
+                "[SHARED_STATE] 打开共享状态后端失败，仅使用进程内状态: {}",
+                e
+            );
+            None
+        }
+    };
+    let kiro_refresh_lock = Arc::new(RefreshLock::new("kiro"));
+    let gemini_refresh_lock = Arc::new(RefreshLock::new("gemini"));
+    let qwen_refresh_lock = Arc::new(RefreshLock::new("qwen"));
+    let shared_sync_task = shared_state.as_ref().map(|shared| {
+        tracing::info!(
+            "[SHARED_STATE] 使用 {} 后端共享状态，实例标识: {}",
+            shared.backend_name(),
+            shared.node_id()
+        );
+        for lock in [&kiro_refresh_lock, &gemini_refresh_lock, &qwen_refresh_lock] {
+            lock.set_shared_state(Some(shared.clone()));
+        }
+        token_cache.set_shared_state(Some(shared.clone()));
+        quota_manager.set_shared_state(Some(shared.clone()));
+        processor
+            .stats
+            .read()
+            .set_shared_state(Some(shared.clone()));
+
+        let quota_manager = quota_manager.clone();
+        let mut interval = tokio::time::interval(shared.sync_interval());
+        tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                let changed = quota_manager.sync_shared().await;
+                if changed > 0 {
+                    tracing::debug!("[SHARED_STATE] 已同步 {} 条配额冷却记录", changed);
+                }
+            }
+        })
+    });
+
     let state = AppState {
         api_key: api_key.to_string(),
         base_url,
         default_provider,
         kiro: Arc::new(RwLock::new(kiro)),
         logs,
-        kiro_refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
-        gemini_refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
-        qwen_refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
+        kiro_refresh_lock,
+        gemini_refresh_lock,
+        qwen_refresh_lock,
         pool_service,
         token_cache,
         db,
@@ -769,31 +1039,114 @@ async fn run_server(
         flow_interceptor,
         endpoint_providers,
         kiro_event_service,
+        image_config,
+        image_fetcher,
+        structured_output_config,
+        quota_manager,
+        batch_manager,
+        request_queue,
+        shadow_mirror,
+        flow_suites: Arc::new(flow_suites),
+        health_scorers: Arc::new(CredentialHealthScorers::default()),
+        usage_quota,
+        event_bus,
+        notifications,
+        shared_state: shared_state.clone(),
+        management_config: Arc::new(
+            config
+                .as_ref()
+                .map(|c| c.remote_management.clone())
+                .unwrap_or_default(),
+        ),
     };
 
+    // 启动用量配额轮询
+    let usage_poller = state.db.clone().map(|db| {
+        state
+            .usage_quota
+            .clone()
+            .spawn(db, state.token_cache.clone())
+    });
+    let state_pool_service = state.pool_service.clone();
+    let state_token_cache = state.token_cache.clone();
+
+    // 启动 Flow 文件存储的保留期清理（每小时一次）
+    let flow_retention_task = state
+        .flow_monitor
+        .file_store()
+        .map(|store| store.spawn_retention_cleanup(std::time::Duration::from_secs(60 * 60)));
+
+    // 启动批处理后台执行器
+    let batch_runner = batch_enabled.then(|| {
+        BatchRunner::new(
+            state.batch_manager.clone(),
+            Arc::new(handlers::AppStateBatchExecutor::new(state.clone())),
+            state.quota_manager.clone(),
+        )
+        .spawn()
+    });
+
     // 启动配置文件监控
     let _file_watcher = if let Some(path) = config_path {
         start_config_watcher(
             path,
             hot_reload_manager,
-            processor,
+            ReloadTargets::from_state(&state),
             logs_clone,
             db_clone,
             config_manager,
+            state.event_bus.clone(),
         )
         .await
     } else {
         None
     };
 
+    let app = build_router(state);
+
+    let addr: std::net::SocketAddr = format!("{host}:{port}").parse()?;
+    let listener = std::net::TcpListener::bind(addr)?;
+
+    tracing::info!("Server listening on {}://{}", scheme, addr);
+
+    tls::serve(listener, app, &tls_config, async move {
+        let _ = shutdown.await;
+    })
+    .await?;
+
+    if let Some(runner) = batch_runner {
+        runner.abort();
+    }
+    if let Some(poller) = usage_poller {
+        poller.abort();
+    }
+    if let Some(task) = flow_retention_task {
+        task.abort();
+    }
+    for bridge in event_bridges {
+        bridge.abort();
+    }
+    notification_task.abort();
+    // 关闭插件，停止原生插件进程
+    processor.plugins.unload_all().await;
+    if let Some(task) = shared_sync_task {
+        task.abort();
+    }
+    state_pool_service.set_selector(None);
+    state_pool_service.set_event_bus(None);
+    state_token_cache.set_event_bus(None);
+    state_token_cache.set_shared_state(None);
+
+    Ok(())
+}
+
+/// 构建 HTTP 路由（API、管理 API 和 WebSocket）
+fn build_router(state: AppState) -> Router {
     // 设置请求体大小限制为 100MB，支持大型上下文请求（如 Claude Code 的 /compact 命令）
     let body_limit = 100 * 1024 * 1024; // 100MB
 
     // 创建管理 API 路由（带认证中间件）
-    let management_config = config
-        .as_ref()
-        .map(|c| c.remote_management.clone())
-        .unwrap_or_default();
+    let management_config = (*state.management_config).clone();
 
     let management_routes = Router::new()
         .route("/v0/management/status", get(handlers::management_status))
@@ -805,6 +1158,26 @@ async fn run_server(
             "/v0/management/credentials",
             post(handlers::management_add_credential),
         )
+        .route(
+            "/v0/management/credentials/:uuid",
+            axum::routing::delete(handlers::management_delete_credential),
+        )
+        .route(
+            "/v0/management/credentials/quota",
+            get(handlers::management_get_usage_quota),
+        )
+        .route(
+            "/v0/management/credentials/quota/config",
+            axum::routing::put(handlers::management_update_usage_quota_config),
+        )
+        .route(
+            "/v0/management/credentials/quota/poll",
+            post(handlers::management_poll_usage_quota),
+        )
+        .route(
+            "/v0/management/queue",
+            get(handlers::management_queue_status),
+        )
         .route(
             "/v0/management/config",
             get(handlers::management_get_config),
@@ -813,6 +1186,161 @@ async fn run_server(
             "/v0/management/config",
             axum::routing::put(handlers::management_update_config),
         )
+        .route(
+            "/v0/management/intercept/config",
+            get(handlers::management_get_intercept_config)
+                .put(handlers::management_update_intercept_config),
+        )
+        .route(
+            "/v0/management/intercept/flows",
+            get(handlers::management_list_intercepted_flows),
+        )
+        .route(
+            "/v0/management/intercept/flows/:id",
+            get(handlers::management_get_intercepted_flow),
+        )
+        .route(
+            "/v0/management/intercept/flows/:id/edit",
+            post(handlers::management_edit_intercepted_flow),
+        )
+        .route(
+            "/v0/management/intercept/flows/:id/continue",
+            post(handlers::management_continue_intercepted_flow),
+        )
+        .route(
+            "/v0/management/intercept/flows/:id/cancel",
+            post(handlers::management_cancel_intercepted_flow),
+        )
+        .route(
+            "/v0/management/flows",
+            get(handlers::management_query_flows),
+        )
+        .route(
+            "/v0/management/flows/export",
+            get(handlers::management_export_flows),
+        )
+        .route(
+            "/v0/management/flows/stats",
+            get(handlers::management_get_flow_stats),
+        )
+        .route(
+            "/v0/management/flows/dataset",
+            post(handlers::management_export_dataset),
+        )
+        .route(
+            "/v0/management/flows/import",
+            post(handlers::management_import_flows),
+        )
+        .route(
+            "/v0/management/flows/conversations",
+            get(handlers::management_list_conversations),
+        )
+        .route(
+            "/v0/management/flows/:id/conversation",
+            get(handlers::management_get_flow_conversation),
+        )
+        .route(
+            "/v0/management/flows/storage",
+            get(handlers::management_get_flow_storage),
+        )
+        .route(
+            "/v0/management/flows/storage/archive",
+            post(handlers::management_archive_flows),
+        )
+        .route(
+            "/v0/management/suites",
+            get(handlers::management_list_suites).post(handlers::management_create_suite),
+        )
+        .route(
+            "/v0/management/suites/:id",
+            get(handlers::management_get_suite)
+                .put(handlers::management_update_suite)
+                .delete(handlers::management_delete_suite),
+        )
+        .route(
+            "/v0/management/suites/:id/run",
+            post(handlers::management_run_suite),
+        )
+        .route(
+            "/v0/management/mirror/config",
+            get(handlers::management_get_mirror_config)
+                .put(handlers::management_update_mirror_config),
+        )
+        .route(
+            "/v0/management/mirror/report",
+            get(handlers::management_get_mirror_report),
+        )
+        .route(
+            "/v0/management/events",
+            get(handlers::management_event_stream),
+        )
+        .route(
+            "/v0/management/notifications/config",
+            get(handlers::management_get_notifications_config)
+                .put(handlers::management_update_notifications_config),
+        )
+        .route(
+            "/v0/management/notifications/dead-letters",
+            get(handlers::management_get_notification_dead_letters),
+        )
+        .route(
+            "/v0/management/notifications/test",
+            post(handlers::management_test_notification_sink),
+        )
+        .route(
+            "/v0/management/backups",
+            get(handlers::management_list_backups).post(handlers::management_create_backup),
+        )
+        .route(
+            "/v0/management/backups/restore",
+            post(handlers::management_restore_backup),
+        )
+        .route(
+            "/v0/management/shared-state",
+            get(handlers::management_get_shared_state),
+        )
+        // 通用凭证管理 API（所有 Provider 类型）
+        .route(
+            "/api/credentials/:provider",
+            get(handlers::list_pool_credentials),
+        )
+        .route(
+            "/api/credentials/:provider/select",
+            post(handlers::select_pool_credential),
+        )
+        .route(
+            "/api/credentials/:provider/:uuid",
+            get(handlers::get_pool_credential),
+        )
+        .route(
+            "/api/credentials/:provider/:uuid/token",
+            get(handlers::get_pool_credential_token),
+        )
+        .route(
+            "/api/credentials/:provider/:uuid/refresh",
+            post(handlers::refresh_pool_credential),
+        )
+        .route(
+            "/api/credentials/:provider/:uuid/test",
+            post(handlers::test_pool_credential),
+        )
+        .route(
+            "/api/credentials/:provider/:uuid/enable",
+            post(handlers::enable_pool_credential),
+        )
+        .route(
+            "/api/credentials/:provider/:uuid/disable",
+            post(handlers::disable_pool_credential),
+        )
+        .route(
+            "/api/credentials/:provider/:uuid/reset",
+            post(handlers::reset_pool_credential),
+        )
+        .route(
+            "/api/credentials/:provider/:uuid/cooldown",
+            axum::routing::put(handlers::set_pool_credential_cooldown)
+                .delete(handlers::clear_pool_credential_cooldown),
+        )
         .layer(crate::middleware::ManagementAuthLayer::new(
             management_config,
         ));
@@ -836,13 +1364,42 @@ async fn run_server(
             get(handlers::get_credential_status),
         );
 
-    let app = Router::new()
+    Router::new()
         .route("/health", get(health))
         .route("/v1/models", get(models))
         .route("/v1/routes", get(list_routes))
         .route("/v1/chat/completions", post(handlers::chat_completions))
         .route("/v1/messages", post(handlers::anthropic_messages))
         .route("/v1/messages/count_tokens", post(count_tokens))
+        // 批处理路由
+        .route(
+            "/v1/messages/batches",
+            post(handlers::create_message_batch).get(handlers::list_message_batches),
+        )
+        .route("/v1/messages/batches/:id", get(handlers::get_message_batch))
+        .route(
+            "/v1/messages/batches/:id/results",
+            get(handlers::get_message_batch_results),
+        )
+        .route(
+            "/v1/messages/batches/:id/cancel",
+            post(handlers::cancel_message_batch),
+        )
+        .route(
+            "/v1/files",
+            post(handlers::upload_file).get(handlers::list_files),
+        )
+        .route(
+            "/v1/files/:id",
+            get(handlers::get_file).delete(handlers::delete_file),
+        )
+        .route("/v1/files/:id/content", get(handlers::get_file_content))
+        .route(
+            "/v1/batches",
+            post(handlers::create_batch).get(handlers::list_batches),
+        )
+        .route("/v1/batches/:id", get(handlers::get_batch))
+        .route("/v1/batches/:id/cancel", post(handlers::cancel_batch))
         // Gemini 原生协议路由
         .route("/v1/gemini/*path", post(gemini_generate_content))
         // WebSocket 路由
@@ -877,20 +1434,7 @@ async fn run_server(
         // Kiro凭证管理API路由
         .merge(kiro_api_routes)
         .layer(DefaultBodyLimit::max(body_limit))
-        .with_state(state);
-
-    let addr: std::net::SocketAddr = format!("{host}:{port}").parse()?;
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-
-    tracing::info!("Server listening on {}", addr);
-
-    axum::serve(listener, app)
-        .with_graceful_shutdown(async move {
-            let _ = shutdown.await;
-        })
-        .await?;
-
-    Ok(())
+        .with_state(state)
 }
 
 async fn count_tokens(
@@ -1159,35 +1703,11 @@ async fn list_routes(State(state): State<AppState>) -> impl IntoResponse {
     // 获取默认 Provider
     let default_provider = state.default_provider.read().await.clone();
 
-    // 添加默认路由
-    let mut all_routes = vec![RouteInfo {
-        selector: "default".to_string(),
-        provider_type: default_provider.clone(),
-        credential_count: 1,
-        endpoints: vec![
-            crate::models::route_model::RouteEndpoint {
-                path: "/v1/messages".to_string(),
-                protocol: "claude".to_string(),
-                url: format!("{}/v1/messages", state.base_url),
-            },
-            crate::models::route_model::RouteEndpoint {
-                path: "/v1/chat/completions".to_string(),
-                protocol: "openai".to_string(),
-                url: format!("{}/v1/chat/completions", state.base_url),
-            },
-        ],
-        tags: vec!["默认".to_string()],
-        enabled: true,
-    }];
-    all_routes.extend(routes);
-
-    let response = RouteListResponse {
-        base_url: state.base_url.clone(),
-        default_provider,
-        routes: all_routes,
-    };
-
-    Json(response)
+    Json(RouteListResponse::new(
+        &state.base_url,
+        &default_provider,
+        routes,
+    ))
 }
 
 /// 带选择器的 Anthropic messages 处理
@@ -1253,7 +1773,14 @@ async fn anthropic_messages_with_selector(
 
             // 根据凭证类型调用相应的 Provider
             // 注意：这里没有 Flow 捕获，因为是通过 selector 路由的请求
-            handlers::call_provider_anthropic(&state, &cred, &request, None).await
+            handlers::call_provider_anthropic(
+                &state,
+                &cred,
+                &request,
+                None,
+                &handlers::upstream_queue_class(&state, &headers),
+            )
+            .await
         }
         None => {
             // 回退到默认 Kiro provider
@@ -1326,7 +1853,14 @@ async fn chat_completions_with_selector(
             );
 
             // 注意：这里没有 Flow 捕获，因为是通过 selector 路由的请求
-            handlers::call_provider_openai(&state, &cred, &request, None).await
+            handlers::call_provider_openai(
+                &state,
+                &cred,
+                &request,
+                None,
+                &handlers::upstream_queue_class(&state, &headers),
+            )
+            .await
         }
         None => {
             state.logs.write().await.add(
@@ -1423,7 +1957,14 @@ async fn amp_chat_completions(
                 ),
             );
             // 注意：这里没有 Flow 捕获，因为是通过 AMP CLI 路由的请求
-            handlers::call_provider_openai(&state, &cred, &request, None).await
+            handlers::call_provider_openai(
+                &state,
+                &cred,
+                &request,
+                None,
+                &handlers::upstream_queue_class(&state, &headers),
+            )
+            .await
         }
         None => {
             state.logs.write().await.add(
@@ -1519,7 +2060,14 @@ async fn amp_messages(
                 ),
             );
             // 注意：这里没有 Flow 捕获，因为是通过 AMP CLI 路由的请求
-            handlers::call_provider_anthropic(&state, &cred, &request, None).await
+            handlers::call_provider_anthropic(
+                &state,
+                &cred,
+                &request,
+                None,
+                &handlers::upstream_queue_class(&state, &headers),
+            )
+            .await
         }
         None => {
             state.logs.write().await.add(
@@ -1742,8 +2290,17 @@ async fn anthropic_messages_internal(
 ) -> Response {
     // 检查 token
     {
-        let _guard = state.kiro_refresh_lock.lock().await;
+        let guard = match handlers::lock_kiro_refresh(state, None).await {
+            Ok(guard) => guard,
+            Err(response) => return response,
+        };
         let mut kiro = state.kiro.write().await;
+        // 其他副本已刷新时，内存中的 refresh_token 可能已被轮换，先重新加载凭证文件
+        if guard.peer_refreshed() {
+            if let Err(e) = kiro.load_credentials().await {
+                tracing::warn!("[AUTH] 重新加载其他副本刷新后的凭证失败: {}", e);
+            }
+        }
         let needs_refresh =
             kiro.credentials.access_token.is_none() || kiro.is_token_expiring_soon();
         if needs_refresh {
@@ -1759,6 +2316,7 @@ async fn anthropic_messages_internal(
                 )
                     .into_response();
             }
+            guard.mark_refreshed().await;
         }
     }
 
@@ -1805,8 +2363,17 @@ async fn anthropic_messages_internal(
 /// 内部 OpenAI chat completions 处理 (使用默认 Kiro)
 async fn chat_completions_internal(state: &AppState, request: &ChatCompletionRequest) -> Response {
     {
-        let _guard = state.kiro_refresh_lock.lock().await;
+        let guard = match handlers::lock_kiro_refresh(state, None).await {
+            Ok(guard) => guard,
+            Err(response) => return response,
+        };
         let mut kiro = state.kiro.write().await;
+        // 其他副本已刷新时，内存中的 refresh_token 可能已被轮换，先重新加载凭证文件
+        if guard.peer_refreshed() {
+            if let Err(e) = kiro.load_credentials().await {
+                tracing::warn!("[AUTH] 重新加载其他副本刷新后的凭证失败: {}", e);
+            }
+        }
         let needs_refresh =
             kiro.credentials.access_token.is_none() || kiro.is_token_expiring_soon();
         if needs_refresh {
@@ -1817,6 +2384,7 @@ async fn chat_completions_internal(state: &AppState, request: &ChatCompletionReq
                 )
                     .into_response();
             }
+            guard.mark_refreshed().await;
         }
     }
 
@@ -1895,3 +2463,334 @@ async fn chat_completions_internal(state: &AppState, request: &ChatCompletionReq
             .into_response(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reload_targets(config: &Config) -> ReloadTargets {
+        let pool_service = Arc::new(ProviderPoolService::new());
+        ReloadTargets {
+            processor: Arc::new(RequestProcessor::with_defaults(pool_service)),
+            endpoint_providers: Arc::new(RwLock::new(config.endpoint_providers.clone())),
+            image_config: Arc::new(RwLock::new(config.images.clone())),
+            structured_output_config: Arc::new(RwLock::new(config.structured_output.clone())),
+            request_queue: FairQueue::new(config.request_queue.clone()),
+            shadow_mirror: Arc::new(ShadowMirror::new(config.mirror.clone())),
+            usage_quota: Arc::new(UsageQuotaTracker::new(
+                config.usage_quota.clone(),
+                create_shared_quota_manager(config.quota_exceeded.clone()),
+            )),
+            notifications: Arc::new(NotificationService::new(config.notifications.clone())),
+        }
+    }
+
+    /// 构建测试用的应用状态，插件从 `plugins_dir` 加载
+    async fn test_state(plugins_dir: &std::path::Path, db: Option<DbConnection>) -> AppState {
+        let config = Config::default();
+        let pool_service = Arc::new(ProviderPoolService::new());
+        let event_bus = Arc::new(EventBus::default());
+        let mut processor = RequestProcessor::with_defaults(pool_service.clone());
+        processor.plugins = Arc::new(crate::plugin::PluginManager::new(
+            plugins_dir.to_path_buf(),
+            crate::plugin::PluginManagerConfig::default(),
+        ));
+        processor.plugins.set_event_bus(Some(event_bus.clone()));
+        processor.plugins.load_all().await.unwrap();
+        let quota_manager = create_shared_quota_manager(config.quota_exceeded.clone());
+        let ws_manager = Arc::new(WsConnectionManager::new(WsConfig::default()));
+        let ws_stats = ws_manager.stats().clone();
+
+        AppState {
+            api_key: "test-key".to_string(),
+            base_url: "http://127.0.0.1:0".to_string(),
+            default_provider: Arc::new(RwLock::new("claude".to_string())),
+            kiro: Arc::new(RwLock::new(KiroProvider::new())),
+            logs: Arc::new(RwLock::new(LogStore::with_config(
+                &crate::config::LoggingConfig {
+                    enabled: false,
+                    ..Default::default()
+                },
+            ))),
+            kiro_refresh_lock: Arc::new(RefreshLock::new("kiro")),
+            gemini_refresh_lock: Arc::new(RefreshLock::new("gemini")),
+            qwen_refresh_lock: Arc::new(RefreshLock::new("qwen")),
+            pool_service,
+            token_cache: Arc::new(TokenCacheService::new()),
+            db,
+            injector: Arc::new(RwLock::new(Injector::new())),
+            injection_enabled: Arc::new(RwLock::new(false)),
+            processor: Arc::new(processor),
+            ws_manager,
+            ws_stats,
+            hot_reload_manager: None,
+            request_logger: None,
+            amp_router: Arc::new(crate::router::AmpRouter::new(config.ampcode.clone())),
+            flow_monitor: Arc::new(FlowMonitor::new(FlowMonitorConfig::default(), None)),
+            flow_interceptor: Arc::new(FlowInterceptor::default()),
+            endpoint_providers: Arc::new(RwLock::new(config.endpoint_providers.clone())),
+            kiro_event_service: Arc::new(KiroEventService::new()),
+            image_config: Arc::new(RwLock::new(config.images.clone())),
+            image_fetcher: Arc::new(ImageFetcher::new(None)),
+            structured_output_config: Arc::new(RwLock::new(config.structured_output.clone())),
+            quota_manager: quota_manager.clone(),
+            batch_manager: Arc::new(BatchManager::new(
+                BatchStore::in_memory().unwrap(),
+                config.batch.clone(),
+            )),
+            request_queue: FairQueue::new(config.request_queue.clone()),
+            shadow_mirror: Arc::new(ShadowMirror::new(config.mirror.clone())),
+            flow_suites: Arc::new(SuiteStore::in_memory().unwrap()),
+            health_scorers: Arc::new(CredentialHealthScorers::default()),
+            usage_quota: Arc::new(UsageQuotaTracker::new(
+                config.usage_quota.clone(),
+                quota_manager,
+            )),
+            event_bus,
+            notifications: Arc::new(NotificationService::new(config.notifications.clone())),
+            shared_state: None,
+            management_config: Arc::new(config.remote_management.clone()),
+        }
+    }
+
+    /// 在插件目录中写入一个 Lua 脚本插件
+    fn write_script_plugin(root: &std::path::Path, name: &str, hooks: &[&str], source: &str) {
+        let dir = root.join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        let manifest = serde_json::json!({
+            "name": name,
+            "version": "1.0.0",
+            "entry": "main.lua",
+            "hooks": hooks
+        });
+        std::fs::write(dir.join("manifest.json"), manifest.to_string()).unwrap();
+        std::fs::write(dir.join("main.lua"), source).unwrap();
+    }
+
+    /// 在本地端口上启动路由，返回服务地址
+    async fn spawn_router(state: AppState) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, build_router(state)).await });
+        format!("http://{}", addr)
+    }
+
+    /// 发送一个 JSON POST 请求，返回状态码和 JSON 响应体
+    async fn post_json(url: String, body: serde_json::Value) -> (StatusCode, serde_json::Value) {
+        let response = reqwest::Client::new()
+            .post(url)
+            .bearer_auth("test-key")
+            .header("x-api-key", "test-key")
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        let status = StatusCode::from_u16(response.status().as_u16()).unwrap();
+        (status, response.json().await.unwrap_or_default())
+    }
+
+    const GUARD_SCRIPT: &str = r#"
+        function on_request(ctx, req)
+          for _, msg in ipairs(req.messages) do
+            if type(msg.content) == "string" then
+              if string.find(msg.content, "forbidden") then
+                reject(403, "blocked by policy")
+              end
+              if string.find(msg.content, "crash") then
+                error("guard crashed")
+              end
+            end
+          end
+        end
+    "#;
+
+    #[tokio::test]
+    async fn test_plugin_rejects_request_through_router() {
+        let temp = tempfile::tempdir().unwrap();
+        write_script_plugin(temp.path(), "guard", &["on_request"], GUARD_SCRIPT);
+        let state = test_state(temp.path(), None).await;
+        assert_eq!(state.processor.plugins.count(), 1);
+        let base = spawn_router(state).await;
+
+        let (status, body) = post_json(
+            format!("{}/v1/chat/completions", base),
+            serde_json::json!({
+                "model": "claude-sonnet-4-5",
+                "messages": [{"role": "user", "content": "say something forbidden"}]
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["error"]["type"], "plugin_rejected");
+        assert_eq!(body["error"]["message"], "blocked by policy");
+
+        let (status, body) = post_json(
+            format!("{}/v1/messages", base),
+            serde_json::json!({
+                "model": "claude-sonnet-4-5",
+                "max_tokens": 16,
+                "messages": [{"role": "user", "content": "say something forbidden"}]
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["type"], "error");
+        assert_eq!(body["error"]["type"], "plugin_rejected");
+        assert_eq!(body["error"]["message"], "blocked by policy");
+    }
+
+    #[tokio::test]
+    async fn test_plugin_script_error_publishes_event() {
+        let temp = tempfile::tempdir().unwrap();
+        write_script_plugin(temp.path(), "guard", &["on_request"], GUARD_SCRIPT);
+        let state = test_state(temp.path(), None).await;
+        let mut events = state.event_bus.subscribe();
+        let base = spawn_router(state).await;
+
+        // 脚本出错不会拒绝请求，请求继续路由（没有可用凭证）
+        let (status, _) = post_json(
+            format!("{}/v1/chat/completions", base),
+            serde_json::json!({
+                "model": "claude-sonnet-4-5",
+                "messages": [{"role": "user", "content": "please crash"}]
+            }),
+        )
+        .await;
+        assert_ne!(status, StatusCode::FORBIDDEN);
+
+        let (plugin, hook, message) = loop {
+            let event = tokio::time::timeout(std::time::Duration::from_secs(5), events.recv())
+                .await
+                .expect("plugin_error event")
+                .unwrap();
+            if let SystemEvent::PluginError {
+                plugin,
+                hook,
+                message,
+            } = &event.event
+            {
+                break (plugin.clone(), hook.clone(), message.clone());
+            }
+        };
+        assert_eq!(plugin, "guard");
+        assert_eq!(hook, "on_request");
+        assert!(message.contains("guard crashed"), "{}", message);
+    }
+
+    /// 启动返回固定 Anthropic SSE 响应的模拟上游
+    async fn spawn_sse_upstream(body: &'static str) -> String {
+        let upstream = Router::new().route(
+            "/v1/messages",
+            post(move || async move {
+                (
+                    [(axum::http::header::CONTENT_TYPE, "text/event-stream")],
+                    body,
+                )
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, upstream).await });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_stream_hooks_rewrite_streaming_response() {
+        const UPSTREAM_SSE: &str = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"model\":\"claude-sonnet-4-5\",\"usage\":{\"input_tokens\":5,\"output_tokens\":0}}}\n\n",
+            "event: content_block_start\n",
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"the secret is 42\"}}\n\n",
+            "event: content_block_stop\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+            "event: message_stop\n",
+            "data: {\"type\":\"message_stop\"}\n\n",
+        );
+        let upstream = spawn_sse_upstream(UPSTREAM_SSE).await;
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        let credential = crate::models::provider_pool_model::ProviderCredential::new(
+            crate::models::provider_pool_model::PoolProviderType::Claude,
+            CredentialData::ClaudeKey {
+                api_key: "sk-test".to_string(),
+                base_url: Some(upstream),
+            },
+        );
+        ProviderPoolDao::insert(&conn, &credential).unwrap();
+
+        let temp = tempfile::tempdir().unwrap();
+        write_script_plugin(
+            temp.path(),
+            "redact",
+            &["on_stream_event"],
+            r#"
+                function on_stream_event(ctx, event)
+                  if event.type == "text_delta" then
+                    event.text = string.replace(event.text, "secret", "******")
+                  end
+                end
+            "#,
+        );
+        let state = test_state(temp.path(), Some(Arc::new(std::sync::Mutex::new(conn)))).await;
+        let base = spawn_router(state).await;
+
+        let response = reqwest::Client::new()
+            .post(format!("{}/v1/messages", base))
+            .header("x-api-key", "test-key")
+            .json(&serde_json::json!({
+                "model": "claude-sonnet-4-5",
+                "max_tokens": 16,
+                "stream": true,
+                "messages": [{"role": "user", "content": "tell me"}]
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        let body = response.text().await.unwrap();
+        assert!(body.contains("the ****** is 42"), "{}", body);
+        assert!(!body.contains("secret"), "{}", body);
+        assert!(body.contains("event: message_stop"), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_reload_updates_runtime_config_sections() {
+        let targets = reload_targets(&Config::default());
+
+        let mut config = Config::default();
+        config.endpoint_providers.cursor = Some("gemini".to_string());
+        config.images.fetch_remote = !config.images.fetch_remote;
+        config.images.max_image_bytes = 1234;
+        config.structured_output.max_retries = 7;
+        config.request_queue.max_concurrent = 3;
+        config.mirror.enabled = true;
+        config.usage_quota.poll_interval_secs = 42;
+        config.notifications.enabled = true;
+        config
+            .routing
+            .model_aliases
+            .insert("fast".to_string(), "claude-haiku".to_string());
+
+        targets.apply(&config).await;
+
+        assert_eq!(
+            targets.endpoint_providers.read().await.cursor.as_deref(),
+            Some("gemini")
+        );
+        let images = targets.image_config.read().await.clone();
+        assert_eq!(images.fetch_remote, config.images.fetch_remote);
+        assert_eq!(images.max_image_bytes, 1234);
+        assert_eq!(targets.structured_output_config.read().await.max_retries, 7);
+        assert_eq!(targets.request_queue.config().max_concurrent, 3);
+        assert!(targets.shadow_mirror.config().enabled);
+        assert_eq!(targets.usage_quota.config().poll_interval_secs, 42);
+        assert!(targets.notifications.config().enabled);
+        assert_eq!(
+            targets.processor.mapper.read().await.resolve("fast"),
+            "claude-haiku"
+        );
+    }
+}
@@ -0,0 +1,388 @@
+//! 凭证健康评分
+//!
+//! 按 Provider 类型挂载不同的评分器，统一输出 0-100 的健康分数：
+//! - OAuth 类凭证（Kiro、Gemini 等）：基于 Token 状态与配额冷却
+//! - API Key 类凭证（OpenAI、Claude 等）：基于请求错误率
+//!
+//! 自定义评分器实现 [`CredentialHealthScorer`] 后通过
+//! [`CredentialHealthScorers::register`] 覆盖默认映射。
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::models::provider_pool_model::{CachedTokenInfo, PoolProviderType, ProviderCredential};
+
+/// 健康分数高于该值的凭证视为可用
+pub const AVAILABLE_HEALTH_SCORE: f64 = 50.0;
+
+/// 评分输入
+#[derive(Debug, Clone, Copy)]
+pub struct HealthScoreInput<'a> {
+    /// 凭证
+    pub credential: &'a ProviderCredential,
+    /// Token 缓存状态
+    pub cached_token: Option<&'a CachedTokenInfo>,
+    /// 配额冷却结束时间（未处于冷却期时为 None）
+    pub cooldown_until: Option<DateTime<Utc>>,
+    /// 评分时刻
+    pub now: DateTime<Utc>,
+}
+
+impl<'a> HealthScoreInput<'a> {
+    /// 以当前时间创建评分输入
+    pub fn new(
+        credential: &'a ProviderCredential,
+        cached_token: Option<&'a CachedTokenInfo>,
+    ) -> Self {
+        Self {
+            credential,
+            cached_token,
+            cooldown_until: None,
+            now: Utc::now(),
+        }
+    }
+
+    /// 设置配额冷却结束时间
+    pub fn with_cooldown(mut self, cooldown_until: Option<DateTime<Utc>>) -> Self {
+        self.cooldown_until = cooldown_until;
+        self
+    }
+
+    /// 是否处于配额冷却期
+    pub fn in_cooldown(&self) -> bool {
+        self.cooldown_until.is_some_and(|until| until > self.now)
+    }
+}
+
+/// 凭证健康评分器
+pub trait CredentialHealthScorer: Send + Sync {
+    /// 评分器名称（在 API 响应中展示）
+    fn name(&self) -> &'static str;
+
+    /// 计算健康分数，范围 0-100
+    fn score(&self, input: &HealthScoreInput<'_>) -> f64;
+}
+
+/// Token 与配额评分器
+///
+/// 适用于 OAuth 凭证：健康状态 40 分、错误计数 20 分、Token 缓存 25 分、
+/// 使用活跃度 15 分；处于配额冷却期时扣 50 分。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenQuotaScorer;
+
+impl CredentialHealthScorer for TokenQuotaScorer {
+    fn name(&self) -> &'static str {
+        "token_quota"
+    }
+
+    fn score(&self, input: &HealthScoreInput<'_>) -> f64 {
+        let credential = input.credential;
+        let now = input.now;
+        let mut score = 0.0;
+
+        // 1. 基础健康状态 (40分)
+        if credential.is_healthy {
+            score += 40.0;
+        } else {
+            score -= 20.0; // 不健康严重扣分
+        }
+
+        // 2. 错误计数影响 (20分)
+        let error_count = credential.error_count;
+        if error_count == 0 {
+            score += 20.0;
+        } else if error_count <= 2 {
+            score += 10.0; // 少量错误，轻微扣分
+        } else {
+            score -= error_count as f64 * 5.0; // 错误越多扣分越多
+        }
+
+        // 3. Token缓存状态 (25分)
+        if let Some(cache) = input.cached_token {
+            if cache.access_token.is_some() {
+                score += 15.0; // 有缓存token
+
+                // 检查过期时间
+                if let Some(expiry_time) = cache.expiry_time {
+                    let time_until_expiry = expiry_time - now;
+
+                    if time_until_expiry > Duration::hours(1) {
+                        score += 10.0; // 距离过期还有较长时间
+                    } else if time_until_expiry > Duration::minutes(30) {
+                        score += 5.0; // 距离过期还有一些时间
+                    } else if time_until_expiry <= Duration::zero() {
+                        score -= 10.0; // 已过期
+                    }
+                }
+            } else {
+                score -= 5.0; // 没有缓存token
+            }
+
+            // 刷新错误计数影响
+            if cache.refresh_error_count > 2 {
+                score -= cache.refresh_error_count as f64 * 5.0; // 大量刷新错误严重扣分
+            } else {
+                score -= cache.refresh_error_count as f64 * 2.0; // 少量刷新错误
+            }
+        } else {
+            score -= 10.0; // 完全没有缓存状态
+        }
+
+        // 4. 使用活跃度 (15分)
+        if let Some(last_used) = credential.last_used {
+            let time_since_used = now - last_used;
+
+            if time_since_used <= Duration::hours(1) {
+                score += 15.0; // 最近1小时内使用过
+            } else if time_since_used <= Duration::hours(24) {
+                score += 10.0; // 最近24小时内使用过
+            } else if time_since_used <= Duration::days(7) {
+                score += 5.0; // 最近一周内使用过
+            }
+        } else {
+            score -= 5.0; // 从未使用过
+        }
+
+        // 5. 配额冷却
+        if input.in_cooldown() {
+            score -= 50.0;
+        }
+
+        score.clamp(0.0, 100.0)
+    }
+}
+
+/// 错误率评分器
+///
+/// 适用于 API Key 凭证：分数 = 100 × (1 - 错误数 / 请求数)，
+/// 标记为不健康时减半，处于配额冷却期时归零。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ErrorRateScorer;
+
+impl CredentialHealthScorer for ErrorRateScorer {
+    fn name(&self) -> &'static str {
+        "error_rate"
+    }
+
+    fn score(&self, input: &HealthScoreInput<'_>) -> f64 {
+        if input.in_cooldown() {
+            return 0.0;
+        }
+
+        let credential = input.credential;
+        let attempts = credential.usage_count + credential.error_count as u64;
+        let mut score = if attempts == 0 {
+            100.0
+        } else {
+            100.0 * (1.0 - credential.error_count as f64 / attempts as f64)
+        };
+
+        if !credential.is_healthy {
+            score /= 2.0;
+        }
+
+        score.clamp(0.0, 100.0)
+    }
+}
+
+/// 按 Provider 类型注册的评分器集合
+#[derive(Clone)]
+pub struct CredentialHealthScorers {
+    scorers: HashMap<PoolProviderType, Arc<dyn CredentialHealthScorer>>,
+    fallback: Arc<dyn CredentialHealthScorer>,
+}
+
+impl Default for CredentialHealthScorers {
+    fn default() -> Self {
+        let token: Arc<dyn CredentialHealthScorer> = Arc::new(TokenQuotaScorer);
+        let error_rate: Arc<dyn CredentialHealthScorer> = Arc::new(ErrorRateScorer);
+
+        let mut scorers = HashMap::new();
+        for pt in [
+            PoolProviderType::Kiro,
+            PoolProviderType::Gemini,
+            PoolProviderType::Qwen,
+            PoolProviderType::Antigravity,
+            PoolProviderType::Codex,
+            PoolProviderType::ClaudeOAuth,
+            PoolProviderType::IFlow,
+        ] {
+            scorers.insert(pt, token.clone());
+        }
+        for pt in [
+            PoolProviderType::OpenAI,
+            PoolProviderType::Claude,
+            PoolProviderType::Vertex,
+            PoolProviderType::GeminiApiKey,
+        ] {
+            scorers.insert(pt, error_rate.clone());
+        }
+
+        Self {
+            scorers,
+            fallback: error_rate,
+        }
+    }
+}
+
+impl std::fmt::Debug for CredentialHealthScorers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut map = f.debug_map();
+        for (pt, scorer) in &self.scorers {
+            map.entry(&pt.to_string(), &scorer.name());
+        }
+        map.finish()
+    }
+}
+
+impl CredentialHealthScorers {
+    /// 为指定 Provider 类型注册评分器（覆盖已有映射）
+    pub fn register(
+        &mut self,
+        provider_type: PoolProviderType,
+        scorer: Arc<dyn CredentialHealthScorer>,
+    ) {
+        self.scorers.insert(provider_type, scorer);
+    }
+
+    /// 获取指定 Provider 类型的评分器
+    pub fn scorer_for(&self, provider_type: PoolProviderType) -> &dyn CredentialHealthScorer {
+        self.scorers
+            .get(&provider_type)
+            .unwrap_or(&self.fallback)
+            .as_ref()
+    }
+
+    /// 使用凭证所属 Provider 的评分器计算分数
+    ///
+    /// 手动禁用的凭证固定为 0 分。
+    pub fn score(&self, input: &HealthScoreInput<'_>) -> f64 {
+        if input.credential.is_disabled {
+            return 0.0;
+        }
+        self.scorer_for(input.credential.provider_type).score(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::provider_pool_model::CredentialData;
+
+    fn kiro_credential() -> ProviderCredential {
+        ProviderCredential::new(
+            PoolProviderType::Kiro,
+            CredentialData::KiroOAuth {
+                creds_file_path: "/tmp/kiro.json".to_string(),
+            },
+        )
+    }
+
+    fn openai_credential() -> ProviderCredential {
+        ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-test".to_string(),
+                base_url: None,
+            },
+        )
+    }
+
+    fn fresh_token(now: DateTime<Utc>) -> CachedTokenInfo {
+        CachedTokenInfo {
+            access_token: Some("token".to_string()),
+            refresh_token: None,
+            expiry_time: Some(now + Duration::hours(2)),
+            last_refresh: Some(now),
+            refresh_error_count: 0,
+            last_refresh_error: None,
+        }
+    }
+
+    #[test]
+    fn test_token_quota_scorer_penalizes_cooldown() {
+        let mut cred = kiro_credential();
+        let now = Utc::now();
+        cred.last_used = Some(now);
+        let token = fresh_token(now);
+
+        let input = HealthScoreInput::new(&cred, Some(&token));
+        let healthy = TokenQuotaScorer.score(&input);
+        assert_eq!(healthy, 100.0);
+
+        let cooling =
+            TokenQuotaScorer.score(&input.with_cooldown(Some(now + Duration::minutes(5))));
+        assert_eq!(cooling, 50.0);
+        assert!(cooling <= AVAILABLE_HEALTH_SCORE);
+
+        // 已过期的冷却不扣分
+        let expired =
+            TokenQuotaScorer.score(&input.with_cooldown(Some(now - Duration::minutes(5))));
+        assert_eq!(expired, 100.0);
+    }
+
+    #[test]
+    fn test_token_quota_scorer_without_cache() {
+        let cred = kiro_credential();
+        let score = TokenQuotaScorer.score(&HealthScoreInput::new(&cred, None));
+        // 40 + 20 - 10 - 5
+        assert_eq!(score, 45.0);
+    }
+
+    #[test]
+    fn test_error_rate_scorer() {
+        let mut cred = openai_credential();
+        assert_eq!(
+            ErrorRateScorer.score(&HealthScoreInput::new(&cred, None)),
+            100.0
+        );
+
+        cred.usage_count = 75;
+        cred.error_count = 25;
+        assert_eq!(
+            ErrorRateScorer.score(&HealthScoreInput::new(&cred, None)),
+            75.0
+        );
+
+        cred.is_healthy = false;
+        assert_eq!(
+            ErrorRateScorer.score(&HealthScoreInput::new(&cred, None)),
+            37.5
+        );
+
+        let input = HealthScoreInput::new(&cred, None)
+            .with_cooldown(Some(Utc::now() + Duration::minutes(1)));
+        assert_eq!(ErrorRateScorer.score(&input), 0.0);
+    }
+
+    #[test]
+    fn test_registry_dispatch_and_override() {
+        struct Constant;
+        impl CredentialHealthScorer for Constant {
+            fn name(&self) -> &'static str {
+                "constant"
+            }
+            fn score(&self, _input: &HealthScoreInput<'_>) -> f64 {
+                42.0
+            }
+        }
+
+        let mut scorers = CredentialHealthScorers::default();
+        assert_eq!(
+            scorers.scorer_for(PoolProviderType::Kiro).name(),
+            "token_quota"
+        );
+        assert_eq!(
+            scorers.scorer_for(PoolProviderType::OpenAI).name(),
+            "error_rate"
+        );
+
+        scorers.register(PoolProviderType::OpenAI, Arc::new(Constant));
+        let mut cred = openai_credential();
+        assert_eq!(scorers.score(&HealthScoreInput::new(&cred, None)), 42.0);
+
+        cred.is_disabled = true;
+        assert_eq!(scorers.score(&HealthScoreInput::new(&cred, None)), 0.0);
+    }
+}
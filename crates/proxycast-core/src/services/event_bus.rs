@@ -0,0 +1,495 @@
+//! 统一事件总线
+//!
+//! 各子系统（Flow 监控、Kiro 凭证、凭证池健康、配额冷却、配置热重载、
+//! 故障转移、插件）将类型化事件发布到总线，订阅方按主题过滤接收。
+//!
+//! 总线保留最近的事件作为有界重放缓冲区，断线重连的订阅方可以通过
+//! 最后收到的事件 ID 补齐错过的事件。
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use crate::flow_monitor::monitor::FlowEvent;
+use crate::websocket::{WsFlowEvent, WsKiroEvent};
+use crate::ProviderType;
+
+/// 默认重放缓冲区大小
+pub const DEFAULT_REPLAY_CAPACITY: usize = 1024;
+
+/// 事件主题
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventTopic {
+    /// Flow 生命周期（开始、完成、失败、阈值告警）
+    Flow,
+    /// Kiro 凭证状态
+    Kiro,
+    /// 凭证池健康状态
+    Pool,
+    /// 配额冷却
+    Quota,
+    /// 配置热重载
+    Config,
+    /// Provider 故障转移
+    ///
+    /// 仅由 `ProviderStep` 的故障转移路径发布；当前服务端的请求处理不经过该步骤，
+    /// 因此此主题暂无事件。
+    Failover,
+    /// 插件错误
+    Plugin,
+}
+
+impl EventTopic {
+    /// 所有主题
+    pub const ALL: [EventTopic; 7] = [
+        EventTopic::Flow,
+        EventTopic::Kiro,
+        EventTopic::Pool,
+        EventTopic::Quota,
+        EventTopic::Config,
+        EventTopic::Failover,
+        EventTopic::Plugin,
+    ];
+
+    /// 主题名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventTopic::Flow => "flow",
+            EventTopic::Kiro => "kiro",
+            EventTopic::Pool => "pool",
+            EventTopic::Quota => "quota",
+            EventTopic::Config => "config",
+            EventTopic::Failover => "failover",
+            EventTopic::Plugin => "plugin",
+        }
+    }
+}
+
+impl std::fmt::Display for EventTopic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for EventTopic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EventTopic::ALL
+            .into_iter()
+            .find(|topic| topic.as_str() == s)
+            .ok_or_else(|| format!("未知的事件主题: {}", s))
+    }
+}
+
+/// 主题过滤器
+#[derive(Debug, Clone, Default)]
+pub struct TopicFilter {
+    /// 允许的主题（None 表示全部）
+    topics: Option<HashSet<EventTopic>>,
+}
+
+impl TopicFilter {
+    /// 接收所有主题
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// 解析逗号分隔的主题列表，空字符串表示全部主题
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let topics = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::parse)
+            .collect::<Result<HashSet<EventTopic>, String>>()?;
+        Ok(Self {
+            topics: (!topics.is_empty()).then_some(topics),
+        })
+    }
+
+    /// 主题是否匹配
+    pub fn matches(&self, topic: EventTopic) -> bool {
+        self.topics.as_ref().is_none_or(|t| t.contains(&topic))
+    }
+}
+
+/// 系统事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SystemEvent {
+    /// Flow 监控事件
+    Flow { event: WsFlowEvent },
+    /// Kiro 凭证事件
+    Kiro { event: WsKiroEvent },
+    /// 凭证健康状态变化
+    CredentialHealthChanged {
+        uuid: String,
+        provider_type: String,
+        healthy: bool,
+        error_count: u32,
+        message: Option<String>,
+    },
+    /// 凭证进入配额冷却
+    QuotaCooldownStarted {
+        credential_id: String,
+        cooldown_until: DateTime<Utc>,
+        reason: String,
+    },
+    /// 凭证配额冷却解除
+    QuotaCooldownCleared { credential_id: String },
+    /// 匹配的凭证全部处于配额冷却期，请求被拒绝
+    QuotaExhausted {
+        provider: String,
+        model: String,
+        earliest_recovery: Option<DateTime<Utc>>,
+    },
+    /// Token 刷新永久失败（凭证已被自动禁用）
+    TokenRefreshFailed {
+        uuid: String,
+        provider_type: String,
+        error_type: String,
+        message: String,
+    },
+    /// 配置热重载
+    ConfigReloaded {
+        success: bool,
+        rolled_back: bool,
+        error: Option<String>,
+    },
+    /// Provider 故障转移（见 [`EventTopic::Failover`]，当前服务端不会发布）
+    ProviderSwitched {
+        request_id: String,
+        from: ProviderType,
+        to: ProviderType,
+        failure_type: String,
+    },
+    /// 插件钩子执行失败
+    PluginError {
+        plugin: String,
+        hook: String,
+        message: String,
+    },
+}
+
+impl SystemEvent {
+    /// 事件所属主题
+    pub fn topic(&self) -> EventTopic {
+        match self {
+            SystemEvent::Flow { .. } => EventTopic::Flow,
+            SystemEvent::Kiro { .. } => EventTopic::Kiro,
+            SystemEvent::CredentialHealthChanged { .. }
+            | SystemEvent::TokenRefreshFailed { .. } => EventTopic::Pool,
+            SystemEvent::QuotaCooldownStarted { .. }
+            | SystemEvent::QuotaCooldownCleared { .. }
+            | SystemEvent::QuotaExhausted { .. } => EventTopic::Quota,
+            SystemEvent::ConfigReloaded { .. } => EventTopic::Config,
+            SystemEvent::ProviderSwitched { .. } => EventTopic::Failover,
+            SystemEvent::PluginError { .. } => EventTopic::Plugin,
+        }
+    }
+
+    /// 事件类型名称（与序列化后的 `type` 字段一致）
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            SystemEvent::Flow { .. } => "flow",
+            SystemEvent::Kiro { .. } => "kiro",
+            SystemEvent::CredentialHealthChanged { .. } => "credential_health_changed",
+            SystemEvent::QuotaCooldownStarted { .. } => "quota_cooldown_started",
+            SystemEvent::QuotaCooldownCleared { .. } => "quota_cooldown_cleared",
+            SystemEvent::QuotaExhausted { .. } => "quota_exhausted",
+            SystemEvent::TokenRefreshFailed { .. } => "token_refresh_failed",
+            SystemEvent::ConfigReloaded { .. } => "config_reloaded",
+            SystemEvent::ProviderSwitched { .. } => "provider_switched",
+            SystemEvent::PluginError { .. } => "plugin_error",
+        }
+    }
+}
+
+/// 总线事件（带序号与时间戳）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BusEvent {
+    /// 单调递增的事件 ID（从 1 开始）
+    pub id: u64,
+    /// 发布时间
+    pub timestamp: DateTime<Utc>,
+    /// 主题
+    pub topic: EventTopic,
+    /// 事件内容
+    #[serde(flatten)]
+    pub event: SystemEvent,
+}
+
+/// 订阅结果：需要重放的历史事件与实时事件接收端
+pub struct EventSubscription {
+    /// `after` 之后仍在缓冲区中的历史事件（已按主题过滤）
+    pub replay: Vec<Arc<BusEvent>>,
+    /// 请求的起点是否已被挤出缓冲区（存在无法补齐的事件）
+    pub truncated: bool,
+    /// 实时事件接收端（未过滤）
+    pub receiver: broadcast::Receiver<Arc<BusEvent>>,
+}
+
+/// 事件总线
+#[derive(Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<Arc<BusEvent>>,
+    replay: Mutex<VecDeque<Arc<BusEvent>>>,
+    capacity: usize,
+    next_id: AtomicU64,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_REPLAY_CAPACITY)
+    }
+}
+
+impl EventBus {
+    /// 创建事件总线，`capacity` 为重放缓冲区大小
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            replay: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// 发布事件，返回事件 ID
+    pub fn publish(&self, event: SystemEvent) -> u64 {
+        let mut replay = self.replay.lock().unwrap_or_else(|e| e.into_inner());
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let event = Arc::new(BusEvent {
+            id,
+            timestamp: Utc::now(),
+            topic: event.topic(),
+            event,
+        });
+
+        if replay.len() >= self.capacity {
+            replay.pop_front();
+        }
+        replay.push_back(event.clone());
+        // 在持有缓冲区锁时广播，保证订阅时的重放与实时事件不重不漏
+        let _ = self.sender.send(event);
+        id
+    }
+
+    /// 订阅实时事件
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<BusEvent>> {
+        self.sender.subscribe()
+    }
+
+    /// 从指定事件 ID 之后开始订阅
+    ///
+    /// `after` 为 None 时只接收之后发布的事件。
+    pub fn subscribe_after(&self, after: Option<u64>, filter: &TopicFilter) -> EventSubscription {
+        let replay = self.replay.lock().unwrap_or_else(|e| e.into_inner());
+        let receiver = self.sender.subscribe();
+
+        let Some(after) = after else {
+            return EventSubscription {
+                replay: Vec::new(),
+                truncated: false,
+                receiver,
+            };
+        };
+
+        let truncated = replay.front().is_some_and(|oldest| oldest.id > after + 1);
+        let events = replay
+            .iter()
+            .filter(|e| e.id > after && filter.matches(e.topic))
+            .cloned()
+            .collect();
+
+        EventSubscription {
+            replay: events,
+            truncated,
+            receiver,
+        }
+    }
+
+    /// 最近发布的事件 ID（尚未发布事件时为 0）
+    pub fn latest_id(&self) -> u64 {
+        self.next_id.load(Ordering::Relaxed) - 1
+    }
+
+    /// 重放缓冲区大小
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// 将外部广播通道的事件转发到总线
+    ///
+    /// `map` 返回 None 的事件会被丢弃。
+    pub fn forward<T, F>(
+        self: &Arc<Self>,
+        mut receiver: broadcast::Receiver<T>,
+        map: F,
+    ) -> JoinHandle<()>
+    where
+        T: Clone + Send + 'static,
+        F: Fn(T) -> Option<SystemEvent> + Send + 'static,
+    {
+        let bus = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(item) => {
+                        if let Some(event) = map(item) {
+                            bus.publish(event);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("[EVENT_BUS] 转发落后，丢弃 {} 个事件", n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+}
+
+/// 将 Flow 监控事件映射为总线事件
+///
+/// 只保留生命周期与告警事件，逐块的 `FlowUpdated` 与速率更新不进入总线，
+/// 以免挤占重放缓冲区。
+pub fn flow_event_to_system(event: FlowEvent) -> Option<SystemEvent> {
+    match event {
+        FlowEvent::FlowUpdated { .. } | FlowEvent::RequestRateUpdate { .. } => None,
+        other => Some(SystemEvent::Flow {
+            event: other.into(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin_error(n: usize) -> SystemEvent {
+        SystemEvent::PluginError {
+            plugin: format!("plugin-{}", n),
+            hook: "on_request".to_string(),
+            message: "boom".to_string(),
+        }
+    }
+
+    fn cooldown_cleared() -> SystemEvent {
+        SystemEvent::QuotaCooldownCleared {
+            credential_id: "cred-1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_topic_filter_parse() {
+        let filter = TopicFilter::parse("pool, quota").unwrap();
+        assert!(filter.matches(EventTopic::Pool));
+        assert!(filter.matches(EventTopic::Quota));
+        assert!(!filter.matches(EventTopic::Flow));
+
+        assert!(TopicFilter::parse("").unwrap().matches(EventTopic::Plugin));
+        assert!(TopicFilter::parse("pool,bogus").is_err());
+    }
+
+    #[test]
+    fn test_bus_event_serialization_is_flat() {
+        let bus = EventBus::new(4);
+        bus.publish(cooldown_cleared());
+        let sub = bus.subscribe_after(Some(0), &TopicFilter::all());
+
+        let json = serde_json::to_value(sub.replay[0].as_ref()).unwrap();
+        assert_eq!(json["id"], 1);
+        assert_eq!(json["topic"], "quota");
+        assert_eq!(json["type"], "quota_cooldown_cleared");
+        assert_eq!(json["credential_id"], "cred-1");
+        assert_eq!(cooldown_cleared().event_type(), "quota_cooldown_cleared");
+
+        let parsed: BusEvent = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.topic, EventTopic::Quota);
+    }
+
+    #[test]
+    fn test_replay_after_last_event_id() {
+        let bus = EventBus::new(8);
+        for n in 0..3 {
+            bus.publish(plugin_error(n));
+        }
+        bus.publish(cooldown_cleared());
+        assert_eq!(bus.latest_id(), 4);
+
+        let sub = bus.subscribe_after(Some(1), &TopicFilter::all());
+        assert_eq!(
+            sub.replay.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![2, 3, 4]
+        );
+        assert!(!sub.truncated);
+
+        let quota_only = TopicFilter::parse("quota").unwrap();
+        let sub = bus.subscribe_after(Some(0), &quota_only);
+        assert_eq!(sub.replay.len(), 1);
+        assert_eq!(sub.replay[0].id, 4);
+
+        // 未指定起点时不重放
+        assert!(bus
+            .subscribe_after(None, &TopicFilter::all())
+            .replay
+            .is_empty());
+    }
+
+    #[test]
+    fn test_replay_buffer_is_bounded() {
+        let bus = EventBus::new(2);
+        for n in 0..5 {
+            bus.publish(plugin_error(n));
+        }
+
+        let sub = bus.subscribe_after(Some(1), &TopicFilter::all());
+        assert_eq!(
+            sub.replay.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![4, 5]
+        );
+        assert!(sub.truncated);
+    }
+
+    #[tokio::test]
+    async fn test_subscription_receives_live_events_without_gap() {
+        let bus = EventBus::new(8);
+        bus.publish(plugin_error(0));
+
+        let mut sub = bus.subscribe_after(Some(0), &TopicFilter::all());
+        bus.publish(plugin_error(1));
+
+        assert_eq!(sub.replay.len(), 1);
+        let live = sub.receiver.recv().await.unwrap();
+        assert_eq!(live.id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_forward_maps_external_channel() {
+        let bus = Arc::new(EventBus::new(8));
+        let (tx, rx) = broadcast::channel::<u32>(8);
+        let mut live = bus.subscribe();
+        let handle = bus.forward(rx, |n| (n % 2 == 0).then(|| plugin_error(n as usize)));
+
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        let event = live.recv().await.unwrap();
+        match &event.event {
+            SystemEvent::PluginError { plugin, .. } => assert_eq!(plugin, "plugin-2"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        drop(tx);
+        handle.await.unwrap();
+        assert_eq!(bus.latest_id(), 1);
+    }
+}
@@ -1,12 +1,16 @@
 pub mod backup_service;
+pub mod credential_health;
+pub mod event_bus;
 pub mod kiro_event_service;
 pub mod live_sync;
 pub mod machine_id_service;
 pub mod mcp_service;
 pub mod mcp_sync;
+pub mod notification_service;
 pub mod prompt_service;
 pub mod prompt_sync;
 pub mod provider_pool_service;
+pub mod shared_state;
 pub mod skill_service;
 pub mod switch;
 pub mod token_cache_service;
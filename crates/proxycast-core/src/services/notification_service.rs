@@ -0,0 +1,1166 @@
+//! 运维告警通知服务
+//!
+//! 订阅统一事件总线，把告警类事件转换为通知并投递到配置的 Sink：
+//! - 通用 Webhook（配置密钥时附带 HMAC-SHA256 签名）
+//! - Slack / 飞书 / 钉钉机器人
+//! - 本地命令（通知 JSON 写入标准输入）
+//!
+//! 每个 Sink 独立进行事件路由、去重节流和失败重试，重试耗尽的通知写入死信日志。
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use crate::config::{
+    expand_tilde, NotificationSinkConfig, NotificationSinkKind, NotificationsConfig,
+};
+use crate::flow_monitor::monitor::NotificationType;
+use crate::services::event_bus::{BusEvent, EventBus, EventTopic, SystemEvent};
+use crate::websocket::WsFlowEvent;
+
+/// 内存中保留的死信条数
+const DEAD_LETTER_MEMORY_LIMIT: usize = 200;
+
+/// 节流表超过该大小时清理过期条目
+const THROTTLE_PRUNE_THRESHOLD: usize = 1024;
+
+/// 重试退避上限
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// 通用 Webhook 签名请求头
+pub const SIGNATURE_HEADER: &str = "X-ProxyCast-Signature";
+
+/// 通用 Webhook 时间戳请求头（参与签名）
+pub const TIMESTAMP_HEADER: &str = "X-ProxyCast-Timestamp";
+
+/// 告警级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationSeverity {
+    /// 信息
+    Info,
+    /// 警告
+    Warning,
+    /// 严重
+    Critical,
+}
+
+impl NotificationSeverity {
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotificationSeverity::Info => "信息",
+            NotificationSeverity::Warning => "警告",
+            NotificationSeverity::Critical => "严重",
+        }
+    }
+}
+
+/// 告警通知
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    /// 来源事件 ID
+    pub event_id: u64,
+    /// 告警类型（Flow 通知为通知类型，如 `error_flow`；其余为事件类型，如 `quota_exhausted`）
+    pub kind: String,
+    /// 来源主题
+    pub topic: EventTopic,
+    /// 告警级别
+    pub severity: NotificationSeverity,
+    /// 标题
+    pub title: String,
+    /// 内容
+    pub message: String,
+    /// 去重键（节流窗口内相同的键只发送一次）
+    pub dedup_key: String,
+    /// 事件时间
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Notification {
+    fn new(
+        event: &BusEvent,
+        kind: &str,
+        severity: NotificationSeverity,
+        title: impl Into<String>,
+        message: String,
+        subject: &str,
+    ) -> Self {
+        Self {
+            event_id: event.id,
+            kind: kind.to_string(),
+            topic: event.topic,
+            severity,
+            title: title.into(),
+            message,
+            dedup_key: format!("{}:{}", kind, subject),
+            timestamp: event.timestamp,
+        }
+    }
+
+    /// 从总线事件生成告警通知
+    ///
+    /// Flow 只转换监控器产生的通知事件（新 Flow、错误 Flow、延迟 / Token 警告），
+    /// Flow 生命周期事件和 Kiro 事件不产生通知。
+    pub fn from_event(event: &BusEvent) -> Option<Self> {
+        let kind = event.event.event_type();
+        let notification = match &event.event {
+            SystemEvent::Flow {
+                event: WsFlowEvent::Notification { notification },
+            } => {
+                let (kind, severity) = match notification.notification_type {
+                    NotificationType::NewFlow => ("new_flow", NotificationSeverity::Info),
+                    NotificationType::ErrorFlow => ("error_flow", NotificationSeverity::Warning),
+                    NotificationType::LatencyWarning => {
+                        ("latency_warning", NotificationSeverity::Warning)
+                    }
+                    NotificationType::TokenWarning => {
+                        ("token_warning", NotificationSeverity::Warning)
+                    }
+                };
+                Self::new(
+                    event,
+                    kind,
+                    severity,
+                    notification.title.clone(),
+                    format!("{}（Flow {}）", notification.message, notification.flow_id),
+                    &notification.message,
+                )
+            }
+            SystemEvent::Flow { .. } | SystemEvent::Kiro { .. } => return None,
+            SystemEvent::CredentialHealthChanged {
+                uuid,
+                provider_type,
+                healthy,
+                error_count,
+                message,
+            } => {
+                let (severity, title, state) = if *healthy {
+                    (NotificationSeverity::Info, "凭证已恢复", "恢复健康")
+                } else {
+                    (NotificationSeverity::Warning, "凭证不健康", "标记为不健康")
+                };
+                let mut text = format!(
+                    "{} 凭证 {} {}（连续错误 {} 次）",
+                    provider_type, uuid, state, error_count
+                );
+                if let Some(message) = message {
+                    text.push_str(&format!(": {}", message));
+                }
+                Self::new(
+                    event,
+                    kind,
+                    severity,
+                    title,
+                    text,
+                    &format!("{}:{}", uuid, healthy),
+                )
+            }
+            SystemEvent::QuotaCooldownStarted {
+                credential_id,
+                cooldown_until,
+                reason,
+            } => Self::new(
+                event,
+                kind,
+                NotificationSeverity::Warning,
+                "凭证进入配额冷却",
+                format!(
+                    "凭证 {} 冷却至 {}: {}",
+                    credential_id,
+                    cooldown_until.to_rfc3339(),
+                    reason
+                ),
+                credential_id,
+            ),
+            SystemEvent::QuotaCooldownCleared { credential_id } => Self::new(
+                event,
+                kind,
+                NotificationSeverity::Info,
+                "凭证配额冷却解除",
+                format!("凭证 {} 已恢复可用", credential_id),
+                credential_id,
+            ),
+            SystemEvent::QuotaExhausted {
+                provider,
+                model,
+                earliest_recovery,
+            } => Self::new(
+                event,
+                kind,
+                NotificationSeverity::Critical,
+                "凭证配额全部耗尽",
+                format!(
+                    "{} / {} 的凭证全部处于配额冷却期，最早恢复时间: {}",
+                    provider,
+                    model,
+                    earliest_recovery
+                        .map(|t| t.to_rfc3339())
+                        .unwrap_or_else(|| "未知".to_string())
+                ),
+                &format!("{}:{}", provider, model),
+            ),
+            SystemEvent::TokenRefreshFailed {
+                uuid,
+                provider_type,
+                error_type,
+                message,
+            } => Self::new(
+                event,
+                kind,
+                NotificationSeverity::Critical,
+                "Token 刷新永久失败",
+                format!(
+                    "{} 凭证 {} 刷新失败（{}），已自动禁用: {}",
+                    provider_type, uuid, error_type, message
+                ),
+                uuid,
+            ),
+            SystemEvent::ConfigReloaded {
+                success,
+                rolled_back,
+                error,
+            } => {
+                let (severity, title) = match (success, rolled_back) {
+                    (true, _) => (NotificationSeverity::Info, "配置热重载成功"),
+                    (false, true) => (NotificationSeverity::Warning, "配置热重载失败，已回滚"),
+                    (false, false) => (NotificationSeverity::Critical, "配置热重载失败"),
+                };
+                Self::new(
+                    event,
+                    kind,
+                    severity,
+                    title,
+                    error.clone().unwrap_or_else(|| title.to_string()),
+                    &format!("{}:{}", success, rolled_back),
+                )
+            }
+            SystemEvent::ProviderSwitched {
+                request_id,
+                from,
+                to,
+                failure_type,
+            } => Self::new(
+                event,
+                kind,
+                NotificationSeverity::Warning,
+                "Provider 故障转移",
+                format!(
+                    "请求 {} 从 {} 切换到 {}（{}）",
+                    request_id, from, to, failure_type
+                ),
+                &format!("{}:{}", from, to),
+            ),
+            SystemEvent::PluginError {
+                plugin,
+                hook,
+                message,
+            } => Self::new(
+                event,
+                kind,
+                NotificationSeverity::Warning,
+                "插件执行失败",
+                format!("插件 {} 的钩子 {} 失败: {}", plugin, hook, message),
+                &format!("{}:{}", plugin, hook),
+            ),
+        };
+        Some(notification)
+    }
+
+    /// 机器人消息使用的纯文本
+    pub fn text(&self) -> String {
+        format!(
+            "[{}] {}\n{}",
+            self.severity.label(),
+            self.title,
+            self.message
+        )
+    }
+}
+
+/// 死信记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// Sink 名称
+    pub sink: String,
+    /// 尝试次数
+    pub attempts: u32,
+    /// 最后一次错误
+    pub error: String,
+    /// 放弃投递的时间
+    pub failed_at: DateTime<Utc>,
+    /// 通知内容
+    pub notification: Notification,
+}
+
+/// 投递统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationStats {
+    /// 成功投递数
+    pub delivered: u64,
+    /// 重试耗尽进入死信的数量
+    pub failed: u64,
+    /// 被节流窗口抑制的数量
+    pub suppressed: u64,
+}
+
+/// 校验通知配置
+pub fn validate_notifications_config(config: &NotificationsConfig) -> Result<(), String> {
+    let mut names = HashSet::new();
+    for sink in &config.sinks {
+        if sink.name.trim().is_empty() {
+            return Err("Sink 名称不能为空".to_string());
+        }
+        if !names.insert(sink.name.as_str()) {
+            return Err(format!("Sink 名称重复: {}", sink.name));
+        }
+        match sink.kind {
+            NotificationSinkKind::Command => {
+                if sink.command.is_empty() {
+                    return Err(format!("Sink '{}' 未配置 command", sink.name));
+                }
+            }
+            _ => match sink.url.as_deref().map(url::Url::parse) {
+                Some(Ok(url)) if matches!(url.scheme(), "http" | "https") => {}
+                Some(_) => return Err(format!("Sink '{}' 的 url 无效", sink.name)),
+                None => return Err(format!("Sink '{}' 未配置 url", sink.name)),
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Sink 是否订阅该通知
+///
+/// `events` 为空时接收全部通知；否则按告警类型、主题名称或 `*` 匹配。
+pub fn sink_accepts(sink: &NotificationSinkConfig, notification: &Notification) -> bool {
+    sink.enabled
+        && (sink.events.is_empty()
+            || sink
+                .events
+                .iter()
+                .any(|e| e == "*" || *e == notification.kind || e == notification.topic.as_str()))
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC 接受任意长度的密钥");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// 计算通用 Webhook 签名：`sha256=<hex(HMAC-SHA256(secret, "{timestamp}.{body}"))>`
+pub fn webhook_signature(secret: &str, timestamp: i64, body: &str) -> String {
+    let digest = hmac_sha256(
+        secret.as_bytes(),
+        format!("{}.{}", timestamp, body).as_bytes(),
+    );
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+/// 待发送的 Sink HTTP 请求
+#[derive(Debug, Clone)]
+pub struct SinkRequest {
+    /// 请求 URL
+    pub url: String,
+    /// 额外请求头
+    pub headers: Vec<(&'static str, String)>,
+    /// JSON 请求体
+    pub body: String,
+}
+
+/// 构造 HTTP 类 Sink 的请求
+pub fn build_sink_request(
+    sink: &NotificationSinkConfig,
+    notification: &Notification,
+    now: DateTime<Utc>,
+) -> Result<SinkRequest, String> {
+    let url = sink
+        .url
+        .clone()
+        .ok_or_else(|| format!("Sink '{}' 未配置 url", sink.name))?;
+    let secret = sink.secret.as_deref().filter(|s| !s.is_empty());
+    let base64 = base64::engine::general_purpose::STANDARD;
+
+    let request = match sink.kind {
+        NotificationSinkKind::Webhook => {
+            let body = serde_json::to_string(notification).map_err(|e| e.to_string())?;
+            let timestamp = now.timestamp();
+            let mut headers = vec![
+                ("X-ProxyCast-Event", notification.kind.clone()),
+                ("X-ProxyCast-Delivery", notification.event_id.to_string()),
+                (TIMESTAMP_HEADER, timestamp.to_string()),
+            ];
+            if let Some(secret) = secret {
+                headers.push((
+                    SIGNATURE_HEADER,
+                    webhook_signature(secret, timestamp, &body),
+                ));
+            }
+            SinkRequest { url, headers, body }
+        }
+        NotificationSinkKind::Slack => SinkRequest {
+            url,
+            headers: Vec::new(),
+            body: serde_json::json!({ "text": notification.text() }).to_string(),
+        },
+        NotificationSinkKind::Feishu => {
+            let mut body = serde_json::json!({
+                "msg_type": "text",
+                "content": { "text": notification.text() },
+            });
+            if let Some(secret) = secret {
+                // 飞书加签：以 "{timestamp}\n{secret}" 为密钥对空串做 HMAC-SHA256
+                let timestamp = now.timestamp();
+                let key = format!("{}\n{}", timestamp, secret);
+                body["timestamp"] = timestamp.to_string().into();
+                body["sign"] = base64.encode(hmac_sha256(key.as_bytes(), b"")).into();
+            }
+            SinkRequest {
+                url,
+                headers: Vec::new(),
+                body: body.to_string(),
+            }
+        }
+        NotificationSinkKind::Dingtalk => {
+            let mut url = url;
+            if let Some(secret) = secret {
+                // 钉钉加签：以 secret 为密钥对 "{timestamp}\n{secret}" 做 HMAC-SHA256
+                let timestamp = now.timestamp_millis();
+                let sign = base64.encode(hmac_sha256(
+                    secret.as_bytes(),
+                    format!("{}\n{}", timestamp, secret).as_bytes(),
+                ));
+                let separator = if url.contains('?') { '&' } else { '?' };
+                url = format!(
+                    "{}{}timestamp={}&sign={}",
+                    url,
+                    separator,
+                    timestamp,
+                    urlencoding::encode(&sign)
+                );
+            }
+            SinkRequest {
+                url,
+                headers: Vec::new(),
+                body: serde_json::json!({
+                    "msgtype": "text",
+                    "text": { "content": notification.text() },
+                })
+                .to_string(),
+            }
+        }
+        NotificationSinkKind::Command => {
+            return Err(format!("Sink '{}' 不是 HTTP 类型", sink.name));
+        }
+    };
+    Ok(request)
+}
+
+/// 检查机器人接口的业务状态码（飞书 `code`、钉钉 `errcode`，非 0 表示失败）
+fn check_bot_response(kind: NotificationSinkKind, body: &str) -> Result<(), String> {
+    let field = match kind {
+        NotificationSinkKind::Feishu => "code",
+        NotificationSinkKind::Dingtalk => "errcode",
+        _ => return Ok(()),
+    };
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(body) else {
+        return Ok(());
+    };
+    match value.get(field).and_then(|c| c.as_i64()) {
+        Some(code) if code != 0 => Err(format!(
+            "{}={}: {}",
+            field,
+            code,
+            value
+                .get("msg")
+                .or_else(|| value.get("errmsg"))
+                .and_then(|m| m.as_str())
+                .unwrap_or_default()
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn truncate_error(text: &str) -> String {
+    const LIMIT: usize = 200;
+    match text.char_indices().nth(LIMIT) {
+        Some((idx, _)) => format!("{}...", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+/// 运维告警通知服务
+pub struct NotificationService {
+    config: RwLock<NotificationsConfig>,
+    client: reqwest::Client,
+    /// (Sink 名称, 去重键) -> 节流窗口结束时间
+    throttle: Mutex<HashMap<(String, String), Instant>>,
+    dead_letters: Mutex<VecDeque<DeadLetter>>,
+    delivered: AtomicU64,
+    failed: AtomicU64,
+    suppressed: AtomicU64,
+}
+
+impl Default for NotificationService {
+    fn default() -> Self {
+        Self::new(NotificationsConfig::default())
+    }
+}
+
+impl NotificationService {
+    /// 创建通知服务
+    pub fn new(config: NotificationsConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            client: reqwest::Client::new(),
+            throttle: Mutex::new(HashMap::new()),
+            dead_letters: Mutex::new(VecDeque::new()),
+            delivered: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+            suppressed: AtomicU64::new(0),
+        }
+    }
+
+    /// 获取当前配置
+    pub fn config(&self) -> NotificationsConfig {
+        self.config.read().clone()
+    }
+
+    /// 更新配置
+    pub fn set_config(&self, config: NotificationsConfig) {
+        *self.config.write() = config;
+    }
+
+    /// 死信日志路径
+    pub fn dead_letter_path(&self) -> Option<PathBuf> {
+        match &self.config.read().dead_letter_path {
+            Some(path) => Some(expand_tilde(path)),
+            None => dirs::home_dir().map(|home| {
+                home.join(".proxycast")
+                    .join("notification_dead_letters.jsonl")
+            }),
+        }
+    }
+
+    /// 最近的死信记录（按时间先后）
+    pub fn dead_letters(&self) -> Vec<DeadLetter> {
+        self.dead_letters.lock().iter().cloned().collect()
+    }
+
+    /// 投递统计
+    pub fn stats(&self) -> NotificationStats {
+        NotificationStats {
+            delivered: self.delivered.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            suppressed: self.suppressed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 按路由与节流规则选出需要投递的 Sink
+    ///
+    /// 被选中的 Sink 会立即占用节流窗口。
+    pub fn route(&self, notification: &Notification) -> Vec<NotificationSinkConfig> {
+        let config = self.config.read();
+        if !config.enabled {
+            return Vec::new();
+        }
+        config
+            .sinks
+            .iter()
+            .filter(|sink| sink_accepts(sink, notification))
+            .filter(|sink| self.acquire_throttle(sink, notification))
+            .cloned()
+            .collect()
+    }
+
+    fn acquire_throttle(&self, sink: &NotificationSinkConfig, notification: &Notification) -> bool {
+        if sink.throttle_secs == 0 {
+            return true;
+        }
+        let now = Instant::now();
+        let mut throttle = self.throttle.lock();
+        if throttle.len() > THROTTLE_PRUNE_THRESHOLD {
+            throttle.retain(|_, until| *until > now);
+        }
+        let key = (sink.name.clone(), notification.dedup_key.clone());
+        match throttle.get(&key) {
+            Some(until) if *until > now => {
+                self.suppressed.fetch_add(1, Ordering::Relaxed);
+                tracing::debug!(
+                    "[NOTIFY] 节流抑制: sink={}, key={}",
+                    sink.name,
+                    notification.dedup_key
+                );
+                false
+            }
+            _ => {
+                throttle.insert(key, now + Duration::from_secs(sink.throttle_secs));
+                true
+            }
+        }
+    }
+
+    /// 将通知投递到所有匹配的 Sink（每个 Sink 一个后台任务）
+    pub fn dispatch(self: &Arc<Self>, notification: Notification) -> Vec<JoinHandle<()>> {
+        let notification = Arc::new(notification);
+        self.route(&notification)
+            .into_iter()
+            .map(|sink| {
+                let service = self.clone();
+                let notification = notification.clone();
+                tokio::spawn(async move {
+                    let _ = service.deliver(&sink, &notification).await;
+                })
+            })
+            .collect()
+    }
+
+    /// 投递到单个 Sink，失败时按指数退避重试，重试耗尽后写入死信日志
+    pub async fn deliver(
+        &self,
+        sink: &NotificationSinkConfig,
+        notification: &Notification,
+    ) -> Result<(), DeadLetter> {
+        let attempts = sink.max_retries.saturating_add(1);
+        let mut backoff = Duration::from_millis(sink.retry_backoff_ms);
+        let mut last_error = String::new();
+
+        for attempt in 1..=attempts {
+            match self.send_once(sink, notification).await {
+                Ok(()) => {
+                    self.delivered.fetch_add(1, Ordering::Relaxed);
+                    tracing::info!(
+                        "[NOTIFY] 已投递: sink={}, kind={}, attempt={}",
+                        sink.name,
+                        notification.kind,
+                        attempt
+                    );
+                    return Ok(());
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "[NOTIFY] 投递失败: sink={}, kind={}, attempt={}/{}, error={}",
+                        sink.name,
+                        notification.kind,
+                        attempt,
+                        attempts,
+                        e
+                    );
+                    last_error = e;
+                }
+            }
+            if attempt < attempts {
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+            }
+        }
+
+        let dead_letter = DeadLetter {
+            sink: sink.name.clone(),
+            attempts,
+            error: last_error,
+            failed_at: Utc::now(),
+            notification: notification.clone(),
+        };
+        self.record_dead_letter(&dead_letter);
+        Err(dead_letter)
+    }
+
+    /// 向指定 Sink 发送一条测试通知（不重试、不节流）
+    pub async fn send_test(&self, sink_name: &str) -> Result<(), String> {
+        let sink = self
+            .config
+            .read()
+            .sinks
+            .iter()
+            .find(|s| s.name == sink_name)
+            .cloned()
+            .ok_or_else(|| format!("Sink 不存在: {}", sink_name))?;
+        let notification = Notification {
+            event_id: 0,
+            kind: "test".to_string(),
+            topic: EventTopic::Config,
+            severity: NotificationSeverity::Info,
+            title: "ProxyCast 测试通知".to_string(),
+            message: format!("Sink '{}' 配置正常", sink.name),
+            dedup_key: "test".to_string(),
+            timestamp: Utc::now(),
+        };
+        self.send_once(&sink, &notification).await
+    }
+
+    async fn send_once(
+        &self,
+        sink: &NotificationSinkConfig,
+        notification: &Notification,
+    ) -> Result<(), String> {
+        let timeout = Duration::from_secs(sink.timeout_secs.max(1));
+        if sink.kind == NotificationSinkKind::Command {
+            return run_command(sink, notification, timeout).await;
+        }
+
+        let request = build_sink_request(sink, notification, Utc::now())?;
+        let mut builder = self
+            .client
+            .post(&request.url)
+            .timeout(timeout)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(request.body);
+        for (name, value) in request.headers {
+            builder = builder.header(name, value);
+        }
+        let response = builder.send().await.map_err(|e| e.to_string())?;
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(format!("HTTP {}: {}", status, truncate_error(&body)));
+        }
+        check_bot_response(sink.kind, &body)
+    }
+
+    fn record_dead_letter(&self, dead_letter: &DeadLetter) {
+        self.failed.fetch_add(1, Ordering::Relaxed);
+        tracing::error!(
+            "[NOTIFY] 重试耗尽，写入死信: sink={}, kind={}, error={}",
+            dead_letter.sink,
+            dead_letter.notification.kind,
+            dead_letter.error
+        );
+
+        {
+            let mut dead_letters = self.dead_letters.lock();
+            if dead_letters.len() >= DEAD_LETTER_MEMORY_LIMIT {
+                dead_letters.pop_front();
+            }
+            dead_letters.push_back(dead_letter.clone());
+        }
+
+        let Some(path) = self.dead_letter_path() else {
+            return;
+        };
+        let result = (|| -> std::io::Result<()> {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)?;
+            let line = serde_json::to_string(dead_letter)?;
+            writeln!(file, "{}", line)
+        })();
+        if let Err(e) = result {
+            tracing::error!("[NOTIFY] 写入死信日志失败 {:?}: {}", path, e);
+        }
+    }
+
+    /// 订阅事件总线并持续投递告警
+    pub fn start(self: &Arc<Self>, event_bus: &EventBus) -> JoinHandle<()> {
+        let service = self.clone();
+        let mut receiver = event_bus.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        if let Some(notification) = Notification::from_event(&event) {
+                            service.dispatch(notification);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("[NOTIFY] 事件消费落后，丢弃 {} 个事件", n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+}
+
+/// 执行本地命令 Sink
+///
+/// 通知 JSON 写入标准输入，并通过 `PROXYCAST_NOTIFICATION_*` 环境变量提供摘要，
+/// 退出码为 0 视为成功。
+async fn run_command(
+    sink: &NotificationSinkConfig,
+    notification: &Notification,
+    timeout: Duration,
+) -> Result<(), String> {
+    let (program, args) = sink
+        .command
+        .split_first()
+        .ok_or_else(|| format!("Sink '{}' 未配置 command", sink.name))?;
+    let payload = serde_json::to_vec(notification).map_err(|e| e.to_string())?;
+
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .env("PROXYCAST_NOTIFICATION_KIND", &notification.kind)
+        .env(
+            "PROXYCAST_NOTIFICATION_SEVERITY",
+            serde_json::to_value(notification.severity)
+                .ok()
+                .and_then(|v| v.as_str().map(str::to_string))
+                .unwrap_or_default(),
+        )
+        .env("PROXYCAST_NOTIFICATION_TITLE", &notification.title)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("启动命令失败: {}", e))?;
+
+    let run = async {
+        if let Some(mut stdin) = child.stdin.take() {
+            // 命令可能不读取标准输入，写入失败不视为投递失败
+            let _ = stdin.write_all(&payload).await;
+        }
+        child.wait_with_output().await
+    };
+    let output = tokio::time::timeout(timeout, run)
+        .await
+        .map_err(|_| "命令执行超时".to_string())?
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "命令退出状态 {}: {}",
+            output.status,
+            truncate_error(String::from_utf8_lossy(&output.stderr).trim())
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow_monitor::monitor::{NotificationEvent, NotificationSettings};
+    use axum::{extract::State, http::HeaderMap, routing::post, Router};
+    use std::sync::atomic::AtomicUsize;
+
+    /// 本地 HTTP 替身：记录收到的请求，前 `failures` 次返回 500
+    #[derive(Clone, Default)]
+    struct StandIn {
+        requests: Arc<Mutex<Vec<(HeaderMap, String)>>>,
+        failures: Arc<AtomicUsize>,
+    }
+
+    async fn stand_in_handler(
+        State(stand_in): State<StandIn>,
+        headers: HeaderMap,
+        body: String,
+    ) -> axum::http::StatusCode {
+        stand_in.requests.lock().push((headers, body));
+        let remaining = stand_in.failures.load(Ordering::SeqCst);
+        if remaining > 0 {
+            stand_in.failures.store(remaining - 1, Ordering::SeqCst);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        } else {
+            axum::http::StatusCode::OK
+        }
+    }
+
+    async fn spawn_stand_in(failures: usize) -> (String, StandIn) {
+        let stand_in = StandIn::default();
+        stand_in.failures.store(failures, Ordering::SeqCst);
+        let app = Router::new()
+            .route("/hook", post(stand_in_handler))
+            .with_state(stand_in.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+        (format!("http://{}/hook", addr), stand_in)
+    }
+
+    fn sink(name: &str, kind: NotificationSinkKind, url: Option<String>) -> NotificationSinkConfig {
+        NotificationSinkConfig {
+            name: name.to_string(),
+            kind,
+            url,
+            secret: None,
+            command: Vec::new(),
+            events: Vec::new(),
+            throttle_secs: 0,
+            max_retries: 0,
+            retry_backoff_ms: 1,
+            timeout_secs: 5,
+            enabled: true,
+        }
+    }
+
+    fn bus_event(id: u64, event: SystemEvent) -> BusEvent {
+        BusEvent {
+            id,
+            timestamp: Utc::now(),
+            topic: event.topic(),
+            event,
+        }
+    }
+
+    fn quota_exhausted(id: u64) -> Notification {
+        Notification::from_event(&bus_event(
+            id,
+            SystemEvent::QuotaExhausted {
+                provider: "kiro".to_string(),
+                model: "claude-sonnet-4-5".to_string(),
+                earliest_recovery: None,
+            },
+        ))
+        .unwrap()
+    }
+
+    fn service_with(
+        sinks: Vec<NotificationSinkConfig>,
+        dir: &tempfile::TempDir,
+    ) -> Arc<NotificationService> {
+        Arc::new(NotificationService::new(NotificationsConfig {
+            enabled: true,
+            sinks,
+            dead_letter_path: Some(dir.path().join("dead.jsonl").to_string_lossy().into_owned()),
+        }))
+    }
+
+    #[test]
+    fn test_from_event_maps_alerts() {
+        let flow_notification = NotificationEvent::error_flow(
+            "flow-1".to_string(),
+            "gpt-4o".to_string(),
+            "upstream 500".to_string(),
+            &NotificationSettings::default(),
+        );
+        let n = Notification::from_event(&bus_event(
+            1,
+            SystemEvent::Flow {
+                event: WsFlowEvent::Notification {
+                    notification: flow_notification,
+                },
+            },
+        ))
+        .unwrap();
+        assert_eq!(n.kind, "error_flow");
+        assert_eq!(n.topic, EventTopic::Flow);
+        assert!(n.message.contains("flow-1"));
+
+        let n = quota_exhausted(2);
+        assert_eq!(n.kind, "quota_exhausted");
+        assert_eq!(n.severity, NotificationSeverity::Critical);
+        assert_eq!(n.dedup_key, "quota_exhausted:kiro:claude-sonnet-4-5");
+
+        let lifecycle = SystemEvent::Flow {
+            event: WsFlowEvent::RequestRateUpdate {
+                rate: 1.0,
+                count: 1,
+            },
+        };
+        assert!(Notification::from_event(&bus_event(3, lifecycle)).is_none());
+    }
+
+    #[test]
+    fn test_routing_and_throttle() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut quota_only = sink("quota", NotificationSinkKind::Webhook, None);
+        quota_only.events = vec!["quota".to_string()];
+        quota_only.throttle_secs = 60;
+        let mut plugins_only = sink("plugins", NotificationSinkKind::Webhook, None);
+        plugins_only.events = vec!["plugin_error".to_string()];
+        let service = service_with(vec![quota_only, plugins_only], &dir);
+
+        let routed = service.route(&quota_exhausted(1));
+        assert_eq!(routed.len(), 1);
+        assert_eq!(routed[0].name, "quota");
+
+        // 节流窗口内相同告警被抑制
+        assert!(service.route(&quota_exhausted(2)).is_empty());
+        assert_eq!(service.stats().suppressed, 1);
+
+        // 未启用时不路由
+        let mut config = service.config();
+        config.enabled = false;
+        service.set_config(config);
+        let plugin = Notification::from_event(&bus_event(
+            3,
+            SystemEvent::PluginError {
+                plugin: "p".to_string(),
+                hook: "on_request".to_string(),
+                message: "boom".to_string(),
+            },
+        ))
+        .unwrap();
+        assert!(service.route(&plugin).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_signed_delivery_with_retry() {
+        let (url, stand_in) = spawn_stand_in(2).await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut webhook = sink("ops", NotificationSinkKind::Webhook, Some(url));
+        webhook.secret = Some("s3cret".to_string());
+        webhook.max_retries = 2;
+        let service = service_with(vec![webhook.clone()], &dir);
+
+        let notification = quota_exhausted(7);
+        assert!(service.deliver(&webhook, &notification).await.is_ok());
+
+        let requests = stand_in.requests.lock().clone();
+        assert_eq!(requests.len(), 3);
+        let (headers, body) = &requests[2];
+        let timestamp: i64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+        assert_eq!(
+            headers[SIGNATURE_HEADER].to_str().unwrap(),
+            webhook_signature("s3cret", timestamp, body)
+        );
+        let payload: Notification = serde_json::from_str(body).unwrap();
+        assert_eq!(payload.event_id, 7);
+        assert_eq!(service.stats().delivered, 1);
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_retries_write_dead_letter() {
+        let (url, stand_in) = spawn_stand_in(usize::MAX).await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut webhook = sink("ops", NotificationSinkKind::Slack, Some(url));
+        webhook.max_retries = 1;
+        let service = service_with(vec![webhook.clone()], &dir);
+
+        let dead_letter = service
+            .deliver(&webhook, &quota_exhausted(1))
+            .await
+            .unwrap_err();
+        assert_eq!(dead_letter.attempts, 2);
+        assert!(dead_letter.error.contains("500"));
+        assert_eq!(stand_in.requests.lock().len(), 2);
+
+        // Slack 兼容负载
+        let body: serde_json::Value = serde_json::from_str(&stand_in.requests.lock()[0].1).unwrap();
+        assert!(body["text"].as_str().unwrap().contains("凭证配额全部耗尽"));
+
+        assert_eq!(service.dead_letters().len(), 1);
+        let log = std::fs::read_to_string(dir.path().join("dead.jsonl")).unwrap();
+        let logged: DeadLetter = serde_json::from_str(log.lines().next().unwrap()).unwrap();
+        assert_eq!(logged.sink, "ops");
+        assert_eq!(service.stats().failed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_routes_to_stand_in() {
+        let (url, stand_in) = spawn_stand_in(0).await;
+        let dir = tempfile::tempdir().unwrap();
+        let service = service_with(
+            vec![sink("bot", NotificationSinkKind::Feishu, Some(url))],
+            &dir,
+        );
+
+        for handle in service.dispatch(quota_exhausted(1)) {
+            handle.await.unwrap();
+        }
+        let requests = stand_in.requests.lock().clone();
+        assert_eq!(requests.len(), 1);
+        let body: serde_json::Value = serde_json::from_str(&requests[0].1).unwrap();
+        assert_eq!(body["msg_type"], "text");
+    }
+
+    #[test]
+    fn test_bot_signatures() {
+        let now = Utc::now();
+        let notification = quota_exhausted(1);
+
+        let mut feishu = sink(
+            "feishu",
+            NotificationSinkKind::Feishu,
+            Some("https://f/hook".into()),
+        );
+        feishu.secret = Some("key".to_string());
+        let request = build_sink_request(&feishu, &notification, now).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["timestamp"], now.timestamp().to_string());
+        assert!(!body["sign"].as_str().unwrap().is_empty());
+
+        let mut dingtalk = sink(
+            "dingtalk",
+            NotificationSinkKind::Dingtalk,
+            Some("https://d/robot/send?access_token=t".into()),
+        );
+        dingtalk.secret = Some("key".to_string());
+        let request = build_sink_request(&dingtalk, &notification, now).unwrap();
+        assert!(request
+            .url
+            .starts_with("https://d/robot/send?access_token=t&timestamp="));
+        assert!(request.url.contains("&sign="));
+
+        assert!(check_bot_response(
+            NotificationSinkKind::Dingtalk,
+            r#"{"errcode":310000,"errmsg":"sign not match"}"#
+        )
+        .is_err());
+        assert!(check_bot_response(NotificationSinkKind::Feishu, r#"{"code":0}"#).is_ok());
+    }
+
+    #[test]
+    fn test_validate_config() {
+        let mut config = NotificationsConfig {
+            enabled: true,
+            sinks: vec![sink(
+                "a",
+                NotificationSinkKind::Webhook,
+                Some("https://x".into()),
+            )],
+            dead_letter_path: None,
+        };
+        assert!(validate_notifications_config(&config).is_ok());
+
+        config.sinks.push(sink(
+            "a",
+            NotificationSinkKind::Slack,
+            Some("https://y".into()),
+        ));
+        assert!(validate_notifications_config(&config).is_err());
+
+        config.sinks[1] = sink("cmd", NotificationSinkKind::Command, None);
+        assert!(validate_notifications_config(&config).is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_sink_receives_payload() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out.json");
+        let mut command = sink("cmd", NotificationSinkKind::Command, None);
+        command.command = vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            format!("cat > '{}'", out.display()),
+        ];
+        let service = service_with(vec![command.clone()], &dir);
+
+        service
+            .deliver(&command, &quota_exhausted(3))
+            .await
+            .unwrap();
+        let written: Notification =
+            serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+        assert_eq!(written.event_id, 3);
+
+        let mut failing = command.clone();
+        failing.command = vec!["sh".to_string(), "-c".to_string(), "exit 3".to_string()];
+        assert!(service
+            .deliver(&failing, &quota_exhausted(4))
+            .await
+            .is_err());
+    }
+}
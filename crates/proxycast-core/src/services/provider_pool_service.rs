@@ -11,12 +11,29 @@ use crate::models::provider_pool_model::{
 };
 use crate::models::route_model::RouteInfo;
 use crate::providers::kiro::KiroProvider;
+use crate::services::event_bus::{EventBus, SystemEvent};
 use chrono::Utc;
 use reqwest::Client;
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
+/// 凭证选择扩展
+///
+/// 在默认权重算法之前介入，例如根据上游剩余配额排除或挑选凭证
+pub trait CredentialSelector: Send + Sync {
+    /// 是否允许选择该凭证
+    ///
+    /// 所有候选都被排除时会忽略该过滤，避免因预冷却导致无凭证可用
+    fn is_selectable(&self, _credential: &ProviderCredential) -> bool {
+        true
+    }
+
+    /// 从候选凭证中挑选一个，返回候选下标；返回 None 时使用默认权重算法
+    fn pick(&self, candidates: &[ProviderCredential]) -> Option<usize>;
+}
+
 /// 凭证池管理服务
 pub struct ProviderPoolService {
     /// HTTP 客户端（用于健康检测）
@@ -27,6 +44,10 @@ pub struct ProviderPoolService {
     max_error_count: u32,
     /// 健康检查超时时间
     health_check_timeout: Duration,
+    /// 凭证选择扩展
+    selector: std::sync::RwLock<Option<Arc<dyn CredentialSelector>>>,
+    /// 事件总线（发布健康状态变化）
+    event_bus: std::sync::RwLock<Option<Arc<EventBus>>>,
 }
 
 impl Default for ProviderPoolService {
@@ -45,6 +66,26 @@ impl ProviderPoolService {
             round_robin_index: std::sync::RwLock::new(HashMap::new()),
             max_error_count: 3,
             health_check_timeout: Duration::from_secs(30),
+            selector: std::sync::RwLock::new(None),
+            event_bus: std::sync::RwLock::new(None),
+        }
+    }
+
+    /// 设置事件总线（传入 None 时停止发布）
+    pub fn set_event_bus(&self, event_bus: Option<Arc<EventBus>>) {
+        if let Ok(mut guard) = self.event_bus.write() {
+            *guard = event_bus;
+        }
+    }
+
+    fn event_bus(&self) -> Option<Arc<EventBus>> {
+        self.event_bus.read().ok().and_then(|guard| guard.clone())
+    }
+
+    /// 设置凭证选择扩展（传入 None 时恢复默认算法）
+    pub fn set_selector(&self, selector: Option<Arc<dyn CredentialSelector>>) {
+        if let Ok(mut guard) = self.selector.write() {
+            *guard = selector;
         }
     }
 
@@ -184,6 +225,22 @@ impl ProviderPoolService {
         provider_type: &str,
         model: Option<&str>,
     ) -> Result<Option<ProviderCredential>, String> {
+        self.select_credential_filtered(db, provider_type, model, |_| true)
+    }
+
+    /// 选择一个可用的凭证，并额外排除 `filter` 返回 false 的凭证
+    ///
+    /// 用于批处理等需要跳过处于配额冷却期凭证的场景
+    pub fn select_credential_filtered<F>(
+        &self,
+        db: &DbConnection,
+        provider_type: &str,
+        model: Option<&str>,
+        filter: F,
+    ) -> Result<Option<ProviderCredential>, String>
+    where
+        F: Fn(&ProviderCredential) -> bool,
+    {
         let pt: PoolProviderType = provider_type.parse().map_err(|e: String| e)?;
         let conn = db.lock().map_err(|e| e.to_string())?;
         let credentials = ProviderPoolDao::get_by_type(&conn, &pt).map_err(|e| e.to_string())?;
@@ -192,7 +249,7 @@ impl ProviderPoolService {
         // 过滤可用的凭证
         let mut available: Vec<_> = credentials
             .into_iter()
-            .filter(|c| c.is_available())
+            .filter(|c| c.is_available() && filter(c))
             .collect();
 
         // 如果指定了模型，进一步过滤支持该模型的凭证
@@ -204,11 +261,24 @@ impl ProviderPoolService {
             return Ok(None);
         }
 
+        let selector = self.selector.read().ok().and_then(|guard| guard.clone());
+        if let Some(selector) = &selector {
+            if available.iter().any(|c| selector.is_selectable(c)) {
+                available.retain(|c| selector.is_selectable(c));
+            }
+        }
+
         // 如果只有一个可用凭证，直接返回
         if available.len() == 1 {
             return Ok(Some(available.into_iter().next().unwrap()));
         }
 
+        if let Some(index) = selector.and_then(|s| s.pick(&available)) {
+            if index < available.len() {
+                return Ok(Some(available.swap_remove(index)));
+            }
+        }
+
         // 智能选择：基于权重分数选择最优凭证
         let selected = self.select_best_credential_by_weight(&available);
 
@@ -312,6 +382,12 @@ impl ProviderPoolService {
         check_model: Option<&str>,
     ) -> Result<(), String> {
         let conn = db.lock().map_err(|e| e.to_string())?;
+        let event_bus = self.event_bus();
+        let previous = match &event_bus {
+            Some(_) => ProviderPoolDao::get_by_uuid(&conn, uuid).map_err(|e| e.to_string())?,
+            None => None,
+        };
+
         ProviderPoolDao::update_health_status(
             &conn,
             uuid,
@@ -322,7 +398,21 @@ impl ProviderPoolService {
             Some(Utc::now()),
             check_model,
         )
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+        // 从不健康恢复时发布事件
+        if let (Some(bus), Some(cred)) = (event_bus, previous) {
+            if !cred.is_healthy {
+                bus.publish(SystemEvent::CredentialHealthChanged {
+                    uuid: uuid.to_string(),
+                    provider_type: cred.provider_type.to_string(),
+                    healthy: true,
+                    error_count: 0,
+                    message: None,
+                });
+            }
+        }
+        Ok(())
     }
 
     /// 标记凭证为不健康
@@ -350,7 +440,21 @@ impl ProviderPoolService {
             None,
             None,
         )
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+        // 健康 -> 不健康时发布事件
+        if cred.is_healthy && !is_healthy {
+            if let Some(bus) = self.event_bus() {
+                bus.publish(SystemEvent::CredentialHealthChanged {
+                    uuid: uuid.to_string(),
+                    provider_type: cred.provider_type.to_string(),
+                    healthy: false,
+                    error_count: new_error_count,
+                    message: error_message.map(|m| m.to_string()),
+                });
+            }
+        }
+        Ok(())
     }
 
     /// 重置凭证计数器
@@ -0,0 +1,326 @@
+//! 分布式锁与 Token 刷新锁
+
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{Mutex, MutexGuard};
+use tokio::task::JoinHandle;
+
+use super::{SharedState, SharedStateBackend, SharedStateError, SharedStateResult};
+
+/// 分布式锁守卫
+///
+/// 持有期间每隔租期的三分之一续租一次，持有时间超过 `lock_ttl_secs` 也不会被他人获取。
+/// 调用 [`release`](Self::release) 释放；直接 drop 时在后台释放。
+/// 只有持有者（按随机令牌识别）才能释放，过期后被他人重新获取的锁不会被误删。
+pub struct SharedLockGuard {
+    backend: Arc<dyn SharedStateBackend>,
+    key: String,
+    token: String,
+    released: bool,
+    lost: Arc<AtomicBool>,
+    renewal: Option<JoinHandle<()>>,
+}
+
+impl SharedLockGuard {
+    pub(super) fn new(
+        backend: Arc<dyn SharedStateBackend>,
+        key: String,
+        token: String,
+        ttl: Duration,
+    ) -> Self {
+        let lost = Arc::new(AtomicBool::new(false));
+        let renewal = tokio::runtime::Handle::try_current().ok().map(|handle| {
+            handle.spawn(renew(
+                backend.clone(),
+                key.clone(),
+                token.clone(),
+                ttl,
+                lost.clone(),
+            ))
+        });
+        Self {
+            backend,
+            key,
+            token,
+            released: false,
+            lost,
+            renewal,
+        }
+    }
+
+    /// 锁是否仍由自己持有（续租失败、锁已过期时为 false）
+    pub fn is_held(&self) -> bool {
+        !self.lost.load(Ordering::SeqCst)
+    }
+
+    /// 锁在后端中的键
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 释放锁，返回锁是否仍由自己持有（已过期时为 false）
+    pub async fn release(mut self) -> SharedStateResult<bool> {
+        self.released = true;
+        self.stop_renewal();
+        self.backend.delete_if_equals(&self.key, &self.token).await
+    }
+
+    fn stop_renewal(&mut self) {
+        if let Some(renewal) = self.renewal.take() {
+            renewal.abort();
+        }
+    }
+}
+
+/// 续租任务：锁已不由自己持有时停止
+async fn renew(
+    backend: Arc<dyn SharedStateBackend>,
+    key: String,
+    token: String,
+    ttl: Duration,
+    lost: Arc<AtomicBool>,
+) {
+    let interval = (ttl / 3).max(Duration::from_millis(100));
+    loop {
+        tokio::time::sleep(interval).await;
+        match backend.extend_if_equals(&key, &token, ttl).await {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::warn!("[SHARED_STATE] 锁 {} 已过期并可能被其他副本获取", key);
+                lost.store(true, Ordering::SeqCst);
+                return;
+            }
+            Err(e) => tracing::warn!("[SHARED_STATE] 锁 {} 续租失败: {}", key, e),
+        }
+    }
+}
+
+impl Drop for SharedLockGuard {
+    fn drop(&mut self) {
+        self.stop_renewal();
+        if self.released {
+            return;
+        }
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let backend = self.backend.clone();
+        let key = std::mem::take(&mut self.key);
+        let token = std::mem::take(&mut self.token);
+        handle.spawn(async move {
+            if let Err(e) = backend.delete_if_equals(&key, &token).await {
+                tracing::warn!(
+                    "[SHARED_STATE] 释放锁 {} 失败（将在租期结束后过期）: {}",
+                    key,
+                    e
+                );
+            }
+        });
+    }
+}
+
+/// Token 刷新锁
+///
+/// 进程内先获取本地互斥锁，配置共享状态后再获取同名分布式锁，
+/// 保证所有副本中同一时刻只有一个在刷新。共享后端不可用时退回进程内锁；
+/// 其他副本持有锁超过 `lock_wait_secs` 时返回 [`SharedStateError::LockTimeout`]，不会并发刷新。
+///
+/// 每次刷新成功后调用 [`RefreshLockGuard::mark_refreshed`] 递增共享的刷新代数；
+/// 其他副本拿到锁时据此判断凭证文件已被更新，应先重新加载再决定是否刷新。
+pub struct RefreshLock {
+    name: String,
+    local: Mutex<()>,
+    shared: std::sync::RwLock<Option<Arc<SharedState>>>,
+    seen_generation: AtomicI64,
+}
+
+impl RefreshLock {
+    /// 创建刷新锁（`name` 在所有副本间标识同一份凭证）
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            local: Mutex::new(()),
+            shared: std::sync::RwLock::new(None),
+            seen_generation: AtomicI64::new(0),
+        }
+    }
+
+    /// 设置共享状态（传入 None 时只使用进程内锁）
+    pub fn set_shared_state(&self, shared: Option<Arc<SharedState>>) {
+        if let Ok(mut guard) = self.shared.write() {
+            *guard = shared;
+        }
+    }
+
+    fn lock_name(&self) -> String {
+        format!("refresh:{}", self.name)
+    }
+
+    /// 获取刷新锁
+    ///
+    /// 其他副本持有分布式锁超过等待时间时返回 [`SharedStateError::LockTimeout`]：
+    /// 对方很可能正在刷新，此时刷新会使用即将被轮换的 refresh_token，调用方应稍后重试。
+    pub async fn lock(&self) -> SharedStateResult<RefreshLockGuard<'_>> {
+        let local = self.local.lock().await;
+        let mut guard = RefreshLockGuard {
+            lock: self,
+            _local: local,
+            shared: None,
+            distributed: None,
+            peer_refreshed: false,
+        };
+
+        let Some(shared) = self.shared.read().ok().and_then(|s| s.clone()) else {
+            return Ok(guard);
+        };
+        match shared.lock(&self.lock_name()).await {
+            Ok(distributed) => {
+                match shared.generation(&self.name).await {
+                    Ok(generation) => {
+                        let seen = self.seen_generation.swap(generation, Ordering::SeqCst);
+                        guard.peer_refreshed = generation != seen;
+                    }
+                    Err(e) => {
+                        tracing::warn!("[SHARED_STATE] 读取 {} 刷新代数失败: {}", self.name, e);
+                    }
+                }
+                guard.distributed = Some(distributed);
+                guard.shared = Some(shared);
+            }
+            Err(e @ SharedStateError::LockTimeout(_)) => return Err(e),
+            Err(e) => {
+                tracing::warn!(
+                    "[SHARED_STATE] 获取 {} 分布式刷新锁失败，仅使用进程内锁: {}",
+                    self.name,
+                    e
+                );
+            }
+        }
+        Ok(guard)
+    }
+}
+
+/// Token 刷新锁守卫（drop 时释放本地锁与分布式锁）
+pub struct RefreshLockGuard<'a> {
+    lock: &'a RefreshLock,
+    _local: MutexGuard<'a, ()>,
+    shared: Option<Arc<SharedState>>,
+    distributed: Option<SharedLockGuard>,
+    peer_refreshed: bool,
+}
+
+impl RefreshLockGuard<'_> {
+    /// 自上次持有锁以来，是否有其他副本完成了刷新
+    ///
+    /// 为 true 时内存中的凭证可能已过时（refresh_token 已被轮换），应先从文件重新加载。
+    pub fn peer_refreshed(&self) -> bool {
+        self.peer_refreshed
+    }
+
+    /// 记录本副本刷新成功
+    pub async fn mark_refreshed(&self) {
+        let Some(shared) = &self.shared else {
+            return;
+        };
+        match shared.bump_generation(&self.lock.name).await {
+            Ok(generation) => self
+                .lock
+                .seen_generation
+                .store(generation, Ordering::SeqCst),
+            Err(e) => tracing::warn!("[SHARED_STATE] 更新 {} 刷新代数失败: {}", self.lock.name, e),
+        }
+    }
+
+    /// 是否持有分布式锁
+    pub fn is_distributed(&self) -> bool {
+        self.distributed.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SharedStateConfig;
+    use crate::services::shared_state::MemoryBackend;
+
+    #[tokio::test]
+    async fn test_refresh_lock_detects_peer_refresh() {
+        let backend: Arc<dyn SharedStateBackend> = Arc::new(MemoryBackend::new());
+        let config = SharedStateConfig::default();
+        let replica_a = RefreshLock::new("kiro");
+        let replica_b = RefreshLock::new("kiro");
+        replica_a.set_shared_state(Some(Arc::new(SharedState::new(backend.clone(), &config))));
+        replica_b.set_shared_state(Some(Arc::new(SharedState::new(backend, &config))));
+
+        {
+            let guard = replica_a.lock().await.unwrap();
+            assert!(guard.is_distributed());
+            assert!(!guard.peer_refreshed());
+            guard.mark_refreshed().await;
+        }
+        // 守卫在后台释放分布式锁
+        let guard = replica_b.lock().await.unwrap();
+        assert!(guard.peer_refreshed());
+        drop(guard);
+
+        // 自己刷新后不再视为他人刷新
+        let guard = replica_a.lock().await.unwrap();
+        assert!(!guard.peer_refreshed());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_lock_times_out_while_peer_holds_it() {
+        let backend: Arc<dyn SharedStateBackend> = Arc::new(MemoryBackend::new());
+        let config = SharedStateConfig {
+            lock_wait_secs: 0,
+            ..SharedStateConfig::default()
+        };
+        let replica_a = RefreshLock::new("kiro");
+        let replica_b = RefreshLock::new("kiro");
+        replica_a.set_shared_state(Some(Arc::new(SharedState::new(backend.clone(), &config))));
+        replica_b.set_shared_state(Some(Arc::new(SharedState::new(backend, &config))));
+
+        let guard = replica_a.lock().await.unwrap();
+        assert!(matches!(
+            replica_b.lock().await,
+            Err(SharedStateError::LockTimeout(_))
+        ));
+        // 超时后不占用本地锁
+        assert!(replica_b.local.try_lock().is_ok());
+
+        drop(guard);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(replica_b.lock().await.unwrap().is_distributed());
+    }
+
+    #[tokio::test]
+    async fn test_lock_is_renewed_past_ttl() {
+        let backend: Arc<dyn SharedStateBackend> = Arc::new(MemoryBackend::new());
+        let config = SharedStateConfig {
+            lock_ttl_secs: 1,
+            lock_wait_secs: 0,
+            ..SharedStateConfig::default()
+        };
+        let a = SharedState::new(backend.clone(), &config);
+        let b = SharedState::new(backend, &config);
+
+        let guard = a.lock("refresh:kiro").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(guard.is_held());
+        assert!(b.try_lock("refresh:kiro").await.unwrap().is_none());
+
+        assert!(guard.release().await.unwrap());
+        assert!(b.try_lock("refresh:kiro").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_lock_without_shared_state() {
+        let lock = RefreshLock::new("gemini");
+        let guard = lock.lock().await.unwrap();
+        assert!(!guard.is_distributed());
+        assert!(!guard.peer_refreshed());
+        guard.mark_refreshed().await;
+    }
+}
@@ -0,0 +1,190 @@
+//! 进程内共享状态后端
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use super::{SharedStateBackend, SharedStateError, SharedStateResult};
+
+struct Entry {
+    value: String,
+    expires_at: Option<Instant>,
+}
+
+impl Entry {
+    fn is_live(&self, now: Instant) -> bool {
+        self.expires_at.is_none_or(|at| at > now)
+    }
+}
+
+/// 进程内后端
+///
+/// 状态只在当前进程可见，用于单实例部署与测试。
+#[derive(Default)]
+pub struct MemoryBackend {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_entries<T>(&self, f: impl FnOnce(&mut HashMap<String, Entry>, Instant) -> T) -> T {
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        entries.retain(|_, entry| entry.is_live(now));
+        f(&mut entries, now)
+    }
+}
+
+#[async_trait]
+impl SharedStateBackend for MemoryBackend {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn set_if_absent(
+        &self,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> SharedStateResult<bool> {
+        Ok(self.with_entries(|entries, now| {
+            if entries.contains_key(key) {
+                return false;
+            }
+            entries.insert(
+                key.to_string(),
+                Entry {
+                    value: value.to_string(),
+                    expires_at: Some(now + ttl),
+                },
+            );
+            true
+        }))
+    }
+
+    async fn delete_if_equals(&self, key: &str, expected: &str) -> SharedStateResult<bool> {
+        Ok(self.with_entries(|entries, _| {
+            if entries
+                .get(key)
+                .is_some_and(|entry| entry.value == expected)
+            {
+                entries.remove(key);
+                true
+            } else {
+                false
+            }
+        }))
+    }
+
+    async fn extend_if_equals(
+        &self,
+        key: &str,
+        expected: &str,
+        ttl: Duration,
+    ) -> SharedStateResult<bool> {
+        Ok(
+            self.with_entries(|entries, now| match entries.get_mut(key) {
+                Some(entry) if entry.value == expected => {
+                    entry.expires_at = Some(now + ttl);
+                    true
+                }
+                _ => false,
+            }),
+        )
+    }
+
+    async fn get(&self, key: &str) -> SharedStateResult<Option<String>> {
+        Ok(self.with_entries(|entries, _| entries.get(key).map(|entry| entry.value.clone())))
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: Option<Duration>) -> SharedStateResult<()> {
+        self.with_entries(|entries, now| {
+            entries.insert(
+                key.to_string(),
+                Entry {
+                    value: value.to_string(),
+                    expires_at: ttl.map(|ttl| now + ttl),
+                },
+            );
+        });
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> SharedStateResult<bool> {
+        Ok(self.with_entries(|entries, _| entries.remove(key).is_some()))
+    }
+
+    async fn scan_prefix(&self, prefix: &str) -> SharedStateResult<Vec<(String, String)>> {
+        Ok(self.with_entries(|entries, _| {
+            let mut found: Vec<(String, String)> = entries
+                .iter()
+                .filter(|(key, _)| key.starts_with(prefix))
+                .map(|(key, entry)| (key.clone(), entry.value.clone()))
+                .collect();
+            found.sort();
+            found
+        }))
+    }
+
+    async fn increment(&self, key: &str, delta: i64) -> SharedStateResult<i64> {
+        self.with_entries(|entries, _| {
+            let entry = entries.entry(key.to_string()).or_insert_with(|| Entry {
+                value: "0".to_string(),
+                expires_at: None,
+            });
+            let current: i64 = entry.value.parse().map_err(|_| {
+                SharedStateError::Config(format!("键 {} 的值不是整数: {}", key, entry.value))
+            })?;
+            let next = current + delta;
+            entry.value = next.to_string();
+            Ok(next)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_expired_entries_are_invisible() {
+        let backend = MemoryBackend::new();
+        assert!(backend
+            .set_if_absent("k", "v", Duration::from_millis(20))
+            .await
+            .unwrap());
+        assert!(!backend
+            .set_if_absent("k", "w", Duration::from_secs(1))
+            .await
+            .unwrap());
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert!(backend.get("k").await.unwrap().is_none());
+        assert!(backend
+            .set_if_absent("k", "w", Duration::from_secs(1))
+            .await
+            .unwrap());
+        assert!(!backend
+            .extend_if_equals("k", "v", Duration::from_secs(1))
+            .await
+            .unwrap());
+        assert!(!backend.delete_if_equals("k", "v").await.unwrap());
+        assert!(backend.delete_if_equals("k", "w").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_increment_rejects_non_integer() {
+        let backend = MemoryBackend::new();
+        assert_eq!(backend.increment("n", 5).await.unwrap(), 5);
+        assert_eq!(backend.increment("n", -2).await.unwrap(), 3);
+        backend.set("s", "abc", None).await.unwrap();
+        assert!(backend.increment("s", 1).await.is_err());
+    }
+}
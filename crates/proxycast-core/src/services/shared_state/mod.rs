@@ -0,0 +1,566 @@
+//! 多实例共享状态
+//!
+//! 多个 ProxyCast 副本指向同一后端时，通过本模块协调进程间状态：
+//! - 分布式 Token 刷新锁（避免多个副本同时使用同一个一次性 refresh_token）
+//! - 配额冷却状态
+//! - 刷新后的 Token 缓存
+//! - 请求计数器
+//!
+//! 后端实现：
+//! - [`MemoryBackend`]：进程内存储（默认，不跨进程共享）
+//! - [`SqliteBackend`]：SQLite 文件，依赖 SQLite 文件锁，适用于同一主机上的副本
+//! - [`RedisBackend`]：Redis（`redis` crate），适用于跨主机部署
+
+mod lock;
+mod memory;
+mod redis;
+mod sqlite;
+
+pub use lock::{RefreshLock, RefreshLockGuard, SharedLockGuard};
+pub use memory::MemoryBackend;
+pub use redis::RedisBackend;
+pub use sqlite::SqliteBackend;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Serialize;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::config::{expand_tilde, SharedStateBackendKind, SharedStateConfig};
+use crate::credential::QuotaExceededRecord;
+use crate::models::provider_pool_model::CachedTokenInfo;
+
+/// 键类别：分布式锁
+const KIND_LOCK: &str = "lock";
+/// 键类别：配额冷却记录
+const KIND_COOLDOWN: &str = "cooldown";
+/// 键类别：刷新后的 Token
+const KIND_TOKEN: &str = "token";
+/// 键类别：计数器
+const KIND_COUNTER: &str = "counter";
+/// 键类别：刷新代数（每次刷新成功后递增）
+const KIND_GENERATION: &str = "generation";
+
+/// 未知过期时间的 Token 在共享缓存中的保留时长
+const DEFAULT_TOKEN_TTL: Duration = Duration::from_secs(3600);
+
+/// 获取锁失败后的初始重试间隔
+const LOCK_RETRY_INITIAL: Duration = Duration::from_millis(50);
+/// 获取锁失败后的最大重试间隔
+const LOCK_RETRY_MAX: Duration = Duration::from_millis(500);
+
+/// 共享状态错误
+#[derive(Debug, Error)]
+pub enum SharedStateError {
+    #[error("数据库错误: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("IO 错误: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("序列化错误: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Redis 错误: {0}")]
+    Redis(String),
+
+    #[error("配置错误: {0}")]
+    Config(String),
+
+    #[error("等待锁超时: {0}")]
+    LockTimeout(String),
+
+    #[error("后台任务失败: {0}")]
+    Task(String),
+}
+
+pub type SharedStateResult<T> = Result<T, SharedStateError>;
+
+/// 共享状态后端
+///
+/// 后端只提供带过期时间的键值操作与原子计数，锁、冷却和 Token 缓存的语义由
+/// [`SharedState`] 在其上构建。所有写操作必须对指向同一后端的全部进程原子可见。
+#[async_trait]
+pub trait SharedStateBackend: Send + Sync {
+    /// 后端名称（用于日志与状态查询）
+    fn name(&self) -> &'static str;
+
+    /// 仅在键不存在（或已过期）时写入，返回是否写入成功
+    async fn set_if_absent(&self, key: &str, value: &str, ttl: Duration)
+        -> SharedStateResult<bool>;
+
+    /// 仅在当前值等于 `expected` 时删除，返回是否删除
+    async fn delete_if_equals(&self, key: &str, expected: &str) -> SharedStateResult<bool>;
+
+    /// 仅在当前值等于 `expected` 时把过期时间重设为 `ttl`，返回是否续期
+    async fn extend_if_equals(
+        &self,
+        key: &str,
+        expected: &str,
+        ttl: Duration,
+    ) -> SharedStateResult<bool>;
+
+    /// 读取未过期的值
+    async fn get(&self, key: &str) -> SharedStateResult<Option<String>>;
+
+    /// 写入值，`ttl` 为 None 时永不过期
+    async fn set(&self, key: &str, value: &str, ttl: Option<Duration>) -> SharedStateResult<()>;
+
+    /// 删除键，返回键是否存在
+    async fn delete(&self, key: &str) -> SharedStateResult<bool>;
+
+    /// 列出指定前缀下所有未过期的键值
+    async fn scan_prefix(&self, prefix: &str) -> SharedStateResult<Vec<(String, String)>>;
+
+    /// 原子递增整数值（键不存在时视为 0），返回递增后的值
+    async fn increment(&self, key: &str, delta: i64) -> SharedStateResult<i64>;
+}
+
+/// 默认 SQLite 状态文件路径：`~/.proxycast/shared_state.db`
+pub fn default_sqlite_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".proxycast").join("shared_state.db"))
+}
+
+/// 验证共享状态配置
+pub fn validate_shared_state_config(config: &SharedStateConfig) -> Result<(), String> {
+    if config.key_prefix.trim().is_empty() {
+        return Err("key_prefix 不能为空".to_string());
+    }
+    if config.lock_ttl_secs == 0 {
+        return Err("lock_ttl_secs 必须大于 0".to_string());
+    }
+    if config.sync_interval_secs == 0 {
+        return Err("sync_interval_secs 必须大于 0".to_string());
+    }
+    if config.backend == SharedStateBackendKind::Redis {
+        let url = config
+            .redis_url
+            .as_deref()
+            .ok_or_else(|| "redis 后端需要配置 redis_url".to_string())?;
+        RedisBackend::new(url).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// 共享状态快照（管理 API 使用）
+#[derive(Debug, Clone, Serialize)]
+pub struct SharedStateSnapshot {
+    /// 后端名称
+    pub backend: String,
+    /// 当前实例标识
+    pub node_id: String,
+    /// 键前缀
+    pub key_prefix: String,
+    /// 集群计数器
+    pub counters: BTreeMap<String, i64>,
+    /// 集群范围内的配额冷却记录
+    pub cooldowns: Vec<QuotaExceededRecord>,
+}
+
+/// 共享状态
+///
+/// 在 [`SharedStateBackend`] 之上提供分布式锁、冷却状态、Token 缓存与计数器。
+/// 所有键都带 `{key_prefix}:{类别}:` 前缀。
+pub struct SharedState {
+    backend: Arc<dyn SharedStateBackend>,
+    key_prefix: String,
+    node_id: String,
+    lock_ttl: Duration,
+    lock_wait: Duration,
+    sync_interval: Duration,
+}
+
+impl std::fmt::Debug for SharedState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedState")
+            .field("backend", &self.backend.name())
+            .field("key_prefix", &self.key_prefix)
+            .field("node_id", &self.node_id)
+            .finish()
+    }
+}
+
+impl SharedState {
+    /// 使用指定后端创建共享状态
+    pub fn new(backend: Arc<dyn SharedStateBackend>, config: &SharedStateConfig) -> Self {
+        let node_id = config.node_id.clone().unwrap_or_else(|| {
+            let id = Uuid::new_v4().simple().to_string();
+            format!("node-{}-{}", std::process::id(), &id[..8])
+        });
+        Self {
+            backend,
+            key_prefix: config.key_prefix.clone(),
+            node_id,
+            lock_ttl: Duration::from_secs(config.lock_ttl_secs.max(1)),
+            lock_wait: Duration::from_secs(config.lock_wait_secs),
+            sync_interval: Duration::from_secs(config.sync_interval_secs.max(1)),
+        }
+    }
+
+    /// 按配置打开共享状态
+    ///
+    /// `memory` 后端返回 None：进程内状态已由各服务自行维护，无需额外同步。
+    pub fn open(config: &SharedStateConfig) -> SharedStateResult<Option<Self>> {
+        validate_shared_state_config(config).map_err(SharedStateError::Config)?;
+        let backend: Arc<dyn SharedStateBackend> = match config.backend {
+            SharedStateBackendKind::Memory => return Ok(None),
+            SharedStateBackendKind::Sqlite => {
+                let path = match &config.sqlite_path {
+                    Some(path) => expand_tilde(path),
+                    None => default_sqlite_path().ok_or_else(|| {
+                        SharedStateError::Config("无法确定默认 SQLite 路径".to_string())
+                    })?,
+                };
+                Arc::new(SqliteBackend::open(path)?)
+            }
+            SharedStateBackendKind::Redis => Arc::new(RedisBackend::new(
+                config.redis_url.as_deref().unwrap_or_default(),
+            )?),
+        };
+        Ok(Some(Self::new(backend, config)))
+    }
+
+    /// 后端名称
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    /// 当前实例标识
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    /// 冷却状态同步间隔
+    pub fn sync_interval(&self) -> Duration {
+        self.sync_interval
+    }
+
+    fn key(&self, kind: &str, name: &str) -> String {
+        format!("{}:{}:{}", self.key_prefix, kind, name)
+    }
+
+    fn prefix(&self, kind: &str) -> String {
+        format!("{}:{}:", self.key_prefix, kind)
+    }
+
+    /// 尝试获取分布式锁（不等待）
+    pub async fn try_lock(&self, name: &str) -> SharedStateResult<Option<SharedLockGuard>> {
+        let key = self.key(KIND_LOCK, name);
+        let token = format!("{}:{}", self.node_id, Uuid::new_v4().simple());
+        if self
+            .backend
+            .set_if_absent(&key, &token, self.lock_ttl)
+            .await?
+        {
+            Ok(Some(SharedLockGuard::new(
+                self.backend.clone(),
+                key,
+                token,
+                self.lock_ttl,
+            )))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// 获取分布式锁，最多等待 `lock_wait_secs`
+    ///
+    /// 持有期间守卫在后台续租；持有者崩溃后锁在 `lock_ttl_secs` 内过期，不会造成死锁。
+    pub async fn lock(&self, name: &str) -> SharedStateResult<SharedLockGuard> {
+        let deadline = tokio::time::Instant::now() + self.lock_wait;
+        let mut backoff = LOCK_RETRY_INITIAL;
+        loop {
+            if let Some(guard) = self.try_lock(name).await? {
+                return Ok(guard);
+            }
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                return Err(SharedStateError::LockTimeout(name.to_string()));
+            }
+            tokio::time::sleep(backoff.min(deadline - now)).await;
+            backoff = (backoff * 2).min(LOCK_RETRY_MAX);
+        }
+    }
+
+    /// 写入配额冷却记录（冷却结束时自动过期）
+    pub async fn put_cooldown(&self, record: &QuotaExceededRecord) -> SharedStateResult<()> {
+        let remaining = (record.cooldown_until - Utc::now())
+            .to_std()
+            .unwrap_or_default();
+        if remaining.is_zero() {
+            return Ok(());
+        }
+        let value = serde_json::to_string(record)?;
+        self.backend
+            .set(
+                &self.key(KIND_COOLDOWN, &record.credential_id),
+                &value,
+                Some(remaining),
+            )
+            .await
+    }
+
+    /// 移除配额冷却记录
+    pub async fn clear_cooldown(&self, credential_id: &str) -> SharedStateResult<bool> {
+        self.backend
+            .delete(&self.key(KIND_COOLDOWN, credential_id))
+            .await
+    }
+
+    /// 列出集群范围内仍在冷却中的记录
+    pub async fn cooldowns(&self) -> SharedStateResult<Vec<QuotaExceededRecord>> {
+        let now = Utc::now();
+        let entries = self
+            .backend
+            .scan_prefix(&self.prefix(KIND_COOLDOWN))
+            .await?;
+        Ok(entries
+            .into_iter()
+            .filter_map(|(key, value)| match serde_json::from_str(&value) {
+                Ok(record) => Some(record),
+                Err(e) => {
+                    tracing::warn!("[SHARED_STATE] 忽略无法解析的冷却记录 {}: {}", key, e);
+                    None
+                }
+            })
+            .filter(|record: &QuotaExceededRecord| record.cooldown_until > now)
+            .collect())
+    }
+
+    /// 读取其他副本刷新后写入的 Token
+    pub async fn cached_token(&self, uuid: &str) -> SharedStateResult<Option<CachedTokenInfo>> {
+        match self.backend.get(&self.key(KIND_TOKEN, uuid)).await? {
+            Some(value) => Ok(Some(serde_json::from_str(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 写入刷新后的 Token（过期时自动移除）
+    pub async fn put_token(&self, uuid: &str, info: &CachedTokenInfo) -> SharedStateResult<()> {
+        let ttl = match info.expiry_time {
+            Some(expiry) => match (expiry - Utc::now()).to_std() {
+                Ok(remaining) if !remaining.is_zero() => remaining,
+                _ => return Ok(()),
+            },
+            None => DEFAULT_TOKEN_TTL,
+        };
+        let value = serde_json::to_string(info)?;
+        self.backend
+            .set(&self.key(KIND_TOKEN, uuid), &value, Some(ttl))
+            .await
+    }
+
+    /// 递增集群计数器，返回递增后的值
+    pub async fn increment_counter(&self, name: &str, delta: i64) -> SharedStateResult<i64> {
+        self.backend
+            .increment(&self.key(KIND_COUNTER, name), delta)
+            .await
+    }
+
+    /// 在后台递增计数器（供同步代码调用，失败只记录日志）
+    pub fn spawn_increment(self: &Arc<Self>, name: String, delta: i64) {
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let shared = self.clone();
+        handle.spawn(async move {
+            if let Err(e) = shared.increment_counter(&name, delta).await {
+                tracing::debug!("[SHARED_STATE] 递增计数器 {} 失败: {}", name, e);
+            }
+        });
+    }
+
+    /// 读取集群计数器
+    pub async fn counter(&self, name: &str) -> SharedStateResult<i64> {
+        Ok(self
+            .backend
+            .get(&self.key(KIND_COUNTER, name))
+            .await?
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0))
+    }
+
+    /// 列出全部集群计数器
+    pub async fn counters(&self) -> SharedStateResult<BTreeMap<String, i64>> {
+        let prefix = self.prefix(KIND_COUNTER);
+        let entries = self.backend.scan_prefix(&prefix).await?;
+        Ok(entries
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let name = key.strip_prefix(&prefix)?.to_string();
+                Some((name, value.parse().ok()?))
+            })
+            .collect())
+    }
+
+    /// 读取刷新代数
+    pub(crate) async fn generation(&self, name: &str) -> SharedStateResult<i64> {
+        Ok(self
+            .backend
+            .get(&self.key(KIND_GENERATION, name))
+            .await?
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0))
+    }
+
+    /// 递增刷新代数
+    pub(crate) async fn bump_generation(&self, name: &str) -> SharedStateResult<i64> {
+        self.backend
+            .increment(&self.key(KIND_GENERATION, name), 1)
+            .await
+    }
+
+    /// 获取状态快照
+    pub async fn snapshot(&self) -> SharedStateResult<SharedStateSnapshot> {
+        Ok(SharedStateSnapshot {
+            backend: self.backend_name().to_string(),
+            node_id: self.node_id.clone(),
+            key_prefix: self.key_prefix.clone(),
+            counters: self.counters().await?,
+            cooldowns: self.cooldowns().await?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+
+    fn shared_pair() -> (SharedState, SharedState) {
+        let backend: Arc<dyn SharedStateBackend> = Arc::new(MemoryBackend::new());
+        let config = SharedStateConfig {
+            lock_wait_secs: 0,
+            ..SharedStateConfig::default()
+        };
+        (
+            SharedState::new(backend.clone(), &config),
+            SharedState::new(backend, &config),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_lock_is_exclusive_across_instances() {
+        let (a, b) = shared_pair();
+        let guard = a.try_lock("refresh:kiro").await.unwrap().unwrap();
+        assert!(b.try_lock("refresh:kiro").await.unwrap().is_none());
+        assert!(matches!(
+            b.lock("refresh:kiro").await,
+            Err(SharedStateError::LockTimeout(_))
+        ));
+        // 不同名称的锁互不影响
+        assert!(b.try_lock("refresh:qwen").await.unwrap().is_some());
+
+        assert!(guard.release().await.unwrap());
+        assert!(b.try_lock("refresh:kiro").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_lock_waits_for_release() {
+        let backend: Arc<dyn SharedStateBackend> = Arc::new(MemoryBackend::new());
+        let config = SharedStateConfig::default();
+        let a = SharedState::new(backend.clone(), &config);
+        let b = SharedState::new(backend, &config);
+
+        let guard = a.lock("x").await.unwrap();
+        let waiter = tokio::spawn(async move { b.lock("x").await.map(|_| ()) });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!waiter.is_finished());
+        drop(guard);
+        waiter.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cooldowns_are_shared() {
+        let (a, b) = shared_pair();
+        let now = Utc::now();
+        let record = QuotaExceededRecord {
+            credential_id: "cred-1".to_string(),
+            exceeded_at: now,
+            cooldown_until: now + ChronoDuration::minutes(5),
+            reason: "429".to_string(),
+        };
+        a.put_cooldown(&record).await.unwrap();
+        // 已结束的冷却不写入
+        a.put_cooldown(&QuotaExceededRecord {
+            credential_id: "cred-2".to_string(),
+            cooldown_until: now - ChronoDuration::seconds(1),
+            ..record.clone()
+        })
+        .await
+        .unwrap();
+
+        let cooldowns = b.cooldowns().await.unwrap();
+        assert_eq!(cooldowns.len(), 1);
+        assert_eq!(cooldowns[0].credential_id, "cred-1");
+
+        assert!(b.clear_cooldown("cred-1").await.unwrap());
+        assert!(a.cooldowns().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_token_cache_and_counters() {
+        let (a, b) = shared_pair();
+        let info = CachedTokenInfo {
+            access_token: Some("at".to_string()),
+            refresh_token: Some("rt".to_string()),
+            expiry_time: Some(Utc::now() + ChronoDuration::hours(1)),
+            ..CachedTokenInfo::default()
+        };
+        a.put_token("uuid-1", &info).await.unwrap();
+        let cached = b.cached_token("uuid-1").await.unwrap().unwrap();
+        assert_eq!(cached.access_token.as_deref(), Some("at"));
+        assert!(b.cached_token("uuid-2").await.unwrap().is_none());
+
+        a.increment_counter("requests:total", 2).await.unwrap();
+        b.increment_counter("requests:total", 3).await.unwrap();
+        assert_eq!(a.counter("requests:total").await.unwrap(), 5);
+        let counters = b.counters().await.unwrap();
+        assert_eq!(counters.get("requests:total"), Some(&5));
+
+        // 刷新代数不计入计数器
+        a.bump_generation("kiro").await.unwrap();
+        assert_eq!(b.generation("kiro").await.unwrap(), 1);
+        assert_eq!(b.counters().await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_validate_config() {
+        assert!(validate_shared_state_config(&SharedStateConfig::default()).is_ok());
+
+        let redis = SharedStateConfig {
+            backend: SharedStateBackendKind::Redis,
+            ..SharedStateConfig::default()
+        };
+        assert!(validate_shared_state_config(&redis).is_err());
+        assert!(validate_shared_state_config(&SharedStateConfig {
+            redis_url: Some("redis://:pw@127.0.0.1:6379/2".to_string()),
+            ..redis.clone()
+        })
+        .is_ok());
+        assert!(validate_shared_state_config(&SharedStateConfig {
+            redis_url: Some("http://127.0.0.1".to_string()),
+            ..redis
+        })
+        .is_err());
+
+        assert!(validate_shared_state_config(&SharedStateConfig {
+            lock_ttl_secs: 0,
+            ..SharedStateConfig::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_memory_backend_is_not_opened() {
+        assert!(SharedState::open(&SharedStateConfig::default())
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,325 @@
+//! SQLite 共享状态后端
+//!
+//! 同一主机上的多个副本打开同一个数据库文件，依赖 SQLite 的文件锁保证原子性：
+//! 所有写操作都在 `BEGIN IMMEDIATE` 事务中完成，并发写入者在 busy_timeout 内排队等待。
+//! 数据库调用在阻塞线程池中执行，不占用异步运行时线程。
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+
+use super::{SharedStateBackend, SharedStateError, SharedStateResult};
+
+/// 等待其他进程释放文件锁的最长时间
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// SQLite 文件后端
+pub struct SqliteBackend {
+    conn: Arc<Mutex<Connection>>,
+    path: PathBuf,
+}
+
+fn now_ms() -> i64 {
+    Utc::now().timestamp_millis()
+}
+
+fn expires_at(ttl: Option<Duration>) -> Option<i64> {
+    ttl.map(|ttl| now_ms() + ttl.as_millis() as i64)
+}
+
+impl SqliteBackend {
+    /// 打开（或创建）状态数据库
+    pub fn open(path: PathBuf) -> SharedStateResult<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(&path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        // WAL 允许读者与写者并发；journal_mode 返回结果行，需要用 query 执行
+        conn.query_row("PRAGMA journal_mode=WAL", [], |_| Ok(()))?;
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS shared_state (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                expires_at INTEGER
+            );
+            CREATE INDEX IF NOT EXISTS idx_shared_state_expires
+                ON shared_state(expires_at);
+            "#,
+        )?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            path,
+        })
+    }
+
+    /// 数据库文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 在阻塞线程池中执行数据库操作
+    async fn run<T, F>(&self, f: F) -> SharedStateResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> SharedStateResult<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            f(&mut conn)
+        })
+        .await
+        .map_err(|e| SharedStateError::Task(e.to_string()))?
+    }
+}
+
+/// 读取未过期的值
+fn live_value(conn: &Connection, key: &str, now: i64) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT value FROM shared_state
+         WHERE key = ?1 AND (expires_at IS NULL OR expires_at > ?2)",
+        params![key, now],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+#[async_trait]
+impl SharedStateBackend for SqliteBackend {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    async fn set_if_absent(
+        &self,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> SharedStateResult<bool> {
+        let (key, value) = (key.to_string(), value.to_string());
+        self.run(move |conn| {
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            let now = now_ms();
+            tx.execute(
+                "DELETE FROM shared_state WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+                params![now],
+            )?;
+            let inserted = tx.execute(
+                "INSERT OR IGNORE INTO shared_state (key, value, expires_at) VALUES (?1, ?2, ?3)",
+                params![key, value, expires_at(Some(ttl))],
+            )?;
+            tx.commit()?;
+            Ok(inserted == 1)
+        })
+        .await
+    }
+
+    async fn delete_if_equals(&self, key: &str, expected: &str) -> SharedStateResult<bool> {
+        let (key, expected) = (key.to_string(), expected.to_string());
+        self.run(move |conn| {
+            let deleted = conn.execute(
+                "DELETE FROM shared_state
+                 WHERE key = ?1 AND value = ?2 AND (expires_at IS NULL OR expires_at > ?3)",
+                params![key, expected, now_ms()],
+            )?;
+            Ok(deleted == 1)
+        })
+        .await
+    }
+
+    async fn extend_if_equals(
+        &self,
+        key: &str,
+        expected: &str,
+        ttl: Duration,
+    ) -> SharedStateResult<bool> {
+        let (key, expected) = (key.to_string(), expected.to_string());
+        self.run(move |conn| {
+            let updated = conn.execute(
+                "UPDATE shared_state SET expires_at = ?1
+                 WHERE key = ?2 AND value = ?3 AND (expires_at IS NULL OR expires_at > ?4)",
+                params![expires_at(Some(ttl)), key, expected, now_ms()],
+            )?;
+            Ok(updated == 1)
+        })
+        .await
+    }
+
+    async fn get(&self, key: &str) -> SharedStateResult<Option<String>> {
+        let key = key.to_string();
+        self.run(move |conn| Ok(live_value(conn, &key, now_ms())?))
+            .await
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: Option<Duration>) -> SharedStateResult<()> {
+        let (key, value) = (key.to_string(), value.to_string());
+        self.run(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO shared_state (key, value, expires_at) VALUES (?1, ?2, ?3)",
+                params![key, value, expires_at(ttl)],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn delete(&self, key: &str) -> SharedStateResult<bool> {
+        let key = key.to_string();
+        self.run(move |conn| {
+            let deleted = conn.execute(
+                "DELETE FROM shared_state
+                 WHERE key = ?1 AND (expires_at IS NULL OR expires_at > ?2)",
+                params![key, now_ms()],
+            )?;
+            Ok(deleted == 1)
+        })
+        .await
+    }
+
+    async fn scan_prefix(&self, prefix: &str) -> SharedStateResult<Vec<(String, String)>> {
+        let prefix = prefix.to_string();
+        self.run(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT key, value FROM shared_state
+                 WHERE substr(key, 1, length(?1)) = ?1
+                   AND (expires_at IS NULL OR expires_at > ?2)
+                 ORDER BY key",
+            )?;
+            let rows = stmt
+                .query_map(params![prefix, now_ms()], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })?
+                .collect::<rusqlite::Result<Vec<(String, String)>>>()?;
+            Ok(rows)
+        })
+        .await
+    }
+
+    async fn increment(&self, key: &str, delta: i64) -> SharedStateResult<i64> {
+        let key = key.to_string();
+        self.run(move |conn| {
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            let current = match live_value(&tx, &key, now_ms())? {
+                Some(value) => value.parse::<i64>().map_err(|_| {
+                    SharedStateError::Config(format!("键 {} 的值不是整数: {}", key, value))
+                })?,
+                None => 0,
+            };
+            let next = current + delta;
+            tx.execute(
+                "INSERT INTO shared_state (key, value, expires_at) VALUES (?1, ?2, NULL)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value,
+                     expires_at = CASE WHEN shared_state.expires_at > ?3
+                         THEN shared_state.expires_at ELSE NULL END",
+                params![key, next.to_string(), now_ms()],
+            )?;
+            tx.commit()?;
+            Ok(next)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SharedStateConfig;
+    use crate::services::shared_state::SharedState;
+    use tempfile::TempDir;
+
+    /// 两个后端实例打开同一文件，模拟同一主机上的两个副本
+    fn replicas() -> (TempDir, SqliteBackend, SqliteBackend) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("state").join("shared.db");
+        let a = SqliteBackend::open(path.clone()).unwrap();
+        let b = SqliteBackend::open(path).unwrap();
+        (dir, a, b)
+    }
+
+    #[tokio::test]
+    async fn test_lock_semantics_across_connections() {
+        let (_dir, a, b) = replicas();
+        let ttl = Duration::from_secs(30);
+        assert!(a.set_if_absent("lock", "a", ttl).await.unwrap());
+        assert!(!b.set_if_absent("lock", "b", ttl).await.unwrap());
+        assert!(!b.delete_if_equals("lock", "b").await.unwrap());
+        assert!(!b.extend_if_equals("lock", "b", ttl).await.unwrap());
+        assert!(a.extend_if_equals("lock", "a", ttl).await.unwrap());
+        assert!(a.delete_if_equals("lock", "a").await.unwrap());
+        assert!(b.set_if_absent("lock", "b", ttl).await.unwrap());
+
+        // 过期的锁可被重新获取
+        assert!(a
+            .set_if_absent("short", "a", Duration::from_millis(10))
+            .await
+            .unwrap());
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(b.get("short").await.unwrap().is_none());
+        assert!(b.set_if_absent("short", "b", ttl).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_increments_are_atomic() {
+        let (_dir, a, b) = replicas();
+        let (a, b) = (Arc::new(a), Arc::new(b));
+        let mut tasks = Vec::new();
+        for backend in [a.clone(), b.clone()] {
+            tasks.push(tokio::spawn(async move {
+                for _ in 0..25 {
+                    backend.increment("counter", 1).await.unwrap();
+                }
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(a.get("counter").await.unwrap().as_deref(), Some("50"));
+    }
+
+    #[tokio::test]
+    async fn test_scan_prefix_and_delete() {
+        let (_dir, a, b) = replicas();
+        a.set("p:cooldown:1", "x", Some(Duration::from_secs(60)))
+            .await
+            .unwrap();
+        a.set("p:cooldown:2", "y", None).await.unwrap();
+        a.set("p:token:1", "z", None).await.unwrap();
+        a.set("p:cooldown:3", "gone", Some(Duration::from_millis(1)))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let found = b.scan_prefix("p:cooldown:").await.unwrap();
+        assert_eq!(
+            found,
+            vec![
+                ("p:cooldown:1".to_string(), "x".to_string()),
+                ("p:cooldown:2".to_string(), "y".to_string()),
+            ]
+        );
+        assert!(b.delete("p:cooldown:1").await.unwrap());
+        assert!(!b.delete("p:cooldown:1").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_shared_state_over_sqlite() {
+        let (_dir, a, b) = replicas();
+        let config = SharedStateConfig {
+            lock_wait_secs: 0,
+            ..SharedStateConfig::default()
+        };
+        let a = SharedState::new(Arc::new(a), &config);
+        let b = SharedState::new(Arc::new(b), &config);
+
+        let guard = a.lock("refresh:kiro").await.unwrap();
+        assert!(b.try_lock("refresh:kiro").await.unwrap().is_none());
+        guard.release().await.unwrap();
+        assert!(b.try_lock("refresh:kiro").await.unwrap().is_some());
+    }
+}
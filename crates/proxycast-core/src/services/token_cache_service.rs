@@ -14,7 +14,9 @@ use crate::models::provider_pool_model::{
 use crate::providers::gemini::GeminiProvider;
 use crate::providers::kiro::KiroProvider;
 use crate::providers::qwen::QwenProvider;
+use crate::services::event_bus::{EventBus, SystemEvent};
 use crate::services::kiro_event_service::KiroEventService;
+use crate::services::shared_state::SharedState;
 use chrono::Utc;
 use dashmap::DashMap;
 use std::sync::Arc;
@@ -56,6 +58,10 @@ pub struct RefreshErrorClassification {
 pub struct TokenCacheService {
     /// 每凭证一把锁，防止并发刷新
     locks: DashMap<String, Arc<Mutex<()>>>,
+    /// 事件总线（用于发布刷新永久失败事件）
+    event_bus: std::sync::RwLock<Option<Arc<EventBus>>>,
+    /// 多实例共享状态（跨副本的刷新锁与刷新结果）
+    shared_state: std::sync::RwLock<Option<Arc<SharedState>>>,
 }
 
 impl Default for TokenCacheService {
@@ -68,9 +74,68 @@ impl TokenCacheService {
     pub fn new() -> Self {
         Self {
             locks: DashMap::new(),
+            event_bus: std::sync::RwLock::new(None),
+            shared_state: std::sync::RwLock::new(None),
         }
     }
 
+    /// 设置事件总线（传入 None 时停止发布）
+    pub fn set_event_bus(&self, event_bus: Option<Arc<EventBus>>) {
+        if let Ok(mut guard) = self.event_bus.write() {
+            *guard = event_bus;
+        }
+    }
+
+    /// 设置多实例共享状态（传入 None 时只在进程内加锁）
+    pub fn set_shared_state(&self, shared: Option<Arc<SharedState>>) {
+        if let Ok(mut guard) = self.shared_state.write() {
+            *guard = shared;
+        }
+    }
+
+    fn shared_state(&self) -> Option<Arc<SharedState>> {
+        self.shared_state
+            .read()
+            .ok()
+            .and_then(|guard| guard.clone())
+    }
+
+    /// 采用其他副本刷新后的 Token
+    ///
+    /// `refreshed_after` 为 Some 时，只要 Token 是在此之后刷新的就采用（用于强制刷新期间
+    /// 其他副本已完成刷新的情况）；否则要求 Token 有效且未即将过期。
+    async fn adopt_shared_token(
+        &self,
+        shared: &SharedState,
+        db: &DbConnection,
+        uuid: &str,
+        refreshed_after: Option<chrono::DateTime<Utc>>,
+    ) -> Option<String> {
+        let info = match shared.cached_token(uuid).await {
+            Ok(info) => info?,
+            Err(e) => {
+                tracing::warn!("[TOKEN_CACHE] 读取共享 Token 失败 {}: {}", &uuid[..8], e);
+                return None;
+            }
+        };
+        let usable = match refreshed_after {
+            Some(after) => info.is_valid() && info.last_refresh.is_some_and(|at| at >= after),
+            None => info.is_valid() && !info.is_expiring_soon(),
+        };
+        if !usable {
+            return None;
+        }
+        let token = info.access_token.clone()?;
+        if let Ok(conn) = db.lock() {
+            let _ = ProviderPoolDao::update_token_cache(&conn, uuid, &info);
+        }
+        tracing::info!(
+            "[TOKEN_CACHE] Using token refreshed by another replica for {}",
+            &uuid[..8]
+        );
+        Some(token)
+    }
+
     /// 获取有效的 Token（核心方法）
     ///
     /// 1. 检查数据库缓存是否有效
@@ -247,8 +312,37 @@ impl TokenCacheService {
             .or_insert_with(|| Arc::new(Mutex::new(())))
             .clone();
 
+        let requested_at = Utc::now();
         let _guard = lock.lock().await;
 
+        // 跨副本加锁：同一凭证同一时刻只有一个副本刷新
+        let shared = self.shared_state();
+        let _shared_guard = match &shared {
+            Some(shared) => match shared.lock(&format!("token-refresh:{}", uuid)).await {
+                Ok(guard) => Some(guard),
+                Err(e) => {
+                    tracing::warn!(
+                        "[TOKEN_CACHE] 获取分布式刷新锁失败，仅使用进程内锁 {}: {}",
+                        &uuid[..8],
+                        e
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+
+        // 其他副本可能已完成刷新（强制刷新时只采用等待期间刷新的结果）
+        if let Some(shared) = &shared {
+            let refreshed_after = force.then_some(requested_at);
+            if let Some(token) = self
+                .adopt_shared_token(shared, db, uuid, refreshed_after)
+                .await
+            {
+                return Ok(token);
+            }
+        }
+
         // 双重检查：可能其他线程已完成刷新
         if !force {
             let cached = {
@@ -302,6 +396,13 @@ impl TokenCacheService {
                         .map_err(|e| e.to_string())?;
                 }
 
+                // 共享给其他副本
+                if let Some(shared) = &shared {
+                    if let Err(e) = shared.put_token(uuid, &token_info).await {
+                        tracing::warn!("[TOKEN_CACHE] 写入共享 Token 失败 {}: {}", &uuid[..8], e);
+                    }
+                }
+
                 let token = token_info
                     .access_token
                     .ok_or_else(|| "Refresh succeeded but no access_token".to_string())?;
@@ -365,6 +466,16 @@ impl TokenCacheService {
                                 error_classification.error_type
                             );
 
+                            let event_bus = self.event_bus.read().ok().and_then(|g| g.clone());
+                            if let Some(event_bus) = event_bus {
+                                event_bus.publish(SystemEvent::TokenRefreshFailed {
+                                    uuid: uuid.to_string(),
+                                    provider_type: credential.provider_type.to_string(),
+                                    error_type: format!("{:?}", error_classification.error_type),
+                                    message: error_classification.error_description.clone(),
+                                });
+                            }
+
                             // 发送凭证禁用事件
                             if let Some(event_service) = &kiro_event_service {
                                 if credential.provider_type == PoolProviderType::Kiro {
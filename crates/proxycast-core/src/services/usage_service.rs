@@ -32,6 +32,9 @@ pub const HTTP_TIMEOUT_SECS: u64 = 10;
 pub struct UsageLimitsResponse {
     pub subscription_info: SubscriptionInfo,
     pub usage_breakdown_list: Vec<UsageBreakdown>,
+    /// 下次重置时间（Unix 秒）
+    #[serde(default)]
+    pub next_date_reset: Option<f64>,
 }
 
 /// 订阅信息结构
@@ -93,6 +96,9 @@ pub struct UsageInfo {
     pub balance: f64,
     /// 余额低于 20%
     pub is_low_balance: bool,
+    /// 下次重置时间（Unix 秒）
+    #[serde(default)]
+    pub next_reset: Option<i64>,
 }
 
 impl UsageInfo {
@@ -378,6 +384,7 @@ pub fn calculate_balance_with_threshold(
         current_usage: total_current_usage,
         balance,
         is_low_balance,
+        next_reset: response.next_date_reset.map(|ts| ts as i64),
     }
 }
 
@@ -469,6 +476,7 @@ mod tests {
                 |(subscription_info, usage_breakdown_list)| UsageLimitsResponse {
                     subscription_info,
                     usage_breakdown_list,
+                    next_date_reset: None,
                 },
             )
     }
@@ -563,6 +571,7 @@ mod tests {
                     free_trial_info: None,
                     bonuses: None,
                 }],
+                next_date_reset: None,
             };
 
             let result = calculate_balance(&response);
@@ -597,6 +606,7 @@ mod tests {
                     free_trial_info: None,
                     bonuses: None,
                 }],
+                next_date_reset: None,
             };
 
             let result = calculate_balance(&response);
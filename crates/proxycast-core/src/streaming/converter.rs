@@ -57,8 +57,12 @@ struct ToolCallAccumulator {
     /// 累积的输入 JSON（部分）
     input: String,
     /// 是否已发送开始事件
+    ///
+    /// 名称到达前收到的输入增量会先缓存在 `input` 中，开始事件发出时一并补发
     started: bool,
-    /// 内容块索引（用于 Anthropic 格式）
+    /// 是否已结束
+    stopped: bool,
+    /// 内容块索引（Anthropic 格式）或 tool_calls 索引（OpenAI 格式）
     index: u32,
 }
 
@@ -158,6 +162,10 @@ pub struct StreamConverter {
     tool_accumulators: HashMap<String, ToolCallAccumulator>,
     /// 下一个内容块索引（用于 Anthropic 格式）
     next_content_block_index: u32,
+    /// 当前打开的文本内容块索引（用于 Anthropic 格式）
+    text_block_index: Option<u32>,
+    /// 下一个 tool_calls 索引（用于 OpenAI 格式）
+    next_tool_call_index: u32,
     /// 是否已发送 message_start（用于 Anthropic 格式）
     message_started: bool,
     /// 累积的内容（用于重建完整响应）
@@ -182,6 +190,8 @@ impl StreamConverter {
             model: String::new(),
             tool_accumulators: HashMap::new(),
             next_content_block_index: 0,
+            text_block_index: None,
+            next_tool_call_index: 0,
             message_started: false,
             accumulated_content: String::new(),
         }
@@ -218,6 +228,8 @@ impl StreamConverter {
         self.response_id = format!("chatcmpl-{}", Uuid::new_v4());
         self.tool_accumulators.clear();
         self.next_content_block_index = 0;
+        self.text_block_index = None;
+        self.next_tool_call_index = 0;
         self.message_started = false;
         self.accumulated_content.clear();
     }
@@ -294,9 +306,53 @@ impl StreamConverter {
         }
     }
 
+    /// 是否已开始过工具调用
+    fn has_tool_calls(&self) -> bool {
+        self.tool_accumulators.values().any(|acc| acc.started)
+    }
+
+    /// 获取或创建工具调用累积器
+    fn tool_accumulator(&mut self, id: &str) -> &mut ToolCallAccumulator {
+        self.tool_accumulators
+            .entry(id.to_string())
+            .or_insert_with(|| ToolCallAccumulator {
+                id: id.to_string(),
+                ..Default::default()
+            })
+    }
+
+    /// 关闭当前打开的 Anthropic 文本内容块
+    fn close_anthropic_text_block(&mut self, sse_events: &mut Vec<String>) {
+        if let Some(index) = self.text_block_index.take() {
+            sse_events.push(self.create_anthropic_content_block_stop(index));
+        }
+    }
+
+    /// 关闭所有打开的 Anthropic 内容块（文本和工具调用）
+    fn close_anthropic_open_blocks(&mut self, sse_events: &mut Vec<String>) {
+        self.close_anthropic_text_block(sse_events);
+
+        let mut open: Vec<u32> = self
+            .tool_accumulators
+            .values_mut()
+            .filter(|acc| acc.started && !acc.stopped)
+            .map(|acc| {
+                acc.stopped = true;
+                acc.index
+            })
+            .collect();
+        open.sort_unstable();
+        for index in open {
+            sse_events.push(self.create_anthropic_content_block_stop(index));
+        }
+    }
+
     /// AWS Event Stream 到 Anthropic SSE 转换
     ///
     /// 对应需求 3.1
+    ///
+    /// 每个工具调用占用独立的内容块索引，并行工具调用的 `input_json_delta`
+    /// 按各自索引交错发出；文本块在工具调用开始前关闭，之后的文本使用新的内容块。
     fn aws_to_anthropic(&mut self, event: &AwsEvent) -> Vec<String> {
         let mut sse_events = Vec::new();
 
@@ -311,62 +367,70 @@ impl StreamConverter {
                 // 累积内容
                 self.accumulated_content.push_str(text);
 
-                // 如果是第一个内容块，发送 content_block_start
-                if self.next_content_block_index == 0 {
-                    sse_events.push(self.create_anthropic_content_block_start_text(0));
-                    self.next_content_block_index = 1;
-                }
+                // 没有打开的文本块时，发送 content_block_start
+                let index = match self.text_block_index {
+                    Some(index) => index,
+                    None => {
+                        let index = self.next_content_block_index;
+                        self.next_content_block_index += 1;
+                        self.text_block_index = Some(index);
+                        sse_events.push(self.create_anthropic_content_block_start_text(index));
+                        index
+                    }
+                };
 
                 // 发送 content_block_delta
-                sse_events.push(self.create_anthropic_text_delta(0, text));
+                sse_events.push(self.create_anthropic_text_delta(index, text));
             }
             AwsEvent::ToolUseStart { id, name } => {
-                // 如果有文本内容块，先关闭它
-                if self.next_content_block_index > 0 && self.accumulated_content.is_empty() {
-                    // 没有文本内容，不需要关闭
-                } else if self.next_content_block_index > 0 {
-                    sse_events.push(self.create_anthropic_content_block_stop(0));
+                if self.tool_accumulator(id).started {
+                    return sse_events;
                 }
 
+                // 如果有文本内容块，先关闭它
+                self.close_anthropic_text_block(&mut sse_events);
+
                 let index = self.next_content_block_index;
                 self.next_content_block_index += 1;
 
-                // 创建工具调用累积器
-                self.tool_accumulators.insert(
-                    id.clone(),
-                    ToolCallAccumulator {
-                        id: id.clone(),
-                        name: name.clone(),
-                        input: String::new(),
-                        started: true,
-                        index,
-                    },
-                );
+                let acc = self.tool_accumulator(id);
+                acc.name = name.clone();
+                acc.started = true;
+                acc.index = index;
+                let pending_input = acc.input.clone();
 
-                // 发送 content_block_start (tool_use)
+                // 发送 content_block_start (tool_use)，并补发名称到达前缓存的输入
                 sse_events.push(self.create_anthropic_content_block_start_tool(index, id, name));
+                if !pending_input.is_empty() {
+                    sse_events.push(self.create_anthropic_input_json_delta(index, &pending_input));
+                }
             }
             AwsEvent::ToolUseInput { id, input } => {
-                if let Some(acc) = self.tool_accumulators.get_mut(id) {
-                    acc.input.push_str(input);
+                let acc = self.tool_accumulator(id);
+                if acc.stopped {
+                    return sse_events;
                 }
-                // 发送 input_json_delta
-                if let Some(acc) = self.tool_accumulators.get(id) {
-                    sse_events.push(self.create_anthropic_input_json_delta(acc.index, input));
+                acc.input.push_str(input);
+                if acc.started {
+                    let index = acc.index;
+                    // 发送 input_json_delta
+                    sse_events.push(self.create_anthropic_input_json_delta(index, input));
                 }
             }
             AwsEvent::ToolUseStop { id } => {
-                if let Some(acc) = self.tool_accumulators.remove(id) {
-                    // 发送 content_block_stop
-                    sse_events.push(self.create_anthropic_content_block_stop(acc.index));
+                if let Some(acc) = self.tool_accumulators.get_mut(id) {
+                    if acc.started && !acc.stopped {
+                        acc.stopped = true;
+                        let index = acc.index;
+                        // 发送 content_block_stop
+                        sse_events.push(self.create_anthropic_content_block_stop(index));
+                    }
                 }
             }
             AwsEvent::Stop => {
                 // 关闭所有未关闭的内容块
-                if self.next_content_block_index > 0 && !self.accumulated_content.is_empty() {
-                    sse_events.push(self.create_anthropic_content_block_stop(0));
-                }
                 // message_delta 和 message_stop 在 finish() 中处理
+                self.close_anthropic_open_blocks(&mut sse_events);
             }
             AwsEvent::Usage {
                 credits,
@@ -387,6 +451,9 @@ impl StreamConverter {
     /// AWS Event Stream 到 OpenAI SSE 转换
     ///
     /// 对应需求 3.2
+    ///
+    /// 工具调用按开始顺序分配 `tool_calls[i].index`，索引在整个响应内唯一，
+    /// 参数增量通过 `function.arguments` 按索引交错发出。
     fn aws_to_openai(&mut self, event: &AwsEvent) -> Vec<String> {
         let mut sse_events = Vec::new();
 
@@ -398,28 +465,37 @@ impl StreamConverter {
                 sse_events.push(self.create_openai_content_chunk(text, false));
             }
             AwsEvent::ToolUseStart { id, name } => {
-                let index = self.tool_accumulators.len() as u32;
-                self.tool_accumulators.insert(
-                    id.clone(),
-                    ToolCallAccumulator {
-                        id: id.clone(),
-                        name: name.clone(),
-                        input: String::new(),
-                        started: true,
-                        index,
-                    },
-                );
-                // 发送工具调用开始 chunk
-                sse_events.push(self.create_openai_tool_call_chunk(index, id, name, "", true));
+                if self.tool_accumulator(id).started {
+                    return sse_events;
+                }
+                let index = self.next_tool_call_index;
+                self.next_tool_call_index += 1;
+
+                let acc = self.tool_accumulator(id);
+                acc.name = name.clone();
+                acc.started = true;
+                acc.index = index;
+                let pending_input = acc.input.clone();
+
+                // 发送工具调用开始 chunk（携带名称到达前缓存的参数）
+                sse_events.push(self.create_openai_tool_call_chunk(
+                    index,
+                    id,
+                    name,
+                    &pending_input,
+                    true,
+                ));
             }
             AwsEvent::ToolUseInput { id, input } => {
-                let (index, tool_id, tool_name) =
-                    if let Some(acc) = self.tool_accumulators.get_mut(id) {
-                        acc.input.push_str(input);
-                        (acc.index, acc.id.clone(), acc.name.clone())
-                    } else {
-                        return sse_events;
-                    };
+                let acc = self.tool_accumulator(id);
+                if acc.stopped {
+                    return sse_events;
+                }
+                acc.input.push_str(input);
+                if !acc.started {
+                    return sse_events;
+                }
+                let (index, tool_id, tool_name) = (acc.index, acc.id.clone(), acc.name.clone());
                 // 发送工具调用参数增量
                 sse_events.push(
                     self.create_openai_tool_call_chunk(index, &tool_id, &tool_name, input, false),
@@ -427,7 +503,9 @@ impl StreamConverter {
             }
             AwsEvent::ToolUseStop { id } => {
                 // OpenAI 格式不需要显式的工具调用结束事件
-                self.tool_accumulators.remove(id);
+                if let Some(acc) = self.tool_accumulators.get_mut(id) {
+                    acc.stopped = true;
+                }
             }
             AwsEvent::Stop => {
                 // 结束事件在 finish() 中处理
@@ -543,6 +621,7 @@ impl StreamConverter {
                                                 name: name.to_string(),
                                                 input: String::new(),
                                                 started: true,
+                                                stopped: false,
                                                 index,
                                             },
                                         );
@@ -579,6 +658,10 @@ impl StreamConverter {
         match self.target_format {
             StreamFormat::AnthropicSse => {
                 let mut events = Vec::new();
+                // 关闭仍然打开的内容块
+                if self.message_started {
+                    self.close_anthropic_open_blocks(&mut events);
+                }
                 // message_delta
                 events.push(self.create_anthropic_message_delta());
                 // message_stop
@@ -586,10 +669,10 @@ impl StreamConverter {
                 events
             }
             StreamFormat::OpenAiSse => {
-                let finish_reason = if self.tool_accumulators.is_empty() {
-                    "stop"
-                } else {
+                let finish_reason = if self.has_tool_calls() {
                     "tool_calls"
+                } else {
+                    "stop"
                 };
                 vec![
                     self.create_openai_finish_chunk(finish_reason),
@@ -690,10 +773,15 @@ impl StreamConverter {
     }
 
     fn create_anthropic_message_delta(&self) -> String {
+        let stop_reason = if self.has_tool_calls() {
+            "tool_use"
+        } else {
+            "end_turn"
+        };
         let event = serde_json::json!({
             "type": "message_delta",
             "delta": {
-                "stop_reason": "end_turn",
+                "stop_reason": stop_reason,
                 "stop_sequence": null
             },
             "usage": {
@@ -857,75 +945,87 @@ pub fn extract_content_from_sse(events: &[String], format: StreamFormat) -> Stri
 }
 
 /// 从 SSE 事件列表中提取所有工具调用
+///
+/// 按 OpenAI `tool_calls[i].index` 或 Anthropic 内容块索引重组增量，
+/// 返回按索引排序的 (id, name, arguments) 列表
 pub fn extract_tool_calls_from_sse(
     events: &[String],
     format: StreamFormat,
 ) -> Vec<(String, String, String)> {
-    let mut tool_calls: HashMap<String, (String, String)> = HashMap::new();
+    let mut tool_calls: std::collections::BTreeMap<u64, (String, String, String)> =
+        std::collections::BTreeMap::new();
 
     for event in events {
-        match format {
-            StreamFormat::OpenAiSse => {
-                for line in event.lines() {
-                    if let Some(json_str) = line.strip_prefix("data: ") {
-                        if json_str == "[DONE]" {
-                            continue;
+        for line in event.lines() {
+            let Some(json_str) = line.strip_prefix("data: ") else {
+                continue;
+            };
+            if json_str == "[DONE]" {
+                continue;
+            }
+            let Ok(chunk) = serde_json::from_str::<serde_json::Value>(json_str) else {
+                continue;
+            };
+            match format {
+                StreamFormat::OpenAiSse => {
+                    let Some(choices) = chunk.get("choices").and_then(|c| c.as_array()) else {
+                        continue;
+                    };
+                    for tc in choices
+                        .iter()
+                        .filter_map(|choice| choice.get("delta"))
+                        .filter_map(|delta| delta.get("tool_calls").and_then(|t| t.as_array()))
+                        .flatten()
+                    {
+                        let index = tc.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+                        let entry = tool_calls.entry(index).or_default();
+                        if let Some(id) = tc.get("id").and_then(|i| i.as_str()) {
+                            entry.0 = id.to_string();
                         }
-                        if let Ok(chunk) = serde_json::from_str::<serde_json::Value>(json_str) {
-                            if let Some(choices) = chunk.get("choices").and_then(|c| c.as_array()) {
-                                for choice in choices {
-                                    if let Some(delta) = choice.get("delta") {
-                                        if let Some(tcs) =
-                                            delta.get("tool_calls").and_then(|t| t.as_array())
-                                        {
-                                            for tc in tcs {
-                                                let id = tc
-                                                    .get("id")
-                                                    .and_then(|i| i.as_str())
-                                                    .unwrap_or("");
-                                                let name = tc
-                                                    .get("function")
-                                                    .and_then(|f| f.get("name"))
-                                                    .and_then(|n| n.as_str())
-                                                    .unwrap_or("");
-                                                let args = tc
-                                                    .get("function")
-                                                    .and_then(|f| f.get("arguments"))
-                                                    .and_then(|a| a.as_str())
-                                                    .unwrap_or("");
-
-                                                if !id.is_empty() {
-                                                    tool_calls
-                                                        .entry(id.to_string())
-                                                        .or_insert((String::new(), String::new()))
-                                                        .0 = name.to_string();
-                                                }
-                                                if !args.is_empty() {
-                                                    if let Some(entry) =
-                                                        tool_calls.values_mut().last()
-                                                    {
-                                                        entry.1.push_str(args);
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
+                        let function = tc.get("function");
+                        if let Some(name) = function
+                            .and_then(|f| f.get("name"))
+                            .and_then(|n| n.as_str())
+                        {
+                            entry.1 = name.to_string();
+                        }
+                        if let Some(args) = function
+                            .and_then(|f| f.get("arguments"))
+                            .and_then(|a| a.as_str())
+                        {
+                            entry.2.push_str(args);
+                        }
+                    }
+                }
+                StreamFormat::AnthropicSse => {
+                    let index = chunk.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+                    match chunk.get("type").and_then(|t| t.as_str()) {
+                        Some("content_block_start") => {
+                            let block = &chunk["content_block"];
+                            if block.get("type").and_then(|t| t.as_str()) == Some("tool_use") {
+                                let entry = tool_calls.entry(index).or_default();
+                                entry.0 = block["id"].as_str().unwrap_or_default().to_string();
+                                entry.1 = block["name"].as_str().unwrap_or_default().to_string();
+                            }
+                        }
+                        Some("content_block_delta") => {
+                            if let Some(partial) =
+                                chunk["delta"].get("partial_json").and_then(|p| p.as_str())
+                            {
+                                if let Some(entry) = tool_calls.get_mut(&index) {
+                                    entry.2.push_str(partial);
                                 }
                             }
                         }
+                        _ => {}
                     }
                 }
-            }
-            StreamFormat::AnthropicSse | StreamFormat::AwsEventStream => {
-                // 简化处理
+                StreamFormat::AwsEventStream => {}
             }
         }
     }
 
-    tool_calls
-        .into_iter()
-        .map(|(id, (name, args))| (id, name, args))
-        .collect()
+    tool_calls.into_values().collect()
 }
 
 // ============================================================================
@@ -1065,6 +1165,145 @@ mod tests {
         assert!(events3.iter().any(|e| e.contains("content_block_stop")));
     }
 
+    #[test]
+    fn test_aws_to_openai_parallel_tool_calls_keep_index() {
+        let mut converter = StreamConverter::with_model(
+            StreamFormat::AwsEventStream,
+            StreamFormat::OpenAiSse,
+            "test-model",
+        );
+
+        let mut events = Vec::new();
+        for frame in [
+            r#"{"toolUseId":"tool_a","name":"read_file"}"#,
+            r#"{"toolUseId":"tool_b","name":"list_dir"}"#,
+            r#"{"toolUseId":"tool_a","input":"{\"path\":"}"#,
+            r#"{"toolUseId":"tool_b","input":"{\"dir\":\"/\"}"}"#,
+            r#"{"toolUseId":"tool_b","stop":true}"#,
+            r#"{"toolUseId":"tool_a","input":"\"/tmp\"}"}"#,
+            r#"{"toolUseId":"tool_a","stop":true}"#,
+            r#"{"toolUseId":"tool_c","name":"grep"}"#,
+            r#"{"toolUseId":"tool_c","stop":true}"#,
+        ] {
+            events.extend(converter.convert(frame.as_bytes()));
+        }
+        events.extend(converter.finish());
+
+        let tool_calls = extract_tool_calls_from_sse(&events, StreamFormat::OpenAiSse);
+        assert_eq!(
+            tool_calls,
+            vec![
+                (
+                    "tool_a".to_string(),
+                    "read_file".to_string(),
+                    r#"{"path":"/tmp"}"#.to_string()
+                ),
+                (
+                    "tool_b".to_string(),
+                    "list_dir".to_string(),
+                    r#"{"dir":"/"}"#.to_string()
+                ),
+                ("tool_c".to_string(), "grep".to_string(), String::new()),
+            ]
+        );
+        assert!(events
+            .iter()
+            .any(|e| e.contains("\"finish_reason\":\"tool_calls\"")));
+    }
+
+    #[test]
+    fn test_aws_to_openai_input_before_name_is_flushed() {
+        let mut converter = StreamConverter::with_model(
+            StreamFormat::AwsEventStream,
+            StreamFormat::OpenAiSse,
+            "test-model",
+        );
+
+        // 名称到达前的输入不应丢失
+        let early = converter.convert(br#"{"toolUseId":"tool_1","input":"{\"a\":"}"#);
+        assert!(early.is_empty());
+
+        let mut events = converter.convert(br#"{"toolUseId":"tool_1","name":"calc"}"#);
+        events.extend(converter.convert(br#"{"toolUseId":"tool_1","input":"1}"}"#));
+        events.extend(converter.convert(br#"{"toolUseId":"tool_1","stop":true}"#));
+
+        let tool_calls = extract_tool_calls_from_sse(&events, StreamFormat::OpenAiSse);
+        assert_eq!(
+            tool_calls,
+            vec![(
+                "tool_1".to_string(),
+                "calc".to_string(),
+                r#"{"a":1}"#.to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_aws_to_anthropic_text_after_tool_uses_new_block() {
+        let mut converter = StreamConverter::with_model(
+            StreamFormat::AwsEventStream,
+            StreamFormat::AnthropicSse,
+            "test-model",
+        );
+
+        let mut events = converter.convert(br#"{"content":"Let me check."}"#);
+        events.extend(converter.convert(br#"{"toolUseId":"tool_1","name":"read_file"}"#));
+        events.extend(converter.convert(br#"{"toolUseId":"tool_1","input":"{}"}"#));
+        events.extend(converter.convert(br#"{"toolUseId":"tool_1","stop":true}"#));
+        events.extend(converter.convert(br#"{"content":"Done."}"#));
+        events.extend(converter.finish());
+
+        let blocks: Vec<(String, u64)> = events
+            .iter()
+            .filter_map(|e| e.lines().find_map(|l| l.strip_prefix("data: ")))
+            .filter_map(|data| serde_json::from_str::<serde_json::Value>(data).ok())
+            .filter(|v| v["type"] == "content_block_start" || v["type"] == "content_block_stop")
+            .map(|v| {
+                (
+                    v["type"].as_str().unwrap().to_string(),
+                    v["index"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            blocks,
+            vec![
+                ("content_block_start".to_string(), 0),
+                ("content_block_stop".to_string(), 0),
+                ("content_block_start".to_string(), 1),
+                ("content_block_stop".to_string(), 1),
+                ("content_block_start".to_string(), 2),
+                ("content_block_stop".to_string(), 2),
+            ]
+        );
+        assert!(events
+            .iter()
+            .any(|e| e.contains("\"stop_reason\":\"tool_use\"")));
+        assert_eq!(
+            extract_content_from_sse(&events, StreamFormat::AnthropicSse),
+            "Let me check.Done."
+        );
+    }
+
+    #[test]
+    fn test_aws_to_anthropic_finish_closes_open_tool_blocks() {
+        let mut converter = StreamConverter::with_model(
+            StreamFormat::AwsEventStream,
+            StreamFormat::AnthropicSse,
+            "test-model",
+        );
+
+        let mut events = converter.convert(br#"{"toolUseId":"tool_1","name":"a"}"#);
+        events.extend(converter.convert(br#"{"toolUseId":"tool_2","name":"b"}"#));
+        events.extend(converter.finish());
+
+        let stops = events
+            .iter()
+            .filter(|e| e.starts_with("event: content_block_stop"))
+            .count();
+        assert_eq!(stops, 2);
+    }
+
     #[test]
     fn test_converter_finish() {
         let mut converter = StreamConverter::with_model(
@@ -1191,6 +1430,7 @@ mod tests {
 #[cfg(test)]
 mod property_tests {
     use super::*;
+    use crate::server_utils::parse_cw_response;
     use crate::streaming::aws_parser::{
         extract_content, extract_tool_calls, serialize_event, AwsEvent,
     };
@@ -1232,6 +1472,114 @@ mod property_tests {
         prop::collection::vec(arb_content_event(), 1..10)
     }
 
+    /// 生成单个工具调用的事件队列：开始、分片输入、结束
+    ///
+    /// 部分工具调用的首个输入分片会先于名称到达
+    fn arb_tool_call_events() -> impl Strategy<Value = Vec<AwsEvent>> {
+        (arb_tool_name(), arb_tool_input(), 1usize..4, any::<bool>()).prop_map(
+            |(name, input, parts, input_first)| {
+                let chars: Vec<char> = input.chars().collect();
+                let chunk_size = chars.len().div_ceil(parts).max(1);
+                let mut events = vec![AwsEvent::ToolUseStart {
+                    id: String::new(),
+                    name,
+                }];
+                events.extend(
+                    chars
+                        .chunks(chunk_size)
+                        .map(|chunk| AwsEvent::ToolUseInput {
+                            id: String::new(),
+                            input: chunk.iter().collect(),
+                        }),
+                );
+                if input_first {
+                    events.swap(0, 1);
+                }
+                events.push(AwsEvent::ToolUseStop { id: String::new() });
+                events
+            },
+        )
+    }
+
+    /// 生成交错的并行工具调用事件序列（穿插文本内容）
+    fn arb_parallel_tool_sequence() -> impl Strategy<Value = Vec<AwsEvent>> {
+        (
+            prop::collection::vec(arb_tool_call_events(), 1..5),
+            prop::collection::vec(any::<u8>(), 0..64),
+            prop::option::of(arb_content_text()),
+        )
+            .prop_map(|(tools, picks, leading_text)| {
+                let mut queues: Vec<std::collections::VecDeque<AwsEvent>> = tools
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, events)| {
+                        let id = format!("tool_{:04}", i);
+                        events
+                            .into_iter()
+                            .map(|event| match event {
+                                AwsEvent::ToolUseStart { name, .. } => AwsEvent::ToolUseStart {
+                                    id: id.clone(),
+                                    name,
+                                },
+                                AwsEvent::ToolUseInput { input, .. } => AwsEvent::ToolUseInput {
+                                    id: id.clone(),
+                                    input,
+                                },
+                                _ => AwsEvent::ToolUseStop { id: id.clone() },
+                            })
+                            .collect()
+                    })
+                    .collect();
+
+                let mut sequence: Vec<AwsEvent> = leading_text
+                    .map(|text| AwsEvent::Content { text })
+                    .into_iter()
+                    .collect();
+                let mut picks = picks.into_iter();
+                while queues.iter().any(|q| !q.is_empty()) {
+                    let pending: Vec<usize> = (0..queues.len())
+                        .filter(|&i| !queues[i].is_empty())
+                        .collect();
+                    let pick = picks.next().unwrap_or(0) as usize % pending.len();
+                    sequence.extend(queues[pending[pick]].pop_front());
+                }
+                sequence
+            })
+    }
+
+    /// 将 AWS 事件序列序列化为帧
+    fn to_frames(events: &[AwsEvent]) -> Vec<String> {
+        events.iter().filter_map(serialize_event).collect()
+    }
+
+    /// 非流式解析得到的工具调用（按 ID 排序）
+    fn non_streaming_tool_calls(frames: &[String]) -> Vec<(String, String, String)> {
+        let mut calls: Vec<_> = parse_cw_response(&frames.concat())
+            .tool_calls
+            .into_iter()
+            .map(|tc| (tc.id, tc.function.name, tc.function.arguments))
+            .collect();
+        calls.sort();
+        calls
+    }
+
+    /// 流式转换后重组得到的工具调用（按 ID 排序）
+    fn streaming_tool_calls(
+        frames: &[String],
+        format: StreamFormat,
+    ) -> Vec<(String, String, String)> {
+        let mut converter =
+            StreamConverter::with_model(StreamFormat::AwsEventStream, format, "test-model");
+        let mut sse = Vec::new();
+        for frame in frames {
+            sse.extend(converter.convert(frame.as_bytes()));
+        }
+        sse.extend(converter.finish());
+        let mut calls = extract_tool_calls_from_sse(&sse, format);
+        calls.sort();
+        calls
+    }
+
     // ========================================================================
     // Property 2: 流式格式转换内容保留
     //
@@ -1515,4 +1863,37 @@ mod property_tests {
             prop_assert!(acc.is_complete());
         }
     }
+
+    // ========================================================================
+    // Property: 并行工具调用流式重组与非流式解析一致
+    //
+    // *对于任意*交错的并行工具调用帧序列，从 OpenAI SSE 或 Anthropic SSE
+    // 重组出的工具调用应与 `parse_cw_response` 的非流式解析结果一致。
+    // ========================================================================
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn prop_openai_parallel_tool_calls_match_non_streaming(
+            events in arb_parallel_tool_sequence()
+        ) {
+            let frames = to_frames(&events);
+            prop_assert_eq!(
+                streaming_tool_calls(&frames, StreamFormat::OpenAiSse),
+                non_streaming_tool_calls(&frames)
+            );
+        }
+
+        #[test]
+        fn prop_anthropic_parallel_tool_calls_match_non_streaming(
+            events in arb_parallel_tool_sequence()
+        ) {
+            let frames = to_frames(&events);
+            prop_assert_eq!(
+                streaming_tool_calls(&frames, StreamFormat::AnthropicSse),
+                non_streaming_tool_calls(&frames)
+            );
+        }
+    }
 }
@@ -59,6 +59,16 @@ pub enum StreamError {
     ///
     /// 其他内部错误。
     Internal(String),
+
+    /// 被插件拒绝
+    ///
+    /// 当插件的流式钩子拒绝继续输出时发生，流随即中止。
+    Rejected {
+        /// HTTP 状态码
+        status: u16,
+        /// 拒绝原因
+        message: String,
+    },
 }
 
 impl fmt::Display for StreamError {
@@ -73,6 +83,9 @@ impl fmt::Display for StreamError {
             StreamError::ClientDisconnected => write!(f, "客户端已断开连接"),
             StreamError::BufferOverflow => write!(f, "缓冲区溢出"),
             StreamError::Internal(msg) => write!(f, "内部错误: {}", msg),
+            StreamError::Rejected { status, message } => {
+                write!(f, "被插件拒绝 ({}): {}", status, message)
+            }
         }
     }
 }
@@ -161,6 +174,7 @@ impl StreamError {
             StreamError::ClientDisconnected => false,
             StreamError::BufferOverflow => false,
             StreamError::Internal(_) => false,
+            StreamError::Rejected { .. } => false,
         }
     }
 
@@ -173,6 +187,7 @@ impl StreamError {
     pub fn status_code(&self) -> Option<u16> {
         match self {
             StreamError::ProviderError { status, .. } => Some(*status),
+            StreamError::Rejected { status, .. } => Some(*status),
             StreamError::Timeout => Some(504), // Gateway Timeout
             StreamError::Network(_) => Some(502), // Bad Gateway
             _ => None,
@@ -200,6 +215,7 @@ impl StreamError {
             StreamError::ClientDisconnected => "client_disconnected",
             StreamError::BufferOverflow => "buffer_overflow",
             StreamError::Internal(_) => "internal_error",
+            StreamError::Rejected { .. } => "rejected",
         }
     }
 }
@@ -247,6 +263,14 @@ mod tests {
             Some(429)
         );
         assert_eq!(StreamError::ClientDisconnected.status_code(), None);
+        assert_eq!(
+            StreamError::Rejected {
+                status: 451,
+                message: "policy".to_string()
+            }
+            .status_code(),
+            Some(451)
+        );
     }
 
     #[test]
@@ -11,12 +11,14 @@
 //! - `converter`: 流式格式转换器
 //! - `traits`: StreamingProvider trait 定义
 //! - `manager`: 流式管理器
+//! - `plugin_hooks`: 流式插件钩子
 
 pub mod aws_parser;
 pub mod converter;
 pub mod error;
 pub mod manager;
 pub mod metrics;
+pub mod plugin_hooks;
 pub mod traits;
 
 // 重新导出核心类型
@@ -35,6 +37,7 @@ pub use manager::{
     StreamManager, TimeoutStream,
 };
 pub use metrics::StreamMetrics;
+pub use plugin_hooks::{sse_event_blocks, with_plugin_hooks, StreamPluginHooks};
 pub use traits::{
     reqwest_stream_to_stream_response, StreamFormat as TraitsStreamFormat, StreamResponse,
     StreamingProvider,
@@ -0,0 +1,657 @@
+//! 流式插件钩子
+//!
+//! 将目标格式（Anthropic / OpenAI SSE）的流式事件标准化为 [`StreamHookEvent`]，
+//! 交给插件观察或修改后写回原事件；流结束时用 [`StreamRebuilder`] 组装完整响应，
+//! 调用插件的 `on_stream_end` 钩子。插件拒绝时中止流并输出错误事件。
+//!
+//! 钩子作用于 SSE 文本，SSE 和 WebSocket 流式输出都应在转换为传输格式前挂载。
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::stream::{self, BoxStream};
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+
+use crate::flow_monitor::{StreamFormat as RebuildFormat, StreamRebuilder};
+use crate::plugin::{HookResult, PluginContext, PluginManager, StreamHookEvent};
+use crate::streaming::converter::StreamFormat;
+use crate::streaming::error::StreamError;
+
+// ============================================================================
+// 事件标准化
+// ============================================================================
+
+/// 标准化事件及其字段在原始 JSON 中的位置
+#[derive(Debug)]
+struct Binding {
+    event: StreamHookEvent,
+    /// (事件字段名, JSON Pointer)
+    fields: Vec<(&'static str, String)>,
+}
+
+impl Binding {
+    /// 将（可能被插件修改的）事件字段写回原始 JSON
+    fn write_back(&self, data: &mut Value) {
+        let Ok(Value::Object(event)) = serde_json::to_value(&self.event) else {
+            return;
+        };
+        for (field, pointer) in &self.fields {
+            if let Some(slot) = data.pointer_mut(pointer) {
+                *slot = event.get(*field).cloned().unwrap_or(Value::Null);
+            }
+        }
+    }
+}
+
+fn string_at(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+/// 提取 Token 用量，`keys` 为 (输入, 输出) 字段名
+fn usage_binding(usage: &Value, base: &str, keys: (&str, &str)) -> Option<Binding> {
+    let usage = usage.as_object()?;
+    let mut fields = Vec::new();
+    if usage.contains_key(keys.0) {
+        fields.push(("input_tokens", format!("{}/{}", base, keys.0)));
+    }
+    if usage.contains_key(keys.1) {
+        fields.push(("output_tokens", format!("{}/{}", base, keys.1)));
+    }
+    Some(Binding {
+        event: StreamHookEvent::Usage {
+            input_tokens: usage.get(keys.0).and_then(Value::as_u64),
+            output_tokens: usage.get(keys.1).and_then(Value::as_u64),
+        },
+        fields,
+    })
+}
+
+fn extract_anthropic(data: &Value) -> Vec<Binding> {
+    let index = data.get("index").and_then(Value::as_u64).unwrap_or(0) as u32;
+    let mut bindings = Vec::new();
+    match data.get("type").and_then(Value::as_str) {
+        Some("content_block_start") => {
+            let block = &data["content_block"];
+            if block["type"] == "tool_use" {
+                bindings.push(Binding {
+                    event: StreamHookEvent::ToolCallDelta {
+                        index,
+                        id: string_at(block, "id"),
+                        name: string_at(block, "name"),
+                        arguments: String::new(),
+                    },
+                    fields: vec![
+                        ("id", "/content_block/id".to_string()),
+                        ("name", "/content_block/name".to_string()),
+                    ],
+                });
+            }
+        }
+        Some("content_block_delta") => {
+            let delta = &data["delta"];
+            let (event, field, pointer) = match delta["type"].as_str() {
+                Some("text_delta") => (
+                    StreamHookEvent::TextDelta {
+                        index,
+                        text: string_at(delta, "text").unwrap_or_default(),
+                    },
+                    "text",
+                    "/delta/text",
+                ),
+                Some("thinking_delta") => (
+                    StreamHookEvent::ThinkingDelta {
+                        index,
+                        thinking: string_at(delta, "thinking").unwrap_or_default(),
+                    },
+                    "thinking",
+                    "/delta/thinking",
+                ),
+                Some("input_json_delta") => (
+                    StreamHookEvent::ToolCallDelta {
+                        index,
+                        id: None,
+                        name: None,
+                        arguments: string_at(delta, "partial_json").unwrap_or_default(),
+                    },
+                    "arguments",
+                    "/delta/partial_json",
+                ),
+                _ => return bindings,
+            };
+            bindings.push(Binding {
+                event,
+                fields: vec![(field, pointer.to_string())],
+            });
+        }
+        Some("message_start") => {
+            bindings.extend(usage_binding(
+                &data["message"]["usage"],
+                "/message/usage",
+                ("input_tokens", "output_tokens"),
+            ));
+        }
+        Some("message_delta") => {
+            bindings.extend(usage_binding(
+                &data["usage"],
+                "/usage",
+                ("input_tokens", "output_tokens"),
+            ));
+            if let Some(reason) = data["delta"]["stop_reason"].as_str() {
+                bindings.push(Binding {
+                    event: StreamHookEvent::Stop {
+                        reason: Some(reason.to_string()),
+                    },
+                    fields: vec![("reason", "/delta/stop_reason".to_string())],
+                });
+            }
+        }
+        _ => {}
+    }
+    bindings
+}
+
+fn extract_openai(data: &Value) -> Vec<Binding> {
+    let mut bindings = Vec::new();
+    for (i, choice) in data["choices"].as_array().into_iter().flatten().enumerate() {
+        let index = choice["index"].as_u64().unwrap_or(i as u64) as u32;
+        let base = format!("/choices/{}", i);
+        let delta = &choice["delta"];
+
+        if let Some(text) = delta["content"].as_str() {
+            bindings.push(Binding {
+                event: StreamHookEvent::TextDelta {
+                    index,
+                    text: text.to_string(),
+                },
+                fields: vec![("text", format!("{}/delta/content", base))],
+            });
+        }
+        if let Some(thinking) = delta["reasoning_content"].as_str() {
+            bindings.push(Binding {
+                event: StreamHookEvent::ThinkingDelta {
+                    index,
+                    thinking: thinking.to_string(),
+                },
+                fields: vec![("thinking", format!("{}/delta/reasoning_content", base))],
+            });
+        }
+        for (j, call) in delta["tool_calls"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            let call_base = format!("{}/delta/tool_calls/{}", base, j);
+            let function = &call["function"];
+            let mut fields = Vec::new();
+            if call.get("id").is_some() {
+                fields.push(("id", format!("{}/id", call_base)));
+            }
+            if function.get("name").is_some() {
+                fields.push(("name", format!("{}/function/name", call_base)));
+            }
+            if function.get("arguments").is_some() {
+                fields.push(("arguments", format!("{}/function/arguments", call_base)));
+            }
+            bindings.push(Binding {
+                event: StreamHookEvent::ToolCallDelta {
+                    index: call["index"].as_u64().unwrap_or(j as u64) as u32,
+                    id: string_at(call, "id"),
+                    name: string_at(function, "name"),
+                    arguments: string_at(function, "arguments").unwrap_or_default(),
+                },
+                fields,
+            });
+        }
+        if let Some(reason) = choice["finish_reason"].as_str() {
+            bindings.push(Binding {
+                event: StreamHookEvent::Stop {
+                    reason: Some(reason.to_string()),
+                },
+                fields: vec![("reason", format!("{}/finish_reason", base))],
+            });
+        }
+    }
+    bindings.extend(usage_binding(
+        &data["usage"],
+        "/usage",
+        ("prompt_tokens", "completion_tokens"),
+    ));
+    bindings
+}
+
+/// 从单个 SSE 数据中提取标准化事件
+fn extract_events(format: StreamFormat, data: &Value) -> Vec<Binding> {
+    match format {
+        StreamFormat::AnthropicSse => extract_anthropic(data),
+        StreamFormat::OpenAiSse => extract_openai(data),
+        StreamFormat::AwsEventStream => Vec::new(),
+    }
+}
+
+// ============================================================================
+// SSE 解析
+// ============================================================================
+
+/// 解析单个 SSE 事件块，返回 (事件类型, 数据)
+pub(crate) fn parse_sse_block(block: &str) -> (Option<&str>, Option<String>) {
+    let mut event = None;
+    let mut data: Option<String> = None;
+    for line in block.lines() {
+        if let Some(value) = line.strip_prefix("event:") {
+            event = Some(value.trim());
+        } else if let Some(value) = line.strip_prefix("data:") {
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match &mut data {
+                Some(existing) => {
+                    existing.push('\n');
+                    existing.push_str(value);
+                }
+                None => data = Some(value.to_string()),
+            }
+        }
+    }
+    (event, data)
+}
+
+fn format_sse_block(event: Option<&str>, data: &str) -> String {
+    match event {
+        Some(event) => format!("event: {}\ndata: {}\n\n", event, data),
+        None => format!("data: {}\n\n", data),
+    }
+}
+
+fn rejection_error(results: &[HookResult]) -> Option<StreamError> {
+    results
+        .iter()
+        .find_map(|r| r.rejection.as_ref())
+        .map(|rejection| StreamError::Rejected {
+            status: rejection.status_code,
+            message: rejection.message.clone(),
+        })
+}
+
+// ============================================================================
+// 流式钩子
+// ============================================================================
+
+/// 单个流的插件钩子状态
+pub struct StreamPluginHooks {
+    plugins: Arc<PluginManager>,
+    ctx: PluginContext,
+    format: StreamFormat,
+    rebuilder: Option<StreamRebuilder>,
+}
+
+impl StreamPluginHooks {
+    /// 创建流式钩子，`format` 为插件看到的输出格式
+    pub fn new(plugins: Arc<PluginManager>, ctx: PluginContext, format: StreamFormat) -> Self {
+        let rebuild_format = match format {
+            StreamFormat::AnthropicSse => RebuildFormat::Anthropic,
+            StreamFormat::OpenAiSse => RebuildFormat::OpenAI,
+            StreamFormat::AwsEventStream => RebuildFormat::Unknown,
+        };
+        Self {
+            plugins,
+            ctx,
+            format,
+            rebuilder: Some(StreamRebuilder::new(rebuild_format)),
+        }
+    }
+
+    /// 插件上下文
+    pub fn context(&self) -> &PluginContext {
+        &self.ctx
+    }
+
+    /// 处理一个输出项（可能包含多个 SSE 事件），返回改写后的内容
+    ///
+    /// 插件拒绝时返回 [`StreamError::Rejected`]，调用方应中止流。
+    pub async fn process(&mut self, chunk: String) -> Result<String, StreamError> {
+        let mut output = String::new();
+        let mut rewritten = false;
+        for block in chunk.split_inclusive("\n\n") {
+            match self.process_block(block).await? {
+                Some(replacement) => {
+                    output.push_str(&replacement);
+                    rewritten = true;
+                }
+                None => output.push_str(block),
+            }
+        }
+        Ok(if rewritten { output } else { chunk })
+    }
+
+    async fn process_block(&mut self, block: &str) -> Result<Option<String>, StreamError> {
+        let (event, Some(data)) = parse_sse_block(block) else {
+            return Ok(None);
+        };
+        let mut json = match serde_json::from_str::<Value>(&data) {
+            Ok(json) => json,
+            Err(_) => {
+                // 非 JSON 数据（如 [DONE]）原样输出
+                self.feed(event, &data);
+                return Ok(None);
+            }
+        };
+
+        let mut modified = false;
+        for mut binding in extract_events(self.format, &json) {
+            let results = self
+                .plugins
+                .run_on_stream_event(&mut self.ctx, &mut binding.event)
+                .await;
+            if let Some(error) = rejection_error(&results) {
+                return Err(error);
+            }
+            if results.iter().any(|r| r.modified) {
+                binding.write_back(&mut json);
+                modified = true;
+            }
+        }
+
+        if !modified {
+            self.feed(event, &data);
+            return Ok(None);
+        }
+        let data = json.to_string();
+        self.feed(event, &data);
+        Ok(Some(format_sse_block(event, &data)))
+    }
+
+    fn feed(&mut self, event: Option<&str>, data: &str) {
+        if let Some(rebuilder) = &mut self.rebuilder {
+            if let Err(e) = rebuilder.process_event(event, data) {
+                tracing::debug!("[PLUGIN] 流重建失败: {}", e);
+            }
+        }
+    }
+
+    /// 流结束：以重建后的完整响应调用 `on_stream_end`
+    pub async fn finish(&mut self) -> Result<(), StreamError> {
+        let Some(rebuilder) = self.rebuilder.take() else {
+            return Ok(());
+        };
+        let response = rebuilder.finish();
+        let results = self
+            .plugins
+            .run_on_stream_end(&mut self.ctx, &response)
+            .await;
+        match rejection_error(&results) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// 上游流错误：调用 `on_error`
+    pub async fn on_error(&mut self, error: &StreamError) {
+        self.plugins
+            .run_on_error(&mut self.ctx, &error.to_string())
+            .await;
+    }
+}
+
+/// 为 SSE 事件流挂载插件钩子
+///
+/// 插件拒绝时输出 [`StreamError::Rejected`] 并结束流（上游流随之被丢弃）。
+pub fn with_plugin_hooks<S>(
+    stream: S,
+    hooks: StreamPluginHooks,
+) -> BoxStream<'static, Result<String, StreamError>>
+where
+    S: Stream<Item = Result<String, StreamError>> + Send + Unpin + 'static,
+{
+    stream::unfold(Some((stream, hooks)), |state| async move {
+        let (mut stream, mut hooks) = state?;
+        match stream.next().await {
+            Some(Ok(chunk)) => match hooks.process(chunk).await {
+                Ok(chunk) => Some((Ok(chunk), Some((stream, hooks)))),
+                Err(error) => Some((Err(error), None)),
+            },
+            Some(Err(error)) => {
+                hooks.on_error(&error).await;
+                Some((Err(error), Some((stream, hooks))))
+            }
+            None => match hooks.finish().await {
+                Ok(()) => None,
+                Err(error) => Some((Err(error), None)),
+            },
+        }
+    })
+    .boxed()
+}
+
+/// 将响应体字节流切分为完整的 SSE 事件块
+///
+/// 上游分块与 SSE 事件边界无关，这里缓冲到空行再输出，保证 [`with_plugin_hooks`]
+/// 每次处理的都是完整事件。流结束时剩余的不完整数据原样输出。
+pub fn sse_event_blocks<S, E>(stream: S) -> BoxStream<'static, Result<String, StreamError>>
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + Unpin + 'static,
+    E: std::fmt::Display,
+{
+    stream::unfold(Some((stream, Vec::new())), |state| async move {
+        let (mut stream, mut buffer) = state?;
+        loop {
+            if let Some(end) = buffer.windows(2).rposition(|w| w == b"\n\n") {
+                let blocks: Vec<u8> = buffer.drain(..end + 2).collect();
+                let blocks = String::from_utf8_lossy(&blocks).into_owned();
+                return Some((Ok(blocks), Some((stream, buffer))));
+            }
+            match stream.next().await {
+                Some(Ok(bytes)) => buffer.extend_from_slice(&bytes),
+                Some(Err(e)) => {
+                    return Some((
+                        Err(StreamError::Network(e.to_string())),
+                        Some((stream, buffer)),
+                    ))
+                }
+                None if buffer.is_empty() => return None,
+                None => return Some((Ok(String::from_utf8_lossy(&buffer).into_owned()), None)),
+            }
+        }
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::PluginManagerConfig;
+    use crate::ProviderType;
+    use serde_json::json;
+
+    const GUARD_SCRIPT: &str = r#"
+        function on_stream_event(ctx, event)
+          if event.type == "text_delta" then
+            event.text = string.replace(event.text, "secret", "******")
+          elseif event.type == "tool_call_delta" and event.name == "rm_rf" then
+            reject(403, "tool blocked")
+          end
+        end
+
+        function on_stream_end(ctx, response)
+          if string.find(response.content, "forbidden") then
+            reject(451, "policy violation")
+          end
+        end
+    "#;
+
+    /// 加载一个脚本插件，返回钩子和临时目录（需保持存活）
+    async fn hooks(format: StreamFormat) -> (StreamPluginHooks, tempfile::TempDir) {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().join("guard");
+        std::fs::create_dir_all(&dir).unwrap();
+        let manifest = json!({
+            "name": "guard",
+            "version": "1.0.0",
+            "entry": "main.lua",
+            "hooks": ["on_stream_event", "on_stream_end"]
+        });
+        std::fs::write(dir.join("manifest.json"), manifest.to_string()).unwrap();
+        std::fs::write(dir.join("main.lua"), GUARD_SCRIPT).unwrap();
+
+        let manager = PluginManager::new(temp.path().to_path_buf(), PluginManagerConfig::default());
+        manager.load(&dir).await.unwrap();
+        let ctx = PluginContext::new(
+            "req-1".to_string(),
+            ProviderType::Kiro,
+            "claude-sonnet-4-5".to_string(),
+        );
+        (StreamPluginHooks::new(Arc::new(manager), ctx, format), temp)
+    }
+
+    fn anthropic_text(text: &str) -> String {
+        let data = json!({
+            "type": "content_block_delta",
+            "index": 0,
+            "delta": {"type": "text_delta", "text": text}
+        });
+        format!("event: content_block_delta\ndata: {}\n\n", data)
+    }
+
+    fn openai_chunk(delta: Value) -> Result<String, StreamError> {
+        let data = json!({"choices": [{"index": 0, "delta": delta, "finish_reason": null}]});
+        Ok(format!("data: {}\n\n", data))
+    }
+
+    #[test]
+    fn test_extract_openai_chunk() {
+        let data = json!({
+            "choices": [{
+                "index": 0,
+                "delta": {
+                    "content": "hi",
+                    "tool_calls": [{"index": 2, "id": "call_1", "function": {"name": "f", "arguments": "{"}}]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 3}
+        });
+        let events: Vec<_> = extract_events(StreamFormat::OpenAiSse, &data)
+            .into_iter()
+            .map(|b| b.event)
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                StreamHookEvent::TextDelta {
+                    index: 0,
+                    text: "hi".to_string()
+                },
+                StreamHookEvent::ToolCallDelta {
+                    index: 2,
+                    id: Some("call_1".to_string()),
+                    name: Some("f".to_string()),
+                    arguments: "{".to_string()
+                },
+                StreamHookEvent::Stop {
+                    reason: Some("tool_calls".to_string())
+                },
+                StreamHookEvent::Usage {
+                    input_tokens: Some(10),
+                    output_tokens: Some(3)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_back_only_touches_bound_fields() {
+        let mut data = json!({
+            "type": "message_delta",
+            "delta": {"stop_reason": "end_turn", "stop_sequence": null},
+            "usage": {"output_tokens": 7}
+        });
+        let mut bindings = extract_events(StreamFormat::AnthropicSse, &data);
+        assert_eq!(bindings.len(), 2);
+        bindings[0].event = StreamHookEvent::Usage {
+            input_tokens: Some(1),
+            output_tokens: Some(8),
+        };
+        bindings[0].write_back(&mut data);
+        assert_eq!(data["usage"], json!({"output_tokens": 8}));
+    }
+
+    #[tokio::test]
+    async fn test_text_delta_is_rewritten() {
+        let (mut hooks, _temp) = hooks(StreamFormat::AnthropicSse).await;
+
+        let untouched = anthropic_text("hello ");
+        assert_eq!(hooks.process(untouched.clone()).await.unwrap(), untouched);
+
+        let output = hooks.process(anthropic_text("my secret")).await.unwrap();
+        assert!(output.starts_with("event: content_block_delta\ndata: "));
+        assert!(output.ends_with("\n\n"));
+        assert!(output.contains("my ******"));
+        assert!(!output.contains("secret"));
+
+        hooks.finish().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_rejection_aborts_stream() {
+        let (hooks, _temp) = hooks(StreamFormat::OpenAiSse).await;
+        let source = futures::stream::iter(vec![
+            openai_chunk(json!({"content": "ok"})),
+            openai_chunk(json!({
+                "tool_calls": [{"index": 0, "id": "c1", "function": {"name": "rm_rf", "arguments": ""}}]
+            })),
+            Ok("data: [DONE]\n\n".to_string()),
+        ]);
+
+        let items: Vec<_> = with_plugin_hooks(source, hooks).collect().await;
+        assert_eq!(items.len(), 2);
+        assert!(items[0].is_ok());
+        assert_eq!(
+            items[1],
+            Err(StreamError::Rejected {
+                status: 403,
+                message: "tool blocked".to_string()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_end_sees_rebuilt_response() {
+        let (hooks, _temp) = hooks(StreamFormat::OpenAiSse).await;
+        // 敏感词跨 chunk，只有重建后的完整响应才能匹配
+        let source = futures::stream::iter(vec![
+            openai_chunk(json!({"content": "forb"})),
+            openai_chunk(json!({"content": "idden"})),
+            Ok("data: [DONE]\n\n".to_string()),
+        ]);
+
+        let items: Vec<_> = with_plugin_hooks(source, hooks).collect().await;
+        assert_eq!(items.len(), 4);
+        assert!(items[..3].iter().all(Result::is_ok));
+        assert_eq!(
+            items[3],
+            Err(StreamError::Rejected {
+                status: 451,
+                message: "policy violation".to_string()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sse_event_blocks_buffers_until_complete_event() {
+        let source = futures::stream::iter(vec![
+            Ok::<_, std::io::Error>(Bytes::from("data: {\"a\":")),
+            Ok(Bytes::from("1}\n\ndata: {\"b\"")),
+            Ok(Bytes::from(":2}\n\ndata: [DO")),
+            Ok(Bytes::from("NE]")),
+        ]);
+
+        let items: Vec<_> = sse_event_blocks(source).collect().await;
+        assert_eq!(
+            items,
+            vec![
+                Ok("data: {\"a\":1}\n\n".to_string()),
+                Ok("data: {\"b\":2}\n\n".to_string()),
+                Ok("data: [DONE]".to_string()),
+            ]
+        );
+    }
+}
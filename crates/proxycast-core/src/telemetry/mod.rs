@@ -10,8 +10,8 @@ mod types;
 pub use logger::{LogRotationConfig, LoggerError, RequestLogger};
 pub use stats::StatsAggregator;
 pub use tokens::{
-    ModelTokenStats, PeriodTokenStats, ProviderTokenStats, TokenSource, TokenStatsSummary,
-    TokenTracker, TokenUsageRecord,
+    ModelTokenStats, PeriodTokenStats, ProviderTokenStats, TokenEstimator, TokenSource,
+    TokenStatsSummary, TokenTracker, TokenUsageRecord,
 };
 pub use types::{ModelStats, ProviderStats, RequestLog, RequestStatus, StatsSummary, TimeRange};
 
@@ -2,6 +2,7 @@
 //!
 //! 提供请求统计的聚合、分组和查询功能
 
+use crate::services::shared_state::SharedState;
 use crate::telemetry::types::{
     ModelStats, ProviderStats, RequestLog, RequestStatus, StatsSummary, TimeRange,
 };
@@ -9,6 +10,7 @@ use crate::ProviderType;
 use chrono::{Duration, Utc};
 use parking_lot::RwLock;
 use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
 
 /// 统计聚合器
 ///
@@ -20,6 +22,8 @@ pub struct StatsAggregator {
     retention: Duration,
     /// 最大日志条数
     max_logs: usize,
+    /// 多实例共享状态（累加集群请求计数器）
+    shared_state: RwLock<Option<Arc<SharedState>>>,
 }
 
 impl StatsAggregator {
@@ -33,9 +37,18 @@ impl StatsAggregator {
             logs: RwLock::new(VecDeque::with_capacity(max_logs)),
             retention,
             max_logs,
+            shared_state: RwLock::new(None),
         }
     }
 
+    /// 设置多实例共享状态（传入 None 时停止累加集群计数器）
+    ///
+    /// 每条请求日志累加 `requests:total`、`requests:provider:<provider>`
+    /// 与 `requests:status:<status>` 三个计数器。
+    pub fn set_shared_state(&self, shared: Option<Arc<SharedState>>) {
+        *self.shared_state.write() = shared;
+    }
+
     /// 使用默认配置创建统计聚合器（保留 7 天，最多 10000 条）
     pub fn with_defaults() -> Self {
         Self::new(Duration::days(7), 10000)
@@ -45,6 +58,12 @@ impl StatsAggregator {
     ///
     /// 将日志添加到聚合器中，并自动清理过期日志
     pub fn record(&self, log: RequestLog) {
+        if let Some(shared) = self.shared_state.read().as_ref() {
+            shared.spawn_increment("requests:total".to_string(), 1);
+            shared.spawn_increment(format!("requests:provider:{}", log.provider), 1);
+            shared.spawn_increment(format!("requests:status:{}", log.status), 1);
+        }
+
         let mut logs = self.logs.write();
         logs.push_back(log);
 
@@ -246,6 +246,10 @@ async fn handle_message(
             // 忽略客户端发送的错误消息
             None
         }
+        WsMessage::Cancel { .. } | WsMessage::Credit(_) => {
+            // 该处理器同步处理请求，不存在可取消或需要额度的进行中请求
+            None
+        }
         WsMessage::SubscribeFlowEvents | WsMessage::UnsubscribeFlowEvents => {
             // Flow 事件订阅在 server/handlers/websocket.rs 中处理
             // 这里的 handler 是旧的实现，暂时返回不支持的错误
@@ -275,6 +279,16 @@ async fn handle_message(
                 "KiroCredentialEvent messages are server-to-client only",
             )))
         }
+        WsMessage::SubscribeInterceptEvents | WsMessage::UnsubscribeInterceptEvents => {
+            // 拦截事件订阅在 server/handlers/websocket.rs 中处理
+            Some(WsMessage::Error(WsError::invalid_request(
+                None,
+                "Intercept event subscription is not supported in this handler",
+            )))
+        }
+        WsMessage::InterceptEvent(_) => Some(WsMessage::Error(WsError::invalid_message(
+            "InterceptEvent messages are server-to-client only",
+        ))),
     }
 }
 
@@ -0,0 +1,292 @@
+//! WebSocket 进行中请求管理
+//!
+//! 每个连接维护一份进行中请求表：
+//! - 按 `request_id` 取消请求（触发取消令牌，中止上游调用）
+//! - 限制单个连接的并发请求数
+//! - 为流式请求维护基于额度的背压（等待额度超时则中止请求）
+
+use super::WsError;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+
+/// 流式发送额度
+///
+/// 每发送一个流式块消耗 1 个额度，额度耗尽时发送方等待客户端补充，
+/// 等待超过 `wait_timeout` 视为客户端已停止消费。
+#[derive(Debug)]
+pub struct StreamCredits {
+    permits: Semaphore,
+    wait_timeout: Duration,
+}
+
+impl StreamCredits {
+    /// 以初始额度和最长等待时间创建
+    pub fn new(initial: u32, wait_timeout: Duration) -> Self {
+        Self {
+            permits: Semaphore::new((initial as usize).min(Semaphore::MAX_PERMITS)),
+            wait_timeout,
+        }
+    }
+
+    /// 消耗 1 个额度，额度不足时等待；超时返回 false
+    pub async fn acquire(&self) -> bool {
+        match tokio::time::timeout(self.wait_timeout, self.permits.acquire()).await {
+            Ok(Ok(permit)) => {
+                permit.forget();
+                true
+            }
+            Ok(Err(_)) => true,
+            Err(_) => false,
+        }
+    }
+
+    /// 等待额度的最长时间
+    pub fn wait_timeout(&self) -> Duration {
+        self.wait_timeout
+    }
+
+    /// 补充额度
+    pub fn grant(&self, credits: u32) {
+        let room = Semaphore::MAX_PERMITS - self.permits.available_permits();
+        self.permits.add_permits((credits as usize).min(room));
+    }
+
+    /// 当前剩余额度
+    pub fn available(&self) -> usize {
+        self.permits.available_permits()
+    }
+}
+
+#[derive(Debug)]
+struct InFlightEntry {
+    cancel_token: CancellationToken,
+    credits: Option<Arc<StreamCredits>>,
+}
+
+/// 单个连接的进行中请求表
+#[derive(Debug)]
+pub struct InFlightRequests {
+    entries: DashMap<String, InFlightEntry>,
+    max_concurrent: usize,
+    credit_wait_timeout: Duration,
+}
+
+impl InFlightRequests {
+    /// 创建请求表
+    ///
+    /// `credit_wait_timeout` 为流式请求等待客户端补充额度的最长时间。
+    pub fn new(max_concurrent: usize, credit_wait_timeout: Duration) -> Self {
+        Self {
+            entries: DashMap::new(),
+            max_concurrent,
+            credit_wait_timeout,
+        }
+    }
+
+    /// 登记新请求
+    ///
+    /// `request_id` 已在进行中或并发数达到上限时返回错误。
+    /// 返回的守卫在释放时自动注销请求。
+    pub fn begin(
+        self: &Arc<Self>,
+        request_id: &str,
+        stream_credits: Option<u32>,
+    ) -> Result<InFlightGuard, WsError> {
+        if self.entries.len() >= self.max_concurrent {
+            return Err(WsError::too_many_requests(request_id, self.max_concurrent));
+        }
+
+        match self.entries.entry(request_id.to_string()) {
+            Entry::Occupied(_) => Err(WsError::invalid_request(
+                Some(request_id.to_string()),
+                format!("Request {} is already in flight", request_id),
+            )),
+            Entry::Vacant(slot) => {
+                let cancel_token = CancellationToken::new();
+                let credits = stream_credits
+                    .map(|c| Arc::new(StreamCredits::new(c, self.credit_wait_timeout)));
+                slot.insert(InFlightEntry {
+                    cancel_token: cancel_token.clone(),
+                    credits: credits.clone(),
+                });
+                Ok(InFlightGuard {
+                    requests: self.clone(),
+                    request_id: request_id.to_string(),
+                    cancel_token,
+                    credits,
+                })
+            }
+        }
+    }
+
+    /// 取消请求，请求不存在时返回 false
+    pub fn cancel(&self, request_id: &str) -> bool {
+        match self.entries.get(request_id) {
+            Some(entry) => {
+                entry.cancel_token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 为请求补充流式额度
+    ///
+    /// 请求不存在或未启用额度控制时返回 false。
+    pub fn grant(&self, request_id: &str, credits: u32) -> bool {
+        match self.entries.get(request_id).and_then(|e| e.credits.clone()) {
+            Some(stream_credits) => {
+                stream_credits.grant(credits);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 取消所有请求（连接关闭时调用）
+    pub fn cancel_all(&self) {
+        for entry in self.entries.iter() {
+            entry.cancel_token.cancel();
+        }
+    }
+
+    /// 进行中的请求数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 是否没有进行中的请求
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// 进行中请求守卫，释放时从请求表中注销
+#[derive(Debug)]
+pub struct InFlightGuard {
+    requests: Arc<InFlightRequests>,
+    request_id: String,
+    cancel_token: CancellationToken,
+    credits: Option<Arc<StreamCredits>>,
+}
+
+impl InFlightGuard {
+    /// 请求 ID
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    /// 请求的取消令牌
+    pub fn cancel_token(&self) -> &CancellationToken {
+        &self.cancel_token
+    }
+
+    /// 流式发送额度（未启用额度控制时为 None）
+    pub fn credits(&self) -> Option<Arc<StreamCredits>> {
+        self.credits.clone()
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.requests.entries.remove(&self.request_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket::WsErrorCode;
+
+    const CREDIT_WAIT: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn test_begin_enforces_concurrency_limit() {
+        let requests = Arc::new(InFlightRequests::new(2, CREDIT_WAIT));
+        let _a = requests.begin("a", None).unwrap();
+        let b = requests.begin("b", None).unwrap();
+
+        let err = requests.begin("c", None).unwrap_err();
+        assert_eq!(err.code, WsErrorCode::TooManyRequests);
+        assert_eq!(err.request_id.as_deref(), Some("c"));
+
+        // 请求结束后释放名额
+        drop(b);
+        assert_eq!(requests.len(), 1);
+        assert!(requests.begin("c", None).is_ok());
+    }
+
+    #[test]
+    fn test_begin_rejects_duplicate_request_id() {
+        let requests = Arc::new(InFlightRequests::new(4, CREDIT_WAIT));
+        let _a = requests.begin("a", None).unwrap();
+        let err = requests.begin("a", None).unwrap_err();
+        assert_eq!(err.code, WsErrorCode::InvalidRequest);
+    }
+
+    #[test]
+    fn test_cancel_triggers_token() {
+        let requests = Arc::new(InFlightRequests::new(4, CREDIT_WAIT));
+        let a = requests.begin("a", None).unwrap();
+        let b = requests.begin("b", None).unwrap();
+
+        assert!(requests.cancel("a"));
+        assert!(a.cancel_token().is_cancelled());
+        assert!(!b.cancel_token().is_cancelled());
+        assert!(!requests.cancel("missing"));
+
+        requests.cancel_all();
+        assert!(b.cancel_token().is_cancelled());
+
+        drop(a);
+        drop(b);
+        assert!(requests.is_empty());
+    }
+
+    #[test]
+    fn test_grant_requires_credit_mode() {
+        let requests = Arc::new(InFlightRequests::new(4, CREDIT_WAIT));
+        let plain = requests.begin("plain", None).unwrap();
+        let credited = requests.begin("credited", Some(1)).unwrap();
+
+        assert!(plain.credits().is_none());
+        assert!(!requests.grant("plain", 5));
+        assert!(requests.grant("credited", 5));
+        assert_eq!(credited.credits().unwrap().available(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_stream_credits_block_until_granted() {
+        let credits = Arc::new(StreamCredits::new(1, CREDIT_WAIT));
+        assert!(credits.acquire().await);
+        assert_eq!(credits.available(), 0);
+
+        let waiter = {
+            let credits = credits.clone();
+            tokio::spawn(async move { credits.acquire().await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+
+        credits.grant(1);
+        let acquired = tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("acquire should resume after grant")
+            .unwrap();
+        assert!(acquired);
+        assert_eq!(credits.available(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_stream_credits_wait_times_out() {
+        let credits = StreamCredits::new(0, Duration::from_millis(20));
+        assert!(!credits.acquire().await);
+
+        credits.grant(1);
+        assert!(credits.acquire().await);
+    }
+}
@@ -3,16 +3,19 @@
 //! 提供 WebSocket API 支持，允许客户端通过持久连接发送请求：
 //! - 连接握手和升级
 //! - 消息解析和处理
-//! - 流式响应转发
+//! - 流式响应转发（基于额度的背压）
+//! - 按请求 ID 取消进行中的请求
 //! - 心跳检测和连接生命周期管理
 
 mod handler;
+mod inflight;
 mod lifecycle;
 mod processor;
 mod stream;
 mod types;
 
 pub use handler::{parse_message, serialize_message, ws_handler, WsHandlerState};
+pub use inflight::{InFlightGuard, InFlightRequests, StreamCredits};
 pub use lifecycle::{
     ConnectionLifecycle, GracefulShutdown, HeartbeatManager, LifecycleState, ResourceCleaner,
 };
@@ -20,8 +23,8 @@ pub use processor::MessageProcessor;
 pub use stream::{BackpressureController, StreamForwarder};
 pub use types::{
     KiroTokenInfo, WsApiRequest, WsApiResponse, WsConfig, WsConnection, WsConnectionStatus,
-    WsEndpoint, WsError, WsErrorCode, WsFlowEvent, WsKiroEvent, WsMessage, WsStats,
-    WsStatsSnapshot, WsStreamChunk, WsStreamEnd,
+    WsEndpoint, WsError, WsErrorCode, WsFlowEvent, WsInterceptEvent, WsKiroEvent, WsMessage,
+    WsStats, WsStatsSnapshot, WsStreamChunk, WsStreamCredit, WsStreamEnd,
 };
 
 use dashmap::DashMap;
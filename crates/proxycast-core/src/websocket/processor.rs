@@ -174,6 +174,7 @@ mod tests {
             request_id: "".to_string(),
             endpoint: WsEndpoint::Models,
             payload: serde_json::json!({}),
+            stream_credits: None,
         };
         let result = MessageProcessor::validate_request(&request);
         assert!(result.is_err());
@@ -185,6 +186,7 @@ mod tests {
             request_id: "req-1".to_string(),
             endpoint: WsEndpoint::Models,
             payload: serde_json::json!("not an object"),
+            stream_credits: None,
         };
         let result = MessageProcessor::validate_request(&request);
         assert!(result.is_err());
@@ -198,6 +200,7 @@ mod tests {
             payload: serde_json::json!({
                 "messages": [{"role": "user", "content": "hello"}]
             }),
+            stream_credits: None,
         };
         let result = MessageProcessor::validate_request(&request);
         assert!(result.is_err());
@@ -211,6 +214,7 @@ mod tests {
             payload: serde_json::json!({
                 "model": "gpt-4"
             }),
+            stream_credits: None,
         };
         let result = MessageProcessor::validate_request(&request);
         assert!(result.is_err());
@@ -225,6 +229,7 @@ mod tests {
                 "model": "gpt-4",
                 "messages": [{"role": "user", "content": "hello"}]
             }),
+            stream_credits: None,
         };
         let result = MessageProcessor::validate_request(&request);
         assert!(result.is_ok());
@@ -239,6 +244,7 @@ mod tests {
                 "model": "claude-3",
                 "messages": [{"role": "user", "content": "hello"}]
             }),
+            stream_credits: None,
         };
         let result = MessageProcessor::validate_request(&request);
         assert!(result.is_err());
@@ -254,6 +260,7 @@ mod tests {
                 "messages": [{"role": "user", "content": "hello"}],
                 "max_tokens": 1024
             }),
+            stream_credits: None,
         };
         let result = MessageProcessor::validate_request(&request);
         assert!(result.is_ok());
@@ -265,6 +272,7 @@ mod tests {
             request_id: "req-1".to_string(),
             endpoint: WsEndpoint::Models,
             payload: serde_json::json!({}),
+            stream_credits: None,
         };
         let result = MessageProcessor::validate_request(&request);
         assert!(result.is_ok());
@@ -2,8 +2,9 @@
 //!
 //! 将 SSE 流转换为 WebSocket 消息，实现背压控制
 
-use super::{MessageProcessor, WsError, WsMessage};
+use super::{MessageProcessor, StreamCredits, WsError, WsMessage};
 use futures::{Stream, StreamExt};
+use std::sync::Arc;
 use tokio::sync::mpsc;
 
 /// 流式响应转发器
@@ -12,6 +13,8 @@ pub struct StreamForwarder {
     request_id: String,
     /// 背压缓冲区大小
     buffer_size: usize,
+    /// 客户端授予的发送额度（None 表示不限制）
+    credits: Option<Arc<StreamCredits>>,
 }
 
 impl StreamForwarder {
@@ -20,6 +23,7 @@ impl StreamForwarder {
         Self {
             request_id,
             buffer_size: 32, // 默认缓冲区大小
+            credits: None,
         }
     }
 
@@ -29,6 +33,25 @@ impl StreamForwarder {
         self
     }
 
+    /// 设置发送额度
+    ///
+    /// 每个流式块发送前消耗 1 个额度，额度耗尽时停止读取上游。
+    pub fn with_credits(mut self, credits: Option<Arc<StreamCredits>>) -> Self {
+        self.credits = credits;
+        self
+    }
+
+    /// 等待发送额度，超时返回错误（调用方随之中止请求）
+    async fn wait_for_credit(&self) -> Result<(), WsError> {
+        match &self.credits {
+            Some(credits) if !credits.acquire().await => Err(WsError::credit_timeout(
+                self.request_id.clone(),
+                credits.wait_timeout(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
     /// 将 SSE 数据行转换为 WebSocket 消息
     ///
     /// SSE 格式: "data: {...}\n\n"
@@ -115,6 +138,7 @@ impl StreamForwarder {
                         buffer = buffer[pos + 1..].to_string();
 
                         if let Some(msg) = self.convert_sse_line(&line, index) {
+                            self.wait_for_credit().await?;
                             // 发送消息，如果通道满则等待（背压）
                             if sender.send(msg).await.is_err() {
                                 return Err(WsError::internal(
@@ -138,6 +162,7 @@ impl StreamForwarder {
         // 处理缓冲区中剩余的数据
         if !buffer.is_empty() {
             if let Some(msg) = self.convert_sse_line(&buffer, index) {
+                self.wait_for_credit().await?;
                 let _ = sender.send(msg).await;
                 index += 1;
             }
@@ -308,6 +333,49 @@ mod tests {
         assert_eq!(forwarder.buffer_size, 64);
     }
 
+    #[tokio::test]
+    async fn test_forward_string_stream_waits_for_credits() {
+        let credits = Arc::new(StreamCredits::new(1, std::time::Duration::from_secs(60)));
+        let forwarder =
+            StreamForwarder::new("req-1".to_string()).with_credits(Some(credits.clone()));
+        let (tx, mut rx) = forwarder.create_channel();
+        let source = futures::stream::iter(vec![Ok::<_, String>(
+            "data: one\n\ndata: two\n\n".to_string(),
+        )]);
+
+        let forward =
+            tokio::spawn(async move { forwarder.forward_string_stream(source, tx).await });
+
+        assert!(matches!(rx.recv().await, Some(WsMessage::StreamChunk(c)) if c.data == "one"));
+        // 额度耗尽，第二块被挂起
+        let pending = tokio::time::timeout(std::time::Duration::from_millis(20), rx.recv()).await;
+        assert!(pending.is_err());
+
+        credits.grant(1);
+        assert!(matches!(rx.recv().await, Some(WsMessage::StreamChunk(c)) if c.data == "two"));
+        assert!(matches!(rx.recv().await, Some(WsMessage::StreamEnd(e)) if e.total_chunks == 2));
+        assert_eq!(forward.await.unwrap().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_forward_string_stream_aborts_when_credits_run_out() {
+        let credits = Arc::new(StreamCredits::new(1, std::time::Duration::from_millis(20)));
+        let forwarder = StreamForwarder::new("req-1".to_string()).with_credits(Some(credits));
+        let (tx, mut rx) = forwarder.create_channel();
+        let source = futures::stream::iter(vec![Ok::<_, String>(
+            "data: one\n\ndata: two\n\n".to_string(),
+        )]);
+
+        let err = forwarder
+            .forward_string_stream(source, tx)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, crate::websocket::WsErrorCode::Timeout);
+        assert_eq!(err.request_id.as_deref(), Some("req-1"));
+        assert!(matches!(rx.recv().await, Some(WsMessage::StreamChunk(c)) if c.data == "one"));
+        assert!(rx.recv().await.is_none());
+    }
+
     #[test]
     fn test_create_channel() {
         let forwarder = StreamForwarder::new("req-1".to_string()).with_buffer_size(16);
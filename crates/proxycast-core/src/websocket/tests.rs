@@ -82,6 +82,14 @@ fn test_ws_error_constructors() {
     let err = WsError::upstream(Some("req-2".to_string()), "provider error");
     assert_eq!(err.code, WsErrorCode::UpstreamError);
     assert_eq!(err.request_id, Some("req-2".to_string()));
+
+    let err = WsError::cancelled("req-3");
+    assert_eq!(err.code, WsErrorCode::Cancelled);
+    assert_eq!(err.request_id, Some("req-3".to_string()));
+
+    let err = WsError::too_many_requests("req-4", 16);
+    assert_eq!(err.code, WsErrorCode::TooManyRequests);
+    assert!(err.message.contains("16"));
 }
 
 #[test]
@@ -92,6 +100,7 @@ fn test_ws_config_defaults() {
     assert_eq!(config.heartbeat_timeout_secs, 60);
     assert_eq!(config.max_connections, 100);
     assert_eq!(config.max_message_size, 16 * 1024 * 1024);
+    assert_eq!(config.max_concurrent_requests, 16);
 }
 
 #[test]
@@ -219,6 +228,7 @@ fn test_ws_api_request_serialization() {
         request_id: "req-123".to_string(),
         endpoint: WsEndpoint::ChatCompletions,
         payload: serde_json::json!({"model": "gpt-4", "messages": []}),
+        stream_credits: None,
     };
 
     let json = serde_json::to_string(&request).unwrap();
@@ -228,6 +238,33 @@ fn test_ws_api_request_serialization() {
     let parsed: WsApiRequest = serde_json::from_str(&json).unwrap();
     assert_eq!(parsed.request_id, "req-123");
     assert_eq!(parsed.endpoint, WsEndpoint::ChatCompletions);
+    assert!(parsed.stream_credits.is_none());
+    assert!(!json.contains("stream_credits"));
+}
+
+#[test]
+fn test_ws_cancel_and_credit_deserialization() {
+    let msg: WsMessage = serde_json::from_str(r#"{"type":"cancel","request_id":"req-1"}"#).unwrap();
+    assert!(matches!(msg, WsMessage::Cancel { request_id } if request_id == "req-1"));
+
+    let msg: WsMessage =
+        serde_json::from_str(r#"{"type":"credit","request_id":"req-1","credits":8}"#).unwrap();
+    match msg {
+        WsMessage::Credit(credit) => {
+            assert_eq!(credit.request_id, "req-1");
+            assert_eq!(credit.credits, 8);
+        }
+        _ => panic!("Expected Credit message"),
+    }
+
+    let msg: WsMessage = serde_json::from_str(
+        r#"{"type":"request","request_id":"req-2","endpoint":"messages","payload":{},"stream_credits":4}"#,
+    )
+    .unwrap();
+    match msg {
+        WsMessage::Request(request) => assert_eq!(request.stream_credits, Some(4)),
+        _ => panic!("Expected Request message"),
+    }
 }
 
 #[test]
@@ -247,6 +284,38 @@ fn test_ws_stream_chunk_serialization() {
     assert_eq!(parsed.index, 5);
 }
 
+#[test]
+fn test_ws_intercept_event_serialization() {
+    use crate::flow_monitor::{InterceptEvent, TimeoutAction};
+
+    let msg: WsMessage = serde_json::from_str(r#"{"type":"subscribe_intercept_events"}"#).unwrap();
+    assert!(matches!(msg, WsMessage::SubscribeInterceptEvents));
+
+    let event = WsMessage::InterceptEvent(
+        InterceptEvent::FlowTimedOut {
+            flow_id: "flow-1".to_string(),
+            action: TimeoutAction::Cancel,
+        }
+        .into(),
+    );
+    let json: serde_json::Value = serde_json::to_value(&event).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "type": "intercept_event",
+            "event_type": "flow_timed_out",
+            "flow_id": "flow-1",
+            "action": "cancel"
+        })
+    );
+
+    let parsed: WsMessage = serde_json::from_value(json).unwrap();
+    assert!(matches!(
+        parsed,
+        WsMessage::InterceptEvent(WsInterceptEvent::FlowTimedOut { .. })
+    ));
+}
+
 // ============ Property-Based Tests ============
 
 use proptest::prelude::*;
@@ -269,6 +338,8 @@ fn arb_error_code() -> impl Strategy<Value = WsErrorCode> {
         Just(WsErrorCode::InternalError),
         Just(WsErrorCode::UpstreamError),
         Just(WsErrorCode::Timeout),
+        Just(WsErrorCode::Cancelled),
+        Just(WsErrorCode::TooManyRequests),
     ]
 }
 
@@ -287,6 +358,7 @@ fn arb_api_request() -> impl Strategy<Value = WsApiRequest> {
             request_id,
             endpoint,
             payload,
+            stream_credits: None,
         })
 }
 
@@ -356,6 +428,13 @@ fn arb_message() -> impl Strategy<Value = WsMessage> {
         arb_error().prop_map(WsMessage::Error),
         (0i64..i64::MAX).prop_map(|timestamp| WsMessage::Ping { timestamp }),
         (0i64..i64::MAX).prop_map(|timestamp| WsMessage::Pong { timestamp }),
+        "[a-zA-Z0-9-]{1,36}".prop_map(|request_id| WsMessage::Cancel { request_id }),
+        ("[a-zA-Z0-9-]{1,36}", 0u32..1000u32).prop_map(|(request_id, credits)| {
+            WsMessage::Credit(WsStreamCredit {
+                request_id,
+                credits,
+            })
+        }),
     ]
 }
 
@@ -6,6 +6,9 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicU64, Ordering};
 
+use crate::flow_monitor::interceptor::{
+    InterceptConfig, InterceptEvent, InterceptedFlow, TimeoutAction,
+};
 use crate::flow_monitor::models::FlowError;
 use crate::flow_monitor::monitor::{
     FlowEvent, FlowSummary, FlowUpdate, NotificationEvent, ThresholdCheckResult,
@@ -70,6 +73,10 @@ pub enum WsMessage {
     StreamEnd(WsStreamEnd),
     /// 错误消息
     Error(WsError),
+    /// 取消进行中的请求（客户端 -> 服务端）
+    Cancel { request_id: String },
+    /// 为流式请求补充发送额度（客户端 -> 服务端）
+    Credit(WsStreamCredit),
     /// 心跳请求
     Ping { timestamp: i64 },
     /// 心跳响应
@@ -86,6 +93,12 @@ pub enum WsMessage {
     UnsubscribeKiroEvents,
     /// Kiro 凭证状态事件通知
     KiroCredentialEvent(WsKiroEvent),
+    /// 订阅拦截事件（需要 API 密钥认证）
+    SubscribeInterceptEvents,
+    /// 取消订阅拦截事件
+    UnsubscribeInterceptEvents,
+    /// 拦截事件通知
+    InterceptEvent(WsInterceptEvent),
 }
 
 /// WebSocket API 请求
@@ -97,6 +110,13 @@ pub struct WsApiRequest {
     pub endpoint: WsEndpoint,
     /// 请求体（JSON）
     pub payload: serde_json::Value,
+    /// 流式响应的初始发送额度
+    ///
+    /// 设置后启用基于额度的背压：每发送一个流式块消耗 1 个额度，
+    /// 额度耗尽时暂停读取上游，直到客户端发送 `credit` 消息补充。
+    /// 未设置时不限制发送速度。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_credits: Option<u32>,
 }
 
 /// API 端点类型
@@ -140,6 +160,15 @@ pub struct WsStreamEnd {
     pub total_chunks: u32,
 }
 
+/// WebSocket 流式发送额度补充
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WsStreamCredit {
+    /// 请求 ID（关联请求）
+    pub request_id: String,
+    /// 补充的额度（可发送的流式块数）
+    pub credits: u32,
+}
+
 /// WebSocket 错误
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WsError {
@@ -167,6 +196,10 @@ pub enum WsErrorCode {
     UpstreamError,
     /// 请求超时
     Timeout,
+    /// 请求已被客户端取消
+    Cancelled,
+    /// 连接上的并发请求数已达上限
+    TooManyRequests,
 }
 
 impl WsError {
@@ -214,6 +247,36 @@ impl WsError {
             message: message.into(),
         }
     }
+
+    /// 创建请求已取消错误
+    pub fn cancelled(request_id: impl Into<String>) -> Self {
+        Self {
+            request_id: Some(request_id.into()),
+            code: WsErrorCode::Cancelled,
+            message: "Request cancelled by client".to_string(),
+        }
+    }
+
+    /// 创建等待流式额度超时错误
+    pub fn credit_timeout(request_id: impl Into<String>, timeout: std::time::Duration) -> Self {
+        Self {
+            request_id: Some(request_id.into()),
+            code: WsErrorCode::Timeout,
+            message: format!(
+                "No stream credits granted within {}s, request aborted",
+                timeout.as_secs()
+            ),
+        }
+    }
+
+    /// 创建并发请求超限错误
+    pub fn too_many_requests(request_id: impl Into<String>, limit: usize) -> Self {
+        Self {
+            request_id: Some(request_id.into()),
+            code: WsErrorCode::TooManyRequests,
+            message: format!("Maximum concurrent requests ({}) reached", limit),
+        }
+    }
 }
 
 /// WebSocket 配置
@@ -234,6 +297,12 @@ pub struct WsConfig {
     /// 消息大小限制（字节）
     #[serde(default = "default_max_message_size")]
     pub max_message_size: usize,
+    /// 单个连接的最大并发请求数
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    /// 流式请求等待客户端补充额度的最长时间（秒），超时后中止请求
+    #[serde(default = "default_credit_wait_timeout")]
+    pub credit_wait_timeout_secs: u64,
 }
 
 fn default_enabled() -> bool {
@@ -256,6 +325,14 @@ fn default_max_message_size() -> usize {
     16 * 1024 * 1024 // 16MB
 }
 
+fn default_max_concurrent_requests() -> usize {
+    16
+}
+
+fn default_credit_wait_timeout() -> u64 {
+    60
+}
+
 impl Default for WsConfig {
     fn default() -> Self {
         Self {
@@ -264,6 +341,8 @@ impl Default for WsConfig {
             heartbeat_timeout_secs: default_heartbeat_timeout(),
             max_connections: default_max_connections(),
             max_message_size: default_max_message_size(),
+            max_concurrent_requests: default_max_concurrent_requests(),
+            credit_wait_timeout_secs: default_credit_wait_timeout(),
         }
     }
 }
@@ -376,6 +455,47 @@ impl From<FlowEvent> for WsFlowEvent {
     }
 }
 
+/// WebSocket 拦截事件
+///
+/// 用于通过 WebSocket 推送 Flow 拦截器事件，客户端据此调用管理 API 继续或取消 Flow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event_type", rename_all = "snake_case")]
+pub enum WsInterceptEvent {
+    /// Flow 被拦截
+    FlowIntercepted { flow: Box<InterceptedFlow> },
+    /// Flow 继续处理
+    FlowContinued { flow_id: String, modified: bool },
+    /// Flow 被取消
+    FlowCancelled { flow_id: String },
+    /// Flow 超时
+    FlowTimedOut {
+        flow_id: String,
+        action: TimeoutAction,
+    },
+    /// 配置已更新
+    ConfigUpdated { config: InterceptConfig },
+}
+
+impl From<InterceptEvent> for WsInterceptEvent {
+    fn from(event: InterceptEvent) -> Self {
+        match event {
+            InterceptEvent::FlowIntercepted { flow } => WsInterceptEvent::FlowIntercepted {
+                flow: Box::new(flow),
+            },
+            InterceptEvent::FlowContinued { flow_id, modified } => {
+                WsInterceptEvent::FlowContinued { flow_id, modified }
+            }
+            InterceptEvent::FlowCancelled { flow_id } => {
+                WsInterceptEvent::FlowCancelled { flow_id }
+            }
+            InterceptEvent::FlowTimedOut { flow_id, action } => {
+                WsInterceptEvent::FlowTimedOut { flow_id, action }
+            }
+            InterceptEvent::ConfigUpdated { config } => WsInterceptEvent::ConfigUpdated { config },
+        }
+    }
+}
+
 /// WebSocket Kiro 凭证事件
 ///
 /// 用于通过 WebSocket 推送 Kiro 凭证状态变化
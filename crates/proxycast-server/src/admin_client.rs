@@ -0,0 +1,334 @@
+//! 管理子命令的执行目标
+//!
+//! `credentials`、`routes`、`flows`、`stats`、`backup` 子命令优先通过管理 API
+//! 操作运行中的实例；检测不到运行中的实例（或指定 `--direct`）时直接读写本地数据库。
+//!
+//! 实例地址默认由配置中的 `server.host` / `server.port` 推断，管理 API 与 `/api/credentials`
+//! 使用 `remote_management.secret_key` 认证，`/v1` 端点使用 `server.api_key`。
+
+use clap::Args;
+use proxycast_core::config::{Config, ConfigManager};
+use proxycast_core::database::{self, DbConnection};
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 探测运行中实例的超时
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 管理请求的超时（刷新、测试调用需要访问上游）
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 执行目标参数
+#[derive(Args, Debug, Clone, Default)]
+pub struct TargetArgs {
+    /// 运行中实例的地址（默认由配置中的 server.host/port 推断）
+    #[arg(long, global = true)]
+    pub server_url: Option<String>,
+
+    /// 管理 API 密钥（默认使用配置中的 remote_management.secret_key）
+    #[arg(long, global = true)]
+    pub management_key: Option<String>,
+
+    /// 不连接运行中的实例，直接操作本地数据库
+    #[arg(long, global = true, conflicts_with = "server_url")]
+    pub direct: bool,
+
+    /// 本地模式下的 Flow 存储目录（默认 ~/.proxycast/flows）
+    #[arg(long, global = true)]
+    pub flows_dir: Option<PathBuf>,
+}
+
+/// 子命令的执行目标
+pub enum Target {
+    /// 通过管理 API 操作运行中的实例
+    Remote(ManagementClient),
+    /// 直接操作本地数据库与 Flow 存储
+    Direct {
+        config: Box<Config>,
+        flows_dir: PathBuf,
+    },
+}
+
+impl Target {
+    /// 解析执行目标
+    ///
+    /// 显式指定 `--server-url` 时连接失败即报错；否则探测失败时退回本地模式。
+    pub async fn resolve(args: &TargetArgs, config_path: &Path) -> Result<Self, String> {
+        let config = load_config(config_path)?;
+        let direct = |config| Target::Direct {
+            config: Box::new(config),
+            flows_dir: args.flows_dir.clone().unwrap_or_else(default_flows_dir),
+        };
+        if args.direct {
+            return Ok(direct(config));
+        }
+
+        let base_url = args
+            .server_url
+            .clone()
+            .unwrap_or_else(|| default_server_url(&config));
+        let management_key = args
+            .management_key
+            .clone()
+            .or_else(|| config.remote_management.secret_key.clone())
+            .filter(|key| !key.is_empty());
+        let client =
+            ManagementClient::new(base_url, management_key, config.server.api_key.clone())?;
+
+        match client.probe().await {
+            Ok(()) => Ok(Target::Remote(client)),
+            Err(e) if args.server_url.is_some() => Err(e),
+            Err(e) => {
+                eprintln!("提示: {}，直接操作本地数据库", e);
+                Ok(direct(config))
+            }
+        }
+    }
+}
+
+/// 加载配置（文件不存在时使用默认配置）
+fn load_config(path: &Path) -> Result<Config, String> {
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("读取配置失败 {}: {}", path.display(), e))?;
+    ConfigManager::parse_yaml(&content)
+        .map_err(|e| format!("解析配置失败 {}: {}", path.display(), e))
+}
+
+/// 由监听地址推断实例地址（通配地址改为本机回环地址）
+fn default_server_url(config: &Config) -> String {
+    let scheme = if config.server.tls.enable {
+        "https"
+    } else {
+        "http"
+    };
+    let host = match config.server.host.as_str() {
+        "0.0.0.0" | "" => "127.0.0.1".to_string(),
+        "::" => "[::1]".to_string(),
+        host if host.contains(':') && !host.starts_with('[') => format!("[{}]", host),
+        host => host.to_string(),
+    };
+    format!("{}://{}:{}", scheme, host, config.server.port)
+}
+
+/// 打开本地数据库
+pub fn open_database() -> Result<DbConnection, String> {
+    database::init_database().map_err(|e| format!("打开数据库失败: {}", e))
+}
+
+/// 默认的本地 Flow 存储目录
+fn default_flows_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".proxycast")
+        .join("flows")
+}
+
+/// 使用管理密钥认证的端点
+fn is_management_path(path: &str) -> bool {
+    path.starts_with("/v0/management") || path.starts_with("/api/credentials")
+}
+
+/// 管理 API 客户端
+pub struct ManagementClient {
+    http: reqwest::Client,
+    base_url: String,
+    management_key: Option<String>,
+    api_key: String,
+}
+
+impl ManagementClient {
+    pub fn new(
+        base_url: String,
+        management_key: Option<String>,
+        api_key: String,
+    ) -> Result<Self, String> {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+        Ok(Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            management_key,
+            api_key,
+        })
+    }
+
+    /// 确认地址上运行着提供管理 API 的实例
+    async fn probe(&self) -> Result<(), String> {
+        let response = self
+            .http
+            .get(format!("{}/health", self.base_url))
+            .timeout(PROBE_TIMEOUT)
+            .send()
+            .await
+            .map_err(|_| format!("无法连接运行中的实例 {}", self.base_url))?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("{} 上的服务不提供管理 API", self.base_url))
+        }
+    }
+
+    /// 构造请求：管理 API 使用管理密钥，其余端点使用代理 API Key
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let builder = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path));
+        if is_management_path(path) {
+            match &self.management_key {
+                Some(key) => builder.header("x-management-key", key),
+                None => builder,
+            }
+        } else {
+            builder.bearer_auth(&self.api_key)
+        }
+    }
+
+    async fn send(&self, path: &str, builder: RequestBuilder) -> Result<String, String> {
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| format!("请求 {} 失败: {}", path, e))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("读取 {} 响应失败: {}", path, e))?;
+        if status.is_success() {
+            return Ok(body);
+        }
+        if status == StatusCode::NOT_FOUND
+            && is_management_path(path)
+            && self.management_key.is_none()
+        {
+            return Err(
+                "管理 API 未启用：请在配置中设置 remote_management.secret_key，或使用 --direct"
+                    .to_string(),
+            );
+        }
+        Err(format!(
+            "{} 返回 {}: {}",
+            path,
+            status,
+            error_message(&body)
+        ))
+    }
+
+    fn decode<T: DeserializeOwned>(path: &str, body: &str) -> Result<T, String> {
+        serde_json::from_str(body).map_err(|e| format!("解析 {} 响应失败: {}", path, e))
+    }
+
+    /// GET 并解析 JSON 响应
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, String> {
+        let body = self.get_text(path, query).await?;
+        Self::decode(path, &body)
+    }
+
+    /// GET 并返回原始响应文本
+    pub async fn get_text(&self, path: &str, query: &[(&str, String)]) -> Result<String, String> {
+        self.send(path, self.request(Method::GET, path).query(query))
+            .await
+    }
+
+    /// POST JSON 请求体并解析 JSON 响应
+    pub async fn post<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &impl Serialize,
+    ) -> Result<T, String> {
+        let body = self
+            .send(path, self.request(Method::POST, path).json(body))
+            .await?;
+        Self::decode(path, &body)
+    }
+
+    /// DELETE 并解析 JSON 响应
+    pub async fn delete<T: DeserializeOwned>(&self, path: &str) -> Result<T, String> {
+        let body = self.send(path, self.request(Method::DELETE, path)).await?;
+        Self::decode(path, &body)
+    }
+}
+
+/// 从错误响应中提取消息
+///
+/// 兼容 `{"error":{"message"}}`、`{"message"}` 与 `{"error":"..."}` 三种格式。
+fn error_message(body: &str) -> String {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(body) else {
+        return body.trim().to_string();
+    };
+    value
+        .pointer("/error/message")
+        .or_else(|| value.get("message"))
+        .or_else(|| value.get("error"))
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| body.trim().to_string())
+}
+
+/// 以 JSON 输出
+pub fn print_json(value: &impl Serialize) -> Result<(), String> {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).map_err(|e| e.to_string())?
+    );
+    Ok(())
+}
+
+/// 按列对齐输出表格
+pub fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| display_width(h)).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(display_width(cell));
+        }
+    }
+
+    let format_row = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| {
+                let padding = width.saturating_sub(display_width(cell));
+                format!("{}{}", cell, " ".repeat(padding))
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!("{}", format_row(headers.to_vec()));
+    for row in rows {
+        println!("{}", format_row(row.iter().map(String::as_str).collect()));
+    }
+}
+
+/// 终端显示宽度（CJK 字符占两列）
+fn display_width(s: &str) -> usize {
+    s.chars()
+        .map(|c| if (c as u32) >= 0x1100 { 2 } else { 1 })
+        .sum()
+}
+
+/// 把子命令结果转换为进程退出码
+pub fn exit_code(result: Result<bool, String>) -> i32 {
+    match result {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            1
+        }
+    }
+}
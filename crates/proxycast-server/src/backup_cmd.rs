@@ -0,0 +1,135 @@
+//! `proxycast-server backup` 子命令
+//!
+//! - `create`：立即备份数据库
+//! - `restore`：从备份恢复数据库（相对路径按备份目录解析）
+//! - `list`：列出备份目录中的备份
+
+use clap::Subcommand;
+use proxycast_core::services::backup_service::BackupService;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::admin_client::{exit_code, open_database, Target, TargetArgs};
+
+/// 备份子命令
+#[derive(Subcommand, Debug)]
+pub enum BackupCommand {
+    /// 立即备份数据库
+    Create,
+    /// 从备份恢复数据库
+    Restore {
+        /// 备份文件路径（相对路径按备份目录解析）
+        path: PathBuf,
+    },
+    /// 列出备份
+    List,
+}
+
+/// `GET /v0/management/backups` 响应
+#[derive(Deserialize)]
+struct BackupListResponse {
+    backup_dir: PathBuf,
+    backups: Vec<PathBuf>,
+}
+
+/// `POST /v0/management/backups` 响应
+#[derive(Deserialize)]
+struct CreateBackupResponse {
+    path: PathBuf,
+}
+
+/// `POST /v0/management/backups/restore` 响应
+#[derive(Deserialize)]
+struct RestoreBackupResponse {
+    restored: PathBuf,
+}
+
+/// 执行子命令，返回进程退出码
+pub async fn run(command: BackupCommand, target: &TargetArgs, config_path: &Path) -> i32 {
+    let target = match Target::resolve(target, config_path).await {
+        Ok(target) => target,
+        Err(e) => return exit_code(Err(e)),
+    };
+    let result = match command {
+        BackupCommand::Create => create(&target).await,
+        BackupCommand::Restore { path } => restore(&target, path).await,
+        BackupCommand::List => list(&target).await,
+    };
+    exit_code(result)
+}
+
+async fn create(target: &Target) -> Result<bool, String> {
+    let path = match target {
+        Target::Remote(client) => {
+            client
+                .post::<CreateBackupResponse>("/v0/management/backups", &serde_json::json!({}))
+                .await?
+                .path
+        }
+        Target::Direct { .. } => {
+            let db = open_database()?;
+            BackupService::with_defaults()?.backup_database_with_connection(&db)?
+        }
+    };
+    println!("已备份数据库到 {}", path.display());
+    Ok(true)
+}
+
+async fn restore(target: &Target, path: PathBuf) -> Result<bool, String> {
+    let restored = match target {
+        Target::Remote(client) => {
+            client
+                .post::<RestoreBackupResponse>(
+                    "/v0/management/backups/restore",
+                    &serde_json::json!({ "path": path }),
+                )
+                .await?
+                .restored
+        }
+        Target::Direct { .. } => {
+            let service = BackupService::with_defaults()?;
+            let path = if path.is_absolute() {
+                path
+            } else {
+                service.backup_dir().join(path)
+            };
+            let db = open_database()?;
+            service.restore_database_with_connection(&db, &path)?;
+            path
+        }
+    };
+    println!("已从 {} 恢复数据库", restored.display());
+    Ok(true)
+}
+
+async fn list(target: &Target) -> Result<bool, String> {
+    let response = match target {
+        Target::Remote(client) => {
+            client
+                .get::<BackupListResponse>("/v0/management/backups", &[])
+                .await?
+        }
+        Target::Direct { .. } => {
+            let service = BackupService::with_defaults()?;
+            BackupListResponse {
+                backups: service.list_backups()?,
+                backup_dir: service.backup_dir().clone(),
+            }
+        }
+    };
+
+    println!("备份目录: {}", response.backup_dir.display());
+    if response.backups.is_empty() {
+        println!("暂无备份");
+        return Ok(true);
+    }
+    for backup in &response.backups {
+        let name = backup
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| backup.display().to_string());
+        println!("  {}", name);
+    }
+    println!("共 {} 个备份", response.backups.len());
+    Ok(true)
+}
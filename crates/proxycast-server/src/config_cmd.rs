@@ -0,0 +1,227 @@
+//! `proxycast-server config` 子命令
+//!
+//! - `validate`：完整验证配置文件，输出带行号的错误与警告
+//! - `diff`：比较运行中的配置与指定文件的语义差异（密钥脱敏）
+//! - `apply`：验证并应用配置文件，`--dry-run` 时只展示差异
+//! - `schema`：导出配置的 JSON Schema
+//!
+//! “运行中的配置”指服务器加载并热重载的配置文件（默认为
+//! `ConfigManager::default_config_path()`，可通过 `--config` 指定）。
+
+use clap::Subcommand;
+use proxycast_core::config::{
+    config_json_schema, diff_configs, validate_config_file, validate_config_source, Config,
+    ConfigChange, ConfigIssue, ConfigManager, ValidationReport,
+};
+use std::path::{Path, PathBuf};
+
+/// 配置管理子命令
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// 验证配置文件（默认验证运行中的配置）
+    Validate {
+        /// 配置文件路径
+        file: Option<PathBuf>,
+        /// 以 JSON 输出结果
+        #[arg(long)]
+        json: bool,
+    },
+    /// 比较运行中的配置与指定文件的差异
+    Diff {
+        /// 待比较的配置文件
+        file: PathBuf,
+        /// 以 JSON 输出结果
+        #[arg(long)]
+        json: bool,
+    },
+    /// 验证并应用配置文件（运行中的服务器通过热重载生效）
+    Apply {
+        /// 待应用的配置文件
+        file: PathBuf,
+        /// 只验证并展示差异，不写入
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// 导出配置的 JSON Schema
+    Schema {
+        /// 输出文件（默认输出到标准输出）
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// 执行子命令，返回进程退出码
+///
+/// 0 表示成功；1 表示验证失败或执行出错。
+pub fn run(command: ConfigCommand, running_path: &Path) -> i32 {
+    let result = match command {
+        ConfigCommand::Validate { file, json } => {
+            validate(file.as_deref().unwrap_or(running_path), json)
+        }
+        ConfigCommand::Diff { file, json } => diff(running_path, &file, json),
+        ConfigCommand::Apply { file, dry_run } => apply(running_path, &file, dry_run),
+        ConfigCommand::Schema { output } => schema(output.as_deref()),
+    };
+    match result {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            1
+        }
+    }
+}
+
+fn print_issues(file: &Path, report: &ValidationReport) {
+    for issue in &report.issues {
+        eprintln!("{}", format_issue(file, issue));
+    }
+}
+
+fn format_issue(file: &Path, issue: &ConfigIssue) -> String {
+    match (issue.line, issue.column) {
+        (Some(line), Some(column)) => format!("{}:{}:{}: {}", file.display(), line, column, issue),
+        (Some(line), None) => format!("{}:{}: {}", file.display(), line, issue),
+        _ => format!("{}: {}", file.display(), issue),
+    }
+}
+
+fn summary(report: &ValidationReport) -> String {
+    format!(
+        "{} 个错误，{} 个警告",
+        report.error_count(),
+        report.warning_count()
+    )
+}
+
+fn validate(file: &Path, json: bool) -> Result<bool, String> {
+    let report = validate_config_file(file);
+    if json {
+        let output = serde_json::json!({
+            "file": file,
+            "valid": report.is_valid(),
+            "issues": report.issues,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output).map_err(|e| e.to_string())?
+        );
+    } else {
+        print_issues(file, &report);
+        if report.is_valid() {
+            println!("{}: 配置有效（{}）", file.display(), summary(&report));
+        } else {
+            eprintln!("{}: 配置无效（{}）", file.display(), summary(&report));
+        }
+    }
+    Ok(report.is_valid())
+}
+
+/// 加载运行中的配置（文件不存在时视为默认配置）
+fn load_running(path: &Path) -> Result<Config, String> {
+    if !path.exists() {
+        eprintln!(
+            "提示: 运行中的配置文件不存在，按默认配置比较: {}",
+            path.display()
+        );
+        return Ok(Config::default());
+    }
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("读取运行中的配置失败 {}: {}", path.display(), e))?;
+    ConfigManager::parse_yaml(&content)
+        .map_err(|e| format!("解析运行中的配置失败 {}: {}", path.display(), e))
+}
+
+fn print_changes(changes: &[ConfigChange]) {
+    if changes.is_empty() {
+        println!("无差异");
+        return;
+    }
+    for change in changes {
+        println!("{}", change);
+    }
+    println!("共 {} 处变更", changes.len());
+}
+
+fn diff(running_path: &Path, file: &Path, json: bool) -> Result<bool, String> {
+    let current = load_running(running_path)?;
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| format!("读取配置文件失败 {}: {}", file.display(), e))?;
+    let candidate = ConfigManager::parse_yaml(&content)
+        .map_err(|e| format!("解析配置文件失败 {}: {}", file.display(), e))?;
+
+    let changes = diff_configs(&current, &candidate);
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&changes).map_err(|e| e.to_string())?
+        );
+    } else {
+        println!("--- {}（运行中）", running_path.display());
+        println!("+++ {}", file.display());
+        print_changes(&changes);
+    }
+    Ok(true)
+}
+
+fn apply(running_path: &Path, file: &Path, dry_run: bool) -> Result<bool, String> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| format!("读取配置文件失败 {}: {}", file.display(), e))?;
+    let report = validate_config_source(&content);
+    print_issues(file, &report);
+    let candidate = match report.config {
+        Some(ref config) if report.is_valid() => config.clone(),
+        _ => {
+            eprintln!(
+                "{}: 配置无效（{}），未应用",
+                file.display(),
+                summary(&report)
+            );
+            return Ok(false);
+        }
+    };
+
+    let current = load_running(running_path)?;
+    let changes = diff_configs(&current, &candidate);
+    print_changes(&changes);
+
+    if dry_run {
+        println!("dry-run：验证通过，未写入 {}", running_path.display());
+        return Ok(true);
+    }
+    if changes.is_empty() {
+        return Ok(true);
+    }
+
+    if let Some(parent) = running_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("创建配置目录失败 {}: {}", parent.display(), e))?;
+    }
+    if running_path.exists() {
+        let backup_path = running_path.with_extension("yaml.backup");
+        std::fs::copy(running_path, &backup_path)
+            .map_err(|e| format!("备份配置失败 {}: {}", backup_path.display(), e))?;
+        println!("已备份原配置到 {}", backup_path.display());
+    }
+    // 原样写入，保留文件中的注释与格式
+    std::fs::write(running_path, &content)
+        .map_err(|e| format!("写入配置失败 {}: {}", running_path.display(), e))?;
+    println!(
+        "已应用到 {}，运行中的服务器将通过热重载生效",
+        running_path.display()
+    );
+    Ok(true)
+}
+
+fn schema(output: Option<&Path>) -> Result<bool, String> {
+    let schema = serde_json::to_string_pretty(&config_json_schema()).map_err(|e| e.to_string())?;
+    match output {
+        Some(path) => {
+            std::fs::write(path, schema + "\n")
+                .map_err(|e| format!("写入 Schema 失败 {}: {}", path.display(), e))?;
+            eprintln!("已导出 JSON Schema 到 {}", path.display());
+        }
+        None => println!("{}", schema),
+    }
+    Ok(true)
+}
@@ -0,0 +1,340 @@
+//! `proxycast-server credentials` 子命令
+//!
+//! - `list`：列出凭证池中的凭证
+//! - `add`：添加凭证（OAuth 类型指定凭证文件，API Key 类型指定密钥）
+//! - `remove`：删除凭证
+//! - `refresh`：强制刷新 OAuth Token
+//! - `test`：发起一次测试调用
+
+use clap::Subcommand;
+use proxycast_core::database::dao::provider_pool::ProviderPoolDao;
+use proxycast_core::models::provider_pool_model::{
+    get_oauth_creds_path, HealthCheckResult, PoolProviderType,
+};
+use proxycast_core::server::handlers::{
+    find_pool_credential, AddCredentialRequest, AddCredentialResponse, CredentialInfo,
+    CredentialsListResponse, DeleteCredentialResponse, PoolRefreshResponse,
+};
+use proxycast_core::services::provider_pool_service::ProviderPoolService;
+use proxycast_core::services::token_cache_service::TokenCacheService;
+use std::path::{Path, PathBuf};
+
+use crate::admin_client::{
+    exit_code, open_database, print_json, print_table, ManagementClient, Target, TargetArgs,
+};
+
+/// 凭证管理子命令
+#[derive(Subcommand, Debug)]
+pub enum CredentialsCommand {
+    /// 列出凭证池中的凭证
+    List {
+        /// 只列出指定 Provider 的凭证
+        #[arg(long)]
+        provider: Option<String>,
+        /// 以 JSON 输出结果
+        #[arg(long)]
+        json: bool,
+    },
+    /// 添加凭证
+    Add {
+        /// Provider 类型（如 kiro、gemini、qwen、openai、claude）
+        provider: String,
+        /// 凭证 ID（默认随机生成）
+        #[arg(long)]
+        id: Option<String>,
+        /// OAuth 凭证文件路径（OAuth 类型必填）
+        #[arg(long)]
+        token_file: Option<PathBuf>,
+        /// API Key（API Key 类型必填）
+        #[arg(long)]
+        api_key: Option<String>,
+        /// 自定义 Base URL
+        #[arg(long)]
+        base_url: Option<String>,
+    },
+    /// 删除凭证
+    Remove {
+        /// 凭证 ID
+        uuid: String,
+    },
+    /// 强制刷新 OAuth Token
+    Refresh {
+        /// 凭证 ID
+        uuid: String,
+    },
+    /// 发起一次测试调用
+    Test {
+        /// 凭证 ID
+        uuid: String,
+    },
+}
+
+/// 执行子命令，返回进程退出码
+pub async fn run(command: CredentialsCommand, target: &TargetArgs, config_path: &Path) -> i32 {
+    let target = match Target::resolve(target, config_path).await {
+        Ok(target) => target,
+        Err(e) => return exit_code(Err(e)),
+    };
+    let result = match command {
+        CredentialsCommand::List { provider, json } => list(&target, provider, json).await,
+        CredentialsCommand::Add {
+            provider,
+            id,
+            token_file,
+            api_key,
+            base_url,
+        } => {
+            add(
+                &target,
+                provider,
+                id,
+                token_file.as_deref(),
+                api_key,
+                base_url,
+            )
+            .await
+        }
+        CredentialsCommand::Remove { uuid } => remove(&target, &uuid).await,
+        CredentialsCommand::Refresh { uuid } => refresh(&target, &uuid).await,
+        CredentialsCommand::Test { uuid } => test(&target, &uuid).await,
+    };
+    exit_code(result)
+}
+
+async fn fetch_credentials(target: &Target) -> Result<Vec<CredentialInfo>, String> {
+    match target {
+        Target::Remote(client) => client
+            .get::<CredentialsListResponse>("/v0/management/credentials", &[])
+            .await
+            .map(|response| response.credentials),
+        Target::Direct { .. } => {
+            let db = open_database()?;
+            let conn = db.lock().map_err(|e| format!("数据库锁定失败: {}", e))?;
+            let credentials =
+                ProviderPoolDao::get_all(&conn).map_err(|e| format!("查询凭证失败: {}", e))?;
+            Ok(credentials
+                .into_iter()
+                .map(|cred| CredentialInfo {
+                    id: cred.uuid,
+                    provider_type: cred.provider_type.to_string(),
+                    disabled: cred.is_disabled,
+                    is_valid: cred.is_healthy,
+                })
+                .collect())
+        }
+    }
+}
+
+async fn list(target: &Target, provider: Option<String>, json: bool) -> Result<bool, String> {
+    let provider = provider
+        .map(|p| p.parse::<PoolProviderType>().map(|p| p.to_string()))
+        .transpose()?;
+    let mut credentials = fetch_credentials(target).await?;
+    if let Some(provider) = provider {
+        credentials.retain(|c| c.provider_type == provider);
+    }
+
+    if json {
+        print_json(&credentials)?;
+        return Ok(true);
+    }
+    if credentials.is_empty() {
+        println!("凭证池为空");
+        return Ok(true);
+    }
+    let rows: Vec<Vec<String>> = credentials
+        .iter()
+        .map(|c| {
+            let status = if c.disabled {
+                "已禁用"
+            } else if c.is_valid {
+                "正常"
+            } else {
+                "不健康"
+            };
+            vec![c.id.clone(), c.provider_type.clone(), status.to_string()]
+        })
+        .collect();
+    print_table(&["ID", "PROVIDER", "STATUS"], &rows);
+    println!("共 {} 个凭证", credentials.len());
+    Ok(true)
+}
+
+async fn add(
+    target: &Target,
+    provider: String,
+    id: Option<String>,
+    token_file: Option<&Path>,
+    api_key: Option<String>,
+    base_url: Option<String>,
+) -> Result<bool, String> {
+    let provider_type: PoolProviderType = provider.parse()?;
+    // 服务器与命令行的工作目录不同，凭证文件统一使用绝对路径
+    let token_file = token_file
+        .map(|path| {
+            path.canonicalize()
+                .map(|p| p.to_string_lossy().to_string())
+                .map_err(|e| format!("凭证文件不可用 {}: {}", path.display(), e))
+        })
+        .transpose()?;
+    let request = AddCredentialRequest {
+        provider_type: provider_type.to_string(),
+        id: id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        api_key,
+        token_file,
+        base_url,
+        proxy_url: None,
+    };
+
+    match target {
+        Target::Remote(client) => {
+            let response: AddCredentialResponse =
+                client.post("/v0/management/credentials", &request).await?;
+            println!("{}", response.message);
+        }
+        Target::Direct { .. } => {
+            let credential = request.to_credential()?;
+            let db = open_database()?;
+            let conn = db.lock().map_err(|e| format!("数据库锁定失败: {}", e))?;
+            ProviderPoolDao::insert(&conn, &credential)
+                .map_err(|e| format!("添加凭证失败: {}", e))?;
+        }
+    }
+    println!("已添加 {} 凭证: {}", provider_type, request.id);
+    Ok(true)
+}
+
+async fn remove(target: &Target, uuid: &str) -> Result<bool, String> {
+    match target {
+        Target::Remote(client) => {
+            let response: DeleteCredentialResponse = client
+                .delete(&format!("/v0/management/credentials/{}", uuid))
+                .await?;
+            println!("{}", response.message);
+        }
+        Target::Direct { .. } => {
+            let db = open_database()?;
+            if !ProviderPoolService::new().delete_credential(&db, uuid)? {
+                return Err(format!("未找到凭证: {}", uuid));
+            }
+            println!("已删除凭证: {}", uuid);
+        }
+    }
+    Ok(true)
+}
+
+/// 查找运行中实例上凭证的 Provider（`/api/credentials` 端点按 Provider 分组）
+async fn remote_provider(client: &ManagementClient, uuid: &str) -> Result<String, String> {
+    let response: CredentialsListResponse = client.get("/v0/management/credentials", &[]).await?;
+    response
+        .credentials
+        .into_iter()
+        .find(|c| c.id == uuid)
+        .map(|c| c.provider_type)
+        .ok_or_else(|| format!("未找到凭证: {}", uuid))
+}
+
+async fn refresh(target: &Target, uuid: &str) -> Result<bool, String> {
+    let response = match target {
+        Target::Remote(client) => {
+            let provider = remote_provider(client, uuid).await?;
+            client
+                .post::<PoolRefreshResponse>(
+                    &format!("/api/credentials/{}/{}/refresh", provider, uuid),
+                    &serde_json::json!({}),
+                )
+                .await?
+        }
+        Target::Direct { .. } => {
+            let db = open_database()?;
+            let credential = find_pool_credential(&db, None, uuid).map_err(|e| e.to_string())?;
+            let provider_type = credential.provider_type;
+            if get_oauth_creds_path(&credential.credential).is_none() {
+                return Err(format!("{} 凭证不支持 Token 刷新", provider_type));
+            }
+
+            let token_cache = TokenCacheService::new();
+            let result = if TokenCacheService::supports_refresh(provider_type) {
+                token_cache
+                    .refresh_and_cache_with_events(&db, uuid, true, None)
+                    .await
+            } else {
+                ProviderPoolService::new()
+                    .refresh_credential_token(&db, uuid)
+                    .await
+            };
+            match result {
+                Ok(_) => PoolRefreshResponse {
+                    uuid: uuid.to_string(),
+                    success: true,
+                    new_expires_at: token_cache
+                        .get_cache_status(&db, uuid)?
+                        .and_then(|c| c.expiry_time),
+                    error: None,
+                },
+                Err(e) => PoolRefreshResponse {
+                    uuid: uuid.to_string(),
+                    success: false,
+                    new_expires_at: None,
+                    error: Some(e),
+                },
+            }
+        }
+    };
+
+    if response.success {
+        match response.new_expires_at {
+            Some(expires_at) => println!(
+                "凭证 {} 刷新成功，新的过期时间: {}",
+                uuid,
+                expires_at.to_rfc3339()
+            ),
+            None => println!("凭证 {} 刷新成功", uuid),
+        }
+    } else {
+        eprintln!(
+            "凭证 {} 刷新失败: {}",
+            uuid,
+            response.error.unwrap_or_default()
+        );
+    }
+    Ok(response.success)
+}
+
+async fn test(target: &Target, uuid: &str) -> Result<bool, String> {
+    let result: HealthCheckResult = match target {
+        Target::Remote(client) => {
+            let provider = remote_provider(client, uuid).await?;
+            client
+                .post(
+                    &format!("/api/credentials/{}/{}/test", provider, uuid),
+                    &serde_json::json!({}),
+                )
+                .await?
+        }
+        Target::Direct { .. } => {
+            let db = open_database()?;
+            find_pool_credential(&db, None, uuid).map_err(|e| e.to_string())?;
+            ProviderPoolService::new()
+                .check_credential_health(&db, uuid)
+                .await?
+        }
+    };
+
+    let model = result.model.as_deref().unwrap_or("-");
+    if result.success {
+        println!(
+            "凭证 {} 测试通过（模型 {}，耗时 {}ms）",
+            uuid, model, result.duration_ms
+        );
+    } else {
+        eprintln!(
+            "凭证 {} 测试失败（模型 {}，耗时 {}ms）: {}",
+            uuid,
+            model,
+            result.duration_ms,
+            result.message.unwrap_or_default()
+        );
+    }
+    Ok(result.success)
+}
@@ -0,0 +1,255 @@
+//! `proxycast-server flows` 子命令
+//!
+//! - `query`：按过滤表达式（`FilterParser` 语法，如 `~m claude & ~p kiro`）查询最近的 Flow
+//! - `export`：导出为 HAR（或 JSON / JSONL / Markdown / CSV）
+//!
+//! 本地模式读取 Flow 文件存储（默认 `~/.proxycast/flows`，可通过 `--flows-dir` 指定）。
+
+use clap::{Subcommand, ValueEnum};
+use proxycast_core::flow_monitor::{
+    ExportFormat, ExportOptions, FlowExporter, FlowFileStore, FlowMemoryStore, FlowQueryService,
+    LLMFlow, RotationConfig,
+};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::admin_client::{exit_code, print_json, print_table, Target, TargetArgs};
+
+/// Flow 子命令
+#[derive(Subcommand, Debug)]
+pub enum FlowsCommand {
+    /// 按过滤表达式查询最近的 Flow
+    Query {
+        /// 过滤表达式（如 '~m claude & ~p kiro'，省略时列出最近的 Flow）
+        filter: Option<String>,
+        /// 最多返回的 Flow 数
+        #[arg(short = 'n', long, default_value_t = 50)]
+        limit: usize,
+        /// 以 JSON 输出完整 Flow
+        #[arg(long)]
+        json: bool,
+    },
+    /// 导出 Flow
+    Export {
+        /// 导出格式
+        #[arg(long, value_enum, default_value_t = FlowExportFormat::Har)]
+        format: FlowExportFormat,
+        /// 过滤表达式
+        #[arg(long)]
+        filter: Option<String>,
+        /// 最多导出的 Flow 数
+        #[arg(short = 'n', long, default_value_t = 1000)]
+        limit: usize,
+        /// 脱敏 API Key 等敏感数据
+        #[arg(long)]
+        redact: bool,
+        /// 输出文件（默认输出到标准输出）
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// 导出格式
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowExportFormat {
+    Har,
+    Json,
+    Jsonl,
+    Markdown,
+    Csv,
+}
+
+impl FlowExportFormat {
+    fn as_str(self) -> &'static str {
+        match self {
+            FlowExportFormat::Har => "har",
+            FlowExportFormat::Json => "json",
+            FlowExportFormat::Jsonl => "jsonl",
+            FlowExportFormat::Markdown => "markdown",
+            FlowExportFormat::Csv => "csv",
+        }
+    }
+
+    fn export_format(self) -> ExportFormat {
+        match self {
+            FlowExportFormat::Har => ExportFormat::HAR,
+            FlowExportFormat::Json => ExportFormat::JSON,
+            FlowExportFormat::Jsonl => ExportFormat::JSONL,
+            FlowExportFormat::Markdown => ExportFormat::Markdown,
+            FlowExportFormat::Csv => ExportFormat::CSV,
+        }
+    }
+}
+
+/// `GET /v0/management/flows` 响应
+#[derive(Deserialize)]
+struct FlowListResponse {
+    flows: Vec<LLMFlow>,
+}
+
+/// 执行子命令，返回进程退出码
+pub async fn run(command: FlowsCommand, target: &TargetArgs, config_path: &Path) -> i32 {
+    let target = match Target::resolve(target, config_path).await {
+        Ok(target) => target,
+        Err(e) => return exit_code(Err(e)),
+    };
+    let result = match command {
+        FlowsCommand::Query {
+            filter,
+            limit,
+            json,
+        } => query(&target, filter.as_deref(), limit, json).await,
+        FlowsCommand::Export {
+            format,
+            filter,
+            limit,
+            redact,
+            output,
+        } => {
+            export(
+                &target,
+                format,
+                filter.as_deref(),
+                limit,
+                redact,
+                output.as_deref(),
+            )
+            .await
+        }
+    };
+    exit_code(result)
+}
+
+/// 打开本地 Flow 查询服务（只读取文件存储）
+pub fn open_query_service(flows_dir: &Path) -> Result<FlowQueryService, String> {
+    if !flows_dir.exists() {
+        return Err(format!("Flow 存储目录不存在: {}", flows_dir.display()));
+    }
+    let file_store = FlowFileStore::new(flows_dir.to_path_buf(), RotationConfig::default())
+        .map_err(|e| format!("打开 Flow 存储失败 {}: {}", flows_dir.display(), e))?;
+    Ok(FlowQueryService::new(
+        Arc::new(RwLock::new(FlowMemoryStore::new(0))),
+        Arc::new(file_store),
+    ))
+}
+
+/// 最近的 Flow（按创建时间降序）
+async fn recent_flows(
+    target: &Target,
+    filter: Option<&str>,
+    limit: usize,
+) -> Result<Vec<LLMFlow>, String> {
+    match target {
+        Target::Remote(client) => {
+            let mut query = vec![("limit", limit.to_string())];
+            if let Some(filter) = filter {
+                query.push(("filter", filter.to_string()));
+            }
+            client
+                .get::<FlowListResponse>("/v0/management/flows", &query)
+                .await
+                .map(|response| response.flows)
+        }
+        Target::Direct { flows_dir, .. } => open_query_service(flows_dir)?
+            .recent_flows(filter, limit)
+            .await
+            .map_err(|e| e.to_string()),
+    }
+}
+
+async fn query(
+    target: &Target,
+    filter: Option<&str>,
+    limit: usize,
+    json: bool,
+) -> Result<bool, String> {
+    let flows = recent_flows(target, filter, limit).await?;
+    if json {
+        print_json(&flows)?;
+        return Ok(true);
+    }
+    if flows.is_empty() {
+        println!("没有匹配的 Flow");
+        return Ok(true);
+    }
+
+    let rows: Vec<Vec<String>> = flows
+        .iter()
+        .map(|flow| {
+            let tokens = flow
+                .response
+                .as_ref()
+                .map(|r| r.usage.total_tokens.to_string())
+                .unwrap_or_else(|| "-".to_string());
+            vec![
+                flow.id.clone(),
+                flow.timestamps
+                    .created
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string(),
+                flow.metadata.provider.to_string(),
+                flow.request.model.clone(),
+                format!("{:?}", flow.state),
+                format!("{}ms", flow.timestamps.duration_ms),
+                tokens,
+            ]
+        })
+        .collect();
+    print_table(
+        &[
+            "ID", "CREATED", "PROVIDER", "MODEL", "STATE", "DURATION", "TOKENS",
+        ],
+        &rows,
+    );
+    println!("共 {} 个 Flow", flows.len());
+    Ok(true)
+}
+
+async fn export(
+    target: &Target,
+    format: FlowExportFormat,
+    filter: Option<&str>,
+    limit: usize,
+    redact: bool,
+    output: Option<&Path>,
+) -> Result<bool, String> {
+    let content = match target {
+        Target::Remote(client) => {
+            let mut query = vec![
+                ("format", format.as_str().to_string()),
+                ("limit", limit.to_string()),
+                ("redact", redact.to_string()),
+            ];
+            if let Some(filter) = filter {
+                query.push(("filter", filter.to_string()));
+            }
+            client
+                .get_text("/v0/management/flows/export", &query)
+                .await?
+        }
+        Target::Direct { .. } => {
+            let mut flows = recent_flows(target, filter, limit).await?;
+            // 导出文件按时间正序排列
+            flows.reverse();
+            FlowExporter::new(ExportOptions {
+                format: format.export_format(),
+                redact_sensitive: redact,
+                ..Default::default()
+            })
+            .export(&flows)
+            .to_string_pretty()
+        }
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, content)
+                .map_err(|e| format!("写入导出文件失败 {}: {}", path.display(), e))?;
+            eprintln!("已导出到 {}", path.display());
+        }
+        None => println!("{}", content),
+    }
+    Ok(true)
+}
@@ -6,16 +6,35 @@
 //! - 代理 API（/v1/chat/completions, /v1/messages 等）
 //! - 管理 API（/api/management/*）
 //! - 静态文件服务（Web UI）
-
-use clap::Parser;
+//! - 配置管理子命令（`proxycast-server config validate|diff|apply|schema`）
+//! - 运维子命令（`credentials`、`routes`、`flows`、`stats`、`backup`），
+//!   优先通过管理 API 操作运行中的实例，否则直接操作本地数据库
+
+mod admin_client;
+mod backup_cmd;
+mod config_cmd;
+mod credentials_cmd;
+mod flows_cmd;
+mod routes_cmd;
+mod stats_cmd;
+
+use admin_client::TargetArgs;
+use backup_cmd::BackupCommand;
+use clap::{Parser, Subcommand};
+use config_cmd::ConfigCommand;
+use credentials_cmd::CredentialsCommand;
+use flows_cmd::FlowsCommand;
 use proxycast_api::{create_app, ApiConfig, AppState as ManagementState};
 use proxycast_core::{
-    config::{load_config, Config},
+    config::{load_config, Config, ConfigManager},
     database,
     logger::LogStore,
-    server::ServerState,
-    services::{provider_pool_service::ProviderPoolService, token_cache_service::TokenCacheService},
+    server::{tls, ServerState},
+    services::{
+        provider_pool_service::ProviderPoolService, token_cache_service::TokenCacheService,
+    },
 };
+use routes_cmd::RoutesCommand;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -35,7 +54,7 @@ struct Args {
     port: Option<u16>,
 
     /// 配置文件路径
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     config: Option<String>,
 
     /// 静态文件目录
@@ -57,12 +76,93 @@ struct Args {
     /// 管理 API 端口（默认与代理端口相同）
     #[arg(long)]
     management_port: Option<u16>,
+
+    /// 子命令（省略时启动服务器）
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// 子命令
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// 配置管理：验证、比较、应用配置文件与导出 JSON Schema
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// 凭证管理：列出、添加、删除、刷新与测试凭证
+    Credentials {
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(subcommand)]
+        action: CredentialsCommand,
+    },
+    /// 路由管理：列出可用路由
+    Routes {
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(subcommand)]
+        action: RoutesCommand,
+    },
+    /// Flow 管理：按过滤表达式查询与导出
+    Flows {
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(subcommand)]
+        action: FlowsCommand,
+    },
+    /// 请求统计：请求数、成功率、延迟与 Token 用量
+    Stats {
+        /// 统计起点（如 30m、24h、7d 或 RFC 3339 时间）
+        #[arg(long, default_value = "24h")]
+        since: String,
+        /// 以 JSON 输出结果
+        #[arg(long)]
+        json: bool,
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+    /// 数据库备份：创建、恢复与列出备份
+    Backup {
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(subcommand)]
+        action: BackupCommand,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
+    if let Some(command) = args.command {
+        let running_path = args
+            .config
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(ConfigManager::default_config_path);
+        let code = match command {
+            Command::Config { action } => config_cmd::run(action, &running_path),
+            Command::Credentials { target, action } => {
+                credentials_cmd::run(action, &target, &running_path).await
+            }
+            Command::Routes { target, action } => {
+                routes_cmd::run(action, &target, &running_path).await
+            }
+            Command::Flows { target, action } => {
+                flows_cmd::run(action, &target, &running_path).await
+            }
+            Command::Stats {
+                since,
+                json,
+                target,
+            } => stats_cmd::run(&since, json, &target, &running_path).await,
+            Command::Backup { target, action } => {
+                backup_cmd::run(action, &target, &running_path).await
+            }
+        };
+        std::process::exit(code);
+    }
+
     // 初始化日志
     let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(&args.log_level));
@@ -124,18 +224,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 启动管理 API 服务器
     let addr: SocketAddr = format!("{}:{}", config.server.host, management_port).parse()?;
 
+    let scheme = if config.server.tls.enable {
+        "https"
+    } else {
+        "http"
+    };
+
     tracing::info!("Starting ProxyCast Server on {}", addr);
     if !args.no_static {
-        tracing::info!("  Web UI: http://{}", addr);
+        tracing::info!("  Web UI: {}://{}", scheme, addr);
     }
-    tracing::info!("  Management API: http://{}/api/management", addr);
-    tracing::info!("  Proxy API: http://{}/v1/chat/completions", addr);
+    tracing::info!("  Management API: {}://{}/api/management", scheme, addr);
+    tracing::info!("  Proxy API: {}://{}/v1/chat/completions", scheme, addr);
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let listener = std::net::TcpListener::bind(addr)?;
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    tls::serve(listener, app, &config.server.tls, shutdown_signal())
+        .await
+        .map_err(|e| e.to_string())?;
 
     tracing::info!("Server shutdown complete");
 
@@ -153,12 +259,18 @@ fn validate_config(config: &Config) -> Result<(), String> {
         || host.starts_with("10.")
         || host.starts_with("172.");
 
-    if !is_local {
+    if !is_local && !config.server.tls.enable {
         tracing::warn!(
             "Server is configured to listen on non-local address: {}",
             host
         );
-        tracing::warn!("This may expose your API keys to the network!");
+        tracing::warn!(
+            "This may expose your API keys to the network! Consider enabling server.tls."
+        );
+    }
+
+    if config.server.tls.enable {
+        tls::load_server_config(&config.server.tls).map_err(|e| e.to_string())?;
     }
 
     // 检查 API Key
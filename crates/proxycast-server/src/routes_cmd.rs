@@ -0,0 +1,89 @@
+//! `proxycast-server routes` 子命令
+//!
+//! - `list`：列出多供应商路由（选择器、Provider、凭证数与端点）
+
+use clap::Subcommand;
+use proxycast_core::models::route_model::RouteListResponse;
+use proxycast_core::services::provider_pool_service::ProviderPoolService;
+use std::path::Path;
+
+use crate::admin_client::{exit_code, open_database, print_json, print_table, Target, TargetArgs};
+
+/// 路由子命令
+#[derive(Subcommand, Debug)]
+pub enum RoutesCommand {
+    /// 列出可用路由
+    List {
+        /// 以 JSON 输出结果
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// 执行子命令，返回进程退出码
+pub async fn run(command: RoutesCommand, target: &TargetArgs, config_path: &Path) -> i32 {
+    let target = match Target::resolve(target, config_path).await {
+        Ok(target) => target,
+        Err(e) => return exit_code(Err(e)),
+    };
+    let result = match command {
+        RoutesCommand::List { json } => list(&target, json).await,
+    };
+    exit_code(result)
+}
+
+async fn list(target: &Target, json: bool) -> Result<bool, String> {
+    let response = match target {
+        Target::Remote(client) => client.get::<RouteListResponse>("/v1/routes", &[]).await?,
+        Target::Direct { config, .. } => {
+            let scheme = if config.server.tls.enable {
+                "https"
+            } else {
+                "http"
+            };
+            let base_url = format!("{}://{}:{}", scheme, config.server.host, config.server.port);
+            let db = open_database()?;
+            let routes = ProviderPoolService::new().get_available_routes(&db, &base_url)?;
+            RouteListResponse::new(&base_url, &config.default_provider, routes)
+        }
+    };
+
+    if json {
+        print_json(&response)?;
+        return Ok(true);
+    }
+    let rows: Vec<Vec<String>> = response
+        .routes
+        .iter()
+        .map(|route| {
+            let endpoints = route
+                .endpoints
+                .iter()
+                .map(|e| e.path.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            vec![
+                route.selector.clone(),
+                route.provider_type.clone(),
+                route.credential_count.to_string(),
+                if route.enabled { "是" } else { "否" }.to_string(),
+                endpoints,
+            ]
+        })
+        .collect();
+    print_table(
+        &[
+            "SELECTOR",
+            "PROVIDER",
+            "CREDENTIALS",
+            "ENABLED",
+            "ENDPOINTS",
+        ],
+        &rows,
+    );
+    println!(
+        "Base URL: {}（默认 Provider: {}）",
+        response.base_url, response.default_provider
+    );
+    Ok(true)
+}
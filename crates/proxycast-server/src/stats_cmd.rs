@@ -0,0 +1,138 @@
+//! `proxycast-server stats` 子命令
+//!
+//! 统计指定时间之后的请求数、成功率、延迟与 Token 用量（按 Provider / 模型分组）。
+
+use chrono::{DateTime, Duration, Utc};
+use proxycast_core::flow_monitor::FlowStats;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::admin_client::{exit_code, print_json, print_table, Target, TargetArgs};
+use crate::flows_cmd::open_query_service;
+
+/// `GET /v0/management/flows/stats` 响应
+#[derive(Serialize, Deserialize)]
+struct StatsResponse {
+    since: DateTime<Utc>,
+    stats: FlowStats,
+}
+
+/// 执行子命令，返回进程退出码
+pub async fn run(since: &str, json: bool, target: &TargetArgs, config_path: &Path) -> i32 {
+    let since = match parse_since(since, Utc::now()) {
+        Ok(since) => since,
+        Err(e) => return exit_code(Err(e)),
+    };
+    let target = match Target::resolve(target, config_path).await {
+        Ok(target) => target,
+        Err(e) => return exit_code(Err(e)),
+    };
+    exit_code(stats(&target, since, json).await)
+}
+
+/// 解析统计起点：相对时长（`30m`、`24h`、`7d`、`90s`）或 RFC 3339 时间
+fn parse_since(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    let invalid = || {
+        format!(
+            "无效的时间: {}（示例: 30m、24h、7d 或 RFC 3339 时间）",
+            value
+        )
+    };
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    let duration = match unit {
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => None,
+    }
+    .ok_or_else(invalid)?;
+    Ok(now - duration)
+}
+
+async fn stats(target: &Target, since: DateTime<Utc>, json: bool) -> Result<bool, String> {
+    let response = match target {
+        Target::Remote(client) => {
+            client
+                .get::<StatsResponse>(
+                    "/v0/management/flows/stats",
+                    &[("since", since.to_rfc3339())],
+                )
+                .await?
+        }
+        Target::Direct { flows_dir, .. } => StatsResponse {
+            since,
+            stats: open_query_service(flows_dir)?
+                .get_stats_since(since)
+                .await
+                .map_err(|e| e.to_string())?,
+        },
+    };
+
+    if json {
+        print_json(&response)?;
+        return Ok(true);
+    }
+
+    let stats = &response.stats;
+    println!("统计起点: {}", response.since.to_rfc3339());
+    println!(
+        "请求数: {}（成功 {}，失败 {}，成功率 {:.1}%）",
+        stats.total_requests,
+        stats.successful_requests,
+        stats.failed_requests,
+        stats.success_rate * 100.0
+    );
+    if stats.total_requests == 0 {
+        return Ok(true);
+    }
+    println!(
+        "延迟: 平均 {:.0}ms，最小 {}ms，最大 {}ms",
+        stats.avg_latency_ms, stats.min_latency_ms, stats.max_latency_ms
+    );
+    println!(
+        "Token: 输入 {}，输出 {}",
+        stats.total_input_tokens, stats.total_output_tokens
+    );
+
+    println!();
+    let rows: Vec<Vec<String>> = stats
+        .by_provider
+        .iter()
+        .map(|p| {
+            vec![
+                p.provider.clone(),
+                p.count.to_string(),
+                format!("{:.1}%", p.success_rate * 100.0),
+                format!("{:.0}ms", p.avg_latency_ms),
+            ]
+        })
+        .collect();
+    print_table(&["PROVIDER", "REQUESTS", "SUCCESS", "AVG LATENCY"], &rows);
+
+    println!();
+    let rows: Vec<Vec<String>> = stats
+        .by_model
+        .iter()
+        .map(|m| {
+            vec![
+                m.model.clone(),
+                m.count.to_string(),
+                format!("{:.1}%", m.success_rate * 100.0),
+                format!("{:.0}ms", m.avg_latency_ms),
+            ]
+        })
+        .collect();
+    print_table(&["MODEL", "REQUESTS", "SUCCESS", "AVG LATENCY"], &rows);
+    Ok(true)
+}
@@ -91,7 +91,7 @@ export function RemoteManagementSettings() {
 
   const rm = config.remote_management;
   const isEnabled = Boolean(rm.secret_key && rm.secret_key.length > 0);
-  const remoteAccessSupported = false;
+  const remoteAccessSupported = config.server.tls.enable;
   const allowRemoteToggleEnabled = remoteAccessSupported && isEnabled;
   const allowRemoteToggleDisabled =
     !allowRemoteToggleEnabled && !rm.allow_remote;
@@ -132,7 +132,9 @@ export function RemoteManagementSettings() {
         {!remoteAccessSupported && (
           <div className="flex items-start gap-2 rounded-lg bg-yellow-50 dark:bg-yellow-900/20 p-3 text-sm text-yellow-700 dark:text-yellow-400">
             <AlertTriangle className="h-4 w-4 shrink-0 mt-0.5" />
-            <span>当前版本未启用 TLS，暂不支持远程管理访问，请保持关闭。</span>
+            <span>
+              未启用 TLS 时不支持远程管理访问，请先在 TLS 设置中启用 HTTPS。
+            </span>
           </div>
         )}
 
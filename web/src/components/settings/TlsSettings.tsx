@@ -67,6 +67,20 @@ export function TlsSettings() {
     }
   };
 
+  const handleSelectClientCa = async () => {
+    try {
+      const selected = await open({
+        multiple: false,
+        filters: [{ name: "Certificate", extensions: ["pem", "crt", "cer"] }],
+      });
+      if (selected) {
+        updateTls({ client_ca_path: selected as string });
+      }
+    } catch (e) {
+      console.error("Failed to open file dialog:", e);
+    }
+  };
+
   const handleSelectKey = async () => {
     try {
       const selected = await open({
@@ -89,7 +103,7 @@ export function TlsSettings() {
     );
   }
 
-  const tlsSupported = false;
+  const tlsSupported = true;
   const tls = config.server.tls;
   const isConfigValid = !tls.enable || (tls.cert_path && tls.key_path);
   const tlsUnsupportedEnabled = tls.enable && !tlsSupported;
@@ -123,16 +137,6 @@ export function TlsSettings() {
       )}
 
       <div className="p-4 rounded-lg border space-y-4">
-        {!tlsSupported && (
-          <div className="flex items-start gap-2 rounded-lg bg-yellow-50 dark:bg-yellow-900/20 p-3 text-sm text-yellow-700 dark:text-yellow-400">
-            <AlertTriangle className="h-4 w-4 shrink-0 mt-0.5" />
-            <span>
-              当前版本暂不支持 TLS。启用后服务将无法启动，请使用反向代理或 TLS
-              终止。
-            </span>
-          </div>
-        )}
-
         {/* 启用开关 */}
         <label className="flex items-center justify-between p-3 rounded-lg border cursor-pointer hover:bg-muted/50">
           <div>
@@ -217,6 +221,52 @@ export function TlsSettings() {
           </p>
         </div>
 
+        {/* 客户端 CA 路径（mTLS） */}
+        <div
+          className={
+            tls.enable && tlsSupported ? "" : "opacity-50 pointer-events-none"
+          }
+        >
+          <label className="block text-sm font-medium mb-1.5">
+            客户端证书 CA（可选）
+          </label>
+          <div className="flex gap-2">
+            <input
+              type="text"
+              value={tls.client_ca_path || ""}
+              onChange={(e) =>
+                updateTls({ client_ca_path: e.target.value || null })
+              }
+              placeholder="/path/to/client-ca.pem"
+              className="flex-1 px-3 py-2 rounded-lg border bg-background text-sm focus:ring-2 focus:ring-primary/20 focus:border-primary outline-none"
+            />
+            <button
+              type="button"
+              onClick={handleSelectClientCa}
+              className="flex items-center gap-1 rounded-lg border px-3 py-2 text-sm hover:bg-muted"
+            >
+              <FolderOpen className="h-4 w-4" />
+              浏览
+            </button>
+          </div>
+          <p className="text-xs text-muted-foreground mt-1">
+            设置后要求客户端提供由该 CA 签发的证书（mTLS）
+          </p>
+          {tls.client_ca_path && (
+            <label className="flex items-center gap-2 mt-2 text-sm">
+              <input
+                type="checkbox"
+                checked={Boolean(tls.client_auth_optional)}
+                onChange={(e) =>
+                  updateTls({ client_auth_optional: e.target.checked })
+                }
+                className="w-4 h-4 rounded border-gray-300"
+              />
+              允许不带客户端证书的连接
+            </label>
+          )}
+        </div>
+
         {/* 警告提示 */}
         {tls.enable && !isConfigValid && (
           <div className="flex items-start gap-2 rounded-lg bg-yellow-50 dark:bg-yellow-900/20 p-3 text-sm text-yellow-700 dark:text-yellow-400">
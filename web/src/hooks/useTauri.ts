@@ -13,6 +13,8 @@ export interface TlsConfig {
   enable: boolean;
   cert_path: string | null;
   key_path: string | null;
+  client_ca_path?: string | null;
+  client_auth_optional?: boolean;
 }
 
 // Remote Management Configuration